@@ -0,0 +1,52 @@
+;; A scrolling colour gradient, to show the frame interface of
+;; `"content_type": "webassembly"` casts.
+;;
+;; The cast window calls the exported `render(width, height, time_ms)` for
+;; every frame it shows. It returns where in memory the frame is: its width
+;; and height (little-endian u32s) followed by width * height RGBA pixels.
+;; Returning 0 would end the animation, so the frame starts past address 0.
+;; This module ignores the size the display would like and always draws
+;; 320x180, which the window scales to fit.
+;;
+;; Cast it with
+;;   {"content_type": "webassembly", "source": "/path/to/examples/wasm/gradient.wat"}
+(module
+  ;; 16: frame header, 24: pixels
+  (memory (export "memory") 4)
+
+  (func (export "render") (param $width i32) (param $height i32) (param $time_ms i32) (result i32)
+    (local $t i32)
+    (local $x i32)
+    (local $y i32)
+    (local $p i32)
+
+    ;; Scroll by one step every 33 ms, about 30 a second
+    (local.set $t (i32.div_u (local.get $time_ms) (i32.const 33)))
+
+    (i32.store (i32.const 16) (i32.const 320))
+    (i32.store (i32.const 20) (i32.const 180))
+
+    (local.set $p (i32.const 24))
+    (loop $rows
+      (local.set $x (i32.const 0))
+      (loop $cols
+        ;; Bytes land in memory as R, G, B, A
+        (i32.store (local.get $p)
+          (i32.or
+            (i32.or
+              (i32.const 0xFF000000)
+              (i32.const 0x00A00000))
+            (i32.or
+              (i32.shl
+                (i32.and (i32.add (local.get $y) (local.get $t)) (i32.const 255))
+                (i32.const 8))
+              (i32.and
+                (i32.add (local.get $x) (i32.shl (local.get $t) (i32.const 1)))
+                (i32.const 255)))))
+        (local.set $p (i32.add (local.get $p) (i32.const 4)))
+        (local.set $x (i32.add (local.get $x) (i32.const 1)))
+        (br_if $cols (i32.lt_u (local.get $x) (i32.const 320))))
+      (local.set $y (i32.add (local.get $y) (i32.const 1)))
+      (br_if $rows (i32.lt_u (local.get $y) (i32.const 180))))
+
+    (i32.const 16)))
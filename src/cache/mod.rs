@@ -2,9 +2,10 @@ use dashmap::DashMap;
 use lru::LruCache;
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use uuid::Uuid;
 
 use crate::error::Result as CasterResult;
@@ -22,43 +23,129 @@ pub struct CachedContent {
     pub cached_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// What is known about a cached item without loading its data
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CacheEntry {
+    pub id: String,
+    pub content_type: ContentType,
+    pub source: ContentSource,
+    pub mime_type: String,
+    pub size: usize,
+    pub cached_at: chrono::DateTime<chrono::Utc>,
+    /// Held in memory as well as on disk
+    pub in_memory: bool,
+}
+
+/// Where the cache lives and how big it may grow
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// The platform cache directory when None
+    pub dir: Option<PathBuf>,
+    pub max_size_mb: usize,
+    /// Items also held in memory
+    pub memory_items: usize,
+    /// Compress data files of compressible types with zstd. Items stored before
+    /// it was turned on stay readable either way.
+    pub compress: bool,
+    /// Directories local files may be cast from or copied into the cache from.
+    /// URLs can always be used; with none set, local files can't.
+    pub media_dirs: Vec<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_size_mb: 500,
+            memory_items: 100,
+            compress: false,
+            media_dirs: Vec::new(),
+        }
+    }
+}
+
 /// Content cache with LRU eviction and persistent storage
 pub struct ContentCache {
     /// In-memory LRU cache
     memory_cache: Arc<Mutex<LruCache<String, CachedContent>>>,
     /// Mapping of cache keys to file paths for persistent storage
     disk_cache: Arc<DashMap<String, PathBuf>>,
+    /// Size of every item on disk, least recently used first. Covers items the
+    /// memory cache has let go of, so eviction bounds the disk tier too.
+    recency: Arc<Mutex<LruCache<String, usize>>>,
     /// Cache directory
     cache_dir: PathBuf,
     /// Maximum cache size in bytes
     max_size: usize,
+    /// How many items the memory cache holds before evicting to disk-only
+    memory_capacity: usize,
+    /// Compress data files on disk
+    compress: bool,
+    /// Where local files may be cached from
+    media_dirs: Vec<PathBuf>,
     /// Current cache size in bytes
     current_size: Arc<Mutex<usize>>,
+    /// Hit/miss counters
+    counters: Arc<CacheCounters>,
+}
+
+/// Running counters describing how effective the cache is
+#[derive(Debug, Default)]
+struct CacheCounters {
+    memory_hits: AtomicU64,
+    disk_hits: AtomicU64,
+    misses: AtomicU64,
+    stores: AtomicU64,
+    evictions: AtomicU64,
+    bytes_served: AtomicU64,
+}
+
+impl CacheCounters {
+    fn record_hit(&self, counter: &AtomicU64, bytes: usize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.bytes_served.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        self.memory_hits.store(0, Ordering::Relaxed);
+        self.disk_hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.stores.store(0, Ordering::Relaxed);
+        self.evictions.store(0, Ordering::Relaxed);
+        self.bytes_served.store(0, Ordering::Relaxed);
+    }
 }
 
 impl ContentCache {
     /// Create a new content cache with default settings
     pub fn new() -> CasterResult<Self> {
-        let cache_dir = directories::ProjectDirs::from("is", "8b", "q8-caster")
-            .map(|dirs| dirs.cache_dir().to_path_buf())
-            .unwrap_or_else(|| std::env::temp_dir().join("q8-caster-cache"));
-
-        Self::with_config(cache_dir, 500) // 500MB default
+        Self::with_config(&CacheConfig::default())
     }
 
     /// Create a new content cache with custom configuration
-    pub fn with_config(cache_dir: PathBuf, max_size_mb: usize) -> CasterResult<Self> {
-        let max_size = max_size_mb * 1024 * 1024; // Convert to bytes
-        let capacity = NonZeroUsize::new(100).unwrap(); // Store up to 100 items in memory
+    pub fn with_config(config: &CacheConfig) -> CasterResult<Self> {
+        let cache_dir = config.dir.clone().unwrap_or_else(|| {
+            directories::ProjectDirs::from("is", "8b", "q8-caster")
+                .map(|dirs| dirs.cache_dir().to_path_buf())
+                .unwrap_or_else(|| std::env::temp_dir().join("q8-caster-cache"))
+        });
+        let max_size = config.max_size_mb * 1024 * 1024; // Convert to bytes
+        let memory_capacity = config.memory_items.max(1);
 
         std::fs::create_dir_all(&cache_dir)?;
 
         Ok(Self {
-            memory_cache: Arc::new(Mutex::new(LruCache::new(capacity))),
+            memory_cache: Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(memory_capacity).unwrap()))),
             disk_cache: Arc::new(DashMap::new()),
+            recency: Arc::new(Mutex::new(LruCache::unbounded())),
             cache_dir,
             max_size,
+            memory_capacity,
+            compress: config.compress,
+            media_dirs: config.media_dirs.clone(),
             current_size: Arc::new(Mutex::new(0)),
+            counters: Arc::new(CacheCounters::default()),
         })
     }
 
@@ -95,19 +182,29 @@ impl ContentCache {
         // Store on disk for persistence
         let file_path = self.cache_dir.join(&id);
         let meta_path = self.cache_dir.join(format!("{}.meta", &id));
+
+        let codec = (self.compress && is_compressible(&cached_content.mime_type)).then_some(ZSTD);
+        let payload = match codec {
+            Some(_) => tokio::task::spawn_blocking(move || compress(&data)).await
+                .map_err(|e| crate::CasterError::Cache(format!("Compression task failed: {}", e)))??,
+            None => data,
+        };
         
         // Write content data
         let mut file = fs::File::create(&file_path).await?;
-        file.write_all(&data).await?;
+        file.write_all(&payload).await?;
         file.sync_all().await?;
         
-        // Write metadata as JSON (without the data field to save space)
+        // Write metadata as JSON (without the data field to save space). `size` is
+        // the uncompressed size, which is what eviction counts.
         let metadata = serde_json::json!({
             "id": cached_content.id,
             "content_type": cached_content.content_type,
             "source": cached_content.source,
             "mime_type": cached_content.mime_type,
             "size": cached_content.size,
+            "codec": codec,
+            "stored_size": payload.len(),
             "cached_at": cached_content.cached_at,
         });
         let mut meta_file = fs::File::create(&meta_path).await?;
@@ -115,6 +212,7 @@ impl ContentCache {
         meta_file.sync_all().await?;
 
         self.disk_cache.insert(id.clone(), file_path);
+        self.recency.lock().unwrap().put(id.clone(), size);
 
         // Update current size
         {
@@ -122,15 +220,85 @@ impl ContentCache {
             *current_size += size;
         }
 
+        self.counters.stores.fetch_add(1, Ordering::Relaxed);
+
         Ok(id)
     }
 
+    /// `path` resolved, if it lies inside one of the media directories. Links
+    /// and `..` are resolved first, so neither can step outside them.
+    pub fn media_path(&self, path: &str) -> CasterResult<PathBuf> {
+        media_path_in(&self.media_dirs, path)
+    }
+
+    /// Load bytes for a `cache://<key>` reference, an http(s) URL or a local file
+    /// path. Files outside the media directories are refused, as with `media_path`.
+    pub async fn read_source(&self, source: &str) -> CasterResult<Vec<u8>> {
+        if let Some(key) = source.strip_prefix("cache://") {
+            return self
+                .get(key)
+                .await?
+                .map(|content| content.data)
+                .ok_or_else(|| crate::CasterError::NotFound(format!("No cached content for key {}", key)));
+        }
+        if source.starts_with("http://") || source.starts_with("https://") {
+            let response = reqwest::get(source).await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| crate::CasterError::Network(format!("Cannot fetch {}: {}", source, e)))?;
+            let data = response.bytes().await
+                .map_err(|e| crate::CasterError::Network(format!("Cannot fetch {}: {}", source, e)))?;
+            return Ok(data.to_vec());
+        }
+        Ok(fs::read(self.media_path(source)?).await?)
+    }
+
+    /// Copy an http(s) URL or a file under the media directories into the cache,
+    /// returning the key it's stored under. Types that can't be cast are refused
+    /// with `UnsupportedMedia`.
+    pub async fn store_source(&self, source: &str) -> CasterResult<String> {
+        let (data, mime, content_source) = self.read_cacheable(source).await?;
+        let content_type = cacheable_type(&mime)?;
+        self.store(content_type, content_source, data, mime.to_string()).await
+    }
+
+    /// The bytes and MIME type of what `store_source` may store. Any path outside
+    /// the media directories is refused, as the copy can be read back by anyone
+    /// with a token.
+    async fn read_cacheable(&self, source: &str) -> CasterResult<(Vec<u8>, mime_guess::Mime, ContentSource)> {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            let response = reqwest::get(source).await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| crate::CasterError::Network(format!("Cannot fetch {}: {}", source, e)))?;
+            // The server's word first, then the URL's extension
+            let mime = response.headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<mime_guess::Mime>().ok())
+                .filter(|mime| *mime != mime_guess::mime::APPLICATION_OCTET_STREAM)
+                .unwrap_or_else(|| {
+                    let path = url::Url::parse(source).map(|url| url.path().to_string()).unwrap_or_default();
+                    mime_guess::from_path(path).first_or_octet_stream()
+                });
+            let data = response.bytes().await
+                .map_err(|e| crate::CasterError::Network(format!("Cannot fetch {}: {}", source, e)))?;
+            return Ok((data.to_vec(), mime, ContentSource::Url { url: source.to_string() }));
+        }
+
+        let path = self.media_path(source)?;
+        let data = fs::read(&path).await
+            .map_err(|e| crate::CasterError::NotFound(format!("Cannot read {}: {}", source, e)))?;
+        let mime = mime_guess::from_path(&path).first_or_octet_stream();
+        Ok((data, mime, ContentSource::File { path: path.to_string_lossy().into_owned() }))
+    }
+
     /// Retrieve content from cache
     pub async fn get(&self, key: &str) -> CasterResult<Option<CachedContent>> {
         // Try memory cache first
         {
             let mut cache = self.memory_cache.lock().unwrap();
             if let Some(content) = cache.get(key) {
+                self.counters.record_hit(&self.counters.memory_hits, content.size);
+                self.touch(key);
                 return Ok(Some(content.clone()));
             }
         }
@@ -144,6 +312,15 @@ impl ContentCache {
                 let data = fs::read(path.value()).await?;
                 let meta_data = fs::read(&meta_path).await?;
                 let metadata: serde_json::Value = serde_json::from_slice(&meta_data)?;
+                // No codec means the data was written as is
+                let data = match metadata["codec"].as_str() {
+                    None => data,
+                    Some(ZSTD) => {
+                        tokio::task::spawn_blocking(move || decompress(&data)).await
+                            .map_err(|e| crate::CasterError::Cache(format!("Decompression task failed: {}", e)))??
+                    }
+                    Some(codec) => return Err(crate::CasterError::Cache(format!("Cached item {} uses unknown codec {}", key, codec))),
+                };
                 
                 // Reconstruct CachedContent from metadata and data
                 let cached_content = CachedContent {
@@ -161,21 +338,22 @@ impl ContentCache {
                     let mut cache = self.memory_cache.lock().unwrap();
                     cache.put(key.to_string(), cached_content.clone());
                 }
-                
+
+                self.counters.record_hit(&self.counters.disk_hits, cached_content.size);
+                self.touch(key);
                 return Ok(Some(cached_content));
             }
         }
 
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
         Ok(None)
     }
 
     /// Remove content from cache
     pub async fn remove(&self, key: &str) -> CasterResult<()> {
         // Remove from memory
-        let size = {
-            let mut cache = self.memory_cache.lock().unwrap();
-            cache.pop(key).map(|c| c.size)
-        };
+        self.memory_cache.lock().unwrap().pop(key);
+        let size = self.recency.lock().unwrap().pop(key);
 
         // Remove from disk (both data and metadata files)
         if let Some((_, path)) = self.disk_cache.remove(key) {
@@ -203,6 +381,7 @@ impl ContentCache {
 
         // Clear disk cache
         self.disk_cache.clear();
+        self.recency.lock().unwrap().clear();
 
         // Remove all files
         let mut read_dir = fs::read_dir(&self.cache_dir).await?;
@@ -219,8 +398,40 @@ impl ContentCache {
         Ok(())
     }
 
+    /// Drop files a store left half-written and make the rest durable, e.g. before
+    /// exiting. Hold the cache exclusively so no store is still in flight.
+    pub async fn flush(&self) -> CasterResult<usize> {
+        let mut removed = 0;
+        let mut read_dir = fs::read_dir(&self.cache_dir).await?;
+        while let Some(entry) = read_dir.next_entry().await? {
+            let path = entry.path();
+            // A store writes the data, then its .meta; either one alone is unfinished
+            let partner = match path.extension() {
+                Some(ext) if ext == "meta" => path.with_extension(""),
+                _ => self.cache_dir.join(format!("{}.meta", entry.file_name().to_string_lossy())),
+            };
+            if !fs::try_exists(&partner).await.unwrap_or(false) {
+                let _ = fs::remove_file(&path).await;
+                removed += 1;
+            }
+        }
+
+        // New directory entries aren't durable until the directory itself is synced
+        #[cfg(unix)]
+        std::fs::File::open(&self.cache_dir)?.sync_all()?;
+
+        Ok(removed)
+    }
+
     /// Ensure there's enough capacity for new content
     async fn ensure_capacity(&self, needed: usize) -> CasterResult<()> {
+        // Evicting can't make room for an item bigger than the whole cache
+        if needed > self.max_size {
+            return Err(crate::error::CasterError::Cache(
+                format!("Cannot fit item of size {} bytes in cache (max: {} bytes)", needed, self.max_size)
+            ));
+        }
+
         loop {
             let current_size = *self.current_size.lock().unwrap();
             
@@ -232,7 +443,7 @@ impl ContentCache {
             let to_free = (current_size + needed) - self.max_size;
             
             // Check if we have items to evict
-            let has_items = self.memory_cache.lock().unwrap().len() > 0;
+            let has_items = !self.recency.lock().unwrap().is_empty();
             if !has_items {
                 // No items to evict but still need space - this is an error condition
                 return Err(crate::error::CasterError::Cache(
@@ -246,16 +457,16 @@ impl ContentCache {
         Ok(())
     }
 
-    /// Evict least recently used items
+    /// Evict least recently used items, whether or not they're still in memory
     async fn evict_lru(&self, target_size: usize) -> CasterResult<()> {
         let mut freed = 0;
         let keys_to_remove: Vec<String> = {
-            let mut cache = self.memory_cache.lock().unwrap();
+            let mut recency = self.recency.lock().unwrap();
             let mut keys = Vec::new();
 
             while freed < target_size {
-                if let Some((key, content)) = cache.pop_lru() {
-                    freed += content.size;
+                if let Some((key, size)) = recency.pop_lru() {
+                    freed += size;
                     keys.push(key);
                 } else {
                     break;
@@ -264,6 +475,14 @@ impl ContentCache {
 
             keys
         };
+        {
+            let mut cache = self.memory_cache.lock().unwrap();
+            for key in &keys_to_remove {
+                cache.pop(key);
+            }
+        }
+
+        self.counters.evictions.fetch_add(keys_to_remove.len() as u64, Ordering::Relaxed);
 
         // Remove from disk cache (both data and metadata files)
         for key in keys_to_remove {
@@ -283,6 +502,141 @@ impl ContentCache {
         Ok(())
     }
 
+    /// What is known about one item. Doesn't count as a hit or touch LRU order.
+    pub async fn entry(&self, key: &str) -> CasterResult<Option<CacheEntry>> {
+        {
+            let cache = self.memory_cache.lock().unwrap();
+            if let Some(content) = cache.peek(key) {
+                return Ok(Some(CacheEntry {
+                    id: content.id.clone(),
+                    content_type: content.content_type.clone(),
+                    source: content.source.clone(),
+                    mime_type: content.mime_type.clone(),
+                    size: content.size,
+                    cached_at: content.cached_at,
+                    in_memory: true,
+                }));
+            }
+        }
+
+        // Only keys we stored, so a key can't point outside the cache directory
+        if !self.disk_cache.contains_key(key) {
+            return Ok(None);
+        }
+        let Some(metadata) = self.read_metadata(key).await? else {
+            return Ok(None);
+        };
+        Ok(Some(CacheEntry {
+            id: metadata["id"].as_str().unwrap_or(key).to_string(),
+            content_type: serde_json::from_value(metadata["content_type"].clone())?,
+            source: serde_json::from_value(metadata["source"].clone())?,
+            mime_type: metadata["mime_type"].as_str().unwrap_or("application/octet-stream").to_string(),
+            size: metadata["size"].as_u64().unwrap_or(0) as usize,
+            cached_at: serde_json::from_value(metadata["cached_at"].clone())?,
+            in_memory: false,
+        }))
+    }
+
+    /// Size of an item's data in bytes, without reading it
+    pub async fn content_length(&self, key: &str) -> CasterResult<Option<usize>> {
+        Ok(self.entry(key).await?.map(|entry| entry.size))
+    }
+
+    /// Bytes `start..end` of an item, clamped to its length. Reads only that slice
+    /// from disk, so large media can be served in pieces. Compressed items have to
+    /// be decompressed whole first.
+    pub async fn get_range(&self, key: &str, start: usize, end: usize) -> CasterResult<Option<Vec<u8>>> {
+        let slice = |data: &[u8]| {
+            let end = end.min(data.len());
+            data[start.min(end)..end].to_vec()
+        };
+
+        {
+            let mut cache = self.memory_cache.lock().unwrap();
+            if let Some(content) = cache.get(key) {
+                let data = slice(&content.data);
+                self.counters.record_hit(&self.counters.memory_hits, data.len());
+                self.touch(key);
+                return Ok(Some(data));
+            }
+        }
+
+        let path = match self.disk_cache.get(key) {
+            Some(path) => path.value().clone(),
+            None => {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+                return Ok(None);
+            }
+        };
+        let Some(metadata) = self.read_metadata(key).await? else {
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        };
+        if !metadata["codec"].is_null() {
+            // Counted by get
+            return Ok(self.get(key).await?.map(|content| slice(&content.data)));
+        }
+
+        let mut file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let length = file.metadata().await?.len() as usize;
+        let end = end.min(length);
+        let start = start.min(end);
+
+        file.seek(std::io::SeekFrom::Start(start as u64)).await?;
+        let mut data = vec![0; end - start];
+        file.read_exact(&mut data).await?;
+
+        self.counters.record_hit(&self.counters.disk_hits, data.len());
+        self.touch(key);
+        Ok(Some(data))
+    }
+
+    /// Mark an item as just used, moving it to the back of the eviction order
+    fn touch(&self, key: &str) {
+        self.recency.lock().unwrap().promote(key);
+    }
+
+    /// An item's .meta JSON, None if it isn't on disk
+    async fn read_metadata(&self, key: &str) -> CasterResult<Option<serde_json::Value>> {
+        let meta_path = self.cache_dir.join(format!("{}.meta", key));
+        match fs::read(&meta_path).await {
+            Ok(meta_data) => Ok(Some(serde_json::from_slice(&meta_data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Every cached item, newest first. Doesn't count as a hit or touch LRU order.
+    pub async fn list_entries(&self) -> CasterResult<Vec<CacheEntry>> {
+        let keys: Vec<String> = self.disk_cache.iter().map(|entry| entry.key().clone()).collect();
+        let mut entries = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            // None if it was evicted while we were listing
+            if let Some(entry) = self.entry(&key).await? {
+                entries.push(entry);
+            }
+        }
+
+        entries.sort_by_key(|e| std::cmp::Reverse(e.cached_at));
+        Ok(entries)
+    }
+
+    /// Check items can still be written to the cache directory
+    pub fn check_writable(&self) -> CasterResult<()> {
+        let probe = self.cache_dir.join(format!(".probe-{}", uuid::Uuid::new_v4()));
+        let written = std::fs::write(&probe, b"ok");
+        let _ = std::fs::remove_file(&probe);
+        written.map_err(|e| crate::CasterError::Cache(format!("Cache directory {} isn't writable: {}", self.cache_dir.display(), e)))
+    }
+
     /// Get cache statistics
     pub fn stats(&self) -> CacheStats {
         let memory_count = self.memory_cache.lock().unwrap().len();
@@ -294,6 +648,8 @@ impl ContentCache {
             disk_items: disk_count,
             total_size_bytes: current_size,
             max_size_bytes: self.max_size,
+            memory_capacity: self.memory_capacity,
+            ..self.counter_stats()
         }
     }
     
@@ -308,6 +664,40 @@ impl ContentCache {
             disk_items: disk_count,
             total_size_bytes: disk_usage,
             max_size_bytes: self.max_size,
+            memory_capacity: self.memory_capacity,
+            ..self.counter_stats()
+        }
+    }
+
+    /// Reset hit/miss counters without touching cached content
+    pub fn reset_counters(&self) {
+        self.counters.reset();
+    }
+
+    /// Same as [`reset_counters`](Self::reset_counters); sizes and item counts
+    /// in [`CacheStats`] describe what's cached and aren't reset
+    pub fn reset_stats(&self) {
+        self.reset_counters();
+    }
+
+    /// Snapshot of the hit/miss counters with the size fields zeroed
+    fn counter_stats(&self) -> CacheStats {
+        let c = &self.counters;
+        let memory_hits = c.memory_hits.load(Ordering::Relaxed);
+        let disk_hits = c.disk_hits.load(Ordering::Relaxed);
+        CacheStats {
+            memory_items: 0,
+            disk_items: 0,
+            total_size_bytes: 0,
+            max_size_bytes: 0,
+            memory_capacity: 0,
+            hits: memory_hits + disk_hits,
+            memory_hits,
+            disk_hits,
+            misses: c.misses.load(Ordering::Relaxed),
+            stores: c.stores.load(Ordering::Relaxed),
+            evictions: c.evictions.load(Ordering::Relaxed),
+            bytes_served: c.bytes_served.load(Ordering::Relaxed),
         }
     }
 
@@ -329,6 +719,72 @@ impl ContentCache {
     }
 }
 
+/// How cached content of type `mime` will be cast; `UnsupportedMedia` for types nothing can show
+fn cacheable_type(mime: &mime_guess::Mime) -> CasterResult<ContentType> {
+    Ok(match (mime.type_().as_str(), mime.subtype().as_str()) {
+        ("image", format) => ContentType::Image { format: format.to_string() },
+        ("video", container) => ContentType::Video { codec: "auto".into(), container: container.to_string() },
+        ("audio", format) => ContentType::Audio { codec: "auto".into(), format: format.to_string() },
+        ("application", "pdf") => ContentType::Pdf { page: None },
+        ("text", "markdown" | "x-markdown" | "plain") => ContentType::Markdown { theme: None, math: false },
+        _ => return Err(crate::CasterError::UnsupportedMedia(format!("Can't cache {} content", mime))),
+    })
+}
+
+/// `path` resolved, if it lies inside one of `media_dirs`; the check behind
+/// [`ContentCache::media_path`], for callers that hold the directories themselves
+pub fn media_path_in(media_dirs: &[PathBuf], path: &str) -> CasterResult<PathBuf> {
+    let resolved = std::fs::canonicalize(path)
+        .map_err(|e| crate::CasterError::NotFound(format!("Cannot read {}: {}", path, e)))?;
+    let allowed = media_dirs.iter()
+        .filter_map(|dir| std::fs::canonicalize(dir).ok())
+        .any(|dir| resolved.starts_with(&dir));
+    match allowed {
+        true => Ok(resolved),
+        false => Err(crate::CasterError::InvalidRequest(format!(
+            "{} is outside the media directories; use a URL or a file under cache.media_dirs",
+            path
+        ))),
+    }
+}
+
+const ZSTD: &str = "zstd";
+
+/// Level 3, zstd's default: about gzip's ratio at several times its speed
+const ZSTD_LEVEL: i32 = 3;
+
+/// Whether compressing a payload of this type is worth it; media and archives
+/// are compressed already
+fn is_compressible(mime_type: &str) -> bool {
+    const COMPRESSED: &[&str] = &[
+        "application/gzip",
+        "application/x-gzip",
+        "application/zip",
+        "application/zstd",
+        "application/x-xz",
+        "application/x-bzip2",
+        "application/x-7z-compressed",
+        "application/pdf",
+    ];
+
+    let mime_type = mime_type.split(';').next().unwrap_or("").trim();
+    if mime_type == "image/svg+xml" {
+        return true;
+    }
+    !(mime_type.starts_with("image/")
+        || mime_type.starts_with("video/")
+        || mime_type.starts_with("audio/")
+        || COMPRESSED.contains(&mime_type))
+}
+
+fn compress(data: &[u8]) -> CasterResult<Vec<u8>> {
+    Ok(zstd::encode_all(data, ZSTD_LEVEL)?)
+}
+
+fn decompress(data: &[u8]) -> CasterResult<Vec<u8>> {
+    Ok(zstd::decode_all(data)?)
+}
+
 /// Cache statistics
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct CacheStats {
@@ -336,4 +792,223 @@ pub struct CacheStats {
     pub disk_items: usize,
     pub total_size_bytes: usize,
     pub max_size_bytes: usize,
+    pub memory_capacity: usize,
+    /// Reads found in memory or on disk
+    pub hits: u64,
+    pub memory_hits: u64,
+    pub disk_hits: u64,
+    pub misses: u64,
+    pub stores: u64,
+    pub evictions: u64,
+    pub bytes_served: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("q8-caster-{}-{}", name, Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn only_files_in_media_dirs_can_be_read() {
+        let media = test_dir("media");
+        let outside = test_dir("outside");
+        std::fs::write(media.join("clip.mp4"), b"clip").unwrap();
+        std::fs::write(outside.join("secret.txt"), b"secret").unwrap();
+
+        let cache = ContentCache::with_config(&CacheConfig {
+            dir: Some(test_dir("cache")),
+            media_dirs: vec![media.clone()],
+            ..CacheConfig::default()
+        }).unwrap();
+
+        let clip = media.join("clip.mp4");
+        assert_eq!(cache.media_path(&clip.to_string_lossy()).unwrap(), clip.canonicalize().unwrap());
+        let secret = outside.join("secret.txt");
+        assert!(matches!(cache.media_path(&secret.to_string_lossy()), Err(crate::CasterError::InvalidRequest(_))));
+        // `..` is resolved before the check
+        let escape = format!("{}/../{}/secret.txt", media.display(), outside.file_name().unwrap().to_string_lossy());
+        assert!(matches!(cache.media_path(&escape), Err(crate::CasterError::InvalidRequest(_))));
+
+        // No media directories, no local files
+        let closed = ContentCache::with_config(&CacheConfig { dir: Some(test_dir("cache")), ..CacheConfig::default() }).unwrap();
+        assert!(closed.media_path(&clip.to_string_lossy()).is_err());
+
+        // Casts read their sources through the same check
+        assert_eq!(cache.read_source(&clip.to_string_lossy()).await.unwrap(), b"clip");
+        assert!(matches!(cache.read_source(&secret.to_string_lossy()).await, Err(crate::CasterError::InvalidRequest(_))));
+        assert!(matches!(cache.read_source(&escape).await, Err(crate::CasterError::InvalidRequest(_))));
+        assert!(closed.read_source(&clip.to_string_lossy()).await.is_err());
+        // Missing files and keys aren't there, rather than refused
+        let missing = media.join("missing.mp4");
+        assert!(matches!(cache.read_source(&missing.to_string_lossy()).await, Err(crate::CasterError::NotFound(_))));
+        assert!(matches!(cache.read_source("cache://no-such-key").await, Err(crate::CasterError::NotFound(_))));
+
+        for dir in [media, outside, cache.cache_dir.clone(), closed.cache_dir.clone()] {
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn unknown_types_are_not_cached_as_documents() {
+        let mime = |name: &str| mime_guess::from_path(name).first_or_octet_stream();
+
+        assert!(matches!(cacheable_type(&mime("talk.pdf")), Ok(ContentType::Pdf { .. })));
+        assert!(matches!(cacheable_type(&mime("notes.md")), Ok(ContentType::Markdown { .. })));
+        assert!(matches!(cacheable_type(&mime("tool.exe")), Err(crate::CasterError::UnsupportedMedia(_))));
+    }
+
+    #[tokio::test]
+    async fn sources_are_stored_under_a_key_the_cache_picks() {
+        let media = test_dir("media");
+        std::fs::write(media.join("notes.md"), b"# Notes").unwrap();
+        std::fs::write(media.join("tool.exe"), b"MZ").unwrap();
+        let cache = ContentCache::with_config(&CacheConfig {
+            dir: Some(test_dir("cache")),
+            media_dirs: vec![media.clone()],
+            ..CacheConfig::default()
+        }).unwrap();
+
+        let key = cache.store_source(&media.join("notes.md").to_string_lossy()).await.unwrap();
+        let cached = cache.get(&key).await.unwrap().unwrap();
+        assert_eq!(cached.data, b"# Notes");
+        assert!(matches!(cached.content_type, ContentType::Markdown { .. }));
+
+        let refused = cache.store_source(&media.join("tool.exe").to_string_lossy()).await;
+        assert!(matches!(refused, Err(crate::CasterError::UnsupportedMedia(_))));
+        assert!(matches!(cache.store_source("/etc/hostname").await, Err(crate::CasterError::InvalidRequest(_))));
+
+        for dir in [media, cache.cache_dir.clone()] {
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    fn compressing_cache() -> ContentCache {
+        ContentCache::with_config(&CacheConfig {
+            dir: Some(test_dir("cache")),
+            compress: true,
+            ..CacheConfig::default()
+        }).unwrap()
+    }
+
+    async fn store_text(cache: &ContentCache, data: Vec<u8>, mime_type: &str) -> String {
+        let content_type = ContentType::Markdown { theme: None, math: false };
+        let source = ContentSource::Memory { data: Vec::new() };
+        cache.store(content_type, source, data, mime_type.to_string()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn the_memory_cache_lets_go_of_its_least_recently_used_item() {
+        let cache = ContentCache::with_config(&CacheConfig {
+            dir: Some(test_dir("cache")),
+            memory_items: 2,
+            ..CacheConfig::default()
+        }).unwrap();
+        let first = store_text(&cache, b"first".to_vec(), "text/plain").await;
+        let second = store_text(&cache, b"second".to_vec(), "text/plain").await;
+        cache.get(&first).await.unwrap();
+        let third = store_text(&cache, b"third".to_vec(), "text/plain").await;
+
+        {
+            let memory = cache.memory_cache.lock().unwrap();
+            assert!(memory.contains(&first));
+            assert!(!memory.contains(&second));
+            assert!(memory.contains(&third));
+        }
+        // Still on disk
+        assert_eq!(cache.get(&second).await.unwrap().unwrap().data, b"second");
+
+        std::fs::remove_dir_all(&cache.cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_full_cache_evicts_its_least_recently_used_item_from_disk() {
+        let cache = ContentCache::with_config(&CacheConfig {
+            dir: Some(test_dir("cache")),
+            max_size_mb: 1,
+            memory_items: 1,
+            ..CacheConfig::default()
+        }).unwrap();
+        let item = |byte| vec![byte; 400 * 1024];
+        let first = store_text(&cache, item(1), "text/plain").await;
+        let second = store_text(&cache, item(2), "text/plain").await;
+        // Only on disk now, but used more recently than the second
+        assert!(!cache.memory_cache.lock().unwrap().contains(&first));
+        cache.get_range(&first, 0, 1).await.unwrap();
+
+        let third = store_text(&cache, item(3), "text/plain").await;
+
+        assert!(cache.get(&second).await.unwrap().is_none());
+        assert!(!cache.cache_dir.join(&second).exists());
+        assert!(!cache.cache_dir.join(format!("{}.meta", second)).exists());
+        assert_eq!(cache.get(&first).await.unwrap().unwrap().data, item(1));
+        assert_eq!(cache.get(&third).await.unwrap().unwrap().data, item(3));
+
+        let stats = cache.stats();
+        assert_eq!(stats.disk_items, 2);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.total_size_bytes, 2 * 400 * 1024);
+
+        std::fs::remove_dir_all(&cache.cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn an_item_bigger_than_the_cache_is_refused_without_evicting_anything() {
+        let cache = ContentCache::with_config(&CacheConfig {
+            dir: Some(test_dir("cache")),
+            max_size_mb: 1,
+            ..CacheConfig::default()
+        }).unwrap();
+        let kept = store_text(&cache, vec![1; 400 * 1024], "text/plain").await;
+
+        let content_type = ContentType::Markdown { theme: None, math: false };
+        let source = ContentSource::Memory { data: Vec::new() };
+        let oversized = cache.store(content_type, source, vec![2; 2 * 1024 * 1024], "text/plain".to_string()).await;
+        assert!(oversized.is_err());
+
+        assert_eq!(cache.get(&kept).await.unwrap().unwrap().data, vec![1; 400 * 1024]);
+        let stats = cache.stats();
+        assert_eq!(stats.disk_items, 1);
+        assert_eq!(stats.evictions, 0);
+
+        std::fs::remove_dir_all(&cache.cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn compressed_items_round_trip_through_zstd() {
+        let cache = compressing_cache();
+        let text = "# Heading\n\nThe same line over and over.\n".repeat(200).into_bytes();
+        let key = store_text(&cache, text.clone(), "text/markdown").await;
+
+        let on_disk = std::fs::read(cache.cache_dir.join(&key)).unwrap();
+        assert_eq!(on_disk[..4], [0x28, 0xB5, 0x2F, 0xFD], "not a zstd frame");
+        assert!(on_disk.len() < text.len() / 10, "{} bytes from {}", on_disk.len(), text.len());
+
+        // Read back from disk rather than memory
+        cache.memory_cache.lock().unwrap().clear();
+        let content = cache.get(&key).await.unwrap().unwrap();
+        assert_eq!(content.data, text);
+        assert_eq!(content.size, text.len());
+        // Ranges of compressed items come from the decoded data
+        assert_eq!(cache.get_range(&key, 2, 9).await.unwrap().unwrap(), b"Heading");
+
+        std::fs::remove_dir_all(&cache.cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn media_is_stored_as_is() {
+        let cache = compressing_cache();
+        let png = vec![0x89, b'P', b'N', b'G', 1, 2, 3, 4];
+        let key = store_text(&cache, png.clone(), "image/png").await;
+        assert_eq!(std::fs::read(cache.cache_dir.join(&key)).unwrap(), png);
+
+        cache.memory_cache.lock().unwrap().clear();
+        assert_eq!(cache.get(&key).await.unwrap().unwrap().data, png);
+
+        std::fs::remove_dir_all(&cache.cache_dir).unwrap();
+    }
 }
@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use crate::cache::ContentCache;
+use crate::{ContentSource, ContentType};
+
+/// What an idle display shows, from the `[ambient]` section of the server config
+/// or `POST /api/displays/:id/ambient`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmbientConfig {
+    pub enabled: bool,
+    /// Seconds with nothing cast before the ambient screen appears
+    pub idle_timeout_secs: u64,
+    /// Shown under the clock; the hostname if unset
+    pub device_name: Option<String>,
+    /// Encoded in the join QR code; the dashboard on our LAN address if unset
+    pub dashboard_url: Option<String>,
+    /// Cached images whose key or source starts with this are cycled behind the clock
+    pub image_prefix: Option<String>,
+    /// Seconds each background image stays up
+    pub image_interval_secs: u64,
+}
+
+impl Default for AmbientConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            idle_timeout_secs: 60,
+            device_name: None,
+            dashboard_url: None,
+            image_prefix: None,
+            image_interval_secs: 15,
+        }
+    }
+}
+
+/// An ambient config with everything the window thread can't fetch itself
+#[derive(Clone, Default)]
+pub struct Ambient {
+    pub config: AmbientConfig,
+    /// Encoded background images
+    pub images: Arc<Vec<Vec<u8>>>,
+    /// PNG of the join QR code, if one could be generated
+    pub join_qr: Option<Arc<Vec<u8>>>,
+}
+
+impl fmt::Debug for Ambient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ambient")
+            .field("config", &self.config)
+            .field("images", &self.images.len())
+            .field("join_qr", &self.join_qr.is_some())
+            .finish()
+    }
+}
+
+impl Ambient {
+    /// Fill in the dashboard URL, make the join code and load the background images.
+    /// Missing pieces just leave them off the screen.
+    pub async fn load(mut config: AmbientConfig, cache: &ContentCache) -> Self {
+        if config.dashboard_url.is_none() {
+            config.dashboard_url = crate::network::media_registry().server_url();
+        }
+
+        let join_qr = match config.dashboard_url {
+            Some(ref url) if config.enabled => qr_png(url).await.map(Arc::new),
+            _ => None,
+        };
+
+        let mut images = Vec::new();
+        if let Some(ref prefix) = config.image_prefix {
+            let entries = match cache.list_entries().await {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("Cannot list cached ambient images: {}", e);
+                    Vec::new()
+                }
+            };
+            for entry in entries {
+                let source = match entry.source {
+                    ContentSource::File { ref path } => path.as_str(),
+                    ContentSource::Url { ref url } => url.as_str(),
+                    _ => "",
+                };
+                if !matches!(entry.content_type, ContentType::Image { .. })
+                    || !(entry.id.starts_with(prefix.as_str()) || source.starts_with(prefix.as_str()))
+                {
+                    continue;
+                }
+                match cache.get(&entry.id).await {
+                    Ok(Some(content)) => images.push(content.data),
+                    Ok(None) => {}
+                    Err(e) => warn!("Cannot load ambient image {}: {}", entry.id, e),
+                }
+            }
+        }
+
+        Self {
+            config,
+            images: Arc::new(images),
+            join_qr,
+        }
+    }
+}
+
+/// `url` as a QR code PNG, drawn by the `qrencode` tool if it's installed
+async fn qr_png(url: &str) -> Option<Vec<u8>> {
+    let output = Command::new("qrencode")
+        .args(["-t", "PNG", "-m", "1", "-s", "8", "-o", "-", url])
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => Some(output.stdout),
+        Ok(output) => {
+            warn!("qrencode failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+            None
+        }
+        Err(e) => {
+            // Not installed; the URL is still shown as text
+            debug!("No join code without qrencode: {}", e);
+            None
+        }
+    }
+}
+
+/// Clock, device name, join code and background slideshow for an idle window
+pub(crate) struct AmbientView {
+    ambient: Ambient,
+    device_name: String,
+    qr_texture: Option<egui::TextureHandle>,
+    qr_loaded: bool,
+    image_index: usize,
+    image_shown_at: Instant,
+    // Background for `image_index`, decoded when it comes up
+    background: Option<(usize, egui::TextureHandle)>,
+}
+
+impl AmbientView {
+    pub(crate) fn new(ambient: Ambient) -> Self {
+        let device_name = ambient.config.device_name.clone()
+            .unwrap_or_else(|| gethostname::gethostname().to_string_lossy().into_owned());
+
+        Self {
+            ambient,
+            device_name,
+            qr_texture: None,
+            qr_loaded: false,
+            image_index: 0,
+            image_shown_at: Instant::now(),
+            background: None,
+        }
+    }
+
+    pub(crate) fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.ambient.config.idle_timeout_secs)
+    }
+
+    /// When the screen next changes: the clock's next minute or the next slide
+    pub(crate) fn next_change(&self) -> Instant {
+        let now = chrono::Local::now();
+        let next_minute = Instant::now() + Duration::from_secs(60 - now.timestamp() as u64 % 60);
+        if self.ambient.images.len() > 1 {
+            next_minute.min(self.image_shown_at + self.image_interval())
+        } else {
+            next_minute
+        }
+    }
+
+    /// Start the slideshow over, e.g. each time the display goes idle
+    pub(crate) fn restart(&mut self) {
+        self.image_shown_at = Instant::now();
+    }
+
+    fn image_interval(&self) -> Duration {
+        Duration::from_secs(self.ambient.config.image_interval_secs.max(1))
+    }
+
+    fn load(ctx: &egui::Context, name: &str, data: &[u8], options: egui::TextureOptions) -> Option<egui::TextureHandle> {
+        let image = match image::load_from_memory(data) {
+            Ok(image) => image.to_rgba8(),
+            Err(e) => {
+                warn!("Skipping ambient image {}: {}", name, e);
+                return None;
+            }
+        };
+        let size = [image.width() as usize, image.height() as usize];
+        let image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+        Some(ctx.load_texture(name, image, options))
+    }
+
+    fn poll(&mut self, ctx: &egui::Context) {
+        if !self.qr_loaded {
+            self.qr_loaded = true;
+            if let Some(ref png) = self.ambient.join_qr {
+                // Nearest keeps the modules crisp when scaled up
+                self.qr_texture = Self::load(ctx, "ambient-qr", png, egui::TextureOptions::NEAREST);
+            }
+        }
+
+        let count = self.ambient.images.len();
+        if count == 0 {
+            return;
+        }
+        if count > 1 && self.image_shown_at.elapsed() >= self.image_interval() {
+            self.image_index = (self.image_index + 1) % count;
+            self.image_shown_at = Instant::now();
+        }
+        if self.background.as_ref().map(|(index, _)| *index) != Some(self.image_index) {
+            let name = format!("ambient-image-{}", self.image_index);
+            let data = &self.ambient.images[self.image_index];
+            self.background = Self::load(ctx, &name, data, egui::TextureOptions::LINEAR)
+                .map(|texture| (self.image_index, texture));
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        self.poll(ui.ctx());
+        let area = ui.max_rect();
+        let painter = ui.painter();
+
+        painter.rect_filled(area, 0.0, egui::Color32::from_rgb(12, 14, 20));
+        if let Some((_, ref texture)) = self.background {
+            // Cover the screen, cropping whatever overhangs
+            let size = texture.size_vec2();
+            let scale = (area.width() / size.x).max(area.height() / size.y);
+            let rect = egui::Rect::from_center_size(area.center(), size * scale);
+            painter.with_clip_rect(area).image(
+                texture.id(),
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+            // Dimmed so the clock stays readable on bright photos
+            painter.rect_filled(area, 0.0, egui::Color32::from_black_alpha(110));
+        }
+
+        let now = chrono::Local::now();
+        let margin = 48.0;
+        let corner = area.left_bottom() + egui::vec2(margin, -margin);
+        painter.text(
+            corner - egui::vec2(0.0, 72.0),
+            egui::Align2::LEFT_BOTTOM,
+            now.format("%H:%M").to_string(),
+            egui::FontId::proportional(120.0),
+            egui::Color32::WHITE,
+        );
+        painter.text(
+            corner - egui::vec2(0.0, 36.0),
+            egui::Align2::LEFT_BOTTOM,
+            now.format("%A %-d %B").to_string(),
+            egui::FontId::proportional(28.0),
+            egui::Color32::from_white_alpha(220),
+        );
+        painter.text(
+            corner,
+            egui::Align2::LEFT_BOTTOM,
+            &self.device_name,
+            egui::FontId::proportional(22.0),
+            egui::Color32::from_white_alpha(170),
+        );
+
+        let url = self.ambient.config.dashboard_url.as_deref().unwrap_or_default();
+        let join_corner = area.right_bottom() + egui::vec2(-margin, -margin);
+        if let Some(ref qr) = self.qr_texture {
+            let side = 180.0;
+            let rect = egui::Rect::from_min_size(join_corner - egui::vec2(side, side + 32.0), egui::vec2(side, side));
+            painter.rect_filled(rect.expand(8.0), 8.0, egui::Color32::WHITE);
+            painter.image(
+                qr.id(),
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+        }
+        if !url.is_empty() {
+            painter.text(
+                join_corner,
+                egui::Align2::RIGHT_BOTTOM,
+                format!("Cast here: {}", url),
+                egui::FontId::proportional(18.0),
+                egui::Color32::from_white_alpha(200),
+            );
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use crate::media::audio::{AudioFeed, TAP_SAMPLE_RATE, TAP_WINDOW};
+use crate::render::audio::{compute_levels, compute_stft, AudioLevels};
+
+/// Spectrum bars across the view, spaced by octave
+const BARS: usize = 48;
+/// Frequencies the bars cover; the tap is resampled to well under 20kHz
+const LOWEST_BAR_HZ: f32 = 40.0;
+/// Range of the bars, in dB below a full-scale sine
+const FLOOR_DB: f32 = 60.0;
+/// How much of a bar's height survives each update, so peaks fall rather than flicker
+const BAR_DECAY: f32 = 0.8;
+
+/// A live spectrum and level for the audio the media engine is playing. The
+/// feed wakes the window as samples arrive, and each redraw analyses the
+/// newest ones. While the feed is paused the view holds its last analysis.
+pub(crate) struct AudioView {
+    feed: Arc<AudioFeed>,
+    // Bar heights and levels as last analysed, kept as they are while paused
+    bars: [f32; BARS],
+    levels: AudioLevels,
+    // Chunks the feed had received when it was last analysed
+    analysed: u64,
+}
+
+impl AudioView {
+    /// `wake` is called from the feed's thread, at most 30 times a second
+    pub(crate) fn new(feed: Arc<AudioFeed>, wake: impl Fn() + Send + 'static) -> Self {
+        feed.set_wake(wake);
+        Self {
+            feed,
+            bars: [0.0; BARS],
+            levels: AudioLevels::default(),
+            analysed: 0,
+        }
+    }
+
+    /// Analyse the newest samples, unless nothing new has arrived or playback is paused
+    fn update(&mut self) {
+        let chunks = self.feed.chunks();
+        if self.feed.is_paused() || chunks == self.analysed {
+            return;
+        }
+        self.analysed = chunks;
+
+        let samples = self.feed.recent_samples();
+        // The last frame's worth sets the level, so the meter follows the beat
+        let frame = (TAP_SAMPLE_RATE / 30) as usize;
+        self.levels = compute_levels(&samples[samples.len().saturating_sub(frame)..]);
+
+        // Raw magnitudes, so quiet passages look quiet; a full-scale sine peaks at a quarter of the window
+        let Some(spectrum) = compute_stft(&samples, TAP_WINDOW, 0.0).into_iter().next() else {
+            return;
+        };
+        let full_scale = TAP_WINDOW as f32 / 4.0;
+        let bin_hz = TAP_SAMPLE_RATE as f32 / TAP_WINDOW as f32;
+        let nyquist = TAP_SAMPLE_RATE as f32 / 2.0;
+
+        for (i, bar) in self.bars.iter_mut().enumerate() {
+            let band = |i: usize| LOWEST_BAR_HZ * (nyquist / LOWEST_BAR_HZ).powf(i as f32 / BARS as f32);
+            let first = (band(i) / bin_hz) as usize;
+            let last = ((band(i + 1) / bin_hz) as usize).max(first + 1).min(spectrum.len());
+            let magnitude = spectrum[first.min(last - 1)..last].iter().copied().fold(0.0f32, f32::max);
+
+            let db = 20.0 * (magnitude / full_scale).max(1e-6).log10();
+            let height = (1.0 + db / FLOOR_DB).clamp(0.0, 1.0);
+            *bar = height.max(*bar * BAR_DECAY);
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        self.update();
+        let available = ui.available_rect_before_wrap();
+
+        if self.feed.is_ended() {
+            ui.centered_and_justified(|ui| {
+                ui.label("Audio playback ended");
+            });
+            return;
+        }
+        if self.analysed == 0 {
+            ui.centered_and_justified(|ui| {
+                ui.spinner();
+            });
+            return;
+        }
+
+        let painter = ui.painter();
+        let margin = available.height() * 0.1;
+        let meter_height = (available.height() * 0.04).max(6.0);
+        let spectrum = egui::Rect::from_min_max(
+            available.min + egui::vec2(margin, margin),
+            available.max - egui::vec2(margin, margin * 1.5 + meter_height),
+        );
+
+        // Bars, blue when quiet to red when loud
+        let slot = spectrum.width() / BARS as f32;
+        for (i, &height) in self.bars.iter().enumerate() {
+            let top = spectrum.bottom() - height * spectrum.height();
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(spectrum.left() + i as f32 * slot + slot * 0.1, top),
+                egui::pos2(spectrum.left() + (i + 1) as f32 * slot - slot * 0.1, spectrum.bottom()),
+            );
+            painter.rect_filled(bar, 2.0, ramp(height));
+        }
+
+        // Level meter under the bars: RMS filled, peak as a tick
+        let meter = egui::Rect::from_min_size(
+            egui::pos2(spectrum.left(), spectrum.bottom() + margin * 0.5),
+            egui::vec2(spectrum.width(), meter_height),
+        );
+        painter.rect_filled(meter, meter_height / 2.0, egui::Color32::from_rgb(40, 40, 55));
+        let rms = meter.width() * self.levels.rms.clamp(0.0, 1.0);
+        painter.rect_filled(
+            egui::Rect::from_min_size(meter.min, egui::vec2(rms, meter.height())),
+            meter_height / 2.0,
+            ramp(self.levels.rms),
+        );
+        let peak_x = meter.left() + meter.width() * self.levels.peak.clamp(0.0, 1.0);
+        painter.line_segment(
+            [egui::pos2(peak_x, meter.top()), egui::pos2(peak_x, meter.bottom())],
+            egui::Stroke::new(2.0, egui::Color32::WHITE),
+        );
+
+        if self.feed.is_paused() {
+            painter.text(
+                available.center_top() + egui::vec2(0.0, margin / 2.0),
+                egui::Align2::CENTER_CENTER,
+                "Paused",
+                egui::FontId::proportional(20.0),
+                egui::Color32::GRAY,
+            );
+        }
+    }
+}
+
+/// Blue at 0.0 to red at 1.0, like the rendered spectra
+fn ramp(value: f32) -> egui::Color32 {
+    let value = value.clamp(0.0, 1.0);
+    let blue = egui::Color32::from_rgb(60, 120, 255);
+    let red = egui::Color32::from_rgb(255, 70, 60);
+    blue.lerp_to_gamma(red, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::time::{Duration, Instant};
+
+    /// Connect to a feed's tap socket the way its tcpclientsink would
+    fn connect(filter: &str) -> TcpStream {
+        let port = filter.split("port=").nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|port| port.parse::<u16>().ok())
+            .expect("filter has a port");
+        TcpStream::connect(("127.0.0.1", port)).unwrap()
+    }
+
+    fn send_tone(stream: &mut TcpStream, hz: f32, samples: usize) {
+        let bytes: Vec<u8> = (0..samples)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * hz * i as f32 / TAP_SAMPLE_RATE as f32).sin())
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        stream.write_all(&bytes).unwrap();
+    }
+
+    fn wait_for_chunks(feed: &AudioFeed, chunks: u64) {
+        let started = Instant::now();
+        while feed.chunks() < chunks {
+            assert!(started.elapsed() < Duration::from_secs(5), "feed only got {} chunks", feed.chunks());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// The bar whose band holds `hz`
+    fn bar_for(hz: f32) -> usize {
+        let nyquist = TAP_SAMPLE_RATE as f32 / 2.0;
+        ((hz / LOWEST_BAR_HZ).ln() / (nyquist / LOWEST_BAR_HZ).ln() * BARS as f32) as usize
+    }
+
+    fn tallest(view: &AudioView) -> usize {
+        (0..BARS).max_by(|&a, &b| view.bars[a].total_cmp(&view.bars[b])).unwrap()
+    }
+
+    #[test]
+    fn tapped_samples_reach_the_spectrum() {
+        let (feed, filter) = AudioFeed::listen().unwrap();
+        let mut stream = connect(&filter);
+        let mut view = AudioView::new(Arc::clone(&feed), || {});
+
+        send_tone(&mut stream, 1000.0, TAP_WINDOW);
+        wait_for_chunks(&feed, (TAP_WINDOW / 256) as u64);
+        view.update();
+
+        assert_eq!(tallest(&view), bar_for(1000.0));
+        // A half-scale sine is 6 dB down
+        assert!(view.bars[bar_for(1000.0)] > 0.8, "bar only reached {}", view.bars[bar_for(1000.0)]);
+        assert!((view.levels.peak - 0.5).abs() < 0.01, "peak was {}", view.levels.peak);
+        feed.close();
+    }
+
+    #[test]
+    fn a_paused_feed_holds_the_spectrum() {
+        let (feed, filter) = AudioFeed::listen().unwrap();
+        let mut stream = connect(&filter);
+        let mut view = AudioView::new(Arc::clone(&feed), || {});
+
+        send_tone(&mut stream, 1000.0, TAP_WINDOW);
+        wait_for_chunks(&feed, (TAP_WINDOW / 256) as u64);
+        view.update();
+        let bars = view.bars;
+
+        // Samples still in flight when playback paused don't move the bars
+        feed.set_paused(true);
+        send_tone(&mut stream, 200.0, TAP_WINDOW);
+        wait_for_chunks(&feed, (TAP_WINDOW / 128) as u64);
+        view.update();
+        assert_eq!(view.bars, bars);
+
+        feed.set_paused(false);
+        view.update();
+        assert_eq!(tallest(&view), bar_for(200.0));
+        feed.close();
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{broadcast, oneshot};
+use tracing::{debug, error, info};
+use winit::{
+    application::ApplicationHandler,
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+    window::{Fullscreen, Window, WindowId},
+};
+
+use super::ambient::Ambient;
+use super::window::{CastWindow, ImageFit, WindowMode};
+use crate::media::audio::AudioFeed;
+use crate::media::video::VideoFeed;
+use crate::{Result, CasterError, ContentType, DisplayInfo};
+
+/// What a cast window can be told to do from outside the event loop
+#[derive(Debug, Clone)]
+pub enum WindowCommand {
+    /// `title` is announced on screen when the content appears
+    SetContent { content_type: ContentType, data: Vec<u8>, title: Option<String> },
+    /// Draw the frames of the video the media engine is playing for this window
+    AttachVideo(Arc<VideoFeed>),
+    /// Visualize the audio the media engine is playing for this window
+    AttachAudio(Arc<AudioFeed>),
+    Play,
+    Pause,
+    /// Position as a fraction of the duration, 0.0-1.0
+    Seek(f32),
+    /// 0.0-1.0
+    SetVolume(f32),
+    SetImageFit(ImageFit),
+    /// 1-based page of a PDF
+    PdfPage(u32),
+    /// Next slide of a presentation, or page of a PDF
+    NextSlide,
+    PreviousSlide,
+    /// 1-based slide of a presentation, or page of a PDF
+    GoToSlide(u32),
+    SetWindowMode(WindowMode),
+    /// Where playback is, in seconds; duration is None for live or still content
+    SetPosition { position: f64, duration: Option<f64>, seekable: bool, paused: bool },
+    /// What to show once nothing has been cast for a while
+    SetAmbient(Ambient),
+    /// Ends the session; a window with an ambient screen stays up to show it
+    Close,
+}
+
+/// What cast windows report back to the rest of the server
+#[derive(Debug, Clone)]
+pub enum WindowNotice {
+    /// The seek bar was dragged to `position` seconds
+    SeekRequested { display_id: String, position: f64 },
+    /// The window was paused (true) or resumed
+    PauseRequested { display_id: String, paused: bool },
+    /// The volume was changed, 0.0-1.0
+    VolumeRequested { display_id: String, volume: f32 },
+}
+
+/// Requests from async code to the window thread
+pub(crate) enum HostRequest {
+    Open {
+        display: DisplayInfo,
+        // None keeps an open window's mode; new windows then get `default_mode`
+        mode: Option<WindowMode>,
+        default_mode: WindowMode,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Command {
+        display_id: String,
+        command: WindowCommand,
+    },
+}
+
+/// Handle to the cast window on one display; cheap to clone and send between tasks
+#[derive(Clone)]
+pub struct DisplayWindow {
+    display_id: String,
+    proxy: EventLoopProxy<HostRequest>,
+}
+
+impl DisplayWindow {
+    pub fn display_id(&self) -> &str {
+        &self.display_id
+    }
+
+    /// Commands for a window the user has since closed are dropped
+    pub fn send(&self, command: WindowCommand) -> Result<()> {
+        self.proxy
+            .send_event(HostRequest::Command {
+                display_id: self.display_id.clone(),
+                command,
+            })
+            .map_err(|_| CasterError::Display("Window thread has stopped".into()))
+    }
+}
+
+/// The winit event loop, running on a thread of its own, and every cast window on it
+pub(crate) struct WindowHost {
+    proxy: EventLoopProxy<HostRequest>,
+}
+
+impl WindowHost {
+    /// `osd` shows on-screen toasts in every window the host opens
+    pub(crate) fn start(notices: broadcast::Sender<WindowNotice>, osd: bool) -> Result<Self> {
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+
+        std::thread::Builder::new()
+            .name("cast-windows".into())
+            .spawn(move || {
+                let event_loop = match build_event_loop() {
+                    Ok(event_loop) => event_loop,
+                    Err(e) => {
+                        let _ = started_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = started_tx.send(Ok(event_loop.create_proxy()));
+
+                let mut app = HostApp {
+                    windows: HashMap::new(),
+                    displays: HashMap::new(),
+                    notices,
+                    osd,
+                };
+                if let Err(e) = event_loop.run_app(&mut app) {
+                    error!("Window event loop failed: {}", e);
+                }
+            })
+            .map_err(|e| CasterError::Display(format!("Failed to start window thread: {}", e)))?;
+
+        let proxy = started_rx.recv()
+            .map_err(|_| CasterError::Display("Window thread exited during startup".into()))??;
+
+        info!("Window event loop started");
+        Ok(Self { proxy })
+    }
+
+    /// Open a window on `display`, or find the one already there and switch it to `mode`
+    pub(crate) async fn open(&self, display: &DisplayInfo, mode: Option<WindowMode>, default_mode: WindowMode) -> Result<DisplayWindow> {
+        let (reply, opened) = oneshot::channel();
+        self.proxy
+            .send_event(HostRequest::Open {
+                display: display.clone(),
+                mode,
+                default_mode,
+                reply,
+            })
+            .map_err(|_| CasterError::Display("Window thread has stopped".into()))?;
+
+        opened.await
+            .map_err(|_| CasterError::Display("Window thread dropped the request".into()))??;
+
+        Ok(self.window(&display.id))
+    }
+
+    /// Handle for `display_id` without opening anything
+    pub(crate) fn window(&self, display_id: &str) -> DisplayWindow {
+        DisplayWindow {
+            display_id: display_id.to_string(),
+            proxy: self.proxy.clone(),
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn build_event_loop() -> Result<EventLoop<HostRequest>> {
+    #[allow(unused_mut)]
+    let mut builder = EventLoop::<HostRequest>::with_user_event();
+    #[cfg(target_os = "linux")]
+    winit::platform::x11::EventLoopBuilderExtX11::with_any_thread(&mut builder, true);
+    #[cfg(target_os = "windows")]
+    winit::platform::windows::EventLoopBuilderExtWindows::with_any_thread(&mut builder, true);
+
+    builder.build()
+        .map_err(|e| CasterError::Display(format!("Failed to create window event loop: {}", e)))
+}
+
+#[cfg(target_os = "macos")]
+fn build_event_loop() -> Result<EventLoop<HostRequest>> {
+    // macOS only allows an event loop on the main thread, which the server owns
+    Err(CasterError::NotImplemented("Cast windows are not supported on macOS".into()))
+}
+
+struct HostApp {
+    windows: HashMap<WindowId, CastWindow>,
+    displays: HashMap<String, WindowId>,
+    notices: broadcast::Sender<WindowNotice>,
+    osd: bool,
+}
+
+impl HostApp {
+    fn open(&mut self, event_loop: &ActiveEventLoop, target: &DisplayInfo, mode: Option<WindowMode>, default_mode: WindowMode) -> Result<()> {
+        if let Some(window_id) = self.displays.get(&target.id) {
+            if let (Some(mode), Some(window)) = (mode, self.windows.get_mut(window_id)) {
+                if window.window_mode() != mode {
+                    window.set_window_mode(mode);
+                }
+            }
+            return Ok(());
+        }
+        let mode = mode.unwrap_or(default_mode);
+
+        // winit knows monitors by position, not by our display ids
+        let monitor = event_loop.available_monitors().find(|monitor| {
+            let position = monitor.position();
+            position.x == target.position.x && position.y == target.position.y
+        });
+
+        let attributes = Window::default_attributes()
+            .with_title(format!("Q8 Caster - {}", target.name))
+            .with_position(PhysicalPosition::new(target.position.x, target.position.y));
+        let attributes = match mode {
+            // Two thirds of the display, so it's obviously a window
+            WindowMode::Windowed => attributes
+                .with_inner_size(PhysicalSize::new(target.resolution.width * 2 / 3, target.resolution.height * 2 / 3)),
+            WindowMode::Fullscreen | WindowMode::Kiosk => attributes
+                .with_inner_size(PhysicalSize::new(target.resolution.width, target.resolution.height))
+                .with_fullscreen(Some(Fullscreen::Borderless(monitor))),
+        };
+
+        let mut window = CastWindow::open(event_loop, attributes)?;
+        window.set_window_mode(mode);
+        window.set_osd_enabled(self.osd);
+        let Some(window_id) = window.window_id() else {
+            return Err(CasterError::Display(format!("No window created for {}", target.id)));
+        };
+
+        self.windows.insert(window_id, window);
+        self.displays.insert(target.id.clone(), window_id);
+        info!("Opened {:?} cast window on {}", mode, target.id);
+        Ok(())
+    }
+
+    fn command(&mut self, display_id: &str, command: WindowCommand) {
+        let Some(&window_id) = self.displays.get(display_id) else {
+            debug!("No cast window on {} for {:?}", display_id, command);
+            return;
+        };
+
+        match command {
+            WindowCommand::Close => match self.windows.get_mut(&window_id) {
+                Some(window) if window.has_ambient() => window.clear(),
+                _ => self.close(window_id),
+            },
+            command => {
+                if let Some(window) = self.windows.get_mut(&window_id) {
+                    window.apply(command);
+                }
+                self.forward_requests(window_id);
+            }
+        }
+    }
+
+    /// Pass seeks, pauses and volume changes made in a window on to whoever
+    /// is playing its content
+    fn forward_requests(&mut self, window_id: WindowId) {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        let (seek, pause, volume) = (window.take_seek_request(), window.take_pause_request(), window.take_volume_request());
+        let display_id = self.displays.iter()
+            .find(|(_, id)| **id == window_id)
+            .map(|(display_id, _)| display_id.clone());
+        let Some(display_id) = display_id else {
+            return;
+        };
+
+        // Nobody listening just means nothing is playing
+        if let Some(paused) = pause {
+            let _ = self.notices.send(WindowNotice::PauseRequested { display_id: display_id.clone(), paused });
+        }
+        if let Some(volume) = volume {
+            let _ = self.notices.send(WindowNotice::VolumeRequested { display_id: display_id.clone(), volume });
+        }
+        if let Some(position) = seek {
+            let _ = self.notices.send(WindowNotice::SeekRequested { display_id, position });
+        }
+    }
+
+    fn close(&mut self, window_id: WindowId) {
+        // Dropping the CastWindow drops the winit window with it
+        self.windows.remove(&window_id);
+        self.displays.retain(|display_id, id| {
+            if *id == window_id {
+                info!("Closed cast window on {}", display_id);
+            }
+            *id != window_id
+        });
+    }
+}
+
+impl ApplicationHandler<HostRequest> for HostApp {
+    // Windows are only opened on request
+    fn resumed(&mut self, _event_loop: &ActiveEventLoop) {}
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, request: HostRequest) {
+        match request {
+            HostRequest::Open { display, mode, default_mode, reply } => {
+                let _ = reply.send(self.open(event_loop, &display, mode, default_mode));
+            }
+            HostRequest::Command { display_id, command } => self.command(&display_id, command),
+        }
+    }
+
+    fn window_event(&mut self, _event_loop: &ActiveEventLoop, window_id: WindowId, event: WindowEvent) {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+
+        if !window.handle_window_event(event) {
+            // Closed by hand; the next cast to this display opens a fresh one
+            self.close(window_id);
+            return;
+        }
+        self.forward_requests(window_id);
+    }
+
+    /// Sleep until the earliest window wants waking, e.g. to hide kiosk controls
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let now = Instant::now();
+        let mut next: Option<Instant> = None;
+        for window in self.windows.values_mut() {
+            match window.next_wakeup() {
+                Some(at) if at <= now => window.wake(),
+                Some(at) => next = Some(next.map_or(at, |next| next.min(at))),
+                None => {}
+            }
+        }
+
+        event_loop.set_control_flow(match next {
+            Some(at) => ControlFlow::WaitUntil(at),
+            None => ControlFlow::Wait,
+        });
+    }
+}
@@ -0,0 +1,706 @@
+use comrak::nodes::{AstNode, ListType, NodeValue, TableAlignment};
+use comrak::{parse_document, Arena, Options};
+use egui::{Color32, RichText};
+use std::collections::HashMap;
+use std::sync::mpsc;
+use tracing::warn;
+
+/// Body text size; headings scale from it the way the CSS themes do
+const BODY_SIZE: f32 = 18.0;
+/// Column width cap, matching `.markdown-body` in the themes
+const MAX_WIDTH: f32 = 900.0;
+const PADDING: f32 = 40.0;
+/// How far the arrow and page keys scroll, in points
+const LINE_SCROLL: f32 = 60.0;
+/// Diagrams are drawn at twice their size so they stay sharp on big screens
+const DIAGRAM_SCALE: f32 = 2.0;
+
+/// Colours from the dark/light theme CSS used for remote targets
+struct Palette {
+    background: Color32,
+    text: Color32,
+    heading: Color32,
+    link: Color32,
+    code_background: Color32,
+    muted: Color32,
+    border: Color32,
+    stripe: Color32,
+    warning: Color32,
+}
+
+impl Palette {
+    /// Custom themes get the dark palette, as they do for code highlighting
+    fn for_theme(theme: Option<&str>) -> Self {
+        match theme {
+            Some("light") => Self {
+                background: Color32::from_rgb(0xff, 0xff, 0xff),
+                text: Color32::from_rgb(0x24, 0x29, 0x2e),
+                heading: Color32::from_rgb(0x24, 0x29, 0x2e),
+                link: Color32::from_rgb(0x03, 0x66, 0xd6),
+                code_background: Color32::from_rgb(0xf6, 0xf8, 0xfa),
+                muted: Color32::from_rgb(0x6a, 0x73, 0x7d),
+                border: Color32::from_rgb(0xdf, 0xe2, 0xe5),
+                stripe: Color32::from_rgb(0xfa, 0xfb, 0xfc),
+                warning: Color32::from_rgb(0x9a, 0x67, 0x00),
+            },
+            _ => Self {
+                background: Color32::from_rgb(0x1e, 0x1e, 0x1e),
+                text: Color32::from_rgb(0xd4, 0xd4, 0xd4),
+                heading: Color32::from_rgb(0xe0, 0xe0, 0xe0),
+                link: Color32::from_rgb(0x58, 0xa6, 0xff),
+                code_background: Color32::from_rgb(0x2d, 0x2d, 0x2d),
+                muted: Color32::from_rgb(0xa0, 0xa0, 0xa0),
+                border: Color32::from_rgb(0x3e, 0x3e, 0x3e),
+                stripe: Color32::from_rgb(0x25, 0x25, 0x25),
+                warning: Color32::from_rgb(0xe3, 0xb3, 0x41),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct SpanStyle {
+    strong: bool,
+    emphasis: bool,
+    strikethrough: bool,
+    code: bool,
+    math: bool,
+    link: Option<String>,
+}
+
+/// A run of inline text with one style; "\n" is a hard line break
+#[derive(Debug, Clone)]
+struct Span {
+    text: String,
+    style: SpanStyle,
+}
+
+#[derive(Debug, Clone)]
+struct ListItem {
+    /// Some for task list items, with whether the box is ticked
+    task: Option<bool>,
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone)]
+enum Block {
+    Heading { level: u8, spans: Vec<Span> },
+    Paragraph(Vec<Span>),
+    List { start: Option<usize>, items: Vec<ListItem> },
+    Quote(Vec<Block>),
+    Code { language: String, text: String },
+    /// `$$...$$` set on its own line
+    Math(String),
+    /// A ```mermaid block, keyed into `MarkdownView::diagrams` by its source
+    Diagram(String),
+    Table { alignments: Vec<TableAlignment>, header: Vec<Vec<Span>>, rows: Vec<Vec<Vec<Span>>> },
+    Rule,
+}
+
+enum Diagram {
+    Drawing,
+    Ready(egui::TextureHandle),
+    Failed(String),
+}
+
+/// Markdown parsed once when it arrives, then laid out with egui widgets every frame
+pub(crate) struct MarkdownView {
+    blocks: Vec<Block>,
+    palette: Palette,
+    diagrams: HashMap<String, Diagram>,
+    drawn: mpsc::Receiver<(String, Result<egui::ColorImage, String>)>,
+}
+
+impl MarkdownView {
+    /// Parse `markdown` and start drawing its diagrams; `wake` is called as each is ready
+    pub(crate) fn new(markdown: &str, theme: Option<&str>, math: bool, wake: impl Fn() + Send + 'static) -> Self {
+        // Same extensions as render::render_markdown
+        let mut options = Options::default();
+        options.extension.strikethrough = true;
+        options.extension.table = true;
+        options.extension.autolink = true;
+        options.extension.tasklist = true;
+        options.extension.math_dollars = math;
+
+        let arena = Arena::new();
+        let root = parse_document(&arena, markdown, &options);
+        let blocks = collect_blocks(root);
+
+        let mut sources = Vec::new();
+        diagram_sources(&blocks, &mut sources);
+        let diagrams = sources.iter().map(|source| (source.clone(), Diagram::Drawing)).collect();
+
+        // mermaid-cli starts a headless browser per diagram, far too slow for the UI thread
+        let (done, drawn) = mpsc::channel();
+        if !sources.is_empty() {
+            let mermaid_theme = if theme == Some("light") { "default" } else { "dark" };
+            let spawned = std::thread::Builder::new()
+                .name("mermaid".into())
+                .spawn(move || {
+                    for source in sources {
+                        let result = render_mermaid(&source, mermaid_theme);
+                        if let Err(ref e) = result {
+                            warn!("Failed to draw mermaid diagram: {}", e);
+                        }
+                        // The view is gone once the window shows something else
+                        if done.send((source, result)).is_err() {
+                            return;
+                        }
+                        wake();
+                    }
+                });
+            if let Err(e) = spawned {
+                warn!("Failed to start the mermaid renderer: {}", e);
+            }
+        }
+
+        Self {
+            blocks,
+            palette: Palette::for_theme(theme),
+            diagrams,
+            drawn,
+        }
+    }
+
+    /// Take whatever diagrams the render thread has finished since the last frame
+    fn poll(&mut self, ctx: &egui::Context) {
+        while let Ok((source, result)) = self.drawn.try_recv() {
+            let diagram = match result {
+                Ok(image) => Diagram::Ready(ctx.load_texture("mermaid", image, egui::TextureOptions::LINEAR)),
+                Err(e) => Diagram::Failed(e),
+            };
+            self.diagrams.insert(source, diagram);
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        self.poll(ui.ctx());
+
+        let palette = &self.palette;
+        ui.painter().rect_filled(ui.max_rect(), 0.0, palette.background);
+
+        let visuals = ui.visuals_mut();
+        visuals.override_text_color = Some(palette.text);
+        visuals.hyperlink_color = palette.link;
+        visuals.faint_bg_color = palette.stripe;
+        visuals.code_bg_color = palette.code_background;
+
+        // Remotes and keyboards scroll as well as the mouse wheel
+        let scroll = ui.input(|i| {
+            if i.key_pressed(egui::Key::ArrowDown) {
+                -LINE_SCROLL
+            } else if i.key_pressed(egui::Key::ArrowUp) {
+                LINE_SCROLL
+            } else if i.key_pressed(egui::Key::PageDown) || i.key_pressed(egui::Key::Space) {
+                -i.screen_rect().height() * 0.9
+            } else if i.key_pressed(egui::Key::PageUp) {
+                i.screen_rect().height() * 0.9
+            } else {
+                0.0
+            }
+        });
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                if scroll != 0.0 {
+                    ui.scroll_with_delta(egui::vec2(0.0, scroll));
+                }
+
+                let side = ((ui.available_width() - MAX_WIDTH) / 2.0).max(PADDING);
+                egui::Frame::none()
+                    .inner_margin(egui::Margin::symmetric(side, PADDING))
+                    .show(ui, |ui| {
+                        ui.spacing_mut().item_spacing.y = BODY_SIZE * 0.6;
+                        for block in &self.blocks {
+                            self.show_block(ui, block);
+                        }
+                    });
+            });
+    }
+
+    fn show_block(&self, ui: &mut egui::Ui, block: &Block) {
+        let palette = &self.palette;
+        match block {
+            Block::Heading { level, spans } => {
+                let size = match level {
+                    1 => BODY_SIZE * 2.0,
+                    2 => BODY_SIZE * 1.5,
+                    3 => BODY_SIZE * 1.25,
+                    _ => BODY_SIZE,
+                };
+                ui.add_space(BODY_SIZE * 0.5);
+                self.show_spans(ui, spans, size, Some(palette.heading));
+                if *level <= 2 {
+                    ui.add(egui::Separator::default().spacing(2.0));
+                }
+            }
+            Block::Paragraph(spans) => self.show_spans(ui, spans, BODY_SIZE, None),
+            Block::List { start, items } => {
+                for (index, item) in items.iter().enumerate() {
+                    ui.horizontal_top(|ui| {
+                        ui.add_space(BODY_SIZE);
+                        match (item.task, start) {
+                            (Some(mut done), _) => {
+                                ui.add_enabled(false, egui::Checkbox::without_text(&mut done));
+                            }
+                            (None, Some(start)) => {
+                                ui.label(RichText::new(format!("{}.", start + index)).size(BODY_SIZE));
+                            }
+                            (None, None) => {
+                                ui.label(RichText::new("•").size(BODY_SIZE));
+                            }
+                        }
+                        ui.vertical(|ui| {
+                            for block in &item.blocks {
+                                self.show_block(ui, block);
+                            }
+                        });
+                    });
+                }
+            }
+            Block::Quote(blocks) => {
+                ui.horizontal_top(|ui| {
+                    let (bar, _) = ui.allocate_exact_size(egui::vec2(4.0, BODY_SIZE), egui::Sense::hover());
+                    let bar_index = ui.painter().add(egui::Shape::Noop);
+                    ui.add_space(12.0);
+                    let inner = ui.vertical(|ui| {
+                        ui.visuals_mut().override_text_color = Some(palette.muted);
+                        for block in blocks {
+                            self.show_block(ui, block);
+                        }
+                    });
+                    // The bar runs the full height of the quote, known only after its contents
+                    let full = egui::Rect::from_min_max(bar.min, egui::pos2(bar.max.x, inner.response.rect.max.y));
+                    ui.painter().set(bar_index, egui::Shape::rect_filled(full, 0.0, palette.border));
+                });
+            }
+            Block::Code { language, text } => self.show_code(ui, language, text),
+            Block::Diagram(source) => match self.diagrams.get(source) {
+                Some(Diagram::Ready(texture)) => {
+                    let size = texture.size_vec2() / DIAGRAM_SCALE;
+                    let size = size * (ui.available_width() / size.x).min(1.0);
+                    ui.vertical_centered(|ui| {
+                        ui.add(egui::Image::new((texture.id(), size)));
+                    });
+                }
+                // Keep the source readable rather than lose the block
+                Some(Diagram::Failed(error)) => {
+                    ui.label(RichText::new(format!("⚠ Couldn't draw this diagram: {}", error)).color(palette.warning));
+                    self.show_code(ui, "mermaid", source);
+                }
+                _ => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(RichText::new("Drawing diagram…").color(palette.muted));
+                    });
+                }
+            },
+            Block::Math(tex) => {
+                ui.vertical_centered(|ui| {
+                    ui.label(RichText::new(tex_to_unicode(tex)).italics().size(BODY_SIZE * 1.2));
+                });
+            }
+            Block::Table { alignments, header, rows } => {
+                egui::Frame::none()
+                    .stroke(egui::Stroke::new(1.0, palette.border))
+                    .inner_margin(egui::Margin::symmetric(13.0, 6.0))
+                    .show(ui, |ui| {
+                        egui::Grid::new(header.as_ptr())
+                            .striped(true)
+                            .spacing(egui::vec2(26.0, 12.0))
+                            .show(ui, |ui| {
+                                for (column, cell) in header.iter().enumerate() {
+                                    self.show_cell(ui, cell, alignments.get(column), true);
+                                }
+                                ui.end_row();
+                                for row in rows {
+                                    for (column, cell) in row.iter().enumerate() {
+                                        self.show_cell(ui, cell, alignments.get(column), false);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            }
+            Block::Rule => {
+                ui.add(egui::Separator::default().spacing(BODY_SIZE));
+            }
+        }
+    }
+
+    fn show_code(&self, ui: &mut egui::Ui, language: &str, text: &str) {
+        let palette = &self.palette;
+        egui::Frame::none()
+            .fill(palette.code_background)
+            .rounding(6.0)
+            .inner_margin(16.0)
+            .show(ui, |ui| {
+                ui.set_min_width(ui.available_width());
+                if !language.is_empty() {
+                    ui.label(RichText::new(language).small().color(palette.muted));
+                }
+                // Long lines scroll sideways instead of wrapping, like <pre>
+                egui::ScrollArea::horizontal()
+                    .id_salt(text.as_ptr())
+                    .show(ui, |ui| {
+                        ui.add(egui::Label::new(
+                            RichText::new(text.trim_end_matches('\n'))
+                                .monospace()
+                                .size(BODY_SIZE * 0.85),
+                        ).extend());
+                    });
+            });
+    }
+
+    fn show_cell(&self, ui: &mut egui::Ui, spans: &[Span], alignment: Option<&TableAlignment>, header: bool) {
+        let align = match alignment {
+            Some(TableAlignment::Center) => egui::Align::Center,
+            Some(TableAlignment::Right) => egui::Align::Max,
+            _ => egui::Align::Min,
+        };
+        ui.with_layout(egui::Layout::top_down(align), |ui| {
+            let spans: Vec<Span> = spans.iter().cloned().map(|mut span| {
+                span.style.strong |= header;
+                span
+            }).collect();
+            self.show_spans(ui, &spans, BODY_SIZE, None);
+        });
+    }
+
+    fn show_spans(&self, ui: &mut egui::Ui, spans: &[Span], size: f32, color: Option<Color32>) {
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            for span in spans {
+                if span.text == "\n" {
+                    ui.end_row();
+                    continue;
+                }
+
+                let mut text = if span.style.math {
+                    RichText::new(tex_to_unicode(&span.text)).italics().size(size)
+                } else {
+                    RichText::new(&span.text).size(size)
+                };
+                if span.style.code {
+                    text = text.code().size(size * 0.85);
+                }
+                if span.style.strong {
+                    text = text.strong();
+                }
+                if span.style.emphasis {
+                    text = text.italics();
+                }
+                if span.style.strikethrough {
+                    text = text.strikethrough();
+                }
+                if let Some(color) = color {
+                    text = text.color(color);
+                }
+
+                match &span.style.link {
+                    Some(url) => {
+                        ui.hyperlink_to(text, url);
+                    }
+                    None => {
+                        ui.label(text);
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn collect_blocks<'a>(parent: &'a AstNode<'a>) -> Vec<Block> {
+    parent.children().filter_map(block).collect()
+}
+
+fn block<'a>(node: &'a AstNode<'a>) -> Option<Block> {
+    let value = node.data.borrow().value.clone();
+    match value {
+        NodeValue::Heading(heading) => Some(Block::Heading {
+            level: heading.level,
+            spans: spans(node),
+        }),
+        // A paragraph that is only display math is set as a block of its own
+        NodeValue::Paragraph => match display_math(node) {
+            Some(tex) => Some(Block::Math(tex)),
+            None => Some(Block::Paragraph(spans(node))),
+        },
+        NodeValue::List(list) => Some(Block::List {
+            start: (list.list_type == ListType::Ordered).then_some(list.start),
+            items: node.children().map(|item| ListItem {
+                task: match item.data.borrow().value {
+                    NodeValue::TaskItem(mark) => Some(mark.is_some()),
+                    _ => None,
+                },
+                blocks: collect_blocks(item),
+            }).collect(),
+        }),
+        NodeValue::BlockQuote => Some(Block::Quote(collect_blocks(node))),
+        NodeValue::CodeBlock(code) => match code.info.split_whitespace().next().unwrap_or_default() {
+            "mermaid" => Some(Block::Diagram(code.literal)),
+            language => Some(Block::Code {
+                language: language.to_string(),
+                text: code.literal,
+            }),
+        },
+        // Raw HTML can't be shown here; keep its text rather than drop it
+        NodeValue::HtmlBlock(html) => Some(Block::Code {
+            language: String::new(),
+            text: html.literal,
+        }),
+        NodeValue::Table(table) => {
+            let mut rows = node.children().map(|row| row.children().map(spans).collect::<Vec<_>>());
+            let header = rows.next().unwrap_or_default();
+            Some(Block::Table {
+                alignments: table.alignments,
+                header,
+                rows: rows.collect(),
+            })
+        }
+        NodeValue::ThematicBreak => Some(Block::Rule),
+        _ => None,
+    }
+}
+
+/// Every diagram's source, nested ones included, each once
+fn diagram_sources(blocks: &[Block], out: &mut Vec<String>) {
+    for block in blocks {
+        match block {
+            Block::Diagram(source) if !out.contains(source) => out.push(source.clone()),
+            Block::Quote(blocks) => diagram_sources(blocks, out),
+            Block::List { items, .. } => items.iter().for_each(|item| diagram_sources(&item.blocks, out)),
+            _ => {}
+        }
+    }
+}
+
+/// Draw a diagram to an image with mermaid-cli (`mmdc`)
+fn render_mermaid(source: &str, theme: &str) -> Result<egui::ColorImage, String> {
+    let stem = std::env::temp_dir().join(format!("q8-mermaid-{}", uuid::Uuid::new_v4()));
+    let input = stem.with_extension("mmd");
+    let output = stem.with_extension("png");
+    std::fs::write(&input, source).map_err(|e| format!("Failed to write diagram: {}", e))?;
+
+    let run = std::process::Command::new("mmdc")
+        .arg("-i").arg(&input)
+        .arg("-o").arg(&output)
+        .args(["-t", theme, "-b", "transparent", "-s", &DIAGRAM_SCALE.to_string()])
+        .output();
+    let _ = std::fs::remove_file(&input);
+
+    let image = match run {
+        Ok(run) if run.status.success() => image::open(&output).map_err(|e| format!("Failed to read diagram: {}", e)),
+        Ok(run) => {
+            let stderr = String::from_utf8_lossy(&run.stderr);
+            Err(stderr.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("mmdc failed").to_string())
+        }
+        Err(e) => Err(format!("mermaid-cli (mmdc) isn't available: {}", e)),
+    };
+    let _ = std::fs::remove_file(&output);
+
+    let rgba = image?.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw()))
+}
+
+/// Flatten a block's inline children into styled spans
+fn spans<'a>(node: &'a AstNode<'a>) -> Vec<Span> {
+    let mut out = Vec::new();
+    collect_spans(node, &SpanStyle::default(), &mut out);
+    out
+}
+
+fn collect_spans<'a>(node: &'a AstNode<'a>, style: &SpanStyle, out: &mut Vec<Span>) {
+    for child in node.children() {
+        let value = child.data.borrow().value.clone();
+        let push = |out: &mut Vec<Span>, text: String, style: SpanStyle| out.push(Span { text, style });
+
+        match value {
+            NodeValue::Text(text) => push(out, text, style.clone()),
+            NodeValue::SoftBreak => push(out, " ".into(), style.clone()),
+            NodeValue::LineBreak => push(out, "\n".into(), style.clone()),
+            NodeValue::Code(code) => push(out, code.literal, SpanStyle { code: true, ..style.clone() }),
+            NodeValue::HtmlInline(html) => push(out, html, style.clone()),
+            NodeValue::Math(math) => push(out, math.literal, SpanStyle { math: true, ..style.clone() }),
+            NodeValue::Emph => collect_spans(child, &SpanStyle { emphasis: true, ..style.clone() }, out),
+            NodeValue::Strong => collect_spans(child, &SpanStyle { strong: true, ..style.clone() }, out),
+            NodeValue::Strikethrough => collect_spans(child, &SpanStyle { strikethrough: true, ..style.clone() }, out),
+            NodeValue::Link(link) => collect_spans(child, &SpanStyle { link: Some(link.url), ..style.clone() }, out),
+            // Images aren't fetched; show the alt text where they would be
+            NodeValue::Image(_) => collect_spans(child, &SpanStyle { emphasis: true, ..style.clone() }, out),
+            _ => collect_spans(child, style, out),
+        }
+    }
+}
+
+/// The formula of a paragraph holding nothing but `$$...$$`
+fn display_math<'a>(node: &'a AstNode<'a>) -> Option<String> {
+    let mut children = node.children()
+        .filter(|child| !matches!(child.data.borrow().value, NodeValue::SoftBreak | NodeValue::LineBreak));
+    let only = children.next()?;
+    if children.next().is_some() {
+        return None;
+    }
+    match &only.data.borrow().value {
+        NodeValue::Math(math) if math.display_math => Some(math.literal.trim().to_string()),
+        _ => None,
+    }
+}
+
+/// egui can't typeset TeX, so approximate it in Unicode: symbols by name, single
+/// super- and subscripts, and fractions as a/b. Anything else keeps its source.
+fn tex_to_unicode(tex: &str) -> String {
+    let mut out = String::new();
+    let mut chars = tex.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let mut name = String::new();
+                while let Some(&next) = chars.peek() {
+                    if !next.is_ascii_alphabetic() {
+                        break;
+                    }
+                    name.push(next);
+                    chars.next();
+                }
+                match name.as_str() {
+                    // "\{", "\," and friends
+                    "" => match chars.next() {
+                        Some(',' | ';' | ' ') => out.push(' '),
+                        Some(other) => out.push(other),
+                        None => {}
+                    },
+                    "frac" => {
+                        let numerator = tex_to_unicode(&group(&mut chars));
+                        let denominator = tex_to_unicode(&group(&mut chars));
+                        out.push_str(&format!("{}/{}", numerator, denominator));
+                    }
+                    "sqrt" => out.push_str(&format!("√({})", tex_to_unicode(&group(&mut chars)))),
+                    "left" | "right" | "displaystyle" | "mathrm" | "mathbf" | "mathit" | "text" => {}
+                    name => match tex_symbol(name) {
+                        Some(symbol) => out.push_str(symbol),
+                        None => {
+                            out.push('\\');
+                            out.push_str(name);
+                        }
+                    },
+                }
+            }
+            '^' | '_' => {
+                let script = tex_to_unicode(&group(&mut chars));
+                let converted: Option<String> = script.chars()
+                    .map(|ch| if c == '^' { superscript(ch) } else { subscript(ch) })
+                    .collect();
+                match converted {
+                    Some(converted) => out.push_str(&converted),
+                    None if script.chars().count() == 1 => {
+                        out.push(c);
+                        out.push_str(&script);
+                    }
+                    None => {
+                        out.push(c);
+                        out.push_str(&format!("({})", script));
+                    }
+                }
+            }
+            '{' | '}' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The next `{...}` group, or the next single character
+fn group(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    if chars.next_if_eq(&'{').is_none() {
+        return chars.next().map(String::from).unwrap_or_default();
+    }
+
+    let mut depth = 1;
+    let mut text = String::new();
+    for c in chars.by_ref() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        text.push(c);
+    }
+    text
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰', '1' => '¹', '2' => '²', '3' => '³', '4' => '⁴',
+        '5' => '⁵', '6' => '⁶', '7' => '⁷', '8' => '⁸', '9' => '⁹',
+        '+' => '⁺', '-' | '−' => '⁻', '=' => '⁼', '(' => '⁽', ')' => '⁾',
+        'n' => 'ⁿ', 'i' => 'ⁱ', 'T' => 'ᵀ',
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀', '1' => '₁', '2' => '₂', '3' => '₃', '4' => '₄',
+        '5' => '₅', '6' => '₆', '7' => '₇', '8' => '₈', '9' => '₉',
+        '+' => '₊', '-' | '−' => '₋', '=' => '₌', '(' => '₍', ')' => '₎',
+        'a' => 'ₐ', 'e' => 'ₑ', 'i' => 'ᵢ', 'j' => 'ⱼ', 'k' => 'ₖ', 'n' => 'ₙ', 'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+fn tex_symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α", "beta" => "β", "gamma" => "γ", "delta" => "δ", "epsilon" => "ε",
+        "zeta" => "ζ", "eta" => "η", "theta" => "θ", "lambda" => "λ", "mu" => "μ",
+        "pi" => "π", "rho" => "ρ", "sigma" => "σ", "tau" => "τ", "phi" => "φ",
+        "chi" => "χ", "psi" => "ψ", "omega" => "ω",
+        "Gamma" => "Γ", "Delta" => "Δ", "Theta" => "Θ", "Lambda" => "Λ", "Pi" => "Π",
+        "Sigma" => "Σ", "Phi" => "Φ", "Psi" => "Ψ", "Omega" => "Ω",
+        "sum" => "∑", "prod" => "∏", "int" => "∫", "partial" => "∂", "nabla" => "∇",
+        "infty" => "∞", "cdot" => "·", "times" => "×", "div" => "÷", "pm" => "±",
+        "leq" | "le" => "≤", "geq" | "ge" => "≥", "neq" | "ne" => "≠", "approx" => "≈",
+        "equiv" => "≡", "sim" => "∼", "propto" => "∝",
+        "in" => "∈", "notin" => "∉", "subset" => "⊂", "subseteq" => "⊆", "cup" => "∪",
+        "cap" => "∩", "emptyset" => "∅", "forall" => "∀", "exists" => "∃", "neg" => "¬",
+        "land" => "∧", "lor" => "∨",
+        "to" | "rightarrow" => "→", "leftarrow" => "←", "Rightarrow" => "⇒", "iff" => "⇔",
+        "mapsto" => "↦", "ldots" | "dots" | "cdots" => "…", "quad" => "  ",
+        "sin" => "sin", "cos" => "cos", "tan" => "tan", "log" => "log", "ln" => "ln",
+        "exp" => "exp", "lim" => "lim", "max" => "max", "min" => "min",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn math_delimiters_inside_code_stay_code() {
+        let markdown = "Inline $e^x$ and `$not math$`.\n\n```\nlet price = \"$$5$$\";\nlet cost = \"$x$\";\n```\n\n$$a^2$$\n";
+        let view = MarkdownView::new(markdown, None, true, || {});
+
+        let Block::Paragraph(spans) = &view.blocks[0] else {
+            panic!("{:?}", view.blocks[0]);
+        };
+        let math: Vec<&str> = spans.iter().filter(|span| span.style.math).map(|span| span.text.as_str()).collect();
+        assert_eq!(math, ["e^x"]);
+        assert!(spans.iter().any(|span| span.style.code && span.text == "$not math$"));
+
+        let Block::Code { text, .. } = &view.blocks[1] else {
+            panic!("{:?}", view.blocks[1]);
+        };
+        assert_eq!(text, "let price = \"$$5$$\";\nlet cost = \"$x$\";\n");
+        assert!(matches!(&view.blocks[2], Block::Math(tex) if tex == "a^2"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::render::ScreenMirror;
+use crate::server::sse::notify_error;
+use crate::MirrorQuality;
+
+/// How long to wait before capturing again after a failed capture
+const CAPTURE_RETRY: Duration = Duration::from_secs(1);
+
+struct Frame {
+    image: egui::ColorImage,
+    captured_at: Instant,
+}
+
+/// What the capture thread hands over; only the latest frame is kept
+#[derive(Default)]
+struct Shared {
+    frame: Option<Frame>,
+    error: Option<String>,
+}
+
+/// A screen captured live. Frames are grabbed and scaled on a thread of their own
+/// and drawn into one texture that is overwritten in place.
+pub(crate) struct MirrorView {
+    shared: Arc<Mutex<Shared>>,
+    stop: Arc<AtomicBool>,
+    texture: Option<egui::TextureHandle>,
+    // When recent frames were shown, for the FPS overlay
+    shown: VecDeque<Instant>,
+    latency: Duration,
+}
+
+impl MirrorView {
+    /// Start capturing `source_display` (the primary monitor if None); `wake` is
+    /// called whenever a frame or error is ready
+    pub(crate) fn new(source_display: Option<String>, quality: MirrorQuality, wake: impl Fn() + Send + 'static) -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (max_width, max_height, fps) = quality.limits();
+        let frame_interval = Duration::from_secs(1) / fps;
+
+        let thread_shared = Arc::clone(&shared);
+        let thread_stop = Arc::clone(&stop);
+        let spawned = std::thread::Builder::new()
+            .name("screen-mirror".into())
+            .spawn(move || {
+                let report = |error: String| {
+                    let mut shared = thread_shared.lock().unwrap();
+                    // Errors repeat every retry; only tell SSE clients when one starts
+                    if shared.error.as_ref() != Some(&error) {
+                        warn!("Screen mirror: {}", error);
+                        notify_error(format!("Screen mirror: {}", error));
+                    }
+                    shared.error = Some(error);
+                    drop(shared);
+                    wake();
+                };
+
+                let mut mirror = match ScreenMirror::new(source_display) {
+                    Ok(mirror) => mirror,
+                    Err(e) => return report(e.to_string()),
+                };
+                debug!("Mirroring {:?} at up to {}x{} @ {}fps", mirror.get_monitor_info().name, max_width, max_height, fps);
+
+                while !thread_stop.load(Ordering::Relaxed) {
+                    let started = Instant::now();
+                    match mirror.capture_frame() {
+                        Ok(Some(image)) => {
+                            let image = if image.width() > max_width || image.height() > max_height {
+                                image.resize(max_width, max_height, image::imageops::FilterType::Triangle)
+                            } else {
+                                image
+                            };
+                            let rgba = image.to_rgba8();
+                            let size = [rgba.width() as usize, rgba.height() as usize];
+                            let frame = Frame {
+                                image: egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw()),
+                                captured_at: started,
+                            };
+
+                            let mut shared = thread_shared.lock().unwrap();
+                            shared.frame = Some(frame);
+                            shared.error = None;
+                            drop(shared);
+                            wake();
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            report(e.to_string());
+                            std::thread::sleep(CAPTURE_RETRY);
+                            continue;
+                        }
+                    }
+
+                    // Capture time counts against the frame budget
+                    if let Some(rest) = frame_interval.checked_sub(started.elapsed()) {
+                        std::thread::sleep(rest);
+                    }
+                }
+                debug!("Screen mirror capture stopped");
+            });
+
+        if let Err(e) = spawned {
+            let error = format!("Failed to start screen capture: {}", e);
+            notify_error(error.clone());
+            shared.lock().unwrap().error = Some(error);
+        }
+
+        Self {
+            shared,
+            stop,
+            texture: None,
+            shown: VecDeque::new(),
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// Upload the newest frame, if there is one, over the previous texture
+    fn poll(&mut self, ctx: &egui::Context) {
+        let Some(frame) = self.shared.lock().unwrap().frame.take() else {
+            return;
+        };
+
+        match self.texture {
+            Some(ref mut texture) => texture.set(frame.image, egui::TextureOptions::LINEAR),
+            None => self.texture = Some(ctx.load_texture("screen-mirror", frame.image, egui::TextureOptions::LINEAR)),
+        }
+
+        let now = Instant::now();
+        self.latency = now - frame.captured_at;
+        self.shown.push_back(now);
+        while self.shown.front().is_some_and(|at| now - *at > Duration::from_secs(1)) {
+            self.shown.pop_front();
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        self.poll(ui.ctx());
+        let error = self.shared.lock().unwrap().error.clone();
+        let available = ui.available_rect_before_wrap();
+
+        match self.texture {
+            Some(ref texture) => {
+                let size = texture.size_vec2();
+                let fitted = size * (available.width() / size.x).min(available.height() / size.y);
+                let rect = egui::Rect::from_center_size(available.center(), fitted);
+                ui.painter().image(
+                    texture.id(),
+                    rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+
+                let stats = format!("{} fps · {} ms", self.shown.len(), self.latency.as_millis());
+                ui.painter().text(
+                    rect.right_top() + egui::vec2(-8.0, 8.0),
+                    egui::Align2::RIGHT_TOP,
+                    stats,
+                    egui::FontId::monospace(14.0),
+                    egui::Color32::from_white_alpha(200),
+                );
+            }
+            None if error.is_none() => {
+                ui.centered_and_justified(|ui| {
+                    ui.spinner();
+                });
+            }
+            None => {}
+        }
+
+        if let Some(error) = error {
+            let banner = egui::Rect::from_min_size(available.min, egui::vec2(available.width(), 32.0));
+            ui.painter().rect_filled(banner, 0.0, egui::Color32::from_rgb(140, 30, 30));
+            ui.painter().text(
+                banner.left_center() + egui::vec2(12.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+                format!("Screen capture failed: {}", error),
+                egui::FontId::proportional(16.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
+
+impl Drop for MirrorView {
+    fn drop(&mut self) {
+        // The capture thread notices before its next frame and exits
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -1,10 +1,44 @@
 use crate::{Result, CasterError, DisplayInfo, Resolution, Position};
 
+mod ambient;
+mod audio_view;
+mod host;
+mod markdown;
+mod mirror_view;
+mod pdf_view;
+mod slides_view;
+mod video_view;
+mod wasm_view;
 pub mod window;
-pub use window::{CastWindow, run_cast_window};
+#[cfg(target_os = "linux")]
+mod wlr_randr;
+#[cfg(target_os = "linux")]
+mod xrandr;
+pub use ambient::{Ambient, AmbientConfig};
+pub use host::{DisplayWindow, WindowCommand, WindowNotice};
+pub use window::{CastWindow, ImageFit, WindowMode, run_cast_window};
+
+use host::WindowHost;
+use crate::server::sse::notify_display_changed;
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+
+/// Window notices are rare; a slow subscriber only loses old seeks
+const WINDOW_NOTICE_CAPACITY: usize = 16;
 
 pub struct DisplayManager {
     displays: Vec<DisplayInfo>,
+    // Started by the first create_window
+    window_host: Option<WindowHost>,
+    // For casts that don't ask for a mode
+    default_window_mode: WindowMode,
+    // Volume/seek/cast toasts; off for clean signage output
+    osd_enabled: bool,
+    // Idle screen for every display, and any set for one display alone
+    ambient: Ambient,
+    ambient_overrides: HashMap<String, Ambient>,
+    // Shared with the window host once it starts
+    window_notices: broadcast::Sender<WindowNotice>,
 }
 
 impl DisplayManager {
@@ -29,6 +63,12 @@ impl DisplayManager {
         
         Ok(Self {
             displays,
+            window_host: None,
+            default_window_mode: WindowMode::default(),
+            osd_enabled: true,
+            ambient: Ambient::default(),
+            ambient_overrides: HashMap::new(),
+            window_notices: broadcast::channel(WINDOW_NOTICE_CAPACITY).0,
         })
     }
     
@@ -36,24 +76,184 @@ impl DisplayManager {
         Ok(self.displays.clone())
     }
     
-    pub async fn configure_display(&mut self, _display_id: &str, _config: DisplayConfig) -> Result<()> {
-        // TODO: Implement display configuration
-        // Trish says: "Future us will make displays dance!" 💃
+    /// Apply a mode, position and/or mirror source to a display and update our cached info
+    pub async fn configure_display(&mut self, display_id: &str, config: DisplayConfig) -> Result<()> {
+        let index = self.display_index(display_id)?;
+
+        let mirror_source = match config.mirror_from.as_deref() {
+            Some(source_id) if source_id == display_id => {
+                return Err(CasterError::InvalidRequest("A display cannot mirror itself".into()));
+            }
+            Some(source_id) => Some(self.displays[self.display_index(source_id)?].clone()),
+            None => None,
+        };
+
+        self.apply_config(&self.displays[index], &config, mirror_source.as_ref()).await?;
+
+        let display = &mut self.displays[index];
+        if let Some(resolution) = config.resolution {
+            display.resolution = resolution;
+        }
+        if let Some(source) = mirror_source {
+            // Mirrored outputs sit on top of their source
+            display.position = source.position;
+        } else if let Some(position) = config.position {
+            display.position = position;
+        }
+
+        notify_display_changed(display_id.to_string(), "configured".to_string());
         Ok(())
     }
+
+    fn display_index(&self, display_id: &str) -> Result<usize> {
+        self.displays.iter()
+            .position(|d| d.id == display_id)
+            .ok_or_else(|| CasterError::NotFound(format!("Display not found: {}", display_id)))
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn apply_config(&self, display: &DisplayInfo, config: &DisplayConfig, mirror_source: Option<&DisplayInfo>) -> Result<()> {
+        // XWayland's xrandr lists outputs but can't change them
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return self.apply_wayland_config(display, config, mirror_source).await;
+        }
+
+        let outputs = xrandr::query_outputs().await?;
+
+        // Match on the output name, falling back to xrandr's primary for our primary display
+        let find_output = |info: &DisplayInfo| {
+            outputs.iter()
+                .find(|o| o.name == info.name)
+                .or_else(|| outputs.iter().find(|o| info.is_primary && o.primary))
+                .ok_or_else(|| CasterError::Display(format!("No connected xrandr output for {}", info.id)))
+        };
+
+        let output = find_output(display)?;
+        if let Some(resolution) = &config.resolution {
+            if !output.supports(resolution) {
+                return Err(CasterError::InvalidRequest(format!(
+                    "{} does not support {}x{}",
+                    display.id, resolution.width, resolution.height
+                )));
+            }
+        }
+
+        let same_as = match mirror_source {
+            Some(source) => Some(find_output(source)?.name.as_str()),
+            None => None,
+        };
+
+        xrandr::apply(&output.name, config.resolution.as_ref(), config.position.as_ref(), same_as).await
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn apply_wayland_config(&self, display: &DisplayInfo, config: &DisplayConfig, mirror_source: Option<&DisplayInfo>) -> Result<()> {
+        if mirror_source.is_some() {
+            return Err(CasterError::NotImplemented("Mirroring displays is not supported on Wayland yet".into()));
+        }
+
+        let outputs = wlr_randr::query_outputs().await?;
+        // Wayland has no primary output; our primary display takes the first one
+        let output = outputs.iter()
+            .find(|o| o.name == display.name)
+            .or_else(|| outputs.first().filter(|_| display.is_primary))
+            .ok_or_else(|| CasterError::Display(format!("No enabled Wayland output for {}", display.id)))?;
+
+        if let Some(resolution) = &config.resolution {
+            if !output.supports(resolution) {
+                return Err(CasterError::InvalidRequest(format!(
+                    "{} does not support {}x{}",
+                    display.id, resolution.width, resolution.height
+                )));
+            }
+        }
+
+        wlr_randr::apply(&output.name, config.resolution.as_ref(), config.position.as_ref()).await
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn apply_config(&self, _display: &DisplayInfo, _config: &DisplayConfig, _mirror_source: Option<&DisplayInfo>) -> Result<()> {
+        Err(CasterError::Display("Display configuration is only supported on Linux".into()))
+    }
     
-    pub async fn create_window(&mut self, _display_id: &str) -> Result<DisplayWindow> {
-        // TODO: Create window for casting
-        Err(CasterError::Display("Not implemented".into()))
+    /// Seek requests and other reports from cast windows
+    pub fn subscribe_window_notices(&self) -> broadcast::Receiver<WindowNotice> {
+        self.window_notices.subscribe()
+    }
+
+    pub fn set_default_window_mode(&mut self, mode: WindowMode) {
+        self.default_window_mode = mode;
+    }
+
+    /// Show on-screen toasts in cast windows; applies to windows opened from now on
+    pub fn set_osd_enabled(&mut self, enabled: bool) {
+        self.osd_enabled = enabled;
+    }
+
+    /// Set the idle screen for one display, or the default for all of them; an open
+    /// window switches straight away
+    pub fn set_ambient(&mut self, display_id: Option<&str>, ambient: Ambient) -> Result<()> {
+        match display_id {
+            Some(display_id) => {
+                self.display_index(display_id)?;
+                self.ambient_overrides.insert(display_id.to_string(), ambient.clone());
+                if let Some(ref host) = self.window_host {
+                    host.window(display_id).send(WindowCommand::SetAmbient(ambient))?;
+                }
+            }
+            None => {
+                if let Some(ref host) = self.window_host {
+                    for display in self.displays.iter().filter(|d| !self.ambient_overrides.contains_key(&d.id)) {
+                        host.window(&display.id).send(WindowCommand::SetAmbient(ambient.clone()))?;
+                    }
+                }
+                self.ambient = ambient;
+            }
+        }
+        Ok(())
+    }
+
+    /// The idle screen a display's window shows
+    pub fn ambient_for(&self, display_id: &str) -> &Ambient {
+        self.ambient_overrides.get(display_id).unwrap_or(&self.ambient)
+    }
+
+    /// The cast window on a display, opening one if there isn't one yet.
+    /// `mode` switches an open window; None keeps its mode, or uses the default for a new one.
+    pub async fn create_window(&mut self, display_id: &str, mode: Option<WindowMode>) -> Result<DisplayWindow> {
+        let display = self.displays[self.display_index(display_id)?].clone();
+
+        let host = match self.window_host {
+            Some(ref host) => host,
+            None => self.window_host.insert(WindowHost::start(self.window_notices.clone(), self.osd_enabled)?),
+        };
+        let window = host.open(&display, mode, self.default_window_mode).await?;
+        window.send(WindowCommand::SetAmbient(self.ambient_for(display_id).clone()))?;
+        Ok(window)
+    }
+
+    /// Send a command to the cast window on a display; dropped if none is open
+    pub fn send_to_window(&self, display_id: &str, command: WindowCommand) -> Result<()> {
+        self.display_index(display_id)?;
+        match self.window_host {
+            Some(ref host) => host.window(display_id).send(command),
+            None => Err(CasterError::Display(format!("No cast window on {}", display_id))),
+        }
+    }
+
+    /// Close the cast window on a display, if there is one
+    pub fn close_window(&self, display_id: &str) -> Result<()> {
+        match self.window_host {
+            Some(ref host) => host.window(display_id).send(WindowCommand::Close),
+            None => Ok(()),
+        }
     }
 }
 
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
 pub struct DisplayConfig {
     pub resolution: Option<Resolution>,
     pub position: Option<Position>,
     pub mirror_from: Option<String>,
 }
-
-pub struct DisplayWindow {
-    // Window handle and rendering surface
-}
\ No newline at end of file
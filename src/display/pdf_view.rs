@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::render::PdfRenderer;
+
+/// Pages kept rendered on either side of the current one
+const LOOKAHEAD: u32 = 1;
+
+enum PageEvent {
+    Opened(u32),
+    OpenFailed(String),
+    Rendered(u32, egui::ColorImage),
+    Failed(u32, String),
+}
+
+/// A PDF shown a page at a time. Pages are rasterized on a thread of their own,
+/// since Pdfium isn't Send and a large page takes long enough to drop frames.
+pub(crate) struct PdfView {
+    requests: mpsc::Sender<u32>,
+    events: mpsc::Receiver<PageEvent>,
+    /// 1-based, like `ContentType::Pdf { page }`
+    page: u32,
+    page_count: Option<u32>,
+    textures: HashMap<u32, egui::TextureHandle>,
+    pending: HashSet<u32>,
+    errors: HashMap<u32, String>,
+    open_error: Option<String>,
+}
+
+impl PdfView {
+    /// Start rendering `data` from `page`; `wake` is called whenever a page is ready
+    pub(crate) fn new(data: Vec<u8>, page: u32, wake: impl Fn() + Send + 'static) -> Self {
+        let (requests, page_requests) = mpsc::channel::<u32>();
+        let (event_tx, events) = mpsc::channel();
+
+        let spawned = std::thread::Builder::new()
+            .name("pdf-pages".into())
+            .spawn(move || {
+                let mut renderer = match PdfRenderer::new() {
+                    Ok(renderer) => renderer,
+                    Err(e) => {
+                        let _ = event_tx.send(PageEvent::OpenFailed(e.to_string()));
+                        wake();
+                        return;
+                    }
+                };
+
+                let opened = match renderer.get_page_count(&data) {
+                    Ok(count) => PageEvent::Opened(count),
+                    Err(e) => PageEvent::OpenFailed(e.to_string()),
+                };
+                let _ = event_tx.send(opened);
+                wake();
+
+                // Ends when the view is dropped and its sender with it
+                while let Ok(page) = page_requests.recv() {
+                    let event = match renderer.render_page(&data, page) {
+                        Ok(image) => {
+                            let rgba = image.to_rgba8();
+                            let size = [rgba.width() as usize, rgba.height() as usize];
+                            PageEvent::Rendered(page, egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw()))
+                        }
+                        Err(e) => PageEvent::Failed(page, e.to_string()),
+                    };
+                    if event_tx.send(event).is_err() {
+                        break;
+                    }
+                    wake();
+                }
+            });
+
+        let mut view = Self {
+            requests,
+            events,
+            page: page.max(1),
+            page_count: None,
+            textures: HashMap::new(),
+            pending: HashSet::new(),
+            errors: HashMap::new(),
+            open_error: None,
+        };
+        if let Err(e) = spawned {
+            view.open_error = Some(format!("Failed to start PDF renderer: {}", e));
+        }
+        view
+    }
+
+    /// 1-based page being shown
+    pub(crate) fn page(&self) -> u32 {
+        self.page
+    }
+
+    /// Move to `page`, clamped to the document once its length is known
+    pub(crate) fn go_to(&mut self, page: u32) {
+        self.page = match self.page_count {
+            Some(count) => page.clamp(1, count.max(1)),
+            None => page.max(1),
+        };
+        self.request_nearby();
+    }
+
+    /// Render the current page first, then its neighbours, and forget pages far away
+    fn request_nearby(&mut self) {
+        let Some(count) = self.page_count else {
+            return;
+        };
+
+        let first = self.page.saturating_sub(LOOKAHEAD).max(1);
+        let last = (self.page + LOOKAHEAD).min(count);
+        self.textures.retain(|page, _| (first..=last).contains(page));
+
+        let wanted = std::iter::once(self.page)
+            .chain((1..=LOOKAHEAD).map(|offset| self.page + offset))
+            .chain((1..=LOOKAHEAD).filter_map(|offset| self.page.checked_sub(offset)))
+            .filter(|page| (1..=count).contains(page));
+
+        for page in wanted {
+            if self.textures.contains_key(&page) || self.pending.contains(&page) || self.errors.contains_key(&page) {
+                continue;
+            }
+            if self.requests.send(page).is_ok() {
+                self.pending.insert(page);
+            }
+        }
+    }
+
+    /// Take whatever the render thread has finished since the last frame
+    fn poll(&mut self, ctx: &egui::Context) {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                PageEvent::Opened(count) => {
+                    debug!("PDF opened with {} pages", count);
+                    self.page_count = Some(count);
+                    self.go_to(self.page);
+                }
+                PageEvent::OpenFailed(e) => {
+                    warn!("Failed to open PDF: {}", e);
+                    self.open_error = Some(e);
+                }
+                PageEvent::Rendered(page, image) => {
+                    self.pending.remove(&page);
+                    // The user may have moved on while this page was rendering
+                    let first = self.page.saturating_sub(LOOKAHEAD);
+                    if (first..=self.page + LOOKAHEAD).contains(&page) {
+                        let texture = ctx.load_texture(format!("pdf-page-{}", page), image, egui::TextureOptions::LINEAR);
+                        self.textures.insert(page, texture);
+                    }
+                }
+                PageEvent::Failed(page, e) => {
+                    self.pending.remove(&page);
+                    self.errors.insert(page, e);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        self.poll(ui.ctx());
+
+        if let Some(ref error) = self.open_error {
+            ui.centered_and_justified(|ui| {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Can't display PDF: {}", error));
+            });
+            return;
+        }
+
+        let count = self.page_count.unwrap_or(0);
+        let step = ui.input(|i| {
+            if i.key_pressed(egui::Key::PageDown) || i.key_pressed(egui::Key::ArrowRight) || i.key_pressed(egui::Key::Space) {
+                Some(self.page + 1)
+            } else if i.key_pressed(egui::Key::PageUp) || i.key_pressed(egui::Key::ArrowLeft) {
+                Some(self.page.saturating_sub(1))
+            } else if i.key_pressed(egui::Key::Home) {
+                Some(1)
+            } else if i.key_pressed(egui::Key::End) {
+                Some(count)
+            } else {
+                None
+            }
+        });
+
+        let mut target = step;
+        ui.horizontal(|ui| {
+            if ui.add_enabled(self.page > 1, egui::Button::new("◀ Previous")).clicked() {
+                target = Some(self.page - 1);
+            }
+            if count > 0 {
+                ui.label(format!("page {} of {}", self.page, count));
+            } else {
+                ui.label(format!("page {}", self.page));
+            }
+            if ui.add_enabled(self.page < count, egui::Button::new("Next ▶")).clicked() {
+                target = Some(self.page + 1);
+            }
+        });
+
+        if let Some(page) = target {
+            if page != self.page {
+                self.go_to(page);
+            }
+        }
+
+        let available = ui.available_rect_before_wrap();
+        if let Some(texture) = self.textures.get(&self.page) {
+            // Whole page visible, letterboxed
+            let size = texture.size_vec2();
+            let fitted = size * (available.width() / size.x).min(available.height() / size.y);
+            let rect = egui::Rect::from_center_size(available.center(), fitted);
+            ui.painter().image(
+                texture.id(),
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+        } else if let Some(error) = self.errors.get(&self.page) {
+            ui.centered_and_justified(|ui| {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Page {} failed to render: {}", self.page, error));
+            });
+        } else {
+            ui.centered_and_justified(|ui| {
+                ui.spinner();
+            });
+        }
+    }
+}
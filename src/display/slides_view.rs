@@ -0,0 +1,77 @@
+use super::markdown::MarkdownView;
+use crate::render::split_slides;
+
+/// A markdown presentation shown a slide at a time, each slide laid out like
+/// a markdown document of its own
+pub(crate) struct SlidesView {
+    slides: Vec<MarkdownView>,
+    /// 0-based
+    current: usize,
+}
+
+impl SlidesView {
+    /// Split `markdown` at its `---` lines; `wake` is called as each slide's diagrams are ready
+    pub(crate) fn new(markdown: &str, theme: Option<&str>, math: bool, wake: impl Fn() + Clone + Send + 'static) -> Self {
+        let slides = split_slides(markdown)
+            .iter()
+            .map(|slide| MarkdownView::new(slide, theme, math, wake.clone()))
+            .collect();
+        Self { slides, current: 0 }
+    }
+
+    /// 1-based slide being shown
+    pub(crate) fn slide(&self) -> u32 {
+        self.current as u32 + 1
+    }
+
+    /// Move to a 1-based `slide`, clamped to the deck
+    pub(crate) fn go_to(&mut self, slide: u32) {
+        self.current = (slide.max(1) as usize - 1).min(self.slides.len() - 1);
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        let count = self.slides.len();
+        // Taken before the slide sees them, so they turn the slide rather than scroll it;
+        // the arrow keys up and down still scroll a slide that runs long
+        let step = ui.input_mut(|i| {
+            let next = [egui::Key::PageDown, egui::Key::ArrowRight, egui::Key::Space];
+            let previous = [egui::Key::PageUp, egui::Key::ArrowLeft];
+            if next.into_iter().any(|key| i.consume_key(egui::Modifiers::NONE, key)) {
+                Some(self.current + 1)
+            } else if previous.into_iter().any(|key| i.consume_key(egui::Modifiers::NONE, key)) {
+                Some(self.current.saturating_sub(1))
+            } else if i.consume_key(egui::Modifiers::NONE, egui::Key::Home) {
+                Some(0)
+            } else if i.consume_key(egui::Modifiers::NONE, egui::Key::End) {
+                Some(count - 1)
+            } else {
+                None
+            }
+        });
+        if let Some(index) = step {
+            self.current = index.min(count - 1);
+        }
+
+        let available = ui.available_rect_before_wrap();
+        let bar_height = ui.spacing().interact_size.y + ui.spacing().item_spacing.y * 2.0;
+        let (slide_rect, bar_rect) = available.split_top_bottom_at_y(available.bottom() - bar_height);
+
+        // Each slide keeps its own scroll position
+        let current = self.current;
+        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(slide_rect).id_salt(("slide", current)), |ui| {
+            self.slides[current].show(ui);
+        });
+
+        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(bar_rect), |ui| {
+            ui.horizontal_centered(|ui| {
+                if ui.add_enabled(self.current > 0, egui::Button::new("◀ Previous")).clicked() {
+                    self.current -= 1;
+                }
+                ui.label(format!("slide {} of {}", self.current + 1, count));
+                if ui.add_enabled(self.current + 1 < count, egui::Button::new("Next ▶")).clicked() {
+                    self.current += 1;
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use crate::media::video::VideoFeed;
+
+/// Video decoded by the media engine, drawn into one texture that each frame
+/// overwrites in place. The pipeline paces the frames; this just shows the newest.
+pub(crate) struct VideoView {
+    feed: Arc<VideoFeed>,
+    texture: Option<egui::TextureHandle>,
+}
+
+impl VideoView {
+    /// `wake` is called from the feed's thread whenever a frame arrives
+    pub(crate) fn new(feed: Arc<VideoFeed>, wake: impl Fn() + Send + 'static) -> Self {
+        feed.set_wake(wake);
+        Self { feed, texture: None }
+    }
+
+    /// Upload the newest frame, if there is one, over the previous one
+    fn poll(&mut self, ctx: &egui::Context) {
+        let Some(pixels) = self.feed.take_frame() else {
+            return;
+        };
+        let (width, height) = self.feed.size();
+        let image = egui::ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &pixels);
+
+        match self.texture {
+            // Every frame is the same size, so a partial update at the origin rewrites
+            // the existing GPU texture instead of allocating a new one
+            Some(ref mut texture) => texture.set_partial([0, 0], image, egui::TextureOptions::LINEAR),
+            None => self.texture = Some(ctx.load_texture("video-frame", image, egui::TextureOptions::LINEAR)),
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        self.poll(ui.ctx());
+        let available = ui.available_rect_before_wrap();
+
+        match self.texture {
+            Some(ref texture) => {
+                let size = texture.size_vec2();
+                let fitted = size * (available.width() / size.x).min(available.height() / size.y);
+                let rect = egui::Rect::from_center_size(available.center(), fitted);
+                ui.painter().rect_filled(available, 0.0, egui::Color32::BLACK);
+                ui.painter().image(
+                    texture.id(),
+                    rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+            }
+            None if self.feed.is_ended() => {
+                ui.centered_and_justified(|ui| {
+                    ui.label("No video");
+                });
+            }
+            None => {
+                ui.centered_and_justified(|ui| {
+                    ui.spinner();
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::render::{WasmFrames, WasmSandboxConfig};
+use crate::server::sse::notify_error;
+
+/// Most frames a second a module is asked for and shown
+pub(crate) const WASM_FPS: u32 = 30;
+
+/// What the module thread hands over; only the latest frame is kept
+#[derive(Default)]
+struct Shared {
+    frame: Option<egui::ColorImage>,
+    error: Option<String>,
+    finished: bool,
+}
+
+/// A WebAssembly module drawing frames. It runs on a thread of its own, and
+/// its frames are drawn into one texture that is overwritten in place.
+pub(crate) struct WasmView {
+    shared: Arc<Mutex<Shared>>,
+    stop: Arc<AtomicBool>,
+    texture: Option<egui::TextureHandle>,
+}
+
+impl WasmView {
+    /// Start `module` drawing `width` x `height` frames; `wake` is called whenever
+    /// a frame or error is ready
+    pub(crate) fn new(
+        module: Vec<u8>,
+        sandbox: WasmSandboxConfig,
+        width: u32,
+        height: u32,
+        wake: impl Fn() + Send + 'static,
+    ) -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let frame_interval = Duration::from_secs(1) / WASM_FPS;
+
+        let thread_shared = Arc::clone(&shared);
+        let thread_stop = Arc::clone(&stop);
+        let spawned = std::thread::Builder::new()
+            .name("wasm-frames".into())
+            .spawn(move || {
+                let report = |error: String| {
+                    warn!("WebAssembly: {}", error);
+                    notify_error(format!("WebAssembly: {}", error));
+                    thread_shared.lock().unwrap().error = Some(error);
+                    wake();
+                };
+
+                let mut frames = match WasmFrames::new(&module, &sandbox, width, height) {
+                    Ok(frames) => frames,
+                    Err(e) => return report(e.to_string()),
+                };
+                debug!("Running WebAssembly module at {}x{} @ {}fps", width, height, WASM_FPS);
+
+                while !thread_stop.load(Ordering::Relaxed) {
+                    let started = Instant::now();
+                    match frames.next_frame() {
+                        Ok(Some(frame)) => {
+                            let size = [frame.width() as usize, frame.height() as usize];
+                            let image = egui::ColorImage::from_rgba_unmultiplied(size, frame.as_raw());
+                            thread_shared.lock().unwrap().frame = Some(image);
+                            wake();
+                        }
+                        Ok(None) => {
+                            thread_shared.lock().unwrap().finished = true;
+                            wake();
+                            break;
+                        }
+                        Err(e) => return report(e.to_string()),
+                    }
+
+                    // The module only draws when asked, which holds it to the cap
+                    if let Some(rest) = frame_interval.checked_sub(started.elapsed()) {
+                        std::thread::sleep(rest);
+                    }
+                }
+                debug!("WebAssembly module stopped");
+            });
+
+        if let Err(e) = spawned {
+            let error = format!("Failed to start WebAssembly module: {}", e);
+            notify_error(error.clone());
+            shared.lock().unwrap().error = Some(error);
+        }
+
+        Self {
+            shared,
+            stop,
+            texture: None,
+        }
+    }
+
+    /// Upload the newest frame, if there is one, over the previous texture
+    fn poll(&mut self, ctx: &egui::Context) {
+        let Some(frame) = self.shared.lock().unwrap().frame.take() else {
+            return;
+        };
+
+        match self.texture {
+            Some(ref mut texture) => texture.set(frame, egui::TextureOptions::LINEAR),
+            None => self.texture = Some(ctx.load_texture("wasm-frame", frame, egui::TextureOptions::LINEAR)),
+        }
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+        self.poll(ui.ctx());
+        let (error, finished) = {
+            let shared = self.shared.lock().unwrap();
+            (shared.error.clone(), shared.finished)
+        };
+        let available = ui.available_rect_before_wrap();
+
+        match self.texture {
+            Some(ref texture) => {
+                let size = texture.size_vec2();
+                let fitted = size * (available.width() / size.x).min(available.height() / size.y);
+                let rect = egui::Rect::from_center_size(available.center(), fitted);
+                ui.painter().image(
+                    texture.id(),
+                    rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+            }
+            None if finished => {
+                ui.centered_and_justified(|ui| {
+                    ui.label("WebAssembly module finished without drawing a frame");
+                });
+            }
+            None if error.is_none() => {
+                ui.centered_and_justified(|ui| {
+                    ui.spinner();
+                });
+            }
+            None => {}
+        }
+
+        if let Some(error) = error {
+            let banner = egui::Rect::from_min_size(available.min, egui::vec2(available.width(), 32.0));
+            ui.painter().rect_filled(banner, 0.0, egui::Color32::from_rgb(140, 30, 30));
+            ui.painter().text(
+                banner.left_center() + egui::vec2(12.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+                error,
+                egui::FontId::proportional(16.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
+
+impl Drop for WasmView {
+    fn drop(&mut self) {
+        // The module thread notices after its current frame and stops the module
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
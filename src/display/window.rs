@@ -1,15 +1,28 @@
-use egui::Rgba;
 use egui_wgpu::ScreenDescriptor;
+use std::str::FromStr;
 use std::sync::Arc;
-use wgpu::SurfaceTexture;
+use std::time::{Duration, Instant};
 use winit::{
     application::ApplicationHandler,
-    event::WindowEvent,
+    event::{ElementState, KeyEvent, WindowEvent},
     event_loop::{ActiveEventLoop, EventLoop},
-    window::Window,
+    keyboard::{Key, NamedKey},
+    window::{Fullscreen, Window, WindowAttributes, WindowId},
 };
 
-use crate::{error::Result as CasterResult, ContentType};
+use super::ambient::{Ambient, AmbientView};
+use super::audio_view::AudioView;
+use super::host::WindowCommand;
+use super::markdown::MarkdownView;
+use super::mirror_view::MirrorView;
+use super::pdf_view::PdfView;
+use super::slides_view::SlidesView;
+use super::video_view::VideoView;
+use super::wasm_view::WasmView;
+use crate::media::audio::AudioFeed;
+use crate::media::video::VideoFeed;
+use crate::render::SlideFormat;
+use crate::{error::{CasterError, Result as CasterResult}, ContentType};
 
 /// egui-based display window for casting content
 pub struct CastWindow {
@@ -25,10 +38,54 @@ pub struct CastWindow {
     volume: f32,
     seek_position: f32,
     duration: Option<u64>, // Duration in seconds
+    // Seconds played, as last reported by whatever is playing the content
+    position: f64,
+    seekable: bool,
+    // Seconds the seek bar was moved to, until the host passes it on
+    pending_seek: Option<f64>,
+    // Pause (true) or resume the player asked for, likewise
+    pending_pause: Option<bool>,
+    // Volume the player was asked for, likewise
+    pending_volume: Option<f32>,
 
     // Content rendering
-    content_texture: Option<wgpu::Texture>,
+    content_texture: Option<egui::TextureHandle>,
     content_data: Vec<u8>,
+    content_error: Option<String>,
+    // Set when content_data changes and the texture must be re-uploaded
+    texture_dirty: bool,
+    // Parsed once per document rather than every frame
+    markdown: Option<MarkdownView>,
+    pdf: Option<PdfView>,
+    // Markdown presentations; PDF decks use `pdf`
+    slides: Option<SlidesView>,
+    // Captures for as long as it exists
+    mirror: Option<MirrorView>,
+    // Runs its module for as long as it exists
+    wasm: Option<WasmView>,
+    // Frames from the media engine, once it starts playing this window's video
+    video: Option<VideoView>,
+    // Samples from the media engine, visualized while audio plays
+    audio: Option<AudioView>,
+    // Image view: fit mode, then zoom and pan on top of it
+    image_fit: ImageFit,
+    zoom: f32,
+    pan: egui::Vec2,
+
+    window_mode: WindowMode,
+    // Kiosk mode shows the controls and cursor for a while after the pointer moves
+    last_pointer_activity: Option<Instant>,
+    cursor_hidden: bool,
+    // Keyboard shortcut overlay, toggled with "?"
+    show_help: bool,
+    // On-screen toast and when it went up; off entirely for signage
+    toast: Option<(String, Instant)>,
+    osd_enabled: bool,
+    // Clock and join code shown once nothing has been cast for a while
+    ambient: Option<AmbientView>,
+    // When the window last had nothing cast; None while something is
+    idle_since: Option<Instant>,
+    ambient_visible: bool,
     
     // Redraw tracking
     needs_redraw: bool,
@@ -46,6 +103,87 @@ struct WgpuState {
     surface_config: wgpu::SurfaceConfiguration,
 }
 
+/// How an image is sized to the window before zoom and pan
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFit {
+    /// Whole image visible, letterboxed
+    #[default]
+    Contain,
+    /// Window filled, edges cropped
+    Cover,
+    /// Window filled, aspect ratio ignored
+    Stretch,
+    /// One image pixel per screen pixel
+    Original,
+}
+
+impl ImageFit {
+    const ALL: [ImageFit; 4] = [ImageFit::Contain, ImageFit::Cover, ImageFit::Stretch, ImageFit::Original];
+
+    fn label(self) -> &'static str {
+        match self {
+            ImageFit::Contain => "Contain",
+            ImageFit::Cover => "Cover",
+            ImageFit::Stretch => "Stretch",
+            ImageFit::Original => "1:1",
+        }
+    }
+}
+
+/// How a cast window occupies its display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowMode {
+    /// Decorated window on the display
+    Windowed,
+    /// Borderless fullscreen on the display
+    #[default]
+    Fullscreen,
+    /// Fullscreen with the cursor and controls hidden until the pointer moves,
+    /// and only closed by an explicit stop
+    Kiosk,
+}
+
+impl FromStr for WindowMode {
+    type Err = CasterError;
+
+    fn from_str(s: &str) -> CasterResult<Self> {
+        match s {
+            "windowed" => Ok(WindowMode::Windowed),
+            "fullscreen" => Ok(WindowMode::Fullscreen),
+            "kiosk" => Ok(WindowMode::Kiosk),
+            _ => Err(CasterError::Display(format!("Unknown window mode: {}", s))),
+        }
+    }
+}
+
+/// How long kiosk controls stay up after the last pointer movement
+const KIOSK_CONTROLS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How far the arrow keys seek, in seconds
+const KEY_SEEK_STEP: f64 = 10.0;
+/// How much +/- change the volume
+const KEY_VOLUME_STEP: f32 = 0.1;
+
+/// Shown in the "?" overlay; keep in step with `CastWindow::shortcut`
+const SHORTCUTS: &[(&str, &str)] = &[
+    ("Space", "Play / pause"),
+    ("← / →", "Seek back / forward 10 s"),
+    ("+ / -", "Volume up / down"),
+    ("F", "Toggle fullscreen"),
+    ("N / P", "Next / previous slide or PDF page"),
+    ("?", "Show / hide this help"),
+];
+
+/// How long an on-screen toast stays up, the last part of it fading out
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+const TOAST_FADE: Duration = Duration::from_millis(500);
+
+/// Zoom limits for pan/zoom on images
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 20.0;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PlaybackState {
     Playing,
@@ -54,6 +192,12 @@ pub enum PlaybackState {
     Loading,
 }
 
+impl Default for CastWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CastWindow {
     pub fn new() -> Self {
         Self {
@@ -67,28 +211,509 @@ impl CastWindow {
             volume: 0.8,
             seek_position: 0.0,
             duration: None,
+            position: 0.0,
+            seekable: false,
+            pending_seek: None,
+            pending_pause: None,
+            pending_volume: None,
             content_texture: None,
             content_data: Vec::new(),
+            content_error: None,
+            texture_dirty: false,
+            markdown: None,
+            pdf: None,
+            slides: None,
+            mirror: None,
+            wasm: None,
+            video: None,
+            audio: None,
+            image_fit: ImageFit::default(),
+            zoom: 1.0,
+            pan: egui::Vec2::ZERO,
+            window_mode: WindowMode::Windowed,
+            last_pointer_activity: None,
+            cursor_hidden: false,
+            show_help: false,
+            toast: None,
+            osd_enabled: true,
+            ambient: None,
+            idle_since: Some(Instant::now()),
+            ambient_visible: false,
             needs_redraw: true,
         }
     }
 
+    /// Create the window and its rendering state straight away, for hosts
+    /// that manage several windows on one event loop
+    pub fn open(event_loop: &ActiveEventLoop, attributes: WindowAttributes) -> CasterResult<Self> {
+        let mut cast_window = Self::new();
+        cast_window.init(event_loop, attributes)?;
+        Ok(cast_window)
+    }
+
+    pub fn window_id(&self) -> Option<WindowId> {
+        self.window.as_ref().map(|window| window.id())
+    }
+
+    pub fn apply(&mut self, command: WindowCommand) {
+        match command {
+            WindowCommand::SetContent { content_type, data, title } => {
+                self.set_content(content_type, data);
+                if let Some(title) = title {
+                    self.show_toast(format!("Now casting: {}", title));
+                }
+            }
+            WindowCommand::AttachVideo(feed) => self.attach_video(feed),
+            WindowCommand::AttachAudio(feed) => self.attach_audio(feed),
+            WindowCommand::Play => {
+                self.play();
+                self.show_toast("Playing");
+            }
+            WindowCommand::Pause => {
+                self.pause();
+                self.show_toast("Paused");
+            }
+            WindowCommand::Seek(position) => {
+                self.request_seek(position);
+                match self.duration {
+                    Some(duration) if self.seekable => {
+                        let target = (self.seek_position as f64 * duration as f64) as u64;
+                        self.show_toast(format!("Seeking to {}", Self::format_time(target)));
+                    }
+                    _ => self.show_toast("This content can't seek"),
+                }
+            }
+            WindowCommand::SetVolume(volume) => {
+                self.set_volume(volume);
+                self.show_toast(format!("Volume {:.0}%", self.volume * 100.0));
+            }
+            WindowCommand::SetImageFit(fit) => self.set_image_fit(fit),
+            WindowCommand::PdfPage(page) => self.set_pdf_page(page),
+            WindowCommand::NextSlide => self.step_slide(1),
+            WindowCommand::PreviousSlide => self.step_slide(-1),
+            WindowCommand::GoToSlide(slide) => self.go_to_slide(slide),
+            WindowCommand::SetWindowMode(mode) => self.set_window_mode(mode),
+            WindowCommand::SetPosition { position, duration, seekable, paused } => {
+                self.set_position(position, duration, seekable, paused)
+            }
+            WindowCommand::SetAmbient(ambient) => self.set_ambient(ambient),
+            // Closing is up to whoever owns the window; all we can do is stop
+            WindowCommand::Close => self.stop(),
+        }
+
+        if let Some(ref window) = self.window {
+            window.request_redraw();
+        }
+    }
+
     pub fn set_content(&mut self, content_type: ContentType, data: Vec<u8>) {
+        self.markdown = match &content_type {
+            ContentType::Markdown { theme, math } => {
+                // Diagrams are drawn off this thread and wake the window when they're ready
+                let window = self.window.clone();
+                Some(MarkdownView::new(&String::from_utf8_lossy(&data), theme.as_deref(), *math, move || {
+                    if let Some(ref window) = window {
+                        window.request_redraw();
+                    }
+                }))
+            }
+            _ => None,
+        };
+        // Presentations are markdown split into slides, or PDFs shown a page per slide
+        let slide_format = match &content_type {
+            ContentType::Presentation { format, .. } => Some(SlideFormat::resolve(format, &data)),
+            _ => None,
+        };
+        self.slides = match (&content_type, &slide_format) {
+            (ContentType::Presentation { theme, math, .. }, Some(Ok(SlideFormat::Markdown))) => {
+                let window = self.window.clone();
+                Some(SlidesView::new(&String::from_utf8_lossy(&data), theme.as_deref(), *math, move || {
+                    if let Some(ref window) = window {
+                        window.request_redraw();
+                    }
+                }))
+            }
+            _ => None,
+        };
+        let pdf_page = match (&content_type, &slide_format) {
+            (ContentType::Pdf { page }, _) => Some(page.unwrap_or(1)),
+            (_, Some(Ok(SlideFormat::Pdf))) => Some(1),
+            _ => None,
+        };
+        self.pdf = pdf_page.map(|page| {
+            // Pages render off this thread and wake the window when they're ready
+            let window = self.window.clone();
+            PdfView::new(data.clone(), page, move || {
+                if let Some(ref window) = window {
+                    window.request_redraw();
+                }
+            })
+        });
+        // Replacing the view stops the previous capture
+        self.mirror = match &content_type {
+            ContentType::ScreenMirror { source_display, quality } => {
+                let window = self.window.clone();
+                Some(MirrorView::new(source_display.clone(), quality.clone(), move || {
+                    if let Some(ref window) = window {
+                        window.request_redraw();
+                    }
+                }))
+            }
+            _ => None,
+        };
+        // Modules draw at the window's size, up to 1080p
+        self.wasm = match &content_type {
+            ContentType::WebAssembly { sandbox, .. } => {
+                let window = self.window.clone();
+                let (width, height) = match self.window {
+                    Some(ref window) => {
+                        let size = window.inner_size();
+                        let scale = (1920.0 / size.width.max(1) as f32).min(1080.0 / size.height.max(1) as f32).min(1.0);
+                        ((size.width as f32 * scale).max(1.0) as u32, (size.height as f32 * scale).max(1.0) as u32)
+                    }
+                    None => (1280, 720),
+                };
+                Some(WasmView::new(data.clone(), sandbox.clone(), width, height, move || {
+                    if let Some(ref window) = window {
+                        window.request_redraw();
+                    }
+                }))
+            }
+            _ => None,
+        };
+        // Frames from the last video would otherwise keep drawing over the new content
+        self.video = None;
+        self.audio = None;
         self.content_type = Some(content_type);
         self.content_data = data;
+        self.seek_position = 0.0;
+        self.duration = None;
+        self.position = 0.0;
+        self.seekable = false;
+        self.pending_seek = None;
+        self.pending_pause = None;
+        self.pending_volume = None;
+        self.content_texture = None;
+        self.content_error = match slide_format {
+            Some(Err(e)) => Some(e.to_string()),
+            _ => None,
+        };
+        self.texture_dirty = true;
+        self.zoom = 1.0;
+        self.pan = egui::Vec2::ZERO;
         self.playback_state = PlaybackState::Loading;
+        self.idle_since = None;
+        self.needs_redraw = true;
+    }
+
+    /// Drop whatever is cast and go back to idle, ambient screen and all
+    pub fn clear(&mut self) {
+        self.stop();
+        self.markdown = None;
+        self.pdf = None;
+        self.slides = None;
+        self.content_type = None;
+        self.content_data = Vec::new();
+        self.content_texture = None;
+        self.content_error = None;
+        self.duration = None;
+        self.position = 0.0;
+        self.seekable = false;
+        self.idle_since = Some(Instant::now());
+    }
+
+    /// What to show while idle; a disabled config turns the ambient screen off
+    pub fn set_ambient(&mut self, ambient: Ambient) {
+        self.ambient = ambient.config.enabled.then(|| AmbientView::new(ambient));
+        self.ambient_visible = false;
+        self.needs_redraw = true;
+    }
+
+    /// Whether closing the session should leave the window up for the ambient screen
+    pub fn has_ambient(&self) -> bool {
+        self.ambient.is_some()
+    }
+
+    /// True once the window has been idle for the ambient timeout
+    fn ambient_due(&self) -> bool {
+        match (&self.ambient, self.idle_since) {
+            (Some(ambient), Some(since)) => since.elapsed() >= ambient.idle_timeout(),
+            _ => false,
+        }
+    }
+
+    /// Turn the on-screen toasts on or off; off hides any toast showing now
+    pub fn set_osd_enabled(&mut self, enabled: bool) {
+        self.osd_enabled = enabled;
+        if !enabled {
+            self.toast = None;
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Flash `text` over the content for `TOAST_DURATION`, replacing any toast showing
+    pub fn show_toast(&mut self, text: impl Into<String>) {
+        if self.osd_enabled {
+            self.toast = Some((text.into(), Instant::now()));
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Show frames from `feed` in place of the video placeholder
+    pub fn attach_video(&mut self, feed: Arc<VideoFeed>) {
+        let window = self.window.clone();
+        self.video = Some(VideoView::new(feed, move || {
+            if let Some(ref window) = window {
+                window.request_redraw();
+            }
+        }));
+        self.needs_redraw = true;
+    }
+
+    /// Visualize samples from `feed` in the audio view
+    pub fn attach_audio(&mut self, feed: Arc<AudioFeed>) {
+        let window = self.window.clone();
+        self.audio = Some(AudioView::new(feed, move || {
+            if let Some(ref window) = window {
+                window.request_redraw();
+            }
+        }));
+        self.needs_redraw = true;
+    }
+
+    /// Change how images are fitted; also resets zoom and pan
+    pub fn set_image_fit(&mut self, fit: ImageFit) {
+        self.image_fit = fit;
+        self.zoom = 1.0;
+        self.pan = egui::Vec2::ZERO;
+        self.needs_redraw = true;
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        self.window_mode
+    }
+
+    /// Switch between windowed, fullscreen and kiosk; the content stays as it is
+    pub fn set_window_mode(&mut self, mode: WindowMode) {
+        self.window_mode = mode;
+        self.last_pointer_activity = None;
+        self.cursor_hidden = mode == WindowMode::Kiosk;
+
+        if let Some(ref window) = self.window {
+            match mode {
+                WindowMode::Windowed => {
+                    window.set_fullscreen(None);
+                    window.set_decorations(true);
+                }
+                WindowMode::Fullscreen | WindowMode::Kiosk => {
+                    window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+                }
+            }
+            window.set_cursor_visible(!self.cursor_hidden);
+            window.request_redraw();
+        }
+        self.needs_redraw = true;
+    }
+
+    fn controls_visible(&self) -> bool {
+        self.window_mode != WindowMode::Kiosk
+            || self.last_pointer_activity.is_some_and(|at| at.elapsed() < KIOSK_CONTROLS_TIMEOUT)
+    }
+
+    /// When the window next needs attention without any input arriving
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        // The ambient screen appearing, then its clock ticking and slides changing
+        let ambient = match (&self.ambient, self.idle_since) {
+            (Some(ambient), Some(_)) if self.ambient_visible => Some(ambient.next_change()),
+            (Some(ambient), Some(since)) => Some(since + ambient.idle_timeout()),
+            _ => None,
+        };
+        let kiosk = if self.window_mode != WindowMode::Kiosk || self.cursor_hidden {
+            None
+        } else {
+            self.last_pointer_activity.map(|at| at + KIOSK_CONTROLS_TIMEOUT)
+        };
+        match (ambient, kiosk) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Called once `next_wakeup` has passed: hides kiosk controls that have timed out
+    /// and redraws the ambient screen
+    pub fn wake(&mut self) {
+        if self.ambient_due() {
+            if let Some(ref window) = self.window {
+                window.request_redraw();
+            }
+            self.needs_redraw = true;
+        }
+        if self.controls_visible() || self.cursor_hidden {
+            return;
+        }
+
+        self.cursor_hidden = true;
+        if let Some(ref window) = self.window {
+            window.set_cursor_visible(false);
+            window.request_redraw();
+        }
+        self.needs_redraw = true;
+    }
+
+    /// The command a key stands for, so shortcuts behave exactly like the control API
+    fn shortcut(&self, key: &Key) -> Option<WindowCommand> {
+        let plays = matches!(self.content_type, Some(ContentType::Video { .. } | ContentType::Audio { .. }));
+        // Seek commands are a fraction of the duration
+        let seek_by = |offset: f64| {
+            let duration = self.duration.filter(|_| self.seekable)? as f64;
+            Some(WindowCommand::Seek(((self.position + offset) / duration).clamp(0.0, 1.0) as f32))
+        };
+        let turns = self.slides.is_some() || self.pdf.is_some();
+
+        match key {
+            Key::Named(NamedKey::Space) if plays => Some(match self.playback_state {
+                PlaybackState::Playing => WindowCommand::Pause,
+                _ => WindowCommand::Play,
+            }),
+            // PDFs and presentations keep the arrow keys for turning pages
+            Key::Named(NamedKey::ArrowLeft) if plays => seek_by(-KEY_SEEK_STEP),
+            Key::Named(NamedKey::ArrowRight) if plays => seek_by(KEY_SEEK_STEP),
+            Key::Character(c) => match c.as_str() {
+                "+" | "=" => Some(WindowCommand::SetVolume(self.volume + KEY_VOLUME_STEP)),
+                "-" => Some(WindowCommand::SetVolume(self.volume - KEY_VOLUME_STEP)),
+                // Kiosk windows stay fullscreen
+                "f" | "F" => match self.window_mode {
+                    WindowMode::Windowed => Some(WindowCommand::SetWindowMode(WindowMode::Fullscreen)),
+                    WindowMode::Fullscreen => Some(WindowCommand::SetWindowMode(WindowMode::Windowed)),
+                    WindowMode::Kiosk => None,
+                },
+                "n" | "N" if turns => Some(WindowCommand::NextSlide),
+                "p" | "P" if turns => Some(WindowCommand::PreviousSlide),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent) {
+        if event.state != ElementState::Pressed {
+            return;
+        }
+
+        // Held keys repeat seeks and volume steps, but shouldn't flicker toggles
+        if let Key::Character(c) = &event.logical_key {
+            if c.as_str() == "?" {
+                if !event.repeat {
+                    self.show_help = !self.show_help;
+                    self.needs_redraw = true;
+                    if let Some(ref window) = self.window {
+                        window.request_redraw();
+                    }
+                }
+                return;
+            }
+        }
+
+        let Some(command) = self.shortcut(&event.logical_key) else {
+            return;
+        };
+        if event.repeat && !matches!(command, WindowCommand::Seek(_) | WindowCommand::SetVolume(_)) {
+            return;
+        }
+
+        tracing::debug!("Shortcut {:?} -> {:?}", event.logical_key, command);
+        self.apply(command);
+    }
+
+    fn render_help(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_help;
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts").num_columns(2).spacing([24.0, 6.0]).show(ui, |ui| {
+                    for (keys, action) in SHORTCUTS {
+                        ui.strong(*keys);
+                        ui.label(*action);
+                        ui.end_row();
+                    }
+                });
+            });
+        self.show_help = open;
+    }
+
+    /// Floats over the content in its own layer, so the content never moves for it
+    fn render_toast(&mut self, ctx: &egui::Context) {
+        let Some((ref text, shown_at)) = self.toast else {
+            return;
+        };
+        let Some(left) = TOAST_DURATION.checked_sub(shown_at.elapsed()) else {
+            self.toast = None;
+            return;
+        };
+        let opacity = (left.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0);
+
+        egui::Area::new(egui::Id::new("osd-toast"))
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 64.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.set_opacity(opacity);
+                egui::Frame::none()
+                    .fill(egui::Color32::from_black_alpha(190))
+                    .rounding(8.0)
+                    .inner_margin(egui::Margin::symmetric(20.0, 10.0))
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new(text.as_str()).size(22.0).color(egui::Color32::WHITE));
+                    });
+            });
+    }
+
+    /// Go to a 1-based page of the PDF being shown
+    pub fn set_pdf_page(&mut self, page: u32) {
+        if let Some(ref mut pdf) = self.pdf {
+            pdf.go_to(page);
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Go to a 1-based slide of the presentation being shown; for a PDF, the page
+    pub fn go_to_slide(&mut self, slide: u32) {
+        match self.slides {
+            Some(ref mut slides) => {
+                slides.go_to(slide);
+                self.needs_redraw = true;
+            }
+            None => self.set_pdf_page(slide),
+        }
     }
 
+    /// Move `offset` slides, or PDF pages, on from the one being shown
+    pub fn step_slide(&mut self, offset: i64) {
+        let current = match (&self.slides, &self.pdf) {
+            (Some(slides), _) => slides.slide(),
+            (None, Some(pdf)) => pdf.page(),
+            (None, None) => return,
+        };
+        self.go_to_slide((current as i64 + offset).max(1) as u32);
+    }
+
+    /// Play, and ask the player to resume
     pub fn play(&mut self) {
         if self.playback_state != PlaybackState::Playing {
             self.playback_state = PlaybackState::Playing;
+            self.pending_pause = Some(false);
             self.needs_redraw = true;
         }
     }
 
+    /// Pause, and ask the player to follow
     pub fn pause(&mut self) {
         if self.playback_state == PlaybackState::Playing {
             self.playback_state = PlaybackState::Paused;
+            self.pending_pause = Some(true);
             self.needs_redraw = true;
         }
     }
@@ -96,11 +721,18 @@ impl CastWindow {
     pub fn stop(&mut self) {
         self.playback_state = PlaybackState::Stopped;
         self.seek_position = 0.0;
+        // Ends the capture loop and frees its texture
+        self.mirror = None;
+        self.wasm = None;
+        self.video = None;
+        self.audio = None;
         self.needs_redraw = true;
     }
 
+    /// Set the volume, and ask the player to follow
     pub fn set_volume(&mut self, volume: f32) {
         self.volume = volume.clamp(0.0, 1.0);
+        self.pending_volume = Some(self.volume);
         self.needs_redraw = true;
     }
 
@@ -108,7 +740,118 @@ impl CastWindow {
         self.seek_position = position.clamp(0.0, 1.0);
         self.needs_redraw = true;
     }
+
+    /// Move the seek bar and ask the player to follow, if the content can seek
+    pub fn request_seek(&mut self, position: f32) {
+        self.seek(position);
+        if let (true, Some(duration)) = (self.seekable, self.duration) {
+            self.pending_seek = Some(self.seek_position as f64 * duration as f64);
+        }
+    }
+
+    /// The seek the user asked for since the last call, in seconds
+    pub fn take_seek_request(&mut self) -> Option<f64> {
+        self.pending_seek.take()
+    }
+
+    /// Whether the user paused (true) or resumed since the last call
+    pub fn take_pause_request(&mut self) -> Option<bool> {
+        self.pending_pause.take()
+    }
+
+    /// The volume the user asked for since the last call
+    pub fn take_volume_request(&mut self) -> Option<f32> {
+        self.pending_volume.take()
+    }
+
+    /// Playback progress reported by the player. The player's pause state wins
+    /// over the window's, e.g. when a pause was refused.
+    pub fn set_position(&mut self, position: f64, duration: Option<f64>, seekable: bool, paused: bool) {
+        self.position = position.max(0.0);
+        self.duration = duration.filter(|duration| *duration > 0.0).map(|duration| duration.round() as u64);
+        self.seekable = seekable && self.duration.is_some();
+        if let Some(duration) = duration.filter(|duration| *duration > 0.0) {
+            self.seek_position = (self.position / duration).clamp(0.0, 1.0) as f32;
+        }
+        self.playback_state = match (self.playback_state, paused) {
+            (PlaybackState::Stopped, _) => PlaybackState::Stopped,
+            (_, true) => PlaybackState::Paused,
+            (_, false) => PlaybackState::Playing,
+        };
+        self.needs_redraw = true;
+    }
     
+    fn init(&mut self, event_loop: &ActiveEventLoop, window_attributes: WindowAttributes) -> CasterResult<()> {
+        let window = Arc::new(event_loop.create_window(window_attributes)
+            .map_err(|e| CasterError::Display(format!("Failed to create window: {}", e)))?);
+
+        // Initialize egui
+        let egui_ctx = egui::Context::default();
+        let egui_state = egui_winit::State::new(
+            egui_ctx.clone(),
+            egui::ViewportId::ROOT,
+            &window,
+            Some(window.scale_factor() as f32),
+            None,
+            Some(2048),
+        );
+
+        // Initialize wgpu
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let surface = instance.create_surface(window.clone())
+            .map_err(|e| CasterError::Display(format!("Failed to create surface: {}", e)))?;
+
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: Some(&surface),
+            force_fallback_adapter: false,
+        }))
+        .ok_or_else(|| CasterError::Display("No suitable graphics adapter".into()))?;
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Device"),
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::default(),
+                memory_hints: Default::default(),
+            },
+            None,
+        ))
+        .map_err(|e| CasterError::Display(format!("Failed to open graphics device: {}", e)))?;
+
+        let size = window.inner_size();
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface.get_capabilities(&adapter).formats[0],
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(&device, &surface_config);
+
+        let egui_renderer = egui_wgpu::Renderer::new(&device, surface_config.format, None, 1, false);
+
+        self.window = Some(window);
+        self.egui_ctx = Some(egui_ctx);
+        self.egui_state = Some(egui_state);
+        self.egui_renderer = Some(egui_renderer);
+        self.wgpu_state = Some(WgpuState {
+            device,
+            queue,
+            surface,
+            surface_config,
+        });
+
+        Ok(())
+    }
+
     /// Format seconds as MM:SS
     fn format_time(seconds: u64) -> String {
         let minutes = seconds / 60;
@@ -116,8 +859,8 @@ impl CastWindow {
         format!("{}:{:02}", minutes, seconds)
     }
 
-    fn render_ui(&mut self, ctx: &egui::Context) {
-        // Top panel with controls
+    /// Top bar with playback controls and the seek bar along the bottom
+    fn render_controls(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::top("controls").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.heading("Q8 Caster");
@@ -126,7 +869,7 @@ impl CastWindow {
 
                 // Playback controls
                 if ui.button("⏮").clicked() {
-                    self.seek_position = 0.0;
+                    self.request_seek(0.0);
                 }
 
                 match self.playback_state {
@@ -150,14 +893,16 @@ impl CastWindow {
                 }
 
                 if ui.button("⏭").clicked() {
-                    self.seek_position = 1.0;
+                    self.request_seek(1.0);
                 }
 
                 ui.separator();
 
                 // Volume control
                 ui.label("🔊");
-                ui.add(egui::Slider::new(&mut self.volume, 0.0..=1.0).show_value(false));
+                if ui.add(egui::Slider::new(&mut self.volume, 0.0..=1.0).show_value(false)).changed() {
+                    self.pending_volume = Some(self.volume);
+                }
 
                 ui.separator();
 
@@ -167,6 +912,22 @@ impl CastWindow {
                 if let Some(ref content_type) = self.content_type {
                     ui.label(format!("Type: {:?}", content_type));
                 }
+
+                if matches!(self.content_type, Some(ContentType::Image { .. })) {
+                    ui.separator();
+
+                    let mut fit = self.image_fit;
+                    egui::ComboBox::from_label("Fit")
+                        .selected_text(fit.label())
+                        .show_ui(ui, |ui| {
+                            for option in ImageFit::ALL {
+                                ui.selectable_value(&mut fit, option, option.label());
+                            }
+                        });
+                    if fit != self.image_fit {
+                        self.set_image_fit(fit);
+                    }
+                }
             });
         });
 
@@ -174,24 +935,43 @@ impl CastWindow {
         egui::TopBottomPanel::bottom("seek").show(ctx, |ui| {
             ui.add_space(4.0);
             ui.horizontal(|ui| {
-                // Calculate and display current time and duration
-                let (current_time, total_time) = if let Some(duration) = self.duration {
-                    let current_secs = (self.seek_position * duration as f32) as u64;
-                    (Self::format_time(current_secs), Self::format_time(duration))
-                } else {
-                    ("0:00".to_string(), "0:00".to_string())
+                // While dragging, show where the bar is rather than where playback is
+                let current_secs = match self.duration {
+                    Some(duration) if self.seekable => (self.seek_position * duration as f32) as u64,
+                    _ => self.position as u64,
                 };
-                
-                ui.label(current_time);
-                if ui.add(egui::Slider::new(&mut self.seek_position, 0.0..=1.0).show_value(false)).changed() {
-                    // User seeked to a new position - trigger actual seek operation
-                    self.seek(self.seek_position);
-                    // Note: Actual media seek would be implemented here when media backend is added
+                let total_time = self.duration.map(Self::format_time).unwrap_or_else(|| "--:--".to_string());
+
+                ui.label(Self::format_time(current_secs));
+                // Live streams and stills have nowhere to seek to
+                let slider = ui.add_enabled(
+                    self.seekable,
+                    egui::Slider::new(&mut self.seek_position, 0.0..=1.0).show_value(false),
+                );
+                // Seek once the drag ends rather than on every step of it
+                if slider.drag_stopped() || (slider.changed() && !slider.dragged()) {
+                    self.request_seek(self.seek_position);
                 }
                 ui.label(total_time);
             });
             ui.add_space(4.0);
         });
+    }
+
+    fn render_ui(&mut self, ctx: &egui::Context) {
+        if self.ambient_due() {
+            self.render_ambient(ctx);
+            return;
+        }
+        self.ambient_visible = false;
+
+        if self.controls_visible() {
+            self.render_controls(ctx);
+        }
+        if self.show_help {
+            self.render_help(ctx);
+        }
+        self.render_toast(ctx);
 
         // Central panel for content display
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -209,8 +989,11 @@ impl CastWindow {
                 Some(ContentType::Audio { .. }) => {
                     self.render_audio(ui);
                 }
-                Some(ContentType::Pdf { page }) => {
-                    self.render_pdf(ui, *page);
+                Some(ContentType::Pdf { .. }) => {
+                    self.render_pdf(ui);
+                }
+                Some(ContentType::Presentation { .. }) => {
+                    self.render_presentation(ui);
                 }
                 Some(ContentType::WebAssembly { .. }) => {
                     self.render_wasm(ui);
@@ -233,147 +1016,198 @@ impl CastWindow {
         });
     }
 
-    fn render_markdown(&self, ui: &mut egui::Ui) {
-        if let Ok(text) = std::str::from_utf8(&self.content_data) {
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.add(egui::Label::new(text).wrap());
-            });
+    /// The idle screen in place of everything else; toasts still show over it
+    fn render_ambient(&mut self, ctx: &egui::Context) {
+        let Some(ref mut ambient) = self.ambient else {
+            return;
+        };
+        if !self.ambient_visible {
+            self.ambient_visible = true;
+            ambient.restart();
         }
-    }
 
-    fn render_image(&self, ui: &mut egui::Ui) {
-        ui.centered_and_justified(|ui| {
-            ui.label("Image rendering");
-            // TODO: Load image with image crate and display
-        });
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none())
+            .show(ctx, |ui| ambient.show(ui));
+        self.render_toast(ctx);
     }
 
-    fn render_video(&self, ui: &mut egui::Ui) {
-        ui.centered_and_justified(|ui| {
-            ui.label("Video playback");
-            // TODO: Integrate with GStreamer for video playback
-        });
+    fn render_markdown(&mut self, ui: &mut egui::Ui) {
+        if let Some(ref mut markdown) = self.markdown {
+            markdown.show(ui);
+        }
     }
 
-    fn render_audio(&self, ui: &mut egui::Ui) {
-        ui.centered_and_justified(|ui| {
-            ui.vertical(|ui| {
-                ui.heading("🎵");
-                ui.label("Audio playback");
-                ui.add_space(20.0);
-                ui.label("Audio visualization not yet implemented.");
-                ui.add_space(10.0);
-                ui.label(format!("Volume: {:.0}%", self.volume * 100.0));
+    fn render_image(&mut self, ui: &mut egui::Ui) {
+        if self.texture_dirty {
+            self.upload_image_texture(ui.ctx());
+        }
+
+        if let Some(ref error) = self.content_error {
+            let error = error.clone();
+            self.render_image_error(ui, &error);
+            return;
+        }
+
+        let Some(ref texture) = self.content_texture else {
+            ui.centered_and_justified(|ui| {
+                ui.spinner();
             });
-        });
+            return;
+        };
+        let texture_id = texture.id();
+        let image_size = texture.size_vec2();
+
+        // Drag to pan, scroll to zoom around the pointer, double-click to reset
+        let available = ui.available_rect_before_wrap();
+        let response = ui.allocate_rect(available, egui::Sense::click_and_drag());
+        if response.double_clicked() {
+            self.zoom = 1.0;
+            self.pan = egui::Vec2::ZERO;
+        }
+        if response.dragged() {
+            self.pan += response.drag_delta();
+        }
+        if let Some(pointer) = response.hover_pos() {
+            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                let zoom = (self.zoom * (scroll / 200.0).exp()).clamp(MIN_ZOOM, MAX_ZOOM);
+                let factor = zoom / self.zoom;
+                let center = available.center() + self.pan;
+                self.pan = pointer + (center - pointer) * factor - available.center();
+                self.zoom = zoom;
+            }
+        }
+
+        let fitted = match self.image_fit {
+            ImageFit::Contain => image_size * (available.width() / image_size.x).min(available.height() / image_size.y),
+            ImageFit::Cover => image_size * (available.width() / image_size.x).max(available.height() / image_size.y),
+            ImageFit::Stretch => available.size(),
+            ImageFit::Original => image_size / ui.ctx().pixels_per_point(),
+        };
+        let rect = egui::Rect::from_center_size(available.center() + self.pan, fitted * self.zoom);
+
+        // Cover and zoomed images overflow the panel; keep them inside it
+        ui.set_clip_rect(available);
+        egui::Image::new((texture_id, rect.size())).paint_at(ui, rect);
     }
 
-    fn render_pdf(&self, ui: &mut egui::Ui, page: Option<u32>) {
+    fn render_image_error(&self, ui: &mut egui::Ui, error: &str) {
         ui.centered_and_justified(|ui| {
-            ui.label(format!("PDF Document - Page: {}", page.unwrap_or(1)));
-            // TODO: Render PDF using pdfium-render
+            egui::Frame::group(ui.style())
+                .fill(egui::Color32::from_rgb(60, 20, 20))
+                .inner_margin(egui::Margin::same(16.0))
+                .show(ui, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.heading("Can't display this image");
+                        ui.add_space(8.0);
+                        ui.colored_label(egui::Color32::LIGHT_RED, error);
+                        ui.add_space(8.0);
+                        ui.label(format!("{} bytes received", self.content_data.len()));
+                    });
+                });
         });
     }
 
-    fn render_wasm(&self, ui: &mut egui::Ui) {
-        ui.centered_and_justified(|ui| {
-            ui.label("WebAssembly Module");
-            ui.label("Running WASM...");
-            // TODO: Execute WASM module with wasmer
-        });
+    /// Decode `content_data` and upload it as an egui texture
+    fn upload_image_texture(&mut self, ctx: &egui::Context) {
+        self.texture_dirty = false;
+
+        match decode_image(&self.content_data) {
+            Ok(decoded) => {
+                let rgba = decoded.to_rgba8();
+                let size = [rgba.width() as usize, rgba.height() as usize];
+                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw());
+
+                self.content_texture = Some(ctx.load_texture("cast-content", color_image, egui::TextureOptions::LINEAR));
+                self.content_error = None;
+                if self.playback_state == PlaybackState::Loading {
+                    self.playback_state = PlaybackState::Playing;
+                }
+            }
+            Err(e) => {
+                self.content_texture = None;
+                self.content_error = Some(e);
+                self.playback_state = PlaybackState::Stopped;
+            }
+        }
     }
 
-    fn render_screen_mirror(&self, ui: &mut egui::Ui) {
-        ui.centered_and_justified(|ui| {
-            ui.label("Screen Mirroring Active");
-            // TODO: Display captured screen content
-        });
+    fn render_video(&mut self, ui: &mut egui::Ui) {
+        match self.video {
+            Some(ref mut video) => video.show(ui),
+            None => {
+                ui.centered_and_justified(|ui| {
+                    ui.label("Video playback");
+                });
+            }
+        }
     }
-}
 
-impl ApplicationHandler for CastWindow {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        if self.window.is_none() {
-            let window_attributes = Window::default_attributes()
-                .with_title("Q8 Caster")
-                .with_inner_size(winit::dpi::LogicalSize::new(1280, 720))
-                .with_resizable(true);
+    fn render_audio(&mut self, ui: &mut egui::Ui) {
+        match self.audio {
+            Some(ref mut audio) => audio.show(ui),
+            None => {
+                ui.centered_and_justified(|ui| {
+                    ui.vertical(|ui| {
+                        ui.heading("🎵");
+                        ui.label("Audio playback");
+                        ui.add_space(10.0);
+                        ui.label(format!("Volume: {:.0}%", self.volume * 100.0));
+                    });
+                });
+            }
+        }
+    }
 
-            let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
-
-            // Initialize egui
-            let egui_ctx = egui::Context::default();
-            let egui_state = egui_winit::State::new(
-                egui_ctx.clone(),
-                egui::ViewportId::ROOT,
-                &window,
-                Some(window.scale_factor() as f32),
-                Some(2048),
-            );
-
-            // Initialize wgpu
-            let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-                backends: wgpu::Backends::all(),
-                ..Default::default()
-            });
+    fn render_pdf(&mut self, ui: &mut egui::Ui) {
+        if let Some(ref mut pdf) = self.pdf {
+            pdf.show(ui);
+        }
+    }
 
-            let surface = instance.create_surface(window.clone()).unwrap();
-
-            let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            }))
-            .unwrap();
-
-            let (device, queue) = pollster::block_on(adapter.request_device(
-                &wgpu::DeviceDescriptor {
-                    label: Some("Device"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    memory_hints: Default::default(),
-                },
-                None,
-            ))
-            .unwrap();
-
-            let size = window.inner_size();
-            let surface_config = wgpu::SurfaceConfiguration {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                format: surface.get_capabilities(&adapter).formats[0],
-                width: size.width,
-                height: size.height,
-                present_mode: wgpu::PresentMode::Fifo,
-                alpha_mode: wgpu::CompositeAlphaMode::Auto,
-                view_formats: vec![],
-                desired_maximum_frame_latency: 2,
-            };
-            surface.configure(&device, &surface_config);
-
-            let egui_renderer = egui_wgpu::Renderer::new(&device, surface_config.format, None, 1, false);
-
-            self.window = Some(window);
-            self.egui_ctx = Some(egui_ctx);
-            self.egui_state = Some(egui_state);
-            self.egui_renderer = Some(egui_renderer);
-            self.wgpu_state = Some(WgpuState {
-                device,
-                queue,
-                surface,
-                surface_config,
+    fn render_presentation(&mut self, ui: &mut egui::Ui) {
+        if let Some(ref mut slides) = self.slides {
+            slides.show(ui);
+        } else if let Some(ref mut pdf) = self.pdf {
+            pdf.show(ui);
+        } else if let Some(ref error) = self.content_error {
+            ui.centered_and_justified(|ui| {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Can't display presentation: {}", error));
             });
         }
     }
 
-    fn window_event(
-        &mut self,
-        event_loop: &ActiveEventLoop,
-        _window_id: winit::window::WindowId,
-        event: WindowEvent,
-    ) {
+    fn render_wasm(&mut self, ui: &mut egui::Ui) {
+        match self.wasm {
+            Some(ref mut wasm) => wasm.show(ui),
+            None => {
+                ui.centered_and_justified(|ui| {
+                    ui.label("WebAssembly module stopped");
+                });
+            }
+        }
+    }
+
+    fn render_screen_mirror(&mut self, ui: &mut egui::Ui) {
+        match self.mirror {
+            Some(ref mut mirror) => mirror.show(ui),
+            None => {
+                ui.centered_and_justified(|ui| {
+                    ui.label("Screen mirroring stopped");
+                });
+            }
+        }
+    }
+
+    /// Handle an event for this window; false once the user has asked to close it
+    pub fn handle_window_event(&mut self, event: WindowEvent) -> bool {
+        // Keys egui used, e.g. typing into a field, aren't shortcuts
+        let mut consumed = false;
         if let Some(ref mut egui_state) = self.egui_state {
             if let Some(ref window) = self.window {
                 let response = egui_state.on_window_event(window, &event);
+                consumed = response.consumed;
 
                 if response.repaint {
                     self.needs_redraw = true;
@@ -384,8 +1218,24 @@ impl ApplicationHandler for CastWindow {
 
         match event {
             WindowEvent::CloseRequested => {
-                event_loop.exit();
+                // Signage stays up until it is stopped through the API
+                if self.window_mode == WindowMode::Kiosk {
+                    tracing::debug!("Ignoring close request in kiosk mode");
+                    return true;
+                }
+                return false;
             }
+            WindowEvent::CursorMoved { .. } | WindowEvent::MouseInput { .. } if self.window_mode == WindowMode::Kiosk => {
+                self.last_pointer_activity = Some(Instant::now());
+                if self.cursor_hidden {
+                    self.cursor_hidden = false;
+                    if let Some(ref window) = self.window {
+                        window.set_cursor_visible(true);
+                        window.request_redraw();
+                    }
+                }
+            }
+            WindowEvent::KeyboardInput { ref event, .. } if !consumed => self.handle_key(event),
             WindowEvent::Resized(size) => {
                 if let Some(ref mut wgpu_state) = self.wgpu_state {
                     wgpu_state.surface_config.width = size.width;
@@ -394,15 +1244,22 @@ impl ApplicationHandler for CastWindow {
                 }
             }
             WindowEvent::RedrawRequested => {
-                if let (Some(ref window), Some(ref egui_ctx), Some(ref mut egui_state), Some(ref mut egui_renderer), Some(ref mut wgpu_state)) =
-                    (&self.window, &self.egui_ctx, &mut self.egui_state, &mut self.egui_renderer, &mut self.wgpu_state)
+                // The UI is laid out first, since it needs all of the window's state
+                let (Some(window), Some(egui_ctx)) = (self.window.clone(), self.egui_ctx.clone()) else {
+                    return true;
+                };
+                let Some(raw_input) = self.egui_state.as_mut().map(|egui_state| egui_state.take_egui_input(&window)) else {
+                    return true;
+                };
+                let output = egui_ctx.run(raw_input, |ctx| {
+                    self.render_ui(ctx);
+                });
+
+                if let (Some(ref mut egui_state), Some(ref mut egui_renderer), Some(ref mut wgpu_state)) =
+                    (&mut self.egui_state, &mut self.egui_renderer, &mut self.wgpu_state)
                 {
-                    let raw_input = egui_state.take_egui_input(window);
-                    let output = egui_ctx.run(raw_input, |ctx| {
-                        self.render_ui(ctx);
-                    });
 
-                    egui_state.handle_platform_output(window, output.platform_output);
+                    egui_state.handle_platform_output(&window, output.platform_output);
 
                     let paint_jobs = egui_ctx.tessellate(output.shapes, output.pixels_per_point);
 
@@ -425,6 +1282,7 @@ impl ApplicationHandler for CastWindow {
 
                     // Render pass
                     {
+                        // egui-wgpu wants a pass that doesn't borrow the encoder
                         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                             label: Some("Render Pass"),
                             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -443,7 +1301,7 @@ impl ApplicationHandler for CastWindow {
                             depth_stencil_attachment: None,
                             timestamp_writes: None,
                             occlusion_query_set: None,
-                        });
+                        }).forget_lifetime();
 
                         egui_renderer.render(&mut render_pass, &paint_jobs, &screen_descriptor);
                     }
@@ -458,16 +1316,65 @@ impl ApplicationHandler for CastWindow {
 
                     // Only request redraw if needed (content is playing or UI state changed)
                     self.needs_redraw = false;
-                    if self.playback_state == PlaybackState::Playing {
+                    // A toast keeps frames coming until it has faded out
+                    if self.playback_state == PlaybackState::Playing || self.toast.is_some() {
                         window.request_redraw();
                     }
                 }
             }
             _ => {}
         }
+
+        true
+    }
+}
+
+impl ApplicationHandler for CastWindow {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_none() {
+            let window_attributes = Window::default_attributes()
+                .with_title("Q8 Caster")
+                .with_inner_size(winit::dpi::LogicalSize::new(1280, 720))
+                .with_resizable(true);
+
+            if let Err(e) = self.init(event_loop, window_attributes) {
+                tracing::error!("{}", e);
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if !self.handle_window_event(event) {
+            event_loop.exit();
+        }
     }
 }
 
+/// Decode an image the right way up; for animated GIFs this is the first frame
+fn decode_image(data: &[u8]) -> std::result::Result<image::DynamicImage, String> {
+    use image::ImageDecoder;
+
+    let reader = image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?;
+    if reader.format().is_none() {
+        return Err("Unrecognised image format".to_string());
+    }
+
+    let mut decoder = reader.into_decoder().map_err(|e| e.to_string())?;
+    // Phone photos are stored sideways with an EXIF tag saying which way is up
+    let orientation = decoder.orientation().unwrap_or(image::metadata::Orientation::NoTransforms);
+    let mut decoded = image::DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+    decoded.apply_orientation(orientation);
+    Ok(decoded)
+}
+
 pub fn run_cast_window() -> CasterResult<()> {
     let event_loop = EventLoop::new().map_err(|e| crate::error::CasterError::Display(e.to_string()))?;
     let mut app = CastWindow::new();
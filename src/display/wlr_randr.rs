@@ -0,0 +1,74 @@
+use serde::Deserialize;
+use tokio::process::Command;
+
+use crate::{Result, CasterError, Resolution, Position};
+
+/// An enabled output as reported by `wlr-randr --json`, for compositors
+/// implementing wlr-output-management (sway, Hyprland, labwc, ...)
+#[derive(Debug, Clone, Deserialize)]
+pub struct WlrOutput {
+    pub name: String,
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub modes: Vec<WlrMode>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WlrMode {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl WlrOutput {
+    pub fn supports(&self, resolution: &Resolution) -> bool {
+        self.modes.iter().any(|mode| mode.width == resolution.width && mode.height == resolution.height)
+    }
+}
+
+/// Enabled outputs and the modes each one supports
+pub async fn query_outputs() -> Result<Vec<WlrOutput>> {
+    let output = Command::new("wlr-randr")
+        .arg("--json")
+        .output()
+        .await
+        .map_err(|e| CasterError::Display(format!("Failed to run wlr-randr: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(CasterError::Display(format!(
+            "wlr-randr failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let outputs: Vec<WlrOutput> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| CasterError::Display(format!("Unexpected wlr-randr output: {}", e)))?;
+    Ok(outputs.into_iter().filter(|output| output.enabled).collect())
+}
+
+/// Set mode and/or position for one output in a single wlr-randr call
+pub async fn apply(output: &str, resolution: Option<&Resolution>, position: Option<&Position>) -> Result<()> {
+    let mut command = Command::new("wlr-randr");
+    command.args(["--output", output]);
+
+    if let Some(resolution) = resolution {
+        command.args(["--mode", &format!("{}x{}", resolution.width, resolution.height)]);
+    }
+    if let Some(position) = position {
+        command.args(["--pos", &format!("{},{}", position.x, position.y)]);
+    }
+
+    let result = command.output()
+        .await
+        .map_err(|e| CasterError::Display(format!("Failed to run wlr-randr: {}", e)))?;
+
+    if !result.status.success() {
+        return Err(CasterError::Display(format!(
+            "wlr-randr failed to configure {}: {}",
+            output,
+            String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
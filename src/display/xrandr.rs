@@ -0,0 +1,116 @@
+use tokio::process::Command;
+
+use crate::{Result, CasterError, Resolution, Position};
+
+/// A connected output as reported by `xrandr --query`
+#[derive(Debug, Clone)]
+pub struct XrandrOutput {
+    pub name: String,
+    pub primary: bool,
+    pub modes: Vec<(u32, u32)>,
+}
+
+impl XrandrOutput {
+    pub fn supports(&self, resolution: &Resolution) -> bool {
+        self.modes.contains(&(resolution.width, resolution.height))
+    }
+}
+
+/// Connected outputs and the modes each one supports
+pub async fn query_outputs() -> Result<Vec<XrandrOutput>> {
+    let output = Command::new("xrandr")
+        .arg("--query")
+        .output()
+        .await
+        .map_err(|e| CasterError::Display(format!("Failed to run xrandr: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(CasterError::Display(format!(
+            "xrandr --query failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(parse_outputs(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Set mode, position and/or mirroring for one output in a single xrandr call
+pub async fn apply(
+    output: &str,
+    resolution: Option<&Resolution>,
+    position: Option<&Position>,
+    same_as: Option<&str>,
+) -> Result<()> {
+    let mut command = Command::new("xrandr");
+    command.args(["--output", output]);
+
+    if let Some(resolution) = resolution {
+        command.args(["--mode", &format!("{}x{}", resolution.width, resolution.height)]);
+    }
+    if let Some(position) = position {
+        command.args(["--pos", &format!("{}x{}", position.x, position.y)]);
+    }
+    if let Some(source) = same_as {
+        command.args(["--same-as", source]);
+    }
+
+    let result = command.output()
+        .await
+        .map_err(|e| CasterError::Display(format!("Failed to run xrandr: {}", e)))?;
+
+    if !result.status.success() {
+        return Err(CasterError::Display(format!(
+            "xrandr failed to configure {}: {}",
+            output,
+            String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+// Output headers start at column 0 ("HDMI-1 connected primary 1920x1080+0+0 ..."),
+// their modes follow indented ("   1920x1080     60.00*+  50.00").
+fn parse_outputs(query: &str) -> Vec<XrandrOutput> {
+    let mut outputs: Vec<XrandrOutput> = Vec::new();
+    let mut in_connected = false;
+
+    for line in query.lines() {
+        if line.starts_with(char::is_whitespace) {
+            if !in_connected {
+                continue;
+            }
+            let mode = line.split_whitespace().next().and_then(parse_mode);
+            if let (Some(mode), Some(current)) = (mode, outputs.last_mut()) {
+                if !current.modes.contains(&mode) {
+                    current.modes.push(mode);
+                }
+            }
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let (Some(name), Some(state)) = (tokens.next(), tokens.next()) else {
+            in_connected = false;
+            continue;
+        };
+
+        in_connected = state == "connected";
+        if in_connected {
+            outputs.push(XrandrOutput {
+                name: name.to_string(),
+                primary: tokens.next() == Some("primary"),
+                modes: Vec::new(),
+            });
+        }
+    }
+
+    outputs
+}
+
+/// "1920x1080" or interlaced "1920x1080i"
+fn parse_mode(token: &str) -> Option<(u32, u32)> {
+    let (width, height) = token.split_once('x')?;
+    let height = height.trim_end_matches('i');
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
@@ -12,6 +12,29 @@ pub enum CasterError {
     
     #[error("Network error: {0}")]
     Network(String),
+
+    #[error("Not casting: {0}")]
+    NotCasting(String),
+
+    /// A caller sent a missing, malformed or out-of-range argument
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    /// A display, device, group or other named thing doesn't exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// Content of a type the server can't store or show
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMedia(String),
+
+    /// The feature isn't built yet, or not on this platform
+    #[error("Not implemented: {0}")]
+    NotImplemented(String),
+
+    /// The session's work was cancelled, e.g. by stop_cast
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
     
     #[error("Render error: {0}")]
     Render(String),
@@ -21,6 +44,16 @@ pub enum CasterError {
     
     #[error("MCP error: {0}")]
     Mcp(String),
+
+    #[error("Secrets error: {0}")]
+    Secrets(String),
+
+    /// A login or bearer token was missing, invalid or expired
+    #[error("Authentication error: {0}")]
+    Auth(String),
+
+    #[error("Config error: {0}")]
+    Config(String),
     
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -36,6 +69,32 @@ pub enum CasterError {
     Unknown(String),
 }
 
+impl CasterError {
+    /// Short name for the variant, for clients that react to a kind of failure
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CasterError::Display(_) => "display",
+            CasterError::Media(_) => "media",
+            CasterError::Network(_) => "network",
+            CasterError::NotCasting(_) => "not_casting",
+            CasterError::InvalidRequest(_) => "invalid_request",
+            CasterError::NotFound(_) => "not_found",
+            CasterError::UnsupportedMedia(_) => "unsupported_media",
+            CasterError::NotImplemented(_) => "not_implemented",
+            CasterError::Cancelled(_) => "cancelled",
+            CasterError::Render(_) => "render",
+            CasterError::Cache(_) => "cache",
+            CasterError::Mcp(_) => "mcp",
+            CasterError::Secrets(_) => "secrets",
+            CasterError::Auth(_) => "auth",
+            CasterError::Config(_) => "config",
+            CasterError::Io(_) => "io",
+            CasterError::Json(_) => "json",
+            CasterError::Unknown(_) => "unknown",
+        }
+    }
+}
+
 impl From<String> for CasterError {
     fn from(s: String) -> Self {
         CasterError::Unknown(s)
@@ -1,3 +1,5 @@
+#![recursion_limit = "512"]
+
 pub mod server;
 pub mod display;
 pub mod media;
@@ -6,6 +8,7 @@ pub mod network;
 pub mod cache;
 pub mod error;
 pub mod secrets;
+pub mod mcp;
 
 pub use error::{Result, CasterError};
 
@@ -26,19 +29,38 @@ pub struct CastSession {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ContentType {
-    Markdown { theme: Option<String> },
+    Markdown {
+        theme: Option<String>,
+        /// Typeset `$...$` and `$$...$$` as math
+        #[serde(default)]
+        math: bool,
+    },
     Video { codec: String, container: String },
     Audio { codec: String, format: String },
     Image { format: String },
     Pdf { page: Option<u32> },
     Model3D { format: String },
     Stream { protocol: StreamProtocol },
-    Presentation { format: String },
+    Presentation {
+        /// "markdown" (slides split at `---` lines), "pdf", or "auto" to tell from the file
+        format: String,
+        /// Theme for markdown slides
+        #[serde(default)]
+        theme: Option<String>,
+        #[serde(default)]
+        math: bool,
+    },
     ScreenMirror { source_display: Option<String>, quality: MirrorQuality },
-    WebAssembly { module_url: String, entry_point: Option<String> },
+    WebAssembly {
+        module_url: String,
+        entry_point: Option<String>,
+        /// Limits and access for the module, closed unless asked otherwise
+        #[serde(default)]
+        sandbox: render::WasmSandboxConfig,
+    },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum MirrorQuality {
     Low,      // 720p @ 30fps
@@ -47,6 +69,18 @@ pub enum MirrorQuality {
     Ultra,    // 4K @ 60fps
 }
 
+impl MirrorQuality {
+    /// Largest frame size and the frame rate this quality asks for
+    pub fn limits(&self) -> (u32, u32, u32) {
+        match self {
+            MirrorQuality::Low => (1280, 720, 30),
+            MirrorQuality::Medium => (1920, 1080, 30),
+            MirrorQuality::High => (1920, 1080, 60),
+            MirrorQuality::Ultra => (3840, 2160, 60),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "protocol", rename_all = "snake_case")]
 pub enum StreamProtocol {
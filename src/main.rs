@@ -1,17 +1,41 @@
+use q8_caster::display::WindowMode;
 use q8_caster::server::HttpServer;
+use q8_caster::server::config::ServerConfig;
+use std::path::PathBuf;
 use tracing_subscriber::EnvFilter;
 use clap::Parser;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[arg(short, long, default_value = "8420")]
-    port: u16,
+    /// Port to listen on, overriding the config file (default 8420)
+    #[arg(short, long)]
+    port: Option<u16>,
     
     /// Run in elevated mode (requires sudo)
     #[arg(short, long)]
     elevated: bool,
+
+    /// How cast windows open unless a cast asks otherwise: windowed, fullscreen or kiosk
+    #[arg(long, default_value = "fullscreen")]
+    window_mode: WindowMode,
+
+    /// Don't show volume, seek and "now casting" toasts on cast windows, e.g. for signage
+    #[arg(long)]
+    no_osd: bool,
+
+    /// TOML config file for the cache, discovery, auth and idle screen settings
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Requests per second each client may make to casting, discovery and capture
+    /// routes, overriding the config file; 0 turns the limit off
+    #[arg(long, env = "Q8_RATE_LIMIT")]
+    rate_limit: Option<f64>,
+
+    /// Requests a client may make in a burst before the rate limit applies
+    #[arg(long, env = "Q8_RATE_BURST")]
+    rate_burst: Option<u32>,
 }
 
 #[tokio::main]
@@ -33,15 +57,35 @@ async fn main() -> anyhow::Result<()> {
         // Check if running with elevated permissions
         if !is_elevated() {
             tracing::error!("Elevated mode requires root/administrator privileges");
-            tracing::info!("Please run with: sudo {} --elevated", std::env::args().next().unwrap());
+            if cfg!(windows) {
+                tracing::info!("Please run from an Administrator prompt: {} --elevated", std::env::args().next().unwrap());
+            } else {
+                tracing::info!("Please run with: sudo {} --elevated", std::env::args().next().unwrap());
+            }
             std::process::exit(1);
         }
         tracing::info!("Running in elevated mode");
     }
     
     // Create and run HTTP server
-    let server = HttpServer::new().await?;
-    server.run(args.port).await?;
+    let mut config = match args.config {
+        Some(ref path) => ServerConfig::load(path)?,
+        None => ServerConfig::default(),
+    };
+    if let Some(rate) = args.rate_limit {
+        config.rate_limit.requests_per_second = rate;
+    }
+    if let Some(burst) = args.rate_burst {
+        config.rate_limit.burst = burst;
+    }
+    let port = args.port.unwrap_or(config.port);
+    let server = HttpServer::new(config).await?;
+    {
+        let mut display_manager = server.display_manager.write().await;
+        display_manager.set_default_window_mode(args.window_mode);
+        display_manager.set_osd_enabled(!args.no_osd);
+    }
+    server.run(port).await?;
     
     Ok(())
 }
@@ -51,8 +95,35 @@ fn is_elevated() -> bool {
     unsafe { libc::geteuid() == 0 }
 }
 
-#[cfg(not(unix))]
+/// Whether the process token is elevated, i.e. running "as administrator" past UAC
+#[cfg(windows)]
+fn is_elevated() -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    unsafe {
+        let mut token: HANDLE = std::ptr::null_mut();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+            return false;
+        }
+
+        let mut elevation = TOKEN_ELEVATION { TokenIsElevated: 0 };
+        let mut returned = 0u32;
+        let ok = GetTokenInformation(
+            token,
+            TokenElevation,
+            &mut elevation as *mut TOKEN_ELEVATION as *mut std::ffi::c_void,
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut returned,
+        );
+        CloseHandle(token);
+
+        ok != 0 && elevation.TokenIsElevated != 0
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
 fn is_elevated() -> bool {
-    // TODO: Implement Windows elevation check
     false
 }
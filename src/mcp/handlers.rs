@@ -3,92 +3,185 @@ use serde_json::{json, Value};
 use tracing::info;
 
 use crate::mcp::server::McpServer;
-use crate::{ContentType, ContentSource, StreamProtocol};
+use crate::server::groups::{self, CastTarget};
+use crate::server::cancel;
+use crate::server::live_reload;
+use crate::server::sse::{notify_cast_started, notify_display_stopped};
+use crate::server::playlist::{self, PlaylistContext, PlaylistItem};
+use crate::{CasterError, ContentType, ContentSource, Resolution, Position};
+use crate::display::{DisplayConfig, WindowCommand, WindowMode};
+use crate::network::{DeviceFilter, DialLaunch, DiscoveryConfig, ReceiverConfig, RetryPolicy, SubtitleTrack};
+use crate::render::SlideFormat;
 
-pub async fn cast_content_handler(_server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
-    let display_id = args["display_id"].as_str().map(|s| s.to_string());
-    let content_type = args["content_type"].as_str().unwrap_or("");
-    let source = args["source"].as_str().unwrap_or("");
+/// JSON-RPC error code for a tool call that was understood but failed
+pub const TOOL_FAILED: i64 = -32000;
+/// Error code when a device has no media session to control; `data` has the
+/// `reason` and `device_name`
+pub const NOT_CASTING: i64 = -32001;
+
+/// The arguments are missing, malformed or unsupported
+fn invalid_params(message: impl std::fmt::Display) -> jsonrpc_core::Error {
+    jsonrpc_core::Error::invalid_params(message.to_string())
+}
+
+fn tool_failed(message: impl std::fmt::Display) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(TOOL_FAILED),
+        message: message.to_string(),
+        data: None,
+    }
+}
+
+fn not_casting(message: impl std::fmt::Display, device_name: &str) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(NOT_CASTING),
+        message: message.to_string(),
+        data: Some(json!({"reason": "not_casting", "device_name": device_name})),
+    }
+}
+
+/// Bad arguments, naming the field in `data` so clients can point at it
+fn invalid_field(field: &str, message: impl std::fmt::Display) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        data: Some(json!({"field": field})),
+        ..invalid_params(message)
+    }
+}
+
+/// The engines a playlist or cast group casts to displays with
+fn playlist_context(server: &McpServer) -> PlaylistContext {
+    PlaylistContext {
+        display_manager: Arc::clone(&server.display_manager),
+        media_engine: Arc::clone(&server.media_engine),
+        content_cache: Arc::clone(&server.content_cache),
+    }
+}
+
+/// A request field that doesn't parse, named in `data`
+fn content_error(error: groups::ContentError) -> jsonrpc_core::Error {
+    invalid_field(&error.field, error.message)
+}
+
+pub async fn cast_content_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let display_id = match &args["display_id"] {
+        Value::Null => None,
+        Value::String(id) => Some(id.clone()),
+        _ => return Err(invalid_field("display_id", "display_id must be a string")),
+    };
+    let (mut content_type, content_source) = groups::parse_content(args).map_err(content_error)?;
     let options = &args["options"];
-    
-    info!("Casting {} to display {:?}", content_type, display_id);
-    
-    let content_type = match content_type {
-        "markdown" => ContentType::Markdown {
-            theme: options["theme"].as_str().map(|s| s.to_string())
-        },
-        "video" => ContentType::Video {
-            codec: options["codec"].as_str().unwrap_or("auto").to_string(),
-            container: options["container"].as_str().unwrap_or("auto").to_string()
-        },
-        "audio" => ContentType::Audio {
-            codec: options["codec"].as_str().unwrap_or("auto").to_string(),
-            format: options["format"].as_str().unwrap_or("auto").to_string()
-        },
-        "image" => ContentType::Image {
-            format: options["format"].as_str().unwrap_or("auto").to_string()
-        },
-        "pdf" => ContentType::Pdf {
-            page: options["page"].as_u64().map(|p| p as u32)
-        },
-        "model3d" => ContentType::Model3D {
-            format: options["format"].as_str().unwrap_or("gltf").to_string()
-        },
-        "stream" => {
-            let protocol = options["protocol"].as_str().unwrap_or("rtsp");
-            match protocol {
-                "rtsp" => ContentType::Stream {
-                    protocol: StreamProtocol::Rtsp { url: source.to_string() }
-                },
-                "webrtc" => ContentType::Stream {
-                    protocol: StreamProtocol::WebRtc { offer: options["offer"].as_str().unwrap_or("").to_string() }
-                },
-                "hls" => ContentType::Stream {
-                    protocol: StreamProtocol::Hls { manifest_url: source.to_string() }
-                },
-                "dash" => ContentType::Stream {
-                    protocol: StreamProtocol::Dash { manifest_url: source.to_string() }
-                },
-                _ => return Ok(json!({"error": format!("Unknown stream protocol: {}", protocol)}))
-            }
-        },
-        "presentation" => ContentType::Presentation {
-            format: options["format"].as_str().unwrap_or("auto").to_string()
-        },
-        "screen_mirror" => {
-            use crate::MirrorQuality;
-            let quality = match options["quality"].as_str().unwrap_or("medium") {
-                "low" => MirrorQuality::Low,
-                "high" => MirrorQuality::High,
-                "ultra" => MirrorQuality::Ultra,
-                _ => MirrorQuality::Medium,
-            };
-            ContentType::ScreenMirror {
-                source_display: options["source_display"].as_str().map(|s| s.to_string()),
-                quality
-            }
-        },
-        "webassembly" | "wasm" => ContentType::WebAssembly {
-            module_url: source.to_string(),
-            entry_point: options["entry_point"].as_str().map(|s| s.to_string())
-        },
-        _ => return Ok(json!({"error": format!("Unknown content type: {}", content_type)}))
+    let source = args["source"].as_str().unwrap_or("");
+
+    info!("Casting {} to display {:?}", groups::content_label(&content_type), display_id);
+
+    if let ContentType::WebAssembly { sandbox, .. } = &mut content_type {
+        groups::limit_wasm_sandbox(sandbox, &server.wasm_limits, &*server.content_cache.read().await).map_err(content_error)?;
+    }
+
+    // Markdown files can be recast on every save
+    let watch_path = if groups::option_bool(options, "watch").map_err(content_error)?.unwrap_or(false) {
+        match live_reload::validate(&content_type, &content_source) {
+            Ok(path) => Some(path),
+            Err(e) => return Err(invalid_params(e)),
+        }
+    } else {
+        None
     };
+
+    let session_id = uuid::Uuid::new_v4().to_string();
     
-    let content_source = if source.starts_with("http://") || source.starts_with("https://") {
-        ContentSource::Url { url: source.to_string() }
-    } else if source.starts_with("cache://") {
-        ContentSource::Cache { key: source.strip_prefix("cache://").unwrap_or("").to_string() }
+    // Markdown, images, PDFs, presentations, screen mirrors and WebAssembly frames are drawn by a cast window on the display itself.
+    // Video and audio are played by the media engine, with the window showing controls.
+    let plays = matches!(content_type, ContentType::Video { .. } | ContentType::Audio { .. });
+    let mirrors = matches!(content_type, ContentType::ScreenMirror { .. });
+    let windowed = matches!(
+        content_type,
+        ContentType::Markdown { .. }
+            | ContentType::Image { .. }
+            | ContentType::Pdf { .. }
+            | ContentType::Presentation { .. }
+            | ContentType::WebAssembly { .. }
+    );
+    // Nothing draws 3D models or streams on a display yet
+    if !(plays || mirrors || windowed) {
+        let what = if matches!(content_type, ContentType::Model3D { .. }) { "3D models" } else { "streams" };
+        return Err(tool_failed(format!("Casting {} to a display is not supported yet", what)));
+    }
+
+    let displays = server.display_manager.read().await.list_displays().await.unwrap_or_default();
+    let display = match display_id {
+        Some(ref id) => displays.iter().find(|d| &d.id == id),
+        None => displays.iter().find(|d| d.is_primary).or(displays.first()),
+    };
+    let Some(display) = display.cloned() else {
+        return Err(tool_failed("No display to cast to"));
+    };
+    let display_id = display.id.clone();
+    // A cast by hand takes over from any playlist, watched file or earlier session
+    playlist::stop(&display_id);
+    live_reload::stop(&display_id);
+    cancel::cancel_display(&display_id);
+
+    let window_mode = match options["window_mode"].as_str().map(str::parse::<WindowMode>).transpose() {
+        Ok(window_mode) => window_mode,
+        Err(e) => return Err(invalid_params(e)),
+    };
+
+    let data = if plays || mirrors {
+        Vec::new()
     } else {
-        ContentSource::File { path: source.to_string() }
+        match server.content_cache.read().await.read_source(source).await {
+            Ok(data) => data,
+            Err(e) => return Err(tool_failed(e)),
+        }
     };
-    
-    // TODO: Implement actual casting logic
-    let session_id = uuid::Uuid::new_v4();
-    
+    let uri = match plays {
+        true => match crate::media::playback_uri(&*server.content_cache.read().await, source) {
+            Ok(uri) => Some(uri),
+            Err(e) => return Err(tool_failed(e)),
+        },
+        false => None,
+    };
+
+    let opened = server.display_manager.write().await.create_window(&display_id, window_mode).await;
+    let window = match opened.and_then(|window| window.send(WindowCommand::SetContent {
+        content_type: content_type.clone(),
+        data,
+        title: Some(groups::source_title(source)),
+    }).map(|_| window)) {
+        Ok(window) => window,
+        Err(e) => return Err(tool_failed(e)),
+    };
+
+    if let Some(uri) = uri {
+        let played = server.media_engine.write().await.play_uri_on(&uri, Some(&display));
+        let attached = played.and_then(|feeds| match feeds {
+            Some(feeds) => feeds.attach(&window),
+            None => Ok(()),
+        });
+        if let Err(e) = attached {
+            return Err(tool_failed(e));
+        }
+    }
+
+    // Until it's stopped or replaced, so stop_cast can find it by session id
+    cancel::register(&display_id, &session_id);
+    notify_cast_started(display_id.clone(), groups::content_label(&content_type), session_id.clone());
+
+    if let Some(path) = watch_path {
+        live_reload::start(
+            display_id.clone(),
+            session_id.clone(),
+            path,
+            options["theme"].as_str().map(str::to_string),
+            options["math"].as_bool().unwrap_or(false),
+            Arc::clone(&server.display_manager),
+        );
+    }
+
     Ok(json!({
         "success": true,
-        "session_id": session_id.to_string(),
+        "session_id": session_id,
         "display_id": display_id,
         "content_type": content_type,
         "source": content_source
@@ -122,20 +215,33 @@ pub async fn list_audio_devices_handler(server: Arc<McpServer>, _args: &Value) -
     }))
 }
 
-pub async fn configure_display_handler(_server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+pub async fn configure_display_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
     let display_id = args["display_id"].as_str().unwrap_or("");
-    let _resolution = &args["resolution"];
-    let _position = &args["position"];
-    let _mirror = args["mirror"].as_str();
+
+    let parsed = (
+        serde_json::from_value::<Option<Resolution>>(args["resolution"].clone()),
+        serde_json::from_value::<Option<Position>>(args["position"].clone()),
+    );
+    let (resolution, position) = match parsed {
+        (Ok(resolution), Ok(position)) => (resolution, position),
+        (Err(e), _) | (_, Err(e)) => return Err(invalid_params(format!("Invalid display configuration: {}", e))),
+    };
+    let config = DisplayConfig {
+        resolution,
+        position,
+        mirror_from: args["mirror"].as_str().map(|s| s.to_string()),
+    };
     
     info!("Configuring display {}", display_id);
     
-    // TODO: Implement display configuration
-    
-    Ok(json!({
-        "success": true,
-        "display_id": display_id
-    }))
+    let mut display_manager = server.display_manager.write().await;
+    match display_manager.configure_display(display_id, config).await {
+        Ok(()) => Ok(json!({
+            "success": true,
+            "display_id": display_id
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
 }
 
 pub async fn start_receiver_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
@@ -144,15 +250,37 @@ pub async fn start_receiver_handler(server: Arc<McpServer>, args: &Value) -> jso
             .filter_map(|v| v.as_str())
             .map(|s| s.to_string())
             .collect::<Vec<_>>()
-        )
-        .unwrap_or_else(|| vec!["upnp".to_string(), "airplay".to_string()]);
+        );
     
     let port = args["port"].as_u64().unwrap_or(8420) as u16;
+
+    let mut config = ReceiverConfig::default();
+    if let Some(name) = args["friendly_name"].as_str() {
+        config.friendly_name = name.to_string();
+    }
     
-    info!("Starting receivers: {:?} on port {}", protocols, port);
+    // Without a discovery section, keep whatever timings are already configured
+    let discovery_config = match args["discovery"] {
+        Value::Null => None,
+        ref discovery => match serde_json::from_value::<DiscoveryConfig>(discovery.clone()) {
+            Ok(discovery_config) => Some(discovery_config),
+            Err(e) => return Err(invalid_params(format!("Invalid discovery config: {}", e))),
+        },
+    };
     
     let mut network_receiver = server.network_receiver.write().await;
-    network_receiver.start(protocols, port).await.unwrap();
+    let protocols = protocols.unwrap_or_else(|| network_receiver.default_protocols().to_vec());
+    info!("Starting receivers: {:?} on port {}", protocols, port);
+    
+    if let Some(discovery_config) = discovery_config {
+        if let Err(e) = network_receiver.set_discovery_config(discovery_config).await {
+            return Err(tool_failed(e));
+        }
+    }
+    // e.g. the port is already taken
+    if let Err(e) = network_receiver.start(protocols, port, config).await {
+        return Err(tool_failed(e));
+    }
     
     Ok(json!({
         "success": true,
@@ -160,54 +288,324 @@ pub async fn start_receiver_handler(server: Arc<McpServer>, args: &Value) -> jso
     }))
 }
 
-pub async fn stop_cast_handler(_server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+pub async fn stop_cast_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
     let display_id = args["display_id"].as_str();
+    let session_id = match &args["session_id"] {
+        Value::Null => None,
+        Value::String(session_id) => Some(session_id.as_str()),
+        _ => return Err(invalid_field("session_id", "session_id must be a string")),
+    };
     
+    // A session on its own stops the display it was cast to; a mirror stream has no display
+    let display_id = display_id.map(str::to_string).or_else(|| {
+        session_id.and_then(cancel::display_of)
+            .filter(|display_id| display_id != crate::server::api::MIRROR_STREAM_DISPLAY)
+    });
+    let display_id = display_id.as_deref();
     info!("Stopping cast on display {:?}", display_id);
     
-    // TODO: Implement stop casting
+    // In-flight rendering or streaming for the session, or for everything on the display
+    let mut cancelled = Vec::new();
+    if let Some(session_id) = session_id {
+        if cancel::cancel_session(session_id) {
+            cancelled.push(session_id.to_string());
+        }
+    } else if let Some(display_id) = display_id {
+        cancelled = cancel::cancel_display(display_id);
+    }
+
+    if let Some(display_id) = display_id {
+        playlist::stop(display_id);
+        live_reload::stop(display_id);
+        if let Err(e) = server.display_manager.read().await.close_window(display_id) {
+            return Err(tool_failed(e));
+        }
+        notify_display_stopped(display_id.to_string(), session_id.map(str::to_string));
+    }
     
     Ok(json!({
         "success": true,
-        "display_id": display_id
+        "display_id": display_id,
+        "cancelled": cancelled
     }))
 }
 
-pub async fn cache_content_handler(_server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
-    let key = args["key"].as_str().unwrap_or("");
-    let source = args["source"].as_str().unwrap_or("");
-    // Note: TTL support is not yet implemented in the cache system
-    // Future implementation should add expiration tracking to CachedContent
+pub async fn cache_content_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let source = args["source"].as_str()
+        .ok_or_else(|| invalid_field("source", "source is required"))?;
+    // The cache names entries by content and keeps them until evicted
+    if !args["key"].is_null() {
+        return Err(invalid_field("key", "The cache picks the key; cache_content returns it"));
+    }
+    if !args["ttl"].is_null() {
+        return Err(invalid_field("ttl", "Cached content stays until evicted; ttl isn't supported"));
+    }
 
-    info!("Caching content: {} -> {}", source, key);
+    info!("Caching content: {}", source);
 
-    // TODO: Implement content caching by fetching source and storing in cache
-    // For now, just return success
+    let key = server.content_cache.read().await.store_source(source).await
+        .map_err(|e| match e {
+            CasterError::InvalidRequest(_) | CasterError::NotFound(_) => invalid_field("source", e),
+            e => tool_failed(e),
+        })?;
 
     Ok(json!({
         "success": true,
-        "key": key,
-        "note": "Cache storage not yet fully implemented; TTL support pending"
+        "key": key
     }))
 }
 
-pub async fn get_cast_status_handler(_server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
-    let display_id = args["display_id"].as_str();
-    
-    // TODO: Implement status retrieval
-    
+pub async fn cache_stats_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let cache = server.content_cache.read().await;
+    let stats = cache.stats();
+
+    if args["reset"].as_bool().unwrap_or(false) {
+        cache.reset_counters();
+    }
+
     Ok(json!({
-        "display_id": display_id,
-        "active": false,
-        "session": null
+        "stats": stats
     }))
 }
 
+pub async fn extract_pdf_text_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let source = args["source"].as_str().unwrap_or("");
+    let page = args["page"].as_u64().map(|p| p as u32);
+
+    info!("Extracting text from PDF {} (page {:?})", source, page);
+
+    let data = match server.content_cache.read().await.read_source(source).await {
+        Ok(data) => data,
+        Err(e) => return Err(tool_failed(e))
+    };
+
+    let pdf = server.render_engine.read().await.pdf_pool();
+    match pdf.extract_text(data, page).await {
+        Ok(text) => Ok(json!({
+            "success": true,
+            "page": page,
+            "text": text
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn capture_screen_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    use base64::Engine as _;
+
+    let monitor = args["display_id"].as_str();
+    let format = args["format"].as_str().unwrap_or("png");
+    let scale = args["scale"].as_f64().unwrap_or(1.0) as f32;
+    let quality = args["quality"].as_u64().unwrap_or(85).min(100) as u8;
+
+    info!("Capturing screen {:?} as {}", monitor, format);
+
+    let captured = server.render_engine.write().await.capture_screen(monitor);
+    match captured.and_then(|image| crate::render::encode_image(image, format, scale, quality)) {
+        Ok(encoded) => Ok(json!({
+            "success": true,
+            "mime_type": encoded.mime_type,
+            "width": encoded.width,
+            "height": encoded.height,
+            "data": base64::engine::general_purpose::STANDARD.encode(encoded.data)
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn list_monitors_handler(_server: Arc<McpServer>, _args: &Value) -> jsonrpc_core::Result<Value> {
+    match crate::render::ScreenMirror::list_monitors() {
+        Ok(monitors) => Ok(json!({"success": true, "monitors": monitors})),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+/// Samples per second decoded for waveforms; plenty for an overview image
+const WAVEFORM_SAMPLE_RATE: u32 = 8000;
+
+/// Enough for everything up to 11 kHz, which covers speech and most of music's energy
+const SPECTROGRAM_SAMPLE_RATE: u32 = 22050;
+
+pub async fn render_content_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    use base64::Engine as _;
+
+    let content_type = args["content_type"].as_str().unwrap_or("");
+    let source = args["source"].as_str().unwrap_or("");
+    let options = &args["options"];
+    let width = args["width"].as_u64().map(|w| w as u32);
+    let height = args["height"].as_u64().map(|h| h as u32);
+
+    info!("Rendering {} from {}", content_type, source);
+
+    let data = match server.content_cache.read().await.read_source(source).await {
+        Ok(data) => data,
+        Err(e) => return Err(tool_failed(e))
+    };
+
+    let rendered = match content_type {
+        // The first page, laid out as it would be cast to a device that only shows images
+        "markdown" => {
+            let markdown = String::from_utf8_lossy(&data).into_owned();
+            let theme = options["theme"].as_str().map(str::to_string);
+            let engine = Arc::clone(&server.render_engine);
+            tokio::task::spawn_blocking(move || {
+                engine.blocking_read().render_markdown_to_image(&markdown, theme.as_deref(), width.unwrap_or(1920), height.unwrap_or(1080))
+            }).await
+                .map_err(|e| CasterError::Render(format!("Markdown render task failed: {}", e)))
+                .and_then(|image| image)
+        }
+        "pdf" => {
+            let page = options["page"].as_u64().unwrap_or(1) as u32;
+            let pdf = server.render_engine.read().await.pdf_pool();
+            pdf.render_page(data, page).await
+                .map(|image| crate::render::fit_within(image, width, height))
+        }
+        "presentation" => match SlideFormat::resolve(options["format"].as_str().unwrap_or("auto"), &data) {
+            // The whole deck as one page, a slide at a time
+            Ok(SlideFormat::Markdown) => {
+                let markdown = String::from_utf8_lossy(&data);
+                return match server.render_engine.read().await.render_presentation(&markdown, options["theme"].as_str(), options["math"].as_bool().unwrap_or(false)) {
+                    Ok(html) => Ok(json!({
+                        "success": true,
+                        "content_type": content_type,
+                        "mime_type": "text/html",
+                        "html": html
+                    })),
+                    Err(e) => Err(tool_failed(e)),
+                };
+            }
+            // A slide of an exported deck is a page of the PDF
+            Ok(SlideFormat::Pdf) => {
+                let page = options["page"].as_u64().unwrap_or(1) as u32;
+                let pdf = server.render_engine.read().await.pdf_pool();
+                pdf.render_page(data, page).await
+                    .map(|image| crate::render::fit_within(image, width, height))
+            }
+            Err(e) => return Err(invalid_params(e)),
+        },
+        "image" => image::load_from_memory(&data)
+            .map(|image| crate::render::fit_within(image, width, height))
+            .map_err(|e| CasterError::Render(format!("Failed to decode image: {}", e))),
+        "waveform" => {
+            let decoded = tokio::task::spawn_blocking(move || crate::media::decode_audio_samples(&data, WAVEFORM_SAMPLE_RATE)).await
+                .map_err(|e| CasterError::Media(format!("Audio decode task failed: {}", e)))
+                .and_then(|samples| samples);
+            match decoded {
+                Ok(samples) => server.render_engine.read().await
+                    .render_audio_waveform(&samples, WAVEFORM_SAMPLE_RATE, width.unwrap_or(800), height.unwrap_or(200)),
+                Err(e) => Err(e),
+            }
+        }
+        "spectrogram" => {
+            let scale = crate::render::SpectrogramScale {
+                log_frequency: options["log_frequency"].as_bool().unwrap_or(true),
+                decibels: options["decibels"].as_bool().unwrap_or(true),
+                ..Default::default()
+            };
+            let window_size = options["window_size"].as_u64().unwrap_or(1024).clamp(64, 16384) as usize;
+            let overlap = options["overlap"].as_f64().unwrap_or(0.75) as f32;
+            let decoded = tokio::task::spawn_blocking(move || crate::media::decode_audio_samples(&data, SPECTROGRAM_SAMPLE_RATE)).await
+                .map_err(|e| CasterError::Media(format!("Audio decode task failed: {}", e)))
+                .and_then(|samples| samples);
+            match decoded {
+                Ok(samples) => server.render_engine.read().await.render_audio_spectrogram(
+                    &samples,
+                    SPECTROGRAM_SAMPLE_RATE,
+                    width.unwrap_or(800),
+                    height.unwrap_or(300),
+                    window_size,
+                    overlap,
+                    scale,
+                ),
+                Err(e) => Err(e),
+            }
+        }
+        other => Err(CasterError::Render(format!("Can't render {} content", other))),
+    };
+
+    match rendered.and_then(|image| crate::render::encode_png(&image).map(|png| (image, png))) {
+        Ok((image, png)) => Ok(json!({
+            "success": true,
+            "content_type": content_type,
+            "mime_type": "image/png",
+            "width": image.width(),
+            "height": image.height(),
+            "data": base64::engine::general_purpose::STANDARD.encode(png)
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn cast_playlist_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let items: Vec<PlaylistItem> = match serde_json::from_value(args["items"].clone()) {
+        Ok(items) => items,
+        Err(e) => return Err(invalid_params(format!("Invalid items: {}", e))),
+    };
+    let looping = args["loop"].as_bool().unwrap_or(false);
+
+    let displays = server.display_manager.read().await.list_displays().await.unwrap_or_default();
+    let display = match args["display_id"].as_str() {
+        Some(id) => displays.iter().find(|d| d.id == id),
+        None => displays.iter().find(|d| d.is_primary).or(displays.first()),
+    };
+    let Some(display) = display.cloned() else {
+        return Err(tool_failed("No display to cast to"));
+    };
+    let display_id = display.id.clone();
+
+    info!("Casting a playlist of {} items to display {}", items.len(), display_id);
+
+    let context = playlist_context(&server);
+    match playlist::start(context, display, items, looping).await {
+        Ok(playlist) => Ok(json!({
+            "success": true,
+            "display_id": display_id,
+            "playlist": playlist
+        })),
+        Err(e) => Err(tool_failed(e)),
+    }
+}
+
+pub async fn get_cast_status_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let display_id = args["display_id"].as_str();
+    let playlist = display_id.and_then(playlist::status);
+
+    // Only local playback is tracked; it shows on at most one display
+    let playback = server.media_engine.read().await.playback_position()
+        .filter(|playback| display_id.is_none() || playback.display_id.as_deref() == display_id);
+
+    match playback {
+        Some(playback) => Ok(json!({
+            "display_id": playback.display_id,
+            "active": true,
+            "session": playback.session_id,
+            "position": playback.position,
+            "duration": playback.duration,
+            "seekable": playback.seekable,
+            "playlist": playlist
+        })),
+        None => Ok(json!({
+            "display_id": display_id,
+            "active": playlist.is_some(),
+            "session": null,
+            "playlist": playlist
+        })),
+    }
+}
+
 pub async fn discover_chromecasts_handler(server: Arc<McpServer>, _args: &Value) -> jsonrpc_core::Result<Value> {
     info!("Discovering Chromecast devices...");
     
     let mut network_receiver = server.network_receiver.write().await;
-    let devices = network_receiver.discover_chromecasts().await.unwrap_or_default();
+    let devices: Vec<Value> = network_receiver.discover_chromecasts().await
+        .unwrap_or_default()
+        .iter()
+        .map(|device| {
+            let mut json = device.to_json();
+            json["connected"] = json!(network_receiver.is_chromecast_connected(&device.id));
+            json
+        })
+        .collect();
     
     Ok(json!({
         "devices": devices
@@ -220,15 +618,19 @@ pub async fn connect_chromecast_handler(server: Arc<McpServer>, args: &Value) ->
     info!("Connecting to Chromecast: {}", device_name);
     
     let mut network_receiver = server.network_receiver.write().await;
+    if !args["retry"].is_null() {
+        match serde_json::from_value::<RetryPolicy>(args["retry"].clone()) {
+            Ok(policy) => network_receiver.set_chromecast_retry_policy(policy),
+            Err(e) => return Err(invalid_params(format!("Invalid retry policy: {}", e))),
+        }
+    }
+
     match network_receiver.connect_chromecast(device_name).await {
         Ok(_) => Ok(json!({
             "success": true,
             "device_name": device_name
         })),
-        Err(e) => Ok(json!({
-            "success": false,
-            "error": e.to_string()
-        }))
+        Err(e) => Err(tool_failed(e))
     }
 }
 
@@ -239,77 +641,267 @@ pub async fn cast_to_chromecast_handler(server: Arc<McpServer>, args: &Value) ->
     let options = &args["options"];
     
     info!("Casting to Chromecast {} - type: {}", device_name, content_type);
+
+    if content_type == "markdown" {
+        return cast_markdown_to_chromecast(&server, device_name, source, options).await;
+    }
     
-    let content_type = match content_type {
-        "video" => ContentType::Video { 
-            codec: options["codec"].as_str().unwrap_or("auto").to_string(),
-            container: options["container"].as_str().unwrap_or("auto").to_string()
-        },
-        "image" => ContentType::Image { 
-            format: options["format"].as_str().unwrap_or("auto").to_string()
-        },
-        "stream" => {
-            let protocol = options["protocol"].as_str().unwrap_or("hls");
-            match protocol {
-                "hls" => ContentType::Stream { 
-                    protocol: StreamProtocol::Hls { manifest_url: source.to_string() }
-                },
-                "dash" => ContentType::Stream { 
-                    protocol: StreamProtocol::Dash { manifest_url: source.to_string() }
-                },
-                _ => return Ok(json!({"error": format!("Unsupported stream protocol for Chromecast: {}", protocol)}))
-            }
-        },
-        _ => return Ok(json!({"error": format!("Unsupported content type for Chromecast: {}", content_type)}))
-    };
-    
-    let content_source = if source.starts_with("http://") || source.starts_with("https://") {
-        ContentSource::Url { url: source.to_string() }
+    let (content_type, content_source) = groups::parse_device_content(args).map_err(content_error)?;
+
+    let subtitles: Vec<SubtitleTrack> = if args["subtitles"].is_null() {
+        Vec::new()
     } else {
-        ContentSource::File { path: source.to_string() }
+        match serde_json::from_value(args["subtitles"].clone()) {
+            Ok(subtitles) => subtitles,
+            Err(e) => return Err(invalid_params(format!("Invalid subtitles: {}", e))),
+        }
     };
-    
+
     let mut network_receiver = server.network_receiver.write().await;
-    match network_receiver.cast_to_chromecast(device_name, &content_type, &content_source).await {
+    match network_receiver.cast_to_chromecast(device_name, &content_type, &content_source, &subtitles).await {
         Ok(_) => Ok(json!({
             "success": true,
             "device_name": device_name,
             "content_type": content_type,
             "source": content_source
         })),
-        Err(e) => Ok(json!({
-            "success": false,
-            "error": e.to_string()
-        }))
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+/// Chromecasts can't show markdown, so render it to PNG pages and queue them as
+/// a slideshow, stepped with the queue's next/previous
+async fn cast_markdown_to_chromecast(server: &McpServer, device_name: &str, source: &str, options: &Value) -> jsonrpc_core::Result<Value> {
+    let markdown = match server.content_cache.read().await.read_source(source).await {
+        Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+        Err(e) => return Err(tool_failed(e)),
+    };
+    let theme = options["theme"].as_str().map(str::to_string);
+    let width = options["width"].as_u64().unwrap_or(1920) as u32;
+    let height = options["height"].as_u64().unwrap_or(1080) as u32;
+
+    let pages = tokio::task::spawn_blocking(move || crate::render::write_markdown_pages(&markdown, theme.as_deref(), width, height)).await
+        .map_err(|e| CasterError::Render(format!("Markdown render task failed: {}", e)))
+        .and_then(|pages| pages);
+    let pages = match pages {
+        Ok(pages) => pages,
+        Err(e) => return Err(tool_failed(e)),
+    };
+
+    let items = pages.iter()
+        .map(|path| (
+            ContentType::Image { format: "png".to_string() },
+            ContentSource::File { path: path.to_string_lossy().into_owned() },
+        ))
+        .collect();
+    match server.network_receiver.write().await.play_chromecast_queue(device_name, items).await {
+        Ok(count) => Ok(json!({
+            "success": true,
+            "device_name": device_name,
+            "pages": count
+        })),
+        Err(e) => Err(tool_failed(e)),
     }
 }
 
 pub async fn control_chromecast_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
     let device_name = args["device_name"].as_str().unwrap_or("");
     let action = args["action"].as_str().unwrap_or("");
-    let _value = args["value"].as_f64();
+    let value = args["value"].as_f64();
 
     info!("Controlling Chromecast {} - action: {}", device_name, action);
 
-    match action {
+    let result = match action {
         "stop" => {
             let mut network_receiver = server.network_receiver.write().await;
-            match network_receiver.stop_chromecast(device_name).await {
-                Ok(_) => Ok(json!({"success": true})),
-                Err(e) => Ok(json!({"success": false, "error": e.to_string()}))
+            network_receiver.stop_chromecast(device_name).await
+        },
+        "play" => server.network_receiver.read().await.play_chromecast(device_name).await,
+        "pause" => server.network_receiver.read().await.pause_chromecast(device_name).await,
+        "seek" | "volume" => {
+            let Some(value) = value else {
+                return Err(invalid_params(format!("Action {} requires a value", action)));
+            };
+
+            let network_receiver = server.network_receiver.read().await;
+            if action == "seek" {
+                network_receiver.seek_chromecast(device_name, value as f32).await
+            } else {
+                network_receiver.set_chromecast_volume(device_name, value as f32).await
             }
         },
-        "play" | "pause" | "seek" | "volume" => {
-            // TODO: Implement playback controls
-            Ok(json!({
-                "success": false,
-                "error": "Playback controls not yet implemented"
-            }))
+        _ => return Err(invalid_params(format!("Unknown action: {}", action)))
+    };
+
+    match result {
+        Ok(_) => Ok(json!({"success": true, "action": action})),
+        Err(CasterError::NotCasting(e)) => Err(not_casting(e, device_name)),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+/// Parse `chromecast_queue` items: `{content_type, source, options}` like `cast_to_chromecast`
+fn parse_queue_items(items: &Value) -> jsonrpc_core::Result<Vec<(ContentType, ContentSource)>> {
+    let Some(items) = items.as_array() else {
+        return Err(invalid_field("items", "items must be an array"));
+    };
+
+    items.iter().enumerate()
+        .map(|(index, item)| groups::parse_device_content(item)
+            .map_err(|e| invalid_field(&format!("items[{}].{}", index, e.field), format!("Item {}: {}", index, e.message))))
+        .collect()
+}
+
+pub async fn chromecast_queue_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_name = args["device_name"].as_str().unwrap_or("");
+    let action = args["action"].as_str().unwrap_or("");
+
+    info!("Chromecast queue on {} - action: {}", device_name, action);
+
+    let mut network_receiver = server.network_receiver.write().await;
+    let result = match action {
+        "enqueue" => {
+            let items = parse_queue_items(&args["items"])?;
+            network_receiver.enqueue_chromecast(device_name, items).await
+                .map(|length| json!({"success": true, "queue_length": length}))
         },
-        _ => Ok(json!({
-            "success": false,
-            "error": format!("Unknown action: {}", action)
-        }))
+        "next" => network_receiver.chromecast_queue_next(device_name).await
+            .map(|position| json!({"success": true, "position": position})),
+        "prev" => network_receiver.chromecast_queue_prev(device_name).await
+            .map(|position| json!({"success": true, "position": position})),
+        "clear" => network_receiver.clear_chromecast_queue(device_name).await
+            .map(|_| json!({"success": true})),
+        _ => return Err(invalid_params(format!("Unknown action: {}", action)))
+    };
+
+    match result {
+        Ok(response) => Ok(response),
+        Err(CasterError::NotCasting(e)) => Err(not_casting(e, device_name)),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn discover_airplay_handler(server: Arc<McpServer>, _args: &Value) -> jsonrpc_core::Result<Value> {
+    info!("Discovering AirPlay receivers...");
+
+    let mut network_receiver = server.network_receiver.write().await;
+    let devices: Vec<Value> = network_receiver.discover_airplay().await
+        .unwrap_or_default()
+        .iter()
+        .map(|device| {
+            let mut json = device.to_json();
+            json["casting"] = json!(network_receiver.is_airplay_casting(&device.id));
+            json
+        })
+        .collect();
+
+    Ok(json!({
+        "devices": devices
+    }))
+}
+
+pub async fn cast_to_airplay_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_name = args["device_name"].as_str().unwrap_or("");
+
+    info!("Casting to AirPlay receiver {} - type: {}", device_name, args["content_type"].as_str().unwrap_or(""));
+
+    let (content_type, content_source) = groups::parse_content(args).map_err(content_error)?;
+
+    let mut network_receiver = server.network_receiver.write().await;
+    match network_receiver.cast_to_airplay(device_name, &content_type, &content_source).await {
+        Ok(_) => Ok(json!({
+            "success": true,
+            "device_name": device_name,
+            "content_type": content_type,
+            "source": content_source
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn stop_airplay_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_name = args["device_name"].as_str().unwrap_or("");
+
+    let mut network_receiver = server.network_receiver.write().await;
+    match network_receiver.stop_airplay(device_name).await {
+        Ok(_) => Ok(json!({
+            "success": true,
+            "device_name": device_name
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn airplay_status_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_name = args["device_name"].as_str().unwrap_or("");
+
+    let network_receiver = server.network_receiver.read().await;
+    match network_receiver.get_airplay_status(device_name).await {
+        Ok(status) => Ok(json!({
+            "success": true,
+            "status": status
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn cast_to_dlna_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_id = args["device_id"].as_str().unwrap_or("");
+
+    info!("Casting to DLNA renderer {} - type: {}", device_id, args["content_type"].as_str().unwrap_or(""));
+
+    let (content_type, content_source) = groups::parse_content(args).map_err(content_error)?;
+
+    let mut network_receiver = server.network_receiver.write().await;
+    match network_receiver.cast_to_dlna(device_id, &content_type, &content_source).await {
+        Ok(_) => Ok(json!({
+            "success": true,
+            "device_id": device_id,
+            "content_type": content_type,
+            "source": content_source
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn cast_group_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let targets: Vec<CastTarget> = match serde_json::from_value(args["targets"].clone()) {
+        Ok(targets) => targets,
+        Err(e) => return Err(invalid_params(format!("Invalid targets: {}", e))),
+    };
+    let (content_type, content_source) = groups::parse_content(args).map_err(content_error)?;
+
+    info!("Casting to group of {} targets", targets.len());
+
+    let (group_id, results) = groups::start_group(
+        &playlist_context(&server),
+        &server.network_receiver,
+        targets,
+        content_type,
+        content_source,
+    ).await;
+
+    Ok(json!({
+        "success": results.iter().any(|result| result["success"] == true),
+        "group_id": group_id,
+        "targets": results
+    }))
+}
+
+pub async fn stop_cast_group_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let Some(group_id) = args["group_id"].as_str().and_then(|id| uuid::Uuid::parse_str(id).ok()) else {
+        return Err(invalid_params("Invalid group_id"));
+    };
+
+    info!("Stopping cast group {}", group_id);
+
+    match groups::stop_group(&playlist_context(&server), &server.network_receiver, group_id).await {
+        Ok(results) => Ok(json!({
+            "success": true,
+            "group_id": group_id,
+            "targets": results
+        })),
+        Err(e) => Err(tool_failed(e))
     }
 }
 
@@ -321,48 +913,49 @@ pub async fn discover_devices_handler(server: Arc<McpServer>, args: &Value) -> j
     // Parse optional device type filter
     let device_type_filter = args["device_type"].as_str();
 
-    let network_receiver = server.network_receiver.read().await;
-
-    let devices = if let Some(type_str) = device_type_filter {
-        let device_type = match type_str {
-            "chromecast" => DeviceType::Chromecast,
-            "firetv" | "fire_tv" => DeviceType::FireTv,
-            "airplay" => DeviceType::AirPlay,
-            "dlna" => DeviceType::Dlna,
-            "upnp" => DeviceType::Upnp,
-            "miracast" => DeviceType::Miracast,
-            _ => DeviceType::Custom(type_str.to_string()),
+    if !args["discovery"].is_null() {
+        let discovery_config = match serde_json::from_value::<DiscoveryConfig>(args["discovery"].clone()) {
+            Ok(discovery_config) => discovery_config,
+            Err(e) => return Err(invalid_params(format!("Invalid discovery config: {}", e))),
         };
-        network_receiver.get_discovered_devices_by_type(&device_type)
+        if let Err(e) = server.network_receiver.write().await.set_discovery_config(discovery_config).await {
+            return Err(tool_failed(e));
+        }
+    }
+
+    let filter = match serde_json::from_value::<DeviceFilter>(args.clone()) {
+        Ok(filter) => filter,
+        Err(_) if args.is_null() => DeviceFilter::default(),
+        Err(e) => return Err(invalid_params(format!("Invalid filter: {}", e))),
+    };
+
+    let device_types = device_type_filter
+        .map(|type_str| vec![DeviceType::from_name(type_str)])
+        .unwrap_or_default();
+    let timeout = std::time::Duration::from_secs_f64(args["timeout"].as_f64().unwrap_or(10.0).clamp(1.0, 60.0));
+
+    // Later pages come from what the first page's scan found, so paging doesn't rescan
+    let devices = if filter.offset > 0 {
+        let network_receiver = server.network_receiver.read().await;
+        match device_type_filter {
+            Some(type_str) => network_receiver.get_discovered_devices_by_type(&DeviceType::from_name(type_str)),
+            None => network_receiver.get_discovered_devices(),
+        }
     } else {
-        network_receiver.get_discovered_devices()
+        match server.network_receiver.write().await.discover_once(device_types, timeout).await {
+            Ok(devices) => devices,
+            Err(e) => return Err(tool_failed(e)),
+        }
     };
 
-    let devices_json: Vec<Value> = devices.iter().map(|device| {
-        json!({
-            "id": device.id,
-            "name": device.name,
-            "device_type": device.device_type,
-            "ip": device.ip.to_string(),
-            "port": device.port,
-            "capabilities": {
-                "can_video": device.capabilities.can_video,
-                "can_audio": device.capabilities.can_audio,
-                "can_image": device.capabilities.can_image,
-                "can_mirror": device.capabilities.can_mirror,
-                "supported_codecs": device.capabilities.supported_codecs,
-                "max_resolution": device.capabilities.max_resolution,
-                "protocols": device.capabilities.protocols,
-            },
-            "discovered_at": device.discovered_at.to_rfc3339(),
-            "last_seen": device.last_seen.to_rfc3339(),
-            "metadata": device.metadata,
-        })
-    }).collect();
+    let (total, devices) = filter.apply(devices);
+    let devices_json: Vec<Value> = devices.iter().map(|device| device.to_json()).collect();
 
     Ok(json!({
         "success": true,
         "count": devices.len(),
+        "total": total,
+        "offset": filter.offset,
         "devices": devices_json
     }))
 }
@@ -377,31 +970,113 @@ pub async fn get_device_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc
     if let Some(device) = network_receiver.get_discovered_device(device_id) {
         Ok(json!({
             "success": true,
-            "device": {
-                "id": device.id,
-                "name": device.name,
-                "device_type": device.device_type,
-                "ip": device.ip.to_string(),
-                "port": device.port,
-                "capabilities": {
-                    "can_video": device.capabilities.can_video,
-                    "can_audio": device.capabilities.can_audio,
-                    "can_image": device.capabilities.can_image,
-                    "can_mirror": device.capabilities.can_mirror,
-                    "supported_codecs": device.capabilities.supported_codecs,
-                    "max_resolution": device.capabilities.max_resolution,
-                    "protocols": device.capabilities.protocols,
-                },
-                "discovered_at": device.discovered_at.to_rfc3339(),
-                "last_seen": device.last_seen.to_rfc3339(),
-                "metadata": device.metadata,
-            }
+            "device": device.to_json()
         }))
     } else {
-        Ok(json!({
-            "success": false,
-            "error": format!("Device not found: {}", device_id)
-        }))
+        Err(tool_failed(format!("Device not found: {}", device_id)))
+    }
+}
+
+pub async fn cast_to_device_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_id = args["device_id"].as_str().unwrap_or("");
+    let content_type = args["content_type"].as_str().unwrap_or("");
+
+    info!("Casting to device {} - type: {}", device_id, content_type);
+
+    let (content_type, content_source) = groups::parse_device_content(args).map_err(content_error)?;
+
+    let mut network_receiver = server.network_receiver.write().await;
+    match network_receiver.cast_to_device(device_id, &content_type, &content_source).await {
+        Ok(_) => Ok(json!({
+            "success": true,
+            "device_id": device_id,
+            "content_type": content_type,
+            "source": content_source
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn launch_dial_app_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_id = args["device_id"].as_str().unwrap_or("");
+    let app_name = args["app_name"].as_str().unwrap_or("");
+    let payload = args["payload"].as_str();
+
+    info!("Launching DIAL app {} on {}", app_name, device_id);
+
+    let network_receiver = server.network_receiver.read().await;
+    match network_receiver.launch_dial_app(device_id, app_name, payload).await {
+        Ok(launch @ DialLaunch::Launched { .. }) => Ok(launch.to_json(device_id, app_name)),
+        // The device's answer goes in `data`, so clients can tell a refusal from a missing app
+        Ok(rejected) => Err(jsonrpc_core::Error {
+            data: Some(rejected.to_json(device_id, app_name)),
+            ..tool_failed(format!("{} turned down launching {}", device_id, app_name))
+        }),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn roku_launch_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_id = args["device_id"].as_str().unwrap_or("");
+    let app_id = args["app_id"].as_str().unwrap_or("");
+
+    // Deep-link values go out as query parameters, so numbers and flags are sent as text
+    let params: Vec<(String, String)> = args["params"].as_object()
+        .map(|params| {
+            params.iter()
+                .map(|(key, value)| {
+                    let value = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+                    (key.clone(), value)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    info!("Launching Roku app {} on {}", app_id, device_id);
+
+    let network_receiver = server.network_receiver.read().await;
+    match network_receiver.launch_roku_app(device_id, app_id, &params).await {
+        Ok(_) => Ok(json!({
+            "success": true,
+            "device_id": device_id,
+            "app_id": app_id
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn roku_keypress_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_id = args["device_id"].as_str().unwrap_or("");
+    let keys: Vec<String> = args["keys"].as_array()
+        .map(|keys| keys.iter().filter_map(|key| key.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+
+    if keys.is_empty() {
+        return Err(invalid_params("No keys given"));
+    }
+
+    let network_receiver = server.network_receiver.read().await;
+    match network_receiver.roku_keypress(device_id, &keys).await {
+        Ok(_) => Ok(json!({
+            "success": true,
+            "device_id": device_id,
+            "keys": keys
+        })),
+        Err(e) => Err(tool_failed(e))
+    }
+}
+
+pub async fn roku_media_status_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let device_id = args["device_id"].as_str().unwrap_or("");
+
+    let network_receiver = server.network_receiver.read().await;
+    match network_receiver.roku_media_status(device_id).await {
+        Ok(status) => Ok(json!({
+            "success": true,
+            "device_id": device_id,
+            "status": status
+        })),
+        Err(e) => Err(tool_failed(e))
     }
 }
 
@@ -415,4 +1090,117 @@ pub async fn discovery_status_handler(server: Arc<McpServer>, _args: &Value) ->
         "discovery_running": is_running,
         "device_count": device_count
     }))
-}
\ No newline at end of file
+}
+/// MCP's error code for a resource URI the server doesn't have
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+pub async fn read_resource_handler(server: Arc<McpServer>, args: &Value) -> jsonrpc_core::Result<Value> {
+    let uri = args["uri"].as_str().unwrap_or("");
+    let internal = |e: CasterError| jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::InternalError,
+        message: e.to_string(),
+        data: None,
+    };
+
+    let contents = match uri {
+        "display://status" => {
+            let displays = server.display_manager.read().await.list_displays().await.map_err(internal)?;
+            json!({"displays": displays})
+        }
+        "cache://list" => {
+            let entries = server.content_cache.read().await.list_entries().await.map_err(internal)?;
+            json!({"entries": entries})
+        }
+        _ => return Err(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(RESOURCE_NOT_FOUND),
+            message: format!("Unknown resource: {}", uri),
+            data: Some(json!({"uri": uri})),
+        }),
+    };
+
+    Ok(json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": "application/json",
+            "text": contents.to_string()
+        }]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tools/call request as a client would send it, and the parsed response
+    async fn call_tool(name: &str, arguments: Value) -> Value {
+        let io = McpServer::new().await.unwrap().io_handler();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": name, "arguments": arguments}
+        });
+        let response = io.handle_request(&request.to_string()).await.expect("a response");
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_failing_tool_is_a_json_rpc_error() {
+        // Hold the port so the receiver can't bind it
+        let taken = std::net::TcpListener::bind("0.0.0.0:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        let response = call_tool("start_receiver", json!({"protocols": ["airplay"], "port": port})).await;
+        assert!(response.get("result").is_none(), "{}", response);
+        assert_eq!(response["error"]["code"], TOOL_FAILED);
+        assert!(response["error"]["message"].as_str().unwrap().contains(&port.to_string()), "{}", response);
+    }
+
+    #[tokio::test]
+    async fn streams_and_3d_models_are_refused_rather_than_reported_as_cast() {
+        for (content_type, source) in [("stream", "rtsp://camera/live"), ("model3d", "scene.gltf")] {
+            let response = call_tool("cast_content", json!({"content_type": content_type, "source": source})).await;
+            assert!(response.get("result").is_none(), "{}", response);
+            assert_eq!(response["error"]["code"], TOOL_FAILED);
+            assert!(response["error"]["message"].as_str().unwrap().contains("not supported"), "{}", response);
+        }
+    }
+
+    #[tokio::test]
+    async fn stopping_a_session_alone_stops_the_display_it_was_cast_to() {
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let token = cancel::register("display_0", &session_id);
+
+        let response = call_tool("stop_cast", json!({"session_id": session_id})).await;
+        assert_eq!(response["result"]["display_id"], "display_0", "{}", response);
+        assert_eq!(response["result"]["cancelled"], json!([session_id]));
+        assert!(token.is_cancelled());
+        assert!(cancel::display_of(&session_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn cast_content_names_the_argument_it_rejects() {
+        for (arguments, field) in [
+            (json!({"content_type": "pdf", "source": "talk.pdf", "options": {"page": 0}}), "options.page"),
+            (json!({"content_type": "image", "source": "cache://"}), "source"),
+            (json!({"content_type": "webassembly", "source": "clock.wasm", "options": {"allow_net": true}}), "options.allow_net"),
+        ] {
+            let response = call_tool("cast_content", arguments).await;
+            assert_eq!(response["error"]["code"], -32602, "{}", response);
+            assert_eq!(response["error"]["data"]["field"], field, "{}", response);
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_content_refuses_what_it_cannot_honour() {
+        for (arguments, field) in [
+            (json!({"source": "https://example.com/a.png", "key": "poster"}), "key"),
+            (json!({"source": "https://example.com/a.png", "ttl": 60}), "ttl"),
+            (json!({"source": "/etc/hostname"}), "source"),
+        ] {
+            let response = call_tool("cache_content", arguments).await;
+            assert!(response.get("result").is_none(), "{}", response);
+            assert_eq!(response["error"]["data"]["field"], field, "{}", response);
+        }
+    }
+}
@@ -7,12 +7,15 @@ use tracing::{info, debug};
 
 use crate::Result;
 use crate::display::DisplayManager;
-use crate::media::MediaEngine;
-use crate::render::RenderEngine;
-use crate::network::NetworkReceiver;
+use crate::media::{spawn_playback_sync, MediaEngine};
+use crate::render::{RenderEngine, WasmLimitsConfig};
+use crate::network::{spawn_media_bridge, NetworkReceiver};
 use crate::cache::ContentCache;
+use crate::server::config::ServerConfig;
+use crate::server::sse::spawn_discovery_bridge;
 
 use super::handlers::*;
+use super::tools::{describe_tool, list_tools};
 
 pub struct McpServer {
     pub display_manager: Arc<RwLock<DisplayManager>>,
@@ -20,20 +23,52 @@ pub struct McpServer {
     pub render_engine: Arc<RwLock<RenderEngine>>,
     pub network_receiver: Arc<RwLock<NetworkReceiver>>,
     pub content_cache: Arc<RwLock<ContentCache>>,
+    pub wasm_limits: WasmLimitsConfig,
 }
 
 impl McpServer {
     pub async fn new() -> Result<Self> {
+        Self::with_config(&ServerConfig::default()).await
+    }
+
+    /// Set up every engine from `config`, as `HttpServer::new` does
+    pub async fn with_config(config: &ServerConfig) -> Result<Self> {
+        // Casting managers publish local files on `/media` from the same directories the cache reads
+        crate::network::media_registry().set_media_dirs(config.cache.media_dirs.clone());
+        let media_engine = Arc::new(RwLock::new(MediaEngine::new()?));
+        let network_receiver = NetworkReceiver::with_config(config.discovery.clone(), config.protocols.clone()).await?;
+        spawn_media_bridge(network_receiver.subscribe_airplay(), Arc::clone(&media_engine));
+        spawn_discovery_bridge(network_receiver.subscribe_discovery());
+        let display_manager = Arc::new(RwLock::new(DisplayManager::new().await?));
+        spawn_playback_sync(Arc::clone(&media_engine), Arc::clone(&display_manager));
+
         Ok(Self {
-            display_manager: Arc::new(RwLock::new(DisplayManager::new().await?)),
-            media_engine: Arc::new(RwLock::new(MediaEngine::new()?)),
+            display_manager,
+            media_engine,
             render_engine: Arc::new(RwLock::new(RenderEngine::new().await?)),
-            network_receiver: Arc::new(RwLock::new(NetworkReceiver::new().await?)),
-            content_cache: Arc::new(RwLock::new(ContentCache::new()?)),
+            network_receiver: Arc::new(RwLock::new(network_receiver)),
+            content_cache: Arc::new(RwLock::new(ContentCache::with_config(&config.cache)?)),
+            wasm_limits: config.wasm.clone(),
         })
     }
 
     pub async fn run(self) -> Result<()> {
+        let io = self.io_handler();
+
+        info!("Starting q8-caster MCP server on stdio");
+        debug!("Server is ready to accept JSON-RPC requests on stdin");
+        
+        // Build and run the server - it will handle stdio until EOF
+        ServerBuilder::new(io)
+            .build()
+            .await;
+        
+        info!("MCP server has shut down");
+        Ok(())
+    }
+
+    /// Every MCP method, answering JSON-RPC requests against this server
+    pub(crate) fn io_handler(self) -> IoHandler {
         let mut io = IoHandler::new();
         
         // Initialize MCP
@@ -63,177 +98,18 @@ impl McpServer {
         // List available tools
         io.add_method("tools/list", |_params: Params| async {
             Ok(json!({
-                "tools": [
-                    {
-                        "name": "cast_content",
-                        "description": "Cast content to a display",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "display_id": {"type": "string", "description": "Target display ID (use list_displays to get IDs)"},
-                                "content_type": {"type": "string", "enum": ["markdown", "video", "image", "model3d", "stream", "presentation"]},
-                                "source": {"type": "string", "description": "File path, URL, or cache key"},
-                                "options": {"type": "object", "description": "Type-specific options"}
-                            },
-                            "required": ["content_type", "source"]
-                        }
-                    },
-                    {
-                        "name": "list_displays",
-                        "description": "List all available displays",
-                        "inputSchema": {"type": "object", "properties": {}}
-                    },
-                    {
-                        "name": "list_codecs",
-                        "description": "List all available codecs",
-                        "inputSchema": {"type": "object", "properties": {}}
-                    },
-                    {
-                        "name": "list_audio_devices",
-                        "description": "List all audio devices",
-                        "inputSchema": {"type": "object", "properties": {}}
-                    },
-                    {
-                        "name": "configure_display",
-                        "description": "Configure display settings",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "display_id": {"type": "string"},
-                                "resolution": {"type": "object", "properties": {"width": {"type": "number"}, "height": {"type": "number"}}},
-                                "position": {"type": "object", "properties": {"x": {"type": "number"}, "y": {"type": "number"}}},
-                                "mirror": {"type": "string", "description": "Display ID to mirror"}
-                            },
-                            "required": ["display_id"]
-                        }
-                    },
-                    {
-                        "name": "start_receiver",
-                        "description": "Start UPnP/AirPlay receiver",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "protocols": {"type": "array", "items": {"type": "string", "enum": ["upnp", "airplay", "chromecast"]}},
-                                "port": {"type": "number"}
-                            }
-                        }
-                    },
-                    {
-                        "name": "stop_cast",
-                        "description": "Stop casting on a display",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "display_id": {"type": "string"}
-                            }
-                        }
-                    },
-                    {
-                        "name": "cache_content",
-                        "description": "Cache content for later use",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "key": {"type": "string"},
-                                "source": {"type": "string"},
-                                "ttl": {"type": "number", "description": "Time to live in seconds"}
-                            },
-                            "required": ["key", "source"]
-                        }
-                    },
-                    {
-                        "name": "get_cast_status",
-                        "description": "Get current casting status",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "display_id": {"type": "string"}
-                            }
-                        }
-                    },
-                    {
-                        "name": "discover_chromecasts",
-                        "description": "Discover available Chromecast devices",
-                        "inputSchema": {"type": "object", "properties": {}}
-                    },
-                    {
-                        "name": "connect_chromecast",
-                        "description": "Connect to a Chromecast device",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "device_name": {"type": "string", "description": "Name of the Chromecast device"}
-                            },
-                            "required": ["device_name"]
-                        }
-                    },
-                    {
-                        "name": "cast_to_chromecast",
-                        "description": "Cast content to a Chromecast device",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "device_name": {"type": "string", "description": "Name of the Chromecast device"},
-                                "content_type": {"type": "string", "enum": ["video", "image", "stream"]},
-                                "source": {"type": "string", "description": "URL or file path of content"},
-                                "options": {"type": "object", "description": "Type-specific options"}
-                            },
-                            "required": ["device_name", "content_type", "source"]
-                        }
-                    },
-                    {
-                        "name": "control_chromecast",
-                        "description": "Control Chromecast playback",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "device_name": {"type": "string"},
-                                "action": {"type": "string", "enum": ["play", "pause", "stop", "seek", "volume"]},
-                                "value": {"type": "number", "description": "Value for seek (seconds) or volume (0-1)"}
-                            },
-                            "required": ["device_name", "action"]
-                        }
-                    },
-                    {
-                        "name": "discover_devices",
-                        "description": "Discover all network devices (Chromecast, FireTV, AirPlay, DLNA, UPnP) via mDNS, Bonjour, and SSDP",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "device_type": {
-                                    "type": "string",
-                                    "enum": ["chromecast", "firetv", "fire_tv", "airplay", "dlna", "upnp", "miracast"],
-                                    "description": "Optional: Filter by device type. If not specified, returns all discovered devices."
-                                }
-                            }
-                        }
-                    },
-                    {
-                        "name": "get_device",
-                        "description": "Get detailed information about a specific discovered device",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {
-                                "device_id": {
-                                    "type": "string",
-                                    "description": "The ID of the device to retrieve"
-                                }
-                            },
-                            "required": ["device_id"]
-                        }
-                    },
-                    {
-                        "name": "discovery_status",
-                        "description": "Get the current status of device discovery",
-                        "inputSchema": {
-                            "type": "object",
-                            "properties": {}
-                        }
-                    }
-                ]
+                "tools": list_tools()
             }))
         });
 
+        // Full definition of one tool, including what it returns
+        io.add_method("tools/describe", |params: Params| async move {
+            let params = params.parse::<Value>().unwrap_or_default();
+            let name = params["name"].as_str().unwrap_or("");
+            describe_tool(name)
+                .ok_or_else(|| jsonrpc_core::Error::invalid_params(format!("Unknown tool: {}", name)))
+        });
+
         // Tool execution
         let server = Arc::new(self);
         let server_clone = Arc::clone(&server);
@@ -254,15 +130,34 @@ impl McpServer {
                     "start_receiver" => start_receiver_handler(server, arguments).await,
                     "stop_cast" => stop_cast_handler(server, arguments).await,
                     "cache_content" => cache_content_handler(server, arguments).await,
+                    "cache_stats" => cache_stats_handler(server, arguments).await,
+                    "extract_pdf_text" => extract_pdf_text_handler(server, arguments).await,
+                    "render_content" => render_content_handler(server, arguments).await,
+                    "capture_screen" => capture_screen_handler(server, arguments).await,
+                    "list_monitors" => list_monitors_handler(server, arguments).await,
                     "get_cast_status" => get_cast_status_handler(server, arguments).await,
+                    "cast_playlist" => cast_playlist_handler(server, arguments).await,
                     "discover_chromecasts" => discover_chromecasts_handler(server, arguments).await,
                     "connect_chromecast" => connect_chromecast_handler(server, arguments).await,
                     "cast_to_chromecast" => cast_to_chromecast_handler(server, arguments).await,
                     "control_chromecast" => control_chromecast_handler(server, arguments).await,
+                    "chromecast_queue" => chromecast_queue_handler(server, arguments).await,
+                    "discover_airplay" => discover_airplay_handler(server, arguments).await,
+                    "cast_to_airplay" => cast_to_airplay_handler(server, arguments).await,
+                    "stop_airplay" => stop_airplay_handler(server, arguments).await,
+                    "airplay_status" => airplay_status_handler(server, arguments).await,
+                    "cast_to_dlna" => cast_to_dlna_handler(server, arguments).await,
+                    "cast_group" => cast_group_handler(server, arguments).await,
+                    "stop_cast_group" => stop_cast_group_handler(server, arguments).await,
                     "discover_devices" => discover_devices_handler(server, arguments).await,
                     "get_device" => get_device_handler(server, arguments).await,
+                    "cast_to_device" => cast_to_device_handler(server, arguments).await,
+                    "launch_dial_app" => launch_dial_app_handler(server, arguments).await,
+                    "roku_launch" => roku_launch_handler(server, arguments).await,
+                    "roku_keypress" => roku_keypress_handler(server, arguments).await,
+                    "roku_media_status" => roku_media_status_handler(server, arguments).await,
                     "discovery_status" => discovery_status_handler(server, arguments).await,
-                    _ => Ok(json!({"error": format!("Unknown tool: {}", tool_name)}))
+                    _ => Err(jsonrpc_core::Error::invalid_params(format!("Unknown tool: {}", tool_name)))
                 }
             }
         });
@@ -287,15 +182,15 @@ impl McpServer {
             }))
         });
 
-        info!("Starting q8-caster MCP server on stdio");
-        debug!("Server is ready to accept JSON-RPC requests on stdin");
-        
-        // Build and run the server - it will handle stdio until EOF
-        ServerBuilder::new(io)
-            .build()
-            .await;
-        
-        info!("MCP server has shut down");
-        Ok(())
+        let server_clone = Arc::clone(&server);
+        io.add_method("resources/read", move |params: Params| {
+            let server = Arc::clone(&server_clone);
+            async move {
+                let params = params.parse::<Value>().unwrap_or_default();
+                read_resource_handler(server, &params).await
+            }
+        });
+
+        io
     }
 }
\ No newline at end of file
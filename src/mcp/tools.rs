@@ -1,2 +1,864 @@
-// MCP tool definitions
-// This module is currently empty but will be populated with tool-specific implementations
\ No newline at end of file
+// MCP tool definitions: what each tool takes, for tools/list, and what it
+// returns, for clients that validate responses
+use serde_json::{json, Value};
+
+/// Every tool with its input and output schemas, as listed by tools/list
+pub fn list_tools() -> Vec<Value> {
+    let tools = input_definitions().as_array().cloned().unwrap_or_default();
+
+    tools.into_iter()
+        .map(|mut tool| {
+            let name = tool["name"].as_str().unwrap_or_default().to_string();
+            tool["outputSchema"] = output_schema(&name);
+            tool
+        })
+        .collect()
+}
+
+/// One tool's full definition, or None if there is no such tool
+pub fn describe_tool(name: &str) -> Option<Value> {
+    list_tools().into_iter().find(|tool| tool["name"] == name)
+}
+
+fn input_definitions() -> Value {
+    json!([
+        {
+            "name": "cast_content",
+            "description": "Cast content to a display",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "display_id": {"type": "string", "description": "Target display ID (use list_displays to get IDs)"},
+                    "content_type": {"type": "string", "enum": ["markdown", "video", "audio", "image", "pdf", "stream", "presentation", "screen_mirror", "webassembly"]},
+                    "source": {"type": "string", "description": "File path, URL, or cache://key; not needed for screen_mirror or a webrtc stream"},
+                    "options": {
+                        "type": "object",
+                        "description": "Type-specific options",
+                        "properties": {
+                            "page": {"type": "integer", "minimum": 1, "description": "PDF page"},
+                            "format": {"type": "string", "description": "For a presentation: markdown (slides split at --- lines), pdf, or auto (the default) to tell from the file"},
+                            "protocol": {"type": "string", "enum": ["rtsp", "webrtc", "hls", "dash"]},
+                            "offer": {"type": "string", "description": "SDP offer for a webrtc stream"},
+                            "quality": {"type": "string", "enum": ["low", "medium", "high", "ultra"]},
+                            "entry_point": {"type": "string", "description": "WebAssembly export to run, _start by default"},
+                            "max_memory_bytes": {"type": "integer", "minimum": 1, "description": "WebAssembly memory limit, 64 MiB by default and at most the server's wasm.max_memory_bytes"},
+                            "max_fuel": {"type": "integer", "minimum": 1, "description": "WebAssembly instruction budget, 1e9 by default"},
+                            "timeout_secs": {"type": "integer", "minimum": 1, "description": "WebAssembly wall-clock limit, 30 by default"},
+                            "allow_fs": {"type": "array", "items": {"type": "string"}, "description": "Directories a WebAssembly module may open, each under the server's cache.media_dirs; none by default"},
+                            "allow_net": {"type": "boolean", "description": "Let a WebAssembly module use the network, if the server's wasm.allow_net permits; off by default"}
+                        }
+                    }
+                },
+                "required": ["content_type"]
+            }
+        },
+        {
+            "name": "list_displays",
+            "description": "List all available displays",
+            "inputSchema": {"type": "object", "properties": {}}
+        },
+        {
+            "name": "list_codecs",
+            "description": "List all available codecs",
+            "inputSchema": {"type": "object", "properties": {}}
+        },
+        {
+            "name": "list_audio_devices",
+            "description": "List all audio devices",
+            "inputSchema": {"type": "object", "properties": {}}
+        },
+        {
+            "name": "configure_display",
+            "description": "Configure display settings",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "display_id": {"type": "string"},
+                    "resolution": {"type": "object", "properties": {"width": {"type": "number"}, "height": {"type": "number"}}},
+                    "position": {"type": "object", "properties": {"x": {"type": "number"}, "y": {"type": "number"}}},
+                    "mirror": {"type": "string", "description": "Display ID to mirror"}
+                },
+                "required": ["display_id"]
+            }
+        },
+        {
+            "name": "start_receiver",
+            "description": "Start UPnP/AirPlay receiver",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "protocols": {"type": "array", "items": {"type": "string", "enum": ["upnp", "airplay", "chromecast"]}},
+                    "port": {"type": "number"},
+                    "friendly_name": {"type": "string", "description": "Name shown in cast pickers"},
+                    "discovery": {
+                        "type": "object",
+                        "description": "Discovery timings in seconds; defaults are 300/30/60/5",
+                        "properties": {
+                            "stale_timeout": {"type": "number"},
+                            "cleanup_interval": {"type": "number"},
+                            "ssdp_interval": {"type": "number"},
+                            "ssdp_timeout": {"type": "number"}
+                        }
+                    }
+                }
+            }
+        },
+        {
+            "name": "stop_cast",
+            "description": "Stop casting on a display, cancelling any rendering still in flight",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "display_id": {"type": "string"},
+                    "session_id": {"type": "string", "description": "Cancel only this session's work, as returned by cast_content or the mirror stream's X-Session-Id header. Without display_id, a cast_content session's display is stopped too"}
+                }
+            }
+        },
+        {
+            "name": "cache_content",
+            "description": "Cache content for later use, returning the key to cast it as cache://<key>",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "source": {"type": "string", "description": "An http(s) URL or a file under the media directories"}
+                },
+                "required": ["source"]
+            }
+        },
+        {
+            "name": "cache_stats",
+            "description": "Get content cache statistics including hit/miss counters",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "reset": {"type": "boolean", "description": "Reset the counters after reading them"}
+                }
+            }
+        },
+        {
+            "name": "extract_pdf_text",
+            "description": "Extract the text of a PDF for search or accessibility",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "source": {"type": "string", "description": "File path or cache://key"},
+                    "page": {"type": "number", "description": "1-based page number; omit for all pages"}
+                },
+                "required": ["source"]
+            }
+        },
+        {
+            "name": "render_content",
+            "description": "Render content to an image (or HTML for markdown presentations) and return it, without casting",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "content_type": {
+                        "type": "string",
+                        "enum": ["markdown", "pdf", "presentation", "image", "waveform", "spectrogram"],
+                        "description": "waveform and spectrogram draw an audio file"
+                    },
+                    "source": {"type": "string", "description": "File path, URL or cache://key"},
+                    "width": {"type": "number", "description": "Maximum width in pixels; exact width for audio (default 800) and markdown pages (default 1920)"},
+                    "height": {"type": "number", "description": "Maximum height in pixels; exact height for audio (default 200, 300 for spectrograms) and markdown pages (default 1080)"},
+                    "options": {
+                        "type": "object",
+                        "properties": {
+                            "page": {"type": "number", "description": "1-based PDF page, or slide of a PDF presentation (default 1)"},
+                            "format": {"type": "string", "enum": ["markdown", "pdf", "auto"], "description": "Presentation format (default auto, PDF if the file is one)"},
+                            "theme": {"type": "string", "description": "Markdown theme"},
+                            "math": {"type": "boolean", "description": "Typeset $...$ and $$...$$ in markdown with KaTeX (default false)"},
+                            "window_size": {"type": "number", "description": "Spectrogram FFT window in samples (default 1024)"},
+                            "overlap": {"type": "number", "description": "Spectrogram window overlap, 0 to 0.95 (default 0.75)"},
+                            "log_frequency": {"type": "boolean", "description": "Spectrogram frequency axis in octaves (default true)"},
+                            "decibels": {"type": "boolean", "description": "Spectrogram colors in dB (default true)"}
+                        }
+                    }
+                },
+                "required": ["content_type", "source"]
+            }
+        },
+        {
+            "name": "capture_screen",
+            "description": "Capture one frame of a local monitor as a base64 image",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "display_id": {"type": "string", "description": "Monitor id or name from list_monitors; primary monitor if omitted"},
+                    "format": {"type": "string", "enum": ["png", "jpeg"], "description": "Default png"},
+                    "scale": {"type": "number", "description": "Downscale factor, 0.05-1.0 (default 1.0), e.g. 0.5 to halve a 4K capture"},
+                    "quality": {"type": "number", "description": "JPEG quality 1-100 (default 85)"}
+                }
+            }
+        },
+        {
+            "name": "list_monitors",
+            "description": "List the monitors screen capture can read from",
+            "inputSchema": {"type": "object", "properties": {}}
+        },
+        {
+            "name": "cast_playlist",
+            "description": "Cast a list of content to one display in order, advancing when video or audio ends or after an item's duration",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "display_id": {"type": "string", "description": "Target display ID; the primary display if omitted"},
+                    "items": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "content_type": {"type": "string", "enum": ["markdown", "video", "audio", "image", "pdf", "presentation", "screen_mirror"]},
+                                "source": {"type": "string", "description": "File path or URL"},
+                                "options": {"type": "object", "description": "Type-specific options, as for cast_content"},
+                                "duration_secs": {"type": "integer", "description": "How long static content shows; without it the item waits for next"}
+                            },
+                            "required": ["content_type", "source"]
+                        }
+                    },
+                    "loop": {"type": "boolean", "description": "Start over after the last item"}
+                },
+                "required": ["items"]
+            }
+        },
+        {
+            "name": "get_cast_status",
+            "description": "Get current casting status",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "display_id": {"type": "string"}
+                }
+            }
+        },
+        {
+            "name": "discover_chromecasts",
+            "description": "Discover available Chromecast devices",
+            "inputSchema": {"type": "object", "properties": {}}
+        },
+        {
+            "name": "connect_chromecast",
+            "description": "Connect to a Chromecast device, retrying with backoff while it is unreachable",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_name": {"type": "string", "description": "Device id or friendly name of the Chromecast"},
+                    "retry": {
+                        "type": "object",
+                        "description": "Retry policy for this and later connections",
+                        "properties": {
+                            "max_attempts": {"type": "integer", "minimum": 1},
+                            "initial_backoff_ms": {"type": "integer", "minimum": 0},
+                            "max_backoff_ms": {"type": "integer", "minimum": 0}
+                        }
+                    }
+                },
+                "required": ["device_name"]
+            }
+        },
+        {
+            "name": "cast_to_chromecast",
+            "description": "Cast content to a Chromecast device. Markdown is rendered to image pages and played as a queue; step through it with chromecast_queue next/prev",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_name": {"type": "string", "description": "Device id or friendly name of the Chromecast"},
+                    "content_type": {"type": "string", "enum": ["video", "audio", "image", "stream", "markdown"]},
+                    "source": {"type": "string", "description": "URL or file path of content"},
+                    "options": {"type": "object", "description": "Type-specific options; markdown takes theme and the page width/height (default 1920x1080)"},
+                    "subtitles": {
+                        "type": "array",
+                        "description": "WebVTT subtitle tracks",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "url": {"type": "string", "description": "WebVTT URL or local .vtt file"},
+                                "language": {"type": "string", "description": "BCP 47 language tag, e.g. en-US"},
+                                "label": {"type": "string"},
+                                "default": {"type": "boolean", "description": "Show this track when playback starts"}
+                            },
+                            "required": ["url", "language"]
+                        }
+                    }
+                },
+                "required": ["device_name", "content_type", "source"]
+            }
+        },
+        {
+            "name": "control_chromecast",
+            "description": "Control Chromecast playback",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_name": {"type": "string", "description": "Device id or friendly name of the Chromecast"},
+                    "action": {"type": "string", "enum": ["play", "pause", "stop", "seek", "volume"]},
+                    "value": {"type": "number", "description": "Value for seek (seconds) or volume (0-1)"}
+                },
+                "required": ["device_name", "action"]
+            }
+        },
+        {
+            "name": "chromecast_queue",
+            "description": "Queue items to play one after another on a Chromecast, and move through them",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_name": {"type": "string", "description": "Device id or friendly name of the Chromecast"},
+                    "action": {"type": "string", "enum": ["enqueue", "next", "prev", "clear"]},
+                    "items": {
+                        "type": "array",
+                        "description": "Items to enqueue; local files and URLs can be mixed",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "content_type": {"type": "string", "enum": ["video", "audio", "image", "stream"]},
+                                "source": {"type": "string", "description": "URL or local file path"},
+                                "options": {"type": "object"}
+                            },
+                            "required": ["content_type", "source"]
+                        }
+                    }
+                },
+                "required": ["device_name", "action"]
+            }
+        },
+        {
+            "name": "discover_airplay",
+            "description": "Discover available AirPlay receivers",
+            "inputSchema": {"type": "object", "properties": {}}
+        },
+        {
+            "name": "cast_to_airplay",
+            "description": "Send a video, audio URL or photo to an AirPlay receiver (classic AirPlay, no pairing)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_name": {"type": "string", "description": "Device id or friendly name of the AirPlay receiver"},
+                    "content_type": {"type": "string", "enum": ["video", "audio", "image", "stream"]},
+                    "source": {"type": "string", "description": "URL or file path of content"},
+                    "options": {"type": "object", "description": "Type-specific options"}
+                },
+                "required": ["device_name", "content_type", "source"]
+            }
+        },
+        {
+            "name": "stop_airplay",
+            "description": "Stop playback on an AirPlay receiver",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_name": {"type": "string", "description": "Device id or friendly name of the AirPlay receiver"}
+                },
+                "required": ["device_name"]
+            }
+        },
+        {
+            "name": "airplay_status",
+            "description": "Get playback position and duration from an AirPlay receiver",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_name": {"type": "string", "description": "Device id or friendly name of the AirPlay receiver"}
+                },
+                "required": ["device_name"]
+            }
+        },
+        {
+            "name": "cast_to_dlna",
+            "description": "Push media to a discovered DLNA/UPnP MediaRenderer",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_id": {"type": "string", "description": "Device id from discover_devices"},
+                    "content_type": {"type": "string", "enum": ["video", "audio", "image", "stream"]},
+                    "source": {"type": "string", "description": "URL or local file path"},
+                    "options": {"type": "object", "description": "Type-specific options"}
+                },
+                "required": ["device_id", "content_type", "source"]
+            }
+        },
+        {
+            "name": "cast_group",
+            "description": "Cast the same content to several displays and network devices at once",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "targets": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "kind": {"type": "string", "enum": ["display", "device"]},
+                                "id": {"type": "string", "description": "Display id or discovered device id"}
+                            },
+                            "required": ["kind", "id"]
+                        }
+                    },
+                    "content_type": {"type": "string", "enum": ["markdown", "video", "audio", "image", "pdf", "stream"]},
+                    "source": {"type": "string", "description": "URL or local file path"},
+                    "options": {"type": "object"}
+                },
+                "required": ["targets", "content_type", "source"]
+            }
+        },
+        {
+            "name": "stop_cast_group",
+            "description": "Stop every session started by cast_group",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "group_id": {"type": "string"}
+                },
+                "required": ["group_id"]
+            }
+        },
+        {
+            "name": "discover_devices",
+            "description": "Discover all network devices (Chromecast, FireTV, AirPlay, DLNA, UPnP, Roku) via mDNS, Bonjour, and SSDP",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_type": {
+                        "type": "string",
+                        "enum": ["chromecast", "firetv", "fire_tv", "airplay", "dlna", "upnp", "miracast", "roku"],
+                        "description": "Optional: Filter by device type. If not specified, returns all discovered devices."
+                    },
+                    "timeout": {
+                        "type": "number",
+                        "description": "Seconds to search before returning (1-60, default 10); returns sooner once no new devices appear"
+                    },
+                    "can_video": {
+                        "type": "boolean",
+                        "description": "Optional: Only devices that can (or can't) play video"
+                    },
+                    "can_mirror": {
+                        "type": "boolean",
+                        "description": "Optional: Only devices that can (or can't) mirror a screen"
+                    },
+                    "name_contains": {
+                        "type": "string",
+                        "description": "Optional: Only devices whose name contains this, ignoring case"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Optional: Devices per page, sorted by name. `total` counts every match."
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Optional: Matches to skip (default 0). Above 0, pages through the devices already found instead of searching again."
+                    },
+                    "discovery": {
+                        "type": "object",
+                        "description": "Discovery timings in seconds; defaults are 300/30/60/5",
+                        "properties": {
+                            "stale_timeout": {"type": "number"},
+                            "cleanup_interval": {"type": "number"},
+                            "ssdp_interval": {"type": "number"},
+                            "ssdp_timeout": {"type": "number"}
+                        }
+                    }
+                }
+            }
+        },
+        {
+            "name": "get_device",
+            "description": "Get detailed information about a specific discovered device",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_id": {
+                        "type": "string",
+                        "description": "The ID of the device to retrieve"
+                    }
+                },
+                "required": ["device_id"]
+            }
+        },
+        {
+            "name": "cast_to_device",
+            "description": "Cast content to any discovered device (Chromecast, DLNA, UPnP)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_id": {"type": "string", "description": "The ID of a discovered device"},
+                    "content_type": {"type": "string", "enum": ["video", "audio", "image", "stream"]},
+                    "source": {"type": "string", "description": "URL or file path of content"},
+                    "options": {"type": "object", "description": "Type-specific options"}
+                },
+                "required": ["device_id", "content_type", "source"]
+            }
+        },
+        {
+            "name": "launch_dial_app",
+            "description": "Launch an app (YouTube, Netflix, ...) on a DIAL device such as a Fire TV",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_id": {"type": "string", "description": "The ID of a discovered DIAL device"},
+                    "app_name": {"type": "string", "description": "DIAL app name, e.g. YouTube or Netflix"},
+                    "payload": {"type": "string", "description": "Optional launch parameters passed to the app"}
+                },
+                "required": ["device_id", "app_name"]
+            }
+        },
+        {
+            "name": "roku_launch",
+            "description": "Launch an app on a Roku by channel id, optionally deep-linking into content",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_id": {"type": "string", "description": "The ID of a discovered Roku"},
+                    "app_id": {"type": "string", "description": "Roku channel id, e.g. 12 for Netflix or 837 for YouTube"},
+                    "params": {"type": "object", "description": "Deep-link parameters such as contentId and mediaType"}
+                },
+                "required": ["device_id", "app_id"]
+            }
+        },
+        {
+            "name": "roku_keypress",
+            "description": "Send remote control keys to a Roku",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_id": {"type": "string", "description": "The ID of a discovered Roku"},
+                    "keys": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Keys pressed in order: Home, Play, Select, Up, Down, Left, Right, Back, VolumeUp, ... or Lit_<character>"
+                    }
+                },
+                "required": ["device_id", "keys"]
+            }
+        },
+        {
+            "name": "roku_media_status",
+            "description": "Get what a Roku's media player is playing and where it is",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "device_id": {"type": "string", "description": "The ID of a discovered Roku"}
+                },
+                "required": ["device_id"]
+            }
+        },
+        {
+            "name": "discovery_status",
+            "description": "Get the current status of device discovery",
+            "inputSchema": {
+                "type": "object",
+                "properties": {}
+            }
+        }
+    ])
+}
+
+/// Schema of a tool's result. Most tools answer `{success, error}` plus fields of
+/// their own; failures only carry `error`.
+fn output_schema(name: &str) -> Value {
+    match name {
+        "cast_content" => outcome(json!({
+            "session_id": {"type": "string"},
+            "display_id": {"type": ["string", "null"]},
+            "content_type": {"type": "object"},
+            "source": {"type": "object"}
+        })),
+        "list_displays" => json!({
+            "type": "object",
+            "properties": {
+                "displays": {"type": "array", "items": display()}
+            },
+            "required": ["displays"]
+        }),
+        "list_codecs" => json!({
+            "type": "object",
+            "properties": {
+                "codecs": {"type": "array", "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "mime_type": {"type": "string"},
+                        "hardware_accelerated": {"type": "boolean"},
+                        "encode": {"type": "boolean"},
+                        "decode": {"type": "boolean"}
+                    }
+                }}
+            },
+            "required": ["codecs"]
+        }),
+        "list_audio_devices" => json!({
+            "type": "object",
+            "properties": {
+                "audio_devices": {"type": "array", "items": {
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "string"},
+                        "name": {"type": "string"},
+                        "is_input": {"type": "boolean"},
+                        "is_default": {"type": "boolean"},
+                        "channels": {"type": "integer"},
+                        "sample_rate": {"type": "integer"}
+                    }
+                }}
+            },
+            "required": ["audio_devices"]
+        }),
+        "configure_display" => outcome(json!({
+            "display_id": {"type": "string"}
+        })),
+        "start_receiver" => outcome(json!({
+            "port": {"type": "integer"}
+        })),
+        "stop_cast" => outcome(json!({
+            "display_id": {"type": ["string", "null"]},
+            "cancelled": {"type": "array", "items": {"type": "string"}}
+        })),
+        "cache_content" => outcome(json!({
+            "key": {"type": "string", "description": "Cast it as cache://<key>"}
+        })),
+        "cache_stats" => json!({
+            "type": "object",
+            "properties": {
+                "stats": {
+                    "type": "object",
+                    "properties": {
+                        "memory_items": {"type": "integer"},
+                        "disk_items": {"type": "integer"},
+                        "total_size_bytes": {"type": "integer"},
+                        "max_size_bytes": {"type": "integer"},
+                        "memory_capacity": {"type": "integer", "description": "Items the memory cache holds"},
+                        "hits": {"type": "integer", "description": "memory_hits plus disk_hits"},
+                        "memory_hits": {"type": "integer"},
+                        "disk_hits": {"type": "integer"},
+                        "misses": {"type": "integer"},
+                        "stores": {"type": "integer"},
+                        "evictions": {"type": "integer"},
+                        "bytes_served": {"type": "integer"}
+                    }
+                }
+            },
+            "required": ["stats"]
+        }),
+        "extract_pdf_text" => outcome(json!({
+            "page": {"type": ["integer", "null"]},
+            "text": {"type": "string"}
+        })),
+        "render_content" => outcome(json!({
+            "content_type": {"type": "string"},
+            "mime_type": {"type": "string", "enum": ["image/png", "text/html"]},
+            "width": {"type": "integer"},
+            "height": {"type": "integer"},
+            "data": {"type": "string", "description": "Base64 PNG"},
+            "html": {"type": "string"}
+        })),
+        "capture_screen" => outcome(json!({
+            "mime_type": {"type": "string", "enum": ["image/png", "image/jpeg"]},
+            "width": {"type": "integer"},
+            "height": {"type": "integer"},
+            "data": {"type": "string", "description": "Base64 image"}
+        })),
+        "list_monitors" => outcome(json!({
+            "monitors": {"type": "array", "items": {
+                "type": "object",
+                "properties": {
+                    "id": {"type": "string"},
+                    "name": {"type": "string"},
+                    "width": {"type": "integer"},
+                    "height": {"type": "integer"},
+                    "x": {"type": "integer"},
+                    "y": {"type": "integer"},
+                    "is_primary": {"type": "boolean"}
+                }
+            }}
+        })),
+        "get_cast_status" => json!({
+            "type": "object",
+            "properties": {
+                "display_id": {"type": ["string", "null"]},
+                "active": {"type": "boolean"},
+                "session": {"type": ["string", "null"]},
+                "position": {"type": "number", "description": "Seconds from the start"},
+                "duration": {"type": ["number", "null"], "description": "Seconds; null for live content"},
+                "seekable": {"type": "boolean"},
+                "playlist": playlist_schema()
+            },
+            "required": ["active", "session"]
+        }),
+        "cast_playlist" => outcome(json!({
+            "display_id": {"type": "string"},
+            "playlist": playlist_schema()
+        })),
+        "discover_chromecasts" => devices(json!({"connected": {"type": "boolean"}})),
+        "discover_airplay" => devices(json!({"casting": {"type": "boolean"}})),
+        "connect_chromecast" | "stop_airplay" => outcome(json!({
+            "device_name": {"type": "string"}
+        })),
+        "cast_to_chromecast" | "cast_to_airplay" => outcome(json!({
+            "device_name": {"type": "string"},
+            "content_type": {"type": "object"},
+            "source": {"type": "object"},
+            "pages": {"type": "integer", "description": "Markdown only: pages queued"}
+        })),
+        "control_chromecast" => outcome(json!({
+            "action": {"type": "string"}
+        })),
+        "chromecast_queue" => outcome(json!({
+            "queue_length": {"type": "integer", "description": "After enqueue"},
+            "position": {"type": "integer", "description": "After next or prev"}
+        })),
+        "airplay_status" => outcome(json!({
+            "status": {"type": "object"}
+        })),
+        "cast_to_dlna" | "cast_to_device" => outcome(json!({
+            "device_id": {"type": "string"},
+            "content_type": {"type": "object"},
+            "source": {"type": "object"}
+        })),
+        "cast_group" | "stop_cast_group" => outcome(json!({
+            "group_id": {"type": "string"},
+            "targets": {"type": "array", "items": {"type": "object"}, "description": "One result per target, in request order"}
+        })),
+        "discover_devices" => outcome(json!({
+            "count": {"type": "integer", "description": "Devices on this page"},
+            "total": {"type": "integer", "description": "Devices matching the filters"},
+            "offset": {"type": "integer"},
+            "devices": {"type": "array", "items": device()}
+        })),
+        "get_device" => outcome(json!({
+            "device": device()
+        })),
+        "launch_dial_app" => outcome(json!({
+            "device_id": {"type": "string"},
+            "app_name": {"type": "string"},
+            "instance_url": {"type": ["string", "null"], "description": "Where to stop the app, if the device gave one"}
+        })),
+        "roku_launch" => outcome(json!({
+            "device_id": {"type": "string"},
+            "app_id": {"type": "string"}
+        })),
+        "roku_keypress" => outcome(json!({
+            "device_id": {"type": "string"},
+            "keys": {"type": "array", "items": {"type": "string"}}
+        })),
+        "roku_media_status" => outcome(json!({
+            "device_id": {"type": "string"},
+            "status": {
+                "type": "object",
+                "properties": {
+                    "state": {"type": "string"},
+                    "error": {"type": "boolean"},
+                    "app_id": {"type": ["string", "null"]},
+                    "app_name": {"type": ["string", "null"]},
+                    "position_ms": {"type": ["integer", "null"]},
+                    "duration_ms": {"type": ["integer", "null"]},
+                    "is_live": {"type": "boolean"}
+                }
+            }
+        })),
+        "discovery_status" => outcome(json!({
+            "discovery_running": {"type": "boolean"},
+            "device_count": {"type": "integer"}
+        })),
+        _ => json!({"type": "object"}),
+    }
+}
+
+/// `{success}` plus `properties`; failures come back as JSON-RPC errors instead
+fn outcome(properties: Value) -> Value {
+    let mut schema = json!({
+        "type": "object",
+        "properties": {
+            "success": {"type": "boolean"}
+        },
+        "required": ["success"]
+    });
+    if let (Some(all), Value::Object(extra)) = (schema["properties"].as_object_mut(), properties) {
+        all.extend(extra);
+    }
+    schema
+}
+
+/// `{devices}` where each device has `extra` fields on top of the usual ones
+fn devices(extra: Value) -> Value {
+    let mut item = device();
+    if let (Some(all), Value::Object(extra)) = (item["properties"].as_object_mut(), extra) {
+        all.extend(extra);
+    }
+    json!({
+        "type": "object",
+        "properties": {
+            "devices": {"type": "array", "items": item}
+        },
+        "required": ["devices"]
+    })
+}
+
+/// A discovered device, as `DiscoveredDevice::to_json` writes it
+fn device() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "id": {"type": "string"},
+            "name": {"type": "string"},
+            "device_type": {"description": "e.g. \"chromecast\", or {\"custom\": name}"},
+            "ip": {"type": "string"},
+            "addresses": {"type": "array", "items": {"type": "string"}},
+            "port": {"type": "integer"},
+            "capabilities": {
+                "type": "object",
+                "properties": {
+                    "can_video": {"type": "boolean"},
+                    "can_audio": {"type": "boolean"},
+                    "can_image": {"type": "boolean"},
+                    "can_mirror": {"type": "boolean"},
+                    "supported_codecs": {"type": "array", "items": {"type": "string"}},
+                    "max_resolution": {"type": ["string", "null"]},
+                    "protocols": {"type": "array", "items": {"type": "string"}}
+                }
+            },
+            "discovered_at": {"type": "string", "format": "date-time"},
+            "last_seen": {"type": "string", "format": "date-time"},
+            "metadata": {"type": "object"}
+        },
+        "required": ["id", "name", "device_type", "ip", "port"]
+    })
+}
+
+/// A local display, as `DisplayInfo` serializes
+fn display() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "id": {"type": "string"},
+            "name": {"type": "string"},
+            "resolution": {"type": "object", "properties": {"width": {"type": "integer"}, "height": {"type": "integer"}}},
+            "position": {"type": "object", "properties": {"x": {"type": "integer"}, "y": {"type": "integer"}}},
+            "is_primary": {"type": "boolean"},
+            "refresh_rate": {"type": "number"},
+            "scale_factor": {"type": "number"}
+        }
+    })
+}
+
+/// A playlist as `Playlist` serializes; null when the display has none
+fn playlist_schema() -> Value {
+    json!({
+        "type": ["object", "null"],
+        "properties": {
+            "id": {"type": "string"},
+            "display_id": {"type": "string"},
+            "items": {"type": "array", "items": {
+                "type": "object",
+                "properties": {
+                    "content_type": {"type": "string"},
+                    "source": {"type": "string"},
+                    "options": {},
+                    "duration_secs": {"type": ["integer", "null"]}
+                }
+            }},
+            "current": {"type": "integer", "description": "0-based index of the item showing"},
+            "loop": {"type": "boolean"}
+        }
+    })
+}
@@ -0,0 +1,164 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::Read;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use super::video::accept;
+use crate::{Result, CasterError};
+
+/// Rate the tap resamples to. Visualizers don't need the top octaves, and a
+/// low rate keeps the reader's work small whatever the media is.
+pub const TAP_SAMPLE_RATE: u32 = 11025;
+
+/// Samples kept for the visualizer, a little under 0.2s at the tap rate
+pub const TAP_WINDOW: usize = 2048;
+
+/// Mono samples read from the socket at a time, about 23ms at the tap rate
+const CHUNK_SAMPLES: usize = 256;
+
+/// The window is woken at most this often, however fast chunks arrive
+const WAKE_INTERVAL: Duration = Duration::from_millis(33);
+
+/// The latest stretch of a playing pipeline's audio, for a cast window to
+/// visualize.
+///
+/// Like `VideoFeed`, gst-launch gives us no appsink, so the pipeline's audio
+/// filter tees a downsampled mono copy into `tcpclientsink` on a loopback
+/// socket. That sink syncs to the pipeline clock, so samples arrive as they are
+/// heard and stop arriving while the pipeline is paused. The media engine
+/// also marks the feed paused, so a view can hold still and say so rather than
+/// wait on samples that won't come.
+pub struct AudioFeed {
+    recent: Mutex<VecDeque<f32>>,
+    // Bumped for every chunk, so a view can tell new audio from a pause
+    chunks: AtomicU64,
+    wake: Mutex<Option<Box<dyn Fn() + Send>>>,
+    paused: AtomicBool,
+    ended: AtomicBool,
+}
+
+impl fmt::Debug for AudioFeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioFeed")
+            .field("chunks", &self.chunks())
+            .field("paused", &self.is_paused())
+            .field("ended", &self.is_ended())
+            .finish()
+    }
+}
+
+impl AudioFeed {
+    /// Start listening for samples. Returns the feed and the bin for a
+    /// playbin's `audio-filter`, which passes the audio through unchanged.
+    pub fn listen() -> Result<(Arc<Self>, String)> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .map_err(|e| CasterError::Media(format!("Failed to open an audio tap socket: {}", e)))?;
+        let port = listener.local_addr()
+            .map_err(|e| CasterError::Media(format!("Failed to open an audio tap socket: {}", e)))?
+            .port();
+
+        let feed = Arc::new(Self {
+            recent: Mutex::new(VecDeque::with_capacity(TAP_WINDOW)),
+            chunks: AtomicU64::new(0),
+            wake: Mutex::new(None),
+            paused: AtomicBool::new(false),
+            ended: AtomicBool::new(false),
+        });
+
+        let reader = Arc::clone(&feed);
+        std::thread::Builder::new()
+            .name("audio-tap".into())
+            .spawn(move || {
+                match accept(&listener, || reader.is_ended()) {
+                    Some(stream) => reader.read_samples(stream),
+                    // Video-only media never opens the audio branch
+                    None => debug!("Audio tap never connected its socket"),
+                }
+                reader.close();
+            })
+            .map_err(|e| CasterError::Media(format!("Failed to start audio tap reader: {}", e)))?;
+
+        // The leaky queue drops tap buffers rather than holding up what's heard
+        let filter = format!(
+            "progressreport ! tee name=audiotap ! queue \
+             audiotap. ! queue leaky=downstream max-size-buffers=16 ! audioconvert ! audioresample \
+             ! audio/x-raw,format=F32LE,channels=1,rate={} ! tcpclientsink host=127.0.0.1 port={} sync=true async=false",
+            TAP_SAMPLE_RATE, port
+        );
+        Ok((feed, filter))
+    }
+
+    /// The last `TAP_WINDOW` samples, oldest first; fewer until that many have arrived
+    pub fn recent_samples(&self) -> Vec<f32> {
+        self.recent.lock().unwrap().iter().copied().collect()
+    }
+
+    /// Chunks received so far; unchanged while playback is paused
+    pub fn chunks(&self) -> u64 {
+        self.chunks.load(Ordering::Relaxed)
+    }
+
+    /// Called from the reader thread at most every `WAKE_INTERVAL` while samples
+    /// arrive, and once when the feed ends
+    pub fn set_wake(&self, wake: impl Fn() + Send + 'static) {
+        *self.wake.lock().unwrap() = Some(Box::new(wake));
+    }
+
+    /// True while the playback the feed taps is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Pause or resume the feed along with its playback, waking the view to show it
+    pub(crate) fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+        self.wake();
+    }
+
+    /// True once the pipeline has stopped sending samples
+    pub fn is_ended(&self) -> bool {
+        self.ended.load(Ordering::Relaxed)
+    }
+
+    fn read_samples(&self, mut stream: TcpStream) {
+        let mut chunk = [0u8; CHUNK_SAMPLES * 4];
+        let mut last_wake: Option<Instant> = None;
+
+        // Ends at EOF, when the pipeline exits or is stopped
+        while stream.read_exact(&mut chunk).is_ok() {
+            {
+                let mut recent = self.recent.lock().unwrap();
+                for bytes in chunk.chunks_exact(4) {
+                    if recent.len() == TAP_WINDOW {
+                        recent.pop_front();
+                    }
+                    recent.push_back(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+                }
+            }
+            self.chunks.fetch_add(1, Ordering::Relaxed);
+
+            if last_wake.is_none_or(|at| at.elapsed() >= WAKE_INTERVAL) {
+                last_wake = Some(Instant::now());
+                self.wake();
+            }
+        }
+        debug!("Audio tap ended after {} chunks", self.chunks());
+    }
+
+    /// Mark the feed ended, e.g. when playback stops before the pipeline connected
+    pub(crate) fn close(&self) {
+        self.ended.store(true, Ordering::Relaxed);
+        self.wake();
+    }
+
+    fn wake(&self) {
+        if let Some(ref wake) = *self.wake.lock().unwrap() {
+            wake();
+        }
+    }
+}
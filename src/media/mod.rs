@@ -1,66 +1,1025 @@
-use crate::{Result, CodecInfo, AudioDevice};
+pub mod audio;
+pub mod pipeline;
+pub mod video;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::{Result, CasterError, CodecInfo, AudioDevice, ContentType, DisplayInfo};
+use crate::cache::ContentCache;
+use crate::display::{DisplayManager, DisplayWindow, WindowCommand, WindowNotice};
+use crate::server::sse::{notify_error, notify_progress};
+use self::pipeline::{quote, Pipeline, PipelineExit, Progress};
+use self::audio::AudioFeed;
+use self::video::VideoFeed;
+
+/// HLS segment length for live transcodes, in seconds
+const HLS_TARGET_DURATION: u32 = 4;
+
+/// How often playback position is pushed to the cast window showing it
+const POSITION_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// GStreamer element names that implement a codec, split by software and hardware paths
+struct CodecElements {
+    name: &'static str,
+    mime_type: &'static str,
+    software_encoders: &'static [&'static str],
+    software_decoders: &'static [&'static str],
+    // VA-API (legacy and new va plugin), NVENC/NVDEC, V4L2 stateful and Intel QSV
+    hardware_encoders: &'static [&'static str],
+    hardware_decoders: &'static [&'static str],
+}
+
+const CODECS: &[CodecElements] = &[
+    CodecElements {
+        name: "h264",
+        mime_type: "video/x-h264",
+        software_encoders: &["x264enc", "openh264enc"],
+        software_decoders: &["avdec_h264", "openh264dec"],
+        hardware_encoders: &["vaapih264enc", "vah264enc", "nvh264enc", "v4l2h264enc", "qsvh264enc"],
+        hardware_decoders: &["vaapih264dec", "vah264dec", "nvh264dec", "v4l2h264dec", "qsvh264dec"],
+    },
+    CodecElements {
+        name: "h265",
+        mime_type: "video/x-h265",
+        software_encoders: &["x265enc"],
+        software_decoders: &["avdec_h265"],
+        hardware_encoders: &["vaapih265enc", "vah265enc", "nvh265enc", "v4l2h265enc", "qsvh265enc"],
+        hardware_decoders: &["vaapih265dec", "vah265dec", "nvh265dec", "v4l2h265dec", "qsvh265dec"],
+    },
+    CodecElements {
+        name: "vp9",
+        mime_type: "video/x-vp9",
+        software_encoders: &["vp9enc"],
+        software_decoders: &["vp9dec", "avdec_vp9"],
+        hardware_encoders: &["vaapivp9enc", "vavp9enc", "qsvvp9enc"],
+        hardware_decoders: &["vaapivp9dec", "vavp9dec", "nvvp9dec", "v4l2vp9dec", "qsvvp9dec"],
+    },
+    CodecElements {
+        name: "opus",
+        mime_type: "audio/x-opus",
+        software_encoders: &["opusenc"],
+        software_decoders: &["opusdec"],
+        hardware_encoders: &[],
+        hardware_decoders: &[],
+    },
+    CodecElements {
+        name: "aac",
+        mime_type: "audio/mpeg",
+        software_encoders: &["avenc_aac", "fdkaacenc", "voaacenc"],
+        software_decoders: &["avdec_aac", "faad", "fdkaacdec"],
+        hardware_encoders: &[],
+        hardware_decoders: &[],
+    },
+];
 
 pub struct MediaEngine {
-    // Placeholder for media engine
+    // Element factories GStreamer reports, probed once; None if GStreamer isn't installed
+    gst_elements: OnceLock<Option<HashSet<String>>>,
+    transcode: Option<MediaJob>,
+    playback: Option<MediaJob>,
+}
+
+/// A running pipeline and the session id its progress events carry
+struct MediaJob {
+    session_id: String,
+    pipeline: Pipeline,
+    // Display whose cast window shows this playback's controls
+    display_id: Option<String>,
+    // Latest progressreport update and when it arrived
+    progress: Arc<Mutex<Option<(Progress, Instant)>>>,
+    // Frames for the cast window, when video is drawn there rather than in a sink window
+    video: Option<Arc<VideoFeed>>,
+    // Samples for the cast window's visualizer
+    audio: Option<Arc<AudioFeed>>,
+    paused: bool,
+}
+
+/// What a cast window draws for the media engine's playback
+#[derive(Debug, Clone)]
+pub struct PlaybackFeeds {
+    pub video: Arc<VideoFeed>,
+    pub audio: Arc<AudioFeed>,
+}
+
+impl PlaybackFeeds {
+    /// Hand both feeds to the window
+    pub fn attach(self, window: &DisplayWindow) -> Result<()> {
+        window.send(WindowCommand::AttachVideo(self.video))?;
+        window.send(WindowCommand::AttachAudio(self.audio))
+    }
+
+    fn close(&self) {
+        self.video.close();
+        self.audio.close();
+    }
+}
+
+/// Where local playback has got to
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackPosition {
+    pub session_id: String,
+    pub display_id: Option<String>,
+    /// Seconds from the start
+    pub position: f64,
+    /// None for live streams and stills
+    pub duration: Option<f64>,
+    pub seekable: bool,
+    pub paused: bool,
 }
 
 impl MediaEngine {
     pub fn new() -> Result<Self> {
-        Ok(Self {})
+        Ok(Self {
+            gst_elements: OnceLock::new(),
+            transcode: None,
+            playback: None,
+        })
     }
 
-    pub fn list_codecs(&self) -> Result<Vec<CodecInfo>> {
-        // Return static list of commonly supported codecs
-        Ok(vec![
-            CodecInfo {
-                name: "h264".to_string(),
-                mime_type: "video/x-h264".to_string(),
-                hardware_accelerated: true,
-                encode: false,
-                decode: true,
+    /// Whether GStreamer's tools are installed; probed once, on first use
+    pub fn gstreamer_available(&self) -> bool {
+        self.gst_elements.get_or_init(probe_gst_elements).is_some()
+    }
+
+    /// Transcode `input` into an MP4 at `output` using `target`'s codec.
+    ///
+    /// Runs in the background; progress is broadcast as `CastProgress` events
+    /// under `transcode_session()`, and `stop()` or `cancel` ends it.
+    pub fn transcode(&mut self, input: &str, output: &str, target: CodecInfo, cancel: CancellationToken) -> Result<()> {
+        let (raw_caps, encode) = self.encode_branch(&target)?;
+        let description = format!(
+            "filesrc location={} ! decodebin ! {} ! progressreport update-freq=1 ! {} ! mp4mux ! filesink location={}",
+            quote(input),
+            raw_caps,
+            encode,
+            quote(output)
+        );
+
+        self.start_transcode(&description, "transcoding", cancel)
+    }
+
+    /// Transcode into an HLS playlist plus segments under `output_dir` for live casting.
+    ///
+    /// Returns the playlist path, which exists once the first segment is written.
+    /// Cancelling leaves the segments written so far.
+    pub fn transcode_hls(
+        &mut self,
+        input: &str,
+        output_dir: &str,
+        target: CodecInfo,
+        cancel: CancellationToken,
+    ) -> Result<PathBuf> {
+        let (raw_caps, encode) = self.encode_branch(&target)?;
+
+        let dir = Path::new(output_dir);
+        std::fs::create_dir_all(dir)?;
+        let playlist = dir.join("playlist.m3u8");
+        let segments = dir.join("segment%05d.ts");
+
+        let description = format!(
+            "filesrc location={} ! decodebin ! {} ! progressreport update-freq=1 ! {} ! hlssink2 playlist-location={} location={} target-duration={} max-files=0",
+            quote(input),
+            raw_caps,
+            encode,
+            quote(&playlist.to_string_lossy()),
+            quote(&segments.to_string_lossy()),
+            HLS_TARGET_DURATION
+        );
+
+        self.start_transcode(&description, "transcoding_hls", cancel)?;
+        Ok(playlist)
+    }
+
+    /// Session id of the running transcode, as used in its progress events
+    pub fn transcode_session(&self) -> Option<&str> {
+        self.transcode.as_ref()
+            .filter(|job| job.pipeline.is_running())
+            .map(|job| job.session_id.as_str())
+    }
+
+    /// Play a video or audio URI locally, replacing whatever is playing.
+    /// Video gets a sink window of its own.
+    pub fn play_uri(&mut self, uri: &str) -> Result<()> {
+        self.play_uri_on(uri, None).map(|_| ())
+    }
+
+    /// Like `play_uri`, with the controls shown in the cast window on `display`.
+    ///
+    /// Video is drawn in that window too, and audio visualized there: the
+    /// returned feeds carry the frames and samples and should be handed over
+    /// with `PlaybackFeeds::attach`.
+    pub fn play_uri_on(&mut self, uri: &str, display: Option<&DisplayInfo>) -> Result<Option<PlaybackFeeds>> {
+        let (feeds, sink, audio_filter) = match display {
+            Some(display) => {
+                let (video, sink) = VideoFeed::listen(&display.resolution)?;
+                let (audio, filter) = match AudioFeed::listen() {
+                    Ok(listening) => listening,
+                    Err(e) => {
+                        video.close();
+                        return Err(e);
+                    }
+                };
+                (Some(PlaybackFeeds { video, audio }), format!(" video-sink={}", quote(&sink)), quote(&filter))
+            }
+            // No window to draw in; playbin picks autovideosink
+            None => (None, String::new(), "progressreport".to_string()),
+        };
+
+        // progressreport in both filters so audio-only and video-only media both report
+        let description = format!(
+            "playbin uri={}{} video-filter=progressreport audio-filter={}",
+            quote(uri),
+            sink,
+            audio_filter
+        );
+        if let Err(e) = self.start_playback(&description, None) {
+            if let Some(ref feeds) = feeds {
+                feeds.close();
+            }
+            return Err(e);
+        }
+        if let Some(ref mut job) = self.playback {
+            job.display_id = display.map(|display| display.id.clone());
+            job.video = feeds.as_ref().map(|feeds| Arc::clone(&feeds.video));
+            job.audio = feeds.as_ref().map(|feeds| Arc::clone(&feeds.audio));
+        }
+        Ok(feeds)
+    }
+
+    /// Position of the running playback, extrapolated from the last report
+    pub fn playback_position(&self) -> Option<PlaybackPosition> {
+        let job = self.playback.as_ref().filter(|job| job.pipeline.is_running())?;
+        let reported = *job.progress.lock().unwrap();
+
+        let (position, duration) = match reported {
+            Some((progress, at)) => {
+                // Reports come every few seconds; assume it kept playing in between, unless paused
+                let since = if job.paused { 0.0 } else { at.elapsed().as_secs_f64() };
+                let position = progress.position.unwrap_or(0.0) + since;
+                let position = progress.duration.map_or(position, |duration| position.min(duration));
+                (position, progress.duration)
+            }
+            None => (0.0, None),
+        };
+
+        Some(PlaybackPosition {
+            session_id: job.session_id.clone(),
+            display_id: job.display_id.clone(),
+            position,
+            duration,
+            // Only pipelines on the GStreamer bindings can seek
+            seekable: Pipeline::CONTROLLABLE && duration.is_some(),
+            paused: job.paused,
+        })
+    }
+
+    /// Seek the running playback to `position` seconds
+    pub fn seek(&mut self, position: f64) -> Result<()> {
+        let job = self.running_playback()?;
+        job.pipeline.seek(position)?;
+        // Extrapolate from the new position until the next report
+        let mut progress = job.progress.lock().unwrap();
+        let reported = progress.map(|(reported, _)| reported).unwrap_or_default();
+        *progress = Some((Progress { position: Some(position), ..reported }, Instant::now()));
+        Ok(())
+    }
+
+    /// Pause the running playback; its video and visualizer hold still until resumed
+    pub fn pause(&mut self) -> Result<()> {
+        self.set_paused(true)
+    }
+
+    pub fn resume(&mut self) -> Result<()> {
+        self.set_paused(false)
+    }
+
+    /// Playback volume, 0.0-1.0
+    pub fn set_volume(&mut self, volume: f32) -> Result<()> {
+        self.running_playback()?.pipeline.set_volume(volume.clamp(0.0, 1.0) as f64)
+    }
+
+    fn set_paused(&mut self, paused: bool) -> Result<()> {
+        let job = self.running_playback()?;
+        if job.paused == paused {
+            return Ok(());
+        }
+        match paused {
+            true => job.pipeline.pause()?,
+            false => job.pipeline.resume()?,
+        }
+
+        // Fold the time played so far into the report, so the position stops with playback
+        let mut progress = job.progress.lock().unwrap();
+        if let Some((ref mut reported, ref mut at)) = *progress {
+            if !job.paused {
+                reported.position = Some(reported.position.unwrap_or(0.0) + at.elapsed().as_secs_f64());
+            }
+            *at = Instant::now();
+        }
+        drop(progress);
+
+        job.paused = paused;
+        if let Some(ref feed) = job.audio {
+            feed.set_paused(paused);
+        }
+        Ok(())
+    }
+
+    fn running_playback(&mut self) -> Result<&mut MediaJob> {
+        self.playback
+            .as_mut()
+            .filter(|job| job.pipeline.is_running())
+            .ok_or_else(|| CasterError::NotCasting("Nothing is playing".into()))
+    }
+
+    /// Show a still image (JPEG, PNG, ...) until stopped or replaced
+    pub fn show_image(&mut self, data: &[u8]) -> Result<()> {
+        // gst-launch can't read from memory, so stage the image in a temp file
+        let path = std::env::temp_dir().join(format!("q8-caster-image-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, data)?;
+
+        let description = format!(
+            "filesrc location={} ! decodebin ! imagefreeze ! videoconvert ! autovideosink",
+            quote(&path.to_string_lossy())
+        );
+        self.start_playback(&description, Some(path))
+    }
+
+    /// Cancel any running transcode and stop playback
+    pub fn stop(&mut self) {
+        if let Some(job) = self.transcode.take() {
+            info!("Stopping transcode {}", job.session_id);
+            job.pipeline.stop();
+        }
+        self.stop_playback();
+    }
+
+    pub fn stop_playback(&mut self) {
+        if let Some(job) = self.playback.take() {
+            info!("Stopping playback {}", job.session_id);
+            job.pipeline.stop();
+            if let Some(feed) = job.video {
+                feed.close();
+            }
+            if let Some(feed) = job.audio {
+                feed.close();
+            }
+        }
+    }
+
+    /// `staged` is a temp file the pipeline reads, removed once it exits
+    fn start_playback(&mut self, description: &str, staged: Option<PathBuf>) -> Result<()> {
+        self.stop_playback();
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let exit_id = session_id.clone();
+        let progress = Arc::new(Mutex::new(None));
+        let reported = Arc::clone(&progress);
+        let pipeline = Pipeline::launch(
+            description,
+            move |update: Progress| {
+                if update.position.is_some() {
+                    *reported.lock().unwrap() = Some((update, Instant::now()));
+                }
             },
-            CodecInfo {
-                name: "h265".to_string(),
-                mime_type: "video/x-h265".to_string(),
-                hardware_accelerated: true,
-                encode: false,
-                decode: true,
+            move |exit| {
+                if let PipelineExit::Failed(e) = exit {
+                    debug!("Playback {} ended: {}", exit_id, e);
+                }
+                if let Some(path) = staged {
+                    let _ = std::fs::remove_file(path);
+                }
             },
-            CodecInfo {
-                name: "vp9".to_string(),
-                mime_type: "video/x-vp9".to_string(),
-                hardware_accelerated: false,
-                encode: false,
-                decode: true,
+        )?;
+
+        self.playback = Some(MediaJob {
+            session_id,
+            pipeline,
+            display_id: None,
+            progress,
+            video: None,
+            audio: None,
+            paused: false,
+        });
+        Ok(())
+    }
+
+    fn start_transcode(&mut self, description: &str, stage: &'static str, cancel: CancellationToken) -> Result<()> {
+        if self.transcode_session().is_some() {
+            return Err(CasterError::Media("A transcode is already running".into()));
+        }
+
+        // Transcodes aren't tied to a display
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let progress_id = session_id.clone();
+        let exit_id = session_id.clone();
+
+        let pipeline = Pipeline::launch_until(
+            description,
+            cancel,
+            move |progress: Progress| {
+                if let Some(fraction) = progress.fraction {
+                    notify_progress(progress_id.clone(), String::new(), fraction, stage.to_string());
+                }
             },
-            CodecInfo {
-                name: "opus".to_string(),
-                mime_type: "audio/x-opus".to_string(),
-                hardware_accelerated: false,
-                encode: false,
-                decode: true,
+            move |exit| match exit {
+                PipelineExit::Finished => notify_progress(exit_id, String::new(), 1.0, "complete".to_string()),
+                PipelineExit::Failed(e) => notify_error(format!("Transcode {} failed: {}", exit_id, e)),
+                PipelineExit::Cancelled => notify_progress(exit_id, String::new(), 0.0, "cancelled".to_string()),
             },
-            CodecInfo {
-                name: "aac".to_string(),
-                mime_type: "audio/mpeg".to_string(),
+        )?;
+
+        info!("Started transcode {}", session_id);
+        self.transcode = Some(MediaJob {
+            session_id,
+            pipeline,
+            display_id: None,
+            progress: Arc::new(Mutex::new(None)),
+            video: None,
+            audio: None,
+            paused: false,
+        });
+        Ok(())
+    }
+
+    /// Raw caps to pull from decodebin and the convert/encode chain for a codec,
+    /// preferring a hardware encoder when the target asks for one
+    fn encode_branch(&self, target: &CodecInfo) -> Result<(&'static str, String)> {
+        let Some(elements) = self.gst_elements.get_or_init(probe_gst_elements) else {
+            return Err(CasterError::Media("GStreamer is not installed".into()));
+        };
+
+        let codec = CODECS.iter()
+            .find(|codec| codec.name == target.name)
+            .ok_or_else(|| CasterError::Media(format!("Unsupported target codec: {}", target.name)))?;
+
+        let present = |names: &'static [&'static str]| names.iter().copied().find(|name| elements.contains(*name));
+        let encoder = if target.hardware_accelerated {
+            present(codec.hardware_encoders).or_else(|| present(codec.software_encoders))
+        } else {
+            present(codec.software_encoders).or_else(|| present(codec.hardware_encoders))
+        }
+        .ok_or_else(|| CasterError::Media(format!("No {} encoder available", codec.name)))?;
+
+        if codec.mime_type.starts_with("audio/") {
+            return Ok(("audio/x-raw", format!("audioconvert ! audioresample ! {}", encoder)));
+        }
+
+        // Muxers want parsed streams with codec data
+        let parser = match codec.name {
+            "h264" => " ! h264parse",
+            "h265" => " ! h265parse",
+            _ => "",
+        };
+        Ok(("video/x-raw", format!("videoconvert ! {}{}", encoder, parser)))
+    }
+
+    pub fn list_codecs(&self) -> Result<Vec<CodecInfo>> {
+        let Some(elements) = self.gst_elements.get_or_init(probe_gst_elements) else {
+            // Without GStreamer we can't see what's there; assume the usual decoders, claim no hardware
+            return Ok(CODECS.iter().map(|codec| CodecInfo {
+                name: codec.name.to_string(),
+                mime_type: codec.mime_type.to_string(),
                 hardware_accelerated: false,
                 encode: false,
                 decode: true,
-            },
-        ])
+            }).collect());
+        };
+
+        let any_present = |names: &[&str]| names.iter().any(|name| elements.contains(*name));
+
+        Ok(CODECS.iter().map(|codec| {
+            let hw_encode = any_present(codec.hardware_encoders);
+            let hw_decode = any_present(codec.hardware_decoders);
+
+            CodecInfo {
+                name: codec.name.to_string(),
+                mime_type: codec.mime_type.to_string(),
+                hardware_accelerated: hw_encode || hw_decode,
+                encode: hw_encode || any_present(codec.software_encoders),
+                decode: hw_decode || any_present(codec.software_decoders),
+            }
+        }).collect())
     }
 
+    /// Audio sources and sinks as GStreamer's device monitor sees them; ids are
+    /// the values the device's own source/sink element accepts
     pub fn list_audio_devices(&self) -> Result<Vec<AudioDevice>> {
-        // Return placeholder audio devices
-        Ok(vec![
-            AudioDevice {
-                id: "default".to_string(),
-                name: "Default Audio Device".to_string(),
-                is_input: false,
-                is_default: true,
-                channels: 2,
-                sample_rate: 48000,
+        let Some(devices) = monitor_audio_devices() else {
+            // No device monitor to ask; the default output is still usable
+            return Ok(vec![
+                AudioDevice {
+                    id: "default".to_string(),
+                    name: "Default Audio Device".to_string(),
+                    is_input: false,
+                    is_default: true,
+                    channels: 2,
+                    sample_rate: 48000,
+                },
+            ]);
+        };
+
+        Ok(devices.into_iter().map(|monitored| monitored.device).collect())
+    }
+
+    /// Play an audio file on an output device, or the default output when `device_id` is None
+    pub fn play_audio(&mut self, path: &str, device_id: Option<&str>) -> Result<()> {
+        self.play_audio_as(path, device_id, None)
+    }
+
+    /// Like `play_audio`, using `ContentType::Audio` hints to pick the demuxer and decoder
+    /// for files decodebin can't autoplug
+    pub fn play_audio_as(&mut self, path: &str, device_id: Option<&str>, hints: Option<&ContentType>) -> Result<()> {
+        let sink = match device_id {
+            None | Some("default") => "autoaudiosink".to_string(),
+            Some(device_id) => {
+                let device = monitor_audio_devices()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|monitored| !monitored.device.is_input && monitored.device.id == device_id)
+                    .ok_or_else(|| CasterError::NotFound(format!("Unknown audio output device: {}", device_id)))?;
+                format!("{} {}={}", device.element, device.property, quote(device_id))
+            }
+        };
+
+        let decode = match hints {
+            Some(ContentType::Audio { codec, format }) => audio_decode_chain(codec, format),
+            _ => "decodebin".to_string(),
+        };
+
+        // Named so the bindings can find it to change the volume
+        let description = format!(
+            "filesrc location={} ! {} ! progressreport ! audioconvert ! audioresample ! volume name=volume ! {}",
+            quote(path),
+            decode,
+            sink
+        );
+        self.start_playback(&description, None)
+    }
+}
+
+/// Keep the cast window showing local playback in step with it, and pass its
+/// seeks, pauses and volume changes back to the engine
+pub fn spawn_playback_sync(
+    media_engine: Arc<RwLock<MediaEngine>>,
+    display_manager: Arc<RwLock<DisplayManager>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut notices = display_manager.read().await.subscribe_window_notices();
+        let mut interval = tokio::time::interval(POSITION_SYNC_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let Some(playback) = media_engine.read().await.playback_position() else {
+                        continue;
+                    };
+                    let Some(ref display_id) = playback.display_id else {
+                        continue;
+                    };
+
+                    let command = WindowCommand::SetPosition {
+                        position: playback.position,
+                        duration: playback.duration,
+                        seekable: playback.seekable,
+                        paused: playback.paused,
+                    };
+                    if let Err(e) = display_manager.read().await.send_to_window(display_id, command) {
+                        debug!("No window for playback on {}: {}", display_id, e);
+                    }
+                }
+                notice = notices.recv() => match notice {
+                    Ok(notice) => {
+                        let (display_id, action) = match notice {
+                            WindowNotice::SeekRequested { ref display_id, .. } => (display_id, "Seek"),
+                            WindowNotice::PauseRequested { ref display_id, paused: true } => (display_id, "Pause"),
+                            WindowNotice::PauseRequested { ref display_id, paused: false } => (display_id, "Resume"),
+                            WindowNotice::VolumeRequested { ref display_id, .. } => (display_id, "Volume change"),
+                        };
+                        let mut media_engine = media_engine.write().await;
+                        let showing = media_engine.playback_position()
+                            .is_some_and(|playback| playback.display_id.as_deref() == Some(display_id.as_str()));
+                        if !showing {
+                            continue;
+                        }
+
+                        let result = match notice {
+                            WindowNotice::SeekRequested { position, .. } => media_engine.seek(position),
+                            WindowNotice::PauseRequested { paused: true, .. } => media_engine.pause(),
+                            WindowNotice::PauseRequested { paused: false, .. } => media_engine.resume(),
+                            WindowNotice::VolumeRequested { volume, .. } => media_engine.set_volume(volume),
+                        };
+                        if let Err(e) = result {
+                            warn!("{} from {} failed: {}", action, display_id, e);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+    })
+}
+
+/// URI playbin can open for a cast source: URLs as they are, paths as file:// URIs.
+/// Paths outside the cache's media directories are refused.
+pub fn playback_uri(cache: &ContentCache, source: &str) -> Result<String> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        return Ok(source.to_string());
+    }
+
+    let path = cache.media_path(source)?;
+    url::Url::from_file_path(&path)
+        .map(String::from)
+        .map_err(|_| CasterError::Media(format!("Can't play {}: not an absolute path", path.display())))
+}
+
+/// Decode audio to mono f32 samples at `sample_rate`, e.g. to draw a waveform.
+/// Blocks until the whole file is decoded.
+pub fn decode_audio_samples(data: &[u8], sample_rate: u32) -> Result<Vec<f32>> {
+    // gst-launch can't read from memory, so stage the audio in a temp file
+    let path = std::env::temp_dir().join(format!("q8-caster-audio-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, data)?;
+
+    let description = format!(
+        "filesrc location={} ! decodebin ! audioconvert ! audioresample ! audio/x-raw,format=F32LE,channels=1,rate={} ! fdsink fd=1",
+        quote(&path.to_string_lossy()),
+        sample_rate
+    );
+    // -q keeps gst-launch's own messages off stdout, which carries the samples
+    let output = Command::new("gst-launch-1.0").arg("-q").arg(&description).output();
+    let _ = std::fs::remove_file(&path);
+
+    let output = output.map_err(|e| CasterError::Media(format!("Failed to start GStreamer pipeline: {}", e)))?;
+    if !output.status.success() {
+        let errors: Vec<_> = String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .filter(|line| line.contains("ERROR"))
+            .map(str::to_string)
+            .collect();
+        return Err(CasterError::Media(format!("Failed to decode audio: {}", errors.join("; "))));
+    }
+
+    Ok(output.stdout
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect())
+}
+
+/// Explicit demuxer/decoder elements for a format and codec, with decodebin
+/// filling in whatever the hints don't name
+fn audio_decode_chain(codec: &str, format: &str) -> String {
+    let demuxer = match format.to_ascii_lowercase().as_str() {
+        "mp4" | "m4a" | "mov" => Some("qtdemux"),
+        "ogg" | "oga" => Some("oggdemux"),
+        "webm" | "mka" | "mkv" => Some("matroskademux"),
+        "wav" => Some("wavparse"),
+        "flac" => Some("flacparse"),
+        "mp3" => Some("mpegaudioparse"),
+        _ => None,
+    };
+    let decoder = match codec.to_ascii_lowercase().as_str() {
+        "opus" => Some("opusdec"),
+        "vorbis" => Some("vorbisdec"),
+        "aac" => Some("avdec_aac"),
+        "flac" => Some("flacdec"),
+        "mp3" => Some("mpg123audiodec"),
+        // wavparse already outputs raw PCM
+        "pcm" | "wav" => Some("identity"),
+        _ => None,
+    };
+
+    let chain: Vec<&str> = match (demuxer, decoder) {
+        (Some(demuxer), Some(decoder)) => vec![demuxer, decoder],
+        (Some(demuxer), None) => vec![demuxer, "decodebin"],
+        (None, Some(decoder)) => vec!["parsebin", decoder],
+        (None, None) => vec!["decodebin"],
+    };
+    chain.join(" ! ")
+}
+
+/// An audio device plus the element and property that address it,
+/// e.g. `pulsesink device=...` or `pipewiresink target-object=...`
+struct MonitoredDevice {
+    device: AudioDevice,
+    element: String,
+    property: String,
+}
+
+/// Ask GStreamer's device monitor for audio sources and sinks; None if
+/// GStreamer can't be initialized
+#[cfg(feature = "gstreamer")]
+fn monitor_audio_devices() -> Option<Vec<MonitoredDevice>> {
+    use gstreamer as gst;
+    use gst::prelude::*;
+
+    pipeline::init().ok()?;
+    let monitor = gst::DeviceMonitor::new();
+    monitor.add_filter(Some("Audio/Source"), None);
+    monitor.add_filter(Some("Audio/Sink"), None);
+    if monitor.start().is_err() {
+        return None;
+    }
+    let devices = monitor.devices();
+    monitor.stop();
+
+    Some(devices.iter().filter_map(|device| {
+        let class = device.device_class();
+        let properties = device.properties();
+        let property = |name: &str| properties.as_ref().and_then(|properties| properties.get::<String>(name).ok());
+        // Sink monitors mirror outputs and aren't real inputs
+        if class == "Audio/Source" && property("device.class").as_deref() == Some("monitor") {
+            return None;
+        }
+
+        // The element the device makes already addresses it; find out how
+        let element = device.create_element(None).ok()?;
+        let factory = element.factory()?.name().to_string();
+        let (address, id) = ["device", "target-object", "path"].into_iter().find_map(|name| {
+            element.find_property(name)?;
+            let id = element.property_value(name).get::<Option<String>>().ok()??;
+            Some((name.to_string(), id))
+        })?;
+
+        let caps = device.caps().map(|caps| caps.to_string()).unwrap_or_default();
+        let is_default = properties.as_ref()
+            .and_then(|properties| properties.get::<bool>("is-default").ok())
+            .unwrap_or(false);
+
+        Some(MonitoredDevice {
+            device: AudioDevice {
+                id,
+                name: device.display_name().to_string(),
+                is_input: class == "Audio/Source",
+                is_default,
+                channels: caps_int(&caps, "channels", 2),
+                sample_rate: caps_int(&caps, "rate", 48000),
             },
-        ])
+            element: factory,
+            property: address,
+        })
+    }).collect())
+}
+
+/// Run `gst-device-monitor-1.0` over audio sources and sinks; None if it isn't installed
+#[cfg(not(feature = "gstreamer"))]
+fn monitor_audio_devices() -> Option<Vec<MonitoredDevice>> {
+    let output = Command::new("gst-device-monitor-1.0")
+        .args(["Audio/Source", "Audio/Sink"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let devices = String::from_utf8_lossy(&output.stdout)
+        .split("Device found:")
+        .filter_map(parse_monitored_device)
+        .collect();
+    Some(devices)
+}
+
+// Each device is a block like:
+//   name  : Built-in Audio Analog Stereo
+//   class : Audio/Sink
+//   caps  : audio/x-raw, format=(string)S16LE, rate=(int)[ 1, 384000 ], channels=(int)[ 1, 32 ];
+//   properties:
+//       is-default = true
+//   gst-launch-1.0 ... ! pulsesink device=alsa_output.pci-0000_00_1f.3.analog-stereo
+#[cfg(not(feature = "gstreamer"))]
+fn parse_monitored_device(block: &str) -> Option<MonitoredDevice> {
+    let field = |name: &str| {
+        block.lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .find(|(key, _)| key.trim() == name)
+            .map(|(_, value)| value.trim())
+    };
+
+    let name = field("name")?.to_string();
+    let class = field("class")?;
+    if !class.starts_with("Audio/") {
+        return None;
+    }
+    // Sink monitors mirror outputs and aren't real inputs
+    if class == "Audio/Source" && block.contains("device.class = monitor") {
+        return None;
+    }
+
+    let launch = block.lines().find(|line| line.trim().starts_with("gst-launch-1.0"))?;
+    let tokens: Vec<&str> = launch.split_whitespace().collect();
+    let (element, property, id) = tokens.windows(2).find_map(|pair| {
+        let (property, id) = pair[1].split_once('=')?;
+        let element = pair[0];
+        let addresses = matches!(property, "device" | "target-object" | "path");
+        (addresses && (element.ends_with("sink") || element.ends_with("src")))
+            .then(|| (element.to_string(), property.to_string(), id.trim_matches('"').to_string()))
+    })?;
+
+    let caps = field("caps").unwrap_or_default();
+    let is_default = block.lines().any(|line| line.trim() == "is-default = true");
+
+    Some(MonitoredDevice {
+        device: AudioDevice {
+            id,
+            name,
+            is_input: class == "Audio/Source",
+            is_default,
+            channels: caps_int(caps, "channels", 2),
+            sample_rate: caps_int(caps, "rate", 48000),
+        },
+        element,
+        property,
+    })
+}
+
+/// An int caps field; a range "[ 1, 32 ]" yields `preferred` clamped into it
+fn caps_int(caps: &str, field: &str, preferred: u32) -> u32 {
+    let Some((_, rest)) = caps.split_once(&format!("{}=(int)", field)) else {
+        return preferred;
+    };
+
+    if let Some(range) = rest.strip_prefix('[') {
+        let bounds: Vec<u32> = range.split(']').next().unwrap_or_default()
+            .split(',')
+            .filter_map(|bound| bound.trim().parse().ok())
+            .collect();
+        return match bounds.as_slice() {
+            [low, high, ..] => preferred.clamp(*low, *high),
+            _ => preferred,
+        };
+    }
+
+    let digits: String = rest.trim_start_matches(['{', ' ']).chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().unwrap_or(preferred)
+}
+
+/// List every element factory via `gst-inspect-1.0`, whose lines look like
+/// "va:  vah264dec: VA-API H.264 Decoder"
+fn probe_gst_elements() -> Option<HashSet<String>> {
+    let output = match Command::new("gst-inspect-1.0").output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            warn!("gst-inspect-1.0 failed with {}; codec capabilities are a guess", output.status);
+            return None;
+        }
+        Err(e) => {
+            warn!("GStreamer not available ({}); codec capabilities are a guess", e);
+            return None;
+        }
+    };
+
+    let elements: HashSet<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split(':').nth(1))
+        .map(|element| element.trim().to_string())
+        .filter(|element| !element.is_empty())
+        .collect();
+
+    debug!("GStreamer reports {} element factories", elements.len());
+    Some(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // gst-device-monitor-1.0 Audio/Source Audio/Sink on PulseAudio, trimmed to three devices
+    #[cfg(not(feature = "gstreamer"))]
+    const PULSE_MONITOR: &str = r#"Probing devices...
+
+
+Device found:
+
+	name  : Built-in Audio Analog Stereo
+	class : Audio/Sink
+	caps  : audio/x-raw, format=(string){ S16LE, S16BE, F32LE, F32BE, S32LE, S32BE, S24LE, S24BE, S24_32LE, S24_32BE, U8 }, layout=(string)interleaved, rate=(int)[ 1, 384000 ], channels=(int)[ 1, 32 ];
+	        audio/x-alaw, rate=(int)[ 1, 384000 ], channels=(int)[ 1, 32 ];
+	        audio/x-mulaw, rate=(int)[ 1, 384000 ], channels=(int)[ 1, 32 ];
+	properties:
+		alsa.resolution_bits = 16
+		device.api = alsa
+		device.class = sound
+		alsa.class = generic
+		alsa.subclass = generic-mix
+		alsa.name = "ALC257 Analog"
+		alsa.card = 0
+		alsa.card_name = "HDA Intel PCH"
+		device.bus_path = pci-0000:00:1f.3
+		sysfs.path = /devices/pci0000:00/0000:00:1f.3/sound/card0
+		device.form_factor = internal
+		device.string = front:0
+		device.buffering.buffer_size = 352800
+		device.profile.name = analog-stereo
+		device.description = "Built-in Audio Analog Stereo"
+		udev.id = pci-0000_00_1f.3
+		is-default = true
+	gst-launch-1.0 ... ! pulsesink device=alsa_output.pci-0000_00_1f.3.analog-stereo
+
+
+Device found:
+
+	name  : Monitor of Built-in Audio Analog Stereo
+	class : Audio/Source
+	caps  : audio/x-raw, format=(string){ S16LE, S16BE, F32LE, F32BE, S32LE, S32BE, S24LE, S24BE, S24_32LE, S24_32BE, U8 }, layout=(string)interleaved, rate=(int)[ 1, 384000 ], channels=(int)[ 1, 32 ];
+	properties:
+		device.description = "Monitor of Built-in Audio Analog Stereo"
+		device.class = monitor
+		device.bus_path = pci-0000:00:1f.3
+		is-default = false
+	gst-launch-1.0 pulsesrc device=alsa_output.pci-0000_00_1f.3.analog-stereo.monitor ! ...
+
+
+Device found:
+
+	name  : USB Microphone Mono
+	class : Audio/Source
+	caps  : audio/x-raw, format=(string)S16LE, layout=(string)interleaved, rate=(int)44100, channels=(int)1;
+	properties:
+		device.api = alsa
+		device.class = sound
+		alsa.card_name = "USB PnP Sound Device"
+		device.description = "USB Microphone Mono"
+		is-default = false
+	gst-launch-1.0 pulsesrc device=alsa_input.usb-C-Media_USB_PnP_Sound_Device-00.mono-fallback ! ...
+"#;
+
+    // The same output device on PipeWire, which addresses nodes by target-object
+    #[cfg(not(feature = "gstreamer"))]
+    const PIPEWIRE_SINK: &str = r#"
+	name  : Built-in Audio Analog Stereo
+	class : Audio/Sink
+	caps  : audio/x-raw, format=(string)F32LE, layout=(string)interleaved, rate=(int)48000, channels=(int)2, channel-mask=(bitmask)0x0000000000000003;
+	properties:
+		object.path = alsa:pcm:0:front:0:playback
+		api.alsa.path = front:0
+		node.name = alsa_output.pci-0000_00_1f.3.analog-stereo
+		media.class = Audio/Sink
+		is-default = true
+	gst-launch-1.0 ... ! pipewiresink target-object=alsa_output.pci-0000_00_1f.3.analog-stereo
+"#;
+
+    #[cfg(not(feature = "gstreamer"))]
+    #[test]
+    fn parses_pulse_devices_and_skips_monitors() {
+        let devices: Vec<MonitoredDevice> = PULSE_MONITOR.split("Device found:").filter_map(parse_monitored_device).collect();
+        assert_eq!(devices.len(), 2);
+
+        let speakers = &devices[0];
+        assert_eq!(speakers.device.id, "alsa_output.pci-0000_00_1f.3.analog-stereo");
+        assert_eq!(speakers.device.name, "Built-in Audio Analog Stereo");
+        assert!(!speakers.device.is_input);
+        assert!(speakers.device.is_default);
+        assert_eq!((speakers.device.channels, speakers.device.sample_rate), (2, 48000));
+        assert_eq!((speakers.element.as_str(), speakers.property.as_str()), ("pulsesink", "device"));
+
+        let microphone = &devices[1];
+        assert_eq!(microphone.device.id, "alsa_input.usb-C-Media_USB_PnP_Sound_Device-00.mono-fallback");
+        assert!(microphone.device.is_input);
+        assert!(!microphone.device.is_default);
+        assert_eq!((microphone.device.channels, microphone.device.sample_rate), (1, 44100));
+        assert_eq!(microphone.element, "pulsesrc");
+    }
+
+    #[cfg(not(feature = "gstreamer"))]
+    #[test]
+    fn parses_pipewire_target_objects() {
+        let sink = parse_monitored_device(PIPEWIRE_SINK).unwrap();
+        assert_eq!(sink.device.id, "alsa_output.pci-0000_00_1f.3.analog-stereo");
+        assert_eq!((sink.element.as_str(), sink.property.as_str()), ("pipewiresink", "target-object"));
+        assert!(sink.device.is_default);
+    }
+
+    #[cfg(not(feature = "gstreamer"))]
+    #[test]
+    fn skips_blocks_that_arent_audio_devices() {
+        assert!(parse_monitored_device("Probing devices...\n\n").is_none());
+        let video = PIPEWIRE_SINK.replace("class : Audio/Sink", "class : Video/Source");
+        assert!(parse_monitored_device(&video).is_none());
+        let no_launch_line: String = PIPEWIRE_SINK.lines().filter(|line| !line.contains("gst-launch-1.0")).collect::<Vec<_>>().join("\n");
+        assert!(parse_monitored_device(&no_launch_line).is_none());
+    }
+
+    #[test]
+    fn caps_ints_take_fixed_values_ranges_and_lists() {
+        let caps = "audio/x-raw, format=(string)S16LE, rate=(int)44100, channels=(int)1;";
+        assert_eq!(caps_int(caps, "rate", 48000), 44100);
+        assert_eq!(caps_int(caps, "channels", 2), 1);
+
+        // The preferred value, clamped into a range
+        let ranged = "audio/x-raw, rate=(int)[ 1, 384000 ], channels=(int)[ 1, 32 ];";
+        assert_eq!(caps_int(ranged, "rate", 48000), 48000);
+        assert_eq!(caps_int("audio/x-raw, rate=(int)[ 8000, 16000 ]", "rate", 48000), 16000);
+
+        // The first of a list, and the preference when the field is missing
+        assert_eq!(caps_int("audio/x-raw, rate=(int){ 44100, 48000 }", "rate", 8000), 44100);
+        assert_eq!(caps_int("audio/x-raw, format=(string)F32LE", "channels", 2), 2);
     }
 }
@@ -0,0 +1,35 @@
+#[cfg(not(feature = "gstreamer"))]
+mod launch;
+#[cfg(not(feature = "gstreamer"))]
+pub use launch::Pipeline;
+
+#[cfg(feature = "gstreamer")]
+mod bindings;
+#[cfg(feature = "gstreamer")]
+pub use bindings::Pipeline;
+#[cfg(feature = "gstreamer")]
+pub(crate) use bindings::init;
+
+/// Where a pipeline has got to: a `progressreport` update from gst-launch, or
+/// a position query on the bindings. Position and duration are in seconds and
+/// only present when known in time rather than bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Progress {
+    pub fraction: Option<f32>,
+    pub position: Option<f64>,
+    pub duration: Option<f64>,
+}
+
+/// How a pipeline run ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum PipelineExit {
+    Finished,
+    Failed(String),
+    /// Killed because its cancellation token fired
+    Cancelled,
+}
+
+/// Quote a property value (file paths, device names) for a pipeline description
+pub fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,182 @@
+use gstreamer as gst;
+use gst::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use super::{PipelineExit, Progress};
+use crate::{Result, CasterError};
+
+/// How often position and duration are queried and reported
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the bus watcher waits for a message before checking for a stop
+const BUS_POLL: Duration = Duration::from_millis(100);
+
+/// A pipeline run in-process on the GStreamer bindings, which unlike a
+/// gst-launch child can be paused, sought and have its volume changed while it
+/// plays.
+pub struct Pipeline {
+    pipeline: gst::Element,
+    // Cleared by stop, and by the bus watcher once the pipeline ends
+    running: Arc<AtomicBool>,
+}
+
+impl Pipeline {
+    /// Whether `pause`, `resume`, `seek` and `set_volume` can work
+    pub const CONTROLLABLE: bool = true;
+
+    /// Start `description`. `on_progress` gets the position and duration about
+    /// once a second, and `on_exit` is called once the pipeline ends.
+    pub fn launch(
+        description: &str,
+        on_progress: impl FnMut(Progress) + Send + 'static,
+        on_exit: impl FnOnce(PipelineExit) + Send + 'static,
+    ) -> Result<Self> {
+        Self::launch_until(description, CancellationToken::new(), on_progress, on_exit)
+    }
+
+    /// Like `launch`, stopping the pipeline once `cancel` fires
+    pub fn launch_until(
+        description: &str,
+        cancel: CancellationToken,
+        mut on_progress: impl FnMut(Progress) + Send + 'static,
+        on_exit: impl FnOnce(PipelineExit) + Send + 'static,
+    ) -> Result<Self> {
+        if cancel.is_cancelled() {
+            return Err(CasterError::Cancelled("Pipeline cancelled before it started".into()));
+        }
+        init()?;
+        debug!("Launching {}", description);
+
+        let pipeline = gst::parse::launch(description)
+            .map_err(|e| CasterError::Media(format!("Invalid GStreamer pipeline: {}", e)))?;
+        let bus = pipeline
+            .bus()
+            .ok_or_else(|| CasterError::Media("GStreamer pipeline has no bus".into()))?;
+        if let Err(e) = pipeline.set_state(gst::State::Playing) {
+            let _ = pipeline.set_state(gst::State::Null);
+            return Err(CasterError::Media(format!("Failed to start GStreamer pipeline: {}", e)));
+        }
+
+        let running = Arc::new(AtomicBool::new(true));
+        let watched = pipeline.clone();
+        let watching = Arc::clone(&running);
+        std::thread::Builder::new()
+            .name("gst-bus".into())
+            .spawn(move || {
+                let mut reported = Instant::now();
+                let exit = loop {
+                    if cancel.is_cancelled() {
+                        break PipelineExit::Cancelled;
+                    }
+                    if !watching.load(Ordering::Relaxed) {
+                        break PipelineExit::Failed("Pipeline stopped".into());
+                    }
+
+                    let timeout = gst::ClockTime::from_mseconds(BUS_POLL.as_millis() as u64);
+                    if let Some(message) = bus.timed_pop(timeout) {
+                        match message.view() {
+                            gst::MessageView::Eos(..) => break PipelineExit::Finished,
+                            gst::MessageView::Error(error) => {
+                                let detail = error.debug().map(|debug| format!(" ({})", debug)).unwrap_or_default();
+                                break PipelineExit::Failed(format!("{}{}", error.error(), detail));
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    if reported.elapsed() >= PROGRESS_INTERVAL {
+                        reported = Instant::now();
+                        on_progress(query_progress(&watched));
+                    }
+                };
+
+                if let Err(e) = watched.set_state(gst::State::Null) {
+                    warn!("Failed to shut down GStreamer pipeline: {}", e);
+                }
+                watching.store(false, Ordering::Relaxed);
+                on_exit(exit);
+            })
+            .map_err(|e| CasterError::Media(format!("Failed to watch GStreamer pipeline: {}", e)))?;
+
+        Ok(Self { pipeline, running })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    pub fn pause(&self) -> Result<()> {
+        self.set_state(gst::State::Paused)
+    }
+
+    pub fn resume(&self) -> Result<()> {
+        self.set_state(gst::State::Playing)
+    }
+
+    /// Seek to `position` seconds
+    pub fn seek(&self, position: f64) -> Result<()> {
+        let position = gst::ClockTime::from_nseconds((position.max(0.0) * 1e9) as u64);
+        self.pipeline
+            .seek_simple(gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT, position)
+            .map_err(|e| CasterError::Media(format!("Failed to seek: {}", e)))
+    }
+
+    /// Volume from 0.0, silent, to 1.0, as recorded. playbin has a volume of
+    /// its own; other pipelines need an element named `volume`.
+    pub fn set_volume(&self, volume: f64) -> Result<()> {
+        let element = if self.pipeline.has_property("volume", None) {
+            Some(self.pipeline.clone())
+        } else {
+            self.pipeline
+                .downcast_ref::<gst::Bin>()
+                .and_then(|bin| bin.by_name("volume"))
+        };
+        let element = element
+            .ok_or_else(|| CasterError::NotImplemented("This pipeline has no volume control".into()))?;
+        element.set_property("volume", volume.clamp(0.0, 1.0));
+        Ok(())
+    }
+
+    /// Stop the pipeline; `on_exit` still fires with a failure
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    fn set_state(&self, state: gst::State) -> Result<()> {
+        if !self.is_running() {
+            return Err(CasterError::NotCasting("The pipeline has ended".into()));
+        }
+        self.pipeline
+            .set_state(state)
+            .map(|_| ())
+            .map_err(|e| CasterError::Media(format!("Failed to set the pipeline {:?}: {}", state, e)))
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Initialize GStreamer once for the process
+pub(crate) fn init() -> Result<()> {
+    static INIT: OnceLock<std::result::Result<(), String>> = OnceLock::new();
+    INIT.get_or_init(|| gst::init().map_err(|e| format!("Failed to initialize GStreamer: {}", e)))
+        .clone()
+        .map_err(CasterError::Media)
+}
+
+fn query_progress(pipeline: &gst::Element) -> Progress {
+    let position = pipeline.query_position::<gst::ClockTime>().map(|time| time.seconds_f64());
+    let duration = pipeline.query_duration::<gst::ClockTime>().map(|time| time.seconds_f64());
+    let fraction = match (position, duration) {
+        (Some(position), Some(duration)) if duration > 0.0 => Some((position / duration).clamp(0.0, 1.0) as f32),
+        _ => None,
+    };
+    Progress { fraction, position, duration }
+}
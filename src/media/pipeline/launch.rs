@@ -0,0 +1,176 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use super::{PipelineExit, Progress};
+use crate::{Result, CasterError};
+
+/// A `gst-launch-1.0` pipeline running as a child process.
+///
+/// This is how we drive GStreamer unless the crate is built with the
+/// `gstreamer` feature; the description syntax is identical, so pipelines run
+/// unchanged on either. A running gst-launch can't be told anything, so this
+/// pipeline can't pause, seek or change volume.
+pub struct Pipeline {
+    child: Arc<Mutex<Child>>,
+}
+
+impl Pipeline {
+    /// Whether `pause`, `resume`, `seek` and `set_volume` can work
+    pub const CONTROLLABLE: bool = false;
+
+    /// Start `description`. `on_progress` gets each report from any `progressreport`
+    /// element in the pipeline, and `on_exit` is called once the process ends.
+    pub fn launch(
+        description: &str,
+        on_progress: impl FnMut(Progress) + Send + 'static,
+        on_exit: impl FnOnce(PipelineExit) + Send + 'static,
+    ) -> Result<Self> {
+        Self::launch_until(description, CancellationToken::new(), on_progress, on_exit)
+    }
+
+    /// Like `launch`, killing the pipeline once `cancel` fires. The token is
+    /// checked at each progress report, so the pipeline needs a `progressreport`
+    /// element for cancellation to reach it.
+    pub fn launch_until(
+        description: &str,
+        cancel: CancellationToken,
+        mut on_progress: impl FnMut(Progress) + Send + 'static,
+        on_exit: impl FnOnce(PipelineExit) + Send + 'static,
+    ) -> Result<Self> {
+        if cancel.is_cancelled() {
+            return Err(CasterError::Cancelled("Pipeline cancelled before it started".into()));
+        }
+        debug!("gst-launch-1.0 {}", description);
+
+        // -e sends EOS on interrupt so muxers can finalize their output
+        let mut child = Command::new("gst-launch-1.0")
+            .arg("-e")
+            .arg(description)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| CasterError::Media(format!("Failed to start GStreamer pipeline: {}", e)))?;
+
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let child = Arc::new(Mutex::new(child));
+
+        // Drain stderr separately so a chatty pipeline can't block on a full pipe
+        let errors = std::thread::spawn(move || {
+            stderr
+                .map(|stderr| {
+                    BufReader::new(stderr)
+                        .lines()
+                        .map_while(|line| line.ok())
+                        .filter(|line| line.contains("ERROR"))
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                })
+                .unwrap_or_default()
+        });
+
+        let watched = Arc::clone(&child);
+        std::thread::spawn(move || {
+            if let Some(stdout) = stdout {
+                for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+                    if cancel.is_cancelled() {
+                        // Keep reading; stdout closes once the kill lands
+                        let _ = watched.lock().unwrap().kill();
+                        continue;
+                    }
+                    if let Some(progress) = parse_progress(&line) {
+                        on_progress(progress);
+                    }
+                }
+            }
+
+            // stdout closes when the process exits; collect its status
+            let status = watched.lock().unwrap().wait();
+            let exit = match status {
+                Ok(status) if status.success() => PipelineExit::Finished,
+                Ok(_) if cancel.is_cancelled() => PipelineExit::Cancelled,
+                Ok(status) => {
+                    let detail = errors.join().unwrap_or_default();
+                    PipelineExit::Failed(format!("GStreamer exited with {} {}", status, detail).trim().to_string())
+                }
+                Err(e) => PipelineExit::Failed(format!("Failed to wait for GStreamer: {}", e)),
+            };
+            on_exit(exit);
+        });
+
+        Ok(Self { child })
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.child.lock().unwrap().try_wait(), Ok(None))
+    }
+
+    pub fn pause(&self) -> Result<()> {
+        Err(uncontrollable("pause"))
+    }
+
+    pub fn resume(&self) -> Result<()> {
+        Err(uncontrollable("resume"))
+    }
+
+    /// Seek to `position` seconds
+    pub fn seek(&self, _position: f64) -> Result<()> {
+        Err(uncontrollable("seek"))
+    }
+
+    /// Volume from 0.0, silent, to 1.0, as recorded
+    pub fn set_volume(&self, _volume: f64) -> Result<()> {
+        Err(uncontrollable("change volume"))
+    }
+
+    /// Kill the pipeline; `on_exit` still fires with a failure
+    pub fn stop(&self) {
+        let mut child = self.child.lock().unwrap();
+        if let Err(e) = child.kill() {
+            warn!("Failed to stop GStreamer pipeline: {}", e);
+        }
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        if self.is_running() {
+            self.stop();
+        }
+    }
+}
+
+fn uncontrollable(action: &str) -> CasterError {
+    CasterError::NotImplemented(format!(
+        "gst-launch pipelines can't {}; build q8-caster with the gstreamer feature",
+        action
+    ))
+}
+
+/// progressreport prints "progressreport0 (00:00:05): 5 / 60 seconds ( 8.3 %)",
+/// or just "progressreport0 (00:00:05): 5 seconds" when the length is unknown
+fn parse_progress(line: &str) -> Option<Progress> {
+    let (_, report) = line.split_once("): ")?;
+
+    let (amount, percent) = match report.split_once(" (") {
+        Some((amount, rest)) => (amount, rest.split_once('%').map(|(percent, _)| percent)),
+        None => (report, None),
+    };
+    let fraction = percent
+        .and_then(|percent| percent.trim().parse::<f32>().ok())
+        .map(|percent| (percent / 100.0).clamp(0.0, 1.0));
+
+    let (amount, unit) = amount.trim().rsplit_once(' ')?;
+    let (position, duration) = match amount.split_once('/') {
+        Some((position, duration)) => (position.trim().parse::<f64>().ok(), duration.trim().parse::<f64>().ok()),
+        None => (amount.trim().parse::<f64>().ok(), None),
+    };
+
+    if unit != "seconds" {
+        return fraction.map(|fraction| Progress { fraction: Some(fraction), ..Progress::default() });
+    }
+    Some(Progress { fraction, position, duration })
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+use std::io::Read;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::{Result, CasterError, Resolution};
+
+/// Largest frame handed to a cast window; bigger displays are upscaled by the GPU
+const MAX_FRAME_SIZE: (u32, u32) = (1920, 1080);
+
+/// How long the pipeline gets to preroll and connect before the feed gives up
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Decoded RGBA frames from a playing pipeline, for a cast window to draw.
+///
+/// gst-launch has no appsink we can read from, so the pipeline's video chain ends in
+/// `tcpclientsink` to a loopback socket and frames arrive here as fixed-size RGBA
+/// buffers. The sink syncs to the pipeline clock, so frames arrive when they are
+/// due and the window only has to draw the latest one.
+pub struct VideoFeed {
+    width: u32,
+    height: u32,
+    latest: Mutex<Option<Vec<u8>>>,
+    wake: Mutex<Option<Box<dyn Fn() + Send>>>,
+    ended: AtomicBool,
+}
+
+impl fmt::Debug for VideoFeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VideoFeed")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("ended", &self.is_ended())
+            .finish()
+    }
+}
+
+impl VideoFeed {
+    /// Start listening for frames sized for `resolution`. Returns the feed and the
+    /// sink bin to end a pipeline's video chain with.
+    pub fn listen(resolution: &Resolution) -> Result<(Arc<Self>, String)> {
+        let (width, height) = frame_size(resolution);
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .map_err(|e| CasterError::Media(format!("Failed to open a video frame socket: {}", e)))?;
+        let port = listener.local_addr()
+            .map_err(|e| CasterError::Media(format!("Failed to open a video frame socket: {}", e)))?
+            .port();
+
+        let feed = Arc::new(Self {
+            width,
+            height,
+            latest: Mutex::new(None),
+            wake: Mutex::new(None),
+            ended: AtomicBool::new(false),
+        });
+
+        let reader = Arc::clone(&feed);
+        std::thread::Builder::new()
+            .name("video-frames".into())
+            .spawn(move || {
+                match accept(&listener, || reader.is_ended()) {
+                    Some(stream) => reader.read_frames(stream),
+                    // Audio-only media never opens the video branch
+                    None => debug!("Video pipeline never connected its frame socket"),
+                }
+                reader.close();
+            })
+            .map_err(|e| CasterError::Media(format!("Failed to start video frame reader: {}", e)))?;
+
+        // videoscale letterboxes into the fixed size since the pixel aspect ratio is pinned
+        let sink = format!(
+            "videoconvert ! videoscale ! video/x-raw,format=RGBA,width={},height={},pixel-aspect-ratio=1/1 ! tcpclientsink host=127.0.0.1 port={} sync=true",
+            width, height, port
+        );
+        Ok((feed, sink))
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The newest frame not yet taken, as tightly packed RGBA
+    pub fn take_frame(&self) -> Option<Vec<u8>> {
+        self.latest.lock().unwrap().take()
+    }
+
+    /// Called from the reader thread whenever a frame arrives or the feed ends
+    pub fn set_wake(&self, wake: impl Fn() + Send + 'static) {
+        *self.wake.lock().unwrap() = Some(Box::new(wake));
+    }
+
+    /// True once the pipeline has stopped sending frames
+    pub fn is_ended(&self) -> bool {
+        self.ended.load(Ordering::Relaxed)
+    }
+
+    fn read_frames(&self, mut stream: TcpStream) {
+        let frame_len = self.width as usize * self.height as usize * 4;
+        let mut spare = vec![0u8; frame_len];
+        let mut frames = 0u64;
+
+        // Ends at EOF, when the pipeline exits or is stopped
+        while stream.read_exact(&mut spare).is_ok() {
+            frames += 1;
+            // A frame the window never took is recycled rather than reallocated
+            let unshown = self.latest.lock().unwrap().replace(spare);
+            spare = unshown.unwrap_or_else(|| vec![0u8; frame_len]);
+            self.wake();
+        }
+        debug!("Video feed ended after {} frames", frames);
+    }
+
+    /// Mark the feed ended, e.g. when playback stops before the pipeline connected
+    pub(crate) fn close(&self) {
+        self.ended.store(true, Ordering::Relaxed);
+        self.wake();
+    }
+
+    fn wake(&self) {
+        if let Some(ref wake) = *self.wake.lock().unwrap() {
+            wake();
+        }
+    }
+}
+
+/// Wait for the pipeline's sink to connect; gives up if it never does, e.g. when
+/// the pipeline failed to start or the media has no such stream, or once
+/// `ended` says the feed was closed meanwhile
+pub(super) fn accept(listener: &TcpListener, ended: impl Fn() -> bool) -> Option<TcpStream> {
+    let started = Instant::now();
+    while started.elapsed() < CONNECT_TIMEOUT {
+        match listener.accept() {
+            Ok((stream, _)) => return stream.set_nonblocking(false).ok().map(|_| stream),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                if ended() {
+                    return None;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => {
+                warn!("Video frame socket failed: {}", e);
+                return None;
+            }
+        }
+    }
+    None
+}
+
+/// The display's size scaled down to fit `MAX_FRAME_SIZE`, kept even for the video converters
+fn frame_size(resolution: &Resolution) -> (u32, u32) {
+    let (max_width, max_height) = MAX_FRAME_SIZE;
+    let width = resolution.width.max(2) as f64;
+    let height = resolution.height.max(2) as f64;
+    let scale = (max_width as f64 / width).min(max_height as f64 / height).min(1.0);
+
+    let even = |size: f64| ((size * scale) as u32 / 2 * 2).max(2);
+    (even(width), even(height))
+}
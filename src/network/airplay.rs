@@ -0,0 +1,263 @@
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, info, warn};
+
+use crate::media::MediaEngine;
+use crate::server::sse::{notify_cast_started, notify_cast_stopped};
+
+/// Display id AirPlay sessions are reported under in cast events
+pub const AIRPLAY_DISPLAY_ID: &str = "airplay";
+
+/// Video, photo; the bits an AirPlay v1 sender checks before offering us
+pub const AIRPLAY_FEATURES: u64 = 0x07;
+pub const AIRPLAY_SRCVERS: &str = "120.2";
+
+/// What an AirPlay sender asked us to do
+#[derive(Debug, Clone)]
+pub enum AirPlayCommand {
+    Play { session_id: String, url: String, start_position: f64 },
+    Photo { session_id: String, data: Bytes },
+    Scrub { position: f64 },
+    Stop,
+}
+
+/// Shared state behind the AirPlay HTTP endpoints
+#[derive(Clone)]
+pub struct AirPlayReceiver {
+    device_id: String,
+    model: String,
+    commands: broadcast::Sender<AirPlayCommand>,
+    playback: Arc<RwLock<Option<Playback>>>,
+}
+
+struct Playback {
+    session_id: String,
+    // Position at `since`; we don't get position reports back from the player
+    position: f64,
+    since: Instant,
+}
+
+impl Playback {
+    fn position(&self) -> f64 {
+        self.position + self.since.elapsed().as_secs_f64()
+    }
+}
+
+impl AirPlayReceiver {
+    pub fn new(device_id: String, model: String, commands: broadcast::Sender<AirPlayCommand>) -> Self {
+        Self {
+            device_id,
+            model,
+            commands,
+            playback: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// AirPlay v1 endpoints; pairing and FairPlay are not supported
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/server-info", get(server_info))
+            .route("/play", post(play))
+            .route("/stop", post(stop))
+            .route("/scrub", get(get_scrub).post(set_scrub))
+            .route("/photo", put(photo))
+            .with_state(self.clone())
+    }
+
+    fn send(&self, command: AirPlayCommand) {
+        // Nobody listening just means nothing renders; the sender still gets its 200
+        if self.commands.send(command).is_err() {
+            debug!("No consumer for AirPlay command");
+        }
+    }
+
+    async fn start_session(&self, content_type: &str) -> String {
+        let session_id = uuid::Uuid::new_v4().to_string();
+
+        let previous = self.playback.write().await.replace(Playback {
+            session_id: session_id.clone(),
+            position: 0.0,
+            since: Instant::now(),
+        });
+        if let Some(previous) = previous {
+            notify_cast_stopped(AIRPLAY_DISPLAY_ID.to_string(), previous.session_id);
+        }
+
+        notify_cast_started(AIRPLAY_DISPLAY_ID.to_string(), content_type.to_string(), session_id.clone());
+        session_id
+    }
+}
+
+#[derive(Serialize)]
+struct ServerInfo<'a> {
+    deviceid: &'a str,
+    features: u64,
+    model: &'a str,
+    protovers: &'a str,
+    srcvers: &'a str,
+}
+
+async fn server_info(State(receiver): State<AirPlayReceiver>) -> Response {
+    let info = ServerInfo {
+        deviceid: &receiver.device_id,
+        features: AIRPLAY_FEATURES,
+        model: &receiver.model,
+        protovers: "1.0",
+        srcvers: AIRPLAY_SRCVERS,
+    };
+
+    let mut body = Vec::new();
+    match plist::to_writer_xml(&mut body, &info) {
+        Ok(()) => ([(header::CONTENT_TYPE, "text/x-apple-plist+xml")], body).into_response(),
+        Err(e) => {
+            warn!("Failed to encode AirPlay server-info: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PlayRequest {
+    #[serde(rename = "Content-Location")]
+    content_location: String,
+    /// Fraction of the duration to start at
+    #[serde(rename = "Start-Position", default)]
+    start_position: f64,
+}
+
+/// Senders post either `text/parameters` ("Key: value" lines) or a plist
+fn parse_play_request(headers: &HeaderMap, body: &[u8]) -> Option<PlayRequest> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    if content_type.starts_with("text/parameters") {
+        let text = std::str::from_utf8(body).ok()?;
+        let field = |name: &str| {
+            text.lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim().to_string())
+        };
+
+        return Some(PlayRequest {
+            content_location: field("Content-Location")?,
+            start_position: field("Start-Position").and_then(|p| p.parse().ok()).unwrap_or(0.0),
+        });
+    }
+
+    plist::from_reader(std::io::Cursor::new(body)).ok()
+}
+
+async fn play(State(receiver): State<AirPlayReceiver>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    let Some(request) = parse_play_request(&headers, &body) else {
+        warn!("Unreadable AirPlay play request");
+        return StatusCode::BAD_REQUEST;
+    };
+
+    info!("AirPlay play: {}", request.content_location);
+
+    let session_id = receiver.start_session("video").await;
+    receiver.send(AirPlayCommand::Play {
+        session_id,
+        url: request.content_location,
+        start_position: request.start_position,
+    });
+    StatusCode::OK
+}
+
+async fn photo(State(receiver): State<AirPlayReceiver>, body: Bytes) -> StatusCode {
+    if body.is_empty() {
+        return StatusCode::BAD_REQUEST;
+    }
+
+    info!("AirPlay photo ({} bytes)", body.len());
+
+    let session_id = receiver.start_session("image").await;
+    receiver.send(AirPlayCommand::Photo { session_id, data: body });
+    StatusCode::OK
+}
+
+async fn stop(State(receiver): State<AirPlayReceiver>) -> StatusCode {
+    if let Some(playback) = receiver.playback.write().await.take() {
+        info!("AirPlay stop");
+        notify_cast_stopped(AIRPLAY_DISPLAY_ID.to_string(), playback.session_id);
+    }
+
+    receiver.send(AirPlayCommand::Stop);
+    StatusCode::OK
+}
+
+async fn get_scrub(State(receiver): State<AirPlayReceiver>) -> impl IntoResponse {
+    let position = receiver.playback.read().await
+        .as_ref()
+        .map(Playback::position)
+        .unwrap_or(0.0);
+
+    // Duration is unknown without player feedback; senders treat 0 as "still loading"
+    (
+        [(header::CONTENT_TYPE, "text/parameters")],
+        format!("duration: 0.000000\nposition: {:.6}\n", position),
+    )
+}
+
+#[derive(Deserialize)]
+struct ScrubQuery {
+    position: f64,
+}
+
+async fn set_scrub(State(receiver): State<AirPlayReceiver>, Query(query): Query<ScrubQuery>) -> StatusCode {
+    if let Some(playback) = receiver.playback.write().await.as_mut() {
+        playback.position = query.position;
+        playback.since = Instant::now();
+    }
+
+    receiver.send(AirPlayCommand::Scrub { position: query.position });
+    StatusCode::OK
+}
+
+/// Play AirPlay requests through the local media engine
+pub fn spawn_media_bridge(
+    mut commands: broadcast::Receiver<AirPlayCommand>,
+    media_engine: Arc<RwLock<MediaEngine>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let command = match commands.recv().await {
+                Ok(command) => command,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("AirPlay bridge skipped {} commands", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            let mut media_engine = media_engine.write().await;
+            let result = match command {
+                AirPlayCommand::Play { url, .. } => media_engine.play_uri(&url),
+                AirPlayCommand::Photo { data, .. } => media_engine.show_image(&data),
+                AirPlayCommand::Stop => {
+                    media_engine.stop_playback();
+                    Ok(())
+                }
+                // gst-launch pipelines can't seek; the position is only tracked for /scrub
+                AirPlayCommand::Scrub { .. } => Ok(()),
+            };
+
+            if let Err(e) = result {
+                warn!("Failed to handle AirPlay request: {}", e);
+            }
+        }
+    })
+}
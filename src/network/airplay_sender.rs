@@ -0,0 +1,271 @@
+use dashmap::DashMap;
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::{Result, CasterError, ContentType, ContentSource};
+use super::discovery::{DeviceType, DiscoveredDevice};
+use super::media_registry;
+use super::media_server::resolve_media;
+
+/// Receivers answer control requests quickly; photo uploads get the same budget
+const AIRPLAY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Apple TVs reject requests from clients that don't identify as a media controller
+const AIRPLAY_USER_AGENT: &str = "MediaControl/1.0";
+
+/// HTTP client for one receiver's classic (unauthenticated) AirPlay v1 endpoints
+pub struct AirPlayClient {
+    http: reqwest::Client,
+    base_url: String,
+    // Ties /play, /photo and /stop together on the receiver
+    session_id: String,
+}
+
+/// What `/playback-info` reports; every field is missing while nothing is loaded
+#[derive(Debug, Deserialize)]
+struct PlaybackInfo {
+    duration: Option<f64>,
+    position: Option<f64>,
+    rate: Option<f64>,
+    #[serde(rename = "readyToPlay")]
+    ready_to_play: Option<bool>,
+}
+
+impl AirPlayClient {
+    pub fn for_device(device: &DiscoveredDevice) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(AIRPLAY_TIMEOUT)
+            .user_agent(AIRPLAY_USER_AGENT)
+            .build()
+            .map_err(|e| CasterError::Network(format!("Failed to create AirPlay client: {}", e)))?;
+
+        Ok(Self {
+            http,
+            base_url: format!("http://{}", SocketAddr::new(device.ip, device.port)),
+            session_id: uuid::Uuid::new_v4().to_string(),
+        })
+    }
+
+    /// Have the receiver fetch and play a video or audio URL from the start
+    pub async fn play(&self, url: &str) -> Result<()> {
+        let body = format!("Content-Location: {}\nStart-Position: 0.0\n", url);
+        let response = self.request(reqwest::Method::POST, "play")
+            .header(header::CONTENT_TYPE, "text/parameters")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| CasterError::Network(format!("AirPlay play request failed: {}", e)))?;
+
+        check_status("play", response.status())
+    }
+
+    /// Show a JPEG or PNG; the receiver keeps it up until the next request
+    pub async fn photo(&self, data: Vec<u8>) -> Result<()> {
+        // The protocol uses PUT here, unlike /play
+        let response = self.request(reqwest::Method::PUT, "photo")
+            .body(data)
+            .send()
+            .await
+            .map_err(|e| CasterError::Network(format!("AirPlay photo request failed: {}", e)))?;
+
+        check_status("photo", response.status())
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        let response = self.request(reqwest::Method::POST, "stop")
+            .send()
+            .await
+            .map_err(|e| CasterError::Network(format!("AirPlay stop request failed: {}", e)))?;
+
+        check_status("stop", response.status())
+    }
+
+    pub async fn playback_info(&self) -> Result<serde_json::Value> {
+        let response = self.request(reqwest::Method::GET, "playback-info")
+            .send()
+            .await
+            .map_err(|e| CasterError::Network(format!("AirPlay playback-info request failed: {}", e)))?;
+        check_status("playback-info", response.status())?;
+
+        let body = response.bytes().await
+            .map_err(|e| CasterError::Network(format!("Failed to read AirPlay playback info: {}", e)))?;
+        let info: PlaybackInfo = plist::from_bytes(&body)
+            .map_err(|e| CasterError::Network(format!("Invalid AirPlay playback info: {}", e)))?;
+
+        Ok(json!({
+            "duration": info.duration,
+            "position": info.position,
+            "rate": info.rate,
+            "ready_to_play": info.ready_to_play.unwrap_or(false),
+        }))
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, format!("{}/{}", self.base_url, path))
+            .header("X-Apple-Session-ID", &self.session_id)
+    }
+}
+
+fn check_status(action: &str, status: StatusCode) -> Result<()> {
+    match status {
+        status if status.is_success() => Ok(()),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(CasterError::Network(format!(
+            "AirPlay {} was refused ({}); the receiver requires pairing or a password", action, status
+        ))),
+        status => Err(CasterError::Network(format!("AirPlay {} failed with {}", action, status))),
+    }
+}
+
+/// A receiver we're casting to, plus the local files published for it
+struct AirPlaySession {
+    client: AirPlayClient,
+    media_tokens: Vec<String>,
+}
+
+impl AirPlaySession {
+    fn revoke_media(&mut self) {
+        for token in self.media_tokens.drain(..) {
+            media_registry().revoke(&token);
+        }
+    }
+}
+
+/// Casting state for AirPlay receivers found by `DeviceDiscovery`
+pub struct AirPlayManager {
+    discovered: Arc<DashMap<String, DiscoveredDevice>>,
+    sessions: HashMap<String, AirPlaySession>,
+}
+
+impl AirPlayManager {
+    pub fn new(discovered: Arc<DashMap<String, DiscoveredDevice>>) -> Self {
+        Self {
+            discovered,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// AirPlay receivers currently known to discovery
+    pub fn list_devices(&self) -> Vec<DiscoveredDevice> {
+        self.discovered
+            .iter()
+            .filter(|entry| entry.value().device_type == DeviceType::AirPlay)
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    pub fn is_casting(&self, device_id: &str) -> bool {
+        self.sessions.contains_key(device_id)
+    }
+
+    pub async fn cast_content(
+        &mut self,
+        device_name: &str,
+        content_type: &ContentType,
+        source: &ContentSource,
+    ) -> Result<()> {
+        let device = self.find_device(device_name)?;
+        if !self.sessions.contains_key(&device.id) {
+            self.sessions.insert(device.id.clone(), AirPlaySession {
+                client: AirPlayClient::for_device(&device)?,
+                media_tokens: Vec::new(),
+            });
+        }
+
+        let Some(session) = self.sessions.get_mut(&device.id) else {
+            return Err(CasterError::Network("Device not connected".into()));
+        };
+
+        if let ContentType::Image { .. } = content_type {
+            // Photos are uploaded rather than fetched by the receiver
+            let data = image_bytes(source).await?;
+            session.client.photo(data).await?;
+            session.revoke_media();
+            info!("Sent photo to AirPlay receiver {}", device.name);
+            return Ok(());
+        }
+
+        let (media_url, _, media_token) = resolve_media(content_type, source)?;
+
+        if let Err(e) = session.client.play(&media_url).await {
+            if let Some(token) = media_token {
+                media_registry().revoke(&token);
+            }
+            return Err(e);
+        }
+
+        // The previous item's files are no longer needed once the new one is playing
+        session.revoke_media();
+        session.media_tokens.extend(media_token);
+
+        info!("Casting {} to AirPlay receiver {}", media_url, device.name);
+        Ok(())
+    }
+
+    pub async fn stop_casting(&mut self, device_name: &str) -> Result<()> {
+        let device = self.find_device(device_name)?;
+        let Some(mut session) = self.sessions.remove(&device.id) else {
+            return Ok(());
+        };
+
+        session.revoke_media();
+        session.client.stop().await
+    }
+
+    pub async fn get_device_status(&self, device_name: &str) -> Result<serde_json::Value> {
+        let device = self.find_device(device_name)?;
+        let Some(session) = self.sessions.get(&device.id) else {
+            return Ok(json!({
+                "device_id": device.id,
+                "device_name": device.name,
+                "casting": false
+            }));
+        };
+
+        let mut status = session.client.playback_info().await?;
+        status["device_id"] = json!(device.id);
+        status["device_name"] = json!(device.name);
+        status["casting"] = json!(true);
+        Ok(status)
+    }
+
+    /// Look up a discovered AirPlay receiver by discovery id or friendly name
+    fn find_device(&self, id_or_name: &str) -> Result<DiscoveredDevice> {
+        if let Some(entry) = self.discovered.get(id_or_name) {
+            if entry.device_type == DeviceType::AirPlay {
+                return Ok(entry.value().clone());
+            }
+        }
+
+        self.discovered
+            .iter()
+            .find(|entry| entry.device_type == DeviceType::AirPlay && entry.name == id_or_name)
+            .map(|entry| entry.value().clone())
+            .ok_or_else(|| CasterError::NotFound(format!("AirPlay device {} not found", id_or_name)))
+    }
+}
+
+/// Image data for `/photo`, read locally or fetched on the receiver's behalf
+async fn image_bytes(source: &ContentSource) -> Result<Vec<u8>> {
+    match source {
+        ContentSource::File { path } => tokio::fs::read(path).await
+            .map_err(|e| CasterError::Network(format!("Failed to read {}: {}", path, e))),
+        ContentSource::Url { url } => {
+            debug!("Fetching {} for AirPlay photo", url);
+            let response = reqwest::get(url).await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| CasterError::Network(format!("Failed to fetch {}: {}", url, e)))?;
+            let data = response.bytes().await
+                .map_err(|e| CasterError::Network(format!("Failed to fetch {}: {}", url, e)))?;
+            Ok(data.to_vec())
+        }
+        ContentSource::Memory { data } => Ok(data.clone()),
+        ContentSource::Cache { .. } => Err(CasterError::Network("Unsupported content source for casting".into())),
+    }
+}
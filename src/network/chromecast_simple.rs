@@ -1,86 +1,1391 @@
+use rust_cast::channels::media::{GenericMediaMetadata, IdleReason, Media, Metadata, PlayerState, StreamType};
+use rust_cast::channels::receiver::{Application, CastDeviceApp};
+use rust_cast::CastDevice;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
 use serde_json::json;
-use tracing::info;
+use tracing::{info, debug, warn};
 
 use crate::{Result, CasterError, ContentType, ContentSource};
+use crate::server::sse::{notify_chromecast_connection, notify_chromecast_status, notify_error};
+use super::discovery::{DeviceType, DiscoveredDevice};
+use super::media_server::{publish_file, resolve_media};
+use super::media_registry;
+
+/// Default transport id of the platform receiver on every Cast device
+const DEFAULT_RECEIVER: &str = "receiver-0";
+
+/// Heartbeat interval; devices drop silent senders after ~10s
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A ping that hasn't gone through by now means the connection is dead
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often media status is polled and pushed over SSE while casting
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Title shown on the TV for everything we cast
+const MEDIA_TITLE: &str = "Q8-Caster Media";
+
+/// How long to wait for media to show up in status after a raw LOAD/QUEUE_LOAD
+const RAW_LOAD_POLLS: u32 = 10;
+const RAW_LOAD_POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Media namespace, for the messages rust_cast doesn't wrap (queues, text tracks)
+const MEDIA_NAMESPACE: &str = "urn:x-cast:com.google.cast.media";
+
+/// Request ids for our raw media messages, well clear of rust_cast's own counter
+static MEDIA_REQUEST_ID: AtomicI32 = AtomicI32::new(1_000_000);
+
+/// Consecutive failed status polls, on a healthy connection, before polling stops
+const MAX_POLL_FAILURES: u32 = 3;
+
+/// How hard to try before giving up on a device, e.g. one that's rebooting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// Delay before the second attempt; doubles after each failure
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 8000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay after the given failed attempt (1-based)
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        Duration::from_millis(self.initial_backoff_ms.saturating_mul(factor).min(self.max_backoff_ms))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionState {
+    Disconnected,
+    Connecting,
+    Connected,
+    /// Retries ran out; `last_error` says why
+    Failed,
+}
+
+/// One item of a cast queue
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub url: String,
+    pub content_type: String,
+}
+
+#[derive(Debug, Clone)]
+struct QueuedItem {
+    entry: QueueEntry,
+    live: bool,
+    // Media server token for a local file
+    token: Option<String>,
+}
+
+impl QueuedItem {
+    /// A QUEUE_LOAD/QUEUE_INSERT item
+    fn queue_json(&self) -> serde_json::Value {
+        let stream_type = if self.live { StreamType::Live } else { StreamType::Buffered };
+        json!({
+            "media": raw_media(&self.entry.url, &self.entry.content_type, &stream_type),
+            "autoplay": true,
+        })
+    }
+}
+
+/// Publish each item for the device to fetch, revoking them all if one can't be
+fn queue_items(items: &[(ContentType, ContentSource)]) -> Result<Vec<QueuedItem>> {
+    let mut queued: Vec<QueuedItem> = Vec::new();
+    for (content_type, source) in items {
+        match resolve_media(content_type, source) {
+            Ok((url, media_type, token)) => queued.push(QueuedItem {
+                entry: QueueEntry { url, content_type: media_type },
+                live: matches!(content_type, ContentType::Stream { .. }),
+                token,
+            }),
+            Err(e) => {
+                for token in queued.into_iter().filter_map(|item| item.token) {
+                    media_registry().revoke(&token);
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(queued)
+}
+
+/// Our copy of the receiver's media queue, which plays and advances it by
+/// itself. Shared with the status poller, which follows the receiver along.
+#[derive(Debug, Default)]
+struct CastQueue {
+    items: Vec<QueuedItem>,
+    /// Index of the item playing now; None before the queue starts
+    position: Option<usize>,
+    /// Whether anything is playing that the queue should follow
+    playing: bool,
+}
+
+impl CastQueue {
+    /// Whether the receiver has items left to play after the current one
+    fn has_next(&self) -> bool {
+        self.position.map_or(!self.items.is_empty(), |position| position + 1 < self.items.len())
+    }
+
+    /// Note that the receiver is playing `url`; true if that's a different item
+    fn follow(&mut self, url: &str) -> bool {
+        let from = self.position.unwrap_or(0);
+        let found = self.items.iter().skip(from).position(|item| item.entry.url == url).map(|index| from + index)
+            .or_else(|| self.items.iter().position(|item| item.entry.url == url));
+
+        match found {
+            Some(index) if Some(index) != self.position => {
+                self.position = Some(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// QUEUE_LOAD of every item, starting the receiver at `start` so its
+    /// indices line up with ours
+    fn load_message(&self, start: usize) -> serde_json::Value {
+        json!({
+            "type": "QUEUE_LOAD",
+            "items": self.items.iter().map(QueuedItem::queue_json).collect::<Vec<_>>(),
+            "startIndex": start,
+            "repeatMode": "REPEAT_OFF",
+        })
+    }
+
+    /// Drop every item, revoking their files
+    fn clear(&mut self) {
+        for item in self.items.drain(..) {
+            if let Some(token) = item.token {
+                media_registry().revoke(&token);
+            }
+        }
+        self.position = None;
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "position": self.position,
+            "length": self.items.len(),
+            "items": self.items.iter().map(|item| &item.entry).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// A WebVTT subtitle track to cast alongside media
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtitleTrack {
+    /// WebVTT URL, or a local `.vtt` file to serve through the media server
+    pub url: String,
+    pub language: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Show this track as soon as playback starts
+    #[serde(default)]
+    pub default: bool,
+}
+
+/// A text track loaded on the device
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveTrack {
+    pub track_id: i32,
+    pub url: String,
+    pub language: String,
+    pub label: String,
+    pub active: bool,
+}
+
+/// The CASTv2 connection, shared with the heartbeat and status tasks so a
+/// dropped connection can be replaced mid-session
+struct CastLink {
+    cast_device: Option<Arc<CastDevice<'static>>>,
+    state: ConnectionState,
+    last_error: Option<String>,
+    // App transport to rejoin after reconnecting
+    app_transport: Option<String>,
+    // Changes with every LOAD, including queue auto-advance
+    media_session_id: Option<i32>,
+}
 
 #[derive(Clone)]
 pub struct ChromecastDevice {
+    /// Same id `DeviceDiscovery` uses for this device
+    pub id: String,
     pub name: String,
     pub ip: IpAddr,
     pub port: u16,
-    pub connected: bool,
+    link: Arc<Mutex<CastLink>>,
+    // Keeps `link` alive and reconnects it when pings stop getting through
+    heartbeat_task: Option<tokio::task::AbortHandle>,
+    // Receiver app launched for the current cast
+    app: Option<Application>,
+    // Local files published for the current cast
+    media_tokens: Vec<String>,
+    // Background media status poller for the current cast
+    status_task: Option<tokio::task::AbortHandle>,
+    // Survives reconnects, but not `stop_casting` or a direct cast
+    queue: Arc<Mutex<CastQueue>>,
+    // Text tracks loaded with the current media
+    tracks: Vec<ActiveTrack>,
+}
+
+impl ChromecastDevice {
+    fn from_discovered(device: &DiscoveredDevice) -> Self {
+        Self {
+            id: device.id.clone(),
+            name: device.name.clone(),
+            ip: device.ip,
+            port: device.port,
+            link: Arc::new(Mutex::new(CastLink {
+                cast_device: None,
+                state: ConnectionState::Disconnected,
+                last_error: None,
+                app_transport: None,
+                media_session_id: None,
+            })),
+            heartbeat_task: None,
+            app: None,
+            media_tokens: Vec::new(),
+            status_task: None,
+            queue: Arc::new(Mutex::new(CastQueue::default())),
+            tracks: Vec::new(),
+        }
+    }
+
+    /// Live connection, if there is one right now
+    fn cast_device(&self) -> Option<Arc<CastDevice<'static>>> {
+        self.link.lock().unwrap().cast_device.clone()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint {
+            name: self.name.clone(),
+            host: self.ip.to_string(),
+            port: self.port,
+        }
+    }
+
+    fn stop_heartbeat(&mut self) {
+        if let Some(task) = self.heartbeat_task.take() {
+            task.abort();
+        }
+    }
+
+    fn media_session_id(&self) -> Option<i32> {
+        self.link.lock().unwrap().media_session_id
+    }
+
+    pub fn connection_state(&self) -> (ConnectionState, Option<String>) {
+        let link = self.link.lock().unwrap();
+        (link.state, link.last_error.clone())
+    }
+
+    fn revoke_media(&mut self) {
+        for token in self.media_tokens.drain(..) {
+            media_registry().revoke(&token);
+        }
+    }
+
+    fn stop_status_polling(&mut self) {
+        if let Some(task) = self.status_task.take() {
+            task.abort();
+        }
+    }
+
+    /// Swap in a newly launched app session, dropping the previous cast's files and poller
+    fn start_session(&mut self, app: Application, media_session_id: Option<i32>, media_tokens: Vec<String>) {
+        self.revoke_media();
+        self.media_tokens = media_tokens;
+        self.stop_status_polling();
+        {
+            let mut link = self.link.lock().unwrap();
+            link.app_transport = Some(app.transport_id.clone());
+            link.media_session_id = media_session_id;
+        }
+        self.queue.lock().unwrap().playing = true;
+        self.status_task = Some(spawn_status_poller(
+            self.name.clone(),
+            Arc::downgrade(&self.link),
+            Arc::clone(&self.queue),
+            app.transport_id.clone(),
+        ));
+        self.app = Some(app);
+        self.tracks.clear();
+    }
 }
 
+/// Connection and casting state for Chromecasts found by `DeviceDiscovery`
 pub struct ChromecastManager {
+    discovered: Arc<DashMap<String, DiscoveredDevice>>,
     devices: Vec<ChromecastDevice>,
+    retry_policy: RetryPolicy,
 }
 
 impl ChromecastManager {
-    pub fn new() -> Self {
+    pub fn new(discovered: Arc<DashMap<String, DiscoveredDevice>>) -> Self {
         Self {
+            discovered,
             devices: Vec::new(),
+            retry_policy: RetryPolicy::default(),
         }
     }
-    
-    pub async fn discover_devices(&mut self) -> Result<Vec<ChromecastDevice>> {
-        info!("Discovering Chromecast devices...");
-        
-        // Simulated discovery for now
-        // TODO: Implement actual mDNS discovery
-        
-        Ok(self.devices.clone())
+
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
     }
-    
+
+    /// Chromecasts currently known to discovery
+    pub fn list_devices(&self) -> Vec<DiscoveredDevice> {
+        self.discovered
+            .iter()
+            .filter(|entry| entry.value().device_type == DeviceType::Chromecast)
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    pub fn is_connected(&self, device_id: &str) -> bool {
+        self.devices.iter().any(|d| d.id == device_id && d.connection_state().0 == ConnectionState::Connected)
+    }
+
+    /// Connect, retrying with backoff per the retry policy; the connection is
+    /// then kept alive and re-established if it drops
     pub async fn connect_to_device(&mut self, device_name: &str) -> Result<()> {
-        if let Some(device) = self.devices.iter_mut().find(|d| d.name == device_name) {
-            device.connected = true;
-            info!("Connected to Chromecast: {}", device_name);
-            Ok(())
-        } else {
-            Err(CasterError::Network(format!("Device {} not found", device_name)))
+        let policy = self.retry_policy.clone();
+        let device = self.find_device_mut(device_name)?;
+        if device.cast_device().is_some() {
+            return Ok(());
+        }
+
+        info!("Connecting to Chromecast {} at {}:{}", device.name, device.ip, device.port);
+
+        let endpoint = device.endpoint();
+        device.stop_heartbeat();
+        connect_with_retry(&endpoint, &device.link, &policy).await?;
+        device.heartbeat_task = Some(spawn_heartbeat(endpoint, Arc::downgrade(&device.link), policy));
+
+        info!("Connected to Chromecast: {}", device_name);
+        Ok(())
+    }
+
+    /// Drop the current connection and dial the device again
+    pub async fn reconnect(&mut self, device_name: &str) -> Result<()> {
+        let device = self.find_device_mut(device_name)?;
+        device.stop_heartbeat();
+        {
+            let mut link = device.link.lock().unwrap();
+            link.cast_device = None;
+            link.state = ConnectionState::Disconnected;
         }
+        self.connect_to_device(device_name).await
     }
-    
+
+    /// Whether the device's connection is gone, i.e. missing or not answering pings
+    async fn connection_lost(&self, device_name: &str) -> bool {
+        let Some(device) = self.find_device(device_name) else {
+            return false;
+        };
+        match device.cast_device() {
+            Some(cast_device) => !ping(cast_device).await,
+            None => true,
+        }
+    }
+
     pub async fn cast_content(
         &mut self,
         device_name: &str,
         content_type: &ContentType,
-        _source: &ContentSource,  // Future me: This will stream amazing content!
+        source: &ContentSource,
+    ) -> Result<()> {
+        self.cast_content_with_subtitles(device_name, content_type, source, &[]).await
+    }
+
+    /// Cast with WebVTT subtitle tracks; the first track marked default is switched on
+    pub async fn cast_content_with_subtitles(
+        &mut self,
+        device_name: &str,
+        content_type: &ContentType,
+        source: &ContentSource,
+        subtitles: &[SubtitleTrack],
+    ) -> Result<()> {
+        match self.cast_once(device_name, content_type, source, subtitles).await {
+            Err(e) if self.connection_lost(device_name).await => {
+                // The connection died under us; one fresh connection, then one more try
+                warn!("Lost connection to {} while casting ({}), reconnecting", device_name, e);
+                self.reconnect(device_name).await?;
+                self.cast_once(device_name, content_type, source, subtitles).await
+            }
+            result => result,
+        }
+    }
+
+    async fn cast_once(
+        &mut self,
+        device_name: &str,
+        content_type: &ContentType,
+        source: &ContentSource,
+        subtitles: &[SubtitleTrack],
+    ) -> Result<()> {
+        // Casting implies connecting
+        self.connect_to_device(device_name).await?;
+
+        if !subtitles.is_empty() {
+            return self.cast_with_tracks(device_name, content_type, source, subtitles).await;
+        }
+
+        let (media_url, media_type, media_token) = resolve_media(content_type, source)?;
+
+        let device = self.find_device_mut(device_name)?;
+        let Some(cast_device) = device.cast_device() else {
+            if let Some(token) = media_token {
+                media_registry().revoke(&token);
+            }
+            return Err(CasterError::Network("Device not connected".into()));
+        };
+
+        let media = cast_media(media_url, media_type, stream_type(content_type));
+        let result = launch_and_load(cast_device, media).await;
+
+        let (app, media_session_id) = match result {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                if let Some(token) = media_token {
+                    media_registry().revoke(&token);
+                }
+                return Err(e);
+            }
+        };
+
+        debug!("Launched media receiver app {} (session {})", app.display_name, app.session_id);
+
+        // The previous item's files are no longer needed once the new one is loaded
+        device.start_session(app, media_session_id, media_token.into_iter().collect());
+        device.queue.lock().unwrap().clear();
+
+        info!("Successfully cast content to {}", device_name);
+        Ok(())
+    }
+
+    // rust_cast's `Media` has no tracks, so subtitled media goes out as a raw LOAD
+    async fn cast_with_tracks(
+        &mut self,
+        device_name: &str,
+        content_type: &ContentType,
+        source: &ContentSource,
+        subtitles: &[SubtitleTrack],
+    ) -> Result<()> {
+        let (media_url, media_type, media_token) = resolve_media(content_type, source)?;
+        let mut tokens: Vec<String> = media_token.into_iter().collect();
+
+        let mut tracks = Vec::new();
+        for (index, subtitle) in subtitles.iter().enumerate() {
+            let url = if subtitle.url.starts_with("http://") || subtitle.url.starts_with("https://") {
+                subtitle.url.clone()
+            } else {
+                match publish_file(&subtitle.url) {
+                    Ok((url, token)) => {
+                        tokens.push(token);
+                        url
+                    }
+                    Err(e) => {
+                        for token in &tokens {
+                            media_registry().revoke(token);
+                        }
+                        return Err(e);
+                    }
+                }
+            };
+
+            tracks.push(ActiveTrack {
+                track_id: index as i32 + 1,
+                url,
+                language: subtitle.language.clone(),
+                label: subtitle.label.clone().unwrap_or_else(|| subtitle.language.clone()),
+                active: false,
+            });
+        }
+        let default_track = subtitles.iter()
+            .position(|subtitle| subtitle.default)
+            .map(|index| tracks[index].track_id);
+
+        let device = self.find_device_mut(device_name)?;
+        let Some(cast_device) = device.cast_device() else {
+            for token in &tokens {
+                media_registry().revoke(token);
+            }
+            return Err(CasterError::Network("Device not connected".into()));
+        };
+
+        let mut media = media_json(&media_url, &media_type, content_type);
+        media["tracks"] = tracks.iter().map(|track| json!({
+            "trackId": track.track_id,
+            "type": "TEXT",
+            "subtype": "SUBTITLES",
+            "trackContentId": track.url,
+            "trackContentType": "text/vtt",
+            "name": track.label,
+            "language": track.language,
+        })).collect();
+
+        let load = json!({
+            "type": "LOAD",
+            "media": media,
+            "autoplay": true,
+        });
+        let (app, media_session_id) = match launch_raw_load(Arc::clone(&cast_device), load).await {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                for token in &tokens {
+                    media_registry().revoke(token);
+                }
+                return Err(e);
+            }
+        };
+
+        if let (Some(track_id), Some(media_session_id)) = (default_track, media_session_id) {
+            let edit = json!({
+                "type": "EDIT_TRACKS_INFO",
+                "requestId": MEDIA_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+                "mediaSessionId": media_session_id,
+                "activeTrackIds": [track_id],
+            });
+            match send_media_message(cast_device, edit).await {
+                Ok(()) => {
+                    if let Some(track) = tracks.iter_mut().find(|track| track.track_id == track_id) {
+                        track.active = true;
+                    }
+                }
+                Err(e) => warn!("Failed to enable subtitles on {}: {}", device_name, e),
+            }
+        }
+
+        device.start_session(app, media_session_id, tokens);
+        device.queue.lock().unwrap().clear();
+        device.tracks = tracks;
+
+        info!("Successfully cast content with {} subtitle tracks to {}", device.tracks.len(), device_name);
+        Ok(())
+    }
+
+    /// Add items to the device's queue. If nothing is playing the receiver loads
+    /// the queue from the first new item; otherwise they're inserted to play once
+    /// the current item finishes. Local files are published through the media
+    /// server; URLs are cast as-is. Returns the queue length.
+    pub async fn enqueue(&mut self, device_name: &str, items: Vec<(ContentType, ContentSource)>) -> Result<usize> {
+        if items.is_empty() {
+            return Err(CasterError::Network("Queue needs at least one item".into()));
+        }
+
+        self.connect_to_device(device_name).await?;
+        let queued = queue_items(&items)?;
+
+        let device = self.find_device_mut(device_name)?;
+        let Some(cast_device) = device.cast_device() else {
+            for token in queued.into_iter().filter_map(|item| item.token) {
+                media_registry().revoke(&token);
+            }
+            return Err(CasterError::Network("Device not connected".into()));
+        };
+
+        // Append to what the receiver is playing rather than restarting playback
+        let playing = device.queue.lock().unwrap().playing;
+        if let (true, Some(media_session_id)) = (playing, device.media_session_id()) {
+            let insert = json!({
+                "type": "QUEUE_INSERT",
+                "requestId": MEDIA_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+                "mediaSessionId": media_session_id,
+                "items": queued.iter().map(QueuedItem::queue_json).collect::<Vec<_>>(),
+            });
+            if let Err(e) = send_media_message(cast_device, insert).await {
+                for token in queued.into_iter().filter_map(|item| item.token) {
+                    media_registry().revoke(&token);
+                }
+                return Err(e);
+            }
+
+            let mut queue = device.queue.lock().unwrap();
+            queue.items.extend(queued);
+            info!("Appended {} items to the queue on {}", items.len(), device_name);
+            return Ok(queue.items.len());
+        }
+
+        let (start, load) = {
+            let mut queue = device.queue.lock().unwrap();
+            let start = queue.items.len();
+            queue.items.extend(queued);
+            (start, queue.load_message(start))
+        };
+        match self.load_queue(device_name, cast_device, load, start).await {
+            Ok(()) => Ok(self.find_device_mut(device_name)?.queue.lock().unwrap().items.len()),
+            Err(e) => {
+                // Only the new items never made it to the receiver
+                let device = self.find_device_mut(device_name)?;
+                let mut queue = device.queue.lock().unwrap();
+                for token in queue.items.drain(start..).filter_map(|item| item.token) {
+                    media_registry().revoke(&token);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Replace the queue with `items` and play the first, whatever was playing before
+    pub async fn play_queue(&mut self, device_name: &str, items: Vec<(ContentType, ContentSource)>) -> Result<usize> {
+        if items.is_empty() {
+            return Err(CasterError::Network("Queue needs at least one item".into()));
+        }
+
+        self.connect_to_device(device_name).await?;
+        let queued = queue_items(&items)?;
+
+        let device = self.find_device_mut(device_name)?;
+        let Some(cast_device) = device.cast_device() else {
+            for token in queued.into_iter().filter_map(|item| item.token) {
+                media_registry().revoke(&token);
+            }
+            return Err(CasterError::Network("Device not connected".into()));
+        };
+        let (length, load) = {
+            let mut queue = device.queue.lock().unwrap();
+            queue.clear();
+            queue.items.extend(queued);
+            (queue.items.len(), queue.load_message(0))
+        };
+
+        if let Err(e) = self.load_queue(device_name, cast_device, load, 0).await {
+            self.find_device_mut(device_name)?.queue.lock().unwrap().clear();
+            return Err(e);
+        }
+        Ok(length)
+    }
+
+    /// Launch the receiver with a QUEUE_LOAD playing item `start`
+    async fn load_queue(
+        &mut self,
+        device_name: &str,
+        cast_device: Arc<CastDevice<'static>>,
+        load: serde_json::Value,
+        start: usize,
     ) -> Result<()> {
-        info!("Casting to {}: {:?}", device_name, content_type);
-        // TODO: Implement actual casting
+        let (app, media_session_id) = launch_raw_load(cast_device, load).await?;
+        if media_session_id.is_none() {
+            warn!("Queue on {} loaded without a media session yet", device_name);
+        }
+
+        // Queue items own their files, so the session doesn't take any
+        let device = self.find_device_mut(device_name)?;
+        device.start_session(app, media_session_id, Vec::new());
+        let mut queue = device.queue.lock().unwrap();
+        queue.position = Some(start);
+
+        info!("Loaded a {}-item queue on {}, playing item {}", queue.items.len(), device_name, start);
         Ok(())
     }
-    
+
+    /// Skip to the next queue item; returns the new position
+    pub async fn queue_next(&mut self, device_name: &str) -> Result<usize> {
+        self.queue_jump(device_name, 1).await
+    }
+
+    /// Go back to the previous queue item; returns the new position
+    pub async fn queue_prev(&mut self, device_name: &str) -> Result<usize> {
+        self.queue_jump(device_name, -1).await
+    }
+
+    async fn queue_jump(&mut self, device_name: &str, jump: i32) -> Result<usize> {
+        let device = self.find_device_mut(device_name)?;
+        let target = {
+            let queue = device.queue.lock().unwrap();
+            // Before the queue starts, the receiver is on whatever it was playing before it
+            let target = match queue.position {
+                Some(position) => position as i64 + jump as i64,
+                None if jump > 0 => 0,
+                None => -1,
+            };
+            (queue.playing && target >= 0 && target < queue.items.len() as i64).then_some(target as usize)
+        };
+        let Some(target) = target else {
+            return Err(CasterError::Network(format!(
+                "No {} item in the queue on {}",
+                if jump > 0 { "next" } else { "previous" },
+                device_name
+            )));
+        };
+
+        let (cast_device, _, media_session_id) = self.active_media(device_name)?;
+        let update = json!({
+            "type": "QUEUE_UPDATE",
+            "requestId": MEDIA_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+            "mediaSessionId": media_session_id,
+            "jump": jump,
+        });
+        send_media_message(cast_device, update).await?;
+
+        self.find_device_mut(device_name)?.queue.lock().unwrap().position = Some(target);
+        debug!("Moved queue on {} to item {}", device_name, target);
+        Ok(target)
+    }
+
+    /// Empty the queue; whatever is playing keeps playing
+    pub async fn clear_queue(&mut self, device_name: &str) -> Result<()> {
+        let device = self.find_device_mut(device_name)?;
+        let (pending, current) = {
+            let queue = device.queue.lock().unwrap();
+            let current = queue.position.and_then(|position| queue.items.get(position)).map(QueuedItem::queue_json);
+            (queue.playing && queue.has_next(), current)
+        };
+
+        // The receiver would go on to the rest of its queue, so reload the
+        // playing item on its own, where it is
+        if pending {
+            if let (Some(cast_device), Some(app)) = (device.cast_device(), device.app.clone()) {
+                let transport_id = app.transport_id.clone();
+                let status_device = Arc::clone(&cast_device);
+                let status = tokio::task::spawn_blocking(move || status_device.media.get_status(transport_id.as_str(), None))
+                    .await
+                    .map_err(|e| CasterError::Network(format!("Status task failed: {}", e)))?
+                    .map_err(|e| CasterError::Network(format!("Failed to get media status: {}", e)))?;
+                let entry = status.entries.first();
+                let playing = entry
+                    .and_then(|entry| entry.media.as_ref())
+                    .map(|media| json!({
+                        "media": raw_media(&media.content_id, &media.content_type, &media.stream_type),
+                        "autoplay": true,
+                    }))
+                    .or(current);
+
+                if let Some(item) = playing {
+                    let load = json!({
+                        "type": "QUEUE_LOAD",
+                        "requestId": MEDIA_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+                        "sessionId": app.session_id,
+                        "items": [item],
+                        "startIndex": 0,
+                        "currentTime": entry.and_then(|entry| entry.current_time).unwrap_or(0.0),
+                        "repeatMode": "REPEAT_OFF",
+                    });
+                    send_media_message(cast_device, load).await?;
+                    // The reload drops the text tracks that came with the media
+                    device.tracks.clear();
+                } else {
+                    warn!("Can't tell what {} is playing; its queue plays on", device_name);
+                }
+            }
+        }
+
+        let mut queue = device.queue.lock().unwrap();
+        // Keep the playing item's file served until it's replaced or stopped
+        if let Some(position) = queue.position {
+            if let Some(token) = queue.items.get_mut(position).and_then(|item| item.token.take()) {
+                device.media_tokens.push(token);
+            }
+        }
+        queue.clear();
+
+        info!("Cleared the queue on {}", device_name);
+        Ok(())
+    }
+
     pub async fn stop_casting(&mut self, device_name: &str) -> Result<()> {
-        info!("Stopping cast on {}", device_name);
+        let device = self.find_device_mut(device_name)?;
+
+        device.revoke_media();
+        device.stop_status_polling();
+        device.tracks.clear();
+        {
+            let mut queue = device.queue.lock().unwrap();
+            queue.clear();
+            queue.playing = false;
+        }
+        {
+            let mut link = device.link.lock().unwrap();
+            link.app_transport = None;
+            link.media_session_id = None;
+        }
+
+        let (Some(cast_device), Some(app)) = (device.cast_device(), device.app.take()) else {
+            info!("Nothing casting on {}", device_name);
+            return Ok(());
+        };
+
+        tokio::task::spawn_blocking(move || {
+            cast_device.receiver.stop_app(app.session_id.as_str())
+                .map_err(|e| CasterError::Network(format!("Failed to stop app: {}", e)))
+        })
+        .await
+        .map_err(|e| CasterError::Network(format!("Stop task failed: {}", e)))??;
+
+        info!("Stopped casting on {}", device_name);
+        Ok(())
+    }
+
+    pub async fn play(&self, device_name: &str) -> Result<()> {
+        let (cast_device, transport_id, media_session_id) = self.active_media(device_name)?;
+        tokio::task::spawn_blocking(move || {
+            cast_device.media.play(transport_id.as_str(), media_session_id)
+                .map_err(|e| CasterError::Network(format!("Failed to resume playback: {}", e)))
+        })
+        .await
+        .map_err(|e| CasterError::Network(format!("Play task failed: {}", e)))??;
+
+        debug!("Resumed playback on {}", device_name);
         Ok(())
     }
-    
+
+    pub async fn pause(&self, device_name: &str) -> Result<()> {
+        let (cast_device, transport_id, media_session_id) = self.active_media(device_name)?;
+        tokio::task::spawn_blocking(move || {
+            cast_device.media.pause(transport_id.as_str(), media_session_id)
+                .map_err(|e| CasterError::Network(format!("Failed to pause playback: {}", e)))
+        })
+        .await
+        .map_err(|e| CasterError::Network(format!("Pause task failed: {}", e)))??;
+
+        debug!("Paused playback on {}", device_name);
+        Ok(())
+    }
+
+    /// Seek to `position` seconds from the start of the current media
+    pub async fn seek(&self, device_name: &str, position: f32) -> Result<()> {
+        if !position.is_finite() || position < 0.0 {
+            return Err(CasterError::Network(format!("Invalid seek position: {}", position)));
+        }
+
+        let (cast_device, transport_id, media_session_id) = self.active_media(device_name)?;
+        tokio::task::spawn_blocking(move || {
+            cast_device.media.seek(transport_id.as_str(), media_session_id, Some(position), None)
+                .map_err(|e| CasterError::Network(format!("Failed to seek: {}", e)))
+        })
+        .await
+        .map_err(|e| CasterError::Network(format!("Seek task failed: {}", e)))??;
+
+        debug!("Seeked {} to {}s", device_name, position);
+        Ok(())
+    }
+
+    /// Set the device volume, 0.0-1.0
+    pub async fn set_volume(&self, device_name: &str, level: f32) -> Result<()> {
+        if !(0.0..=1.0).contains(&level) {
+            return Err(CasterError::Network(format!("Volume must be between 0 and 1, got {}", level)));
+        }
+
+        // Volume belongs to the receiver, but only matters while something is playing
+        let (cast_device, _, _) = self.active_media(device_name)?;
+        tokio::task::spawn_blocking(move || {
+            cast_device.receiver.set_volume(level)
+                .map_err(|e| CasterError::Network(format!("Failed to set volume: {}", e)))
+        })
+        .await
+        .map_err(|e| CasterError::Network(format!("Volume task failed: {}", e)))??;
+
+        debug!("Set volume on {} to {}", device_name, level);
+        Ok(())
+    }
+
     pub async fn get_device_status(&self, device_name: &str) -> Result<serde_json::Value> {
-        if let Some(device) = self.devices.iter().find(|d| d.name == device_name) {
-            Ok(json!({
-                "connected": device.connected,
-                "name": device.name,
-                "ip": device.ip.to_string(),
-            }))
-        } else {
-            Ok(json!({
-                "connected": false,
-                "error": "Device not found"
-            }))
-        }
-    }
-    
-    pub fn list_devices(&self) -> Vec<serde_json::Value> {
-        self.devices.iter().map(|device| {
-            json!({
-                "name": device.name,
-                "ip": device.ip.to_string(),
-                "port": device.port,
-                "connected": device.connected,
+        let session = self.devices.iter().find(|d| d.id == device_name || d.name == device_name);
+        let (state, last_error) = session
+            .map(ChromecastDevice::connection_state)
+            .unwrap_or((ConnectionState::Disconnected, None));
+
+        let Some(cast_device) = session.and_then(ChromecastDevice::cast_device) else {
+            return Ok(match self.find_discovered(device_name) {
+                Some(found) => json!({
+                    "connected": false,
+                    "state": state,
+                    "last_error": last_error,
+                    "id": found.id,
+                    "name": found.name,
+                    "ip": found.ip.to_string(),
+                }),
+                None => json!({
+                    "connected": false,
+                    "error": "Device not found"
+                }),
+            });
+        };
+        let Some(device) = session else {
+            return Err(CasterError::NotFound(format!("Device {} not found", device_name)));
+        };
+
+        let status = tokio::task::spawn_blocking(move || {
+            cast_device.receiver.get_status()
+                .map_err(|e| CasterError::Network(format!("Failed to get status: {}", e)))
+        })
+        .await
+        .map_err(|e| CasterError::Network(format!("Status task failed: {}", e)))??;
+
+        Ok(json!({
+            "connected": true,
+            "state": state,
+            "last_error": last_error,
+            "id": device.id,
+            "name": device.name,
+            "ip": device.ip.to_string(),
+            "volume": {
+                "level": status.volume.level,
+                "muted": status.volume.muted,
+            },
+            "is_active_input": status.is_active_input,
+            "is_stand_by": status.is_stand_by,
+            "app": status.applications.first().map(|app| json!({
+                "id": app.app_id,
+                "name": app.display_name,
+                "status": app.status_text,
+                "session_id": app.session_id,
+            })),
+            "media_session_id": device.media_session_id(),
+            "queue": device.queue.lock().unwrap().to_json(),
+            "tracks": device.tracks,
+        }))
+    }
+
+    /// Connection, transport id and media session of the current cast
+    fn active_media(&self, id_or_name: &str) -> Result<(Arc<CastDevice<'static>>, String, i32)> {
+        let casting = self.find_device(id_or_name).and_then(|device| {
+            Some((device.cast_device()?, device.app.as_ref()?, device.media_session_id()?))
+        });
+        let Some((cast_device, app, media_session_id)) = casting else {
+            return Err(CasterError::NotCasting(format!("{} has no active media session", id_or_name)));
+        };
+
+        Ok((cast_device, app.transport_id.clone(), media_session_id))
+    }
+
+    /// Existing session state for a device, without creating one
+    fn find_device(&self, id_or_name: &str) -> Option<&ChromecastDevice> {
+        self.devices.iter()
+            .find(|d| d.id == id_or_name || d.name == id_or_name)
+            .or_else(|| {
+                let found = self.find_discovered(id_or_name)?;
+                self.devices.iter().find(|d| d.id == found.id)
+            })
+    }
+
+    /// Look up a discovered Chromecast by discovery id or friendly name
+    fn find_discovered(&self, id_or_name: &str) -> Option<DiscoveredDevice> {
+        if let Some(entry) = self.discovered.get(id_or_name) {
+            if entry.device_type == DeviceType::Chromecast {
+                return Some(entry.value().clone());
+            }
+        }
+
+        self.discovered
+            .iter()
+            .find(|entry| entry.device_type == DeviceType::Chromecast && entry.name == id_or_name)
+            .map(|entry| entry.value().clone())
+    }
+
+    /// Session state for a device, created from discovery on first use.
+    /// Sessions outlive stale-device cleanup so live connections aren't lost.
+    fn find_device_mut(&mut self, id_or_name: &str) -> Result<&mut ChromecastDevice> {
+        let id = match self.find_discovered(id_or_name) {
+            Some(found) => {
+                match self.devices.iter_mut().find(|d| d.id == found.id) {
+                    Some(existing) => {
+                        existing.name = found.name.clone();
+                        existing.ip = found.ip;
+                        existing.port = found.port;
+                    }
+                    None => self.devices.push(ChromecastDevice::from_discovered(&found)),
+                }
+                found.id
+            }
+            None => id_or_name.to_string(),
+        };
+
+        self.devices.iter_mut()
+            .find(|d| d.id == id || d.name == id)
+            .ok_or_else(|| CasterError::NotFound(format!("Device {} not found", id_or_name)))
+    }
+}
+
+fn stream_type(content_type: &ContentType) -> StreamType {
+    match content_type {
+        ContentType::Stream { .. } => StreamType::Live,
+        _ => StreamType::Buffered,
+    }
+}
+
+/// The `media` object of a raw LOAD, mirroring what rust_cast sends for `Media`
+fn media_json(url: &str, media_type: &str, content_type: &ContentType) -> serde_json::Value {
+    raw_media(url, media_type, &stream_type(content_type))
+}
+
+fn raw_media(url: &str, media_type: &str, stream_type: &StreamType) -> serde_json::Value {
+    let stream_type = match stream_type {
+        StreamType::Live => "LIVE",
+        _ => "BUFFERED",
+    };
+    json!({
+        "contentId": url,
+        "contentType": media_type,
+        "streamType": stream_type,
+        "metadata": {
+            "metadataType": 0,
+            "title": MEDIA_TITLE,
+        },
+    })
+}
+
+fn cast_media(url: String, media_type: String, stream_type: StreamType) -> Media {
+    Media {
+        content_id: url,
+        content_type: media_type,
+        stream_type,
+        metadata: Some(Metadata::Generic(GenericMediaMetadata {
+            title: Some(MEDIA_TITLE.to_string()),
+            subtitle: None,
+            images: Vec::new(),
+            release_date: None,
+        })),
+        duration: None,
+    }
+}
+
+/// Launch the default media receiver and LOAD `media` into it
+async fn launch_and_load(cast_device: Arc<CastDevice<'static>>, media: Media) -> Result<(Application, Option<i32>)> {
+    tokio::task::spawn_blocking(move || -> Result<(Application, Option<i32>)> {
+        let app = cast_device.receiver
+            .launch_app(&CastDeviceApp::DefaultMediaReceiver)
+            .map_err(|e| CasterError::Network(format!("Failed to launch app: {}", e)))?;
+
+        cast_device.connection.connect(app.transport_id.as_str())
+            .map_err(|e| CasterError::Network(format!("Failed to connect to app: {}", e)))?;
+
+        let status = cast_device.media
+            .load(app.transport_id.as_str(), app.session_id.as_str(), &media)
+            .map_err(|e| CasterError::Network(format!("Failed to load media: {}", e)))?;
+
+        let media_session_id = status.entries.first().map(|entry| entry.media_session_id);
+        Ok((app, media_session_id))
+    })
+    .await
+    .map_err(|e| CasterError::Network(format!("Cast task failed: {}", e)))?
+}
+
+/// Launch the default media receiver and send it a raw LOAD.
+///
+/// Raw messages get no reply, so the media session id comes from polling
+/// status until the media shows up; None if it never does.
+async fn launch_raw_load(
+    cast_device: Arc<CastDevice<'static>>,
+    mut message: serde_json::Value,
+) -> Result<(Application, Option<i32>)> {
+    tokio::task::spawn_blocking(move || -> Result<(Application, Option<i32>)> {
+        let app = cast_device.receiver
+            .launch_app(&CastDeviceApp::DefaultMediaReceiver)
+            .map_err(|e| CasterError::Network(format!("Failed to launch app: {}", e)))?;
+
+        cast_device.connection.connect(app.transport_id.as_str())
+            .map_err(|e| CasterError::Network(format!("Failed to connect to app: {}", e)))?;
+
+        message["requestId"] = json!(MEDIA_REQUEST_ID.fetch_add(1, Ordering::Relaxed));
+        message["sessionId"] = json!(app.session_id);
+        cast_device.receiver.broadcast_message(MEDIA_NAMESPACE, &message)
+            .map_err(|e| CasterError::Network(format!("Failed to load media: {}", e)))?;
+
+        for _ in 0..RAW_LOAD_POLLS {
+            std::thread::sleep(RAW_LOAD_POLL_INTERVAL);
+            let status = cast_device.media.get_status(app.transport_id.as_str(), None)
+                .map_err(|e| CasterError::Network(format!("Failed to get media status: {}", e)))?;
+            if let Some(entry) = status.entries.first() {
+                return Ok((app, Some(entry.media_session_id)));
+            }
+        }
+        Ok((app, None))
+    })
+    .await
+    .map_err(|e| CasterError::Network(format!("Load task failed: {}", e)))?
+}
+
+/// Send a raw message on the media namespace
+async fn send_media_message(cast_device: Arc<CastDevice<'static>>, message: serde_json::Value) -> Result<()> {
+    tokio::task::spawn_blocking(move || {
+        cast_device.receiver.broadcast_message(MEDIA_NAMESPACE, &message)
+            .map_err(|e| CasterError::Network(format!("Failed to send media message: {}", e)))
+    })
+    .await
+    .map_err(|e| CasterError::Network(format!("Media message task failed: {}", e)))?
+}
+
+/// Where to (re)connect to a device
+struct Endpoint {
+    name: String,
+    host: String,
+    port: u16,
+}
+
+/// Open a connection and receiver channel, rejoining `app_transport` if a cast is running
+pub(crate) fn open_connection(host: String, port: u16, app_transport: Option<String>) -> Result<CastDevice<'static>> {
+    let cast_device = CastDevice::connect_without_host_verification(host, port)
+        .map_err(|e| CasterError::Network(format!("Failed to connect to Chromecast: {}", e)))?;
+    cast_device.connection.connect(DEFAULT_RECEIVER)
+        .map_err(|e| CasterError::Network(format!("Failed to open receiver channel: {}", e)))?;
+    cast_device.heartbeat.ping()
+        .map_err(|e| CasterError::Network(format!("Heartbeat failed: {}", e)))?;
+    if let Some(transport_id) = app_transport {
+        cast_device.connection.connect(transport_id)
+            .map_err(|e| CasterError::Network(format!("Failed to rejoin app: {}", e)))?;
+    }
+    Ok(cast_device)
+}
+
+/// Connect with exponential backoff, tracking progress in `link`.
+/// Broadcasts an error event when every attempt fails.
+async fn connect_with_retry(endpoint: &Endpoint, link: &Mutex<CastLink>, policy: &RetryPolicy) -> Result<()> {
+    let app_transport = {
+        let mut link = link.lock().unwrap();
+        link.state = ConnectionState::Connecting;
+        link.cast_device = None;
+        link.app_transport.clone()
+    };
+
+    let attempts = policy.max_attempts.max(1);
+    let mut last_error = None;
+
+    for attempt in 1..=attempts {
+        let host = endpoint.host.clone();
+        let port = endpoint.port;
+        let app_transport = app_transport.clone();
+        let result = tokio::task::spawn_blocking(move || open_connection(host, port, app_transport))
+            .await
+            .map_err(|e| CasterError::Network(format!("Connect task failed: {}", e)))
+            .and_then(|r| r);
+
+        match result {
+            Ok(cast_device) => {
+                {
+                    let mut link = link.lock().unwrap();
+                    link.cast_device = Some(Arc::new(cast_device));
+                    link.state = ConnectionState::Connected;
+                    link.last_error = None;
+                }
+                notify_chromecast_connection(endpoint.name.clone(), "connected".into(), attempt, None);
+                return Ok(());
+            }
+            Err(e) => {
+                warn!("Connection attempt {}/{} to {} failed: {}", attempt, attempts, endpoint.name, e);
+                link.lock().unwrap().last_error = Some(e.to_string());
+                last_error = Some(e);
+            }
+        }
+
+        if attempt < attempts {
+            tokio::time::sleep(policy.backoff(attempt)).await;
+        }
+    }
+
+    link.lock().unwrap().state = ConnectionState::Failed;
+    let e = last_error.unwrap_or_else(|| CasterError::Network("No connection attempts made".into()));
+    notify_error(format!("Giving up on Chromecast {} after {} attempts: {}", endpoint.name, attempts, e));
+    notify_chromecast_connection(endpoint.name.clone(), "failed".into(), attempts, Some(e.to_string()));
+    Err(e)
+}
+
+/// Keep the CASTv2 connection alive, reconnecting if it drops, until the
+/// device is dropped or reconnecting fails
+fn spawn_heartbeat(endpoint: Endpoint, link: Weak<Mutex<CastLink>>, policy: RetryPolicy) -> tokio::task::AbortHandle {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Some(link) = link.upgrade() else {
+                break;
+            };
+            let Some(cast_device) = link.lock().unwrap().cast_device.clone() else {
+                break;
+            };
+
+            if ping(cast_device).await {
+                continue;
+            }
+
+            warn!("Heartbeat to {} failed, reconnecting", endpoint.name);
+            {
+                let mut link = link.lock().unwrap();
+                link.cast_device = None;
+                link.state = ConnectionState::Disconnected;
+            }
+            notify_chromecast_connection(endpoint.name.clone(), "disconnected".into(), 0, None);
+
+            if connect_with_retry(&endpoint, &link, &policy).await.is_err() {
+                break;
+            }
+            info!("Reconnected to Chromecast: {}", endpoint.name);
+        }
+    })
+    .abort_handle()
+}
+
+/// Heartbeat ping that has to get through within `HEARTBEAT_TIMEOUT`
+async fn ping(cast_device: Arc<CastDevice<'static>>) -> bool {
+    let ping = tokio::task::spawn_blocking(move || cast_device.heartbeat.ping());
+    matches!(tokio::time::timeout(HEARTBEAT_TIMEOUT, ping).await, Ok(Ok(Ok(()))))
+}
+
+/// Poll media status for the running app and push it over SSE, following
+/// the receiver through its queue.
+///
+/// Stops when the device connection goes away, the poll fails, or the
+/// player goes IDLE with nothing queued because playback finished, was
+/// stopped or errored.
+fn spawn_status_poller(
+    device_name: String,
+    link: Weak<Mutex<CastLink>>,
+    queue: Arc<Mutex<CastQueue>>,
+    transport_id: String,
+) -> tokio::task::AbortHandle {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(STATUS_POLL_INTERVAL);
+        let mut duration = None;
+        let mut failures = 0;
+
+        loop {
+            interval.tick().await;
+
+            let Some(link) = link.upgrade() else {
+                break;
+            };
+            let (cast_device, state) = {
+                let link = link.lock().unwrap();
+                (link.cast_device.clone(), link.state)
+            };
+            let Some(cast_device) = cast_device else {
+                // Reconnecting; pick up again once the heartbeat restores the connection
+                if state == ConnectionState::Connecting {
+                    continue;
+                }
+                break;
+            };
+
+            let transport_id = transport_id.clone();
+            let polled = tokio::task::spawn_blocking(move || {
+                let media = cast_device.media.get_status(transport_id.as_str(), None)?;
+                let receiver = cast_device.receiver.get_status()?;
+                Ok::<_, rust_cast::errors::Error>((media, receiver.volume.level))
             })
-        }).collect()
+            .await;
+
+            let (media_status, volume) = match polled {
+                Ok(Ok(status)) => {
+                    failures = 0;
+                    status
+                }
+                Ok(Err(e)) => {
+                    // A dropped connection is the heartbeat's to restore
+                    if link.lock().unwrap().state == ConnectionState::Connecting {
+                        continue;
+                    }
+                    failures += 1;
+                    warn!("Media status poll for {} failed ({}/{}): {}", device_name, failures, MAX_POLL_FAILURES, e);
+                    if failures >= MAX_POLL_FAILURES {
+                        break;
+                    }
+                    continue;
+                }
+                Err(_) => break,
+            };
+
+            let Some(entry) = media_status.entries.first() else {
+                continue;
+            };
+
+            // The receiver moves through its queue by itself; keep up with it
+            link.lock().unwrap().media_session_id = Some(entry.media_session_id);
+            if let Some(media) = &entry.media {
+                if queue.lock().unwrap().follow(&media.content_id) {
+                    debug!("{} moved on to {}", device_name, media.content_id);
+                    duration = None;
+                }
+                // Duration is only reported when the media changes, so remember it
+                if let Some(d) = media.duration {
+                    duration = Some(d);
+                }
+            }
+
+            let state = entry.player_state.to_string();
+            notify_chromecast_status(device_name.clone(), state, entry.current_time, duration, volume);
+
+            // IDLE without a reason just means the player hasn't started yet
+            if !matches!(entry.player_state, PlayerState::Idle) || entry.idle_reason.is_none() {
+                continue;
+            }
+
+            // Finishing an item with more queued is the receiver loading the next
+            if matches!(entry.idle_reason, Some(IdleReason::Finished)) && queue.lock().unwrap().has_next() {
+                continue;
+            }
+
+            debug!("{} went idle, stopping status polling", device_name);
+            break;
+        }
+
+        queue.lock().unwrap().playing = false;
+    })
+    .abort_handle()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue_of(urls: &[&str]) -> CastQueue {
+        CastQueue {
+            items: urls.iter()
+                .map(|url| QueuedItem {
+                    entry: QueueEntry { url: url.to_string(), content_type: "video/mp4".into() },
+                    live: false,
+                    token: None,
+                })
+                .collect(),
+            position: None,
+            playing: true,
+        }
+    }
+
+    #[test]
+    fn the_queue_follows_the_receiver_through_repeated_items() {
+        let mut queue = queue_of(&["http://host/a.mp4", "http://host/b.mp4", "http://host/a.mp4"]);
+
+        assert!(queue.follow("http://host/a.mp4"));
+        assert_eq!(queue.position, Some(0));
+        assert!(!queue.follow("http://host/a.mp4"));
+        assert!(queue.follow("http://host/b.mp4"));
+        assert!(queue.follow("http://host/a.mp4"));
+        assert_eq!(queue.position, Some(2));
+        assert!(!queue.has_next());
+        assert!(!queue.follow("http://elsewhere/c.mp4"));
+        assert_eq!(queue.position, Some(2));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn queue_loads_start_the_receiver_where_the_queue_is() {
+        let queue = queue_of(&["http://host/a.mp4", "http://host/b.mp4"]);
+        let load = queue.load_message(1);
+
+        assert_eq!(load["type"], "QUEUE_LOAD");
+        assert_eq!(load["startIndex"], 1);
+        assert_eq!(load["items"][1]["media"]["contentId"], "http://host/b.mp4");
+        assert_eq!(load["items"][1]["media"]["streamType"], "BUFFERED");
+    }
+}
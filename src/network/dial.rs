@@ -0,0 +1,152 @@
+use reqwest::{header, StatusCode};
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::{Result, CasterError};
+use super::discovery::DiscoveredDevice;
+
+/// DIAL servers are expected to answer quickly; a hung TV shouldn't stall a request
+const DIAL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a DIAL server said to a launch request
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DialLaunch {
+    /// App is starting; the instance URL can be DELETEd to stop it
+    Launched { instance_url: Option<String> },
+    /// The device refused the launch (403), usually because the app disallows DIAL
+    Forbidden { message: String },
+    /// The device doesn't know the app (404)
+    NotFound { message: String },
+}
+
+impl DialLaunch {
+    /// Rejections are reported in the body so clients can tell them apart from transport failures
+    pub fn to_json(&self, device_id: &str, app_name: &str) -> serde_json::Value {
+        match self {
+            DialLaunch::Launched { instance_url } => serde_json::json!({
+                "success": true,
+                "device_id": device_id,
+                "app_name": app_name,
+                "instance_url": instance_url
+            }),
+            rejected => serde_json::json!({
+                "success": false,
+                "device_id": device_id,
+                "app_name": app_name,
+                "error": rejected
+            }),
+        }
+    }
+}
+
+/// REST client for a device's DIAL application endpoint
+pub struct DialClient {
+    http: reqwest::Client,
+    apps_url: String,
+}
+
+impl DialClient {
+    /// Find the apps endpoint: SSDP devices advertise it as the `Application-URL`
+    /// header on their description, mDNS ones serve it at `/apps/`
+    pub async fn for_device(device: &DiscoveredDevice) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(DIAL_TIMEOUT)
+            .build()
+            .map_err(|e| CasterError::Network(format!("Failed to create DIAL client: {}", e)))?;
+
+        let apps_url = match device.metadata["location"].as_str() {
+            Some(location) => {
+                let response = http.get(location).send().await
+                    .map_err(|e| CasterError::Network(format!("Failed to fetch device description: {}", e)))?;
+
+                response.headers()
+                    .get("Application-URL")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+                    .ok_or_else(|| CasterError::Network(format!("{} does not advertise a DIAL Application-URL", device.name)))?
+            }
+            None => format!("http://{}/apps/", std::net::SocketAddr::new(device.ip, device.port)),
+        };
+
+        debug!("DIAL apps endpoint for {}: {}", device.name, apps_url);
+
+        Ok(Self {
+            http,
+            apps_url: if apps_url.ends_with('/') { apps_url } else { format!("{}/", apps_url) },
+        })
+    }
+
+    /// Launch an app, passing `payload` as its DIAL launch parameters
+    pub async fn launch(&self, app_name: &str, payload: Option<&str>) -> Result<DialLaunch> {
+        let app = dial_app_name(app_name);
+        let url = format!("{}{}", self.apps_url, app);
+
+        let response = self.http.post(&url)
+            .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(payload.unwrap_or_default().to_string())
+            .send()
+            .await
+            .map_err(|e| CasterError::Network(format!("DIAL launch of {} failed: {}", app, e)))?;
+
+        match response.status() {
+            StatusCode::CREATED | StatusCode::OK => {
+                let instance_url = response.headers()
+                    .get(header::LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                info!("Launched DIAL app {}", app);
+                Ok(DialLaunch::Launched { instance_url })
+            }
+            StatusCode::FORBIDDEN => Ok(DialLaunch::Forbidden {
+                message: format!("Device refused to launch {}", app),
+            }),
+            StatusCode::NOT_FOUND => Ok(DialLaunch::NotFound {
+                message: format!("Device has no app named {}", app),
+            }),
+            status => Err(CasterError::Network(format!("DIAL launch of {} returned {}", app, status))),
+        }
+    }
+
+    /// Current state of an app as reported by the device (`running`, `stopped`, `hidden`, ...)
+    pub async fn app_state(&self, app_name: &str) -> Result<String> {
+        let app = dial_app_name(app_name);
+        let url = format!("{}{}", self.apps_url, app);
+
+        let response = self.http.get(&url).send().await
+            .map_err(|e| CasterError::Network(format!("DIAL query of {} failed: {}", app, e)))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(CasterError::Network(format!("Device has no app named {}", app)));
+        }
+
+        let body = response.error_for_status()
+            .map_err(|e| CasterError::Network(format!("DIAL query of {} failed: {}", app, e)))?
+            .text()
+            .await
+            .map_err(|e| CasterError::Network(format!("Failed to read DIAL app info: {}", e)))?;
+
+        xml_element(&body, "state")
+            .map(str::to_string)
+            .ok_or_else(|| CasterError::Network(format!("DIAL app info for {} has no state", app)))
+    }
+}
+
+/// Map friendly aliases to the app names DIAL registries use
+fn dial_app_name(app_name: &str) -> &str {
+    match app_name.to_ascii_lowercase().as_str() {
+        "youtube" => "YouTube",
+        "netflix" => "Netflix",
+        _ => app_name,
+    }
+}
+
+/// Text of the first `<name>` element; DIAL app info is small enough to not need a parser
+fn xml_element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(xml[start..end].trim())
+}
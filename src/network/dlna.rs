@@ -0,0 +1,234 @@
+use rupnp::http::Uri;
+use rupnp::ssdp::URN;
+use rupnp::{Device, Service};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::{Result, CasterError, ContentType, ContentSource};
+use super::discovery::DiscoveredDevice;
+use super::media_registry;
+use super::media_server::resolve_media;
+
+/// SOAP control for a UPnP MediaRenderer's AVTransport and RenderingControl services
+pub struct DlnaController {
+    device: Device,
+    av_transport: Service,
+    rendering_control: Option<Service>,
+}
+
+impl DlnaController {
+    /// Fetch the device description at `location` and find its control services
+    pub async fn connect(location: &str) -> Result<Self> {
+        let url: Uri = location.parse()
+            .map_err(|e| CasterError::Network(format!("Invalid device location {}: {}", location, e)))?;
+
+        let device = Device::from_url(url).await
+            .map_err(|e| CasterError::Network(format!("Failed to fetch device description: {}", e)))?;
+
+        let av_transport = device
+            .find_service(&URN::service("schemas-upnp-org", "AVTransport", 1))
+            .cloned()
+            .ok_or_else(|| CasterError::Network(format!("{} has no AVTransport service", device.friendly_name())))?;
+        let rendering_control = device
+            .find_service(&URN::service("schemas-upnp-org", "RenderingControl", 1))
+            .cloned();
+
+        debug!("Connected to DLNA renderer {}", device.friendly_name());
+
+        Ok(Self {
+            device,
+            av_transport,
+            rendering_control,
+        })
+    }
+
+    pub fn friendly_name(&self) -> &str {
+        self.device.friendly_name()
+    }
+
+    /// Point the renderer at a media URL; the DIDL-Lite `metadata` tells it what kind of item it is
+    pub async fn set_av_transport_uri(&self, uri: &str, metadata: &str) -> Result<()> {
+        let args = format!(
+            "<InstanceID>0</InstanceID><CurrentURI>{}</CurrentURI><CurrentURIMetaData>{}</CurrentURIMetaData>",
+            xml_escape(uri),
+            xml_escape(metadata)
+        );
+        self.av_transport_action("SetAVTransportURI", &args).await
+    }
+
+    pub async fn play(&self) -> Result<()> {
+        self.av_transport_action("Play", "<InstanceID>0</InstanceID><Speed>1</Speed>").await
+    }
+
+    pub async fn pause(&self) -> Result<()> {
+        self.av_transport_action("Pause", "<InstanceID>0</InstanceID>").await
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        self.av_transport_action("Stop", "<InstanceID>0</InstanceID>").await
+    }
+
+    pub async fn seek(&self, position: Duration) -> Result<()> {
+        let secs = position.as_secs();
+        let target = format!("{:02}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60);
+        let args = format!("<InstanceID>0</InstanceID><Unit>REL_TIME</Unit><Target>{}</Target>", target);
+        self.av_transport_action("Seek", &args).await
+    }
+
+    /// Set the master volume, 0-100
+    pub async fn set_volume(&self, volume: u8) -> Result<()> {
+        let service = self.rendering_control.as_ref()
+            .ok_or_else(|| CasterError::Network(format!("{} has no RenderingControl service", self.friendly_name())))?;
+
+        let args = format!(
+            "<InstanceID>0</InstanceID><Channel>Master</Channel><DesiredVolume>{}</DesiredVolume>",
+            volume.min(100)
+        );
+        service.action(self.device.url(), "SetVolume", &args).await
+            .map_err(|e| CasterError::Network(format!("SetVolume failed: {}", e)))?;
+        Ok(())
+    }
+
+    async fn av_transport_action(&self, action: &str, args: &str) -> Result<()> {
+        self.av_transport.action(self.device.url(), action, args).await
+            .map_err(|e| CasterError::Network(format!("{} failed: {}", action, e)))?;
+        Ok(())
+    }
+}
+
+/// A renderer we're casting to, plus the local files published for it
+struct DlnaSession {
+    controller: DlnaController,
+    media_tokens: Vec<String>,
+}
+
+impl DlnaSession {
+    fn revoke_media(&mut self) {
+        for token in self.media_tokens.drain(..) {
+            media_registry().revoke(&token);
+        }
+    }
+}
+
+/// Casting state for DLNA/UPnP renderers found by `DeviceDiscovery`
+pub struct DlnaManager {
+    sessions: HashMap<String, DlnaSession>,
+}
+
+impl DlnaManager {
+    pub fn new() -> Self {
+        Self {
+            sessions: HashMap::new(),
+        }
+    }
+
+    pub async fn cast_content(
+        &mut self,
+        device: &DiscoveredDevice,
+        content_type: &ContentType,
+        source: &ContentSource,
+    ) -> Result<()> {
+        if !self.sessions.contains_key(&device.id) {
+            // SSDP discovery records the description URL as the location
+            let location = device.metadata["location"].as_str()
+                .ok_or_else(|| CasterError::Network(format!("{} has no device description location", device.name)))?;
+
+            let controller = DlnaController::connect(location).await?;
+            self.sessions.insert(device.id.clone(), DlnaSession {
+                controller,
+                media_tokens: Vec::new(),
+            });
+        }
+
+        let (media_url, media_type, media_token) = resolve_media(content_type, source)?;
+
+        let Some(session) = self.sessions.get_mut(&device.id) else {
+            return Err(CasterError::Network("Device not connected".into()));
+        };
+
+        let metadata = didl_lite(&media_url, &media_type, content_type, &media_title(source));
+        let loaded = match session.controller.set_av_transport_uri(&media_url, &metadata).await {
+            Ok(()) => session.controller.play().await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = loaded {
+            if let Some(token) = media_token {
+                media_registry().revoke(&token);
+            }
+            return Err(e);
+        }
+
+        // The previous item's files are no longer needed once the new one is playing
+        session.revoke_media();
+        session.media_tokens.extend(media_token);
+
+        info!("Casting {} to DLNA renderer {}", media_url, session.controller.friendly_name());
+        Ok(())
+    }
+
+    pub fn controller(&self, device_id: &str) -> Option<&DlnaController> {
+        self.sessions.get(device_id).map(|session| &session.controller)
+    }
+
+    pub async fn stop_casting(&mut self, device_id: &str) -> Result<()> {
+        let Some(mut session) = self.sessions.remove(device_id) else {
+            return Ok(());
+        };
+
+        session.revoke_media();
+        session.controller.stop().await
+    }
+}
+
+impl Default for DlnaManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Title renderers show for an item: the file name, or a generic one
+fn media_title(source: &ContentSource) -> String {
+    let path = match source {
+        ContentSource::File { path } => path.as_str(),
+        ContentSource::Url { url } => url.split(['?', '#']).next().unwrap_or_default(),
+        _ => "",
+    };
+
+    match path.rsplit(['/', '\\']).next() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => "Q8-Caster Media".to_string(),
+    }
+}
+
+/// Single-item DIDL-Lite document; the UPnP class comes from what we're casting
+pub fn didl_lite(uri: &str, mime_type: &str, content_type: &ContentType, title: &str) -> String {
+    let class = match content_type {
+        ContentType::Audio { .. } => "object.item.audioItem.musicTrack",
+        ContentType::Image { .. } => "object.item.imageItem.photo",
+        ContentType::Stream { .. } => "object.item.videoItem.videoBroadcast",
+        _ => "object.item.videoItem",
+    };
+
+    format!(
+        concat!(
+            r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" "#,
+            r#"xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/">"#,
+            r#"<item id="0" parentID="-1" restricted="1"><dc:title>{}</dc:title>"#,
+            r#"<upnp:class>{}</upnp:class><res protocolInfo="http-get:*:{}:*">{}</res></item></DIDL-Lite>"#
+        ),
+        xml_escape(title),
+        class,
+        xml_escape(mime_type),
+        xml_escape(uri)
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
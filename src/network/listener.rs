@@ -0,0 +1,209 @@
+use axum::Router;
+use hyper_util::rt::TokioIo;
+use hyper_util::service::TowerToHyperService;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, Semaphore};
+use tracing::{debug, info, info_span, warn, Instrument};
+
+/// Concurrent receiver connections; senders beyond this are turned away
+pub const MAX_RECEIVER_CONNECTIONS: usize = 64;
+
+/// How long a new connection gets to send its first bytes before we drop it
+const SNIFF_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a connection speaks, judged from its first bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WireProtocol {
+    /// AirPlay v1 and DIAL are both plain HTTP/1.1
+    Http,
+    /// Cast v2 opens with a TLS handshake
+    Tls,
+    Unknown,
+}
+
+impl WireProtocol {
+    fn sniff(head: &[u8]) -> Self {
+        const METHODS: &[&[u8]] = &[b"GET ", b"POST ", b"PUT ", b"HEAD ", b"DELETE ", b"OPTIONS "];
+
+        if METHODS.iter().any(|method| head.starts_with(method)) {
+            WireProtocol::Http
+        } else if head.first() == Some(&0x16) {
+            // TLS record type "handshake"
+            WireProtocol::Tls
+        } else {
+            WireProtocol::Unknown
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            WireProtocol::Http => "http",
+            WireProtocol::Tls => "tls",
+            WireProtocol::Unknown => "unknown",
+        }
+    }
+}
+
+/// Accept loop for the receiver port. HTTP goes to `http` (AirPlay, DIAL), anything
+/// else is logged and closed. Runs until `shutdown` flips to true.
+pub async fn serve(
+    listener: TcpListener,
+    http: Router,
+    max_connections: usize,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let permits = Arc::new(Semaphore::new(max_connections));
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept receiver connection: {}", e);
+                    continue;
+                }
+            },
+            _ = shutdown.changed() => break,
+        };
+
+        let Ok(permit) = permits.clone().try_acquire_owned() else {
+            warn!("Refusing receiver connection from {}: {} connections open", peer, max_connections);
+            continue;
+        };
+
+        let http = http.clone();
+        let shutdown = shutdown.clone();
+        tokio::spawn(
+            async move {
+                handle_connection(stream, peer, http, shutdown).await;
+                drop(permit);
+            }
+            .instrument(info_span!("receiver_connection", %peer, protocol = tracing::field::Empty)),
+        );
+    }
+
+    info!("Receiver listener stopped");
+}
+
+async fn handle_connection(stream: TcpStream, peer: SocketAddr, http: Router, mut shutdown: watch::Receiver<bool>) {
+    let mut head = [0u8; 16];
+    let read = match tokio::time::timeout(SNIFF_TIMEOUT, stream.peek(&mut head)).await {
+        Ok(Ok(read)) if read > 0 => read,
+        Ok(Ok(_)) => return,
+        Ok(Err(e)) => {
+            debug!("Receiver connection failed before sending data: {}", e);
+            return;
+        }
+        Err(_) => {
+            debug!("Receiver connection sent nothing within {:?}", SNIFF_TIMEOUT);
+            return;
+        }
+    };
+
+    let protocol = WireProtocol::sniff(&head[..read]);
+    tracing::Span::current().record("protocol", protocol.as_str());
+    debug!("Receiver connection from {} speaks {}", peer, protocol.as_str());
+
+    match protocol {
+        WireProtocol::Http => {
+            let service = TowerToHyperService::new(http);
+            let connection = hyper::server::conn::http1::Builder::new()
+                .keep_alive(true)
+                .serve_connection(TokioIo::new(stream), service);
+            tokio::pin!(connection);
+
+            tokio::select! {
+                result = connection.as_mut() => {
+                    if let Err(e) = result {
+                        debug!("Receiver HTTP connection ended: {}", e);
+                    }
+                }
+                _ = shutdown.changed() => {
+                    // Let an in-flight response finish, then close
+                    connection.as_mut().graceful_shutdown();
+                    let _ = connection.await;
+                }
+            }
+        }
+        WireProtocol::Tls => {
+            // Cast v2 needs the device certificate chain we don't have
+            info!("Closing TLS connection from {}: Cast v2 receiving is not supported", peer);
+        }
+        WireProtocol::Unknown => {
+            warn!("Closing receiver connection from {}: unrecognised protocol", peer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::airplay::AirPlayReceiver;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::broadcast;
+
+    const TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// The accept loop with the AirPlay routes on an ephemeral port
+    async fn start_airplay() -> (SocketAddr, watch::Sender<bool>, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let airplay = AirPlayReceiver::new("02:00:00:00:00:01".into(), "AppleTV3,2".into(), broadcast::channel(4).0);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let server = tokio::spawn(serve(listener, airplay.router(), 4, shutdown_rx));
+        (addr, shutdown_tx, server)
+    }
+
+    #[test]
+    fn sniffs_the_protocol_from_the_first_bytes() {
+        assert_eq!(WireProtocol::sniff(b"GET /server-info HTTP/1.1\r\n"), WireProtocol::Http);
+        assert_eq!(WireProtocol::sniff(b"POST /play HTTP/1.1"), WireProtocol::Http);
+        assert_eq!(WireProtocol::sniff(&[0x16, 0x03, 0x01, 0x02, 0x00]), WireProtocol::Tls);
+        assert_eq!(WireProtocol::sniff(b"GETX"), WireProtocol::Unknown);
+        assert_eq!(WireProtocol::sniff(b""), WireProtocol::Unknown);
+    }
+
+    #[tokio::test]
+    async fn answers_airplay_http_on_the_receiver_port() {
+        let (addr, shutdown, server) = start_airplay().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /server-info HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        tokio::time::timeout(TEST_TIMEOUT, stream.read_to_string(&mut response))
+            .await
+            .expect("receiver never answered")
+            .unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"), "unexpected response: {}", response);
+        assert!(response.contains("text/x-apple-plist+xml"));
+        assert!(response.contains("02:00:00:00:00:01"));
+
+        shutdown.send(true).unwrap();
+        tokio::time::timeout(TEST_TIMEOUT, server).await.expect("listener did not stop").unwrap();
+    }
+
+    #[tokio::test]
+    async fn closes_connections_it_cannot_speak() {
+        let (addr, shutdown, server) = start_airplay().await;
+
+        for head in [&[0x16, 0x03, 0x01, 0x00, 0x05][..], b"\x00garbage"] {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(head).await.unwrap();
+            let mut rest = Vec::new();
+            let read = tokio::time::timeout(TEST_TIMEOUT, stream.read_to_end(&mut rest))
+                .await
+                .expect("connection was left open");
+            assert!(read.map_or(true, |_| rest.is_empty()));
+        }
+
+        shutdown.send(true).unwrap();
+        tokio::time::timeout(TEST_TIMEOUT, server).await.expect("listener did not stop").unwrap();
+    }
+}
@@ -0,0 +1,251 @@
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::RwLock;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::{Result, CasterError, ContentSource, ContentType, StreamProtocol};
+use super::detect_lan_ip;
+
+// Global registry shared by the casting managers and the HTTP `/media/:token` route
+lazy_static::lazy_static! {
+    static ref MEDIA_REGISTRY: MediaRegistry = MediaRegistry::new();
+}
+
+/// How long a published file stays reachable if its session never revokes it
+const MEDIA_TOKEN_LIFETIME: Duration = Duration::hours(24);
+
+/// Access the process-wide media registry
+pub fn media_registry() -> &'static MediaRegistry {
+    &MEDIA_REGISTRY
+}
+
+/// A local file exposed to cast devices under an unguessable token
+#[derive(Debug, Clone)]
+pub struct MediaEntry {
+    pub path: PathBuf,
+    pub registered_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Token → file path registry backing the HTTP media route.
+///
+/// Cast devices can't read our filesystem, so local files are published
+/// under `/media/<token>` for as long as the cast session needs them. Tokens
+/// are revoked when the session stops and expire on their own regardless.
+/// Only files under the cache's media directories are published.
+pub struct MediaRegistry {
+    entries: DashMap<String, MediaEntry>,
+    /// Port of the HTTP server serving `/media`, 0 until it starts
+    port: AtomicU16,
+    /// `cache.media_dirs`; empty until set, which publishes nothing
+    media_dirs: RwLock<Vec<PathBuf>>,
+}
+
+impl MediaRegistry {
+    fn new() -> Self {
+        Self {
+            entries: DashMap::new(),
+            port: AtomicU16::new(0),
+            media_dirs: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Limit publishing to files under these directories, as `ContentCache` does for reads
+    pub fn set_media_dirs(&self, media_dirs: Vec<PathBuf>) {
+        *self.media_dirs.write().unwrap() = media_dirs;
+    }
+
+    /// Record the port the HTTP server is listening on
+    pub fn set_port(&self, port: u16) {
+        self.port.store(port, Ordering::Relaxed);
+    }
+
+    /// Publish a local file and return its token
+    pub fn register(&self, path: &str) -> Result<String> {
+        self.register_for(path, MEDIA_TOKEN_LIFETIME)
+    }
+
+    fn register_for(&self, path: &str, lifetime: Duration) -> Result<String> {
+        // `/media` is served without auth, so nothing outside the media directories,
+        // or the pages we render ourselves, may get a token
+        let mut allowed = self.media_dirs.read().unwrap().clone();
+        allowed.push(crate::render::markdown_pages_dir());
+        let path = crate::cache::media_path_in(&allowed, path)?;
+        if !path.is_file() {
+            return Err(CasterError::InvalidRequest(format!("Cannot serve {}: not a file", path.display())));
+        }
+
+        let now = Utc::now();
+        self.entries.retain(|_, entry| entry.expires_at > now);
+
+        let token = Uuid::new_v4().simple().to_string();
+        info!("Serving {} as media token {}", path.display(), token);
+        self.entries.insert(token.clone(), MediaEntry {
+            path,
+            registered_at: now,
+            expires_at: now + lifetime,
+        });
+
+        Ok(token)
+    }
+
+    /// Look up the file behind a token, unless it has expired
+    pub fn resolve(&self, token: &str) -> Option<PathBuf> {
+        let now = Utc::now();
+        if self.entries.remove_if(token, |_, entry| entry.expires_at <= now).is_some() {
+            debug!("Media token {} expired", token);
+            return None;
+        }
+        self.entries.get(token).map(|entry| entry.path.clone())
+    }
+
+    /// Stop serving a token
+    pub fn revoke(&self, token: &str) {
+        if self.entries.remove(token).is_some() {
+            debug!("Revoked media token {}", token);
+        }
+    }
+
+    /// URL a device on the LAN can use to fetch the token
+    pub fn url_for(&self, token: &str) -> Result<String> {
+        let server = self.server_url()
+            .ok_or_else(|| CasterError::Network("HTTP server not running, cannot serve local media".into()))?;
+        Ok(format!("{}media/{}", server, token))
+    }
+
+    /// Root URL of the HTTP server as other LAN devices reach it, once it is running
+    pub fn server_url(&self) -> Option<String> {
+        match self.port.load(Ordering::Relaxed) {
+            0 => None,
+            port => Some(format!("http://{}:{}/", detect_lan_ip(), port)),
+        }
+    }
+}
+
+/// Work out the URL and MIME type a cast device should load.
+/// Local files are published through the media registry and their token returned.
+pub fn resolve_media(content_type: &ContentType, source: &ContentSource) -> Result<(String, String, Option<String>)> {
+    if let ContentType::Stream { protocol } = content_type {
+        return match protocol {
+            StreamProtocol::Hls { manifest_url } => {
+                Ok((manifest_url.clone(), "application/x-mpegURL".to_string(), None))
+            }
+            StreamProtocol::Dash { manifest_url } => {
+                Ok((manifest_url.clone(), "application/dash+xml".to_string(), None))
+            }
+            _ => Err(CasterError::Network("Unsupported stream protocol for casting".into())),
+        };
+    }
+
+    let (url, token) = match source {
+        ContentSource::Url { url } => (url.clone(), None),
+        ContentSource::File { path } => {
+            let (url, token) = publish_file(path)?;
+            (url, Some(token))
+        }
+        _ => return Err(CasterError::Network("Unsupported content source for casting".into())),
+    };
+
+    let guessed = mime_guess::from_path(url::Url::parse(&url).map(|u| u.path().to_string()).unwrap_or_else(|_| url.clone()))
+        .first()
+        .map(|m| m.essence_str().to_string());
+
+    let media_type = match content_type {
+        ContentType::Video { .. } => guessed.filter(|m| m.starts_with("video/")).unwrap_or_else(|| "video/mp4".to_string()),
+        ContentType::Audio { .. } => guessed.filter(|m| m.starts_with("audio/")).unwrap_or_else(|| "audio/mpeg".to_string()),
+        ContentType::Image { format } => match format.as_str() {
+            "png" => "image/png".to_string(),
+            "gif" => "image/gif".to_string(),
+            "webp" => "image/webp".to_string(),
+            "jpg" | "jpeg" => "image/jpeg".to_string(),
+            _ => guessed.filter(|m| m.starts_with("image/")).unwrap_or_else(|| "image/jpeg".to_string()),
+        },
+        _ => {
+            if let Some(token) = token {
+                media_registry().revoke(&token);
+            }
+            return Err(CasterError::Network("Unsupported content type for casting".into()));
+        }
+    };
+
+    Ok((url, media_type, token))
+}
+
+/// Publish a local file on the HTTP server's `/media` route
+pub(crate) fn publish_file(file_path: &str) -> Result<(String, String)> {
+    let registry = media_registry();
+    let token = registry.register(file_path)?;
+    match registry.url_for(&token) {
+        Ok(url) => Ok((url, token)),
+        Err(e) => {
+            registry.revoke(&token);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_tokens_expire() {
+        let registry = MediaRegistry::new();
+        registry.set_media_dirs(vec![std::env::temp_dir()]);
+        let file = std::env::temp_dir().join(format!("q8-media-{}.mp4", Uuid::new_v4().simple()));
+        std::fs::write(&file, b"not really a video").unwrap();
+        let path = file.to_str().unwrap();
+
+        let live = registry.register(path).unwrap();
+        assert_eq!(registry.resolve(&live), Some(std::fs::canonicalize(&file).unwrap()));
+
+        let expired = registry.register_for(path, Duration::zero()).unwrap();
+        assert_eq!(registry.resolve(&expired), None);
+        assert!(!registry.entries.contains_key(&expired));
+
+        // Expired entries are dropped as new files are published
+        let stale = registry.register_for(path, Duration::zero()).unwrap();
+        registry.register(path).unwrap();
+        assert!(!registry.entries.contains_key(&stale));
+        assert!(registry.resolve(&live).is_some());
+
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn only_files_in_media_dirs_are_published() {
+        let media = std::env::temp_dir().join(format!("q8-media-{}", Uuid::new_v4().simple()));
+        let outside = std::env::temp_dir().join(format!("q8-outside-{}", Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&media).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(media.join("clip.mp4"), b"clip").unwrap();
+        std::fs::write(outside.join("secret.txt"), b"secret").unwrap();
+        let secret = outside.join("secret.txt");
+
+        // Nothing is published before the media directories are set
+        let registry = MediaRegistry::new();
+        assert!(registry.register(secret.to_str().unwrap()).is_err());
+
+        registry.set_media_dirs(vec![media.clone()]);
+        assert!(registry.register(media.join("clip.mp4").to_str().unwrap()).is_ok());
+        assert!(matches!(registry.register(secret.to_str().unwrap()), Err(CasterError::InvalidRequest(_))));
+        let escape = format!("{}/../{}/secret.txt", media.display(), outside.file_name().unwrap().to_string_lossy());
+        assert!(matches!(registry.register(&escape), Err(CasterError::InvalidRequest(_))));
+        // The directory itself isn't a file
+        assert!(matches!(registry.register(media.to_str().unwrap()), Err(CasterError::InvalidRequest(_))));
+        assert_eq!(registry.entries.len(), 1);
+
+        // Markdown pages rendered for casting are served wherever they are
+        let pages = crate::render::write_markdown_pages("# Slide", None, 320, 180).unwrap();
+        assert!(registry.register(pages[0].to_str().unwrap()).is_ok());
+        for page in pages {
+            std::fs::remove_file(page).unwrap();
+        }
+
+        std::fs::remove_dir_all(media).unwrap();
+        std::fs::remove_dir_all(outside).unwrap();
+    }
+}
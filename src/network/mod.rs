@@ -1,38 +1,174 @@
+pub mod airplay;
+pub mod airplay_sender;
 pub mod chromecast_simple;
+pub mod dial;
 pub mod discovery;
+pub mod dlna;
+pub mod listener;
+pub mod media_server;
+pub mod probe;
+pub mod roku;
 
 // Re-export commonly used types
-pub use discovery::{DeviceDiscovery, DeviceType, DiscoveredDevice, DeviceCapabilities};
+pub use airplay::{spawn_media_bridge, AirPlayCommand};
+pub use chromecast_simple::{ConnectionState, QueueEntry, RetryPolicy, SubtitleTrack};
+pub use dial::DialLaunch;
+pub use discovery::{DeviceDiscovery, DeviceFilter, DeviceType, DiscoveredDevice, DeviceCapabilities, DiscoveryConfig, DiscoveryEvent};
+pub use media_server::{media_registry, MediaRegistry};
 
+use axum::Router;
 use mdns_sd::{ServiceDaemon, ServiceInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 // Removed unused imports - SearchTarget and URN were just window shopping here!
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tracing::info;
+use tokio::sync::{broadcast, watch, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
 
 use crate::{Result, CasterError};
+use self::airplay::{AirPlayReceiver, AIRPLAY_FEATURES, AIRPLAY_SRCVERS};
+use self::airplay_sender::AirPlayManager;
 use self::chromecast_simple::ChromecastManager;
+use self::dial::DialClient;
+use self::dlna::DlnaManager;
+use self::roku::RokuController;
 
 pub struct NetworkReceiver {
     mdns: Option<ServiceDaemon>,
-    tcp_listener: Option<TcpListener>,
     protocols: Vec<String>,
+    // Receivers started when a start request names none
+    default_protocols: Vec<String>,
     chromecast_manager: ChromecastManager,
+    airplay_manager: AirPlayManager,
+    dlna_manager: DlnaManager,
     device_discovery: DeviceDiscovery,
+    advertised: Arc<RwLock<Vec<IpAddr>>>,
+    interface_watch: Option<tokio::task::JoinHandle<()>>,
+    // Outlives receiver restarts so subscribers keep getting AirPlay requests
+    airplay_commands: broadcast::Sender<AirPlayCommand>,
+    // Accept loop for the receiver port, and the flag that shuts it down
+    receiver_server: Option<tokio::task::JoinHandle<()>>,
+    receiver_shutdown: Option<watch::Sender<bool>>,
 }
 
+/// Identity our receiver services advertise to cast clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReceiverConfig {
+    /// Name shown in AirPlay / Cast / DLNA pickers
+    pub friendly_name: String,
+    pub model: String,
+    /// Stable UUID; derived from the hostname unless overridden
+    pub device_uuid: Uuid,
+}
+
+impl Default for ReceiverConfig {
+    fn default() -> Self {
+        let host = gethostname::gethostname().to_string_lossy().into_owned();
+        Self {
+            friendly_name: "q8-caster".to_string(),
+            model: "Q8Caster1,1".to_string(),
+            device_uuid: Uuid::new_v5(&Uuid::NAMESPACE_DNS, host.as_bytes()),
+        }
+    }
+}
+
+impl ReceiverConfig {
+    /// AirPlay wants a MAC-style device id; use a locally administered one from our UUID
+    pub(crate) fn airplay_device_id(&self) -> String {
+        let bytes = self.device_uuid.as_bytes();
+        let first = (bytes[0] | 0x02) & 0xFE;
+        format!(
+            "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+            first, bytes[1], bytes[2], bytes[3], bytes[4], bytes[5]
+        )
+    }
+
+    fn airplay_txt(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("deviceid".to_string(), self.airplay_device_id()),
+            ("features".to_string(), format!("0x{:X}", AIRPLAY_FEATURES)),
+            ("flags".to_string(), "0x4".to_string()),
+            ("model".to_string(), self.model.clone()),
+            ("pi".to_string(), self.device_uuid.to_string()),
+            ("srcvers".to_string(), AIRPLAY_SRCVERS.to_string()),
+        ])
+    }
+
+    fn chromecast_txt(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("id".to_string(), self.device_uuid.simple().to_string()),
+            ("cd".to_string(), self.device_uuid.simple().to_string().to_uppercase()),
+            ("ve".to_string(), "05".to_string()),
+            ("md".to_string(), self.model.clone()),
+            ("fn".to_string(), self.friendly_name.clone()),
+            ("ic".to_string(), "/setup/icon.png".to_string()),
+            ("ca".to_string(), "4101".to_string()),
+            ("st".to_string(), "0".to_string()),
+            ("rs".to_string(), String::new()),
+            ("nf".to_string(), "1".to_string()),
+        ])
+    }
+
+    fn dlna_txt(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("uuid".to_string(), self.device_uuid.to_string()),
+            ("fn".to_string(), self.friendly_name.clone()),
+            ("md".to_string(), self.model.clone()),
+        ])
+    }
+}
+
+/// How long to let a freshly started browse collect Chromecasts and AirPlay receivers
+const CAST_DISCOVERY_WINDOW: Duration = Duration::from_secs(3);
+
+/// How often to check for interface changes that need re-advertising
+const INTERFACE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
 impl NetworkReceiver {
     pub async fn new() -> Result<Self> {
+        Self::with_config(DiscoveryConfig::default(), default_receiver_protocols()).await
+    }
+
+    /// A receiver with `discovery` timings that starts `default_protocols` when asked for none
+    pub async fn with_config(discovery: DiscoveryConfig, default_protocols: Vec<String>) -> Result<Self> {
+        discovery.validate()?;
+        let device_discovery = DeviceDiscovery::with_config(discovery);
+
         Ok(Self {
             mdns: None,
-            tcp_listener: None,
             protocols: Vec::new(),
-            chromecast_manager: ChromecastManager::new(),
-            device_discovery: DeviceDiscovery::new(),
+            default_protocols,
+            chromecast_manager: ChromecastManager::new(device_discovery.device_map()),
+            airplay_manager: AirPlayManager::new(device_discovery.device_map()),
+            dlna_manager: DlnaManager::new(),
+            device_discovery,
+            advertised: Arc::new(RwLock::new(Vec::new())),
+            interface_watch: None,
+            airplay_commands: broadcast::channel(16).0,
+            receiver_server: None,
+            receiver_shutdown: None,
         })
     }
     
-    pub async fn start(&mut self, protocols: Vec<String>, port: u16) -> Result<()> {
+    /// Whether `start` has run without a `stop` since
+    pub fn is_running(&self) -> bool {
+        self.receiver_server.is_some()
+    }
+
+    /// Listen on `port` and advertise `protocols`. Fails if the receiver is already
+    /// running, rather than orphaning the running listener and advertisements.
+    pub async fn start(&mut self, protocols: Vec<String>, port: u16, config: ReceiverConfig) -> Result<()> {
+        if self.is_running() {
+            return Err(CasterError::InvalidRequest(format!(
+                "The receiver is already running with {:?}; stop it first",
+                self.protocols
+            )));
+        }
         self.protocols = protocols.clone();
 
         // Start TCP listener
@@ -40,89 +176,139 @@ impl NetworkReceiver {
         let listener = TcpListener::bind(addr).await
             .map_err(|e| CasterError::Network(format!("Failed to bind to port {}: {}", port, e)))?;
 
-        self.tcp_listener = Some(listener);
+        let mut router = Router::new();
+        if protocols.iter().any(|p| p == "airplay") {
+            let airplay = AirPlayReceiver::new(
+                config.airplay_device_id(),
+                config.model.clone(),
+                self.airplay_commands.clone(),
+            );
+            router = router.merge(airplay.router());
+        }
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        self.receiver_shutdown = Some(shutdown_tx);
+        self.receiver_server = Some(tokio::spawn(listener::serve(
+            listener,
+            router,
+            listener::MAX_RECEIVER_CONNECTIONS,
+            shutdown_rx,
+        )));
 
         // Start mDNS for service discovery
         let mdns = ServiceDaemon::new()
             .map_err(|e| CasterError::Network(format!("Failed to create mDNS daemon: {}", e)))?;
 
-        for protocol in &protocols {
-            match protocol.as_str() {
-                "airplay" => self.register_airplay(&mdns, port)?,
-                "upnp" => self.register_upnp(&mdns, port).await?,
-                "chromecast" => self.register_chromecast(&mdns, port)?,
-                _ => {}
+        let hostname = mdns_hostname();
+        let addrs = lan_addresses();
+
+        Self::register_services(&mdns, &protocols, &config, &hostname, &addrs, port).await?;
+        *self.advertised.write().await = addrs;
+
+        // DHCP renewals and network switches change our address; follow them
+        let watch_mdns = mdns.clone();
+        let advertised = self.advertised.clone();
+        self.interface_watch = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(INTERFACE_POLL_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+
+                let addrs = lan_addresses();
+                if *advertised.read().await == addrs {
+                    continue;
+                }
+
+                info!("Network interfaces changed, re-advertising on {:?}", addrs);
+                match Self::register_services(&watch_mdns, &protocols, &config, &hostname, &addrs, port).await {
+                    Ok(()) => *advertised.write().await = addrs,
+                    Err(e) => warn!("Failed to re-register mDNS services: {}", e),
+                }
             }
-        }
+        }));
 
         self.mdns = Some(mdns);
 
         // Start device discovery for all supported device types
-        let device_types = vec![
-            DeviceType::Chromecast,
-            DeviceType::FireTv,
-            DeviceType::AirPlay,
-            DeviceType::Dlna,
-        ];
-
-        self.device_discovery.start(device_types).await?;
+        let discovery_config = self.device_discovery.config().clone();
+        self.device_discovery.start(default_discovery_types(), discovery_config).await?;
 
-        info!("Network receiver started on port {} with protocols: {:?}", port, protocols);
+        info!("Network receiver started on port {} with protocols: {:?}", port, self.protocols);
 
         Ok(())
     }
     
-    fn register_airplay(&self, mdns: &ServiceDaemon, port: u16) -> Result<()> {
+    async fn register_services(
+        mdns: &ServiceDaemon,
+        protocols: &[String],
+        config: &ReceiverConfig,
+        hostname: &str,
+        addrs: &[IpAddr],
+        port: u16,
+    ) -> Result<()> {
+        for protocol in protocols {
+            match protocol.as_str() {
+                "airplay" => Self::register_airplay(mdns, config, hostname, addrs, port)?,
+                "upnp" => Self::register_upnp(mdns, config, hostname, addrs, port).await?,
+                "chromecast" => Self::register_chromecast(mdns, config, hostname, addrs, port)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn register_airplay(mdns: &ServiceDaemon, config: &ReceiverConfig, hostname: &str, addrs: &[IpAddr], port: u16) -> Result<()> {
         let service_info = ServiceInfo::new(
             "_airplay._tcp.local.",
-            "q8-caster",
-            &format!("q8-caster.local."),
-            "127.0.0.1",
+            &config.friendly_name,
+            hostname,
+            addrs,
             port,
-            None,
+            config.airplay_txt(),
         ).map_err(|e| CasterError::Network(format!("Failed to create AirPlay service: {:?}", e)))?;
         
         mdns.register(service_info)
             .map_err(|e| CasterError::Network(format!("Failed to register AirPlay service: {}", e)))?;
         
-        info!("Registered AirPlay service on port {}", port);
+        info!("Registered AirPlay service on {:?}:{}", addrs, port);
         
         Ok(())
     }
     
-    async fn register_upnp(&self, mdns: &ServiceDaemon, port: u16) -> Result<()> {
+    async fn register_upnp(mdns: &ServiceDaemon, config: &ReceiverConfig, hostname: &str, addrs: &[IpAddr], port: u16) -> Result<()> {
         // Register UPnP/DLNA service
         let service_info = ServiceInfo::new(
             "_dlna._tcp.local.",
-            "q8-caster",
-            &format!("q8-caster.local."),
-            "127.0.0.1",
+            &config.friendly_name,
+            hostname,
+            addrs,
             port,
-            None,
+            config.dlna_txt(),
         ).map_err(|e| CasterError::Network(format!("Failed to create UPnP service: {:?}", e)))?;
         
         mdns.register(service_info)
             .map_err(|e| CasterError::Network(format!("Failed to register UPnP service: {}", e)))?;
         
-        info!("Registered UPnP/DLNA service on port {}", port);
+        info!("Registered UPnP/DLNA service on {:?}:{}", addrs, port);
         
         Ok(())
     }
     
-    fn register_chromecast(&self, mdns: &ServiceDaemon, port: u16) -> Result<()> {
+    fn register_chromecast(mdns: &ServiceDaemon, config: &ReceiverConfig, hostname: &str, addrs: &[IpAddr], port: u16) -> Result<()> {
         let service_info = ServiceInfo::new(
             "_googlecast._tcp.local.",
-            "q8-caster",
-            &format!("q8-caster.local."),
-            "127.0.0.1",
+            &config.friendly_name,
+            hostname,
+            addrs,
             port,
-            None,
+            config.chromecast_txt(),
         ).map_err(|e| CasterError::Network(format!("Failed to create Chromecast service: {:?}", e)))?;
         
         mdns.register(service_info)
             .map_err(|e| CasterError::Network(format!("Failed to register Chromecast service: {}", e)))?;
         
-        info!("Registered Chromecast service on port {}", port);
+        info!("Registered Chromecast service on {:?}:{}", addrs, port);
         
         Ok(())
     }
@@ -131,24 +317,60 @@ impl NetworkReceiver {
         // Stop device discovery
         self.device_discovery.stop().await?;
 
+        if let Some(handle) = self.interface_watch.take() {
+            handle.abort();
+        }
+
         if let Some(mdns) = self.mdns.take() {
             mdns.shutdown()
                 .map_err(|e| CasterError::Network(format!("Failed to shutdown mDNS: {}", e)))?;
         }
 
-        self.tcp_listener = None;
+        // Stop accepting and let open connections finish their current request
+        if let Some(shutdown) = self.receiver_shutdown.take() {
+            let _ = shutdown.send(true);
+        }
+        if let Some(handle) = self.receiver_server.take() {
+            let _ = handle.await;
+        }
+
         self.protocols.clear();
+        self.advertised.write().await.clear();
 
         Ok(())
     }
     
+    /// Requests from AirPlay senders, for whatever plays or shows them
+    pub fn subscribe_airplay(&self) -> broadcast::Receiver<AirPlayCommand> {
+        self.airplay_commands.subscribe()
+    }
+
+    /// Devices found, updated and lost by discovery
+    pub fn subscribe_discovery(&self) -> broadcast::Receiver<DiscoveryEvent> {
+        self.device_discovery.subscribe()
+    }
+
     // Chromecast-specific methods
-    pub async fn discover_chromecasts(&mut self) -> Result<Vec<serde_json::Value>> {
+    pub async fn discover_chromecasts(&mut self) -> Result<Vec<DiscoveredDevice>> {
         // Discovering devices - they're shy but we'll find them!
-        let _devices = self.chromecast_manager.discover_devices().await?;
+        // Without a running receiver, browse just long enough to hear from them
+        if !self.device_discovery.is_running().await {
+            let discovery_config = self.device_discovery.config().clone();
+            self.device_discovery.start(vec![DeviceType::Chromecast], discovery_config).await?;
+            tokio::time::sleep(CAST_DISCOVERY_WINDOW).await;
+        }
+
         Ok(self.chromecast_manager.list_devices())
     }
+
+    pub fn is_chromecast_connected(&self, device_id: &str) -> bool {
+        self.chromecast_manager.is_connected(device_id)
+    }
     
+    pub fn set_chromecast_retry_policy(&mut self, policy: RetryPolicy) {
+        self.chromecast_manager.set_retry_policy(policy);
+    }
+
     pub async fn connect_chromecast(&mut self, device_name: &str) -> Result<()> {
         self.chromecast_manager.connect_to_device(device_name).await
     }
@@ -158,18 +380,201 @@ impl NetworkReceiver {
         device_name: &str,
         content_type: &crate::ContentType,
         source: &crate::ContentSource,
+        subtitles: &[SubtitleTrack],
     ) -> Result<()> {
-        self.chromecast_manager.cast_content(device_name, content_type, source).await
+        self.chromecast_manager.cast_content_with_subtitles(device_name, content_type, source, subtitles).await
     }
     
     pub async fn stop_chromecast(&mut self, device_name: &str) -> Result<()> {
         self.chromecast_manager.stop_casting(device_name).await
     }
     
+    pub async fn play_chromecast(&self, device_name: &str) -> Result<()> {
+        self.chromecast_manager.play(device_name).await
+    }
+
+    pub async fn pause_chromecast(&self, device_name: &str) -> Result<()> {
+        self.chromecast_manager.pause(device_name).await
+    }
+
+    pub async fn seek_chromecast(&self, device_name: &str, position: f32) -> Result<()> {
+        self.chromecast_manager.seek(device_name, position).await
+    }
+
+    pub async fn set_chromecast_volume(&self, device_name: &str, level: f32) -> Result<()> {
+        self.chromecast_manager.set_volume(device_name, level).await
+    }
+
+    pub async fn enqueue_chromecast(
+        &mut self,
+        device_name: &str,
+        items: Vec<(crate::ContentType, crate::ContentSource)>,
+    ) -> Result<usize> {
+        self.chromecast_manager.enqueue(device_name, items).await
+    }
+
+    /// Replace the Chromecast's queue with `items` and start the first
+    pub async fn play_chromecast_queue(
+        &mut self,
+        device_name: &str,
+        items: Vec<(crate::ContentType, crate::ContentSource)>,
+    ) -> Result<usize> {
+        self.chromecast_manager.play_queue(device_name, items).await
+    }
+
+    pub async fn chromecast_queue_next(&mut self, device_name: &str) -> Result<usize> {
+        self.chromecast_manager.queue_next(device_name).await
+    }
+
+    pub async fn chromecast_queue_prev(&mut self, device_name: &str) -> Result<usize> {
+        self.chromecast_manager.queue_prev(device_name).await
+    }
+
+    pub async fn clear_chromecast_queue(&mut self, device_name: &str) -> Result<()> {
+        self.chromecast_manager.clear_queue(device_name).await
+    }
+
     pub async fn get_chromecast_status(&self, device_name: &str) -> Result<serde_json::Value> {
         self.chromecast_manager.get_device_status(device_name).await
     }
 
+    // AirPlay sender methods
+    pub async fn discover_airplay(&mut self) -> Result<Vec<DiscoveredDevice>> {
+        if !self.device_discovery.is_running().await {
+            let discovery_config = self.device_discovery.config().clone();
+            self.device_discovery.start(vec![DeviceType::AirPlay], discovery_config).await?;
+            tokio::time::sleep(CAST_DISCOVERY_WINDOW).await;
+        }
+
+        Ok(self.airplay_manager.list_devices())
+    }
+
+    pub fn is_airplay_casting(&self, device_id: &str) -> bool {
+        self.airplay_manager.is_casting(device_id)
+    }
+
+    pub async fn cast_to_airplay(
+        &mut self,
+        device_name: &str,
+        content_type: &crate::ContentType,
+        source: &crate::ContentSource,
+    ) -> Result<()> {
+        self.airplay_manager.cast_content(device_name, content_type, source).await
+    }
+
+    pub async fn stop_airplay(&mut self, device_name: &str) -> Result<()> {
+        self.airplay_manager.stop_casting(device_name).await
+    }
+
+    pub async fn get_airplay_status(&self, device_name: &str) -> Result<serde_json::Value> {
+        self.airplay_manager.get_device_status(device_name).await
+    }
+
+    /// Push media to a DLNA/UPnP renderer with SetAVTransportURI + Play
+    pub async fn cast_to_dlna(
+        &mut self,
+        device_id: &str,
+        content_type: &crate::ContentType,
+        source: &crate::ContentSource,
+    ) -> Result<()> {
+        let device = self.device_discovery.get_device(device_id)
+            .ok_or_else(|| CasterError::NotFound(format!("Device not found: {}", device_id)))?;
+
+        if !matches!(device.device_type, DeviceType::Dlna | DeviceType::Upnp) {
+            return Err(CasterError::Network(format!("{} is not a DLNA/UPnP renderer", device.name)));
+        }
+
+        self.dlna_manager.cast_content(&device, content_type, source).await
+    }
+
+    /// Cast to any discovered device, picking the control protocol from its type
+    pub async fn cast_to_device(
+        &mut self,
+        device_id: &str,
+        content_type: &crate::ContentType,
+        source: &crate::ContentSource,
+    ) -> Result<()> {
+        let device = self.device_discovery.get_device(device_id)
+            .ok_or_else(|| CasterError::NotFound(format!("Device not found: {}", device_id)))?;
+
+        match device.device_type {
+            DeviceType::Chromecast => {
+                self.chromecast_manager.cast_content(&device.id, content_type, source).await
+            }
+            DeviceType::AirPlay => {
+                self.airplay_manager.cast_content(&device.id, content_type, source).await
+            }
+            DeviceType::Dlna | DeviceType::Upnp => {
+                self.dlna_manager.cast_content(&device, content_type, source).await
+            }
+            ref other => Err(CasterError::NotImplemented(format!(
+                "Casting to {:?} devices is not supported", other
+            ))),
+        }
+    }
+
+    /// Stop whatever `cast_to_device` started on a device
+    pub async fn stop_device(&mut self, device_id: &str) -> Result<()> {
+        let device = self.device_discovery.get_device(device_id)
+            .ok_or_else(|| CasterError::NotFound(format!("Device not found: {}", device_id)))?;
+
+        match device.device_type {
+            DeviceType::Chromecast => self.chromecast_manager.stop_casting(&device.id).await,
+            DeviceType::AirPlay => self.airplay_manager.stop_casting(&device.id).await,
+            DeviceType::Dlna | DeviceType::Upnp => self.dlna_manager.stop_casting(&device.id).await,
+            ref other => Err(CasterError::NotImplemented(format!(
+                "Casting to {:?} devices is not supported", other
+            ))),
+        }
+    }
+
+    /// Launch an app on a DIAL device such as a Fire TV
+    pub async fn launch_dial_app(&self, device_id: &str, app_name: &str, payload: Option<&str>) -> Result<DialLaunch> {
+        self.dial_client(device_id).await?.launch(app_name, payload).await
+    }
+
+    pub async fn dial_app_state(&self, device_id: &str, app_name: &str) -> Result<String> {
+        self.dial_client(device_id).await?.app_state(app_name).await
+    }
+
+    async fn dial_client(&self, device_id: &str) -> Result<DialClient> {
+        let device = self.device_discovery.get_device(device_id)
+            .ok_or_else(|| CasterError::NotFound(format!("Device not found: {}", device_id)))?;
+
+        let speaks_dial = device.device_type == DeviceType::FireTv
+            || device.capabilities.protocols.iter().any(|p| p == "dial");
+        if !speaks_dial {
+            return Err(CasterError::Network(format!("{} does not support DIAL", device.name)));
+        }
+
+        DialClient::for_device(&device).await
+    }
+
+    /// Launch an app on a Roku by channel id, with optional deep-link parameters
+    pub async fn launch_roku_app(&self, device_id: &str, app_id: &str, params: &[(String, String)]) -> Result<()> {
+        self.roku_controller(device_id)?.launch(app_id, params).await
+    }
+
+    /// Press remote keys on a Roku, in order
+    pub async fn roku_keypress(&self, device_id: &str, keys: &[String]) -> Result<()> {
+        let controller = self.roku_controller(device_id)?;
+        for key in keys {
+            controller.keypress(key).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn roku_media_status(&self, device_id: &str) -> Result<serde_json::Value> {
+        self.roku_controller(device_id)?.media_player().await
+    }
+
+    fn roku_controller(&self, device_id: &str) -> Result<RokuController> {
+        let device = self.device_discovery.get_device(device_id)
+            .ok_or_else(|| CasterError::NotFound(format!("Device not found: {}", device_id)))?;
+
+        RokuController::for_device(&device)
+    }
+
     // Device discovery methods
     pub fn get_discovered_devices(&self) -> Vec<DiscoveredDevice> {
         self.device_discovery.get_devices()
@@ -186,4 +591,345 @@ impl NetworkReceiver {
     pub async fn is_discovery_running(&self) -> bool {
         self.device_discovery.is_running().await
     }
-}
\ No newline at end of file
+
+    /// Start discovery; returns false if it was already running
+    pub async fn start_discovery(&mut self, device_types: Vec<DeviceType>) -> Result<bool> {
+        if self.device_discovery.is_running().await {
+            return Ok(false);
+        }
+
+        let device_types = if device_types.is_empty() {
+            default_discovery_types()
+        } else {
+            device_types
+        };
+        let discovery_config = self.device_discovery.config().clone();
+        self.device_discovery.start(device_types, discovery_config).await?;
+        Ok(true)
+    }
+
+    /// Change discovery timings; a running discovery is restarted to pick them up
+    pub async fn set_discovery_config(&mut self, config: DiscoveryConfig) -> Result<()> {
+        self.device_discovery.reconfigure(config).await
+    }
+
+    /// Discover for at most `timeout` and return what was found; an empty list means the defaults
+    pub async fn discover_once(&mut self, device_types: Vec<DeviceType>, timeout: Duration) -> Result<Vec<DiscoveredDevice>> {
+        let device_types = if device_types.is_empty() {
+            default_discovery_types()
+        } else {
+            device_types
+        };
+        self.device_discovery.discover_once(device_types, timeout).await
+    }
+
+    /// Receivers to start when a request doesn't name any
+    pub fn default_protocols(&self) -> &[String] {
+        &self.default_protocols
+    }
+
+    pub async fn stop_discovery(&mut self) -> Result<()> {
+        self.device_discovery.stop().await
+    }
+
+    /// Addresses our mDNS services are currently advertised on, primary first
+    pub async fn advertised_addresses(&self) -> Vec<IpAddr> {
+        self.advertised.read().await.clone()
+    }
+}
+
+/// Device types browsed when the caller doesn't name any
+pub fn default_receiver_protocols() -> Vec<String> {
+    vec!["upnp".to_string(), "airplay".to_string()]
+}
+
+fn default_discovery_types() -> Vec<DeviceType> {
+    vec![
+        DeviceType::Chromecast,
+        DeviceType::FireTv,
+        DeviceType::AirPlay,
+        DeviceType::Dlna,
+        DeviceType::Roku,
+    ]
+}
+
+/// Pick the LAN address to advertise over mDNS.
+///
+/// Prefers the interface that carries the default route, then the first
+/// private IPv4 address, then any non-loopback IPv4 address. Falls back to
+/// loopback only when nothing else is available.
+pub fn detect_lan_ip() -> IpAddr {
+    let candidates = interface_ipv4s();
+
+    match select_lan_ip(&candidates, default_route_ipv4()) {
+        Some((ip, reason)) => {
+            info!("Advertising LAN address {} ({})", ip, reason);
+            IpAddr::V4(ip)
+        }
+        None => {
+            warn!("No non-loopback IPv4 interface found, advertising 127.0.0.1");
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        }
+    }
+}
+
+/// Every non-loopback address, with the one from `detect_lan_ip` first and IPv6 (AAAA) after IPv4
+pub fn lan_addresses() -> Vec<IpAddr> {
+    let candidates = interface_ipv4s();
+    let ipv6s = interface_ipv6s().into_iter().map(|(ip, _)| IpAddr::V6(ip));
+
+    let Some((primary, _)) = select_lan_ip(&candidates, default_route_ipv4()) else {
+        let addrs: Vec<IpAddr> = ipv6s.collect();
+        if addrs.is_empty() {
+            return vec![IpAddr::V4(Ipv4Addr::LOCALHOST)];
+        }
+        return addrs;
+    };
+
+    let mut addrs = vec![IpAddr::V4(primary)];
+    for (ip, _) in candidates {
+        if ip != primary {
+            addrs.push(IpAddr::V4(ip));
+        }
+    }
+    addrs.extend(ipv6s);
+    addrs
+}
+
+/// Order a device's addresses by how likely we are to reach them: same subnet as one
+/// of our interfaces first, then other IPv4, then routable IPv6, then link-local IPv6
+pub(crate) fn rank_addresses(addrs: &[IpAddr]) -> Vec<IpAddr> {
+    let ipv4s = interface_ipv4s();
+    let ipv6s = interface_ipv6s();
+
+    let on_our_subnet = |addr: &IpAddr| match addr {
+        IpAddr::V4(ip) => ipv4s.iter().any(|(local, netmask)| {
+            u32::from(*ip) & u32::from(*netmask) == u32::from(*local) & u32::from(*netmask)
+        }),
+        IpAddr::V6(ip) => ipv6s.iter().any(|(local, netmask)| {
+            u128::from(*ip) & u128::from(*netmask) == u128::from(*local) & u128::from(*netmask)
+        }),
+    };
+
+    let mut ranked = addrs.to_vec();
+    ranked.sort_by_key(|addr| {
+        if on_our_subnet(addr) {
+            0
+        } else {
+            match addr {
+                IpAddr::V4(_) => 1,
+                IpAddr::V6(ip) if !ip.is_unicast_link_local() => 2,
+                IpAddr::V6(_) => 3,
+            }
+        }
+    });
+    ranked.dedup();
+    ranked
+}
+
+/// Non-loopback IPv4 interfaces as (address, netmask) pairs
+fn interface_ipv4s() -> Vec<(Ipv4Addr, Ipv4Addr)> {
+    if_addrs::get_if_addrs()
+        .map(|ifaces| {
+            ifaces
+                .into_iter()
+                .filter(|iface| !iface.is_loopback())
+                .filter_map(|iface| match iface.addr {
+                    if_addrs::IfAddr::V4(v4) => Some((v4.ip, v4.netmask)),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_else(|e| {
+            warn!("Failed to enumerate network interfaces: {}", e);
+            Vec::new()
+        })
+}
+
+/// Non-loopback IPv6 interfaces as (address, netmask) pairs
+fn interface_ipv6s() -> Vec<(Ipv6Addr, Ipv6Addr)> {
+    if_addrs::get_if_addrs()
+        .map(|ifaces| {
+            ifaces
+                .into_iter()
+                .filter(|iface| !iface.is_loopback())
+                .filter_map(|iface| match iface.addr {
+                    if_addrs::IfAddr::V6(v6) => Some((v6.ip, v6.netmask)),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_else(|e| {
+            warn!("Failed to enumerate network interfaces: {}", e);
+            Vec::new()
+        })
+}
+
+/// Choose the primary address from interface candidates, with the reason it won
+fn select_lan_ip(
+    candidates: &[(Ipv4Addr, Ipv4Addr)],
+    route_ip: Option<Ipv4Addr>,
+) -> Option<(Ipv4Addr, &'static str)> {
+    // The source address the kernel would use for the default route
+    if let Some(route_ip) = route_ip {
+        let on_route = candidates.iter().find(|(ip, netmask)| {
+            u32::from(*ip) & u32::from(*netmask) == u32::from(route_ip) & u32::from(*netmask)
+        });
+        if let Some((ip, _)) = on_route {
+            return Some((*ip, "default route interface"));
+        }
+    }
+
+    if let Some((ip, _)) = candidates.iter().find(|(ip, _)| ip.is_private()) {
+        return Some((*ip, "first private interface"));
+    }
+
+    candidates.first().map(|(ip, _)| (*ip, "first non-loopback interface"))
+}
+
+/// This machine's hostname as an mDNS `.local.` name
+fn mdns_hostname() -> String {
+    let host = gethostname::gethostname().to_string_lossy().into_owned();
+
+    // Keep only the first label and drop anything mDNS won't accept
+    let label: String = host
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+
+    if label.is_empty() {
+        "q8-caster.local.".to_string()
+    } else {
+        format!("{}.local.", label)
+    }
+}
+
+/// Ask the OS which local address it would use to reach the internet.
+/// No packets are sent; connecting a UDP socket only selects a route.
+fn default_route_ipv4() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(8, 8, 8, 8), 80)).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) if !ip.is_loopback() && !ip.is_unspecified() => Some(ip),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASK_24: Ipv4Addr = Ipv4Addr::new(255, 255, 255, 0);
+
+    #[tokio::test]
+    async fn a_running_receiver_refuses_a_second_start() {
+        let mut receiver = NetworkReceiver::new().await.unwrap();
+        receiver.start(Vec::new(), 0, ReceiverConfig::default()).await.unwrap();
+        assert!(receiver.is_running());
+
+        let again = receiver.start(vec!["airplay".to_string()], 0, ReceiverConfig::default()).await;
+        assert!(matches!(again, Err(CasterError::InvalidRequest(_))), "{:?}", again);
+        assert!(receiver.protocols.is_empty());
+
+        receiver.stop().await.unwrap();
+        assert!(!receiver.is_running());
+        receiver.start(Vec::new(), 0, ReceiverConfig::default()).await.unwrap();
+        receiver.stop().await.unwrap();
+    }
+
+    #[test]
+    fn lan_ip_prefers_the_default_route_interface() {
+        let candidates = [
+            (Ipv4Addr::new(172, 17, 0, 1), Ipv4Addr::new(255, 255, 0, 0)),
+            (Ipv4Addr::new(192, 168, 1, 20), MASK_24),
+        ];
+        let route = Some(Ipv4Addr::new(192, 168, 1, 20));
+        assert_eq!(select_lan_ip(&candidates, route), Some((Ipv4Addr::new(192, 168, 1, 20), "default route interface")));
+    }
+
+    #[test]
+    fn lan_ip_matches_the_route_by_subnet() {
+        // The route source can be a secondary address on the same subnet
+        let candidates = [
+            (Ipv4Addr::new(10, 0, 0, 5), MASK_24),
+            (Ipv4Addr::new(192, 168, 1, 20), MASK_24),
+        ];
+        let route = Some(Ipv4Addr::new(192, 168, 1, 99));
+        assert_eq!(select_lan_ip(&candidates, route).map(|(ip, _)| ip), Some(Ipv4Addr::new(192, 168, 1, 20)));
+    }
+
+    #[test]
+    fn lan_ip_falls_back_to_a_private_interface() {
+        // e.g. a VPN tunnel carries the default route
+        let candidates = [
+            (Ipv4Addr::new(203, 0, 113, 7), MASK_24),
+            (Ipv4Addr::new(10, 0, 0, 5), MASK_24),
+        ];
+        let route = Some(Ipv4Addr::new(100, 64, 0, 2));
+        assert_eq!(select_lan_ip(&candidates, route), Some((Ipv4Addr::new(10, 0, 0, 5), "first private interface")));
+        assert_eq!(select_lan_ip(&candidates, None).map(|(ip, _)| ip), Some(Ipv4Addr::new(10, 0, 0, 5)));
+    }
+
+    #[test]
+    fn lan_ip_takes_any_interface_when_none_is_private() {
+        let candidates = [
+            (Ipv4Addr::new(203, 0, 113, 7), MASK_24),
+            (Ipv4Addr::new(198, 51, 100, 3), MASK_24),
+        ];
+        assert_eq!(select_lan_ip(&candidates, None), Some((Ipv4Addr::new(203, 0, 113, 7), "first non-loopback interface")));
+    }
+
+    #[test]
+    fn lan_ip_is_none_without_interfaces() {
+        assert_eq!(select_lan_ip(&[], Some(Ipv4Addr::new(192, 168, 1, 20))), None);
+    }
+
+    fn receiver_config() -> ReceiverConfig {
+        ReceiverConfig {
+            friendly_name: "Lobby TV".to_string(),
+            model: "Q8Caster1,1".to_string(),
+            device_uuid: Uuid::parse_str("6f1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d").unwrap(),
+        }
+    }
+
+    fn assert_has_keys(txt: &HashMap<String, String>, keys: &[&str]) {
+        for key in keys {
+            assert!(txt.contains_key(*key), "missing TXT key {}", key);
+        }
+    }
+
+    #[test]
+    fn airplay_txt_has_the_mandatory_keys() {
+        let txt = receiver_config().airplay_txt();
+        assert_has_keys(&txt, &["deviceid", "features", "model", "srcvers", "flags", "pi"]);
+        assert_eq!(txt["model"], "Q8Caster1,1");
+        assert!(txt["features"].starts_with("0x"));
+    }
+
+    #[test]
+    fn airplay_device_id_is_a_locally_administered_mac() {
+        let id = receiver_config().airplay_device_id();
+        let octets: Vec<u8> = id.split(':').map(|octet| u8::from_str_radix(octet, 16).unwrap()).collect();
+        assert_eq!(octets.len(), 6);
+        // Locally administered, unicast
+        assert_eq!(octets[0] & 0x03, 0x02);
+    }
+
+    #[test]
+    fn chromecast_txt_has_the_mandatory_keys() {
+        let txt = receiver_config().chromecast_txt();
+        assert_has_keys(&txt, &["id", "cd", "ve", "md", "fn", "ca", "st", "rs"]);
+        assert_eq!(txt["fn"], "Lobby TV");
+        assert_eq!(txt["id"], "6f1b2c3d4e5f4a6b8c7d9e0f1a2b3c4d");
+    }
+
+    #[test]
+    fn dlna_txt_has_the_mandatory_keys() {
+        let txt = receiver_config().dlna_txt();
+        assert_has_keys(&txt, &["uuid", "fn", "md"]);
+        assert_eq!(txt["uuid"], "6f1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d");
+    }
+}
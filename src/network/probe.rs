@@ -0,0 +1,219 @@
+use rupnp::http::Uri;
+use rupnp::ssdp::URN;
+use rupnp::Device;
+use serde_json::json;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::{Result, CasterError};
+use super::chromecast_simple::open_connection;
+use super::discovery::{DeviceCapabilities, DeviceType, DiscoveredDevice};
+
+/// Probing happens in the background, but a dead device shouldn't hold a task forever
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cast `ca` TXT capability bits
+const CAST_VIDEO_OUT: u32 = 0x01;
+const CAST_AUDIO_OUT: u32 = 0x04;
+const CAST_MULTIZONE_GROUP: u32 = 0x20;
+
+/// What a probe learned: capabilities, plus facts worth keeping in the device metadata
+pub struct ProbeResult {
+    pub capabilities: DeviceCapabilities,
+    pub metadata: serde_json::Value,
+}
+
+/// Ask the device what it can do. None for types without a probe; errors mean the
+/// caller should keep the capabilities it guessed from the type.
+pub async fn probe_capabilities(device: &DiscoveredDevice) -> Option<Result<ProbeResult>> {
+    let probe = match device.device_type {
+        DeviceType::Chromecast => tokio::time::timeout(PROBE_TIMEOUT, probe_chromecast(device)).await,
+        DeviceType::Dlna => tokio::time::timeout(PROBE_TIMEOUT, probe_dlna(device)).await,
+        _ => return None,
+    };
+
+    Some(probe.unwrap_or_else(|_| Err(CasterError::Network(format!("Probing {} timed out", device.name)))))
+}
+
+/// Cast devices say what outputs they have in the `ca` TXT record and what they
+/// are in `md`; the receiver status confirms they answer and reports volume and apps
+async fn probe_chromecast(device: &DiscoveredDevice) -> Result<ProbeResult> {
+    let host = device.ip.to_string();
+    let port = device.port;
+    let status = tokio::task::spawn_blocking(move || {
+        let cast_device = open_connection(host, port, None)?;
+        cast_device.receiver.get_status()
+            .map_err(|e| CasterError::Network(format!("Failed to get receiver status: {}", e)))
+    })
+    .await
+    .map_err(|e| CasterError::Network(format!("Probe task failed: {}", e)))??;
+
+    let flags = device.metadata["ca"].as_str()
+        .and_then(|ca| ca.parse::<u32>().ok())
+        .ok_or_else(|| CasterError::Network(format!("{} did not advertise its capabilities", device.name)))?;
+    let model = device.metadata["md"].as_str().unwrap_or_default();
+
+    let video = flags & CAST_VIDEO_OUT != 0;
+    let audio = flags & CAST_AUDIO_OUT != 0;
+    let uhd = ["Ultra", "Google TV", "4K"].iter().any(|marker| model.contains(marker));
+
+    let supported_codecs = match (video, uhd) {
+        (true, true) => vec!["h264", "h265", "vp8", "vp9", "aac", "opus"],
+        (true, false) => vec!["h264", "vp8", "aac", "opus"],
+        // Speakers and groups only decode audio
+        (false, _) => vec!["aac", "mp3", "opus", "flac"],
+    };
+
+    Ok(ProbeResult {
+        capabilities: DeviceCapabilities {
+            can_video: video,
+            can_audio: audio,
+            can_image: video,
+            can_mirror: video,
+            supported_codecs: supported_codecs.into_iter().map(String::from).collect(),
+            max_resolution: video.then(|| if uhd { "4K" } else { "1080p" }.to_string()),
+            protocols: vec!["cast".into()],
+        },
+        metadata: json!({
+            "model": model,
+            "group": flags & CAST_MULTIZONE_GROUP != 0,
+            "volume": {
+                "level": status.volume.level,
+                "muted": status.volume.muted,
+            },
+            "running_apps": status.applications.iter().map(|app| app.display_name.clone()).collect::<Vec<_>>(),
+        }),
+    })
+}
+
+/// The device description lists its services, and ConnectionManager's
+/// GetProtocolInfo lists every format the renderer will accept (its "sink")
+async fn probe_dlna(device: &DiscoveredDevice) -> Result<ProbeResult> {
+    let location = device.metadata["location"].as_str()
+        .ok_or_else(|| CasterError::Network(format!("{} has no device description location", device.name)))?;
+    let url: Uri = location.parse()
+        .map_err(|e| CasterError::Network(format!("Invalid device location {}: {}", location, e)))?;
+
+    let description = Device::from_url(url).await
+        .map_err(|e| CasterError::Network(format!("Failed to fetch device description: {}", e)))?;
+
+    let services: Vec<String> = description.services()
+        .iter()
+        .map(|service| service.service_type().typ().to_string())
+        .collect();
+
+    let connection_manager = description
+        .find_service(&URN::service("schemas-upnp-org", "ConnectionManager", 1))
+        .ok_or_else(|| CasterError::Network(format!("{} has no ConnectionManager service", description.friendly_name())))?;
+    let protocol_info = connection_manager.action(description.url(), "GetProtocolInfo", "").await
+        .map_err(|e| CasterError::Network(format!("GetProtocolInfo failed: {}", e)))?;
+    let sink = protocol_info.get("Sink").map(String::as_str).unwrap_or_default();
+
+    let formats = parse_sink(sink);
+    debug!("{} accepts {} formats", description.friendly_name(), formats.len());
+
+    let any_mime = |prefix: &str| formats.iter().any(|(mime, _)| mime.starts_with(prefix));
+    let profiles = || formats.iter().filter_map(|(_, profile)| profile.as_deref());
+
+    let mut codecs: Vec<String> = Vec::new();
+    for (mime, profile) in &formats {
+        let codec = profile.as_deref().and_then(profile_codec).or_else(|| mime_codec(mime));
+        if let Some(codec) = codec {
+            if !codecs.iter().any(|c| c == codec) {
+                codecs.push(codec.to_string());
+            }
+        }
+    }
+
+    let max_resolution = if profiles().any(|p| p.contains("UHD") || p.contains("4K")) {
+        Some("4K".to_string())
+    } else if profiles().any(|p| p.contains("_HD")) {
+        Some("1080p".to_string())
+    } else {
+        None
+    };
+
+    let mut protocols = vec!["dlna".to_string()];
+    if services.iter().any(|s| s == "RenderingControl") {
+        protocols.push("rendering_control".to_string());
+    }
+
+    Ok(ProbeResult {
+        capabilities: DeviceCapabilities {
+            can_video: any_mime("video/"),
+            can_audio: any_mime("audio/"),
+            can_image: any_mime("image/"),
+            can_mirror: false,
+            supported_codecs: codecs,
+            max_resolution,
+            protocols,
+        },
+        metadata: json!({
+            "friendly_name": description.friendly_name(),
+            "services": services,
+        }),
+    })
+}
+
+/// Sink entries look like `http-get:*:video/mp4:DLNA.ORG_PN=AVC_MP4_HP_HD_AAC;DLNA.ORG_OP=01`.
+/// Returns each MIME type with its DLNA profile name, if it has one.
+fn parse_sink(sink: &str) -> Vec<(String, Option<String>)> {
+    sink.split(',')
+        .filter_map(|entry| {
+            let mut fields = entry.trim().splitn(4, ':');
+            let protocol = fields.next()?;
+            let _network = fields.next()?;
+            let mime = fields.next()?;
+            let extra = fields.next().unwrap_or("*");
+
+            if protocol != "http-get" || mime == "*" {
+                return None;
+            }
+
+            let profile = extra.split(';')
+                .find_map(|param| param.strip_prefix("DLNA.ORG_PN="))
+                .map(str::to_string);
+            Some((mime.to_ascii_lowercase(), profile))
+        })
+        .collect()
+}
+
+/// Codec named by a DLNA media format profile, e.g. AVC_MP4_HP_HD_AAC
+fn profile_codec(profile: &str) -> Option<&'static str> {
+    if profile.starts_with("AVC") {
+        Some("h264")
+    } else if profile.starts_with("HEVC") {
+        Some("h265")
+    } else if profile.starts_with("MPEG") {
+        Some("mpeg2")
+    } else if profile.starts_with("MP3") {
+        Some("mp3")
+    } else if profile.starts_with("AAC") || profile.starts_with("HEAAC") {
+        Some("aac")
+    } else if profile.starts_with("LPCM") {
+        Some("lpcm")
+    } else if profile.starts_with("JPEG") {
+        Some("jpeg")
+    } else if profile.starts_with("PNG") {
+        Some("png")
+    } else {
+        None
+    }
+}
+
+/// Best guess at the codec for a sink entry without a profile
+fn mime_codec(mime: &str) -> Option<&'static str> {
+    match mime {
+        "video/mp4" | "video/x-m4v" => Some("h264"),
+        "video/x-matroska" | "video/webm" => Some("vp9"),
+        "video/mpeg" => Some("mpeg2"),
+        "audio/mpeg" | "audio/mp3" => Some("mp3"),
+        "audio/mp4" | "audio/aac" | "audio/x-m4a" => Some("aac"),
+        "audio/flac" | "audio/x-flac" => Some("flac"),
+        "audio/wav" | "audio/x-wav" | "audio/l16" => Some("lpcm"),
+        "audio/ogg" => Some("vorbis"),
+        "image/jpeg" => Some("jpeg"),
+        "image/png" => Some("png"),
+        _ => None,
+    }
+}
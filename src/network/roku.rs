@@ -0,0 +1,146 @@
+use reqwest::StatusCode;
+use serde_json::json;
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::{Result, CasterError};
+use super::discovery::{DeviceType, DiscoveredDevice};
+
+/// ECP answers from the device itself; a hung TV shouldn't stall a request
+const ECP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Remote keys ECP accepts besides `Lit_<character>`
+const ROKU_KEYS: &[&str] = &[
+    "Home", "Rev", "Fwd", "Play", "Select", "Left", "Right", "Down", "Up", "Back",
+    "InstantReplay", "Info", "Backspace", "Search", "Enter", "FindRemote",
+    "VolumeDown", "VolumeUp", "VolumeMute", "PowerOff", "PowerOn",
+    "ChannelUp", "ChannelDown", "InputTuner", "InputHDMI1", "InputHDMI2",
+    "InputHDMI3", "InputHDMI4", "InputAV1",
+];
+
+/// HTTP client for a Roku's External Control Protocol
+pub struct RokuController {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl RokuController {
+    pub fn for_device(device: &DiscoveredDevice) -> Result<Self> {
+        if device.device_type != DeviceType::Roku {
+            return Err(CasterError::Network(format!("{} is not a Roku", device.name)));
+        }
+
+        let http = reqwest::Client::builder()
+            .timeout(ECP_TIMEOUT)
+            .build()
+            .map_err(|e| CasterError::Network(format!("Failed to create ECP client: {}", e)))?;
+
+        Ok(Self {
+            http,
+            base_url: format!("http://{}", std::net::SocketAddr::new(device.ip, device.port)),
+        })
+    }
+
+    /// Launch an app by channel id (e.g. 12 for Netflix); `params` become deep-link
+    /// query parameters such as `contentId` and `mediaType`
+    pub async fn launch(&self, app_id: &str, params: &[(String, String)]) -> Result<()> {
+        if app_id.is_empty() || !app_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(CasterError::Network(format!("Invalid Roku app id: {}", app_id)));
+        }
+
+        let response = self.http.post(format!("{}/launch/{}", self.base_url, app_id))
+            .query(params)
+            .send()
+            .await
+            .map_err(|e| CasterError::Network(format!("Roku launch of {} failed: {}", app_id, e)))?;
+
+        check_status(&format!("launch of {}", app_id), response.status())?;
+        info!("Launched Roku app {}", app_id);
+        Ok(())
+    }
+
+    /// Press and release a remote key
+    pub async fn keypress(&self, key: &str) -> Result<()> {
+        let known = ROKU_KEYS.iter().any(|k| k.eq_ignore_ascii_case(key))
+            || key.strip_prefix("Lit_").is_some_and(|c| !c.is_empty());
+        if !known {
+            return Err(CasterError::Network(format!("Unknown Roku key: {}", key)));
+        }
+
+        // Literal characters have to survive the URL path
+        let key = match key.strip_prefix("Lit_") {
+            Some(literal) => format!("Lit_{}", url::form_urlencoded::byte_serialize(literal.as_bytes()).collect::<String>()),
+            None => key.to_string(),
+        };
+
+        let response = self.http.post(format!("{}/keypress/{}", self.base_url, key))
+            .send()
+            .await
+            .map_err(|e| CasterError::Network(format!("Roku keypress {} failed: {}", key, e)))?;
+
+        check_status(&format!("keypress {}", key), response.status())?;
+        debug!("Sent Roku key {}", key);
+        Ok(())
+    }
+
+    /// What the media player is doing: state, the app playing, position and duration in ms
+    pub async fn media_player(&self) -> Result<serde_json::Value> {
+        let response = self.http.get(format!("{}/query/media-player", self.base_url))
+            .send()
+            .await
+            .map_err(|e| CasterError::Network(format!("Roku media query failed: {}", e)))?;
+        check_status("media query", response.status())?;
+
+        let body = response.text().await
+            .map_err(|e| CasterError::Network(format!("Failed to read Roku media status: {}", e)))?;
+
+        let millis = |name: &str| {
+            xml_element(&body, name)
+                .and_then(|value| value.trim_end_matches("ms").trim().parse::<u64>().ok())
+        };
+
+        Ok(json!({
+            "state": xml_attribute(&body, "player", "state").unwrap_or("unknown"),
+            "error": xml_attribute(&body, "player", "error") == Some("true"),
+            "app_id": xml_attribute(&body, "plugin", "id"),
+            "app_name": xml_attribute(&body, "plugin", "name"),
+            "position_ms": millis("position"),
+            "duration_ms": millis("duration"),
+            "is_live": xml_element(&body, "is_live") == Some("true"),
+        }))
+    }
+}
+
+fn check_status(action: &str, status: StatusCode) -> Result<()> {
+    match status {
+        status if status.is_success() => Ok(()),
+        // Newer firmware turns ECP off unless "Control by mobile apps" is enabled
+        StatusCode::FORBIDDEN => Err(CasterError::Network(format!(
+            "Roku refused {}; enable Settings > System > Advanced system settings > Control by mobile apps", action
+        ))),
+        StatusCode::NOT_FOUND => Err(CasterError::NotFound(format!("Roku {} failed: not found", action))),
+        status => Err(CasterError::Network(format!("Roku {} returned {}", action, status))),
+    }
+}
+
+/// Text of the first `<name>` element; ECP replies are small enough to not need a parser
+fn xml_element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(xml[start..end].trim())
+}
+
+/// Value of `attribute` on the first `<element ...>` tag
+fn xml_attribute<'a>(xml: &'a str, element: &str, attribute: &str) -> Option<&'a str> {
+    // Keep the space after the element name so every attribute is preceded by one
+    let open = format!("<{}", element);
+    let start = xml.find(&format!("{} ", open))? + open.len();
+    let tag = &xml[start..start + xml[start..].find('>')?];
+
+    let key = format!(" {}=\"", attribute);
+    let value_start = tag.find(&key)? + key.len();
+    let value_end = value_start + tag[value_start..].find('"')?;
+    Some(&tag[value_start..value_end])
+}
@@ -0,0 +1,162 @@
+use rustfft::{num_complex::Complex, FftPlanner};
+
+/// FFT size used when rendering spectra straight from samples
+pub const DEFAULT_FFT_SIZE: usize = 2048;
+
+/// Magnitudes of evenly spaced frequency bins, from 0 Hz up to just under Nyquist
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Spectrum {
+    /// Scaled to 0.0..1.0, the loudest bin being 1.0
+    pub magnitudes: Vec<f32>,
+    /// Width of each bin in Hz
+    pub bin_hz: f32,
+}
+
+impl Spectrum {
+    /// Frequency at the middle of bin `bin`
+    pub fn frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.bin_hz
+    }
+
+    /// The bin `hz` falls in, if it's below Nyquist
+    pub fn bin(&self, hz: f32) -> Option<usize> {
+        let bin = (hz / self.bin_hz).round();
+        (bin >= 0.0 && (bin as usize) < self.magnitudes.len()).then_some(bin as usize)
+    }
+
+    /// Frequency of the loudest bin, or None for silence
+    pub fn peak_frequency(&self) -> Option<f32> {
+        self.magnitudes.iter()
+            .enumerate()
+            .filter(|(_, magnitude)| **magnitude > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(bin, _)| self.frequency(bin))
+    }
+}
+
+/// Compute normalized frequency magnitudes from raw samples taken at `sample_rate`.
+///
+/// Applies a Hann window over the first `fft_size` samples (zero-padding
+/// shorter buffers) and returns `fft_size / 2` bins scaled to 0.0..1.0, each
+/// `sample_rate / fft_size` Hz wide.
+pub fn compute_spectrum(samples: &[f32], sample_rate: u32, fft_size: usize) -> Spectrum {
+    if fft_size == 0 || sample_rate == 0 {
+        return Spectrum::default();
+    }
+
+    let denom = (fft_size.max(2) - 1) as f32;
+    let mut buffer: Vec<Complex<f32>> = (0..fft_size)
+        .map(|i| {
+            let sample = samples.get(i).copied().unwrap_or(0.0);
+            let window = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / denom).cos();
+            Complex::new(sample * window, 0.0)
+        })
+        .collect();
+
+    let mut planner = FftPlanner::new();
+    planner.plan_fft_forward(fft_size).process(&mut buffer);
+
+    // Only the first half is meaningful for real input
+    let mut magnitudes: Vec<f32> = buffer[..fft_size / 2].iter().map(|c| c.norm()).collect();
+
+    let peak = magnitudes.iter().copied().fold(0.0f32, f32::max);
+    if peak > 0.0 {
+        for magnitude in &mut magnitudes {
+            *magnitude /= peak;
+        }
+    }
+
+    Spectrum {
+        magnitudes,
+        bin_hz: sample_rate as f32 / fft_size as f32,
+    }
+}
+
+/// Short-time Fourier transform: the magnitude of each of `window_size / 2`
+/// bins, for every window across `samples`. Always returns at least one frame.
+pub fn compute_stft(samples: &[f32], window_size: usize, overlap: f32) -> Vec<Vec<f32>> {
+    let window_size = window_size.max(2);
+    let hop = ((window_size as f32 * (1.0 - overlap.clamp(0.0, 0.95))) as usize).max(1);
+    let frames = 1 + samples.len().saturating_sub(window_size) / hop;
+
+    let denom = (window_size - 1) as f32;
+    let window: Vec<f32> = (0..window_size)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / denom).cos())
+        .collect();
+    let fft = FftPlanner::new().plan_fft_forward(window_size);
+    let mut buffer = vec![Complex::new(0.0, 0.0); window_size];
+
+    (0..frames)
+        .map(|frame| {
+            let start = frame * hop;
+            for (i, slot) in buffer.iter_mut().enumerate() {
+                let sample = samples.get(start + i).copied().unwrap_or(0.0);
+                *slot = Complex::new(sample * window[i], 0.0);
+            }
+            fft.process(&mut buffer);
+            buffer[..window_size / 2].iter().map(|c| c.norm()).collect()
+        })
+        .collect()
+}
+
+/// How loud a stretch of samples is, each from 0.0 to 1.0 for full scale
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct AudioLevels {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+/// RMS and peak level of `samples`, for the level meter
+pub fn compute_levels(samples: &[f32]) -> AudioLevels {
+    if samples.is_empty() {
+        return AudioLevels::default();
+    }
+
+    let (sum, peak) = samples.iter().fold((0.0f64, 0.0f32), |(sum, peak), sample| {
+        (sum + (*sample as f64) * (*sample as f64), peak.max(sample.abs()))
+    });
+    AudioLevels {
+        rms: ((sum / samples.len() as f64).sqrt() as f32).min(1.0),
+        peak: peak.min(1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(hz: f32, sample_rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * std::f32::consts::PI * hz * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn a_1khz_tone_peaks_in_its_bin() {
+        let spectrum = compute_spectrum(&sine(1000.0, 48000, 4096), 48000, 4096);
+
+        assert_eq!(spectrum.magnitudes.len(), 2048);
+        assert_eq!(spectrum.bin_hz, 48000.0 / 4096.0);
+        // 1000 Hz / 11.72 Hz a bin
+        assert_eq!(spectrum.bin(1000.0), Some(85));
+        let peak = spectrum.peak_frequency().unwrap();
+        assert!((peak - 1000.0).abs() <= spectrum.bin_hz / 2.0, "peak at {} Hz", peak);
+        assert_eq!(spectrum.magnitudes[85], 1.0);
+        // Well away from the tone there's next to nothing
+        assert!(spectrum.magnitudes[spectrum.bin(4000.0).unwrap()] < 0.01);
+    }
+
+    #[test]
+    fn bins_follow_the_sample_rate() {
+        // The same tone at another rate lands in another bin
+        let spectrum = compute_spectrum(&sine(1000.0, 8000, 1024), 8000, 1024);
+        assert_eq!(spectrum.bin(1000.0), Some(128));
+        assert_eq!(spectrum.peak_frequency(), Some(1000.0));
+    }
+
+    #[test]
+    fn silence_has_no_peak() {
+        assert_eq!(compute_spectrum(&[0.0; 512], 44100, 512).peak_frequency(), None);
+        assert_eq!(compute_spectrum(&[], 44100, 0), Spectrum::default());
+    }
+}
@@ -0,0 +1,212 @@
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+
+use crate::{CasterError, Result};
+
+/// egui's monospace font, so digits line up and nothing needs installing
+static FONT: Lazy<std::result::Result<FontVec, String>> = Lazy::new(|| {
+    let definitions = egui::FontDefinitions::default();
+    let data = definitions.font_data.get("Hack").ok_or("Font Hack is missing")?;
+    FontVec::try_from_vec(data.font.to_vec()).map_err(|e| format!("Font Hack is invalid: {}", e))
+});
+
+pub(super) const TEXT_COLOR: Rgba<u8> = Rgba([170, 170, 190, 255]);
+pub(super) const AXIS_COLOR: Rgba<u8> = Rgba([80, 80, 100, 255]);
+
+/// How a label sits against the x it's drawn at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Align {
+    Center,
+    Right,
+}
+
+/// Label text size for a `width` x `height` image: 13px on a 640x360
+/// thumbnail, growing in proportion up to 4K
+pub(super) fn label_size(width: u32, height: u32) -> f32 {
+    (width.max(height) as f32 / 48.0).min(height as f32 / 8.0).max(9.0)
+}
+
+/// Space a time or frequency ruler takes under a plot
+pub(super) fn ruler_height(size: f32) -> u32 {
+    (size * 1.9).ceil() as u32
+}
+
+fn line_width(size: f32) -> u32 {
+    (size / 12.0).round().max(1.0) as u32
+}
+
+fn font() -> Result<&'static FontVec> {
+    FONT.as_ref().map_err(|e| CasterError::Render(e.clone()))
+}
+
+pub(super) fn text_width(text: &str, size: f32) -> Result<f32> {
+    let scaled = font()?.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    Ok(width)
+}
+
+/// Draw `text` with its vertical middle at `y`
+pub(super) fn draw_label(
+    image: &mut RgbaImage,
+    text: &str,
+    x: f32,
+    y: f32,
+    size: f32,
+    align: Align,
+    color: Rgba<u8>,
+) -> Result<()> {
+    let font = font()?;
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+    let width = text_width(text, size)?;
+    let mut caret = match align {
+        Align::Center => x - width / 2.0,
+        Align::Right => x - width,
+    };
+    // Centre the digits' height rather than ascent plus descent
+    let baseline = y + scaled.ascent() * 0.36;
+
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(scale, point(caret, baseline));
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                blend(image, bounds.min.x as i64 + gx as i64, bounds.min.y as i64 + gy as i64, color, coverage);
+            });
+        }
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    Ok(())
+}
+
+/// A ruler along the top of `top`, from `left` for `width` pixels, marking
+/// values from 0 to `end` with labels from `format(value, step)`
+pub(super) fn draw_ruler(
+    image: &mut RgbaImage,
+    left: u32,
+    top: u32,
+    width: u32,
+    end: f32,
+    size: f32,
+    format: impl Fn(f32, f32) -> String,
+) -> Result<()> {
+    let thickness = line_width(size);
+    fill(image, left, top, width, thickness, AXIS_COLOR);
+    if end <= 0.0 || width == 0 {
+        return Ok(());
+    }
+
+    // Room for the widest label plus a gap either side
+    let widest = text_width(&format(end, end), size)?.max(size * 2.0);
+    let count = (width as f32 / (widest * 1.8)).floor().max(1.0);
+    let step = nice_step(end / count);
+    let tick = (size * 0.4).ceil() as u32;
+    let label_y = top as f32 + tick as f32 + size * 0.75;
+
+    let mut value = 0.0;
+    while value <= end * 1.0001 {
+        let x = left as f32 + value / end * width as f32;
+        let tick_x = (x as u32).min(left + width - thickness);
+        fill(image, tick_x, top, thickness, tick, AXIS_COLOR);
+
+        let label = format(value, step);
+        let label_width = text_width(&label, size)?;
+        // Keep the first and last labels inside the image
+        let x = x.clamp(label_width / 2.0, image.width() as f32 - label_width / 2.0);
+        draw_label(image, &label, x, label_y, size, Align::Center, TEXT_COLOR)?;
+        value += step;
+    }
+    Ok(())
+}
+
+/// Labels down the left of a plot whose left edge is `right`, each at its y;
+/// a label that would overlap the one before it is left out
+pub(super) fn draw_value_axis(image: &mut RgbaImage, right: u32, ticks: &[(f32, String)], size: f32) -> Result<()> {
+    let thickness = line_width(size);
+    let tick = (size * 0.4).ceil() as u32;
+    fill(image, right.saturating_sub(thickness), 0, thickness, image.height(), AXIS_COLOR);
+
+    let mut last: Option<f32> = None;
+    for (y, label) in ticks {
+        if last.is_some_and(|last| (y - last).abs() < size * 1.2) {
+            continue;
+        }
+        let y = y.clamp(size * 0.6, image.height() as f32 - size * 0.6);
+        fill(image, right.saturating_sub(tick), y as u32, tick, thickness, AXIS_COLOR);
+        draw_label(image, label, right as f32 - tick as f32 - size * 0.3, y, size, Align::Right, TEXT_COLOR)?;
+        last = Some(y);
+    }
+    Ok(())
+}
+
+/// 1, 2 or 5 times a power of ten, at least `rough`
+pub(super) fn nice_step(rough: f32) -> f32 {
+    if rough <= 0.0 || !rough.is_finite() {
+        return 1.0;
+    }
+    let magnitude = 10f32.powf(rough.log10().floor());
+    [1.0, 2.0, 5.0, 10.0].iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= rough * 0.999)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// "0.5s" or "45s", with as many decimals as the step needs, and "1:30" past a minute
+pub(super) fn format_seconds(seconds: f32, step: f32) -> String {
+    if step >= 1.0 && seconds >= 60.0 {
+        let seconds = seconds.round() as u32;
+        return format!("{}:{:02}", seconds / 60, seconds % 60);
+    }
+    let decimals = if step >= 1.0 { 0 } else { (-step.log10().floor()) as usize };
+    format!("{:.*}s", decimals, seconds)
+}
+
+/// "500", "1k", "2.5k"
+pub(super) fn format_hertz(hertz: f32) -> String {
+    if hertz >= 1000.0 {
+        let khz = hertz / 1000.0;
+        if (khz - khz.round()).abs() < 0.05 {
+            format!("{}k", khz.round())
+        } else {
+            format!("{:.1}k", khz)
+        }
+    } else {
+        format!("{}", hertz.round())
+    }
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn blend(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>, coverage: f32) {
+    if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+        return;
+    }
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    let alpha = coverage.clamp(0.0, 1.0);
+    for channel in 0..3 {
+        let under = pixel.0[channel] as f32;
+        pixel.0[channel] = (under + (color.0[channel] as f32 - under) * alpha).round() as u8;
+    }
+}
@@ -0,0 +1,677 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::sync::Mutex;
+
+use crate::Result;
+
+pub mod analysis;
+mod labels;
+
+pub use analysis::{compute_levels, compute_spectrum, compute_stft, AudioLevels, Spectrum, DEFAULT_FFT_SIZE};
+
+use labels::{draw_label, draw_ruler, draw_value_axis, format_hertz, format_seconds, label_size, ruler_height, text_width, Align};
+
+/// Ticks down the spectrum's magnitude axis
+const SPECTRUM_DB_TICKS: [f32; 5] = [0.0, -3.0, -6.0, -10.0, -20.0];
+
+/// Ticks up a log frequency axis, where they're spread evenly
+const LOG_FREQUENCY_TICKS: [f32; 9] = [50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0, 20000.0];
+
+/// An audio renderer for generating visualizations.
+///
+/// Each render method generates a new image from the input samples or
+/// frequency data alone. The only state is the level meter's dial, kept so
+/// live metering at one size redraws just the arc and the level.
+pub struct AudioRenderer {
+    meter_background: Mutex<Option<RgbaImage>>,
+}
+
+impl Default for AudioRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioRenderer {
+    pub fn new() -> Self {
+        Self {
+            meter_background: Mutex::new(None),
+        }
+    }
+
+    /// Render audio waveform visualization, over a ruler of its duration
+    pub fn render_waveform(&self, samples: &[f32], sample_rate: u32, width: u32, height: u32) -> Result<DynamicImage> {
+        let size = label_size(width, height);
+        let ruler = ruler_height(size);
+        let samples_per_pixel = samples.len() / width.max(1) as usize;
+        // Only with a duration to show and most of the height left for the waveform
+        if sample_rate == 0 || samples_per_pixel == 0 || ruler * 3 > height {
+            return Ok(DynamicImage::ImageRgba8(waveform_plot(samples, width, height)));
+        }
+
+        let mut img = background(width, height);
+        image::imageops::replace(&mut img, &waveform_plot(samples, width, height - ruler), 0, 0);
+        // Columns only cover whole multiples of samples_per_pixel
+        let shown = (samples_per_pixel * width as usize) as f32 / sample_rate as f32;
+        draw_ruler(&mut img, 0, height - ruler, width, shown, size, format_seconds)?;
+
+        Ok(DynamicImage::ImageRgba8(img))
+    }
+
+    /// Render frequency spectrum visualization, with bins spread up to the
+    /// Nyquist frequency of `sample_rate`
+    pub fn render_spectrum(&self, frequencies: &[f32], sample_rate: u32, width: u32, height: u32) -> Result<DynamicImage> {
+        let size = label_size(width, height);
+        let ruler = ruler_height(size);
+        let gutter = value_gutter(&SPECTRUM_DB_TICKS.map(format_db), size)?;
+        if sample_rate == 0 || ruler * 3 > height || gutter * 4 > width {
+            return Ok(DynamicImage::ImageRgba8(spectrum_plot(frequencies, width, height)));
+        }
+
+        let (plot_width, plot_height) = (width - gutter, height - ruler);
+        let mut img = background(width, height);
+        image::imageops::replace(&mut img, &spectrum_plot(frequencies, plot_width, plot_height), gutter as i64, 0);
+
+        // Bars are linear in magnitude, so the dB ticks bunch towards the bottom
+        let ticks: Vec<(f32, String)> = SPECTRUM_DB_TICKS.iter()
+            .map(|&db| ((1.0 - 10f32.powf(db / 20.0)) * plot_height as f32, format_db(db)))
+            .collect();
+        draw_value_axis(&mut img, gutter, &ticks, size)?;
+
+        // Bars are whole pixels wide, so they may stop short of the right edge
+        let bars = plot_width.min(frequencies.len() as u32).max(1);
+        let drawn = bars * (plot_width / bars);
+        draw_ruler(&mut img, gutter, plot_height, drawn, sample_rate as f32 / 2.0, size, |hz, _| format_hertz(hz))?;
+
+        Ok(DynamicImage::ImageRgba8(img))
+    }
+
+    /// Render frequency spectrum visualization directly from raw samples
+    pub fn render_spectrum_from_samples(&self, samples: &[f32], sample_rate: u32, width: u32, height: u32) -> Result<DynamicImage> {
+        let spectrum = compute_spectrum(samples, sample_rate, DEFAULT_FFT_SIZE);
+        self.render_spectrum(&spectrum.magnitudes, sample_rate, width, height)
+    }
+
+    /// Render a spectrogram: time left to right, frequency bottom to top, on a
+    /// log frequency axis with magnitudes in dB
+    pub fn render_spectrogram(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+        width: u32,
+        height: u32,
+        window_size: usize,
+        overlap: f32,
+    ) -> Result<DynamicImage> {
+        self.render_spectrogram_scaled(samples, sample_rate, width, height, window_size, overlap, SpectrogramScale::default())
+    }
+
+    /// Render a spectrogram with the axes scaled by `scale`.
+    ///
+    /// Each column is a Hann-windowed FFT of `window_size` samples, with
+    /// `overlap` (0.0 to 0.95) of each window shared with the next. Fewer
+    /// samples than one window are zero-padded into a single frame.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_spectrogram_scaled(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+        width: u32,
+        height: u32,
+        window_size: usize,
+        overlap: f32,
+        scale: SpectrogramScale,
+    ) -> Result<DynamicImage> {
+        let size = label_size(width, height);
+        let ruler = ruler_height(size);
+        let nyquist = sample_rate as f32 / 2.0;
+        let lowest = log_floor(sample_rate, window_size.max(2) / 2);
+
+        let plot_height = height.saturating_sub(ruler) as f32;
+        let frequency_ticks: Vec<f32> = if scale.log_frequency {
+            LOG_FREQUENCY_TICKS.iter().copied().filter(|hz| (lowest..=nyquist).contains(hz)).collect()
+        } else {
+            let step = labels::nice_step(nyquist / (plot_height / (size * 2.5)).max(1.0));
+            (1..).map(|i| i as f32 * step).take_while(|hz| *hz <= nyquist).collect()
+        };
+        let ticks: Vec<(f32, String)> = frequency_ticks.iter().rev()
+            .map(|&hz| {
+                let t = if scale.log_frequency {
+                    (hz / lowest).ln() / (nyquist / lowest).ln()
+                } else {
+                    hz / nyquist
+                };
+                ((1.0 - t) * plot_height, format_hertz(hz))
+            })
+            .collect();
+        let labels: Vec<String> = ticks.iter().map(|(_, label)| label.clone()).collect();
+        let gutter = value_gutter(&labels, size)?;
+
+        if samples.is_empty() || sample_rate == 0 || ticks.is_empty() || ruler * 3 > height || gutter * 4 > width {
+            let plot = spectrogram_plot(samples, sample_rate, width, height, window_size, overlap, scale);
+            return Ok(DynamicImage::ImageRgba8(plot));
+        }
+
+        let mut img = background(width, height);
+        let plot = spectrogram_plot(samples, sample_rate, width - gutter, height - ruler, window_size, overlap, scale);
+        image::imageops::replace(&mut img, &plot, gutter as i64, 0);
+        draw_value_axis(&mut img, gutter, &ticks, size)?;
+        let duration = samples.len() as f32 / sample_rate as f32;
+        draw_ruler(&mut img, gutter, height - ruler, width - gutter, duration, size, format_seconds)?;
+
+        Ok(DynamicImage::ImageRgba8(img))
+    }
+
+    /// Render circular audio level meter
+    pub fn render_level_meter(&self, level: f32, width: u32, height: u32) -> Result<DynamicImage> {
+        let mut img = {
+            let mut cached = self.meter_background.lock().unwrap();
+            match *cached {
+                Some(ref background) if background.dimensions() == (width, height) => background.clone(),
+                _ => cached.insert(meter_background(width, height)).clone(),
+            }
+        };
+
+        let center_x = width as f32 / 2.0;
+        let center_y = height as f32 / 2.0;
+        let radius = width.min(height) as f32 / 2.0 - 10.0;
+
+        // Draw level arc
+        let level_clamped = level.clamp(0.0, 1.0);
+        let angle = level_clamped * std::f32::consts::PI * 1.5; // 270 degrees max
+
+        let color = if level_clamped > 0.9 {
+            Rgba([255, 50, 50, 255]) // Red for clipping
+        } else if level_clamped > 0.7 {
+            Rgba([255, 200, 50, 255]) // Yellow for high
+        } else {
+            Rgba([50, 255, 100, 255]) // Green for normal
+        };
+
+        draw_arc(&mut img, center_x, center_y, radius - 5.0, 0.0, angle, color);
+
+        // Level percentage in the middle, sized to the dial so "100%" fits inside it
+        if radius > 0.0 {
+            let label = format!("{}%", (level_clamped * 100.0).round());
+            draw_label(&mut img, &label, center_x, center_y, radius * 0.5, Align::Center, color)?;
+        }
+
+        Ok(DynamicImage::ImageRgba8(img))
+    }
+}
+
+/// Lowest frequency on a log axis; below it there's little but rumble
+const MIN_LOG_FREQUENCY: f32 = 20.0;
+
+/// How a spectrogram's axes are scaled
+#[derive(Debug, Clone, Copy)]
+pub struct SpectrogramScale {
+    /// Space octaves evenly rather than hertz
+    pub log_frequency: bool,
+    /// Color by decibels below the loudest point rather than by raw magnitude
+    pub decibels: bool,
+    /// How many dB below the loudest point shows as silence
+    pub floor_db: f32,
+}
+
+impl Default for SpectrogramScale {
+    fn default() -> Self {
+        Self {
+            log_frequency: true,
+            decibels: true,
+            floor_db: 80.0,
+        }
+    }
+}
+
+fn background(width: u32, height: u32) -> RgbaImage {
+    RgbaImage::from_pixel(width, height, Rgba([20, 20, 30, 255]))
+}
+
+/// The level meter's dial: the background and the outer circle
+fn meter_background(width: u32, height: u32) -> RgbaImage {
+    let mut img = background(width, height);
+    let radius = width.min(height) as f32 / 2.0 - 10.0;
+    draw_circle(&mut img, width as f32 / 2.0, height as f32 / 2.0, radius, Rgba([60, 60, 80, 255]), false);
+    img
+}
+
+fn waveform_plot(samples: &[f32], width: u32, height: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(width, height);
+
+    // Background color (dark)
+    for pixel in img.pixels_mut() {
+        *pixel = Rgba([20, 20, 30, 255]);
+    }
+
+    if samples.is_empty() {
+        return img;
+    }
+
+    let samples_per_pixel = samples.len() / width as usize;
+    let mid_height = height / 2;
+
+    // Draw waveform
+    for x in 0..width {
+        let sample_start = (x as usize) * samples_per_pixel;
+        let sample_end = ((x + 1) as usize * samples_per_pixel).min(samples.len());
+
+        if sample_start >= samples.len() {
+            break;
+        }
+
+        // Get min and max for this pixel column
+        let mut min_val = 1.0f32;
+        let mut max_val = -1.0f32;
+
+        for sample in &samples[sample_start..sample_end] {
+            min_val = min_val.min(*sample);
+            max_val = max_val.max(*sample);
+        }
+
+        // Convert to pixel coordinates
+        let y_min = (mid_height as f32 - (max_val * mid_height as f32)) as u32;
+        let y_max = (mid_height as f32 - (min_val * mid_height as f32)) as u32;
+
+        // Draw vertical line for this sample range
+        let color = Rgba([100, 200, 255, 255]);
+        for y in y_min..=y_max.min(height - 1) {
+            img.put_pixel(x, y, color);
+        }
+    }
+
+    // Draw center line
+    let center_color = Rgba([80, 80, 100, 128]);
+    for x in 0..width {
+        img.put_pixel(x, mid_height, center_color);
+    }
+
+    img
+}
+
+fn spectrum_plot(frequencies: &[f32], width: u32, height: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(width, height);
+
+    // Background color (dark)
+    for pixel in img.pixels_mut() {
+        *pixel = Rgba([20, 20, 30, 255]);
+    }
+
+    if frequencies.is_empty() || width == 0 {
+        return img;
+    }
+
+    let bars = width.min(frequencies.len() as u32);
+    if bars == 0 {
+        return img;
+    }
+    
+    let bar_width = width / bars;
+
+    for i in 0..bars {
+        let freq_index = (i as f32 / bars as f32 * frequencies.len() as f32) as usize;
+        let magnitude = frequencies.get(freq_index).copied().unwrap_or(0.0);
+
+        // Normalize magnitude (assuming 0.0 to 1.0 range)
+        let bar_height = (magnitude * height as f32) as u32;
+
+        // Color gradient based on height (low = blue, high = red)
+        let hue = (1.0 - magnitude) * 240.0; // Blue to red
+        let color = hsv_to_rgb(hue, 1.0, magnitude.max(0.3));
+
+        // Draw bar
+        for x in (i * bar_width)..((i + 1) * bar_width).min(width) {
+            for y in (height.saturating_sub(bar_height))..height {
+                img.put_pixel(x, y, Rgba([color.0, color.1, color.2, 255]));
+            }
+        }
+    }
+
+    img
+}
+
+fn spectrogram_plot(
+    samples: &[f32],
+    sample_rate: u32,
+    width: u32,
+    height: u32,
+    window_size: usize,
+    overlap: f32,
+    scale: SpectrogramScale,
+) -> RgbaImage {
+    let mut img = RgbaImage::new(width, height);
+
+    // Background color (dark)
+    for pixel in img.pixels_mut() {
+        *pixel = Rgba([20, 20, 30, 255]);
+    }
+
+    if samples.is_empty() || width == 0 || height == 0 || sample_rate == 0 {
+        return img;
+    }
+
+    let frames = compute_stft(samples, window_size, overlap);
+    let bins = frames[0].len();
+    let peak = frames.iter().flatten().copied().fold(0.0f32, f32::max);
+    if bins == 0 || peak <= 0.0 {
+        return img;
+    }
+
+    let level = |magnitude: f32| {
+        let relative = magnitude / peak;
+        if scale.decibels {
+            let db = 20.0 * relative.max(1e-10).log10();
+            (1.0 + db / scale.floor_db.max(1.0)).clamp(0.0, 1.0)
+        } else {
+            relative.clamp(0.0, 1.0)
+        }
+    };
+
+    // Bin each row shows, top row highest
+    let bin_width = sample_rate as f32 / (bins * 2) as f32;
+    let nyquist = sample_rate as f32 / 2.0;
+    let lowest = log_floor(sample_rate, bins);
+    let row_bins: Vec<usize> = (0..height)
+        .map(|y| {
+            let t = 1.0 - (y as f32 + 0.5) / height as f32;
+            let frequency = if scale.log_frequency {
+                lowest * (nyquist / lowest).powf(t)
+            } else {
+                t * nyquist
+            };
+            ((frequency / bin_width) as usize).min(bins - 1)
+        })
+        .collect();
+
+    for x in 0..width {
+        let frame = &frames[(x as usize * frames.len() / width as usize).min(frames.len() - 1)];
+        for (y, &bin) in row_bins.iter().enumerate() {
+            let value = level(frame[bin]);
+            // Same ramp as the spectrum: blue when quiet, red when loud
+            let color = hsv_to_rgb((1.0 - value) * 240.0, 1.0, value);
+            img.put_pixel(x, y as u32, Rgba([color.0, color.1, color.2, 255]));
+        }
+    }
+
+    img
+}
+
+/// Lowest frequency a log axis shows, for an STFT with `bins` bins
+fn log_floor(sample_rate: u32, bins: usize) -> f32 {
+    let nyquist = sample_rate as f32 / 2.0;
+    let bin_width = sample_rate as f32 / (bins.max(1) * 2) as f32;
+    bin_width.max(MIN_LOG_FREQUENCY).min(nyquist)
+}
+
+/// Width left of a plot for value labels, their ticks and some space
+fn value_gutter(labels: &[String], size: f32) -> Result<u32> {
+    let mut widest = 0.0f32;
+    for label in labels {
+        widest = widest.max(text_width(label, size)?);
+    }
+    Ok((widest + size * 1.2).ceil() as u32)
+}
+
+fn format_db(db: f32) -> String {
+    if db == 0.0 {
+        "0 dB".to_string()
+    } else {
+        format!("{}", db)
+    }
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (u8, u8, u8) {
+    let c = v * s;
+    let h_prime = h / 60.0;
+    let x = c * (1.0 - ((h_prime % 2.0) - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = if h_prime < 1.0 {
+        (c, x, 0.0)
+    } else if h_prime < 2.0 {
+        (x, c, 0.0)
+    } else if h_prime < 3.0 {
+        (0.0, c, x)
+    } else if h_prime < 4.0 {
+        (0.0, x, c)
+    } else if h_prime < 5.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+
+    (
+        ((r + m) * 255.0) as u8,
+        ((g + m) * 255.0) as u8,
+        ((b + m) * 255.0) as u8,
+    )
+}
+
+fn draw_circle(img: &mut RgbaImage, cx: f32, cy: f32, radius: f32, color: Rgba<u8>, fill: bool) {
+    let (inner, outer) = if fill { (0.0, radius) } else { (radius - 2.0, radius + 2.0) };
+    for_each_ring_pixel(img.width(), img.height(), cx, cy, inner, outer, |x, y| {
+        img.put_pixel(x, y, color);
+    });
+}
+
+fn draw_arc(
+    img: &mut RgbaImage,
+    cx: f32,
+    cy: f32,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    color: Rgba<u8>,
+) {
+    let thickness = 10.0;
+    let (width, height) = img.dimensions();
+
+    for_each_ring_pixel(width, height, cx, cy, radius - thickness / 2.0, radius + thickness / 2.0, |x, y| {
+        let dx = x as f32 - cx;
+        let dy = y as f32 - cy;
+        let angle = dy.atan2(dx) + std::f32::consts::PI / 2.0;
+        let angle = if angle < 0.0 {
+            angle + 2.0 * std::f32::consts::PI
+        } else {
+            angle
+        };
+
+        if angle >= start_angle && angle <= end_angle {
+            img.put_pixel(x, y, color);
+        }
+    });
+}
+
+/// Visit each pixel between `inner` and `outer` from (cx, cy), row by row.
+/// Only the spans the ring covers are visited, so a thin ring costs about its
+/// circumference rather than the whole image.
+fn for_each_ring_pixel(
+    width: u32,
+    height: u32,
+    cx: f32,
+    cy: f32,
+    inner: f32,
+    outer: f32,
+    mut plot: impl FnMut(u32, u32),
+) {
+    if outer <= 0.0 || width == 0 || height == 0 {
+        return;
+    }
+    let top = (cy - outer).ceil().max(0.0) as u32;
+    let bottom = ((cy + outer).floor().max(0.0) as u32).min(height - 1);
+    let mut span = |from: f32, to: f32, y: u32| {
+        let from = from.ceil().max(0.0) as u32;
+        let to = to.floor();
+        if to < 0.0 {
+            return;
+        }
+        for x in from..=(to as u32).min(width - 1) {
+            plot(x, y);
+        }
+    };
+
+    for y in top..=bottom {
+        let dy = y as f32 - cy;
+        let outer_reach = outer * outer - dy * dy;
+        if outer_reach < 0.0 {
+            continue;
+        }
+        let outer_reach = outer_reach.sqrt();
+        let inner_reach = inner.max(0.0).powi(2) - dy * dy;
+        if inner_reach > 0.0 {
+            // The row crosses the hole, leaving a span either side of it
+            let inner_reach = inner_reach.sqrt();
+            span(cx - outer_reach, cx - inner_reach, y);
+            span(cx + inner_reach, cx + outer_reach, y);
+        } else {
+            span(cx - outer_reach, cx + outer_reach, y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compare `image` with the PNG checked in as `golden/<name>.png`, allowing
+    /// for antialiasing that differs slightly between font rasterizer versions.
+    /// Set Q8_UPDATE_GOLDEN=1 to write the images afresh.
+    fn assert_matches_golden(name: &str, image: &DynamicImage) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/render/audio/golden").join(format!("{}.png", name));
+        if std::env::var_os("Q8_UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            image.save(&path).unwrap();
+            return;
+        }
+
+        let golden = image::open(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e)).to_rgba8();
+        let actual = image.to_rgba8();
+        assert_eq!(actual.dimensions(), golden.dimensions(), "{}", name);
+        let differing = actual.pixels().zip(golden.pixels())
+            .filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > 24))
+            .count();
+        if differing * 1000 > actual.len() / 4 {
+            let saved = std::env::temp_dir().join(format!("{}.actual.png", name));
+            actual.save(&saved).unwrap();
+            panic!("{}: {} pixels differ from {}; this render is in {}", name, differing, path.display(), saved.display());
+        }
+    }
+
+    fn two_tones(sample_rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let tone = |hz: f32| (2.0 * std::f32::consts::PI * hz * t).sin();
+                0.6 * tone(1000.0) + 0.3 * tone(5000.0) * (t * 3.0).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn labels_match_the_golden_images() {
+        let renderer = AudioRenderer::new();
+        let samples = two_tones(48000, 48000);
+        for (width, height) in [(640, 360), (1920, 1080)] {
+            let spectrum = renderer.render_spectrum_from_samples(&samples, 48000, width, height).unwrap();
+            assert_matches_golden(&format!("spectrum_{}x{}", width, height), &spectrum);
+            let waveform = renderer.render_waveform(&samples, 48000, width, height).unwrap();
+            assert_matches_golden(&format!("waveform_{}x{}", width, height), &waveform);
+        }
+    }
+
+    /// A sine gliding from `from` to `to` Hz, rising by the same ratio each second
+    fn exponential_sweep(from: f32, to: f32, seconds: f32, sample_rate: u32) -> Vec<f32> {
+        let rate = (to / from).ln() / seconds;
+        (0..(seconds * sample_rate as f32) as usize)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                (2.0 * std::f32::consts::PI * from * ((rate * t).exp() - 1.0) / rate).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_sweep_climbs_across_the_spectrogram() {
+        let (sample_rate, seconds, window) = (16000, 2.0, 1024);
+        let samples = exponential_sweep(200.0, 5000.0, seconds, sample_rate);
+        let (width, height) = (60, 128);
+        let plot = spectrogram_plot(&samples, sample_rate, width, height, window, 0.5, SpectrogramScale::default());
+
+        let nyquist = sample_rate as f32 / 2.0;
+        let lowest = log_floor(sample_rate, window / 2);
+        let frames = 1 + (samples.len() - window) / (window / 2);
+        let mut previous = 0.0;
+        for x in 0..width {
+            // The loudest row is the brightest; value sets the HSV brightness
+            let ridge = (0..height)
+                .max_by_key(|&y| plot.get_pixel(x, y).0[..3].iter().copied().max())
+                .unwrap();
+            let heard = lowest * (nyquist / lowest).powf(1.0 - (ridge as f32 + 0.5) / height as f32);
+
+            let frame = x as usize * frames / width as usize;
+            let middle = (frame * window / 2 + window / 2) as f32 / sample_rate as f32;
+            let played = 200.0 * (5000.0f32 / 200.0).powf(middle / seconds);
+            assert!((heard / played - 1.0).abs() < 0.2, "column {}: ridge at {} Hz, sweep at {} Hz", x, heard, played);
+            assert!(heard >= previous, "column {}: ridge fell from {} Hz to {} Hz", x, previous, heard);
+            previous = heard;
+        }
+    }
+
+    /// `draw_circle` and `draw_arc` as they were: every pixel of the image tested
+    fn full_scan_meter(img: &mut RgbaImage, cx: f32, cy: f32, radius: f32, angle: f32, color: Rgba<u8>) {
+        let (width, height) = img.dimensions();
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+                if ((dx * dx + dy * dy).sqrt() - radius).abs() < 2.0 {
+                    img.put_pixel(x, y, color);
+                }
+            }
+        }
+        let (arc_radius, thickness) = (radius - 5.0, 10.0);
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+                let dist = (dx * dx + dy * dy).sqrt();
+                let mut at = dy.atan2(dx) + std::f32::consts::PI / 2.0;
+                if at < 0.0 {
+                    at += 2.0 * std::f32::consts::PI;
+                }
+                if dist >= arc_radius - thickness / 2.0 && dist <= arc_radius + thickness / 2.0 && at <= angle {
+                    img.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Fastest of a few runs, to keep a busy machine from skewing the comparison
+    fn fastest(mut run: impl FnMut()) -> std::time::Duration {
+        (0..3)
+            .map(|_| {
+                let started = std::time::Instant::now();
+                run();
+                started.elapsed()
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn rings_draw_ten_times_faster_than_a_full_scan_at_4k() {
+        let (width, height) = (3840, 2160);
+        let (cx, cy, radius) = (width as f32 / 2.0, height as f32 / 2.0, height as f32 / 2.0 - 10.0);
+        let (angle, color) = (std::f32::consts::PI, Rgba([50, 255, 100, 255]));
+
+        let mut spans = background(width, height);
+        let span_time = fastest(|| {
+            draw_circle(&mut spans, cx, cy, radius, color, false);
+            draw_arc(&mut spans, cx, cy, radius - 5.0, 0.0, angle, color);
+        });
+        let mut scanned = background(width, height);
+        let scan_time = fastest(|| full_scan_meter(&mut scanned, cx, cy, radius, angle, color));
+
+        // The same pixels, give or take ones sitting exactly on the ring's edge
+        let differing = spans.pixels().zip(scanned.pixels()).filter(|(a, b)| a != b).count();
+        assert!(differing < 64, "{} pixels differ", differing);
+        assert!(span_time * 10 <= scan_time, "spans took {:?}, a full scan {:?}", span_time, scan_time);
+    }
+}
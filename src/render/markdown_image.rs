@@ -0,0 +1,634 @@
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use comrak::nodes::{AstNode, ListType, NodeValue, TableAlignment};
+use comrak::{parse_document, Arena, Options};
+use image::{DynamicImage, Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+
+use crate::{CasterError, Result};
+
+/// Sizes for a 1080-line page; other page heights scale from these
+const BODY_SIZE: f32 = 34.0;
+const MARGIN: f32 = 80.0;
+const LINE_HEIGHT: f32 = 1.45;
+/// Space around inline code and inside code blocks, as a fraction of the text size
+const CODE_PADDING: f32 = 0.25;
+
+/// egui's bundled fonts, so images look like the cast window and need nothing installed
+static FONTS: Lazy<std::result::Result<Fonts, String>> = Lazy::new(|| {
+    let definitions = egui::FontDefinitions::default();
+    let load = |name: &str| {
+        let data = definitions.font_data.get(name)
+            .ok_or_else(|| format!("Font {} is missing", name))?;
+        FontVec::try_from_vec(data.font.to_vec()).map_err(|e| format!("Font {} is invalid: {}", name, e))
+    };
+    Ok(Fonts {
+        body: load("Ubuntu-Light")?,
+        mono: load("Hack")?,
+    })
+});
+
+struct Fonts {
+    body: FontVec,
+    mono: FontVec,
+}
+
+/// Colours from the dark/light theme CSS; custom themes get the dark ones
+struct Palette {
+    background: Rgba<u8>,
+    text: Rgba<u8>,
+    heading: Rgba<u8>,
+    link: Rgba<u8>,
+    code_background: Rgba<u8>,
+    muted: Rgba<u8>,
+    border: Rgba<u8>,
+    stripe: Rgba<u8>,
+}
+
+impl Palette {
+    fn for_theme(theme: Option<&str>) -> Self {
+        match theme {
+            Some("light") => Self {
+                background: Rgba([0xff, 0xff, 0xff, 0xff]),
+                text: Rgba([0x24, 0x29, 0x2e, 0xff]),
+                heading: Rgba([0x24, 0x29, 0x2e, 0xff]),
+                link: Rgba([0x03, 0x66, 0xd6, 0xff]),
+                code_background: Rgba([0xf6, 0xf8, 0xfa, 0xff]),
+                muted: Rgba([0x6a, 0x73, 0x7d, 0xff]),
+                border: Rgba([0xdf, 0xe2, 0xe5, 0xff]),
+                stripe: Rgba([0xfa, 0xfb, 0xfc, 0xff]),
+            },
+            _ => Self {
+                background: Rgba([0x1e, 0x1e, 0x1e, 0xff]),
+                text: Rgba([0xd4, 0xd4, 0xd4, 0xff]),
+                heading: Rgba([0xe0, 0xe0, 0xe0, 0xff]),
+                link: Rgba([0x58, 0xa6, 0xff, 0xff]),
+                code_background: Rgba([0x2d, 0x2d, 0x2d, 0xff]),
+                muted: Rgba([0xa0, 0xa0, 0xa0, 0xff]),
+                border: Rgba([0x3e, 0x3e, 0x3e, 0xff]),
+                stripe: Rgba([0x25, 0x25, 0x25, 0xff]),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Face {
+    Body,
+    Mono,
+}
+
+#[derive(Debug, Clone)]
+struct Style {
+    face: Face,
+    size: f32,
+    color: Rgba<u8>,
+    // There's only a light weight, so bold is drawn twice, slightly offset
+    bold: bool,
+    strikethrough: bool,
+    /// Boxed like inline code
+    background: Option<Rgba<u8>>,
+}
+
+enum Op {
+    Text { x: f32, baseline: f32, text: String, style: Style },
+    Rect { x: f32, y: f32, width: f32, height: f32, color: Rgba<u8> },
+}
+
+/// A strip of the document that is never split across pages; ops are relative to its top
+struct Row {
+    height: f32,
+    ops: Vec<Op>,
+    /// Spacing, dropped at the top of a page
+    gap: bool,
+}
+
+/// A wrapped line of inline text
+struct Line {
+    items: Vec<(f32, String, Style)>,
+    width: f32,
+}
+
+/// Where blocks go across the page, and what quotes they sit in
+#[derive(Clone)]
+struct Indent {
+    left: f32,
+    right: f32,
+    /// x of each enclosing quote's bar
+    bars: Vec<f32>,
+    muted: bool,
+}
+
+/// Lay `markdown` out as `width` x `height` pages, for devices that can only show
+/// images. Long documents run over as many pages as they need; a block is only
+/// split between lines.
+pub fn render_markdown_pages(markdown: &str, theme: Option<&str>, width: u32, height: u32) -> Result<Vec<DynamicImage>> {
+    if width < 64 || height < 64 {
+        return Err(CasterError::Render(format!("Page size {}x{} is too small", width, height)));
+    }
+    let fonts = FONTS.as_ref().map_err(|e| CasterError::Render(e.clone()))?;
+
+    // Same extensions as render_markdown
+    let mut options = Options::default();
+    options.extension.strikethrough = true;
+    options.extension.table = true;
+    options.extension.autolink = true;
+    options.extension.tasklist = true;
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, markdown, &options);
+
+    let palette = Palette::for_theme(theme);
+    let scale = height as f32 / 1080.0;
+    let mut layout = Layout {
+        fonts,
+        palette: &palette,
+        scale,
+        rows: Vec::new(),
+    };
+    let margin = MARGIN * scale;
+    let indent = Indent {
+        left: margin,
+        right: width as f32 - margin,
+        bars: Vec::new(),
+        muted: false,
+    };
+    layout.blocks(root, &indent);
+
+    Ok(paginate(&layout.rows, height as f32, margin)
+        .into_iter()
+        .map(|rows| DynamicImage::ImageRgba8(paint(fonts, &palette, &rows, width, height)))
+        .collect())
+}
+
+/// Where `write_markdown_pages` saves pages. The media route serves them
+/// alongside the media directories.
+pub fn markdown_pages_dir() -> PathBuf {
+    std::env::temp_dir().join("q8-caster-slides")
+}
+
+/// Render the pages and save them as PNGs in the temp dir, for casting from the
+/// media route
+pub fn write_markdown_pages(markdown: &str, theme: Option<&str>, width: u32, height: u32) -> Result<Vec<PathBuf>> {
+    let pages = render_markdown_pages(markdown, theme, width, height)?;
+    let dir = markdown_pages_dir();
+    std::fs::create_dir_all(&dir)?;
+
+    let id = uuid::Uuid::new_v4();
+    pages.iter().enumerate().map(|(index, page)| {
+        let path = dir.join(format!("{}-{}.png", id, index + 1));
+        std::fs::write(&path, super::encode_png(page)?)?;
+        Ok(path)
+    }).collect()
+}
+
+/// Split rows into pages, each row at its y on the page
+fn paginate(rows: &[Row], height: f32, margin: f32) -> Vec<Vec<(f32, &Row)>> {
+    let top = margin;
+    let bottom = height - margin;
+    let mut pages = Vec::new();
+    let mut page = Vec::new();
+    let mut y = top;
+
+    for row in rows {
+        if row.gap && page.is_empty() {
+            continue;
+        }
+        if y + row.height > bottom && !page.is_empty() {
+            pages.push(std::mem::take(&mut page));
+            y = top;
+            if row.gap {
+                continue;
+            }
+        }
+        page.push((y, row));
+        y += row.height;
+    }
+
+    // Trailing spacing alone doesn't make a page, but an empty document still gets one
+    if page.iter().any(|(_, row)| !row.gap) || pages.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+fn paint(fonts: &Fonts, palette: &Palette, rows: &[(f32, &Row)], width: u32, height: u32) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(width, height, palette.background);
+    for (top, row) in rows {
+        for op in &row.ops {
+            match op {
+                Op::Rect { x, y, width, height, color } => fill_rect(&mut image, *x, top + y, *width, *height, *color),
+                Op::Text { x, baseline, text, style } => draw_text(&mut image, fonts, *x, top + baseline, text, style),
+            }
+        }
+    }
+    image
+}
+
+struct Layout<'a> {
+    fonts: &'static Fonts,
+    palette: &'a Palette,
+    scale: f32,
+    rows: Vec<Row>,
+}
+
+impl Layout<'_> {
+    fn body_size(&self) -> f32 {
+        BODY_SIZE * self.scale
+    }
+
+    fn blocks<'a>(&mut self, parent: &'a AstNode<'a>, indent: &Indent) {
+        for node in parent.children() {
+            self.block(node, indent);
+        }
+    }
+
+    fn block<'a>(&mut self, node: &'a AstNode<'a>, indent: &Indent) {
+        let body = self.body_size();
+        let value = node.data.borrow().value.clone();
+        match value {
+            NodeValue::Heading(heading) => {
+                let size = match heading.level {
+                    1 => body * 2.0,
+                    2 => body * 1.5,
+                    3 => body * 1.25,
+                    _ => body,
+                };
+                let style = Style {
+                    bold: true,
+                    color: self.palette.heading,
+                    ..self.text_style(indent, size)
+                };
+                self.gap(indent, body * 0.4);
+                self.paragraph(node, indent, &style);
+                if heading.level <= 2 {
+                    self.rule(indent, body * 0.5);
+                }
+                self.gap(indent, body * 0.4);
+            }
+            NodeValue::Paragraph => {
+                let style = self.text_style(indent, body);
+                self.paragraph(node, indent, &style);
+                self.gap(indent, body * 0.6);
+            }
+            NodeValue::List(list) => {
+                let ordered = list.list_type == ListType::Ordered;
+                let inner = Indent {
+                    left: indent.left + body * 1.6,
+                    ..indent.clone()
+                };
+                for (index, item) in node.children().enumerate() {
+                    let marker = match item.data.borrow().value {
+                        NodeValue::TaskItem(Some(_)) => "[x]".to_string(),
+                        NodeValue::TaskItem(None) => "[ ]".to_string(),
+                        _ if ordered => format!("{}.", list.start + index),
+                        _ => "•".to_string(),
+                    };
+
+                    let first = self.rows.len();
+                    self.blocks(item, &inner);
+                    // The marker sits on the item's first line
+                    let style = self.text_style(indent, body);
+                    let baseline = baseline(self.fonts, &style, body * LINE_HEIGHT);
+                    if let Some(row) = self.rows[first..].iter_mut().find(|row| !row.gap) {
+                        row.ops.push(Op::Text { x: indent.left, baseline, text: marker, style });
+                    }
+                }
+                self.gap(indent, body * 0.3);
+            }
+            NodeValue::BlockQuote => {
+                let mut inner = Indent {
+                    left: indent.left + body,
+                    muted: true,
+                    ..indent.clone()
+                };
+                inner.bars.push(indent.left);
+                self.blocks(node, &inner);
+            }
+            NodeValue::CodeBlock(code) => self.code_block(&code.literal, indent),
+            // Raw HTML can't be laid out here; keep its text rather than drop it
+            NodeValue::HtmlBlock(html) => self.code_block(&html.literal, indent),
+            NodeValue::Table(table) => self.table(node, &table.alignments, indent),
+            NodeValue::ThematicBreak => {
+                self.rule(indent, body);
+            }
+            _ => {}
+        }
+    }
+
+    fn text_style(&self, indent: &Indent, size: f32) -> Style {
+        Style {
+            face: Face::Body,
+            size,
+            color: if indent.muted { self.palette.muted } else { self.palette.text },
+            bold: false,
+            strikethrough: false,
+            background: None,
+        }
+    }
+
+    /// Add a row, drawing the bars of the quotes it sits in down its whole height
+    fn push_row(&mut self, indent: &Indent, height: f32, mut ops: Vec<Op>, gap: bool) {
+        let bar_width = (4.0 * self.scale).max(1.0);
+        for &x in &indent.bars {
+            ops.insert(0, Op::Rect { x, y: 0.0, width: bar_width, height, color: self.palette.border });
+        }
+        self.rows.push(Row { height, ops, gap });
+    }
+
+    fn gap(&mut self, indent: &Indent, height: f32) {
+        self.push_row(indent, height, Vec::new(), true);
+    }
+
+    fn rule(&mut self, indent: &Indent, height: f32) {
+        let thickness = (2.0 * self.scale).max(1.0);
+        let ops = vec![Op::Rect {
+            x: indent.left,
+            y: (height - thickness) / 2.0,
+            width: indent.right - indent.left,
+            height: thickness,
+            color: self.palette.border,
+        }];
+        self.push_row(indent, height, ops, false);
+    }
+
+    /// A block's inline content, wrapped to the indent, one row per line
+    fn paragraph<'a>(&mut self, node: &'a AstNode<'a>, indent: &Indent, style: &Style) {
+        let mut fragments = Vec::new();
+        self.inlines(node, style, &mut fragments);
+
+        let line_height = style.size * LINE_HEIGHT;
+        let baseline = baseline(self.fonts, style, line_height);
+        for line in self.wrap(&fragments, indent.right - indent.left) {
+            let ops = self.line_ops(line.items, indent.left, baseline, line_height);
+            self.push_row(indent, line_height, ops, false);
+        }
+    }
+
+    fn line_ops(&self, items: Vec<(f32, String, Style)>, left: f32, baseline: f32, line_height: f32) -> Vec<Op> {
+        let mut ops = Vec::new();
+        for (x, text, style) in items {
+            let x = left + x;
+            let width = text_width(self.fonts, &text, &style);
+            let padding = style.background.map_or(0.0, |_| style.size * CODE_PADDING);
+            if let Some(color) = style.background {
+                let height = style.size * 1.3;
+                ops.push(Op::Rect { x, y: (line_height - height) / 2.0, width: width + padding * 2.0, height, color });
+            }
+            if style.strikethrough {
+                let thickness = (style.size / 16.0).max(1.0);
+                ops.push(Op::Rect { x, y: baseline - style.size * 0.3, width, height: thickness, color: style.color });
+            }
+            ops.push(Op::Text { x: x + padding, baseline, text, style });
+        }
+        ops
+    }
+
+    /// Flatten inline children into styled runs; "\n" is a hard line break
+    fn inlines<'a>(&self, node: &'a AstNode<'a>, style: &Style, out: &mut Vec<(String, Style)>) {
+        for child in node.children() {
+            let value = child.data.borrow().value.clone();
+            match value {
+                NodeValue::Text(text) => out.push((text, style.clone())),
+                NodeValue::SoftBreak => out.push((" ".into(), style.clone())),
+                NodeValue::LineBreak => out.push(("\n".into(), style.clone())),
+                NodeValue::Code(code) => out.push((code.literal, Style {
+                    face: Face::Mono,
+                    size: style.size * 0.85,
+                    background: Some(self.palette.code_background),
+                    ..style.clone()
+                })),
+                NodeValue::HtmlInline(html) => out.push((html, style.clone())),
+                NodeValue::Strong => self.inlines(child, &Style { bold: true, ..style.clone() }, out),
+                NodeValue::Strikethrough => self.inlines(child, &Style { strikethrough: true, ..style.clone() }, out),
+                NodeValue::Link(_) => self.inlines(child, &Style { color: self.palette.link, ..style.clone() }, out),
+                // Images aren't fetched; show the alt text where they would be
+                NodeValue::Image(_) => self.inlines(child, &Style { color: self.palette.muted, ..style.clone() }, out),
+                _ => self.inlines(child, style, out),
+            }
+        }
+    }
+
+    /// Fill lines greedily, breaking at spaces. Inline code is never broken, and a
+    /// word wider than the line overflows it rather than being cut.
+    fn wrap(&self, fragments: &[(String, Style)], max_width: f32) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let mut line = Line { items: Vec::new(), width: 0.0 };
+        // A space came before the next word, possibly at the end of the previous run
+        let mut spaced = false;
+
+        for (text, style) in fragments {
+            if text == "\n" {
+                lines.push(std::mem::replace(&mut line, Line { items: Vec::new(), width: 0.0 }));
+                spaced = false;
+                continue;
+            }
+
+            let words: Vec<&str> = match style.background {
+                Some(_) => vec![text.as_str()],
+                None => text.split(' ').collect(),
+            };
+            for (index, word) in words.into_iter().enumerate() {
+                spaced |= index > 0;
+                if word.is_empty() {
+                    continue;
+                }
+
+                let padding = style.background.map_or(0.0, |_| style.size * CODE_PADDING * 2.0);
+                let width = text_width(self.fonts, word, style) + padding;
+                let space = if spaced && !line.items.is_empty() {
+                    text_width(self.fonts, " ", style)
+                } else {
+                    0.0
+                };
+                spaced = false;
+
+                if line.width + space + width > max_width && !line.items.is_empty() {
+                    lines.push(std::mem::replace(&mut line, Line { items: Vec::new(), width: 0.0 }));
+                    line.items.push((0.0, word.to_string(), style.clone()));
+                    line.width = width;
+                } else {
+                    line.items.push((line.width + space, word.to_string(), style.clone()));
+                    line.width += space + width;
+                }
+            }
+        }
+
+        if !line.items.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Monospace lines on a shaded box. Lines aren't wrapped, like <pre>; what runs
+    /// past the right edge is cut off.
+    fn code_block(&mut self, text: &str, indent: &Indent) {
+        let body = self.body_size();
+        let style = Style {
+            face: Face::Mono,
+            size: body * 0.85,
+            ..self.text_style(indent, body)
+        };
+        let padding = body * CODE_PADDING * 2.0;
+        let line_height = style.size * LINE_HEIGHT;
+        let baseline = baseline(self.fonts, &style, line_height);
+        let width = indent.right - indent.left;
+        let shade = |height: f32| Op::Rect { x: indent.left, y: 0.0, width, height, color: self.palette.code_background };
+
+        let mut rows = vec![(padding, vec![shade(padding)])];
+        for line in text.trim_end_matches('\n').lines() {
+            let visible = clip_to_width(self.fonts, line, &style, width - padding * 2.0);
+            rows.push((line_height, vec![
+                shade(line_height),
+                Op::Text { x: indent.left + padding, baseline, text: visible, style: style.clone() },
+            ]));
+        }
+        rows.push((padding, vec![shade(padding)]));
+
+        for (height, ops) in rows {
+            self.push_row(indent, height, ops, false);
+        }
+        self.gap(indent, body * 0.6);
+    }
+
+    /// Columns share the width evenly; each table row becomes one page row
+    fn table<'a>(&mut self, node: &'a AstNode<'a>, alignments: &[TableAlignment], indent: &Indent) {
+        let body = self.body_size();
+        let columns = alignments.len().max(1);
+        let spacing = body * 0.8;
+        let padding = body * 0.3;
+        let column_width = ((indent.right - indent.left) - spacing * (columns - 1) as f32) / columns as f32;
+        let thickness = (self.scale).max(1.0);
+
+        for (row_index, table_row) in node.children().enumerate() {
+            let header = row_index == 0;
+            let style = Style { bold: header, ..self.text_style(indent, body) };
+            let line_height = style.size * LINE_HEIGHT;
+            let baseline = baseline(self.fonts, &style, line_height);
+
+            let cells: Vec<Vec<Line>> = table_row.children().map(|cell| {
+                let mut fragments = Vec::new();
+                self.inlines(cell, &style, &mut fragments);
+                self.wrap(&fragments, column_width)
+            }).collect();
+            let lines = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+            let height = lines as f32 * line_height + padding * 2.0;
+
+            let mut ops = Vec::new();
+            if !header && row_index % 2 == 0 {
+                ops.push(Op::Rect { x: indent.left, y: 0.0, width: indent.right - indent.left, height, color: self.palette.stripe });
+            }
+            for (column, cell) in cells.into_iter().enumerate().take(columns) {
+                let left = indent.left + column as f32 * (column_width + spacing);
+                for (line_index, line) in cell.into_iter().enumerate() {
+                    let offset = match alignments.get(column) {
+                        Some(TableAlignment::Center) => (column_width - line.width) / 2.0,
+                        Some(TableAlignment::Right) => column_width - line.width,
+                        _ => 0.0,
+                    };
+                    let top = padding + line_index as f32 * line_height;
+                    for op in self.line_ops(line.items, left + offset.max(0.0), baseline, line_height) {
+                        ops.push(match op {
+                            Op::Text { x, baseline, text, style } => Op::Text { x, baseline: baseline + top, text, style },
+                            Op::Rect { x, y, width, height, color } => Op::Rect { x, y: y + top, width, height, color },
+                        });
+                    }
+                }
+            }
+            ops.push(Op::Rect { x: indent.left, y: height - thickness, width: indent.right - indent.left, height: thickness, color: self.palette.border });
+            self.push_row(indent, height, ops, false);
+        }
+        self.gap(indent, body * 0.6);
+    }
+}
+
+fn font(fonts: &Fonts, face: Face) -> &FontVec {
+    match face {
+        Face::Body => &fonts.body,
+        Face::Mono => &fonts.mono,
+    }
+}
+
+/// Where text sits in a line of `line_height`, centred on its ascent and descent
+fn baseline(fonts: &Fonts, style: &Style, line_height: f32) -> f32 {
+    let scaled = font(fonts, style.face).as_scaled(PxScale::from(style.size));
+    (line_height - (scaled.ascent() - scaled.descent())) / 2.0 + scaled.ascent()
+}
+
+fn text_width(fonts: &Fonts, text: &str, style: &Style) -> f32 {
+    let scaled = font(fonts, style.face).as_scaled(PxScale::from(style.size));
+    let mut width = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// As much of `text` as fits in `max_width`
+fn clip_to_width(fonts: &Fonts, text: &str, style: &Style, max_width: f32) -> String {
+    // Tabs have no glyph; code blocks indent them like an editor would
+    let text = text.replace('\t', "    ");
+    let mut end = text.len();
+    while end > 0 && text_width(fonts, &text[..end], style) > max_width {
+        end = text[..end].char_indices().last().map_or(0, |(index, _)| index);
+    }
+    text[..end].to_string()
+}
+
+fn draw_text(image: &mut RgbaImage, fonts: &Fonts, x: f32, baseline: f32, text: &str, style: &Style) {
+    let font = font(fonts, style.face);
+    let scale = PxScale::from(style.size);
+    let scaled = font.as_scaled(scale);
+    let bold_offset = (style.size / 30.0).max(1.0);
+
+    let mut caret = x;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        let offsets: &[f32] = if style.bold { &[0.0, bold_offset] } else { &[0.0] };
+        for &offset in offsets {
+            let glyph = id.with_scale_and_position(scale, point(caret + offset, baseline));
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    blend(image, bounds.min.x as i64 + gx as i64, bounds.min.y as i64 + gy as i64, style.color, coverage);
+                });
+            }
+        }
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+    }
+}
+
+fn fill_rect(image: &mut RgbaImage, x: f32, y: f32, width: f32, height: f32, color: Rgba<u8>) {
+    let (image_width, image_height) = (image.width() as f32, image.height() as f32);
+    let (left, top) = (x.max(0.0) as u32, y.max(0.0) as u32);
+    let right = (x + width).min(image_width).max(0.0) as u32;
+    let bottom = (y + height).min(image_height).max(0.0) as u32;
+    for py in top..bottom {
+        for px in left..right {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn blend(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>, coverage: f32) {
+    if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+        return;
+    }
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    let alpha = coverage.clamp(0.0, 1.0);
+    for channel in 0..3 {
+        let under = pixel.0[channel] as f32;
+        pixel.0[channel] = (under + (color.0[channel] as f32 - under) * alpha).round() as u8;
+    }
+}
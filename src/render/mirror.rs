@@ -5,6 +5,8 @@ use crate::{CasterError, Result};
 
 pub struct ScreenMirror {
     monitor: Monitor,
+    // Part of the monitor to capture; the whole monitor when None
+    region: Option<CaptureRegion>,
 }
 
 impl ScreenMirror {
@@ -17,7 +19,7 @@ impl ScreenMirror {
             monitors
                 .into_iter()
                 .find(|m| m.id().to_string() == id || m.name() == id)
-                .ok_or_else(|| CasterError::Display(format!("Monitor '{}' not found", id)))?
+                .ok_or_else(|| CasterError::NotFound(format!("Monitor '{}' not found", id)))?
         } else {
             // Use primary monitor or first available
             let primary = monitors.iter().position(|m| m.is_primary());
@@ -29,7 +31,7 @@ impl ScreenMirror {
             }
         };
 
-        Ok(Self { monitor })
+        Ok(Self { monitor, region: None })
     }
 
     pub fn capture_frame(&mut self) -> Result<Option<DynamicImage>> {
@@ -38,15 +40,49 @@ impl ScreenMirror {
             .capture_image()
             .map_err(|e| CasterError::Display(format!("Failed to capture screen: {}", e)))?;
 
-        // Convert xcap::Image to image::DynamicImage
+        // xcap captures straight into an image::RgbaImage
         let width = image.width();
         let height = image.height();
-        let rgba = image.as_rgba();
+        let image = DynamicImage::ImageRgba8(image);
+        match self.region {
+            // The capture can come back smaller than the monitor reports, e.g. under
+            // fractional scaling, so the crop is clamped again to what was captured
+            Some(ref region) if region.x < width && region.y < height => Ok(Some(image.crop_imm(
+                region.x,
+                region.y,
+                region.width.min(width - region.x),
+                region.height.min(height - region.y),
+            ))),
+            _ => Ok(Some(image)),
+        }
+    }
+
+    /// Capture only a rectangle of the monitor, in monitor pixels from its top left.
+    /// A rectangle running off the right or bottom edge is cut off there.
+    pub fn set_region(&mut self, x: u32, y: u32, width: u32, height: u32) -> Result<()> {
+        if width == 0 || height == 0 {
+            return Err(CasterError::Display("Capture region must not be empty".into()));
+        }
+        let (monitor_width, monitor_height) = (self.monitor.width(), self.monitor.height());
+        if x >= monitor_width || y >= monitor_height {
+            return Err(CasterError::Display(format!(
+                "Capture region origin {},{} is outside the {}x{} monitor",
+                x, y, monitor_width, monitor_height
+            )));
+        }
 
-        let rgba_image = image::RgbaImage::from_raw(width, height, rgba.to_vec())
-            .ok_or_else(|| CasterError::Display("Failed to create image from capture".into()))?;
+        self.region = Some(CaptureRegion {
+            x,
+            y,
+            width: width.min(monitor_width - x),
+            height: height.min(monitor_height - y),
+        });
+        Ok(())
+    }
 
-        Ok(Some(DynamicImage::ImageRgba8(rgba_image)))
+    /// Go back to capturing the whole monitor
+    pub fn clear_region(&mut self) {
+        self.region = None;
     }
 
     pub fn get_monitor_info(&self) -> MonitorInfo {
@@ -58,6 +94,7 @@ impl ScreenMirror {
             x: self.monitor.x(),
             y: self.monitor.y(),
             is_primary: self.monitor.is_primary(),
+            region: self.region.clone(),
         }
     }
 
@@ -75,6 +112,7 @@ impl ScreenMirror {
                 x: m.x(),
                 y: m.y(),
                 is_primary: m.is_primary(),
+                region: None,
             })
             .collect())
     }
@@ -89,4 +127,16 @@ pub struct MonitorInfo {
     pub x: i32,
     pub y: i32,
     pub is_primary: bool,
+    /// The part being captured, if not the whole monitor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<CaptureRegion>,
+}
+
+/// A rectangle of a monitor in its own pixels, from its top left corner
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CaptureRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
 }
@@ -0,0 +1,142 @@
+use bytes::Bytes;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use super::mirror::{MonitorInfo, ScreenMirror};
+use crate::{Result, CasterError, MirrorQuality};
+
+/// JPEG quality for streamed frames; screen text stays legible without huge frames
+const STREAM_JPEG_QUALITY: u8 = 80;
+
+/// How long to wait before capturing again after a failed capture
+const CAPTURE_RETRY: Duration = Duration::from_secs(1);
+
+/// A screen captured continuously on a thread of its own, each changed frame
+/// encoded as JPEG for any number of MJPEG clients
+pub struct MirrorStream {
+    frames: watch::Receiver<Option<Bytes>>,
+    // Ends the capture loop, whether it's stopped here or a session is cancelled
+    cancel: CancellationToken,
+    monitor: MonitorInfo,
+    quality: MirrorQuality,
+    fps: u32,
+}
+
+impl MirrorStream {
+    /// Start capturing `display_id` (a monitor id or name, the primary if None) at
+    /// `quality`'s size and frame rate; `fps` caps the rate further
+    pub fn start(display_id: Option<String>, quality: MirrorQuality, fps: Option<u32>) -> Result<Self> {
+        let (max_width, max_height, max_fps) = quality.limits();
+        let fps = fps.unwrap_or(max_fps).clamp(1, max_fps);
+        let frame_interval = Duration::from_secs(1) / fps;
+
+        let (frame_tx, frames) = watch::channel(None);
+        let cancel = CancellationToken::new();
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+
+        let thread_cancel = cancel.clone();
+        std::thread::Builder::new()
+            .name("mirror-stream".into())
+            .spawn(move || {
+                let mut mirror = match ScreenMirror::new(display_id) {
+                    Ok(mirror) => {
+                        let _ = started_tx.send(Ok(mirror.get_monitor_info()));
+                        mirror
+                    }
+                    Err(e) => {
+                        let _ = started_tx.send(Err(e));
+                        return;
+                    }
+                };
+
+                // The last frame sent, to skip encoding while the screen is static
+                let mut previous: Option<Vec<u8>> = None;
+                while !thread_cancel.is_cancelled() {
+                    let started = Instant::now();
+                    match mirror.capture_frame() {
+                        Ok(Some(image)) => {
+                            let image = if image.width() > max_width || image.height() > max_height {
+                                image.resize(max_width, max_height, image::imageops::FilterType::Triangle)
+                            } else {
+                                image
+                            };
+                            let rgba = image.to_rgba8().into_raw();
+
+                            if previous.as_ref() != Some(&rgba) {
+                                match super::encode_image(image, "jpeg", 1.0, STREAM_JPEG_QUALITY) {
+                                    Ok(encoded) => {
+                                        frame_tx.send_replace(Some(Bytes::from(encoded.data)));
+                                    }
+                                    Err(e) => warn!("Mirror stream: {}", e),
+                                }
+                                previous = Some(rgba);
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            warn!("Mirror stream: {}", e);
+                            std::thread::sleep(CAPTURE_RETRY);
+                            continue;
+                        }
+                    }
+
+                    // Capture and encoding count against the frame budget
+                    if let Some(rest) = frame_interval.checked_sub(started.elapsed()) {
+                        std::thread::sleep(rest);
+                    }
+                }
+                // Dropping the sender ends every client's stream
+                debug!("Mirror stream stopped");
+            })
+            .map_err(|e| CasterError::Render(format!("Failed to start mirror stream: {}", e)))?;
+
+        let monitor = started_rx.recv()
+            .map_err(|_| CasterError::Render("Mirror stream exited during startup".into()))??;
+        debug!("Streaming {} at up to {}x{} @ {}fps", monitor.name, max_width, max_height, fps);
+
+        Ok(Self {
+            frames,
+            cancel,
+            monitor,
+            quality,
+            fps,
+        })
+    }
+
+    /// Latest JPEG frame and every change after it; None until the first capture
+    pub fn subscribe(&self) -> watch::Receiver<Option<Bytes>> {
+        self.frames.clone()
+    }
+
+    /// Whether this stream already captures what a new request asks for
+    pub fn matches(&self, display_id: Option<&str>, quality: &MirrorQuality, fps: Option<u32>) -> bool {
+        let monitor = match display_id {
+            Some(id) => self.monitor.id == id || self.monitor.name == id,
+            None => self.monitor.is_primary,
+        };
+        let (_, _, max_fps) = quality.limits();
+        monitor && &self.quality == quality && self.fps == fps.unwrap_or(max_fps).clamp(1, max_fps)
+    }
+
+    pub fn monitor(&self) -> &MonitorInfo {
+        &self.monitor
+    }
+
+    /// Cancelling this ends the capture loop before its next frame
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// End the capture loop; it exits before its next frame
+    pub fn stop(&self) {
+        self.cancel.cancel();
+    }
+}
+
+impl Drop for MirrorStream {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -1,105 +1,363 @@
-use comrak::{markdown_to_html, Options};
+use comrak::nodes::{AstNode, NodeHtmlBlock, NodeValue};
+use comrak::plugins::syntect::{SyntectAdapter, SyntectAdapterBuilder};
+use comrak::{format_html_with_plugins, parse_document, Arena, Options, Plugins};
 use image::DynamicImage;
+use once_cell::sync::Lazy;
+use std::borrow::Cow;
+use tokio_util::sync::CancellationToken;
 
-use crate::{Result, CasterError};
+use crate::{Result, CasterError, MirrorQuality};
 
 pub mod pdf;
+pub mod pdf_pool;
 pub mod audio;
 pub mod wasm;
 pub mod mirror;
+pub mod mirror_stream;
+pub mod model;
+pub mod markdown_image;
+pub mod presentation;
 
 pub use pdf::PdfRenderer;
-pub use audio::AudioRenderer;
-pub use wasm::WasmRunner;
-pub use mirror::ScreenMirror;
+pub use pdf_pool::PdfPool;
+pub use audio::{AudioLevels, AudioRenderer, SpectrogramScale};
+pub use wasm::{WasmFrames, WasmLimitsConfig, WasmRunner, WasmSandboxConfig};
+pub use mirror::{CaptureRegion, MonitorInfo, ScreenMirror};
+pub use mirror_stream::MirrorStream;
+pub use model::{ModelRenderer, OrbitCamera};
+pub use markdown_image::{markdown_pages_dir, render_markdown_pages, write_markdown_pages};
+pub use presentation::{render_presentation, split_slides, SlideFormat};
+
+// Syntect loads its syntax and theme sets on construction, so build each adapter once
+static DARK_HIGHLIGHTER: Lazy<SyntectAdapter> =
+    Lazy::new(|| SyntectAdapterBuilder::new().theme("base16-ocean.dark").build());
+static LIGHT_HIGHLIGHTER: Lazy<SyntectAdapter> =
+    Lazy::new(|| SyntectAdapterBuilder::new().theme("InspiredGitHub").build());
+
+/// KaTeX for pages with math; the browser showing the page fetches it
+const KATEX_HEAD: &str = r#"
+    <link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/katex@0.16.11/dist/katex.min.css">
+    <script src="https://cdn.jsdelivr.net/npm/katex@0.16.11/dist/katex.min.js"></script>"#;
+
+/// Typesets the spans comrak marks as math, leaving their source showing if KaTeX
+/// didn't load or a formula doesn't parse
+const KATEX_RENDER: &str = r#"
+    <script>
+        if (window.katex) {
+            document.querySelectorAll("[data-math-style]").forEach(function (el) {
+                katex.render(el.textContent, el, {
+                    displayMode: el.dataset.mathStyle === "display",
+                    throwOnError: false
+                });
+            });
+        }
+    </script>"#;
+
+/// mermaid.js for pages with ```mermaid blocks, and the banner shown in place of
+/// a diagram it can't draw
+const MERMAID_HEAD: &str = r#"
+    <style>
+        pre.mermaid { background: none; text-align: center; }
+        .diagram-warning { padding: 8px 12px; border-left: 4px solid #e3b341; background: rgba(227, 179, 65, 0.15); }
+    </style>
+    <script src="https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.min.js"></script>"#;
+
+/// Draws each diagram on its own, so one that fails to parse falls back to its
+/// source under a warning while the rest of the page renders
+const MERMAID_RENDER: &str = r#"
+    <script>
+        (function () {
+            function fallBack(block, reason) {
+                var warning = document.createElement("div");
+                warning.className = "diagram-warning";
+                warning.textContent = "Couldn't draw this diagram: " + reason;
+                var code = document.createElement("pre");
+                code.appendChild(document.createElement("code")).textContent = block.textContent;
+                block.replaceWith(warning, code);
+            }
+
+            var blocks = document.querySelectorAll("pre.mermaid");
+            if (!window.mermaid) {
+                blocks.forEach(function (block) { fallBack(block, "mermaid.js didn't load"); });
+                return;
+            }
+            mermaid.initialize({ startOnLoad: false, theme: "MERMAID_THEME" });
+            blocks.forEach(function (block, i) {
+                mermaid.render("mermaid-" + i, block.textContent).then(function (result) {
+                    block.innerHTML = result.svg;
+                }, function (e) {
+                    // Mermaid leaves its error drawing at the end of the body
+                    var leftover = document.getElementById("dmermaid-" + i);
+                    if (leftover) leftover.remove();
+                    fallBack(block, e.message || e);
+                });
+            });
+        })();
+    </script>"#;
+
+/// Markdown as a standalone HTML page styled with `theme`, with `$...$` and
+/// `$$...$$` typeset by KaTeX when `math` is set and ```mermaid blocks drawn
+/// by mermaid.js. Needs no engine state,
+/// so spawned tasks can use it without holding the engine, which isn't `Send`.
+pub fn render_markdown(markdown: &str, theme: Option<&str>, math: bool) -> Result<String> {
+    let (html, diagrams) = markdown_fragment(markdown, theme, math)?;
+    let body = format!(
+        r#"
+    <div class="markdown-body">
+        {}
+    </div>"#,
+        html
+    );
+    markdown_page(&body, theme, math, diagrams > 0, "", "")
+}
+
+/// The HTML for `markdown` without a page around it, and how many diagrams it has
+pub(crate) fn markdown_fragment(markdown: &str, theme: Option<&str>, math: bool) -> Result<(String, usize)> {
+    let mut options = Options::default();
+    options.extension.strikethrough = true;
+    options.extension.table = true;
+    options.extension.autolink = true;
+    options.extension.tasklist = true;
+    // Math becomes <span data-math-style="inline|display">; dollars inside code stay code
+    options.extension.math_dollars = math;
+    options.render.unsafe_ = true;
+
+    // Fenced code blocks are highlighted with a palette matching the page theme
+    // (custom themes get the dark palette).
+    // Unknown languages fall back to plain text inside the same <pre>.
+    let highlighter: &SyntectAdapter = match theme {
+        Some("light") => &LIGHT_HIGHLIGHTER,
+        _ => &DARK_HIGHLIGHTER,
+    };
+    let mut plugins = Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(highlighter);
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, markdown, &options);
+    let diagrams = mark_mermaid_blocks(root);
+
+    let mut html = Vec::new();
+    format_html_with_plugins(root, &options, &mut html, &plugins)
+        .map_err(|e| CasterError::Render(format!("Failed to render markdown: {}", e)))?;
+    Ok((String::from_utf8_lossy(&html).into_owned(), diagrams))
+}
+
+/// Wrap rendered markdown in a page with the theme's CSS, plus KaTeX and
+/// mermaid.js when they're needed. `head` and `script` go in after them.
+pub(crate) fn markdown_page(body: &str, theme: Option<&str>, math: bool, diagrams: bool, head: &str, script: &str) -> Result<String> {
+    let theme_css = load_theme_css(theme)?;
+    let (mermaid_head, mermaid_render) = if diagrams {
+        let mermaid_theme = if theme == Some("light") { "default" } else { "dark" };
+        (MERMAID_HEAD, MERMAID_RENDER.replace("MERMAID_THEME", mermaid_theme))
+    } else {
+        ("", String::new())
+    };
+
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <style>{}</style>{}{}{}
+</head>
+<body>{}{}{}{}
+</body>
+</html>"#,
+        theme_css,
+        if math { KATEX_HEAD } else { "" },
+        mermaid_head,
+        head,
+        body,
+        if math { KATEX_RENDER } else { "" },
+        mermaid_render,
+        script,
+    ))
+}
+
+/// Turn ```mermaid code blocks into `<pre class="mermaid">` for mermaid.js to find,
+/// skipping the highlighter. Returns how many there were.
+fn mark_mermaid_blocks<'a>(root: &'a AstNode<'a>) -> usize {
+    let mut count = 0;
+    for node in root.descendants() {
+        let mut data = node.data.borrow_mut();
+        let NodeValue::CodeBlock(ref code) = data.value else {
+            continue;
+        };
+        if code.info.split_whitespace().next() != Some("mermaid") {
+            continue;
+        }
+
+        let escaped = code.literal.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        data.value = NodeValue::HtmlBlock(NodeHtmlBlock {
+            block_type: 0,
+            literal: format!("<pre class=\"mermaid\">{}</pre>\n", escaped),
+        });
+        count += 1;
+    }
+    count
+}
+
+/// Encode as PNG, e.g. to hand a rendered preview back to a client
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png)
+        .map_err(|e| CasterError::Render(format!("Failed to encode PNG: {}", e)))?;
+    Ok(png.into_inner())
+}
+
+/// Encode many images as PNG, a few at a time on worker threads. Results are in
+/// the same order as `images`.
+pub fn encode_pngs(images: &[DynamicImage]) -> Vec<Result<Vec<u8>>> {
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(4);
+    let chunk = images.len().div_ceil(workers).max(1);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = images
+            .chunks(chunk)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(encode_png).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_default())
+            .collect()
+    })
+}
+
+/// An image encoded for sending to a client
+pub struct EncodedImage {
+    pub data: Vec<u8>,
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Scale `image` by `scale` (clamped to 0.05-1.0) and encode it as "png" or "jpeg";
+/// `quality` only applies to JPEG
+pub fn encode_image(image: DynamicImage, format: &str, scale: f32, quality: u8) -> Result<EncodedImage> {
+    let scale = scale.clamp(0.05, 1.0);
+    let image = if scale < 1.0 {
+        let width = ((image.width() as f32 * scale) as u32).max(1);
+        let height = ((image.height() as f32 * scale) as u32).max(1);
+        image.resize_exact(width, height, image::imageops::FilterType::Triangle)
+    } else {
+        image
+    };
+
+    let (data, mime_type) = match format {
+        "png" => (encode_png(&image)?, "image/png"),
+        "jpeg" | "jpg" => {
+            let mut jpeg = Vec::new();
+            // JPEG has no alpha channel
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, quality.clamp(1, 100))
+                .encode_image(&DynamicImage::ImageRgb8(image.to_rgb8()))
+                .map_err(|e| CasterError::Render(format!("Failed to encode JPEG: {}", e)))?;
+            (jpeg, "image/jpeg")
+        }
+        other => return Err(CasterError::Render(format!("Unsupported image format: {}", other))),
+    };
+
+    Ok(EncodedImage {
+        data,
+        mime_type,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+/// Scale down, never up, to fit within `width` x `height`; a side that is None is unbounded
+pub fn fit_within(image: DynamicImage, width: Option<u32>, height: Option<u32>) -> DynamicImage {
+    let max_width = width.unwrap_or(u32::MAX).max(1);
+    let max_height = height.unwrap_or(u32::MAX).max(1);
+    if image.width() <= max_width && image.height() <= max_height {
+        return image;
+    }
+    image.resize(max_width, max_height, image::imageops::FilterType::Lanczos3)
+}
 
 pub struct RenderEngine {
-    pdf_renderer: Option<PdfRenderer>,
-    pdf_init_error: Option<String>,
+    pdf_pool: PdfPool,
     audio_renderer: AudioRenderer,
     wasm_runner: WasmRunner,
     screen_mirror: Option<ScreenMirror>,
+    mirror_stream: Option<MirrorStream>,
+    model_renderer: Option<ModelRenderer>,
+    model_init_error: Option<String>,
 }
 
 impl RenderEngine {
     pub async fn new() -> Result<Self> {
         Ok(Self {
-            pdf_renderer: None,
-            pdf_init_error: None,
+            pdf_pool: PdfPool::default(),
             audio_renderer: AudioRenderer::new(),
             wasm_runner: WasmRunner::new()?,
             screen_mirror: None,
+            mirror_stream: None,
+            model_renderer: None,
+            model_init_error: None,
         })
     }
 
-    pub fn render_markdown(&self, markdown: &str, theme: Option<&str>) -> Result<String> {
-        let mut options = Options::default();
-        options.extension.strikethrough = true;
-        options.extension.table = true;
-        options.extension.autolink = true;
-        options.extension.tasklist = true;
-        options.render.unsafe_ = true;
+    pub fn render_markdown(&self, markdown: &str, theme: Option<&str>, math: bool) -> Result<String> {
+        render_markdown(markdown, theme, math)
+    }
 
-        let html = markdown_to_html(markdown, &options);
+    /// A markdown deck as an HTML page showing a slide at a time
+    pub fn render_presentation(&self, markdown: &str, theme: Option<&str>, math: bool) -> Result<String> {
+        render_presentation(markdown, theme, math)
+    }
 
-        // Wrap with theme CSS
-        let theme_css = match theme {
-            Some("dark") => include_str!("themes/dark.css"),
-            Some("light") => include_str!("themes/light.css"),
-            _ => include_str!("themes/dark.css"),
-        };
+    /// The first page of `markdown` laid out as a `width` x `height` image
+    pub fn render_markdown_to_image(&self, markdown: &str, theme: Option<&str>, width: u32, height: u32) -> Result<DynamicImage> {
+        render_markdown_pages(markdown, theme, width, height)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| CasterError::Render("Markdown rendered no pages".into()))
+    }
 
-        Ok(format!(
-            r#"<!DOCTYPE html>
-<html>
-<head>
-    <meta charset="UTF-8">
-    <style>{}</style>
-</head>
-<body>
-    <div class="markdown-body">
-        {}
-    </div>
-</body>
-</html>"#,
-            theme_css, html
-        ))
+    /// Every page of `markdown`, for devices that can only show images
+    pub fn render_markdown_pages(&self, markdown: &str, theme: Option<&str>, width: u32, height: u32) -> Result<Vec<DynamicImage>> {
+        render_markdown_pages(markdown, theme, width, height)
     }
 
-    pub fn render_pdf(&mut self, data: &[u8], page: u32) -> Result<DynamicImage> {
-        // If we previously failed to initialize, return that error
-        if let Some(ref error) = self.pdf_init_error {
-            return Err(CasterError::Render(format!("PDF renderer initialization failed: {}", error)));
-        }
-        
-        // Try to initialize PDF renderer if not already done
-        if self.pdf_renderer.is_none() {
-            match PdfRenderer::new() {
-                Ok(renderer) => {
-                    self.pdf_renderer = Some(renderer);
-                }
-                Err(e) => {
-                    // Store the error to avoid repeated initialization attempts
-                    let error_msg = e.to_string();
-                    self.pdf_init_error = Some(error_msg.clone());
-                    return Err(CasterError::Render(format!("Failed to initialize PDF renderer: {}", error_msg)));
-                }
-            }
-        }
+    /// Handle for PDF work. Take it and release the engine before awaiting, so a
+    /// slow document doesn't hold up other rendering.
+    pub fn pdf_pool(&self) -> PdfPool {
+        self.pdf_pool.clone()
+    }
 
-        if let Some(ref mut renderer) = self.pdf_renderer {
-            renderer.render_page(data, page)
-        } else {
-            Err(CasterError::Render("PDF renderer not initialized".into()))
-        }
+    pub fn render_audio_waveform(&self, samples: &[f32], sample_rate: u32, width: u32, height: u32) -> Result<DynamicImage> {
+        self.audio_renderer.render_waveform(samples, sample_rate, width, height)
     }
 
-    pub fn render_audio_waveform(&self, samples: &[f32], width: u32, height: u32) -> Result<DynamicImage> {
-        self.audio_renderer.render_waveform(samples, width, height)
+    /// Spectrum of the start of `samples`, over about a tenth of a second
+    pub fn render_audio_spectrum(&self, samples: &[f32], sample_rate: u32, width: u32, height: u32) -> Result<DynamicImage> {
+        let fft_size = (sample_rate as usize / 10).next_power_of_two().clamp(256, 8192);
+        let spectrum = audio::compute_spectrum(samples, sample_rate, fft_size);
+        self.audio_renderer.render_spectrum(&spectrum.magnitudes, sample_rate, width, height)
     }
 
-    pub async fn run_wasm(&mut self, wasm_bytes: &[u8], entry_point: Option<&str>) -> Result<Vec<u8>> {
-        self.wasm_runner.run(wasm_bytes, entry_point).await
+    /// Level meter for the peak of `samples`
+    pub fn render_audio_level(&self, samples: &[f32], width: u32, height: u32) -> Result<DynamicImage> {
+        let levels = audio::compute_levels(samples);
+        self.audio_renderer.render_level_meter(levels.peak, width, height)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_audio_spectrogram(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+        width: u32,
+        height: u32,
+        window_size: usize,
+        overlap: f32,
+        scale: SpectrogramScale,
+    ) -> Result<DynamicImage> {
+        self.audio_renderer.render_spectrogram_scaled(samples, sample_rate, width, height, window_size, overlap, scale)
+    }
+
+    pub async fn run_wasm(&self, wasm_bytes: &[u8], entry_point: Option<&str>, sandbox: &WasmSandboxConfig) -> Result<Vec<u8>> {
+        self.wasm_runner.run_sandboxed(wasm_bytes, entry_point, sandbox).await
     }
 
     pub async fn start_screen_mirror(&mut self, display_id: Option<String>) -> Result<()> {
@@ -107,6 +365,22 @@ impl RenderEngine {
         Ok(())
     }
 
+    /// Grab one frame of `monitor` (an id or name from `ScreenMirror::list_monitors`, the
+    /// primary if None), reusing the running mirror when it already captures that monitor
+    pub fn capture_screen(&mut self, monitor: Option<&str>) -> Result<DynamicImage> {
+        let current = self.screen_mirror.as_ref().map(|mirror| mirror.get_monitor_info());
+        let reusable = current.is_some_and(|info| match monitor {
+            Some(monitor) => info.id == monitor || info.name == monitor,
+            None => info.is_primary,
+        });
+        if !reusable {
+            self.screen_mirror = Some(ScreenMirror::new(monitor.map(str::to_string))?);
+        }
+
+        self.capture_screen_frame()?
+            .ok_or_else(|| CasterError::Display("Screen capture returned no frame".into()))
+    }
+
     pub fn capture_screen_frame(&mut self) -> Result<Option<DynamicImage>> {
         if let Some(ref mut mirror) = self.screen_mirror {
             mirror.capture_frame()
@@ -115,8 +389,107 @@ impl RenderEngine {
         }
     }
 
-    pub async fn render_3d_model(&mut self, _model_path: &str) -> Result<()> {
-        // TODO: Implement 3D model rendering with Bevy
-        Err(CasterError::Render("3D rendering not yet implemented".into()))
+    /// Start streaming a monitor as JPEG frames, or join the stream already running
+    /// when it captures the same monitor at the same quality and rate
+    /// The stream's frames, and the token that ends it for every client
+    pub fn start_mirror_stream(
+        &mut self,
+        display_id: Option<String>,
+        quality: MirrorQuality,
+        fps: Option<u32>,
+    ) -> Result<(tokio::sync::watch::Receiver<Option<bytes::Bytes>>, CancellationToken)> {
+        if let Some(ref stream) = self.mirror_stream {
+            if stream.matches(display_id.as_deref(), &quality, fps) {
+                return Ok((stream.subscribe(), stream.cancel_token()));
+            }
+        }
+
+        // Replacing the stream ends it for clients of the old one
+        self.stop_mirror_stream();
+        let stream = MirrorStream::start(display_id, quality, fps)?;
+        let frames = (stream.subscribe(), stream.cancel_token());
+        self.mirror_stream = Some(stream);
+        Ok(frames)
+    }
+
+    /// Stop every screen capture: the mirror used for single grabs and the stream
+    pub fn stop_screen_mirror(&mut self) {
+        self.screen_mirror = None;
+        self.stop_mirror_stream();
+    }
+
+    /// Stop the mirror stream, if one is running; true if there was one
+    pub fn stop_mirror_stream(&mut self) -> bool {
+        match self.mirror_stream.take() {
+            Some(stream) => {
+                stream.stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn render_3d_model(&mut self, data: &[u8], width: u32, height: u32, camera: OrbitCamera) -> Result<DynamicImage> {
+        // If we previously failed to initialize, return that error
+        if let Some(ref error) = self.model_init_error {
+            return Err(CasterError::Render(format!("3D renderer initialization failed: {}", error)));
+        }
+
+        // GPU setup is expensive, so only do it the first time a model is rendered
+        if self.model_renderer.is_none() {
+            match ModelRenderer::new() {
+                Ok(renderer) => {
+                    self.model_renderer = Some(renderer);
+                }
+                Err(e) => {
+                    let error_msg = e.to_string();
+                    self.model_init_error = Some(error_msg.clone());
+                    return Err(CasterError::Render(format!("Failed to initialize 3D renderer: {}", error_msg)));
+                }
+            }
+        }
+
+        if let Some(ref renderer) = self.model_renderer {
+            renderer.render(data, width, height, camera)
+        } else {
+            Err(CasterError::Render("3D renderer not initialized".into()))
+        }
+    }
+}
+
+/// Resolve a markdown theme to CSS.
+///
+/// `dark` and `light` are built in, `css:<rules>` is used verbatim, and any
+/// other value is treated as a path to a stylesheet on disk.
+fn load_theme_css(theme: Option<&str>) -> Result<Cow<'static, str>> {
+    match theme {
+        None | Some("dark") => Ok(Cow::Borrowed(include_str!("themes/dark.css"))),
+        Some("light") => Ok(Cow::Borrowed(include_str!("themes/light.css"))),
+        Some(theme) => {
+            if let Some(css) = theme.strip_prefix("css:") {
+                return Ok(Cow::Owned(css.to_string()));
+            }
+
+            std::fs::read_to_string(theme)
+                .map(Cow::Owned)
+                .map_err(|e| CasterError::Render(format!("Failed to load theme '{}': {}", theme, e)))
+        }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATH_AND_CODE: &str = "Inline $e^x$ and `$not math$`.\n\n```\nlet price = \"$$5$$\";\nlet cost = \"$x$\";\n```\n\n$$a^2$$\n";
+
+    #[test]
+    fn math_delimiters_inside_code_stay_code() {
+        let (html, _) = markdown_fragment(MATH_AND_CODE, None, true).unwrap();
+        assert_eq!(html.matches("data-math-style").count(), 2, "{}", html);
+        assert!(html.contains(r#"data-math-style="inline">e^x<"#), "{}", html);
+        assert!(html.contains(r#"data-math-style="display">a^2<"#), "{}", html);
+        assert!(html.contains("<code>$not math$</code>"), "{}", html);
+        assert!(html.contains("$$5$$") && html.contains("$x$"), "{}", html);
+    }
+}
@@ -0,0 +1,421 @@
+use glam::{Mat4, Vec3};
+use image::{DynamicImage, RgbaImage};
+use wgpu::util::DeviceExt;
+
+use crate::{CasterError, Result};
+
+const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+const SHADER: &str = r#"
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    model: mat4x4<f32>,
+    base_color: vec4<f32>,
+    light_dir: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+
+struct VertexOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+};
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(1) normal: vec3<f32>) -> VertexOut {
+    var out: VertexOut;
+    out.position = u.view_proj * u.model * vec4<f32>(position, 1.0);
+    out.normal = normalize((u.model * vec4<f32>(normal, 0.0)).xyz);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let diffuse = max(dot(normalize(in.normal), normalize(u.light_dir.xyz)), 0.0);
+    let shade = 0.25 + 0.75 * diffuse;
+    return vec4<f32>(u.base_color.rgb * shade, u.base_color.a);
+}
+"#;
+
+/// Orbit camera around the model's center.
+///
+/// Angles are in degrees; `distance` is a multiple of the model's bounding radius.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct OrbitCamera {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            yaw: 30.0,
+            pitch: 20.0,
+            distance: 2.5,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    model: [[f32; 4]; 4],
+    base_color: [f32; 4],
+    light_dir: [f32; 4],
+}
+
+/// Mesh data pulled out of a glTF document
+struct LoadedMesh {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    base_color: [f32; 4],
+}
+
+/// Offscreen wgpu renderer for glTF/GLB models
+pub struct ModelRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl ModelRenderer {
+    pub fn new() -> Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }))
+        .ok_or_else(|| CasterError::Render("No GPU adapter available for 3D rendering".into()))?;
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Model Renderer Device"),
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::downlevel_defaults(),
+                memory_hints: Default::default(),
+            },
+            None,
+        ))
+        .map_err(|e| CasterError::Render(format!("Failed to create GPU device: {}", e)))?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Model Shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Model Uniforms Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Model Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Model Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: COLOR_FORMAT,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            bind_group_layout,
+        })
+    }
+
+    /// Render a glTF/GLB document to an image from the given camera angle
+    pub fn render(&self, gltf_data: &[u8], width: u32, height: u32, camera: OrbitCamera) -> Result<DynamicImage> {
+        if width == 0 || height == 0 {
+            return Err(CasterError::Render("Render size must be non-zero".into()));
+        }
+
+        let mesh = load_first_mesh(gltf_data)?;
+
+        // Center the model and scale it to a unit bounding sphere
+        let (min, max) = mesh.vertices.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), v| {
+                let p = Vec3::from(v.position);
+                (min.min(p), max.max(p))
+            },
+        );
+        let center = (min + max) * 0.5;
+        let radius = ((max - min).length() * 0.5).max(f32::EPSILON);
+        let model = Mat4::from_scale(Vec3::splat(1.0 / radius)) * Mat4::from_translation(-center);
+
+        let yaw = camera.yaw.to_radians();
+        let pitch = camera.pitch.clamp(-89.0, 89.0).to_radians();
+        let distance = camera.distance.max(1.1);
+        let eye = Vec3::new(
+            distance * pitch.cos() * yaw.sin(),
+            distance * pitch.sin(),
+            distance * pitch.cos() * yaw.cos(),
+        );
+        let view = Mat4::look_at_rh(eye, Vec3::ZERO, Vec3::Y);
+        let proj = Mat4::perspective_rh(45f32.to_radians(), width as f32 / height as f32, 0.01, 100.0);
+
+        let uniforms = Uniforms {
+            view_proj: (proj * view).to_cols_array_2d(),
+            model: model.to_cols_array_2d(),
+            base_color: mesh.base_color,
+            // Key light over the camera's shoulder
+            light_dir: (eye.normalize() + Vec3::Y).normalize().extend(0.0).to_array(),
+        };
+
+        let device = &self.device;
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Model Vertices"),
+            contents: bytemuck::cast_slice(&mesh.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Model Indices"),
+            contents: bytemuck::cast_slice(&mesh.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Model Uniforms"),
+            contents: bytemuck::bytes_of(&uniforms),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Model Uniforms"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+        let color_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Model Color Target"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: COLOR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Model Depth Target"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let color_view = color_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Rows copied out of a texture must be 256-byte aligned
+        let unpadded_bytes_per_row = width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Model Readback"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Model Encoder"),
+        });
+
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Model Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color { r: 0.08, g: 0.08, b: 0.12, a: 1.0 }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            pass.draw_indexed(0..mesh.indices.len() as u32, 0, 0..1);
+        }
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &color_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv()
+            .map_err(|_| CasterError::Render("GPU readback was cancelled".into()))?
+            .map_err(|e| CasterError::Render(format!("Failed to read rendered frame: {}", e)))?;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let mapped = slice.get_mapped_range();
+            for row in mapped.chunks(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        readback.unmap();
+
+        let rgba_image = RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| CasterError::Render("Failed to create image from rendered frame".into()))?;
+
+        Ok(DynamicImage::ImageRgba8(rgba_image))
+    }
+}
+
+/// Load the first mesh of a glTF/GLB document, merging its primitives
+fn load_first_mesh(gltf_data: &[u8]) -> Result<LoadedMesh> {
+    let (document, buffers, _images) = gltf::import_slice(gltf_data)
+        .map_err(|e| CasterError::Render(format!("Failed to load glTF: {}", e)))?;
+
+    let mesh = document
+        .meshes()
+        .next()
+        .ok_or_else(|| CasterError::Render("glTF document contains no meshes".into()))?;
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut base_color = [0.8, 0.8, 0.8, 1.0];
+
+    for primitive in mesh.primitives() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            continue;
+        }
+
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|b| &b.0[..]));
+        let Some(positions) = reader.read_positions() else {
+            continue;
+        };
+        let positions: Vec<[f32; 3]> = positions.collect();
+        let normals: Vec<[f32; 3]> = reader
+            .read_normals()
+            .map(|n| n.collect())
+            .unwrap_or_else(|| vec![[0.0, 1.0, 0.0]; positions.len()]);
+
+        let offset = vertices.len() as u32;
+        vertices.extend(positions.iter().zip(normals.iter()).map(|(p, n)| Vertex {
+            position: *p,
+            normal: *n,
+        }));
+
+        match reader.read_indices() {
+            Some(read) => indices.extend(read.into_u32().map(|i| i + offset)),
+            None => indices.extend(offset..offset + positions.len() as u32),
+        }
+
+        base_color = primitive.material().pbr_metallic_roughness().base_color_factor();
+    }
+
+    if vertices.is_empty() || indices.is_empty() {
+        return Err(CasterError::Render("glTF mesh has no triangle geometry".into()));
+    }
+
+    Ok(LoadedMesh {
+        vertices,
+        indices,
+        base_color,
+    })
+}
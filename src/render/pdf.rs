@@ -1,10 +1,26 @@
-use image::{DynamicImage, RgbaImage};
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
 use pdfium_render::prelude::*;
+use tokio_util::sync::CancellationToken;
 
 use crate::{CasterError, Result};
 
+/// Default cap on pages included in a thumbnail sheet
+pub const DEFAULT_MAX_THUMBNAIL_PAGES: u32 = 50;
+
+/// Widest page `render_all_pages` will produce
+pub const MAX_PAGE_PREVIEW_WIDTH: u32 = 1024;
+
+/// Pixels `render_all_pages` may hold at once, about 128 MB of RGBA
+const MAX_PREVIEW_PIXELS: u64 = 32 * 1024 * 1024;
+
+// Spacing between thumbnail tiles, in pixels
+const THUMBNAIL_GAP: u32 = 8;
+const SHEET_BACKGROUND: Rgba<u8> = Rgba([32, 32, 32, 255]);
+const PLACEHOLDER_TILE: Rgba<u8> = Rgba([128, 128, 128, 255]);
+
 pub struct PdfRenderer {
     pdfium: Pdfium,
+    max_thumbnail_pages: u32,
 }
 
 impl PdfRenderer {
@@ -16,21 +32,14 @@ impl PdfRenderer {
                 .map_err(|e| CasterError::Render(format!("Failed to initialize Pdfium: {}", e)))?,
         );
 
-        Ok(Self { pdfium })
+        Ok(Self {
+            pdfium,
+            max_thumbnail_pages: DEFAULT_MAX_THUMBNAIL_PAGES,
+        })
     }
 
     pub fn render_page(&mut self, pdf_data: &[u8], page_num: u32) -> Result<DynamicImage> {
-        // Validate page number to prevent overflow when converting to u16
-        if page_num == 0 {
-            return Err(CasterError::Render("Page number must be at least 1".into()));
-        }
-        if page_num > u16::MAX as u32 {
-            return Err(CasterError::Render(format!(
-                "Page number {} exceeds maximum supported page number {}",
-                page_num,
-                u16::MAX
-            )));
-        }
+        let page_index = page_index(page_num)?;
 
         // Load PDF document
         let document = self
@@ -38,8 +47,7 @@ impl PdfRenderer {
             .load_pdf_from_byte_slice(pdf_data, None)
             .map_err(|e| CasterError::Render(format!("Failed to load PDF: {}", e)))?;
 
-        // Get the page (convert 1-based to 0-based)
-        let page_index = (page_num - 1) as u16;
+        // Get the page
         let page = document
             .pages()
             .get(page_index)
@@ -76,4 +84,209 @@ impl PdfRenderer {
 
         Ok(document.pages().len() as u32)
     }
+
+    /// Limit how many pages `render_thumbnail_sheet` will tile
+    pub fn set_max_thumbnail_pages(&mut self, max_pages: u32) {
+        self.max_thumbnail_pages = max_pages.max(1);
+    }
+
+    /// Render every page as a thumbnail and tile them into a grid with `cols` columns.
+    ///
+    /// Tiles share the first page's aspect ratio; pages that fail to render become gray placeholders.
+    pub fn render_thumbnail_sheet(&mut self, pdf_data: &[u8], cols: u32, thumb_width: u32) -> Result<DynamicImage> {
+        self.render_thumbnail_sheet_with_progress(pdf_data, cols, thumb_width, |_, _| {}, &CancellationToken::new())
+    }
+
+    /// Same as `render_thumbnail_sheet`, calling `on_page(done, total)` after each
+    /// tile and giving up with `CasterError::Cancelled` between pages once `cancel` fires
+    pub fn render_thumbnail_sheet_with_progress(
+        &mut self,
+        pdf_data: &[u8],
+        cols: u32,
+        thumb_width: u32,
+        mut on_page: impl FnMut(u32, u32),
+        cancel: &CancellationToken,
+    ) -> Result<DynamicImage> {
+        if cols == 0 || thumb_width == 0 {
+            return Err(CasterError::Render("Thumbnail columns and width must be non-zero".into()));
+        }
+
+        let page_count = self.get_page_count(pdf_data)?.min(self.max_thumbnail_pages);
+        if page_count == 0 {
+            return Err(CasterError::Render("PDF has no pages".into()));
+        }
+
+        let document = self
+            .pdfium
+            .load_pdf_from_byte_slice(pdf_data, None)
+            .map_err(|e| CasterError::Render(format!("Failed to load PDF: {}", e)))?;
+        let pages = document.pages();
+
+        // Size every tile from the first page, falling back to portrait A4
+        let aspect = pages
+            .get(0)
+            .ok()
+            .map(|page| page.height().value / page.width().value)
+            .filter(|aspect| aspect.is_finite() && *aspect > 0.0)
+            .unwrap_or(1.414);
+        let thumb_height = ((thumb_width as f32 * aspect).round() as u32).max(1);
+
+        let cols = cols.min(page_count);
+        let rows = page_count.div_ceil(cols);
+        let sheet_width = cols * thumb_width + (cols + 1) * THUMBNAIL_GAP;
+        let sheet_height = rows * thumb_height + (rows + 1) * THUMBNAIL_GAP;
+        let mut sheet = RgbaImage::from_pixel(sheet_width, sheet_height, SHEET_BACKGROUND);
+
+        let config = PdfRenderConfig::new()
+            .set_target_width(thumb_width as i32)
+            .set_maximum_height(thumb_height as i32);
+
+        for index in 0..page_count {
+            if cancel.is_cancelled() {
+                return Err(CasterError::Cancelled(format!("Thumbnail sheet stopped after {} of {} pages", index, page_count)));
+            }
+            let x = THUMBNAIL_GAP + (index % cols) * (thumb_width + THUMBNAIL_GAP);
+            let y = THUMBNAIL_GAP + (index / cols) * (thumb_height + THUMBNAIL_GAP);
+
+            let thumbnail = pages
+                .get(index as u16)
+                .and_then(|page| {
+                    page.render_with_config(&config)
+                        .map(|bitmap| bitmap.as_image().into_rgba8())
+                });
+
+            match thumbnail {
+                Ok(thumbnail) => {
+                    // Center pages whose aspect differs from the tile
+                    let offset_x = thumb_width.saturating_sub(thumbnail.width()) / 2;
+                    let offset_y = thumb_height.saturating_sub(thumbnail.height()) / 2;
+                    imageops::overlay(&mut sheet, &thumbnail, (x + offset_x) as i64, (y + offset_y) as i64);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to render thumbnail for page {}: {}", index + 1, e);
+                    let placeholder = RgbaImage::from_pixel(thumb_width, thumb_height, PLACEHOLDER_TILE);
+                    imageops::overlay(&mut sheet, &placeholder, x as i64, y as i64);
+                }
+            }
+
+            on_page(index + 1, page_count);
+        }
+
+        Ok(DynamicImage::ImageRgba8(sheet))
+    }
+
+    /// Render each page on its own, scaled to fit `max_width` (capped at
+    /// `MAX_PAGE_PREVIEW_WIDTH`), for previews of every page.
+    ///
+    /// Only the first `max_thumbnail_pages` pages are rendered, and pages past the
+    /// pixel budget are skipped, so a huge deck can't exhaust memory. Pdfium isn't
+    /// thread-safe, so pages render one after another. A page that fails is
+    /// reported in its slot without stopping the rest.
+    /// Stops with `CasterError::Cancelled` between pages once `cancel` fires.
+    pub fn render_all_pages(
+        &self,
+        pdf_data: &[u8],
+        max_width: u32,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<Result<DynamicImage>>> {
+        if max_width == 0 {
+            return Err(CasterError::Render("Page width must be non-zero".into()));
+        }
+        let max_width = max_width.min(MAX_PAGE_PREVIEW_WIDTH);
+
+        let document = self
+            .pdfium
+            .load_pdf_from_byte_slice(pdf_data, None)
+            .map_err(|e| CasterError::Render(format!("Failed to load PDF: {}", e)))?;
+        let pages = document.pages();
+        let page_count = (pages.len() as u32).min(self.max_thumbnail_pages);
+
+        let mut pixels = 0u64;
+        let mut render = |index: u32| -> Result<DynamicImage> {
+            let page = pages
+                .get(index as u16)
+                .map_err(|e| CasterError::Render(format!("Failed to get page {}: {}", index + 1, e)))?;
+
+            // Every preview is max_width wide; very tall pages are capped at twice that
+            let aspect = page.height().value / page.width().value;
+            let aspect = if aspect.is_finite() && aspect > 0.0 { aspect } else { 1.414 };
+            let height = ((max_width as f32 * aspect).round() as u32).clamp(1, max_width * 2);
+
+            pixels += max_width as u64 * height as u64;
+            if pixels > MAX_PREVIEW_PIXELS {
+                return Err(CasterError::Render(format!("Page {} skipped: preview memory limit reached", index + 1)));
+            }
+
+            let config = PdfRenderConfig::new()
+                .set_target_width(max_width as i32)
+                .set_maximum_height(height as i32);
+            page.render_with_config(&config)
+                .map(|bitmap| DynamicImage::ImageRgba8(bitmap.as_image().into_rgba8()))
+                .map_err(|e| CasterError::Render(format!("Failed to render page {}: {}", index + 1, e)))
+        };
+
+        let mut rendered = Vec::with_capacity(page_count as usize);
+        for index in 0..page_count {
+            if cancel.is_cancelled() {
+                return Err(CasterError::Cancelled(format!("Page previews stopped after {} of {} pages", index, page_count)));
+            }
+            rendered.push(render(index));
+        }
+
+        Ok(rendered)
+    }
+
+    /// Extract a page's text, or every page's text joined by form feeds when `page_num` is `None`
+    pub fn extract_text(&self, pdf_data: &[u8], page_num: Option<u32>) -> Result<String> {
+        let document = self
+            .pdfium
+            .load_pdf_from_byte_slice(pdf_data, None)
+            .map_err(|e| CasterError::Render(format!("Failed to load PDF: {}", e)))?;
+        let pages = document.pages();
+
+        let page_text = |index: u16| -> Result<String> {
+            let page = pages
+                .get(index)
+                .map_err(|e| CasterError::Render(format!("Failed to get page {}: {}", index as u32 + 1, e)))?;
+            let text = page
+                .text()
+                .map_err(|e| CasterError::Render(format!("Failed to read text on page {}: {}", index as u32 + 1, e)))?;
+            Ok(text.all())
+        };
+
+        match page_num {
+            Some(page_num) => {
+                let index = page_index(page_num)?;
+                if index >= pages.len() {
+                    return Err(CasterError::Render(format!(
+                        "Page {} is out of range, document has {} pages",
+                        page_num,
+                        pages.len()
+                    )));
+                }
+                page_text(index)
+            }
+            None => {
+                let texts = (0..pages.len()).map(page_text).collect::<Result<Vec<_>>>()?;
+                Ok(texts.join("\u{000C}"))
+            }
+        }
+    }
+}
+
+/// Convert a 1-based page number to pdfium's 0-based u16 index
+fn page_index(page_num: u32) -> Result<u16> {
+    // Validate page number to prevent overflow when converting to u16
+    if page_num == 0 {
+        return Err(CasterError::Render("Page number must be at least 1".into()));
+    }
+    if page_num > u16::MAX as u32 {
+        return Err(CasterError::Render(format!(
+            "Page number {} exceeds maximum supported page number {}",
+            page_num,
+            u16::MAX
+        )));
+    }
+
+    Ok((page_num - 1) as u16)
 }
@@ -0,0 +1,225 @@
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use image::DynamicImage;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use super::PdfRenderer;
+use crate::{CasterError, Result};
+
+/// Threads rendering PDFs. Pdfium serializes calls into the library, so more
+/// mostly adds room for slow documents rather than speed.
+pub const DEFAULT_PDF_WORKERS: usize = 2;
+
+/// Requests waiting for a worker before new ones are turned away
+pub const DEFAULT_PDF_QUEUE: usize = 8;
+
+/// How long a caller waits for its result
+pub const DEFAULT_PDF_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A renderer, or why pdfium couldn't be bound on this worker
+type Job = Box<dyn FnOnce(std::result::Result<&mut PdfRenderer, &str>) + Send>;
+
+/// Runs PDF work on dedicated threads, each with its own pdfium binding, so a
+/// slow document holds neither the render engine lock nor an async worker.
+/// Cheap to clone; every clone feeds the same workers.
+#[derive(Clone)]
+pub struct PdfPool {
+    jobs: Sender<Job>,
+    timeout: Duration,
+    /// How the latest pdfium binding went; None until a worker has tried
+    binding: Arc<Mutex<Option<std::result::Result<(), String>>>>,
+}
+
+impl PdfPool {
+    pub fn new(workers: usize, queue: usize, timeout: Duration) -> Self {
+        let (jobs, receiver) = crossbeam_channel::bounded::<Job>(queue.max(1));
+        let binding = Arc::new(Mutex::new(None));
+        for index in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            let binding = Arc::clone(&binding);
+            let spawned = std::thread::Builder::new()
+                .name(format!("pdf-worker-{}", index))
+                .spawn(move || worker(receiver, binding));
+            if let Err(e) = spawned {
+                warn!("Failed to start PDF worker {}: {}", index, e);
+            }
+        }
+
+        Self { jobs, timeout, binding }
+    }
+
+    /// Whether pdfium is loaded. Binds it on a worker if none has tried yet.
+    pub async fn check(&self) -> Result<()> {
+        let binding = self.binding.lock().unwrap().clone();
+        match binding {
+            Some(Ok(())) => Ok(()),
+            Some(Err(e)) => Err(CasterError::Render(format!("PDF renderer initialization failed: {}", e))),
+            None => self.run(|_| Ok(())).await,
+        }
+    }
+
+    /// Run `work` on a worker. Fails straight away when the queue is full, and
+    /// with `CasterError::Render` if no result arrives within the timeout.
+    pub async fn run<T: Send + 'static>(
+        &self,
+        work: impl FnOnce(&mut PdfRenderer) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        self.run_until(&CancellationToken::new(), work).await
+    }
+
+    /// Like `run`, returning `CasterError::Cancelled` as soon as `cancel` fires.
+    /// Work still waiting in the queue is then skipped; work already running
+    /// should watch the token itself.
+    pub async fn run_until<T: Send + 'static>(
+        &self,
+        cancel: &CancellationToken,
+        work: impl FnOnce(&mut PdfRenderer) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        if cancel.is_cancelled() {
+            return Err(CasterError::Cancelled("PDF rendering cancelled before it started".into()));
+        }
+
+        let (reply, result) = oneshot::channel();
+        let job: Job = Box::new(move |renderer| {
+            // The caller timed out while this sat in the queue
+            if reply.is_closed() {
+                return;
+            }
+            let result = match renderer {
+                Ok(renderer) => work(renderer),
+                Err(e) => Err(CasterError::Render(format!("PDF renderer initialization failed: {}", e))),
+            };
+            let _ = reply.send(result);
+        });
+
+        self.jobs.try_send(job).map_err(|e| match e {
+            TrySendError::Full(_) => CasterError::Render("Too many PDFs are rendering, try again shortly".into()),
+            TrySendError::Disconnected(_) => CasterError::Render("No PDF workers are running".into()),
+        })?;
+
+        tokio::select! {
+            result = tokio::time::timeout(self.timeout, result) => match result {
+                Ok(Ok(result)) => result,
+                Ok(Err(_)) => Err(CasterError::Render("PDF worker stopped before finishing".into())),
+                Err(_) => Err(CasterError::Render(format!("PDF rendering took longer than {}s", self.timeout.as_secs()))),
+            },
+            _ = cancel.cancelled() => Err(CasterError::Cancelled("PDF rendering cancelled".into())),
+        }
+    }
+
+    pub async fn render_page(&self, data: Vec<u8>, page: u32) -> Result<DynamicImage> {
+        self.run(move |renderer| renderer.render_page(&data, page)).await
+    }
+
+    /// Every page as its own preview; a page that fails has an error in its slot
+    pub async fn render_all_pages(
+        &self,
+        data: Vec<u8>,
+        max_width: u32,
+        cancel: CancellationToken,
+    ) -> Result<Vec<Result<DynamicImage>>> {
+        let pages_cancel = cancel.clone();
+        self.run_until(&cancel, move |renderer| renderer.render_all_pages(&data, max_width, &pages_cancel)).await
+    }
+
+    /// Tile the pages into one overview image, reporting progress from 0.0 to 1.0
+    /// from the worker thread
+    pub async fn render_thumbnail_sheet(
+        &self,
+        data: Vec<u8>,
+        cols: u32,
+        thumb_width: u32,
+        mut on_progress: impl FnMut(f32) + Send + 'static,
+        cancel: CancellationToken,
+    ) -> Result<DynamicImage> {
+        let pages_cancel = cancel.clone();
+        self.run_until(&cancel, move |renderer| {
+            renderer.render_thumbnail_sheet_with_progress(&data, cols, thumb_width, |done, total| {
+                on_progress(done as f32 / total as f32)
+            }, &pages_cancel)
+        }).await
+    }
+
+    pub async fn extract_text(&self, data: Vec<u8>, page: Option<u32>) -> Result<String> {
+        self.run(move |renderer| renderer.extract_text(&data, page)).await
+    }
+}
+
+impl Default for PdfPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_PDF_WORKERS, DEFAULT_PDF_QUEUE, DEFAULT_PDF_TIMEOUT)
+    }
+}
+
+/// Bind pdfium on the first job and keep the binding; a failure is remembered
+/// so it isn't retried on every request
+fn worker(jobs: Receiver<Job>, binding: Arc<Mutex<Option<std::result::Result<(), String>>>>) {
+    let mut renderer: Option<std::result::Result<PdfRenderer, String>> = None;
+
+    // Ends when every PdfPool clone has been dropped
+    while let Ok(job) = jobs.recv() {
+        let bound = renderer.get_or_insert_with(|| {
+            let bound = PdfRenderer::new().map_err(|e| e.to_string());
+            *binding.lock().unwrap() = Some(bound.as_ref().map(|_| ()).map_err(Clone::clone));
+            bound
+        });
+        let outcome = std::panic::catch_unwind(AssertUnwindSafe(|| match bound {
+            Ok(renderer) => job(Ok(renderer)),
+            Err(e) => job(Err(e.as_str())),
+        }));
+
+        // Don't trust a binding that was mid-call when it panicked
+        if outcome.is_err() {
+            warn!("PDF render panicked; rebinding pdfium for the next job");
+            renderer = None;
+        }
+    }
+    info!("PDF worker stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-page PDF of `width` x `height` points
+    fn blank_pdf(width: u32, height: u32) -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] >>", width, height),
+        ];
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", index + 1, object).bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        pdf.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).bytes());
+        pdf
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the pdfium library"]
+    async fn two_pdfs_render_at_once() {
+        let pool = PdfPool::default();
+        let (wide, tall) = tokio::join!(
+            pool.render_page(blank_pdf(300, 100), 1),
+            pool.render_page(blank_pdf(100, 300), 1),
+        );
+
+        // Pages render at twice their size in points
+        let wide = wide.unwrap();
+        assert_eq!((wide.width(), wide.height()), (600, 200));
+        let tall = tall.unwrap();
+        assert_eq!((tall.width(), tall.height()), (200, 600));
+    }
+}
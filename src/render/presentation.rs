@@ -0,0 +1,156 @@
+use super::{markdown_fragment, markdown_page};
+use crate::{Result, CasterError};
+
+/// Slides fill the window one at a time, with the controls in a corner
+const DECK_HEAD: &str = r#"
+    <style>
+        body { padding: 0; overflow: hidden; }
+        .slide { display: none; box-sizing: border-box; height: 100vh; max-width: none; padding: 6vh 8vw 10vh; overflow-y: auto; font-size: 1.5em; }
+        .slide.current { display: block; }
+        .deck-controls { position: fixed; right: 24px; bottom: 16px; display: flex; align-items: center; gap: 12px; opacity: 0.6; }
+        .deck-controls:hover { opacity: 1; }
+        .deck-controls button { font: inherit; padding: 2px 12px; cursor: pointer; }
+    </style>"#;
+
+const DECK_CONTROLS: &str = r#"
+    <nav class="deck-controls">
+        <button id="deck-previous" title="Previous slide">◀</button>
+        <span id="deck-counter"></span>
+        <button id="deck-next" title="Next slide">▶</button>
+    </nav>"#;
+
+/// Shows one slide at a time, moved on by the buttons and the same keys as the
+/// cast window. The slide number is kept in the URL's hash, so a reload or a
+/// link lands on the same slide.
+const DECK_SCRIPT: &str = r##"
+    <script>
+        (function () {
+            var slides = document.querySelectorAll(".slide");
+            var counter = document.getElementById("deck-counter");
+            var current = 0;
+
+            function show(index) {
+                current = Math.max(0, Math.min(slides.length - 1, index));
+                slides.forEach(function (slide, i) { slide.classList.toggle("current", i === current); });
+                counter.textContent = (current + 1) + " / " + slides.length;
+                history.replaceState(null, "", "#" + (current + 1));
+            }
+            function fromHash() {
+                show((parseInt(location.hash.slice(1), 10) || 1) - 1);
+            }
+
+            document.getElementById("deck-previous").onclick = function () { show(current - 1); };
+            document.getElementById("deck-next").onclick = function () { show(current + 1); };
+            document.addEventListener("keydown", function (e) {
+                switch (e.key) {
+                    case "ArrowRight": case "PageDown": case " ": case "n": case "N": show(current + 1); break;
+                    case "ArrowLeft": case "PageUp": case "p": case "P": show(current - 1); break;
+                    case "Home": show(0); break;
+                    case "End": show(slides.length - 1); break;
+                    default: return;
+                }
+                e.preventDefault();
+            });
+            window.addEventListener("hashchange", fromHash);
+            fromHash();
+        })();
+    </script>"##;
+
+/// How a presentation's slides are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlideFormat {
+    /// Markdown with slides separated by `---` lines
+    Markdown,
+    /// A deck exported to PDF, a page per slide
+    Pdf,
+}
+
+impl SlideFormat {
+    /// Check a `ContentType::Presentation` format before anything is cast; None for "auto"
+    pub fn parse(format: &str) -> Result<Option<Self>> {
+        match format {
+            "auto" | "" => Ok(None),
+            "markdown" | "md" => Ok(Some(Self::Markdown)),
+            "pdf" => Ok(Some(Self::Pdf)),
+            other => Err(CasterError::InvalidRequest(format!(
+                "Unknown presentation format: {}; expected markdown, pdf or auto",
+                other
+            ))),
+        }
+    }
+
+    /// The format of a deck: the one `format` names, or for "auto", PDF if
+    /// `data` is one and markdown otherwise
+    pub fn resolve(format: &str, data: &[u8]) -> Result<Self> {
+        Ok(Self::parse(format)?.unwrap_or(if data.starts_with(b"%PDF-") { Self::Pdf } else { Self::Markdown }))
+    }
+}
+
+/// Split a markdown deck into slides at `---` lines. A `---` straight under a
+/// line of text underlines it as a heading, as it would in any markdown, and
+/// one in a code block is part of the code. Empty slides are dropped, but
+/// there is always at least one.
+pub fn split_slides(markdown: &str) -> Vec<String> {
+    let mut slides = Vec::new();
+    let mut current = String::new();
+    // The marker and length of the code fence we're in
+    let mut fence: Option<(char, usize)> = None;
+    let mut in_paragraph = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Some((marker, length)) = fence {
+            if trimmed.len() >= length && trimmed.chars().all(|c| c == marker) {
+                fence = None;
+            }
+            in_paragraph = false;
+        } else if let Some(opening) = fence_marker(trimmed) {
+            fence = Some(opening);
+            in_paragraph = false;
+        } else if trimmed == "---" && !in_paragraph {
+            slides.push(std::mem::take(&mut current));
+            continue;
+        } else {
+            in_paragraph = !trimmed.is_empty() && !trimmed.starts_with('#');
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    slides.push(current);
+
+    slides.retain(|slide| !slide.trim().is_empty());
+    if slides.is_empty() {
+        slides.push(String::new());
+    }
+    slides
+}
+
+/// The marker and length of a line opening a code fence
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let marker = line.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let length = line.chars().take_while(|&c| c == marker).count();
+    (length >= 3).then_some((marker, length))
+}
+
+/// A markdown deck as a standalone reveal.js-style HTML page: each slide
+/// rendered like `render_markdown` renders a document, shown one at a time
+/// with next and previous controls
+pub fn render_presentation(markdown: &str, theme: Option<&str>, math: bool) -> Result<String> {
+    let mut body = String::new();
+    let mut diagrams = 0;
+    for (i, slide) in split_slides(markdown).iter().enumerate() {
+        let (html, count) = markdown_fragment(slide, theme, math)?;
+        diagrams += count;
+        body.push_str(&format!(
+            r#"
+    <section class="slide markdown-body" id="slide-{}">
+        {}
+    </section>"#,
+            i + 1,
+            html
+        ));
+    }
+    body.push_str(DECK_CONTROLS);
+
+    markdown_page(&body, theme, math, diagrams > 0, DECK_HEAD, DECK_SCRIPT)
+}
@@ -1,49 +1,827 @@
+use bytes::Bytes;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use wasmtime::{Config, Engine, Instance, Linker, Memory, Module, ResourceLimiter, Store, Trap, TypedFunc, Val, ValType};
+use wasmtime_wasi::pipe::{MemoryOutputPipe, SinkOutputStream};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{
+    async_trait, DirPerms, FilePerms, I32Exit, OutputStream, Pollable, StdoutStream, StreamResult, WasiCtxBuilder,
+};
+
 use crate::{CasterError, Result};
 
-/// Placeholder for WebAssembly runtime execution.
-/// 
-/// TODO: Implement WASM execution using wasmer 3.x or wasmtime:
-/// - Load and compile WASM modules
-/// - Execute functions with proper argument marshalling
-/// - Handle WASI imports for system access
-/// - Manage memory imports/exports
-/// - Support async execution
+/// Fuel a module may burn, roughly one unit per instruction
+pub const DEFAULT_FUEL: u64 = 1_000_000_000;
+
+/// Largest a module's linear memory may grow
+pub const DEFAULT_MAX_MEMORY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Wall-clock limit, for modules blocked in a WASI call rather than burning fuel
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What a module may use and reach. The default is fully closed: no
+/// directories, no network, and the limits above.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WasmSandboxConfig {
+    pub max_memory_bytes: u64,
+    pub max_fuel: u64,
+    /// Host directories the module may open, each visible at the same path
+    pub allow_fs: Vec<PathBuf>,
+    /// Let the module open sockets and resolve names
+    pub allow_net: bool,
+    pub timeout: Duration,
+}
+
+impl Default for WasmSandboxConfig {
+    fn default() -> Self {
+        Self {
+            max_memory_bytes: DEFAULT_MAX_MEMORY_BYTES,
+            max_fuel: DEFAULT_FUEL,
+            allow_fs: Vec::new(),
+            allow_net: false,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+/// How far a cast may loosen its module's sandbox, from the `[wasm]` config section.
+/// Directories are separately held to `cache.media_dirs`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WasmLimitsConfig {
+    /// Most memory a cast may give its module
+    pub max_memory_bytes: u64,
+    /// Whether a cast may let its module use the network
+    pub allow_net: bool,
+}
+
+impl Default for WasmLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_memory_bytes: 4 * DEFAULT_MAX_MEMORY_BYTES,
+            allow_net: false,
+        }
+    }
+}
+
+/// Widest or tallest frame a module may draw
+pub const MAX_FRAME_SIDE: u32 = 4096;
+
+/// How much of a module's stderr is kept for error messages
+const MAX_STDERR: usize = 4096;
+
+/// Most a module may write to stdout in one run
+const MAX_OUTPUT: usize = 64 * 1024 * 1024;
+
+/// How often the engine's epoch ticks, which sandbox timeouts are counted in
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The engine every module is compiled for, metering fuel as modules run and
+/// interrupting them once their epoch deadline passes
+static ENGINE: Lazy<std::result::Result<Engine, String>> = Lazy::new(|| {
+    let mut config = Config::new();
+    config.consume_fuel(true).epoch_interruption(true);
+    let engine = Engine::new(&config).map_err(|e| format!("Failed to start the WebAssembly engine: {}", e))?;
+
+    let ticker = engine.weak();
+    std::thread::Builder::new()
+        .name("wasm-epoch".into())
+        .spawn(move || {
+            while let Some(engine) = ticker.upgrade() {
+                engine.increment_epoch();
+                drop(engine);
+                std::thread::sleep(EPOCH_TICK);
+            }
+        })
+        .map_err(|e| format!("Failed to start the WebAssembly epoch timer: {}", e))?;
+    Ok(engine)
+});
+
+fn engine() -> Result<&'static Engine> {
+    ENGINE.as_ref().map_err(|e| CasterError::Render(e.clone()))
+}
+
+/// What a running module's store holds
+struct Host {
+    wasi: WasiP1Ctx,
+    limits: MemoryLimit,
+    fuel: u64,
+    timeout: Duration,
+}
+
+/// Caps a module's linear memory, remembering whether it hit the cap so the
+/// resulting trap can say why
+struct MemoryLimit {
+    max_bytes: u64,
+    exceeded: bool,
+}
+
+impl ResourceLimiter for MemoryLimit {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> anyhow::Result<bool> {
+        if desired as u64 > self.max_bytes {
+            // An error rather than Ok(false) traps memory.grow instead of letting it return -1
+            self.exceeded = true;
+            anyhow::bail!("memory limit of {} bytes exceeded", self.max_bytes);
+        }
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> anyhow::Result<bool> {
+        Ok(desired <= MAX_TABLE_ELEMENTS)
+    }
+}
+
+/// Most entries a module's tables may hold
+const MAX_TABLE_ELEMENTS: usize = 1_000_000;
+
+/// A module instantiated inside a sandbox, with its stdout and stderr captured
+struct Sandboxed {
+    store: Store<Host>,
+    instance: Instance,
+    stderr: StderrTail,
+}
+
+impl Sandboxed {
+    /// Compile and instantiate `wasm_bytes`, binary or WAT, with `args` as its
+    /// WASI argv and its stdout going to `stdout`
+    fn new(
+        wasm_bytes: &[u8],
+        args: &[String],
+        sandbox: &WasmSandboxConfig,
+        stdout: impl StdoutStream + 'static,
+    ) -> Result<Self> {
+        check_module(wasm_bytes)?;
+        let engine = engine()?;
+        let module = Module::new(engine, wasm_bytes)
+            .map_err(|e| CasterError::Render(format!("Invalid WebAssembly module: {:#}", e)))?;
+
+        let stderr = StderrTail::default();
+        let mut wasi = WasiCtxBuilder::new();
+        wasi.arg("module").args(args).stdout(stdout).stderr(stderr.clone());
+        // Nothing but what the sandbox opens, each directory at its own path
+        for dir in sandbox_dirs(sandbox)? {
+            wasi.preopened_dir(&dir, dir.to_string_lossy(), DirPerms::all(), FilePerms::all())
+                .map_err(|e| CasterError::Render(format!("Failed to open sandbox directory {}: {}", dir.display(), e)))?;
+        }
+        if sandbox.allow_net {
+            wasi.inherit_network().allow_ip_name_lookup(true);
+        }
+        let wasi = wasi.build_p1();
+
+        let mut linker = Linker::new(engine);
+        preview1::add_to_linker_sync(&mut linker, |host: &mut Host| &mut host.wasi)
+            .map_err(|e| CasterError::Render(format!("Failed to link WASI: {}", e)))?;
+
+        let limits = MemoryLimit {
+            max_bytes: memory_limit(sandbox),
+            exceeded: false,
+        };
+        let host = Host {
+            wasi,
+            limits,
+            fuel: sandbox.max_fuel.max(1),
+            timeout: sandbox_timeout(sandbox),
+        };
+        let mut store = Store::new(engine, host);
+        store.limiter(|host| &mut host.limits);
+        refuel(&mut store)?;
+
+        // Instantiating runs the module's start function, under the same limits
+        let instance = match linker.instantiate(&mut store, &module) {
+            Ok(instance) => instance,
+            Err(e) => {
+                let error = failure(&store, &stderr, "instantiation", e);
+                return Err(error);
+            }
+        };
+
+        Ok(Self {
+            store,
+            instance,
+            stderr,
+        })
+    }
+
+    /// Call `entry_point` with `args` as its parameters, giving back its results
+    fn call(&mut self, entry_point: &str, args: &[String]) -> Result<Vec<Val>> {
+        let func = self.instance.get_func(&mut self.store, entry_point).ok_or_else(|| {
+            CasterError::Render(format!("WebAssembly module has no exported function {}", entry_point))
+        })?;
+        let ty = func.ty(&self.store);
+
+        // WASI programs take their arguments from argv instead
+        let params = if ty.params().len() == 0 {
+            Vec::new()
+        } else if ty.params().len() == args.len() {
+            ty.params().zip(args).map(|(ty, arg)| parse_param(entry_point, &ty, arg)).collect::<Result<_>>()?
+        } else {
+            return Err(CasterError::Render(format!(
+                "WebAssembly {} takes {} arguments, got {}",
+                entry_point,
+                ty.params().len(),
+                args.len()
+            )));
+        };
+        let mut results = vec![Val::I32(0); ty.results().len()];
+
+        match func.call(&mut self.store, &params, &mut results) {
+            Ok(()) => Ok(results),
+            // A WASI program exiting cleanly
+            Err(e) if matches!(e.downcast_ref::<I32Exit>(), Some(I32Exit(0))) => Ok(Vec::new()),
+            Err(e) => Err(failure(&self.store, &self.stderr, entry_point, e)),
+        }
+    }
+}
+
+/// Fill the store's fuel and set its deadline, for a run or one frame of one
+fn refuel(store: &mut Store<Host>) -> Result<()> {
+    let ticks = store.data().timeout.as_millis().div_ceil(EPOCH_TICK.as_millis()).max(1);
+    store.set_epoch_deadline(ticks as u64);
+    let fuel = store.data().fuel;
+    store.set_fuel(fuel).map_err(|e| CasterError::Render(e.to_string()))
+}
+
+/// Say why a module stopped, naming the limit it went over if it did
+fn failure(store: &Store<Host>, stderr: &StderrTail, entry_point: &str, error: anyhow::Error) -> CasterError {
+    if store.data().limits.exceeded {
+        return CasterError::Render(format!(
+            "WebAssembly module exceeded {} memory",
+            format_bytes(store.data().limits.max_bytes)
+        ));
+    }
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => {
+            return CasterError::Render(format!(
+                "WebAssembly {} ran out of fuel after {} units",
+                entry_point,
+                store.data().fuel
+            ));
+        }
+        Some(Trap::Interrupt) => return timed_out(entry_point, store.data().timeout),
+        _ => {}
+    }
+
+    let reason = match (error.downcast_ref::<I32Exit>(), error.downcast_ref::<Trap>()) {
+        (Some(I32Exit(status)), _) => format!("exited with status {}", status),
+        (_, Some(trap)) => format!("trapped: {}", trap),
+        _ => format!("failed: {}", error.root_cause()),
+    };
+    match stderr.contents().trim() {
+        "" => CasterError::Render(format!("WebAssembly {} {}", entry_point, reason)),
+        stderr => CasterError::Render(format!("WebAssembly {} {}: {}", entry_point, reason, stderr)),
+    }
+}
+
+fn timed_out(entry_point: &str, timeout: Duration) -> CasterError {
+    CasterError::Render(format!(
+        "WebAssembly {} ran longer than {:.1}s",
+        entry_point,
+        timeout.as_secs_f32()
+    ))
+}
+
+/// A string argument as a value of the parameter type `ty`
+fn parse_param(entry_point: &str, ty: &ValType, arg: &str) -> Result<Val> {
+    let invalid = || CasterError::Render(format!("WebAssembly {} can't take {:?} as {}", entry_point, arg, ty));
+    Ok(match ty {
+        ValType::I32 => Val::I32(arg.parse().map_err(|_| invalid())?),
+        ValType::I64 => Val::I64(arg.parse().map_err(|_| invalid())?),
+        ValType::F32 => Val::F32(arg.parse::<f32>().map_err(|_| invalid())?.to_bits()),
+        ValType::F64 => Val::F64(arg.parse::<f64>().map_err(|_| invalid())?.to_bits()),
+        _ => return Err(invalid()),
+    })
+}
+
+/// A result value as text, one per line of a run's output
+fn format_result(value: &Val) -> String {
+    match value {
+        Val::I32(v) => v.to_string(),
+        Val::I64(v) => v.to_string(),
+        Val::F32(bits) => f32::from_bits(*bits).to_string(),
+        Val::F64(bits) => f64::from_bits(*bits).to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Runs WebAssembly modules on an embedded wasmtime engine. Every run gets a
+/// store of its own, metered by fuel, interrupted at its timeout and with its
+/// memory capped, so a runaway module traps instead of taking the server with
+/// it. WASI sees only the directories its sandbox opens.
 pub struct WasmRunner {
-    // Placeholder for WebAssembly runtime
-    // TODO: Implement with wasmer 3.x or wasmtime
+    sandbox: WasmSandboxConfig,
 }
 
 impl WasmRunner {
     pub fn new() -> Result<Self> {
-        Ok(Self {})
+        engine()?;
+        Ok(Self {
+            sandbox: WasmSandboxConfig::default(),
+        })
+    }
+
+    /// Sandbox for modules run without one of their own
+    pub fn set_sandbox(&mut self, sandbox: WasmSandboxConfig) {
+        self.sandbox = sandbox;
+    }
+
+    pub fn sandbox(&self) -> &WasmSandboxConfig {
+        &self.sandbox
     }
 
-    /// Run a WebAssembly module.
-    /// 
-    /// TODO: Implement WASM execution with proper runtime initialization.
-    pub async fn run(&mut self, _wasm_bytes: &[u8], _entry_point: Option<&str>) -> Result<Vec<u8>> {
-        // TODO: Implement WASM execution
-        Err(CasterError::Render("WebAssembly execution not yet implemented".into()))
+    /// Run a module's `entry_point`, `_start` by default, and give back what it
+    /// wrote to stdout followed by its return values, one per line
+    pub async fn run(&mut self, wasm_bytes: &[u8], entry_point: Option<&str>) -> Result<Vec<u8>> {
+        self.execute(wasm_bytes, entry_point.unwrap_or("_start"), &[], &self.sandbox).await
     }
 
-    /// Run a WebAssembly module with string arguments.
-    /// 
-    /// TODO: Implement WASM execution with argument marshalling.
+    /// Like `run`, inside `sandbox` rather than the runner's own
+    pub async fn run_sandboxed(
+        &self,
+        wasm_bytes: &[u8],
+        entry_point: Option<&str>,
+        sandbox: &WasmSandboxConfig,
+    ) -> Result<Vec<u8>> {
+        self.execute(wasm_bytes, entry_point.unwrap_or("_start"), &[], sandbox).await
+    }
+
+    /// Run a module with `args` as its WASI argv. An export that takes
+    /// parameters also gets them as its arguments, parsed to their types.
     pub async fn run_with_string_args(
         &mut self,
-        _wasm_bytes: &[u8],
-        _entry_point: &str,
-        _args: &[String],
+        wasm_bytes: &[u8],
+        entry_point: &str,
+        args: &[String],
     ) -> Result<String> {
-        // TODO: Implement WASM execution with arguments
-        Err(CasterError::Render("WebAssembly execution not yet implemented".into()))
+        let output = self.execute(wasm_bytes, entry_point, args, &self.sandbox).await?;
+        String::from_utf8(output)
+            .map_err(|e| CasterError::Render(format!("WebAssembly output isn't UTF-8: {}", e)))
+    }
+
+    /// Instantiate a module, run its `_initialize` if it's a WASI reactor, and
+    /// give back the contents of its exported `memory`
+    pub fn get_memory(&mut self, wasm_bytes: &[u8]) -> Result<Vec<u8>> {
+        let mut module = Sandboxed::new(wasm_bytes, &[], &self.sandbox, SinkOutputStream)?;
+        if module.instance.get_func(&mut module.store, "_initialize").is_some() {
+            module.call("_initialize", &[])?;
+        }
+        let memory = module
+            .instance
+            .get_memory(&mut module.store, "memory")
+            .ok_or_else(|| CasterError::Render("WebAssembly module doesn't export its memory".into()))?;
+        Ok(memory.data(&module.store).to_vec())
+    }
+
+    async fn execute(
+        &self,
+        wasm_bytes: &[u8],
+        entry_point: &str,
+        args: &[String],
+        sandbox: &WasmSandboxConfig,
+    ) -> Result<Vec<u8>> {
+        let wasm_bytes = wasm_bytes.to_vec();
+        let entry = entry_point.to_string();
+        let args = args.to_vec();
+        let sandbox = sandbox.clone();
+        let timeout = sandbox_timeout(&sandbox);
+
+        // Compiling and running block, and WASI calls drive a runtime of their own
+        let task = tokio::task::spawn_blocking(move || {
+            let stdout = MemoryOutputPipe::new(MAX_OUTPUT);
+            let mut module = Sandboxed::new(&wasm_bytes, &args, &sandbox, stdout.clone())?;
+            let results = module.call(&entry, &args)?;
+
+            let mut output = stdout.contents().to_vec();
+            for value in &results {
+                output.extend_from_slice(format_result(value).as_bytes());
+                output.push(b'\n');
+            }
+            Ok(output)
+        });
+
+        // Epochs don't interrupt a module waiting in a WASI call; give up on it
+        // here, and it traps once the call returns
+        match tokio::time::timeout(timeout + Duration::from_secs(2), task).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => Err(CasterError::Render(format!("WebAssembly task failed: {}", e))),
+            Err(_) => Err(timed_out(entry_point, timeout)),
+        }
+    }
+}
+
+/// Draws frames from a module that runs for as long as it's shown.
+///
+/// The module exports `render(width: i32, height: i32, time_ms: i32) -> i32`
+/// and its `memory`. Each call gets the size the display would like and the
+/// milliseconds since the module started, and returns where in memory its
+/// frame is: the width and height as little-endian u32s, then that many RGBA
+/// pixels, row by row. Returning 0 ends the animation. A WASI reactor's
+/// `_initialize` runs first. Every frame gets the sandbox's full fuel and
+/// timeout, and the caller decides how often to ask for one.
+pub struct WasmFrames {
+    module: Sandboxed,
+    render: TypedFunc<(i32, i32, i32), i32>,
+    memory: Memory,
+    width: u32,
+    height: u32,
+    started: Instant,
+}
+
+impl WasmFrames {
+    pub fn new(wasm_bytes: &[u8], sandbox: &WasmSandboxConfig, width: u32, height: u32) -> Result<Self> {
+        // Nobody reads a drawing module's stdout
+        let mut module = Sandboxed::new(wasm_bytes, &[], sandbox, SinkOutputStream)?;
+        if module.instance.get_func(&mut module.store, "_initialize").is_some() {
+            module.call("_initialize", &[])?;
+        }
+        let render = module
+            .instance
+            .get_typed_func::<(i32, i32, i32), i32>(&mut module.store, "render")
+            .map_err(|e| CasterError::Render(format!(
+                "WebAssembly module can't draw frames: it needs to export render(width, height, time_ms) -> ptr: {}",
+                e
+            )))?;
+        let memory = module
+            .instance
+            .get_memory(&mut module.store, "memory")
+            .ok_or_else(|| CasterError::Render("WebAssembly module doesn't export its memory".into()))?;
+
+        Ok(Self {
+            module,
+            render,
+            memory,
+            width,
+            height,
+            started: Instant::now(),
+        })
     }
 
-    /// Get exported memory from a WASM module.
-    /// 
-    /// TODO: Implement memory export reading.
-    pub fn get_memory(&mut self, _wasm_bytes: &[u8]) -> Result<Vec<u8>> {
-        // TODO: Implement memory export reading
-        Err(CasterError::Render("WebAssembly memory access not yet implemented".into()))
+    /// Have the module draw its next frame; None once it has finished
+    pub fn next_frame(&mut self) -> Result<Option<RgbaImage>> {
+        refuel(&mut self.module.store)?;
+        let time_ms = self.started.elapsed().as_millis() as i32;
+        let size = (self.width as i32, self.height as i32, time_ms);
+        let ptr = match self.render.call(&mut self.module.store, size) {
+            Ok(0) => return Ok(None),
+            Ok(ptr) => ptr as u32 as usize,
+            Err(e) => return Err(failure(&self.module.store, &self.module.stderr, "render", e)),
+        };
+
+        let memory = self.memory.data(&self.module.store);
+        let outside = || CasterError::Render(format!("WebAssembly frame at {} lies outside the module's memory", ptr));
+        let header = memory.get(ptr..ptr + 8).ok_or_else(outside)?;
+        let width = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let height = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if width == 0 || height == 0 || width > MAX_FRAME_SIDE || height > MAX_FRAME_SIDE {
+            return Err(CasterError::Render(format!(
+                "WebAssembly frame is {}x{}; frames must be between 1x1 and {}x{}",
+                width, height, MAX_FRAME_SIDE, MAX_FRAME_SIDE
+            )));
+        }
+        let pixels = memory
+            .get(ptr + 8..ptr + 8 + width as usize * height as usize * 4)
+            .ok_or_else(outside)?;
+
+        Ok(RgbaImage::from_raw(width, height, pixels.to_vec()))
+    }
+}
+
+/// A module's stderr, keeping only its last `MAX_STDERR` bytes so a chatty
+/// module never fills it
+#[derive(Clone, Default)]
+struct StderrTail(Arc<Mutex<Vec<u8>>>);
+
+impl StderrTail {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl OutputStream for StderrTail {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        let mut tail = self.0.lock().unwrap();
+        tail.extend_from_slice(&bytes);
+        let excess = tail.len().saturating_sub(MAX_STDERR);
+        tail.drain(..excess);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        Ok(MAX_STDERR)
+    }
+}
+
+#[async_trait]
+impl Pollable for StderrTail {
+    async fn ready(&mut self) {}
+}
+
+impl StdoutStream for StderrTail {
+    fn stream(&self) -> Box<dyn OutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+/// The directories a sandbox opens, resolved; each has to exist
+fn sandbox_dirs(sandbox: &WasmSandboxConfig) -> Result<Vec<PathBuf>> {
+    sandbox.allow_fs.iter()
+        .map(|dir| match dir.canonicalize() {
+            Ok(dir) if dir.is_dir() => Ok(dir),
+            _ => Err(CasterError::Render(format!("Sandbox directory {} doesn't exist", dir.display()))),
+        })
+        .collect()
+}
+
+fn sandbox_timeout(sandbox: &WasmSandboxConfig) -> Duration {
+    sandbox.timeout.max(Duration::from_millis(100))
+}
+
+fn memory_limit(sandbox: &WasmSandboxConfig) -> u64 {
+    sandbox.max_memory_bytes.max(64 * 1024)
+}
+
+/// "64 MiB" for whole mebibytes, a byte count otherwise
+fn format_bytes(bytes: u64) -> String {
+    const MIB: u64 = 1024 * 1024;
+    if bytes.is_multiple_of(MIB) {
+        format!("{} MiB", bytes / MIB)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// Turn away what isn't a binary module or WAT text with a clearer message
+/// than the engine's parse error
+fn check_module(bytes: &[u8]) -> Result<()> {
+    if bytes.is_empty() {
+        return Err(CasterError::Render("WebAssembly module is empty".into()));
+    }
+    if bytes.starts_with(b"\0asm") {
+        if bytes.len() < 8 {
+            return Err(CasterError::Render("WebAssembly module is truncated: the header is incomplete".into()));
+        }
+        return Ok(());
+    }
+
+    // The first line of WAT that isn't a comment
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+    let first = text.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with(";;")).unwrap_or("");
+    if first.starts_with("(module") {
+        return Ok(());
+    }
+    Err(CasterError::Render(
+        "Not a WebAssembly module: expected the \\0asm header or WAT text starting with (module".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 16) "hello")
+          (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 16))
+            (i32.store (i32.const 4) (i32.const 5))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+    "#;
+
+    fn run(wat: &str, entry_point: Option<&str>, sandbox: &WasmSandboxConfig) -> Result<Vec<u8>> {
+        let runner = WasmRunner::new().unwrap();
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(runner.run_sandboxed(wat.as_bytes(), entry_point, sandbox))
+    }
+
+    fn error(result: Result<Vec<u8>>) -> String {
+        match result {
+            Ok(output) => panic!("module succeeded with {:?}", String::from_utf8_lossy(&output)),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn start_gives_back_stdout() {
+        assert_eq!(run(HELLO, None, &WasmSandboxConfig::default()).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn an_export_gives_back_its_results() {
+        let module = r#"(module (func (export "answer") (result i32 f64) (i32.const 42) (f64.const 1.5)))"#;
+        assert_eq!(run(module, Some("answer"), &WasmSandboxConfig::default()).unwrap(), b"42\n1.5\n");
+    }
+
+    #[test]
+    fn string_args_become_parameters() {
+        let module = r#"(module (func (export "add") (param i32 i32) (result i32) (i32.add (local.get 0) (local.get 1))))"#;
+        let mut runner = WasmRunner::new().unwrap();
+        let output = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(runner.run_with_string_args(module.as_bytes(), "add", &["2".into(), "40".into()]))
+            .unwrap();
+        assert_eq!(output, "42\n");
+    }
+
+    #[test]
+    fn a_trap_is_reported() {
+        let module = r#"(module (func (export "_start") unreachable))"#;
+        let message = error(run(module, None, &WasmSandboxConfig::default()));
+        assert!(message.contains("_start trapped"), "{}", message);
+        assert!(message.contains("unreachable"), "{}", message);
+    }
+
+    #[test]
+    fn running_out_of_fuel_stops_the_module() {
+        let module = r#"(module (func (export "_start") (loop $forever (br $forever))))"#;
+        let sandbox = WasmSandboxConfig {
+            max_fuel: 10_000,
+            ..WasmSandboxConfig::default()
+        };
+        assert_eq!(
+            error(run(module, None, &sandbox)),
+            "Render error: WebAssembly _start ran out of fuel after 10000 units"
+        );
+    }
+
+    #[test]
+    fn growing_past_the_memory_limit_traps() {
+        // 2 MiB more on top of the first page
+        let module = r#"(module (memory 1) (func (export "_start") (drop (memory.grow (i32.const 32)))))"#;
+        let sandbox = WasmSandboxConfig {
+            max_memory_bytes: 1024 * 1024,
+            ..WasmSandboxConfig::default()
+        };
+        assert_eq!(
+            error(run(module, None, &sandbox)),
+            "Render error: WebAssembly module exceeded 1 MiB memory"
+        );
+    }
+
+    #[test]
+    fn a_module_is_stopped_at_its_timeout() {
+        let module = r#"(module (func (export "_start") (loop $forever (br $forever))))"#;
+        let sandbox = WasmSandboxConfig {
+            max_fuel: u64::MAX,
+            timeout: Duration::from_millis(200),
+            ..WasmSandboxConfig::default()
+        };
+        let started = Instant::now();
+        assert_eq!(
+            error(run(module, None, &sandbox)),
+            "Render error: WebAssembly _start ran longer than 0.2s"
+        );
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    /// Exits with the errno from asking WASI about the first preopened directory
+    const PREOPEN: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_prestat_get" (func $prestat (param i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "proc_exit" (func $exit (param i32)))
+          (memory (export "memory") 1)
+          (func (export "_start") (call $exit (call $prestat (i32.const 3) (i32.const 0)))))
+    "#;
+
+    #[test]
+    fn the_sandbox_opens_no_directories_by_default() {
+        // EBADF: there's nothing at fd 3
+        assert_eq!(
+            error(run(PREOPEN, None, &WasmSandboxConfig::default())),
+            "Render error: WebAssembly _start exited with status 8"
+        );
+    }
+
+    #[test]
+    fn allowed_directories_are_opened() {
+        let sandbox = WasmSandboxConfig {
+            allow_fs: vec![std::env::temp_dir()],
+            ..WasmSandboxConfig::default()
+        };
+        assert_eq!(run(PREOPEN, None, &sandbox).unwrap(), b"");
+
+        let sandbox = WasmSandboxConfig {
+            allow_fs: vec![std::env::temp_dir().join(format!("q8-caster-missing-{}", uuid::Uuid::new_v4()))],
+            ..WasmSandboxConfig::default()
+        };
+        assert!(error(run(PREOPEN, None, &sandbox)).contains("doesn't exist"));
+    }
+
+    #[test]
+    fn a_clean_exit_is_success() {
+        let module = r#"
+            (module
+              (import "wasi_snapshot_preview1" "proc_exit" (func $exit (param i32)))
+              (memory (export "memory") 1)
+              (func (export "_start") (call $exit (i32.const 0))))
+        "#;
+        assert_eq!(run(module, None, &WasmSandboxConfig::default()).unwrap(), b"");
+    }
+
+    #[test]
+    fn invalid_modules_are_described() {
+        let message = error(run("(module (func (export \"_start\") (i32.add)))", None, &WasmSandboxConfig::default()));
+        assert!(message.starts_with("Render error: Invalid WebAssembly module"), "{}", message);
+        let message = error(run("not wasm", None, &WasmSandboxConfig::default()));
+        assert!(message.contains("Not a WebAssembly module"), "{}", message);
+        let message = error(run(HELLO, Some("missing"), &WasmSandboxConfig::default()));
+        assert!(message.contains("no exported function missing"), "{}", message);
+    }
+
+    #[test]
+    fn memory_holds_the_data_segments() {
+        let memory = WasmRunner::new().unwrap().get_memory(HELLO.as_bytes()).unwrap();
+        assert_eq!(memory.len(), 64 * 1024);
+        assert_eq!(&memory[16..21], b"hello");
+    }
+
+    fn frames(wat: &str, sandbox: &WasmSandboxConfig) -> WasmFrames {
+        WasmFrames::new(wat.as_bytes(), sandbox, 4, 2).unwrap()
+    }
+
+    /// Fills the requested size with one colour for two frames, then finishes
+    const TWO_FRAMES: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $drawn (mut i32) (i32.const 0))
+          (func (export "render") (param $width i32) (param $height i32) (param $time_ms i32) (result i32)
+            (local $p i32)
+            (local $end i32)
+            (if (i32.eq (global.get $drawn) (i32.const 2)) (then (return (i32.const 0))))
+            (global.set $drawn (i32.add (global.get $drawn) (i32.const 1)))
+            (i32.store (i32.const 16) (local.get $width))
+            (i32.store (i32.const 20) (local.get $height))
+            (local.set $p (i32.const 24))
+            (local.set $end (i32.add (i32.const 24) (i32.shl (i32.mul (local.get $width) (local.get $height)) (i32.const 2))))
+            (loop $pixels
+              (i32.store (local.get $p) (i32.const 0xFF4080C0))
+              (local.set $p (i32.add (local.get $p) (i32.const 4)))
+              (br_if $pixels (i32.lt_u (local.get $p) (local.get $end))))
+            (i32.const 16)))
+    "#;
+
+    #[test]
+    fn frames_are_drawn_until_render_returns_zero() {
+        let mut frames = frames(TWO_FRAMES, &WasmSandboxConfig::default());
+        for _ in 0..2 {
+            let frame = frames.next_frame().unwrap().expect("a frame");
+            assert_eq!(frame.dimensions(), (4, 2));
+            assert!(frame.pixels().all(|pixel| pixel.0 == [0xC0, 0x80, 0x40, 0xFF]));
+        }
+        assert!(frames.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn the_example_module_draws_a_non_black_frame() {
+        let module = include_str!("../../examples/wasm/gradient.wat");
+        let mut frames = frames(module, &WasmSandboxConfig::default());
+        let frame = frames.next_frame().unwrap().expect("a frame");
+        assert_eq!(frame.dimensions(), (320, 180));
+        assert!(frame.pixels().any(|pixel| pixel.0[..3] != [0, 0, 0]));
+    }
+
+    #[test]
+    fn every_frame_gets_the_sandbox_limits() {
+        let module = r#"
+            (module
+              (memory (export "memory") 1)
+              (func (export "render") (param i32 i32 i32) (result i32) (loop $forever (br $forever)) (i32.const 0)))
+        "#;
+        let sandbox = WasmSandboxConfig {
+            max_fuel: 10_000,
+            ..WasmSandboxConfig::default()
+        };
+        assert_eq!(
+            frames(module, &sandbox).next_frame().unwrap_err().to_string(),
+            "Render error: WebAssembly render ran out of fuel after 10000 units"
+        );
+    }
+
+    #[test]
+    fn frames_must_lie_in_memory_and_exports_must_exist() {
+        let outside = r#"(module (memory (export "memory") 1) (func (export "render") (param i32 i32 i32) (result i32) (i32.const 65532)))"#;
+        let message = frames(outside, &WasmSandboxConfig::default()).next_frame().unwrap_err().to_string();
+        assert!(message.contains("outside the module's memory"), "{}", message);
+
+        let message = match WasmFrames::new(HELLO.as_bytes(), &WasmSandboxConfig::default(), 4, 2) {
+            Ok(_) => panic!("a module without render drew frames"),
+            Err(e) => e.to_string(),
+        };
+        assert!(message.contains("needs to export render"), "{}", message);
     }
 }
@@ -0,0 +1,534 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{Html, Redirect},
+    Json,
+};
+use base64::Engine as _;
+use dashmap::DashMap;
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use ring::rand::{SecureRandom, SystemRandom};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{OnceCell, RwLock};
+use tracing::{debug, info, warn};
+
+use crate::server::auth::bearer_token;
+use crate::server::http::AppState;
+use crate::{CasterError, Result};
+
+/// Requests to Keycloak give up after this long
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a login may take between /auth/login and its callback
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Logins waiting for their callback at once. /auth/login is public, so past
+/// this the oldest is dropped rather than letting anyone grow the map.
+const MAX_PENDING_LOGINS: usize = 1000;
+
+/// A token with an unknown key id refetches the JWKS at most this often, so
+/// forged tokens can't make every request call Keycloak
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(30);
+
+/// Keycloak OpenID Connect client settings.
+#[derive(Debug, Clone)]
+pub struct KeycloakConfig {
+    pub server_url: String,
+    pub realm: String,
+    pub client_id: String,
+    pub client_secret: Option<SecretString>,
+    pub redirect_uri: String,
+}
+
+/// Keycloak client settings from the config file. The client secret isn't one of
+/// them; it belongs in the secrets store or `KEYCLOAK_CLIENT_SECRET`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct KeycloakSettings {
+    pub server_url: Option<String>,
+    pub realm: Option<String>,
+    pub client_id: Option<String>,
+    pub redirect_uri: Option<String>,
+}
+
+impl KeycloakConfig {
+    /// Build from `KEYCLOAK_*` environment variables, then `settings`, then the defaults,
+    /// using `client_secret` when the env has none
+    pub fn from_env(settings: &KeycloakSettings, client_secret: Option<SecretString>) -> Self {
+        let var = |name: &str, setting: &Option<String>, default: &str| {
+            std::env::var(name)
+                .ok()
+                .or_else(|| setting.clone())
+                .unwrap_or_else(|| default.to_string())
+        };
+
+        Self {
+            server_url: var("KEYCLOAK_URL", &settings.server_url, "http://localhost:8080"),
+            realm: var("KEYCLOAK_REALM", &settings.realm, "q8-caster"),
+            client_id: var("KEYCLOAK_CLIENT_ID", &settings.client_id, "q8-caster"),
+            client_secret: std::env::var("KEYCLOAK_CLIENT_SECRET")
+                .ok()
+                .map(SecretString::from)
+                .or(client_secret),
+            redirect_uri: var("KEYCLOAK_REDIRECT_URI", &settings.redirect_uri, "http://localhost:8420/auth/callback"),
+        }
+    }
+
+    /// Base URL of the realm's OpenID Connect endpoints
+    pub fn issuer_url(&self) -> String {
+        format!("{}/realms/{}", self.server_url.trim_end_matches('/'), self.realm)
+    }
+}
+
+/// The realm's endpoints, from its OpenID Connect discovery document
+#[derive(Debug, Clone, Deserialize)]
+struct Provider {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    end_session_endpoint: Option<String>,
+}
+
+/// Roles granted in the realm or for one client
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RoleSet {
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// `aud` is a single string or a list
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, client_id: &str) -> bool {
+        match self {
+            Audience::One(audience) => audience == client_id,
+            Audience::Many(audiences) => audiences.iter().any(|audience| audience == client_id),
+        }
+    }
+}
+
+/// Claims of a Keycloak access token that passed `validate_token`
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeycloakClaims {
+    pub sub: String,
+    pub exp: u64,
+    pub iss: String,
+    #[serde(default)]
+    aud: Option<Audience>,
+    /// Client the token was issued to
+    #[serde(default)]
+    pub azp: Option<String>,
+    /// Keycloak session the token belongs to
+    #[serde(default)]
+    pub sid: Option<String>,
+    #[serde(default)]
+    pub preferred_username: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub realm_access: RoleSet,
+    #[serde(default)]
+    pub resource_access: HashMap<String, RoleSet>,
+    // Our client, whose roles count alongside the realm's
+    #[serde(skip)]
+    client_id: String,
+}
+
+impl KeycloakClaims {
+    /// Realm roles plus the roles granted for this client
+    pub fn roles(&self) -> Vec<String> {
+        let client = self.resource_access.get(&self.client_id).into_iter().flat_map(|set| &set.roles);
+        self.realm_access.roles.iter().chain(client).cloned().collect()
+    }
+}
+
+/// Tokens Keycloak issued for a login
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: u64,
+    refresh_token: Option<String>,
+    #[serde(default)]
+    refresh_expires_in: u64,
+}
+
+/// The realm's signing keys and when they were fetched
+struct KeyCache {
+    keys: JwkSet,
+    fetched: Option<Instant>,
+}
+
+/// A login sent to Keycloak, waiting for its callback
+struct PendingLogin {
+    verifier: String,
+    started: Instant,
+}
+
+/// A session logged in through /auth/callback
+struct LoginSession {
+    refresh_token: Option<SecretString>,
+    expires: u64,
+}
+
+/// Keycloak OpenID Connect: checks bearer tokens against the realm's keys
+/// and runs the authorization code login.
+///
+/// The discovery document and JWKS are fetched on first use, so the server
+/// starts while Keycloak is down. Keys are cached, and refetched when a token
+/// names a key id that isn't among them, e.g. after Keycloak rotates keys.
+pub struct KeycloakAuth {
+    config: KeycloakConfig,
+    http: reqwest::Client,
+    provider: OnceCell<Provider>,
+    keys: RwLock<KeyCache>,
+    rng: SystemRandom,
+    // PKCE verifiers of logins in progress, by their state parameter
+    pending: DashMap<String, PendingLogin>,
+    // Refresh tokens of sessions logged in here, by Keycloak session id, for logout
+    sessions: DashMap<String, LoginSession>,
+    // Sessions logged out here, until their tokens would have expired anyway
+    ended: DashMap<String, u64>,
+}
+
+impl KeycloakAuth {
+    pub async fn new(config: KeycloakConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .map_err(|e| CasterError::Network(format!("Failed to create Keycloak client: {}", e)))?;
+
+        Ok(Self {
+            config,
+            http,
+            provider: OnceCell::new(),
+            keys: RwLock::new(KeyCache { keys: JwkSet { keys: Vec::new() }, fetched: None }),
+            rng: SystemRandom::new(),
+            pending: DashMap::new(),
+            sessions: DashMap::new(),
+            ended: DashMap::new(),
+        })
+    }
+
+    pub fn config(&self) -> &KeycloakConfig {
+        &self.config
+    }
+
+    /// Check an access token's RS256 signature, issuer, audience and expiry
+    pub async fn validate_token(&self, token: &str) -> Result<KeycloakClaims> {
+        let header = decode_header(token)
+            .map_err(|e| CasterError::Auth(format!("Malformed token: {}", e)))?;
+        // Tokens signed with the server's own secret never reach Keycloak
+        if header.alg != Algorithm::RS256 {
+            return Err(CasterError::Auth(format!("Unsupported token algorithm {:?}", header.alg)));
+        }
+        let kid = header.kid.ok_or_else(|| CasterError::Auth("Token has no key id".into()))?;
+
+        let provider = self.provider().await?;
+        let key = self.decoding_key(provider, &kid).await?;
+
+        // Keycloak names the client in `azp` and only adds it to `aud` with an
+        // audience mapper, so the audience is checked below instead
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(&[&provider.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+        validation.validate_aud = false;
+
+        let mut claims = decode::<KeycloakClaims>(token, &key, &validation)
+            .map_err(|e| CasterError::Auth(format!("Invalid token: {}", e)))?
+            .claims;
+
+        let client_id = &self.config.client_id;
+        let for_us = claims.aud.as_ref().is_some_and(|aud| aud.contains(client_id))
+            || claims.azp.as_deref() == Some(client_id.as_str());
+        if !for_us {
+            return Err(CasterError::Auth(format!("Token was not issued for {}", client_id)));
+        }
+        if claims.sid.as_ref().is_some_and(|sid| self.ended.contains_key(sid)) {
+            return Err(CasterError::Auth("Session has been logged out".into()));
+        }
+
+        claims.client_id = client_id.clone();
+        Ok(claims)
+    }
+
+    /// Where to send a browser to log in. The state and PKCE verifier are kept
+    /// until the callback comes back.
+    pub async fn authorization_url(&self) -> Result<String> {
+        let provider = self.provider().await?;
+        let state = uuid::Uuid::new_v4().to_string();
+        let verifier = self.random_verifier()?;
+        let challenge = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(ring::digest::digest(&ring::digest::SHA256, verifier.as_bytes()));
+
+        self.remember_login(state.clone(), verifier);
+
+        let url = url::Url::parse_with_params(&provider.authorization_endpoint, &[
+            ("response_type", "code"),
+            ("client_id", &self.config.client_id),
+            ("redirect_uri", &self.config.redirect_uri),
+            ("scope", "openid profile email"),
+            ("state", &state),
+            ("code_challenge", &challenge),
+            ("code_challenge_method", "S256"),
+        ])
+        .map_err(|e| CasterError::Config(format!("Invalid Keycloak authorization endpoint: {}", e)))?;
+        Ok(url.into())
+    }
+
+    /// Keep a login's verifier for its callback, within `MAX_PENDING_LOGINS`
+    fn remember_login(&self, state: String, verifier: String) {
+        self.pending.retain(|_, login| login.started.elapsed() < LOGIN_TIMEOUT);
+        while self.pending.len() >= MAX_PENDING_LOGINS {
+            let oldest = self.pending.iter()
+                .min_by_key(|login| login.started)
+                .map(|login| login.key().clone());
+            match oldest {
+                Some(oldest) => self.pending.remove(&oldest),
+                None => break,
+            };
+        }
+        self.pending.insert(state, PendingLogin { verifier, started: Instant::now() });
+    }
+
+    /// Trade the code from a login callback for tokens, returning the access
+    /// token and how many seconds it's good for
+    pub async fn exchange_code(&self, code: &str, state: &str) -> Result<(String, u64)> {
+        let (_, login) = self.pending.remove(state)
+            .filter(|(_, login)| login.started.elapsed() < LOGIN_TIMEOUT)
+            .ok_or_else(|| CasterError::Auth("Unknown or expired login".into()))?;
+
+        let provider = self.provider().await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.config.redirect_uri),
+            ("client_id", &self.config.client_id),
+            ("code_verifier", &login.verifier),
+        ];
+        if let Some(ref secret) = self.config.client_secret {
+            form.push(("client_secret", secret.expose_secret()));
+        }
+
+        let response = self.http.post(&provider.token_endpoint).form(&form).send().await
+            .map_err(|e| CasterError::Network(format!("Failed to reach Keycloak: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(CasterError::Auth(format!("Keycloak refused the login code ({}): {}", status, body)));
+        }
+        let tokens: TokenResponse = response.json().await
+            .map_err(|e| CasterError::Network(format!("Invalid token response from Keycloak: {}", e)))?;
+
+        let claims = self.validate_token(&tokens.access_token).await?;
+        info!("Logged in {} through Keycloak", claims.preferred_username.as_deref().unwrap_or(&claims.sub));
+
+        if let Some(sid) = claims.sid {
+            let now = unix_now();
+            self.sessions.retain(|_, session| session.expires > now);
+            self.sessions.insert(sid, LoginSession {
+                refresh_token: tokens.refresh_token.map(SecretString::from),
+                expires: now + tokens.refresh_expires_in.max(tokens.expires_in),
+            });
+        }
+        Ok((tokens.access_token, tokens.expires_in))
+    }
+
+    /// End the token's session: here at once, and in Keycloak when it was
+    /// logged in through this server
+    pub async fn logout(&self, claims: &KeycloakClaims) -> Result<()> {
+        let Some(ref sid) = claims.sid else {
+            return Ok(());
+        };
+        let now = unix_now();
+        self.ended.retain(|_, expires| *expires > now);
+        self.ended.insert(sid.clone(), claims.exp);
+
+        let Some((_, session)) = self.sessions.remove(sid) else {
+            return Ok(());
+        };
+        let (Some(refresh_token), Some(endpoint)) = (session.refresh_token, &self.provider().await?.end_session_endpoint) else {
+            return Ok(());
+        };
+
+        let mut form = vec![
+            ("client_id", self.config.client_id.as_str()),
+            ("refresh_token", refresh_token.expose_secret()),
+        ];
+        if let Some(ref secret) = self.config.client_secret {
+            form.push(("client_secret", secret.expose_secret()));
+        }
+        let response = self.http.post(endpoint).form(&form).send().await
+            .map_err(|e| CasterError::Network(format!("Failed to reach Keycloak: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(CasterError::Network(format!("Keycloak logout failed: {}", response.status())));
+        }
+        Ok(())
+    }
+
+    async fn provider(&self) -> Result<&Provider> {
+        self.provider.get_or_try_init(|| async {
+            let url = format!("{}/.well-known/openid-configuration", self.config.issuer_url());
+            debug!("Fetching OpenID configuration from {}", url);
+            self.fetch_json(&url).await
+        }).await
+    }
+
+    /// The key `kid` names, refetching the JWKS when it's not one we know
+    async fn decoding_key(&self, provider: &Provider, kid: &str) -> Result<DecodingKey> {
+        if let Some(jwk) = self.keys.read().await.keys.find(kid) {
+            return DecodingKey::from_jwk(jwk).map_err(|e| CasterError::Auth(format!("Unusable signing key {}: {}", kid, e)));
+        }
+
+        let mut cache = self.keys.write().await;
+        // Another request may have refetched while this one waited for the lock
+        if cache.keys.find(kid).is_none() && cache.fetched.is_none_or(|at| at.elapsed() >= JWKS_MIN_REFRESH) {
+            debug!("Fetching Keycloak signing keys for unknown key id {}", kid);
+            cache.keys = self.fetch_json(&provider.jwks_uri).await?;
+            cache.fetched = Some(Instant::now());
+        }
+        let jwk = cache.keys.find(kid)
+            .ok_or_else(|| CasterError::Auth(format!("Token signed with unknown key {}", kid)))?;
+        DecodingKey::from_jwk(jwk).map_err(|e| CasterError::Auth(format!("Unusable signing key {}: {}", kid, e)))
+    }
+
+    async fn fetch_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.http.get(url).send().await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| CasterError::Network(format!("Failed to fetch {}: {}", url, e)))?
+            .json().await
+            .map_err(|e| CasterError::Network(format!("Invalid response from {}: {}", url, e)))
+    }
+
+    /// A PKCE code verifier: 32 random bytes, base64url encoded
+    fn random_verifier(&self) -> Result<String> {
+        let mut bytes = [0u8; 32];
+        self.rng
+            .fill(&mut bytes)
+            .map_err(|_| CasterError::Auth("Failed to generate a login verifier".into()))?;
+        Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+async fn claims_from_headers(state: &AppState, headers: &HeaderMap) -> Result<KeycloakClaims> {
+    let token = bearer_token(headers).ok_or_else(|| CasterError::Auth("Missing bearer token".into()))?;
+    state.keycloak_auth.validate_token(token).await
+}
+
+/// Send the browser to Keycloak's login page
+pub async fn login_handler(State(state): State<AppState>) -> Result<Redirect> {
+    Ok(Redirect::to(&state.keycloak_auth.authorization_url().await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Where Keycloak sends the browser back. Hands the access token to the
+/// dashboard, which keeps it in local storage, and returns to it.
+pub async fn callback_handler(
+    State(state): State<AppState>,
+    Query(query): Query<CallbackQuery>,
+) -> Result<Html<String>> {
+    if let Some(error) = query.error {
+        return Err(CasterError::Auth(format!(
+            "Keycloak refused the login: {}",
+            query.error_description.unwrap_or(error)
+        )));
+    }
+    let (Some(code), Some(login_state)) = (query.code, query.state) else {
+        return Err(CasterError::InvalidRequest("Callback needs code and state".into()));
+    };
+
+    let (token, _) = state.keycloak_auth.exchange_code(&code, &login_state).await?;
+    Ok(Html(format!(
+        "<!DOCTYPE html><script>localStorage.setItem('q8_caster_token', {}); location.replace('/');</script>",
+        serde_json::to_string(&token)?
+    )))
+}
+
+/// End the bearer token's session
+pub async fn logout_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>> {
+    let claims = claims_from_headers(&state, &headers).await?;
+    // The token no longer works here even if Keycloak can't be told
+    if let Err(e) = state.keycloak_auth.logout(&claims).await {
+        warn!("Failed to end Keycloak session for {}: {}", claims.sub, e);
+    }
+    Ok(Json(json!({"success": true})))
+}
+
+/// Who the bearer token belongs to, and their roles
+pub async fn userinfo_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>> {
+    let claims = claims_from_headers(&state, &headers).await?;
+    Ok(Json(json!({
+        "user": {
+            "sub": claims.sub,
+            "preferred_username": claims.preferred_username,
+            "email": claims.email,
+            "name": claims.name,
+            "roles": claims.roles(),
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn keycloak() -> KeycloakAuth {
+        KeycloakAuth::new(KeycloakConfig {
+            server_url: "http://127.0.0.1:9".into(),
+            realm: "q8".into(),
+            client_id: "q8-caster".into(),
+            client_secret: None,
+            redirect_uri: "http://localhost:8420/auth/callback".into(),
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn pending_logins_are_capped_oldest_first() {
+        let keycloak = keycloak().await;
+        for login in 0..MAX_PENDING_LOGINS + 5 {
+            keycloak.remember_login(format!("state-{}", login), "verifier".into());
+        }
+
+        assert_eq!(keycloak.pending.len(), MAX_PENDING_LOGINS);
+        assert!(!keycloak.pending.contains_key("state-0"));
+        assert!(!keycloak.pending.contains_key("state-4"));
+        assert!(keycloak.pending.contains_key("state-5"));
+        assert!(keycloak.pending.contains_key(&format!("state-{}", MAX_PENDING_LOGINS + 4)));
+
+        // A dropped login's callback is refused before Keycloak is asked
+        let dropped = keycloak.exchange_code("code", "state-0").await.unwrap_err();
+        assert!(matches!(dropped, CasterError::Auth(_)), "{}", dropped);
+    }
+}
@@ -1,35 +1,108 @@
-use crate::Result;
-
-/// Placeholder for secrets management functionality.
-/// 
-/// TODO: Implement secure storage and retrieval of secrets such as:
-/// - API keys and tokens
-/// - Database credentials
-/// - Encryption keys
-/// - OAuth client secrets
+use secrecy::SecretString;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::{CasterError, Result};
+
+pub mod keycloak;
+mod store;
+
+pub use keycloak::{KeycloakAuth, KeycloakConfig, KeycloakSettings};
+use store::SecretStore;
+
+/// Username/password pair for an RTSP camera
+#[derive(Debug, Clone)]
+pub struct RtspCredential {
+    pub username: String,
+    pub password: SecretString,
+}
+
+/// Stores API keys, camera credentials, and OIDC client secrets.
+///
+/// Backed by the OS keyring when one is reachable, otherwise by an
+/// encrypted file in the app's config directory. Values are held as
+/// `SecretString` so they stay out of logs and debug output.
 pub struct SecretsManager {
-    // Placeholder for secrets management
+    store: Arc<SecretStore>,
+    keycloak_config: KeycloakConfig,
 }
 
 impl SecretsManager {
-    pub fn new() -> Result<Self> {
-        Ok(Self {})
+    pub async fn new(keycloak: &KeycloakSettings) -> Result<Self> {
+        // A shared temp directory is no place for secrets, even encrypted ones
+        let dir = directories::ProjectDirs::from("is", "8b", "q8-caster")
+            .map(|dirs| dirs.config_dir().to_path_buf())
+            .ok_or_else(|| CasterError::Secrets("No config directory to keep secrets in; is HOME set?".into()))?;
+
+        Self::with_dir(dir, keycloak).await
     }
-}
 
-/// Placeholder for Keycloak authentication integration.
-/// 
-/// TODO: Implement Keycloak OpenID Connect authentication:
-/// - User authentication flow
-/// - Token management and refresh
-/// - Role-based access control
-/// - Session management
-pub struct KeycloakAuth {
-    // Placeholder for Keycloak authentication
-}
+    /// Open the store, using `dir` for the encrypted-file fallback
+    pub async fn with_dir(dir: PathBuf, keycloak: &KeycloakSettings) -> Result<Self> {
+        // Keyring backends talk to D-Bus / system services synchronously
+        let store = tokio::task::spawn_blocking(move || SecretStore::open(&dir))
+            .await
+            .map_err(|e| CasterError::Secrets(format!("Secrets store task failed: {}", e)))??;
+        let store = Arc::new(store);
+
+        let client_secret = blocking(&store, |store| store.get("keycloak:client-secret")).await?;
+        let keycloak_config = KeycloakConfig::from_env(keycloak, client_secret);
+
+        Ok(Self {
+            store,
+            keycloak_config,
+        })
+    }
 
-impl KeycloakAuth {
-    pub fn new() -> Result<Self> {
-        Ok(Self {})
+    pub async fn add_api_key(&self, name: String, key: String) -> Result<()> {
+        let key = SecretString::from(key);
+        blocking(&self.store, move |store| store.set(&format!("api-key:{}", name), &key)).await
     }
+
+    pub async fn get_api_key(&self, name: &str) -> Result<Option<SecretString>> {
+        let entry = format!("api-key:{}", name);
+        blocking(&self.store, move |store| store.get(&entry)).await
+    }
+
+    pub async fn add_rtsp_credential(&self, camera_id: String, username: String, password: String) -> Result<()> {
+        let password = SecretString::from(password);
+        blocking(&self.store, move |store| {
+            store.set(&format!("rtsp-user:{}", camera_id), &SecretString::from(username))?;
+            store.set(&format!("rtsp-pass:{}", camera_id), &password)
+        })
+        .await
+    }
+
+    pub async fn get_rtsp_credential(&self, camera_id: &str) -> Result<Option<RtspCredential>> {
+        let camera_id = camera_id.to_string();
+        blocking(&self.store, move |store| {
+            let username = store.get(&format!("rtsp-user:{}", camera_id))?;
+            let password = store.get(&format!("rtsp-pass:{}", camera_id))?;
+
+            Ok(match (username, password) {
+                (Some(username), Some(password)) => Some(RtspCredential {
+                    username: secrecy::ExposeSecret::expose_secret(&username).to_string(),
+                    password,
+                }),
+                _ => None,
+            })
+        })
+        .await
+    }
+
+    pub fn get_keycloak_config(&self) -> &KeycloakConfig {
+        &self.keycloak_config
+    }
+}
+
+/// Run a store operation off the async runtime
+async fn blocking<T, F>(store: &Arc<SecretStore>, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&SecretStore) -> Result<T> + Send + 'static,
+{
+    let store = Arc::clone(store);
+    tokio::task::spawn_blocking(move || f(&store))
+        .await
+        .map_err(|e| CasterError::Secrets(format!("Secrets store task failed: {}", e)))?
 }
@@ -0,0 +1,256 @@
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use secrecy::{ExposeSecret, SecretString};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::{CasterError, Result};
+
+/// Keyring service name all entries are filed under
+const KEYRING_SERVICE: &str = "q8-caster";
+
+/// Where secrets actually live
+pub enum SecretStore {
+    /// OS credential store (Keychain, Credential Manager, Secret Service)
+    Keyring,
+    /// AES-256-GCM encrypted file, for hosts without a usable keyring. Its key
+    /// sits beside it, so this only hides secrets from a casual look.
+    File(Box<EncryptedFileStore>),
+}
+
+impl SecretStore {
+    /// Use the OS keyring if it answers, otherwise fall back to an encrypted file in `dir`
+    pub fn open(dir: &Path) -> Result<Self> {
+        if keyring_available() {
+            info!("Storing secrets in the OS keyring");
+            return Ok(SecretStore::Keyring);
+        }
+
+        warn!(
+            "OS keyring unavailable, storing secrets under {} in a file encrypted with a key kept beside it; \
+             anyone who can read that directory can read the secrets",
+            dir.display()
+        );
+        Ok(SecretStore::File(Box::new(EncryptedFileStore::open(dir)?)))
+    }
+
+    pub fn set(&self, name: &str, value: &SecretString) -> Result<()> {
+        match self {
+            SecretStore::Keyring => keyring_entry(name)?
+                .set_password(value.expose_secret())
+                .map_err(|e| CasterError::Secrets(format!("Failed to store {} in keyring: {}", name, e))),
+            SecretStore::File(store) => store.set(name, value),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<SecretString>> {
+        match self {
+            SecretStore::Keyring => match keyring_entry(name)?.get_password() {
+                Ok(value) => Ok(Some(SecretString::from(value))),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(CasterError::Secrets(format!("Failed to read {} from keyring: {}", name, e))),
+            },
+            SecretStore::File(store) => Ok(store.get(name)),
+        }
+    }
+}
+
+fn keyring_entry(name: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, name)
+        .map_err(|e| CasterError::Secrets(format!("Invalid keyring entry {}: {}", name, e)))
+}
+
+/// Probe the keyring with a lookup; a missing entry still proves the store works
+fn keyring_available() -> bool {
+    match keyring_entry("__probe__").map(|entry| entry.get_password()) {
+        Ok(Ok(_)) | Ok(Err(keyring::Error::NoEntry)) => true,
+        Ok(Err(e)) => {
+            warn!("OS keyring probe failed: {}", e);
+            false
+        }
+        Err(_) => false,
+    }
+}
+
+/// Secrets kept as one encrypted JSON map: `nonce || ciphertext`.
+///
+/// The key is generated on first use and kept beside the data, in `secrets.key`,
+/// with owner-only permissions. Anyone who can read the directory has both, so
+/// this is obfuscation against a casual look at the file, not protection at rest;
+/// the file permissions are what actually keep other users out.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    key: LessSafeKey,
+    rng: SystemRandom,
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl EncryptedFileStore {
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+
+        let rng = SystemRandom::new();
+        let key = load_or_create_key(&dir.join("secrets.key"), &rng)?;
+        let path = dir.join("secrets.enc");
+
+        let entries = if path.exists() {
+            let sealed = std::fs::read(&path)?;
+            let plaintext = open_sealed(&key, sealed)?;
+            serde_json::from_slice(&plaintext)?
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self {
+            path,
+            key,
+            rng,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    fn get(&self, name: &str) -> Option<SecretString> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(name).map(|value| SecretString::from(value.clone()))
+    }
+
+    /// Write the updated map to disk first, so a failed write leaves memory as it was
+    fn set(&self, name: &str, value: &SecretString) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let mut updated = entries.clone();
+        updated.insert(name.to_string(), value.expose_secret().to_string());
+
+        let plaintext = serde_json::to_vec(&updated)?;
+        let sealed = self.seal(plaintext)?;
+        write_private(&self.path, &sealed)?;
+        *entries = updated;
+        Ok(())
+    }
+
+    fn seal(&self, mut data: Vec<u8>) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| CasterError::Secrets("Failed to generate nonce".into()))?;
+
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .map_err(|_| CasterError::Secrets("Failed to encrypt secrets file".into()))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&data);
+        Ok(sealed)
+    }
+}
+
+fn open_sealed(key: &LessSafeKey, mut sealed: Vec<u8>) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(CasterError::Secrets("Secrets file is truncated".into()));
+    }
+
+    let mut ciphertext = sealed.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&sealed)
+        .map_err(|_| CasterError::Secrets("Secrets file has an invalid nonce".into()))?;
+
+    let plaintext = key
+        .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+        .map_err(|_| CasterError::Secrets("Failed to decrypt secrets file; wrong key or corrupted data".into()))?;
+    Ok(plaintext.to_vec())
+}
+
+fn load_or_create_key(path: &Path, rng: &SystemRandom) -> Result<LessSafeKey> {
+    let bytes = if path.exists() {
+        std::fs::read(path)?
+    } else {
+        let mut bytes = vec![0u8; AES_256_GCM.key_len()];
+        rng.fill(&mut bytes)
+            .map_err(|_| CasterError::Secrets("Failed to generate secrets key".into()))?;
+        write_private(path, &bytes)?;
+        bytes
+    };
+
+    let key = UnboundKey::new(&AES_256_GCM, &bytes)
+        .map_err(|_| CasterError::Secrets(format!("Invalid secrets key in {}", path.display())))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Write a file readable only by the current user. The data goes to a sibling
+/// temp file that is synced and then renamed over `path`, so a crash mid-write
+/// leaves the old file rather than a corrupt one.
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    let mut temp_name = path.file_name()
+        .ok_or_else(|| CasterError::Secrets(format!("Not a file path: {}", path.display())))?
+        .to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    use std::io::Write;
+    let written = options.open(&temp_path).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| std::fs::rename(&temp_path, path)) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    // The rename itself only lasts once the directory is synced
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_private_replaces_the_file_without_leaving_a_temp_file() {
+        let dir = std::env::temp_dir().join(format!("q8-caster-secrets-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("secrets.enc");
+
+        write_private(&path, b"first").unwrap();
+        write_private(&path, b"second").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert!(!dir.join("secrets.enc.tmp").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_failed_write_leaves_the_stored_secrets_as_they_were() {
+        let dir = std::env::temp_dir().join(format!("q8-caster-secrets-test-{}", uuid::Uuid::new_v4()));
+        let mut store = EncryptedFileStore::open(&dir).unwrap();
+        store.set("kept", &SecretString::from("before")).unwrap();
+
+        // Writes now fail: the file's directory is gone
+        store.path = dir.join("missing").join("secrets.enc");
+        assert!(store.set("kept", &SecretString::from("after")).is_err());
+        assert!(store.set("new", &SecretString::from("value")).is_err());
+
+        assert_eq!(store.get("kept").unwrap().expose_secret(), "before");
+        assert!(store.get("new").is_none());
+        let reopened = EncryptedFileStore::open(&dir).unwrap();
+        assert_eq!(reopened.get("kept").unwrap().expose_secret(), "before");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,24 +1,86 @@
 use axum::{
-    extract::{State, Path, Json},
+    extract::{State, Path, Query, Json, Request},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
+use std::sync::Arc;
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+use tokio_util::sync::CancellationToken;
 use serde_json::json;
 use tracing::info;
 use uuid::Uuid;
 
+use super::cancel;
+use super::groups::{self, CastTarget};
+use super::live_reload;
+use super::playlist::{self, PlaylistContext, PlaylistControl, PlaylistItem};
+use super::request_log::{record_device, record_session};
 use super::http::AppState;
-use super::sse::{notify_cast_started, notify_cast_stopped, notify_error};
-use crate::{ContentType, ContentSource, StreamProtocol};
-use secrecy::ExposeSecret;
+use super::sse::{notify_cast_started, notify_display_stopped, notify_error, notify_progress, notify_thumbnails_ready};
+use crate::{CasterError, ContentType, ContentSource};
+use crate::display::{Ambient, AmbientConfig, DisplayConfig, WindowCommand, WindowMode};
+use crate::network::{DeviceFilter, DeviceType, DiscoveryConfig, ReceiverConfig, SubtitleTrack};
+
+/// A failed request answers `{"error": ..., "kind": ...}` with a status for the
+/// kind of failure, so clients can tell a bad argument or a missing display
+/// from a device that didn't respond or a real fault
+impl IntoResponse for CasterError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            CasterError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            CasterError::Auth(_) => StatusCode::UNAUTHORIZED,
+            CasterError::NotFound(_) => StatusCode::NOT_FOUND,
+            CasterError::NotCasting(_) | CasterError::Cancelled(_) => StatusCode::CONFLICT,
+            CasterError::Render(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            CasterError::UnsupportedMedia(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            CasterError::NotImplemented(_) => StatusCode::NOT_IMPLEMENTED,
+            CasterError::Network(_) => StatusCode::BAD_GATEWAY,
+            CasterError::Display(_)
+            | CasterError::Media(_)
+            | CasterError::Cache(_)
+            | CasterError::Mcp(_)
+            | CasterError::Secrets(_)
+            | CasterError::Config(_)
+            | CasterError::Io(_)
+            | CasterError::Json(_)
+            | CasterError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(json!({"error": self.to_string(), "kind": self.kind()}))).into_response()
+    }
+}
+
+fn invalid(message: impl std::fmt::Display) -> CasterError {
+    CasterError::InvalidRequest(message.to_string())
+}
+
+/// `payload[field]` as a string, which the request must include
+fn required_str<'a>(payload: &'a serde_json::Value, field: &str) -> Result<&'a str, CasterError> {
+    payload[field].as_str().ok_or_else(|| invalid(format!("Missing {}", field)))
+}
+
+/// The engines a playlist or cast group casts to displays with
+fn playlist_context(state: &AppState) -> PlaylistContext {
+    PlaylistContext {
+        display_manager: Arc::clone(&state.display_manager),
+        media_engine: Arc::clone(&state.media_engine),
+        content_cache: Arc::clone(&state.content_cache),
+    }
+}
+
+/// The bytes of a URL, path or `cache://key` a request names. A path outside
+/// the media directories is a 400, a missing file or key a 404 and a URL that
+/// can't be fetched a 502.
+async fn read_source(state: &AppState, source: &str) -> Result<Vec<u8>, CasterError> {
+    state.content_cache.read().await.read_source(source).await
+}
 
 // Display endpoints
 pub async fn list_displays(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, CasterError> {
     let display_manager = state.display_manager.read().await;
-    let displays = display_manager.list_displays().await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let displays = display_manager.list_displays().await?;
     
     Ok(Json(json!({
         "displays": displays
@@ -29,19 +91,114 @@ pub async fn cast_content(
     State(state): State<AppState>,
     Path(display_id): Path<String>,
     Json(payload): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let content_type = payload["content_type"].as_str().unwrap_or("");
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let (mut content_type, content_source) = groups::parse_content(&payload)?;
+    if let ContentType::WebAssembly { sandbox, .. } = &mut content_type {
+        groups::limit_wasm_sandbox(sandbox, &state.wasm_limits, &*state.content_cache.read().await)?;
+    }
     let source = payload["source"].as_str().unwrap_or("");
     let options = &payload["options"];
-    
-    info!("Casting {} to display {}", content_type, display_id);
-    
-    // Create session
+    let label = groups::content_label(&content_type);
+
+    info!("Casting {} to display {}", label, display_id);
+
+    // Markdown, images, PDFs, presentations, screen mirrors and WebAssembly frames are drawn by a cast window on the display itself.
+    // Video and audio are played by the media engine, with the window showing controls.
+    let plays = matches!(content_type, ContentType::Video { .. } | ContentType::Audio { .. });
+    let mirrors = matches!(content_type, ContentType::ScreenMirror { .. });
+    let windowed = matches!(
+        content_type,
+        ContentType::Markdown { .. }
+            | ContentType::Image { .. }
+            | ContentType::Pdf { .. }
+            | ContentType::Presentation { .. }
+            | ContentType::WebAssembly { .. }
+    );
+    // Nothing draws 3D models or streams on a display yet
+    if !(plays || mirrors || windowed) {
+        let what = if matches!(content_type, ContentType::Model3D { .. }) { "3D models" } else { "streams" };
+        return Err(CasterError::NotImplemented(format!("Casting {} to a display is not supported yet", what)));
+    }
+
+    // Markdown files can be recast on every save
+    let watch_path = if groups::option_bool(options, "watch")?.unwrap_or(false) {
+        Some(live_reload::validate(&content_type, &content_source)?)
+    } else {
+        None
+    };
+    let window_mode = options["window_mode"].as_str()
+        .map(str::parse::<WindowMode>)
+        .transpose()
+        .map_err(invalid)?;
+
+    // A cast by hand takes over from any playlist, watched file or earlier session
+    playlist::stop(&display_id);
+    live_reload::stop(&display_id);
+    cancel::cancel_display(&display_id);
+
+    let data = if plays || mirrors {
+        Vec::new()
+    } else {
+        read_source(&state, source).await?
+    };
+    // A PDF's overview sheet is laid out from the same bytes
+    let pdf_data = matches!(content_type, ContentType::Pdf { .. }).then(|| data.clone());
+    // Checked before a window opens for it
+    let uri = match plays {
+        true => Some(crate::media::playback_uri(&*state.content_cache.read().await, source)?),
+        false => None,
+    };
+
+    let mut display_manager = state.display_manager.write().await;
+    let displays = display_manager.list_displays().await?;
+    let Some(display) = displays.into_iter().find(|d| d.id == display_id) else {
+        return Err(CasterError::NotFound(format!("Display not found: {}", display_id)));
+    };
+
+    let window = match display_manager.create_window(&display_id, window_mode).await {
+        Ok(window) => window,
+        Err(e) => {
+            notify_error(format!("Failed to open a window on {}: {}", display_id, e));
+            return Err(e);
+        }
+    };
+    window.send(WindowCommand::SetContent { content_type: content_type.clone(), data, title: Some(groups::source_title(source)) })?;
+    drop(display_manager);
+
+    if let Some(uri) = uri {
+        match state.media_engine.write().await.play_uri_on(&uri, Some(&display)) {
+            Ok(Some(feeds)) => feeds.attach(&window)?,
+            Ok(None) => {}
+            Err(e) => {
+                notify_error(format!("Failed to play {} on {}: {}", source, display_id, e));
+                return Err(e);
+            }
+        }
+    }
+
+    // Until it's stopped or replaced, so a stop with its session id can find it
     let session_id = Uuid::new_v4().to_string();
-    
+    record_session(&session_id);
+    let cancel = cancel::register(&display_id, &session_id);
+
     // Notify via SSE
-    notify_cast_started(display_id.clone(), content_type.to_string(), session_id.clone());
-    
+    notify_cast_started(display_id.clone(), label, session_id.clone());
+
+    if let Some(path) = watch_path {
+        live_reload::start(
+            display_id.clone(),
+            session_id.clone(),
+            path,
+            options["theme"].as_str().map(str::to_string),
+            options["math"].as_bool().unwrap_or(false),
+            Arc::clone(&state.display_manager),
+        );
+    }
+
+    if let Some(data) = pdf_data {
+        spawn_thumbnail_sheet(&state, data, content_source, options, display_id.clone(), session_id.clone(), cancel);
+    }
+
     Ok(Json(json!({
         "success": true,
         "session_id": session_id,
@@ -49,43 +206,218 @@ pub async fn cast_content(
     })))
 }
 
+/// Lay out a cast PDF's overview sheet in the background, reporting each page
+/// as it fills in, then store it in the cache and announce its key. Stopping
+/// the cast abandons the sheet between pages.
+fn spawn_thumbnail_sheet(
+    state: &AppState,
+    data: Vec<u8>,
+    source: ContentSource,
+    options: &serde_json::Value,
+    display_id: String,
+    session_id: String,
+    cancel: CancellationToken,
+) {
+    let cols = options["thumbnail_columns"].as_u64().unwrap_or(4) as u32;
+    let thumb_width = options["thumbnail_width"].as_u64().unwrap_or(200) as u32;
+    let render_engine = Arc::clone(&state.render_engine);
+    let content_cache = Arc::clone(&state.content_cache);
+
+    tokio::spawn(async move {
+        let pdf = render_engine.read().await.pdf_pool();
+        let sheet = pdf.render_thumbnail_sheet(data, cols, thumb_width, {
+            let session_id = session_id.clone();
+            let display_id = display_id.clone();
+            move |progress| notify_progress(session_id.clone(), display_id.clone(), progress, "rendering_thumbnails".into())
+        }, cancel).await;
+
+        let stored = match sheet.and_then(|sheet| crate::render::encode_png(&sheet)) {
+            Ok(png) => {
+                let cache = content_cache.read().await;
+                cache.store(ContentType::Image { format: "png".into() }, source, png, "image/png".into()).await
+            }
+            Err(e) => Err(e),
+        };
+        match stored {
+            Ok(key) => notify_thumbnails_ready(display_id, session_id, key),
+            Err(CasterError::Cancelled(reason)) => info!("PDF thumbnails for {} cancelled: {}", session_id, reason),
+            Err(e) => notify_error(format!("Failed to render PDF thumbnails: {}", e)),
+        }
+    });
+}
+
+#[derive(serde::Deserialize)]
+pub struct StopCastQuery {
+    /// Cancel only this session's work rather than everything on the display
+    session_id: Option<String>,
+}
+
 pub async fn stop_cast(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
     Path(display_id): Path<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+    Query(query): Query<StopCastQuery>,
+) -> Result<Json<serde_json::Value>, CasterError> {
     info!("Stopping cast on display {}", display_id);
-    
-    // TODO: Get actual session ID
-    let session_id = "mock-session";
-    
-    notify_cast_stopped(display_id.clone(), session_id.to_string());
+    if let Some(ref session_id) = query.session_id {
+        record_session(session_id);
+    }
+
+    playlist::stop(&display_id);
+    live_reload::stop(&display_id);
+    let cancelled = match query.session_id {
+        Some(ref session_id) if cancel::cancel_session(session_id) => vec![session_id.clone()],
+        Some(_) => Vec::new(),
+        None => cancel::cancel_display(&display_id),
+    };
+    state.display_manager.read().await.close_window(&display_id)?;
+
+    notify_display_stopped(display_id.clone(), query.session_id);
     
     Ok(Json(json!({
         "success": true,
-        "display_id": display_id
+        "display_id": display_id,
+        "cancelled": cancelled
     })))
 }
 
+pub async fn control_display(
+    State(state): State<AppState>,
+    Path(display_id): Path<String>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let action = payload["action"].as_str().unwrap_or("");
+    let value = payload["value"].as_f64();
+
+    info!("Controlling display {} - action: {}", display_id, action);
+
+    // Next and previous move a running playlist on; otherwise they turn the slide or page being shown
+    let has_playlist = playlist::status(&display_id).is_some();
+    let playlist_command = match (action, payload["value"].as_u64()) {
+        ("next", _) if has_playlist => Some(PlaylistControl::Next),
+        ("previous", _) if has_playlist => Some(PlaylistControl::Previous),
+        ("jump", Some(index)) => Some(PlaylistControl::Jump(index as usize)),
+        ("jump", None) => return Err(invalid("jump needs an item index as its value")),
+        _ => None,
+    };
+    if let Some(command) = playlist_command {
+        playlist::control(&display_id, command)?;
+        return Ok(Json(json!({"success": true, "action": action, "display_id": display_id})));
+    }
+
+    let command = match (action, value) {
+        ("window_mode", _) => match payload["value"].as_str().map(str::parse::<WindowMode>) {
+            Some(Ok(mode)) => WindowCommand::SetWindowMode(mode),
+            _ => return Err(invalid("window_mode needs a window mode as its value")),
+        },
+        ("play", _) => WindowCommand::Play,
+        ("pause", _) => WindowCommand::Pause,
+        ("seek", Some(value)) => WindowCommand::Seek(value as f32),
+        ("volume", Some(value)) => WindowCommand::SetVolume(value as f32),
+        ("pdf_page", Some(value)) if value >= 1.0 => WindowCommand::PdfPage(value as u32),
+        ("next", _) => WindowCommand::NextSlide,
+        ("previous", _) => WindowCommand::PreviousSlide,
+        ("slide", Some(value)) if value >= 1.0 => WindowCommand::GoToSlide(value as u32),
+        ("seek" | "volume" | "pdf_page" | "slide", _) => return Err(invalid(format!("{} needs a number as its value", action))),
+        _ => return Err(invalid(format!("Unknown action: {}", action))),
+    };
+
+    let display_manager = state.display_manager.read().await;
+    let displays = display_manager.list_displays().await?;
+    if !displays.iter().any(|d| d.id == display_id) {
+        return Err(CasterError::NotFound(format!("Display not found: {}", display_id)));
+    }
+
+    display_manager.send_to_window(&display_id, command)?;
+    Ok(Json(json!({"success": true, "action": action, "display_id": display_id})))
+}
+
 pub async fn configure_display(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
     Path(display_id): Path<String>,
     Json(config): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, CasterError> {
     info!("Configuring display {}: {:?}", display_id, config);
+
+    let config: DisplayConfig = serde_json::from_value(config)
+        .map_err(|e| invalid(format!("Invalid display config: {}", e)))?;
+
+    let mut display_manager = state.display_manager.write().await;
+    // A mode the display lacks or a self-mirror is a 400; xrandr itself failing is a 500
+    if let Err(e) = display_manager.configure_display(&display_id, config).await {
+        notify_error(format!("Failed to configure display {}: {}", display_id, e));
+        return Err(e);
+    }
+
+    let display = display_manager.list_displays().await?
+        .into_iter()
+        .find(|d| d.id == display_id);
     
     Ok(Json(json!({
         "success": true,
-        "display_id": display_id
+        "display_id": display_id,
+        "display": display
+    })))
+}
+
+/// Cast a list of items to a display one after another
+pub async fn cast_playlist(
+    State(state): State<AppState>,
+    Path(display_id): Path<String>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let items: Vec<PlaylistItem> = serde_json::from_value(payload["items"].clone())
+        .map_err(|e| invalid(format!("Invalid items: {}", e)))?;
+    let looping = payload["loop"].as_bool().unwrap_or(false);
+
+    info!("Casting a playlist of {} items to display {}", items.len(), display_id);
+
+    let displays = state.display_manager.read().await.list_displays().await?;
+    let Some(display) = displays.into_iter().find(|d| d.id == display_id) else {
+        return Err(CasterError::NotFound(format!("Display not found: {}", display_id)));
+    };
+
+    let context = playlist_context(&state);
+    let playlist = playlist::start(context, display, items, looping).await?;
+    Ok(Json(json!({
+        "success": true,
+        "display_id": display_id,
+        "playlist": playlist
+    })))
+}
+
+/// Set what a display shows while idle, opening its window so the screen can appear
+pub async fn set_ambient(
+    State(state): State<AppState>,
+    Path(display_id): Path<String>,
+    Json(config): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    info!("Setting ambient screen on display {}: {:?}", display_id, config);
+
+    let config: AmbientConfig = serde_json::from_value(config)
+        .map_err(|e| invalid(format!("Invalid ambient config: {}", e)))?;
+    let ambient = Ambient::load(config, &*state.content_cache.read().await).await;
+    let images = ambient.images.len();
+
+    let mut display_manager = state.display_manager.write().await;
+    let result = match display_manager.create_window(&display_id, None).await {
+        Ok(_) => display_manager.set_ambient(Some(&display_id), ambient),
+        Err(e) => Err(e),
+    };
+    result?;
+
+    Ok(Json(json!({
+        "success": true,
+        "display_id": display_id,
+        "images": images
     })))
 }
 
 // Media endpoints
 pub async fn list_codecs(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, CasterError> {
     let media_engine = state.media_engine.read().await;
-    let codecs = media_engine.list_codecs()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let codecs = media_engine.list_codecs()?;
     
     Ok(Json(json!({
         "codecs": codecs
@@ -94,10 +426,9 @@ pub async fn list_codecs(
 
 pub async fn list_audio_devices(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, CasterError> {
     let media_engine = state.media_engine.read().await;
-    let devices = media_engine.list_audio_devices()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let devices = media_engine.list_audio_devices()?;
     
     Ok(Json(json!({
         "audio_devices": devices
@@ -107,10 +438,16 @@ pub async fn list_audio_devices(
 // Chromecast endpoints
 pub async fn discover_chromecasts(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, CasterError> {
     let mut network_receiver = state.network_receiver.write().await;
-    let devices = network_receiver.discover_chromecasts().await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let devices: Vec<serde_json::Value> = network_receiver.discover_chromecasts().await?
+        .iter()
+        .map(|device| {
+            let mut json = device.to_json();
+            json["connected"] = json!(network_receiver.is_chromecast_connected(&device.id));
+            json
+        })
+        .collect();
     
     Ok(Json(json!({
         "devices": devices
@@ -120,7 +457,8 @@ pub async fn discover_chromecasts(
 pub async fn connect_chromecast(
     State(state): State<AppState>,
     Path(device_name): Path<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, CasterError> {
+    record_device(&device_name);
     info!("Connecting to Chromecast: {}", device_name);
     
     let mut network_receiver = state.network_receiver.write().await;
@@ -131,7 +469,7 @@ pub async fn connect_chromecast(
         }))),
         Err(e) => {
             notify_error(format!("Failed to connect to {}: {}", device_name, e));
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(e)
         }
     }
 }
@@ -140,67 +478,241 @@ pub async fn cast_to_chromecast(
     State(state): State<AppState>,
     Path(device_name): Path<String>,
     Json(payload): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, CasterError> {
+    record_device(&device_name);
     let content_type = payload["content_type"].as_str().unwrap_or("");
     let source = payload["source"].as_str().unwrap_or("");
+    let options = &payload["options"];
     
     info!("Casting to Chromecast {} - type: {}", device_name, content_type);
+
+    if content_type == "markdown" {
+        return cast_markdown_to_chromecast(&state, &device_name, source, options).await;
+    }
     
-    // TODO: Convert JSON to proper types
-    
-    Ok(Json(json!({
-        "success": true,
-        "device_name": device_name
-    })))
+    let (content_type, content_source) = groups::parse_device_content(&payload)?;
+
+    let subtitles: Vec<SubtitleTrack> = if payload["subtitles"].is_null() {
+        Vec::new()
+    } else {
+        serde_json::from_value(payload["subtitles"].clone())
+            .map_err(|e| invalid(format!("Invalid subtitles: {}", e)))?
+    };
+
+    let mut network_receiver = state.network_receiver.write().await;
+    match network_receiver.cast_to_chromecast(&device_name, &content_type, &content_source, &subtitles).await {
+        Ok(_) => Ok(Json(json!({
+            "success": true,
+            "device_name": device_name
+        }))),
+        Err(e) => {
+            notify_error(format!("Failed to cast to {}: {}", device_name, e));
+            Err(e)
+        }
+    }
+}
+
+/// Markdown goes to a Chromecast as a slideshow of rendered PNG pages
+async fn cast_markdown_to_chromecast(
+    state: &AppState,
+    device_name: &str,
+    source: &str,
+    options: &serde_json::Value,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let data = read_source(state, source).await?;
+    let markdown = String::from_utf8_lossy(&data).into_owned();
+    let theme = options["theme"].as_str().map(str::to_string);
+    let width = options["width"].as_u64().unwrap_or(1920) as u32;
+    let height = options["height"].as_u64().unwrap_or(1080) as u32;
+
+    let pages = tokio::task::spawn_blocking(move || crate::render::write_markdown_pages(&markdown, theme.as_deref(), width, height)).await
+        .map_err(|e| CasterError::Unknown(format!("Markdown rendering task failed: {}", e)))?
+        .inspect_err(|e| notify_error(format!("Failed to render markdown for {}: {}", device_name, e)))?;
+
+    let items = pages.iter()
+        .map(|path| (
+            ContentType::Image { format: "png".to_string() },
+            ContentSource::File { path: path.to_string_lossy().into_owned() },
+        ))
+        .collect();
+    match state.network_receiver.write().await.play_chromecast_queue(device_name, items).await {
+        Ok(count) => Ok(Json(json!({
+            "success": true,
+            "device_name": device_name,
+            "pages": count
+        }))),
+        Err(e) => {
+            notify_error(format!("Failed to cast to {}: {}", device_name, e));
+            Err(e)
+        }
+    }
 }
 
 pub async fn control_chromecast(
     State(state): State<AppState>,
     Path(device_name): Path<String>,
     Json(payload): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, CasterError> {
+    record_device(&device_name);
     let action = payload["action"].as_str().unwrap_or("");
+    let value = payload["value"].as_f64();
     
     info!("Controlling Chromecast {} - action: {}", device_name, action);
     
-    match action {
+    let result = match action {
         "stop" => {
             let mut network_receiver = state.network_receiver.write().await;
-            match network_receiver.stop_chromecast(&device_name).await {
-                Ok(_) => Ok(Json(json!({"success": true}))),
-                Err(e) => {
-                    notify_error(format!("Failed to stop {}: {}", device_name, e));
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
+            network_receiver.stop_chromecast(&device_name).await
+        },
+        "play" => state.network_receiver.read().await.play_chromecast(&device_name).await,
+        "pause" => state.network_receiver.read().await.pause_chromecast(&device_name).await,
+        "seek" | "volume" => {
+            let Some(value) = value else {
+                return Err(invalid(format!("{} needs a number as its value", action)));
+            };
+
+            let network_receiver = state.network_receiver.read().await;
+            if action == "seek" {
+                network_receiver.seek_chromecast(&device_name, value as f32).await
+            } else {
+                network_receiver.set_chromecast_volume(&device_name, value as f32).await
             }
         },
-        _ => Ok(Json(json!({
-            "success": false,
-            "error": format!("Unknown action: {}", action)
-        })))
+        _ => return Err(invalid(format!("Unknown action: {}", action))),
+    };
+
+    match result {
+        Ok(_) => Ok(Json(json!({"success": true, "action": action}))),
+        // Nothing playing is the client's mistake, answered with a 409
+        Err(e @ CasterError::NotCasting(_)) => Err(e),
+        Err(e) => {
+            notify_error(format!("Failed to {} {}: {}", action, device_name, e));
+            Err(e)
+        }
     }
 }
 
+pub async fn chromecast_queue(
+    State(state): State<AppState>,
+    Path(device_name): Path<String>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    record_device(&device_name);
+    let action = payload["action"].as_str().unwrap_or("");
+
+    info!("Chromecast queue on {} - action: {}", device_name, action);
+
+    let mut network_receiver = state.network_receiver.write().await;
+    let result = match action {
+        "enqueue" => {
+            let Some(items) = payload["items"].as_array() else {
+                return Err(invalid("enqueue needs a list of items"));
+            };
+            let queue = items.iter()
+                .enumerate()
+                .map(|(index, item)| groups::parse_device_content(item).map_err(|e| invalid(format!("Item {}: {}", index, e))))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            network_receiver.enqueue_chromecast(&device_name, queue).await
+                .map(|length| json!({"success": true, "queue_length": length}))
+        },
+        "next" => network_receiver.chromecast_queue_next(&device_name).await
+            .map(|position| json!({"success": true, "position": position})),
+        "prev" => network_receiver.chromecast_queue_prev(&device_name).await
+            .map(|position| json!({"success": true, "position": position})),
+        "clear" => network_receiver.clear_chromecast_queue(&device_name).await
+            .map(|_| json!({"success": true})),
+        _ => return Err(invalid(format!("Unknown action: {}", action))),
+    };
+
+    match result {
+        Ok(response) => Ok(Json(response)),
+        Err(e @ CasterError::NotCasting(_)) => Err(e),
+        Err(e) => {
+            notify_error(format!("Queue {} on {} failed: {}", action, device_name, e));
+            Err(e)
+        }
+    }
+}
+
+pub async fn cast_group(
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let targets: Vec<CastTarget> = serde_json::from_value(payload["targets"].clone())
+        .map_err(|e| invalid(format!("Invalid targets: {}", e)))?;
+    let (content_type, content_source) = groups::parse_content(&payload)
+        .map_err(invalid)?;
+
+    info!("Casting to group of {} targets", targets.len());
+
+    let (group_id, results) = groups::start_group(
+        &playlist_context(&state),
+        &state.network_receiver,
+        targets,
+        content_type,
+        content_source,
+    ).await;
+
+    Ok(Json(json!({
+        "success": results.iter().any(|result| result["success"] == true),
+        "group_id": group_id,
+        "targets": results
+    })))
+}
+
+pub async fn stop_cast_group(
+    State(state): State<AppState>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    info!("Stopping cast group {}", group_id);
+
+    let results = groups::stop_group(&playlist_context(&state), &state.network_receiver, group_id).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "group_id": group_id,
+        "targets": results
+    })))
+}
+
 // Network receiver
 pub async fn start_receiver(
     State(state): State<AppState>,
     Json(payload): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, CasterError> {
     let protocols = payload["protocols"].as_array()
         .map(|arr| arr.iter()
             .filter_map(|v| v.as_str())
             .map(|s| s.to_string())
             .collect::<Vec<_>>()
-        )
-        .unwrap_or_else(|| vec!["upnp".to_string(), "airplay".to_string()]);
+        );
     
     let port = payload["port"].as_u64().unwrap_or(8420) as u16;
+
+    let mut config = ReceiverConfig::default();
+    if let Some(name) = payload["friendly_name"].as_str() {
+        config.friendly_name = name.to_string();
+    }
     
-    info!("Starting receivers: {:?} on port {}", protocols, port);
+    // Without a discovery section, keep whatever timings are already configured
+    let discovery_config = match payload["discovery"] {
+        serde_json::Value::Null => None,
+        ref discovery => Some(
+            serde_json::from_value::<DiscoveryConfig>(discovery.clone())
+                .map_err(|e| invalid(format!("Invalid discovery config: {}", e)))?
+        ),
+    };
     
     let mut network_receiver = state.network_receiver.write().await;
-    network_receiver.start(protocols, port).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let protocols = protocols.unwrap_or_else(|| network_receiver.default_protocols().to_vec());
+    info!("Starting receivers: {:?} on port {}", protocols, port);
+    
+    if let Some(discovery_config) = discovery_config {
+        network_receiver.set_discovery_config(discovery_config).await
+            .map_err(invalid)?;
+    }
+    network_receiver.start(protocols, port, config).await?;
     
     Ok(Json(json!({
         "success": true,
@@ -208,41 +720,498 @@ pub async fn start_receiver(
     })))
 }
 
+// Device discovery
+pub async fn start_discovery(
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let device_types = payload["device_types"].as_array()
+        .map(|arr| arr.iter()
+            .filter_map(|v| v.as_str())
+            .map(DeviceType::from_name)
+            .collect::<Vec<_>>()
+        )
+        .unwrap_or_default();
+
+    info!("Starting device discovery: {:?}", device_types);
+
+    let mut network_receiver = state.network_receiver.write().await;
+    let started = network_receiver.start_discovery(device_types).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "already_running": !started
+    })))
+}
+
+pub async fn stop_discovery(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let mut network_receiver = state.network_receiver.write().await;
+    network_receiver.stop_discovery().await?;
+
+    Ok(Json(json!({
+        "success": true
+    })))
+}
+
+#[derive(serde::Deserialize)]
+pub struct DeviceQuery {
+    #[serde(rename = "type")]
+    device_type: Option<String>,
+    can_video: Option<bool>,
+    can_mirror: Option<bool>,
+    name_contains: Option<String>,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+pub async fn list_devices(
+    State(state): State<AppState>,
+    Query(query): Query<DeviceQuery>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let network_receiver = state.network_receiver.read().await;
+    let devices = match query.device_type.as_deref() {
+        Some(type_str) => network_receiver.get_discovered_devices_by_type(&DeviceType::from_name(type_str)),
+        None => network_receiver.get_discovered_devices(),
+    };
+
+    let filter = DeviceFilter {
+        can_video: query.can_video,
+        can_mirror: query.can_mirror,
+        name_contains: query.name_contains,
+        offset: query.offset,
+        limit: query.limit,
+    };
+    let (total, devices) = filter.apply(devices);
+    let devices_json: Vec<serde_json::Value> = devices.iter().map(|device| device.to_json()).collect();
+
+    Ok(Json(json!({
+        "success": true,
+        "count": devices.len(),
+        "total": total,
+        "offset": filter.offset,
+        "devices": devices_json
+    })))
+}
+
+pub async fn get_device(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let network_receiver = state.network_receiver.read().await;
+    let device = network_receiver.get_discovered_device(&id)
+        .ok_or_else(|| CasterError::NotFound(format!("Device not found: {}", id)))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "device": device.to_json()
+    })))
+}
+
+pub async fn cast_to_device(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    record_device(&id);
+    let content_type = payload["content_type"].as_str().unwrap_or("");
+
+    info!("Casting to device {} - type: {}", id, content_type);
+
+    let (content_type, content_source) = groups::parse_device_content(&payload)?;
+
+    let mut network_receiver = state.network_receiver.write().await;
+    if network_receiver.get_discovered_device(&id).is_none() {
+        return Err(CasterError::NotFound(format!("Device not found: {}", id)));
+    }
+
+    match network_receiver.cast_to_device(&id, &content_type, &content_source).await {
+        Ok(_) => Ok(Json(json!({
+            "success": true,
+            "device_id": id
+        }))),
+        Err(e) => {
+            notify_error(format!("Failed to cast to {}: {}", id, e));
+            Err(e)
+        }
+    }
+}
+
+pub async fn launch_dial_app(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    record_device(&id);
+    let app_name = required_str(&payload, "app_name")?;
+    let launch_payload = payload["payload"].as_str();
+
+    info!("Launching DIAL app {} on {}", app_name, id);
+
+    let network_receiver = state.network_receiver.read().await;
+    if network_receiver.get_discovered_device(&id).is_none() {
+        return Err(CasterError::NotFound(format!("Device not found: {}", id)));
+    }
+
+    match network_receiver.launch_dial_app(&id, app_name, launch_payload).await {
+        Ok(launch) => Ok(Json(launch.to_json(&id, app_name))),
+        Err(e) => {
+            notify_error(format!("Failed to launch {} on {}: {}", app_name, id, e));
+            Err(e)
+        }
+    }
+}
+
+pub async fn dial_app_state(
+    State(state): State<AppState>,
+    Path((id, app_name)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    record_device(&id);
+    let network_receiver = state.network_receiver.read().await;
+    if network_receiver.get_discovered_device(&id).is_none() {
+        return Err(CasterError::NotFound(format!("Device not found: {}", id)));
+    }
+
+    match network_receiver.dial_app_state(&id, &app_name).await {
+        Ok(app_state) => Ok(Json(json!({
+            "success": true,
+            "device_id": id,
+            "app_name": app_name,
+            "state": app_state
+        }))),
+        Err(e) => {
+            notify_error(format!("Failed to query {} on {}: {}", app_name, id, e));
+            Err(e)
+        }
+    }
+}
+
 // Cache
 pub async fn cache_content(
     State(state): State<AppState>,
     Json(payload): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let key = payload["key"].as_str().unwrap_or("");
-    let source = payload["source"].as_str().unwrap_or("");
-    let ttl = payload["ttl"].as_u64().map(|t| std::time::Duration::from_secs(t));
-    
-    info!("Caching content: {} -> {}", source, key);
-    
-    let mut cache = state.content_cache.write().await;
-    cache.store(key.to_string(), source.to_string(), ttl).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let source = required_str(&payload, "source")?;
+
+    info!("Caching content from {}", source);
+
+    // The cache picks the key, which `cache://<key>` sources then name
+    let key = state.content_cache.read().await.store_source(source).await?;
+
     Ok(Json(json!({
         "success": true,
         "key": key
     })))
 }
 
+/// A cached item's data. Honors a single-range `Range` header with 206 and
+/// `Content-Range`, reading only that part of the file.
+pub async fn get_cached_content(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> Result<Response, CasterError> {
+    use axum::http::header;
+
+    let cache = state.content_cache.read().await;
+    let entry = cache.entry(&key).await?
+        .ok_or_else(|| CasterError::NotFound(format!("No cached content for key {}", key)))?;
+    let length = entry.size;
+
+    let range = match headers.get(header::RANGE).and_then(|value| value.to_str().ok()) {
+        Some(value) => match parse_byte_range(value, length) {
+            Some(range) => Some(range),
+            None => {
+                return Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    [(header::CONTENT_RANGE, format!("bytes */{}", length))],
+                ).into_response());
+            }
+        },
+        None => None,
+    };
+
+    let (start, end) = range.unwrap_or((0, length));
+    let data = cache.get_range(&key, start, end).await?
+        .ok_or_else(|| CasterError::NotFound(format!("No cached content for key {}", key)))?;
+
+    let headers = [
+        (header::CONTENT_TYPE, entry.mime_type),
+        (header::ACCEPT_RANGES, "bytes".to_string()),
+    ];
+    Ok(match range {
+        Some((start, end)) => (
+            StatusCode::PARTIAL_CONTENT,
+            headers,
+            [(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end - 1, length))],
+            data,
+        ).into_response(),
+        None => (headers, data).into_response(),
+    })
+}
+
+/// `bytes=a-b`, `bytes=a-` or `bytes=-n` as a half-open range within `length`;
+/// None if it can't be satisfied. Multiple ranges aren't supported.
+fn parse_byte_range(value: &str, length: usize) -> Option<(usize, usize)> {
+    let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: usize = suffix.parse().ok()?;
+            (length.saturating_sub(suffix), length)
+        }
+        (start, "") => (start.parse().ok()?, length),
+        (start, end) => (start.parse().ok()?, end.parse::<usize>().ok()?.saturating_add(1).min(length)),
+    };
+    (start < end).then_some((start, end))
+}
+
+pub async fn cache_stats(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let cache = state.content_cache.read().await;
+
+    Ok(Json(json!({
+        "stats": cache.stats()
+    })))
+}
+
+// Rendering
+pub async fn extract_pdf_text(
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let source = payload["source"].as_str().unwrap_or("");
+    let page = payload["page"].as_u64().map(|p| p as u32);
+
+    info!("Extracting text from PDF {} (page {:?})", source, page);
+
+    let data = read_source(&state, source).await?;
+
+    let pdf = state.render_engine.read().await.pdf_pool();
+    match pdf.extract_text(data, page).await {
+        Ok(text) => Ok(Json(json!({
+            "page": page,
+            "text": text
+        }))),
+        Err(e) => {
+            notify_error(format!("Failed to extract PDF text: {}", e));
+            Err(e)
+        }
+    }
+}
+
+/// A preview of every page of a PDF, from `source` (a path, URL or `cache://key`) or
+/// base64 `data`. With `"store": true` the PNGs go into the cache and their keys
+/// are returned instead of data URLs.
+pub async fn render_pdf_thumbnails(
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let source = payload["source"].as_str();
+    let max_width = payload["max_width"].as_u64().unwrap_or(200) as u32;
+    let store = payload["store"].as_bool().unwrap_or(false);
+
+    let (data, pdf_source) = match (source, payload["data"].as_str()) {
+        (Some(source), _) => {
+            info!("Rendering page previews of {}", source);
+            let data = read_source(&state, source).await?;
+            (data, groups::content_source(source))
+        }
+        (None, Some(encoded)) => {
+            use base64::Engine as _;
+
+            info!("Rendering page previews of an uploaded PDF");
+            let data = base64::engine::general_purpose::STANDARD.decode(encoded)
+                .map_err(|e| invalid(format!("Invalid base64 data: {}", e)))?;
+            (data.clone(), ContentSource::Memory { data })
+        }
+        (None, None) => return Err(invalid("Either source or data is required")),
+    };
+
+    // A client that hangs up stops the rendering
+    let cancel = CancellationToken::new();
+    let _cancel_on_drop = cancel.clone().drop_guard();
+    let pdf = state.render_engine.read().await.pdf_pool();
+    let pages = pdf.render_all_pages(data, max_width, cancel).await
+        .inspect_err(|e| notify_error(format!("Failed to render PDF pages: {}", e)))?;
+
+    // Encode the pages that rendered, keeping each failure in its page's slot
+    let (images, slots): (Vec<_>, Vec<_>) = pages.into_iter().enumerate()
+        .map(|(index, page)| match page {
+            Ok(image) => (Some(image), Ok(index)),
+            Err(e) => (None, Err(e.to_string())),
+        })
+        .unzip();
+    let images: Vec<_> = images.into_iter().flatten().collect();
+    let dimensions: Vec<_> = images.iter().map(|image| (image.width(), image.height())).collect();
+    let mut encoded = tokio::task::spawn_blocking(move || crate::render::encode_pngs(&images)).await
+        .map_err(|e| CasterError::Unknown(format!("PNG encoding task failed: {}", e)))?
+        .into_iter()
+        .zip(dimensions);
+
+    let cache = state.content_cache.read().await;
+    let mut thumbnails = Vec::with_capacity(slots.len());
+    for (index, slot) in slots.into_iter().enumerate() {
+        let page = index + 1;
+        let png = match slot {
+            Ok(_) => encoded.next().map(|(png, size)| png.map(|png| (png, size))),
+            Err(e) => Some(Err(CasterError::Render(e))),
+        };
+        thumbnails.push(match png {
+            Some(Ok((png, (width, height)))) if store => {
+                match cache.store(ContentType::Image { format: "png".into() }, pdf_source.clone(), png, "image/png".into()).await {
+                    Ok(key) => json!({"page": page, "width": width, "height": height, "key": key}),
+                    Err(e) => json!({"page": page, "error": e.to_string()}),
+                }
+            }
+            Some(Ok((png, (width, height)))) => {
+                use base64::Engine as _;
+                let url = format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png));
+                json!({"page": page, "width": width, "height": height, "data_url": url})
+            }
+            Some(Err(e)) => json!({"page": page, "error": e.to_string()}),
+            None => json!({"page": page, "error": "Page was not encoded"}),
+        });
+    }
+
+    let failed = thumbnails.iter().filter(|thumbnail| thumbnail.get("error").is_some()).count();
+    Ok(Json(json!({
+        "success": failed < thumbnails.len(),
+        "pages": thumbnails.len(),
+        "failed": failed,
+        "thumbnails": thumbnails
+    })))
+}
+
+// Local media published for cast devices. ServeFile handles Range requests
+// (so devices can seek) and picks the Content-Type from the file extension.
+pub async fn serve_media(
+    Path(token): Path<String>,
+    request: Request,
+) -> Response {
+    let Some(path) = crate::network::media_registry().resolve(&token) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match ServeFile::new(path).oneshot(request).await {
+        Ok(response) => response.into_response(),
+        Err(never) => match never {},
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct ScreenCaptureQuery {
+    /// Monitor id or name; the primary monitor if absent
+    display_id: Option<String>,
+    format: Option<String>,
+    scale: Option<f32>,
+    quality: Option<u8>,
+}
+
+/// One frame of a local monitor, as raw image bytes
+pub async fn capture_screen(
+    State(state): State<AppState>,
+    Query(query): Query<ScreenCaptureQuery>,
+) -> Result<Response, CasterError> {
+    let image = state.render_engine.write().await.capture_screen(query.display_id.as_deref())
+        .inspect_err(|e| notify_error(format!("Screen capture failed: {}", e)))?;
+
+    let format = query.format.as_deref().unwrap_or("png");
+    let encoded = crate::render::encode_image(image, format, query.scale.unwrap_or(1.0), query.quality.unwrap_or(85))
+        .map_err(invalid)?;
+
+    Ok(([(axum::http::header::CONTENT_TYPE, encoded.mime_type)], encoded.data).into_response())
+}
+
+/// multipart/x-mixed-replace boundary between MJPEG frames
+const MJPEG_BOUNDARY: &str = "q8-frame";
+
+/// Display id the mirror stream's sessions are registered under, for stop_cast
+pub const MIRROR_STREAM_DISPLAY: &str = "screen_stream";
+
+#[derive(serde::Deserialize)]
+pub struct MirrorStreamQuery {
+    /// Monitor id or name; the primary monitor if absent
+    display_id: Option<String>,
+    quality: Option<crate::MirrorQuality>,
+    fps: Option<u32>,
+}
+
+/// A monitor as a live MJPEG stream, which browsers show in a plain <img>.
+/// Frames are only sent when the screen changes.
+pub async fn mirror_stream(
+    State(state): State<AppState>,
+    Query(query): Query<MirrorStreamQuery>,
+) -> Result<Response, CasterError> {
+    let quality = query.quality.unwrap_or(crate::MirrorQuality::Medium);
+    let (frames, cancel) = state.render_engine.write().await
+        .start_mirror_stream(query.display_id, quality, query.fps)
+        .inspect_err(|e| notify_error(format!("Failed to start mirror stream: {}", e)))?;
+    // A stop_cast naming this session ends the capture, for every client watching it
+    let session_id = Uuid::new_v4().to_string();
+    record_session(&session_id);
+    cancel::register_token(MIRROR_STREAM_DISPLAY, &session_id, cancel);
+    let session = cancel::SessionGuard(session_id.clone());
+
+    // Ends when the stream is stopped and its sender dropped
+    let parts = futures::stream::unfold((frames, session), |(mut frames, session)| async move {
+        loop {
+            frames.changed().await.ok()?;
+            let frame = frames.borrow_and_update().clone();
+            if let Some(jpeg) = frame {
+                let header = format!(
+                    "--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+                    MJPEG_BOUNDARY,
+                    jpeg.len()
+                );
+                let part = [header.as_bytes(), &jpeg[..], &b"\r\n"[..]].concat();
+                return Some((Ok::<_, std::convert::Infallible>(bytes::Bytes::from(part)), (frames, session)));
+            }
+        }
+    });
+
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, format!("multipart/x-mixed-replace; boundary={}", MJPEG_BOUNDARY)),
+            (axum::http::header::HeaderName::from_static("x-session-id"), session_id),
+        ],
+        axum::body::Body::from_stream(parts),
+    ).into_response())
+}
+
+pub async fn stop_mirror_stream(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let stopped = state.render_engine.write().await.stop_mirror_stream();
+
+    Ok(Json(json!({
+        "success": true,
+        "stopped": stopped
+    })))
+}
+
+pub async fn list_monitors() -> Result<Json<serde_json::Value>, CasterError> {
+    let monitors = crate::render::ScreenMirror::list_monitors()?;
+
+    Ok(Json(json!({
+        "success": true,
+        "monitors": monitors
+    })))
+}
+
 // Secrets management endpoints
 pub async fn add_api_key(
     State(state): State<AppState>,
     Json(payload): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let name = payload["name"].as_str()
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    let key = payload["key"].as_str()
-        .ok_or(StatusCode::BAD_REQUEST)?;
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let name = required_str(&payload, "name")?;
+    let key = required_str(&payload, "key")?;
     
     info!("Adding API key: {}", name);
     
-    state.secrets_manager.add_api_key(name.to_string(), key.to_string()).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    state.secrets_manager.add_api_key(name.to_string(), key.to_string()).await?;
     
     Ok(Json(json!({
         "success": true,
@@ -253,13 +1222,10 @@ pub async fn add_api_key(
 pub async fn add_rtsp_credential(
     State(state): State<AppState>,
     Json(payload): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let camera_id = payload["camera_id"].as_str()
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    let username = payload["username"].as_str()
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    let password = payload["password"].as_str()
-        .ok_or(StatusCode::BAD_REQUEST)?;
+) -> Result<Json<serde_json::Value>, CasterError> {
+    let camera_id = required_str(&payload, "camera_id")?;
+    let username = required_str(&payload, "username")?;
+    let password = required_str(&payload, "password")?;
     
     info!("Adding RTSP credential for camera: {}", camera_id);
     
@@ -267,11 +1233,51 @@ pub async fn add_rtsp_credential(
         camera_id.to_string(),
         username.to_string(),
         password.to_string()
-    ).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    ).await?;
     
     Ok(Json(json!({
         "success": true,
         "camera_id": camera_id
     })))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn state() -> AppState {
+        crate::server::http::HttpServer::new(crate::server::config::ServerConfig::default()).await.unwrap().state()
+    }
+
+    #[tokio::test]
+    async fn casts_nothing_can_show_are_refused_before_a_session_starts() {
+        let state = state().await;
+        let cast = |payload: serde_json::Value| cast_content(State(state.clone()), Path("display_0".to_string()), Json(payload));
+
+        for (payload, status) in [
+            (json!({"content_type": "model3d", "source": "scene.gltf"}), StatusCode::NOT_IMPLEMENTED),
+            (json!({"content_type": "stream", "source": "rtsp://camera/live"}), StatusCode::NOT_IMPLEMENTED),
+            (json!({"content_type": "hologram", "source": "a.holo"}), StatusCode::BAD_REQUEST),
+            (json!({"content_type": "", "source": "a.png"}), StatusCode::BAD_REQUEST),
+            // Held to the server's [wasm] limits, which keep modules off the network
+            (json!({"content_type": "webassembly", "source": "clock.wasm", "options": {"allow_net": true}}), StatusCode::BAD_REQUEST),
+            (json!({"source": "a.png"}), StatusCode::BAD_REQUEST),
+        ] {
+            let refused = cast(payload.clone()).await.unwrap_err();
+            assert_eq!(refused.into_response().status(), status, "{}", payload);
+        }
+    }
+
+    #[test]
+    fn error_kinds_answer_with_their_status() {
+        for (error, status) in [
+            (CasterError::InvalidRequest("bad".into()), StatusCode::BAD_REQUEST),
+            (CasterError::NotFound("gone".into()), StatusCode::NOT_FOUND),
+            (CasterError::NotCasting("idle".into()), StatusCode::CONFLICT),
+            (CasterError::UnsupportedMedia("exe".into()), StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            (CasterError::Network("timeout".into()), StatusCode::BAD_GATEWAY),
+            (CasterError::Display("Failed to run xrandr".into()), StatusCode::INTERNAL_SERVER_ERROR),
+        ] {
+            assert_eq!(error.into_response().status(), status);
+        }
+    }
+}
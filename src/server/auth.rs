@@ -1,7 +1,6 @@
 use axum::{
     extract::Request,
-    http::{StatusCode, HeaderMap},
-    middleware::Next,
+    http::{StatusCode, HeaderMap, Method},
     response::Response,
 };
 use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
@@ -17,9 +16,64 @@ pub struct Claims {
     pub role: String,
 }
 
+/// Role that satisfies every route requirement
+pub const ADMIN_ROLE: &str = "admin";
+
+/// The development API key, accepted only when `auth.dev_key` is set. It is
+/// public, so it gets the read-only role and can't pass any role check.
+const DEV_KEY: &str = "q8-caster-dev-key";
+const DEV_KEY_ROLE: &str = "viewer";
+
+/// How long a token from `create_token` is accepted for, in seconds
+const TOKEN_LIFETIME: u64 = 3600 * 24;
+
+/// A route pattern like `/api/displays/*/cast`, matching that path and every
+/// path under it; `*` matches any one segment. Shared by the auth and rate
+/// limit layers so both read their route lists the same way.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct PathPattern {
+    segments: Vec<String>,
+}
+
+impl PathPattern {
+    pub(super) fn new(pattern: &str) -> Self {
+        Self {
+            segments: path_segments(pattern).map(str::to_string).collect(),
+        }
+    }
+
+    pub(super) fn matches(&self, path: &str) -> bool {
+        let mut path = path_segments(path);
+        self.segments.iter().all(|want| path.next().is_some_and(|got| want == "*" || want == got))
+    }
+
+    /// How many segments the pattern names; the longest match is the most specific
+    pub(super) fn depth(&self) -> usize {
+        self.segments.len()
+    }
+}
+
+/// A role required for every path `pattern` matches, or only for requests
+/// with `method` if set
+#[derive(Debug, Clone)]
+struct RoleRequirement {
+    pattern: PathPattern,
+    method: Option<Method>,
+    role: String,
+}
+
+impl RoleRequirement {
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        self.method.as_ref().is_none_or(|want| want == method) && self.pattern.matches(path)
+    }
+}
+
+#[derive(Clone)]
 pub struct AuthLayer {
     secret: String,
     keycloak: Option<Arc<KeycloakAuth>>,
+    requirements: Arc<Vec<RoleRequirement>>,
+    dev_key: bool,
 }
 
 impl AuthLayer {
@@ -27,6 +81,8 @@ impl AuthLayer {
         Self {
             secret: secret.to_string(),
             keycloak: None,
+            requirements: Arc::new(Vec::new()),
+            dev_key: false,
         }
     }
     
@@ -34,8 +90,57 @@ impl AuthLayer {
         Self {
             secret: String::new(),
             keycloak: Some(keycloak),
+            requirements: Arc::new(Vec::new()),
+            dev_key: false,
         }
     }
+
+    /// Also accept tokens from `create_token` signed with `secret`, if there is one
+    pub fn with_secret(mut self, secret: Option<&str>) -> Self {
+        self.secret = secret.unwrap_or_default().to_string();
+        self
+    }
+
+    /// Accept the development API key as a `viewer`
+    pub fn with_dev_key(mut self, enabled: bool) -> Self {
+        self.dev_key = enabled;
+        self
+    }
+
+    /// Require `role` for requests under `path_prefix`, e.g. `/api/displays/*/cast`.
+    /// The longest matching prefix wins; unmatched routes accept any authenticated role.
+    pub fn require_role(self, path_prefix: &str, role: &str) -> Self {
+        self.push_requirement(None, path_prefix, role)
+    }
+
+    /// Like `require_role`, for `method` requests only, e.g. to guard a
+    /// POST that shares its path with reads anyone may make
+    pub fn require_role_for(self, method: Method, path_prefix: &str, role: &str) -> Self {
+        self.push_requirement(Some(method), path_prefix, role)
+    }
+
+    fn push_requirement(mut self, method: Option<Method>, path_prefix: &str, role: &str) -> Self {
+        let requirement = RoleRequirement {
+            pattern: PathPattern::new(path_prefix),
+            method,
+            role: role.to_string(),
+        };
+        Arc::make_mut(&mut self.requirements).push(requirement);
+        self
+    }
+}
+
+fn path_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+/// The role a request requires, if any
+fn required_role<'a>(requirements: &'a [RoleRequirement], method: &Method, path: &str) -> Option<&'a str> {
+    requirements
+        .iter()
+        .filter(|requirement| requirement.matches(method, path))
+        .max_by_key(|requirement| requirement.pattern.depth())
+        .map(|requirement| requirement.role.as_str())
 }
 
 impl<S> tower::Layer<S> for AuthLayer {
@@ -46,6 +151,8 @@ impl<S> tower::Layer<S> for AuthLayer {
             inner,
             secret: self.secret.clone(),
             keycloak: self.keycloak.clone(),
+            requirements: Arc::clone(&self.requirements),
+            dev_key: self.dev_key,
         }
     }
 }
@@ -55,6 +162,8 @@ pub struct AuthMiddleware<S> {
     inner: S,
     secret: String,
     keycloak: Option<Arc<KeycloakAuth>>,
+    requirements: Arc<Vec<RoleRequirement>>,
+    dev_key: bool,
 }
 
 impl<S> tower::Service<Request> for AuthMiddleware<S>
@@ -75,31 +184,52 @@ where
         let mut inner = std::mem::replace(&mut self.inner, inner);
         let secret = self.secret.clone();
         let keycloak = self.keycloak.clone();
+        let requirements = Arc::clone(&self.requirements);
+        let dev_key = self.dev_key;
 
         Box::pin(async move {
             // Skip auth for public endpoints
             let path = request.uri().path();
             if path == "/" || 
                path == "/health" || 
+               path == "/metrics" || 
                path == "/events" || 
+               path == "/ws" || 
                path.starts_with("/auth/") ||
-               path.starts_with("/static/") {
+               path.starts_with("/static/") ||
+               path.starts_with("/media/") {
                 return inner.call(request).await;
             }
 
             // Check for authorization
             let headers = request.headers();
-            let authorized = if let Some(keycloak) = keycloak {
-                check_keycloak_auth(headers, &keycloak).await
-            } else {
-                check_auth(headers, &secret)
+            let roles = match keycloak {
+                _ if dev_key && headers.get("x-api-key").is_some_and(|key| key == DEV_KEY) => Some(vec![DEV_KEY_ROLE.to_string()]),
+                Some(keycloak) => match check_keycloak_auth(headers, &keycloak).await {
+                    // An empty secret would accept tokens signed with no key at all
+                    None if !secret.is_empty() => check_auth(headers, &secret),
+                    roles => roles,
+                },
+                None if !secret.is_empty() => check_auth(headers, &secret),
+                // Without a secret nobody can be told apart from a forger
+                None => None,
             };
 
-            if !authorized {
+            let Some(roles) = roles else {
                 return Ok(Response::builder()
                     .status(StatusCode::UNAUTHORIZED)
                     .body(axum::body::Body::from("Unauthorized"))
                     .unwrap());
+            };
+
+            // Authenticated but under-privileged gets 403, not 401
+            if let Some(required) = required_role(&requirements, request.method(), path) {
+                if !has_role(&roles, required) {
+                    return Ok(Response::builder()
+                        .status(StatusCode::FORBIDDEN)
+                        .body(axum::body::Body::from(format!("Forbidden: requires role '{}'", required)))
+                        .unwrap());
+                }
             }
 
             inner.call(request).await
@@ -107,36 +237,44 @@ where
     }
 }
 
-fn check_auth(headers: &HeaderMap, _secret: &str) -> bool {
-    // Simple API key check for now
-    if let Some(api_key) = headers.get("x-api-key") {
-        if api_key == "q8-caster-dev-key" {
-            return true;
-        }
-    }
-    
-    // TODO: Implement JWT validation
-    false
+fn has_role(roles: &[String], required: &str) -> bool {
+    roles.iter().any(|role| role == required || role == ADMIN_ROLE)
 }
 
-async fn check_keycloak_auth(headers: &HeaderMap, keycloak: &Arc<KeycloakAuth>) -> bool {
-    if let Some(auth_header) = headers.get(axum::http::header::AUTHORIZATION) {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if let Ok(_) = keycloak.validate_token(token).await {
-                    return true;
-                }
-            }
-        }
-    }
-    false
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Roles of the authenticated caller, or `None` if unauthenticated
+fn check_auth(headers: &HeaderMap, secret: &str) -> Option<Vec<String>> {
+    // Tokens issued by `create_token`
+    let token = bearer_token(headers)?;
+    let claims = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_ref()), &token_validation()).ok()?;
+    Some(vec![claims.claims.role])
+}
+
+/// Tokens must carry an expiry, and are refused once it has passed
+fn token_validation() -> Validation {
+    let mut validation = Validation::default();
+    validation.set_required_spec_claims(&["exp", "sub"]);
+    validation.validate_exp = true;
+    validation
+}
+
+async fn check_keycloak_auth(headers: &HeaderMap, keycloak: &Arc<KeycloakAuth>) -> Option<Vec<String>> {
+    let token = bearer_token(headers)?;
+    keycloak.validate_token(token).await.ok().map(|claims| claims.roles())
 }
 
 pub fn create_token(user_id: &str, role: &str, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
     let expiration = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
-        .as_secs() + 3600 * 24; // 24 hours
+        .as_secs() + TOKEN_LIFETIME;
 
     let claims = Claims {
         sub: user_id.to_string(),
@@ -145,4 +283,79 @@ pub fn create_token(user_id: &str, role: &str, secret: &str) -> Result<String, j
     };
 
     encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_ref()))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match_their_path_and_below() {
+        let pattern = PathPattern::new("/api/displays/*/cast");
+        assert!(pattern.matches("/api/displays/display_0/cast"));
+        assert!(pattern.matches("/api/displays/display_0/cast/extra"));
+        // Trailing and doubled slashes don't matter
+        assert!(pattern.matches("//api/displays/display_0/cast/"));
+        assert!(!pattern.matches("/api/displays/display_0"));
+        assert!(!pattern.matches("/api/displays/display_0/stop"));
+        assert!(!pattern.matches("/api/displayscast"));
+        assert_eq!(pattern.depth(), 4);
+    }
+
+    #[test]
+    fn the_longest_matching_requirement_wins() {
+        let layer = AuthLayer::new("secret")
+            .require_role("/api", "viewer")
+            .require_role("/api/secrets", "admin");
+        assert_eq!(required_role(&layer.requirements, &Method::POST, "/api/secrets/api-keys"), Some("admin"));
+        assert_eq!(required_role(&layer.requirements, &Method::GET, "/api/displays"), Some("viewer"));
+        assert_eq!(required_role(&layer.requirements, &Method::GET, "/health"), None);
+    }
+
+    #[test]
+    fn method_requirements_only_guard_their_method() {
+        let layer = AuthLayer::new("secret")
+            .require_role("/api/displays/*/cast", "caster")
+            .require_role_for(Method::POST, "/api/cache", "caster");
+
+        assert_eq!(required_role(&layer.requirements, &Method::POST, "/api/cache"), Some("caster"));
+        assert_eq!(required_role(&layer.requirements, &Method::GET, "/api/cache/stats"), None);
+        assert_eq!(required_role(&layer.requirements, &Method::GET, "/api/displays/main/cast"), Some("caster"));
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(axum::http::header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn expired_tokens_are_refused() {
+        let token = create_token("alice", "viewer", "secret").unwrap();
+        assert_eq!(check_auth(&bearer(&token), "secret"), Some(vec!["viewer".to_string()]));
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let expired = Claims {
+            sub: "alice".to_string(),
+            exp: now - 3600,
+            role: "viewer".to_string(),
+        };
+        let token = encode(&Header::default(), &expired, &EncodingKey::from_secret(b"secret")).unwrap();
+        assert_eq!(check_auth(&bearer(&token), "secret"), None);
+    }
+
+    #[tokio::test]
+    async fn tokens_signed_with_an_empty_secret_are_refused() {
+        let service = tower::Layer::layer(
+            &AuthLayer::new(""),
+            tower::service_fn(|_: Request| async { Ok::<_, std::convert::Infallible>(Response::new(axum::body::Body::empty())) }),
+        );
+        let forged = create_token("mallory", ADMIN_ROLE, "").unwrap();
+        let request = Request::builder()
+            .uri("/api/displays")
+            .header("Authorization", format!("Bearer {}", forged))
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        assert_eq!(tower::ServiceExt::oneshot(service, request).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+}
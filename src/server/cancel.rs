@@ -0,0 +1,61 @@
+use dashmap::DashMap;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+// Work in flight for each cast session: session id -> (display id, token)
+lazy_static::lazy_static! {
+    static ref SESSIONS: DashMap<String, (String, CancellationToken)> = DashMap::new();
+}
+
+/// Token for `session_id`'s work on `display_id`, cancelled when either is stopped.
+/// Call `finish` once the work is done.
+pub fn register(display_id: &str, session_id: &str) -> CancellationToken {
+    let token = CancellationToken::new();
+    register_token(display_id, session_id, token.clone());
+    token
+}
+
+/// Like `register`, for work that already has a token of its own
+pub fn register_token(display_id: &str, session_id: &str, token: CancellationToken) {
+    SESSIONS.insert(session_id.to_string(), (display_id.to_string(), token));
+}
+
+/// Calls `finish` for its session when dropped, for work that ends with a stream
+pub struct SessionGuard(pub String);
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        finish(&self.0);
+    }
+}
+
+/// Forget a session whose work has ended
+pub fn finish(session_id: &str) {
+    SESSIONS.remove(session_id);
+}
+
+/// The display a registered session is on
+pub fn display_of(session_id: &str) -> Option<String> {
+    SESSIONS.get(session_id).map(|entry| entry.value().0.clone())
+}
+
+/// Cancel one session's work; false if it had none in flight
+pub fn cancel_session(session_id: &str) -> bool {
+    match SESSIONS.remove(session_id) {
+        Some((_, (_, token))) => {
+            info!("Cancelling session {}", session_id);
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Cancel the work of every session on `display_id`, returning their ids
+pub fn cancel_display(display_id: &str) -> Vec<String> {
+    let sessions: Vec<String> = SESSIONS.iter()
+        .filter(|entry| entry.value().0 == display_id)
+        .map(|entry| entry.key().clone())
+        .collect();
+    sessions.into_iter().filter(|session_id| cancel_session(session_id)).collect()
+}
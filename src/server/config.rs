@@ -0,0 +1,78 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::{Result, CasterError};
+use crate::cache::CacheConfig;
+use crate::display::AmbientConfig;
+use crate::network::{default_receiver_protocols, DiscoveryConfig};
+use crate::render::WasmLimitsConfig;
+use crate::secrets::KeycloakSettings;
+use super::metrics::MetricsConfig;
+use super::rate_limit::RateLimitConfig;
+use super::request_log::RequestLogConfig;
+
+/// Settings read from the TOML file given with `--config`; every section is optional
+/// and anything left out keeps its built-in default
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Port to listen on when `--port` isn't given
+    pub port: u16,
+    pub cache: CacheConfig,
+    /// Timings for device discovery
+    pub discovery: DiscoveryConfig,
+    /// Receivers started when a start request doesn't name any
+    pub protocols: Vec<String>,
+    pub auth: AuthConfig,
+    /// Idle screen for every display, until a display is given its own
+    pub ambient: AmbientConfig,
+    /// Per-client limit on casting, discovery and capture routes
+    pub rate_limit: RateLimitConfig,
+    /// Who may read `GET /metrics`
+    pub metrics: MetricsConfig,
+    /// When a request is slow enough to log at warn
+    pub request_log: RequestLogConfig,
+    /// How far a WebAssembly cast may loosen its sandbox
+    pub wasm: WasmLimitsConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            port: 8420,
+            cache: CacheConfig::default(),
+            discovery: DiscoveryConfig::default(),
+            protocols: default_receiver_protocols(),
+            auth: AuthConfig::default(),
+            ambient: AmbientConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            metrics: MetricsConfig::default(),
+            request_log: RequestLogConfig::default(),
+            wasm: WasmLimitsConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Signs and checks the server's own bearer tokens, accepted alongside Keycloak's
+    pub secret: Option<String>,
+    /// Accept the well-known `x-api-key: q8-caster-dev-key`, with the read-only
+    /// `viewer` role, for local development. Off unless set.
+    pub dev_key: bool,
+    pub keycloak: KeycloakSettings,
+}
+
+impl ServerConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| CasterError::Config(format!("Cannot read {}: {}", path.display(), e)))?;
+        let config: Self = toml::from_str(&text)
+            .map_err(|e| CasterError::Config(format!("Invalid config {}: {}", path.display(), e)))?;
+
+        config.discovery.validate()
+            .map_err(|e| CasterError::Config(format!("Invalid config {}: {}", path.display(), e)))?;
+        Ok(config)
+    }
+}
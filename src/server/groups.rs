@@ -0,0 +1,680 @@
+use chrono::Utc;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::live_reload;
+use super::playlist::{self, DisplayCaster};
+use super::sse::{notify_cast_started, notify_cast_stopped};
+use crate::network::NetworkReceiver;
+use crate::cache::ContentCache;
+use crate::render::{SlideFormat, WasmLimitsConfig, WasmSandboxConfig};
+use crate::{CastSession, CasterError, ContentSource, ContentType, MirrorQuality, Result, StreamProtocol};
+
+lazy_static::lazy_static! {
+    static ref CAST_GROUPS: DashMap<Uuid, CastGroup> = DashMap::new();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    /// A local display, by display id
+    Display,
+    /// A discovered network device, by discovery id
+    Device,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CastTarget {
+    pub kind: TargetKind,
+    pub id: String,
+}
+
+/// The same content cast to several targets, stopped together
+#[derive(Debug, Clone, Serialize)]
+pub struct CastGroup {
+    pub id: Uuid,
+    /// One session per target that started; failed targets aren't tracked
+    pub sessions: Vec<(CastTarget, CastSession)>,
+}
+
+/// Short name for a cast source, as shown on screen: the file name of a path or URL
+pub fn source_title(source: &str) -> String {
+    let path = source.split(['?', '#']).next().unwrap_or(source).trim_end_matches('/');
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let title = if name.is_empty() { source } else { name };
+    title.to_string()
+}
+
+/// The `type` a content type serializes with, e.g. "markdown", for events
+pub fn content_label(content_type: &ContentType) -> String {
+    serde_json::to_value(content_type)
+        .ok()
+        .and_then(|v| v["type"].as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// A cast request that doesn't parse, naming the field at fault, e.g.
+/// `options.page`, so clients can point at it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentError {
+    pub field: String,
+    pub message: String,
+}
+
+impl ContentError {
+    fn new(field: &str, message: impl std::fmt::Display) -> Self {
+        Self { field: field.to_string(), message: message.to_string() }
+    }
+
+    fn option(name: &str, message: impl std::fmt::Display) -> Self {
+        Self::new(&format!("options.{}", name), message)
+    }
+}
+
+impl std::fmt::Display for ContentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<ContentError> for CasterError {
+    fn from(error: ContentError) -> Self {
+        CasterError::InvalidRequest(error.message)
+    }
+}
+
+type ContentResult<T> = std::result::Result<T, ContentError>;
+
+/// `options.<name>` as a string, None when it's left out
+fn option_str<'a>(options: &'a Value, name: &str) -> ContentResult<Option<&'a str>> {
+    match &options[name] {
+        Value::Null => Ok(None),
+        Value::String(value) => Ok(Some(value)),
+        _ => Err(ContentError::option(name, format!("options.{} must be a string", name))),
+    }
+}
+
+pub(crate) fn option_bool(options: &Value, name: &str) -> ContentResult<Option<bool>> {
+    match &options[name] {
+        Value::Null => Ok(None),
+        Value::Bool(value) => Ok(Some(*value)),
+        _ => Err(ContentError::option(name, format!("options.{} must be true or false", name))),
+    }
+}
+
+const CAST_CONTENT_TYPES: &str = "markdown, video, audio, image, pdf, stream, presentation, screen_mirror, webassembly, model3d";
+
+/// Read `content_type`/`source`/`options` from a cast request body, rejecting
+/// anything malformed rather than falling back to a default
+pub fn parse_content(payload: &Value) -> ContentResult<(ContentType, ContentSource)> {
+    let content_type = match payload["content_type"].as_str() {
+        Some(content_type) if !content_type.is_empty() => content_type,
+        _ => return Err(ContentError::new("content_type", "content_type is required")),
+    };
+    let options = &payload["options"];
+    if !options.is_null() && !options.is_object() {
+        return Err(ContentError::new("options", "options must be an object"));
+    }
+    // A screen mirror captures a local monitor and a WebRTC stream starts from its offer
+    let needs_source = !matches!((content_type, option_str(options, "protocol")?), ("screen_mirror", _) | ("stream", Some("webrtc")));
+    let source = match payload["source"].as_str() {
+        Some(source) if !source.trim().is_empty() => source,
+        _ if needs_source => return Err(ContentError::new("source", "source is required: a file path, URL or cache://key")),
+        _ => "",
+    };
+
+    if source == "cache://" {
+        return Err(ContentError::new("source", "cache:// needs a key"));
+    }
+
+    Ok((parse_content_type(content_type, source, options)?, content_source(source)))
+}
+
+/// Where a source names its content: an http(s) URL, a `cache://key` or a file path
+pub fn content_source(source: &str) -> ContentSource {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        ContentSource::Url { url: source.to_string() }
+    } else if let Some(key) = source.strip_prefix("cache://") {
+        ContentSource::Cache { key: key.to_string() }
+    } else {
+        ContentSource::File { path: source.to_string() }
+    }
+}
+
+fn parse_content_type(content_type: &str, source: &str, options: &Value) -> ContentResult<ContentType> {
+    Ok(match content_type {
+        "markdown" => ContentType::Markdown {
+            theme: option_str(options, "theme")?.map(str::to_string),
+            math: option_bool(options, "math")?.unwrap_or(false)
+        },
+        "video" => ContentType::Video {
+            codec: option_str(options, "codec")?.unwrap_or("auto").to_string(),
+            container: option_str(options, "container")?.unwrap_or("auto").to_string()
+        },
+        "audio" => ContentType::Audio {
+            codec: option_str(options, "codec")?.unwrap_or("auto").to_string(),
+            format: option_str(options, "format")?.unwrap_or("auto").to_string()
+        },
+        "image" => ContentType::Image {
+            format: option_str(options, "format")?.unwrap_or("auto").to_string()
+        },
+        "pdf" => ContentType::Pdf {
+            page: match &options["page"] {
+                Value::Null => None,
+                page => match page.as_u64() {
+                    Some(page @ 1..=0xFFFF) => Some(page as u32),
+                    _ => return Err(ContentError::option("page", "options.page must be a page number from 1")),
+                },
+            }
+        },
+        "model3d" => ContentType::Model3D {
+            format: option_str(options, "format")?.unwrap_or("gltf").to_string()
+        },
+        "stream" => {
+            let protocol = match option_str(options, "protocol")?.unwrap_or("rtsp") {
+                "rtsp" => StreamProtocol::Rtsp { url: source.to_string() },
+                "webrtc" => match option_str(options, "offer")? {
+                    Some(offer) if !offer.is_empty() => StreamProtocol::WebRtc { offer: offer.to_string() },
+                    _ => return Err(ContentError::option("offer", "A webrtc stream needs options.offer, the SDP offer")),
+                },
+                "hls" => StreamProtocol::Hls { manifest_url: source.to_string() },
+                "dash" => StreamProtocol::Dash { manifest_url: source.to_string() },
+                protocol => return Err(ContentError::option(
+                    "protocol",
+                    format!("Unknown stream protocol: {}; expected rtsp, webrtc, hls or dash", protocol),
+                )),
+            };
+            ContentType::Stream { protocol }
+        },
+        "presentation" => {
+            let format = option_str(options, "format")?.unwrap_or("auto");
+            if let Err(CasterError::InvalidRequest(message)) = SlideFormat::parse(format) {
+                return Err(ContentError::option("format", message));
+            }
+            ContentType::Presentation {
+                format: format.to_string(),
+                theme: option_str(options, "theme")?.map(str::to_string),
+                math: option_bool(options, "math")?.unwrap_or(false)
+            }
+        },
+        "screen_mirror" => {
+            let quality = match option_str(options, "quality")?.unwrap_or("medium") {
+                "low" => MirrorQuality::Low,
+                "medium" => MirrorQuality::Medium,
+                "high" => MirrorQuality::High,
+                "ultra" => MirrorQuality::Ultra,
+                quality => return Err(ContentError::option(
+                    "quality",
+                    format!("Unknown quality: {}; expected low, medium, high or ultra", quality),
+                )),
+            };
+            ContentType::ScreenMirror {
+                source_display: option_str(options, "source_display")?.map(str::to_string),
+                quality
+            }
+        },
+        "webassembly" | "wasm" => ContentType::WebAssembly {
+            module_url: source.to_string(),
+            entry_point: option_str(options, "entry_point")?.map(str::to_string),
+            sandbox: parse_wasm_sandbox(options)?
+        },
+        _ => return Err(ContentError::new(
+            "content_type",
+            format!("Unknown content type: {}; expected one of {}", content_type, CAST_CONTENT_TYPES),
+        ))
+    })
+}
+
+/// The sandbox a cast module asks for; anything left out stays closed
+pub fn parse_wasm_sandbox(options: &Value) -> ContentResult<WasmSandboxConfig> {
+    let mut sandbox = WasmSandboxConfig::default();
+    let positive = |name: &str| match &options[name] {
+        Value::Null => Ok(None),
+        value => match value.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n)),
+            _ => Err(ContentError::option(name, format!("options.{} must be a positive integer", name))),
+        },
+    };
+    if let Some(bytes) = positive("max_memory_bytes")? {
+        sandbox.max_memory_bytes = bytes;
+    }
+    if let Some(fuel) = positive("max_fuel")? {
+        sandbox.max_fuel = fuel;
+    }
+    if let Some(secs) = positive("timeout_secs")? {
+        sandbox.timeout = std::time::Duration::from_secs(secs);
+    }
+    if let Some(allow_net) = option_bool(options, "allow_net")? {
+        sandbox.allow_net = allow_net;
+    }
+    match &options["allow_fs"] {
+        Value::Null => {}
+        Value::Array(dirs) => {
+            for dir in dirs {
+                match dir.as_str() {
+                    Some(dir) if !dir.is_empty() => sandbox.allow_fs.push(dir.into()),
+                    _ => return Err(ContentError::option("allow_fs", "options.allow_fs must be a list of directory paths")),
+                }
+            }
+        }
+        _ => return Err(ContentError::option("allow_fs", "options.allow_fs must be a list of directory paths")),
+    }
+    Ok(sandbox)
+}
+
+/// Hold a requested sandbox to what the server allows: memory up to
+/// `wasm.max_memory_bytes`, the network only with `wasm.allow_net`, and only
+/// directories under `cache.media_dirs`, checked after resolving links
+pub fn limit_wasm_sandbox(sandbox: &mut WasmSandboxConfig, limits: &WasmLimitsConfig, cache: &ContentCache) -> ContentResult<()> {
+    sandbox.max_memory_bytes = sandbox.max_memory_bytes.min(limits.max_memory_bytes);
+    if sandbox.allow_net && !limits.allow_net {
+        return Err(ContentError::option("allow_net", "This server doesn't let WebAssembly modules use the network (wasm.allow_net)"));
+    }
+    for dir in &mut sandbox.allow_fs {
+        *dir = match cache.media_path(&dir.to_string_lossy()) {
+            Ok(resolved) => resolved,
+            Err(_) => return Err(ContentError::option(
+                "allow_fs",
+                format!("{} is not under one of the media directories (cache.media_dirs)", dir.display()),
+            )),
+        };
+    }
+    Ok(())
+}
+
+/// Read a cast request for a network device with `parse_content`, refusing
+/// what devices can't play: they take video, audio, images and HLS or DASH
+/// streams, and a stream without a protocol is HLS
+pub fn parse_device_content(payload: &Value) -> ContentResult<(ContentType, ContentSource)> {
+    let mut payload = payload.clone();
+    let options = &payload["options"];
+    if payload["content_type"] == "stream" && (options.is_null() || options.is_object()) && options["protocol"].is_null() {
+        payload["options"]["protocol"] = json!("hls");
+    }
+
+    let (content_type, content_source) = parse_content(&payload)?;
+    match &content_type {
+        ContentType::Video { .. } | ContentType::Audio { .. } | ContentType::Image { .. } => {},
+        ContentType::Stream { protocol: StreamProtocol::Hls { .. } | StreamProtocol::Dash { .. } } => {},
+        ContentType::Stream { .. } => return Err(ContentError::option(
+            "protocol",
+            format!("Unsupported stream protocol for network devices: {}; expected hls or dash", payload["options"]["protocol"].as_str().unwrap_or("")),
+        )),
+        _ => return Err(ContentError::new(
+            "content_type",
+            format!("Unsupported content type for network devices: {}; expected video, audio, image or stream", content_label(&content_type)),
+        )),
+    }
+    Ok((content_type, content_source))
+}
+
+/// Cast to every target, carrying on past failures. A display target opens the
+/// display's cast window and takes over from any playlist or watched file there.
+///
+/// Returns the group id and a result per target, in request order.
+pub(crate) async fn start_group(
+    displays: &impl DisplayCaster,
+    network_receiver: &RwLock<NetworkReceiver>,
+    targets: Vec<CastTarget>,
+    content_type: ContentType,
+    source: ContentSource,
+) -> (Uuid, Vec<Value>) {
+    let group_id = Uuid::new_v4();
+    let content_label = content_label(&content_type);
+    let source_text = match &source {
+        ContentSource::Url { url } => url.clone(),
+        ContentSource::File { path } => path.clone(),
+        ContentSource::Cache { key } => format!("cache://{}", key),
+        ContentSource::Memory { .. } => String::new(),
+    };
+
+    let known_displays = displays.displays().await.unwrap_or_default();
+
+    let mut sessions = Vec::new();
+    let mut results = Vec::new();
+
+    for target in targets {
+        let started = match target.kind {
+            TargetKind::Display => match known_displays.iter().find(|display| display.id == target.id) {
+                Some(display) => {
+                    playlist::stop(&display.id);
+                    live_reload::stop(&display.id);
+                    displays.cast(display, content_type.clone(), &source_text).await.map(|_| ())
+                }
+                None => Err(CasterError::NotFound(format!("Display not found: {}", target.id))),
+            },
+            TargetKind::Device => {
+                network_receiver.write().await.cast_to_device(&target.id, &content_type, &source).await
+            }
+        };
+
+        match started {
+            Ok(()) => {
+                let session = CastSession {
+                    id: Uuid::new_v4(),
+                    display_id: target.id.clone(),
+                    content_type: content_type.clone(),
+                    source: source.clone(),
+                    created_at: Utc::now(),
+                    metadata: json!({"group_id": group_id, "kind": target.kind}),
+                };
+                notify_cast_started(target.id.clone(), content_label.clone(), session.id.to_string());
+
+                results.push(json!({
+                    "kind": target.kind,
+                    "id": target.id,
+                    "success": true,
+                    "session_id": session.id,
+                }));
+                sessions.push((target, session));
+            }
+            Err(e) => {
+                warn!("Group {} failed to cast to {}: {}", group_id, target.id, e);
+                results.push(json!({
+                    "kind": target.kind,
+                    "id": target.id,
+                    "success": false,
+                    "error": e.to_string(),
+                }));
+            }
+        }
+    }
+
+    info!("Started cast group {} with {} sessions", group_id, sessions.len());
+    CAST_GROUPS.insert(group_id, CastGroup { id: group_id, sessions });
+
+    (group_id, results)
+}
+
+/// Stop every session in a group, closing the window on each display target.
+/// Returns a result per session.
+pub(crate) async fn stop_group(
+    displays: &impl DisplayCaster,
+    network_receiver: &RwLock<NetworkReceiver>,
+    group_id: Uuid,
+) -> Result<Vec<Value>> {
+    let Some((_, group)) = CAST_GROUPS.remove(&group_id) else {
+        return Err(CasterError::NotFound(format!("Cast group not found: {}", group_id)));
+    };
+
+    let mut results = Vec::new();
+    for (target, session) in group.sessions {
+        let stopped = match target.kind {
+            TargetKind::Display => {
+                playlist::stop(&target.id);
+                live_reload::stop(&target.id);
+                displays.close(&target.id).await
+            }
+            TargetKind::Device => network_receiver.write().await.stop_device(&target.id).await,
+        };
+
+        notify_cast_stopped(target.id.clone(), session.id.to_string());
+        results.push(match stopped {
+            Ok(()) => json!({"kind": target.kind, "id": target.id, "success": true}),
+            Err(e) => json!({"kind": target.kind, "id": target.id, "success": false, "error": e.to_string()}),
+        });
+    }
+
+    info!("Stopped cast group {}", group_id);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use crate::DisplayInfo;
+
+    /// One display, "display_0"; records what's cast and closed, failing sources
+    /// that start with "fail"
+    #[derive(Default)]
+    struct FakeDisplays {
+        cast: Mutex<Vec<(String, String, String)>>,
+        closed: Mutex<Vec<String>>,
+    }
+
+    impl DisplayCaster for FakeDisplays {
+        async fn displays(&self) -> Result<Vec<DisplayInfo>> {
+            Ok(vec![DisplayInfo {
+                id: "display_0".into(),
+                name: "Primary Display".into(),
+                resolution: crate::Resolution { width: 1920, height: 1080 },
+                position: crate::Position { x: 0, y: 0 },
+                is_primary: true,
+                refresh_rate: 60.0,
+                scale_factor: 1.0,
+            }])
+        }
+
+        async fn cast(&self, display: &DisplayInfo, content_type: ContentType, source: &str) -> Result<Option<String>> {
+            if source.starts_with("fail") {
+                return Err(CasterError::NotFound(source.to_string()));
+            }
+            self.cast.lock().unwrap().push((display.id.clone(), content_label(&content_type), source.to_string()));
+            Ok(None)
+        }
+
+        async fn finished(&self, _session: Option<&str>, _duration: Option<Duration>) {}
+
+        async fn close(&self, display_id: &str) -> Result<()> {
+            self.closed.lock().unwrap().push(display_id.to_string());
+            Ok(())
+        }
+    }
+
+    fn display_target(id: &str) -> CastTarget {
+        CastTarget { kind: TargetKind::Display, id: id.into() }
+    }
+
+    #[test]
+    fn wasm_sandbox_defaults_to_closed() {
+        assert_eq!(parse_wasm_sandbox(&Value::Null).unwrap(), WasmSandboxConfig::default());
+    }
+
+    #[test]
+    fn wasm_sandbox_takes_its_limits_from_options() {
+        let sandbox = parse_wasm_sandbox(&json!({
+            "max_memory_bytes": 1048576,
+            "max_fuel": 500,
+            "timeout_secs": 5,
+            "allow_net": true,
+            "allow_fs": ["/srv/media"]
+        }))
+        .unwrap();
+        assert_eq!(sandbox.max_memory_bytes, 1048576);
+        assert_eq!(sandbox.max_fuel, 500);
+        assert_eq!(sandbox.timeout, std::time::Duration::from_secs(5));
+        assert!(sandbox.allow_net);
+        assert_eq!(sandbox.allow_fs, vec![std::path::PathBuf::from("/srv/media")]);
+    }
+
+    #[test]
+    fn wasm_sandbox_rejects_bad_limits() {
+        for (options, name) in [
+            (json!({"max_memory_bytes": 0}), "options.max_memory_bytes"),
+            (json!({"max_fuel": -1}), "options.max_fuel"),
+            (json!({"timeout_secs": "30"}), "options.timeout_secs"),
+            (json!({"allow_net": "yes"}), "options.allow_net"),
+            (json!({"allow_fs": "/tmp"}), "options.allow_fs"),
+            (json!({"allow_fs": ["/tmp", ""]}), "options.allow_fs"),
+        ] {
+            assert_eq!(parse_wasm_sandbox(&options).unwrap_err().field, name, "{}", options);
+        }
+    }
+
+    #[test]
+    fn wasm_sandbox_stays_within_the_server_limits() {
+        let media = std::env::temp_dir().join(format!("q8-caster-wasm-media-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(media.join("clips")).unwrap();
+        let cache = crate::cache::ContentCache::with_config(&crate::cache::CacheConfig {
+            dir: Some(media.join("cache")),
+            media_dirs: vec![media.clone()],
+            ..Default::default()
+        })
+        .unwrap();
+        let limits = WasmLimitsConfig { max_memory_bytes: 1 << 20, allow_net: false };
+        let limit = |options: Value, limits: &WasmLimitsConfig| {
+            let mut sandbox = parse_wasm_sandbox(&options).unwrap();
+            limit_wasm_sandbox(&mut sandbox, limits, &cache).map(|_| sandbox)
+        };
+
+        // Memory is clamped to the server's maximum
+        assert_eq!(limit(json!({"max_memory_bytes": 1u64 << 40}), &limits).unwrap().max_memory_bytes, 1 << 20);
+        assert_eq!(limit(json!({"max_memory_bytes": 4096}), &limits).unwrap().max_memory_bytes, 4096);
+
+        // The network only when the server permits it
+        assert_eq!(limit(json!({"allow_net": true}), &limits).unwrap_err().field, "options.allow_net");
+        let open = WasmLimitsConfig { allow_net: true, ..limits.clone() };
+        assert!(limit(json!({"allow_net": true}), &open).unwrap().allow_net);
+
+        // Directories only inside the media directories, after resolving `..`
+        let clips = media.join("clips");
+        let sandbox = limit(json!({"allow_fs": [clips.join("..").join("clips")]}), &limits).unwrap();
+        assert_eq!(sandbox.allow_fs, vec![std::fs::canonicalize(&clips).unwrap()]);
+        for dir in ["/", "/etc"] {
+            assert_eq!(limit(json!({"allow_fs": [dir]}), &limits).unwrap_err().field, "options.allow_fs", "{}", dir);
+        }
+        let escape = media.join("clips").join("..").join("..");
+        assert_eq!(limit(json!({"allow_fs": [escape]}), &limits).unwrap_err().field, "options.allow_fs");
+
+        std::fs::remove_dir_all(&media).ok();
+    }
+
+    #[test]
+    fn cast_content_options_fill_in_their_defaults() {
+        let parse = |content_type, options| {
+            parse_content(&json!({"content_type": content_type, "source": "rtsp://camera/live", "options": options})).unwrap().0
+        };
+
+        assert!(matches!(parse("markdown", Value::Null), ContentType::Markdown { theme: None, math: false }));
+        assert!(matches!(
+            parse("markdown", json!({"theme": "light", "math": true})),
+            ContentType::Markdown { theme: Some(theme), math: true } if theme == "light"
+        ));
+        assert!(matches!(parse("pdf", Value::Null), ContentType::Pdf { page: None }));
+        assert!(matches!(parse("pdf", json!({"page": 3})), ContentType::Pdf { page: Some(3) }));
+        assert!(matches!(
+            parse("stream", Value::Null),
+            ContentType::Stream { protocol: StreamProtocol::Rtsp { url } } if url == "rtsp://camera/live"
+        ));
+        assert!(matches!(
+            parse("screen_mirror", json!({"quality": "ultra"})),
+            ContentType::ScreenMirror { source_display: None, quality: MirrorQuality::Ultra }
+        ));
+        assert!(matches!(parse("wasm", Value::Null), ContentType::WebAssembly { entry_point: None, .. }));
+    }
+
+    #[test]
+    fn cast_content_names_the_field_it_rejects() {
+        for (content_type, options, name) in [
+            ("slides", Value::Null, "content_type"),
+            ("markdown", json!({"theme": 1}), "options.theme"),
+            ("markdown", json!({"math": "yes"}), "options.math"),
+            ("pdf", json!({"page": 0}), "options.page"),
+            ("pdf", json!({"page": 70000}), "options.page"),
+            ("pdf", json!({"page": "2"}), "options.page"),
+            ("stream", json!({"protocol": "rtmp"}), "options.protocol"),
+            ("stream", json!({"protocol": "webrtc"}), "options.offer"),
+            ("stream", json!({"protocol": "webrtc", "offer": ""}), "options.offer"),
+            ("presentation", json!({"format": "pptx"}), "options.format"),
+            ("screen_mirror", json!({"quality": "max"}), "options.quality"),
+            ("webassembly", json!({"max_fuel": 0}), "options.max_fuel"),
+        ] {
+            let payload = json!({"content_type": content_type, "source": "source", "options": options});
+            assert_eq!(parse_content(&payload).unwrap_err().field, name, "{} {}", content_type, options);
+        }
+    }
+
+    #[test]
+    fn sources_are_required_and_named_by_kind() {
+        let source = |source: &str| parse_content(&json!({"content_type": "image", "source": source})).map(|(_, source)| source);
+
+        assert!(matches!(source("https://host/a.png"), Ok(ContentSource::Url { .. })));
+        assert!(matches!(source("cache://poster"), Ok(ContentSource::Cache { key }) if key == "poster"));
+        assert!(matches!(source("/srv/media/a.png"), Ok(ContentSource::File { .. })));
+        for missing in ["", "  ", "cache://"] {
+            assert_eq!(source(missing).unwrap_err().field, "source", "{:?}", missing);
+        }
+        assert!(parse_content(&json!({"content_type": "screen_mirror"})).is_ok());
+        assert_eq!(parse_content(&json!({"source": "a.png"})).unwrap_err().field, "content_type");
+    }
+
+    #[test]
+    fn stored_content_keeps_where_it_came_from() {
+        assert!(matches!(content_source("https://host/talk.pdf"), ContentSource::Url { url } if url == "https://host/talk.pdf"));
+        assert!(matches!(content_source("cache://talk"), ContentSource::Cache { key } if key == "talk"));
+        assert!(matches!(content_source("/srv/media/talk.pdf"), ContentSource::File { path } if path == "/srv/media/talk.pdf"));
+    }
+
+    #[test]
+    fn network_devices_get_only_what_they_can_play() {
+        for content_type in ["video", "audio", "image", "stream"] {
+            let payload = json!({"content_type": content_type, "source": "http://host/media"});
+            assert!(parse_device_content(&payload).is_ok(), "{}", content_type);
+        }
+        assert!(matches!(
+            parse_device_content(&json!({"content_type": "stream", "source": "http://host/live.m3u8"})),
+            Ok((ContentType::Stream { protocol: StreamProtocol::Hls { .. } }, _))
+        ));
+        for (payload, field) in [
+            (json!({"content_type": "pdf", "source": "talk.pdf"}), "content_type"),
+            (json!({"content_type": "screen_mirror", "source": ""}), "content_type"),
+            (json!({"content_type": "stream", "source": "rtsp://camera", "options": {"protocol": "rtsp"}}), "options.protocol"),
+            (json!({"content_type": "hologram", "source": "x"}), "content_type"),
+            // Held to the same rules as any other cast
+            (json!({"content_type": "image", "source": "cache://"}), "source"),
+            (json!({"content_type": "video", "source": "a.mp4", "options": {"codec": 264}}), "options.codec"),
+        ] {
+            assert_eq!(parse_device_content(&payload).unwrap_err().field, field, "{}", payload);
+        }
+    }
+
+    #[tokio::test]
+    async fn display_targets_get_the_content_and_are_closed_on_stop() {
+        let displays = FakeDisplays::default();
+        let network_receiver = RwLock::new(NetworkReceiver::new().await.unwrap());
+        let (content_type, source) = parse_content(&json!({
+            "content_type": "markdown",
+            "source": "/srv/media/welcome.md"
+        })).unwrap();
+
+        let targets = vec![display_target("display_0"), display_target("display_9")];
+        let (group_id, results) = start_group(&displays, &network_receiver, targets, content_type, source).await;
+
+        assert_eq!(results[0]["success"], true);
+        assert_eq!(results[1]["success"], false);
+        assert_eq!(
+            *displays.cast.lock().unwrap(),
+            [("display_0".to_string(), "markdown".to_string(), "/srv/media/welcome.md".to_string())]
+        );
+
+        let stopped = stop_group(&displays, &network_receiver, group_id).await.unwrap();
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0]["success"], true);
+        assert_eq!(*displays.closed.lock().unwrap(), ["display_0"]);
+        assert!(stop_group(&displays, &network_receiver, group_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn a_display_that_cant_show_the_content_is_a_failed_target() {
+        let displays = FakeDisplays::default();
+        let network_receiver = RwLock::new(NetworkReceiver::new().await.unwrap());
+        let (content_type, source) = parse_content(&json!({
+            "content_type": "image",
+            "source": "fail.png"
+        })).unwrap();
+
+        let (group_id, results) = start_group(&displays, &network_receiver, vec![display_target("display_0")], content_type, source).await;
+
+        assert_eq!(results[0]["success"], false);
+        assert!(displays.cast.lock().unwrap().is_empty());
+        // Nothing started, so there's nothing to close
+        assert!(stop_group(&displays, &network_receiver, group_id).await.unwrap().is_empty());
+        assert!(displays.closed.lock().unwrap().is_empty());
+    }
+}
@@ -3,27 +3,34 @@ use axum::{
     routing::{get, post},
     response::Html,
     extract::State,
-    http::StatusCode,
+    http::{Method, StatusCode},
     Json,
 };
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
+use std::future::IntoFuture;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::{Result, CasterError};
-use crate::display::DisplayManager;
-use crate::media::MediaEngine;
-use crate::render::RenderEngine;
-use crate::network::NetworkReceiver;
+use crate::display::{Ambient, DisplayManager};
+use crate::media::{spawn_playback_sync, MediaEngine};
+use crate::render::{RenderEngine, WasmLimitsConfig};
+use crate::network::{spawn_media_bridge, NetworkReceiver};
 use crate::cache::ContentCache;
 use crate::secrets::{SecretsManager, keycloak::{KeycloakAuth, login_handler, callback_handler, logout_handler, userinfo_handler}};
 
 use super::api;
-use super::sse::sse_handler;
+use super::config::ServerConfig;
+use super::metrics::{metrics_handler, MetricsConfig};
+use super::sse::{spawn_discovery_bridge, sse_handler};
+use super::ws::ws_handler;
 use super::auth::AuthLayer;
+use super::rate_limit::RateLimitLayer;
+use super::request_log::{self, LogResponse};
 
 pub struct HttpServer {
     pub display_manager: Arc<RwLock<DisplayManager>>,
@@ -33,8 +40,12 @@ pub struct HttpServer {
     pub content_cache: Arc<RwLock<ContentCache>>,
     pub secrets_manager: Arc<SecretsManager>,
     pub keycloak_auth: Arc<KeycloakAuth>,
+    pub config: ServerConfig,
 }
 
+/// How long open connections get to finish once shutdown starts
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 pub struct AppState {
     pub display_manager: Arc<RwLock<DisplayManager>>,
@@ -44,27 +55,42 @@ pub struct AppState {
     pub content_cache: Arc<RwLock<ContentCache>>,
     pub secrets_manager: Arc<SecretsManager>,
     pub keycloak_auth: Arc<KeycloakAuth>,
+    pub metrics: MetricsConfig,
+    /// How far a cast may loosen its WebAssembly module's sandbox
+    pub wasm_limits: WasmLimitsConfig,
 }
 
 impl HttpServer {
-    pub async fn new() -> Result<Self> {
-        let secrets_manager = Arc::new(SecretsManager::new().await?);
+    /// Set up every engine from `config`, the `--config` file or the defaults
+    pub async fn new(config: ServerConfig) -> Result<Self> {
+        let secrets_manager = Arc::new(SecretsManager::new(&config.auth.keycloak).await?);
         let keycloak_config = secrets_manager.get_keycloak_config().clone();
         let keycloak_auth = Arc::new(KeycloakAuth::new(keycloak_config).await?);
         
+        // Casting managers publish local files on `/media` from the same directories the cache reads
+        crate::network::media_registry().set_media_dirs(config.cache.media_dirs.clone());
+        let media_engine = Arc::new(RwLock::new(MediaEngine::new()?));
+        let network_receiver = NetworkReceiver::with_config(config.discovery.clone(), config.protocols.clone()).await?;
+        spawn_media_bridge(network_receiver.subscribe_airplay(), Arc::clone(&media_engine));
+        spawn_discovery_bridge(network_receiver.subscribe_discovery());
+        let display_manager = Arc::new(RwLock::new(DisplayManager::new().await?));
+        spawn_playback_sync(Arc::clone(&media_engine), Arc::clone(&display_manager));
+        
         Ok(Self {
-            display_manager: Arc::new(RwLock::new(DisplayManager::new().await?)),
-            media_engine: Arc::new(RwLock::new(MediaEngine::new()?)),
+            display_manager,
+            media_engine,
             render_engine: Arc::new(RwLock::new(RenderEngine::new().await?)),
-            network_receiver: Arc::new(RwLock::new(NetworkReceiver::new().await?)),
-            content_cache: Arc::new(RwLock::new(ContentCache::new()?)),
+            network_receiver: Arc::new(RwLock::new(network_receiver)),
+            content_cache: Arc::new(RwLock::new(ContentCache::with_config(&config.cache)?)),
             secrets_manager,
             keycloak_auth,
+            config,
         })
     }
 
-    pub async fn run(self, port: u16) -> Result<()> {
-        let state = AppState {
+    /// What every handler gets to work with
+    pub(crate) fn state(&self) -> AppState {
+        AppState {
             display_manager: Arc::clone(&self.display_manager),
             media_engine: Arc::clone(&self.media_engine),
             render_engine: Arc::clone(&self.render_engine),
@@ -72,9 +98,15 @@ impl HttpServer {
             content_cache: Arc::clone(&self.content_cache),
             secrets_manager: Arc::clone(&self.secrets_manager),
             keycloak_auth: Arc::clone(&self.keycloak_auth),
-        };
+            metrics: self.config.metrics.clone(),
+            wasm_limits: self.config.wasm.clone(),
+        }
+    }
+
+    pub async fn run(self, port: u16) -> Result<()> {
+        let state = self.state();
 
-        let app = Router::new()
+        let mut app = Router::new()
             // Public routes (no auth required)
             .route("/", get(dashboard))
             .route("/health", get(health_check))
@@ -83,14 +115,24 @@ impl HttpServer {
             .route("/auth/logout", post(logout_handler))
             .route("/auth/userinfo", get(userinfo_handler))
             
-            // SSE endpoint for real-time updates
+            // SSE and WebSocket endpoints for real-time updates
             .route("/events", get(sse_handler))
+            .route("/ws", get(ws_handler))
+            
+            // Local files published for cast devices (token-gated)
+            .route("/media/:token", get(api::serve_media))
             
             // Protected API endpoints
             .route("/api/displays", get(api::list_displays))
             .route("/api/displays/:id/cast", post(api::cast_content))
             .route("/api/displays/:id/stop", post(api::stop_cast))
+            .route("/api/displays/:id/control", post(api::control_display))
             .route("/api/displays/:id/configure", post(api::configure_display))
+            .route("/api/displays/:id/ambient", post(api::set_ambient))
+            .route("/api/displays/:id/playlist", post(api::cast_playlist))
+            
+            .route("/api/cast/group", post(api::cast_group))
+            .route("/api/cast/group/:id/stop", post(api::stop_cast_group))
             
             .route("/api/codecs", get(api::list_codecs))
             .route("/api/audio", get(api::list_audio_devices))
@@ -99,43 +141,305 @@ impl HttpServer {
             .route("/api/chromecast/:name/connect", post(api::connect_chromecast))
             .route("/api/chromecast/:name/cast", post(api::cast_to_chromecast))
             .route("/api/chromecast/:name/control", post(api::control_chromecast))
+            .route("/api/chromecast/:name/queue", post(api::chromecast_queue))
             
             .route("/api/receiver/start", post(api::start_receiver))
+            .route("/api/discovery/start", post(api::start_discovery))
+            .route("/api/discovery/stop", post(api::stop_discovery))
+            .route("/api/devices", get(api::list_devices))
+            .route("/api/devices/:id", get(api::get_device))
+            .route("/api/devices/:id/cast", post(api::cast_to_device))
+            .route("/api/devices/:id/dial/launch", post(api::launch_dial_app))
+            .route("/api/devices/:id/dial/:app_name", get(api::dial_app_state))
             .route("/api/cache", post(api::cache_content))
+            .route("/api/cache/stats", get(api::cache_stats))
+            .route("/api/cache/:key", get(api::get_cached_content))
+            .route("/api/pdf/text", post(api::extract_pdf_text))
+            .route("/api/render/pdf/thumbnails", post(api::render_pdf_thumbnails))
+            .route("/api/screen/capture", get(api::capture_screen))
+            .route("/api/screen/monitors", get(api::list_monitors))
+            .route("/api/screen/stream", get(api::mirror_stream))
+            .route("/api/screen/stream/stop", post(api::stop_mirror_stream))
             
             // Secrets management endpoints
             .route("/api/secrets/api-keys", post(api::add_api_key))
-            .route("/api/secrets/rtsp-credentials", post(api::add_rtsp_credential))
-            
+            .route("/api/secrets/rtsp-credentials", post(api::add_rtsp_credential));
+
+        // Unauthenticated, and by default only answered for local clients
+        if self.config.metrics.enabled {
+            app = app.route("/metrics", get(metrics_handler));
+        }
+
+        let app = app
             // Add state
             .with_state(state)
             
             // Add middleware
             .layer(CorsLayer::permissive())
-            .layer(TraceLayer::new_for_http())
-            .layer(AuthLayer::with_keycloak(Arc::clone(&self.keycloak_auth)));
+            .layer(role_requirements(
+                AuthLayer::with_keycloak(Arc::clone(&self.keycloak_auth))
+                    .with_secret(self.config.auth.secret.as_deref())
+                    .with_dev_key(self.config.auth.dev_key)
+            ))
+            // Outside auth, so unauthenticated floods are turned away cheaply too
+            .layer(
+                RateLimitLayer::new(self.config.rate_limit.clone())
+                    .limit("/api/displays/*/cast")
+                    .limit("/api/displays/*/playlist")
+                    .limit("/api/cast/group")
+                    .limit("/api/chromecast/discover")
+                    .limit("/api/chromecast/*/cast")
+                    .limit("/api/devices/*/cast")
+                    .limit("/api/discovery/start")
+                    .limit("/api/receiver/start")
+                    .limit("/api/screen/capture")
+                    .limit("/api/screen/stream")
+            )
+            // Outermost, so requests turned away by auth or the rate limit are logged too
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(request_log::make_span)
+                    .on_response(LogResponse::new(&self.config.request_log))
+                    // 5xx responses are already logged with their status
+                    .on_failure(())
+            );
 
         let addr = SocketAddr::from(([0, 0, 0, 0], port));
         info!("Q8-Caster HTTP server listening on http://{}", addr);
         
+        crate::network::media_registry().set_port(port);
+
+        // Needs the port for the join code's dashboard URL
+        let ambient = Ambient::load(self.config.ambient.clone(), &*self.content_cache.read().await).await;
+        self.display_manager.write().await.set_ambient(None, ambient)?;
+        
         let listener = tokio::net::TcpListener::bind(addr).await
             .map_err(|e| CasterError::Network(format!("Failed to bind to port {}: {}", port, e)))?;
             
-        axum::serve(listener, app).await
-            .map_err(|e| CasterError::Network(format!("Server error: {}", e)))?;
-        
+        let (signalled_tx, signalled_rx) = tokio::sync::oneshot::channel();
+        // Client addresses for per-IP rate limiting
+        let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(async move {
+                shutdown_signal().await;
+                let _ = signalled_tx.send(());
+            })
+            .into_future();
+        tokio::pin!(server);
+
+        // SSE and WebSocket clients never hang up on their own, so they only get so long
+        let grace = async {
+            if signalled_rx.await.is_ok() {
+                tokio::time::sleep(SHUTDOWN_GRACE).await;
+            } else {
+                std::future::pending::<()>().await;
+            }
+        };
+        tokio::select! {
+            served = &mut server => served
+                .map_err(|e| CasterError::Network(format!("Server error: {}", e)))?,
+            _ = grace => warn!("Connections still open after {:?}, closing them", SHUTDOWN_GRACE),
+        }
+
+        self.shutdown().await;
         Ok(())
     }
+
+    /// Release what outlives the HTTP server: mDNS registrations, receivers and
+    /// discovery, screen captures and cache files
+    async fn shutdown(&self) {
+        info!("Shutdown: stopping network receiver and discovery");
+        if let Err(e) = self.network_receiver.write().await.stop().await {
+            warn!("Failed to stop network receiver: {}", e);
+        }
+
+        info!("Shutdown: stopping screen mirror");
+        self.render_engine.write().await.stop_screen_mirror();
+
+        info!("Shutdown: flushing content cache");
+        // The write lock waits out any store still running
+        match self.content_cache.write().await.flush().await {
+            Ok(0) => {}
+            Ok(removed) => info!("Removed {} half-written cache files", removed),
+            Err(e) => warn!("Failed to flush content cache: {}", e),
+        }
+
+        info!("Shutdown complete");
+    }
+}
+
+/// Roles the API's routes need; anything not listed takes any authenticated role.
+/// Everything that changes what's shown, starts something or reads the screen
+/// or local files needs `caster`.
+fn role_requirements(layer: AuthLayer) -> AuthLayer {
+    layer
+        .require_role("/api/secrets", "admin")
+        .require_role("/api/displays/*/cast", "caster")
+        .require_role("/api/displays/*/stop", "caster")
+        .require_role("/api/displays/*/control", "caster")
+        .require_role("/api/displays/*/configure", "caster")
+        .require_role("/api/displays/*/ambient", "caster")
+        .require_role("/api/displays/*/playlist", "caster")
+        // Also covers /api/cast/group/:id/stop
+        .require_role("/api/cast/group", "caster")
+        // Reading the cache back is open to any role
+        .require_role_for(Method::POST, "/api/cache", "caster")
+        .require_role("/api/chromecast/*/connect", "caster")
+        .require_role("/api/chromecast/*/cast", "caster")
+        .require_role("/api/chromecast/*/control", "caster")
+        .require_role("/api/chromecast/*/queue", "caster")
+        .require_role("/api/devices/*/cast", "caster")
+        .require_role("/api/devices/*/dial/launch", "caster")
+        .require_role("/api/receiver", "caster")
+        .require_role("/api/discovery", "caster")
+        .require_role("/api/screen/capture", "caster")
+        // Also covers /api/screen/stream/stop
+        .require_role("/api/screen/stream", "caster")
+        .require_role("/api/pdf", "caster")
+        .require_role("/api/render", "caster")
+}
+
+/// Resolves on Ctrl-C or SIGTERM; in-flight requests then get to finish
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl-C, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+    }
 }
 
 async fn dashboard() -> Html<&'static str> {
     Html(include_str!("../../static/dashboard.html"))
 }
 
-async fn health_check() -> (StatusCode, Json<serde_json::Value>) {
-    (StatusCode::OK, Json(serde_json::json!({
-        "status": "healthy",
+/// How long /health waits on pdfium before calling it down
+const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Every subsystem's status. Answers 503 when one casting can't do without is
+/// down, so orchestrators stop routing to this instance.
+async fn health_check(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    let component = |critical: bool, outcome: Result<()>| match outcome {
+        Ok(()) => serde_json::json!({"status": "ok", "critical": critical}),
+        Err(e) => serde_json::json!({"status": "down", "critical": critical, "error": e.to_string()}),
+    };
+
+    let gstreamer = state.media_engine.read().await.gstreamer_available();
+    let media = component(true, if gstreamer {
+        Ok(())
+    } else {
+        Err(CasterError::Media("GStreamer is not installed".into()))
+    });
+    let cache = component(true, state.content_cache.read().await.check_writable());
+
+    let pdf = state.render_engine.read().await.pdf_pool();
+    let pdf = match tokio::time::timeout(HEALTH_PROBE_TIMEOUT, pdf.check()).await {
+        Ok(outcome) => outcome,
+        Err(_) => Err(CasterError::Render("PDF workers didn't answer in time".into())),
+    };
+    let render = component(false, pdf);
+
+    // Discovery runs on demand, so stopped isn't a failure
+    let mut discovery = component(false, Ok(()));
+    discovery["running"] = state.network_receiver.read().await.is_discovery_running().await.into();
+
+    let components = serde_json::json!({
+        "media": media,
+        "cache": cache,
+        "render": render,
+        "discovery": discovery,
+    });
+    let down = |critical: bool| components.as_object().into_iter().flatten()
+        .any(|(_, c)| c["status"] == "down" && c["critical"] == critical);
+    let critical_down = down(true);
+    let healthy = !critical_down && !down(false);
+
+    let code = if critical_down { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+    (code, Json(serde_json::json!({
+        "status": if healthy { "healthy" } else { "degraded" },
         "service": "q8-caster",
         "version": env!("CARGO_PKG_VERSION"),
+        "components": components,
     })))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::Request;
+    use tower::{Layer, ServiceExt};
+    use super::super::auth::create_token;
+
+    /// The status `role` gets for `method path` through the API's role map
+    async fn status_for(role: &str, method: Method, path: &str) -> StatusCode {
+        let token = create_token("tester", role, "secret").unwrap();
+        let service = role_requirements(AuthLayer::new("secret"))
+            .layer(tower::service_fn(|_: Request| async { Ok::<_, std::convert::Infallible>(axum::response::Response::new(Body::empty())) }));
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        service.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn read_only_roles_cant_change_or_capture_displays() {
+        for (method, path) in [
+            (Method::POST, "/api/displays/display_0/control"),
+            (Method::POST, "/api/cast/group/lobby/stop"),
+            (Method::POST, "/api/cache"),
+            (Method::GET, "/api/screen/capture"),
+            (Method::POST, "/api/screen/stream/stop"),
+            (Method::POST, "/api/pdf/text"),
+            (Method::POST, "/api/render/pdf/thumbnails"),
+        ] {
+            assert_eq!(status_for("viewer", method.clone(), path).await, StatusCode::FORBIDDEN, "{} {}", method, path);
+            assert_eq!(status_for("caster", method.clone(), path).await, StatusCode::OK, "{} {}", method, path);
+        }
+
+        // Reads stay open to every role
+        assert_eq!(status_for("viewer", Method::GET, "/api/cache/stats").await, StatusCode::OK);
+        assert_eq!(status_for("viewer", Method::GET, "/api/displays").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn the_dev_key_is_off_by_default_and_only_reads_when_on() {
+        let status = |dev_key: bool, method: Method, path: &'static str| async move {
+            let service = role_requirements(AuthLayer::new("secret").with_dev_key(dev_key))
+                .layer(tower::service_fn(|_: Request| async { Ok::<_, std::convert::Infallible>(axum::response::Response::new(Body::empty())) }));
+            let request = Request::builder()
+                .method(method)
+                .uri(path)
+                .header("x-api-key", "q8-caster-dev-key")
+                .body(Body::empty())
+                .unwrap();
+            service.oneshot(request).await.unwrap().status()
+        };
+
+        assert_eq!(status(false, Method::GET, "/api/displays").await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(true, Method::GET, "/api/displays").await, StatusCode::OK);
+        assert_eq!(status(true, Method::POST, "/api/secrets/api-keys").await, StatusCode::FORBIDDEN);
+        assert_eq!(status(true, Method::POST, "/api/displays/display_0/control").await, StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,137 @@
+use dashmap::DashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+use super::sse::{notify_content_updated, notify_error};
+use crate::display::{DisplayManager, WindowCommand};
+use crate::{CasterError, ContentSource, ContentType, Result};
+
+lazy_static::lazy_static! {
+    // Keyed by display id; a display has at most one watched cast
+    static ref WATCHERS: DashMap<String, Watcher> = DashMap::new();
+}
+
+/// How often the file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long the file must stay unchanged before it's reloaded, so an editor's
+/// save-in-several-writes only reloads once
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+struct Watcher {
+    session_id: String,
+    task: JoinHandle<()>,
+}
+
+/// Check a cast's `watch` option: only markdown files can be watched
+pub fn validate(content_type: &ContentType, source: &ContentSource) -> Result<PathBuf> {
+    if !matches!(content_type, ContentType::Markdown { .. }) {
+        return Err(CasterError::Render("Only markdown casts can be watched".into()));
+    }
+    match source {
+        ContentSource::File { path } if !path.starts_with("cache://") => Ok(PathBuf::from(path)),
+        _ => Err(CasterError::Render("Only local markdown files can be watched, not URLs or cached content".into())),
+    }
+}
+
+/// Recast the markdown at `path` to `display_id` whenever it changes, until `stop`.
+/// Replaces any watcher already running for the display.
+pub fn start(
+    display_id: String,
+    session_id: String,
+    path: PathBuf,
+    theme: Option<String>,
+    math: bool,
+    display_manager: Arc<RwLock<DisplayManager>>,
+) {
+    stop(&display_id);
+    info!("Watching {} for session {}", path.display(), session_id);
+
+    let task = tokio::spawn({
+        let display_id = display_id.clone();
+        let session_id = session_id.clone();
+        async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            let mut shown = modified(&path).await;
+            // A change seen but not yet settled, and when it was last seen changing
+            let mut pending: Option<(Option<(SystemTime, u64)>, tokio::time::Instant)> = None;
+
+            loop {
+                interval.tick().await;
+                let current = modified(&path).await;
+
+                match pending {
+                    Some((seen, _)) if seen != current => pending = Some((current, tokio::time::Instant::now())),
+                    Some((_, since)) if since.elapsed() >= DEBOUNCE => {
+                        pending = None;
+                        shown = current;
+                        reload(&display_id, &session_id, &path, theme.as_deref(), math, &display_manager).await;
+                    }
+                    Some(_) => {}
+                    None if current != shown => pending = Some((current, tokio::time::Instant::now())),
+                    None => {}
+                }
+            }
+        }
+    });
+
+    WATCHERS.insert(display_id, Watcher { session_id, task });
+}
+
+/// Stop watching for a display's cast; true if one was being watched
+pub fn stop(display_id: &str) -> bool {
+    match WATCHERS.remove(display_id) {
+        Some((_, watcher)) => {
+            watcher.task.abort();
+            info!("Stopped watching for session {}", watcher.session_id);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Modification time and size; None while the file is missing, e.g. mid-save
+async fn modified(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+async fn reload(
+    display_id: &str,
+    session_id: &str,
+    path: &Path,
+    theme: Option<&str>,
+    math: bool,
+    display_manager: &RwLock<DisplayManager>,
+) {
+    let markdown = match tokio::fs::read_to_string(path).await {
+        Ok(markdown) => markdown,
+        Err(e) => {
+            debug!("Can't reload {}: {}", path.display(), e);
+            return;
+        }
+    };
+    // Checked the same way a cast is; on failure the last good version stays up
+    if let Err(e) = crate::render::render_markdown(&markdown, theme, math) {
+        notify_error(format!("Failed to render {}: {}", path.display(), e));
+        return;
+    }
+
+    let command = WindowCommand::SetContent {
+        content_type: ContentType::Markdown { theme: theme.map(str::to_string), math },
+        data: markdown.into_bytes(),
+        // No "now casting" toast on every save
+        title: None,
+    };
+    match display_manager.read().await.send_to_window(display_id, command) {
+        Ok(()) => {
+            debug!("Reloaded {} on {}", path.display(), display_id);
+            notify_content_updated(display_id.to_string(), session_id.to_string());
+        }
+        Err(e) => debug!("No window on {} to reload into: {}", display_id, e),
+    }
+}
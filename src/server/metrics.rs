@@ -0,0 +1,217 @@
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use metrics::{counter, describe_counter, describe_gauge, gauge};
+use metrics_exporter_prometheus::PrometheusBuilder;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+
+use crate::cache::CacheStats;
+use super::http::AppState;
+use super::sse::sse_client_count;
+
+// Cast that's showing on each display, as announced by cast_started/cast_stopped events
+lazy_static::lazy_static! {
+    static ref ACTIVE_CASTS: DashMap<String, String> = DashMap::new();
+}
+
+/// Who may read `GET /metrics`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Serve the endpoint at all
+    pub enabled: bool,
+    /// Only answer requests from this machine; the endpoint has no auth
+    pub local_only: bool,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            local_only: true,
+        }
+    }
+}
+
+/// `session_id` is now showing on `display_id`, replacing whatever was there
+pub fn cast_started(display_id: &str, session_id: &str) {
+    ACTIVE_CASTS.insert(display_id.to_string(), session_id.to_string());
+}
+
+/// `session_id` stopped; a newer cast on the same display is left alone
+pub fn cast_stopped(display_id: &str, session_id: &str) {
+    ACTIVE_CASTS.remove_if(display_id, |_, active| active == session_id);
+}
+
+/// Session showing on `display_id`, if any
+pub fn active_cast(display_id: &str) -> Option<String> {
+    ACTIVE_CASTS.get(display_id).map(|session_id| session_id.clone())
+}
+
+fn active_casts() -> usize {
+    ACTIVE_CASTS.len()
+}
+
+/// Counters and gauges in the Prometheus text format
+pub async fn metrics_handler(
+    State(state): State<AppState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+) -> Response {
+    if state.metrics.local_only && !client.ip().is_loopback() {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    let mut devices: BTreeMap<String, usize> = BTreeMap::new();
+    for device in state.network_receiver.read().await.get_discovered_devices() {
+        let device_type = serde_json::to_value(&device.device_type).unwrap_or_default();
+        let device_type = match device_type.as_str() {
+            Some(device_type) => device_type.to_string(),
+            // Custom types serialize as {"custom": name}
+            None => "custom".to_string(),
+        };
+        *devices.entry(device_type).or_default() += 1;
+    }
+    let snapshot = Snapshot {
+        cache: state.content_cache.read().await.stats(),
+        devices,
+        active_casts: active_casts(),
+        sse_clients: sse_client_count(),
+    };
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], render(&snapshot)).into_response()
+}
+
+/// What a scrape reports, read from the rest of the server
+struct Snapshot {
+    cache: CacheStats,
+    /// Discovered devices by type
+    devices: BTreeMap<String, usize>,
+    active_casts: usize,
+    sse_clients: usize,
+}
+
+/// `snapshot` in the Prometheus text exposition. Each scrape records into a
+/// fresh recorder, so device types that have gone away don't linger.
+fn render(snapshot: &Snapshot) -> String {
+    let recorder = PrometheusBuilder::new().build_recorder();
+    metrics::with_local_recorder(&recorder, || {
+        let cache = &snapshot.cache;
+
+        describe_counter!("q8_cache_hits_total", "Cache reads served, by where they were found");
+        counter!("q8_cache_hits_total", "tier" => "memory").absolute(cache.memory_hits);
+        counter!("q8_cache_hits_total", "tier" => "disk").absolute(cache.disk_hits);
+        describe_counter!("q8_cache_misses_total", "Cache reads for items that weren't cached");
+        counter!("q8_cache_misses_total").absolute(cache.misses);
+        describe_counter!("q8_cache_stores_total", "Items stored in the cache");
+        counter!("q8_cache_stores_total").absolute(cache.stores);
+        describe_counter!("q8_cache_evictions_total", "Items evicted to stay under the size limit");
+        counter!("q8_cache_evictions_total").absolute(cache.evictions);
+        describe_counter!("q8_cache_served_bytes_total", "Bytes returned by cache reads");
+        counter!("q8_cache_served_bytes_total").absolute(cache.bytes_served);
+
+        describe_gauge!("q8_cache_size_bytes", "Bytes the cache holds on disk");
+        gauge!("q8_cache_size_bytes").set(cache.total_size_bytes as f64);
+        describe_gauge!("q8_cache_max_size_bytes", "Most bytes the cache may hold");
+        gauge!("q8_cache_max_size_bytes").set(cache.max_size_bytes as f64);
+        describe_gauge!("q8_cache_items", "Cached items, by tier");
+        gauge!("q8_cache_items", "tier" => "memory").set(cache.memory_items as f64);
+        gauge!("q8_cache_items", "tier" => "disk").set(cache.disk_items as f64);
+
+        describe_gauge!("q8_active_casts", "Displays and receivers currently showing a cast");
+        gauge!("q8_active_casts").set(snapshot.active_casts as f64);
+        describe_gauge!("q8_discovered_devices", "Cast devices found on the network, by type");
+        for (device_type, count) in &snapshot.devices {
+            gauge!("q8_discovered_devices", "type" => device_type.clone()).set(*count as f64);
+        }
+        describe_gauge!("q8_sse_clients", "Clients connected to /events");
+        gauge!("q8_sse_clients").set(snapshot.sse_clients as f64);
+    });
+    recorder.handle().render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            cache: CacheStats {
+                memory_items: 2,
+                disk_items: 5,
+                total_size_bytes: 4096,
+                max_size_bytes: 524288000,
+                memory_capacity: 100,
+                hits: 10,
+                memory_hits: 7,
+                disk_hits: 3,
+                misses: 4,
+                stores: 5,
+                evictions: 1,
+                bytes_served: 123456,
+            },
+            devices: BTreeMap::from([("chromecast".to_string(), 2), ("roku".to_string(), 1)]),
+            active_casts: 1,
+            sse_clients: 3,
+        }
+    }
+
+    #[test]
+    fn exposition_has_every_metric() {
+        let text = render(&snapshot());
+        let lines: Vec<&str> = text.lines().collect();
+
+        for expected in [
+            "# HELP q8_cache_hits_total Cache reads served, by where they were found",
+            "# TYPE q8_cache_hits_total counter",
+            "q8_cache_hits_total{tier=\"memory\"} 7",
+            "q8_cache_hits_total{tier=\"disk\"} 3",
+            "q8_cache_misses_total 4",
+            "q8_cache_evictions_total 1",
+            "q8_cache_served_bytes_total 123456",
+            "# TYPE q8_cache_size_bytes gauge",
+            "q8_cache_size_bytes 4096",
+            "q8_cache_max_size_bytes 524288000",
+            "q8_cache_items{tier=\"disk\"} 5",
+            "q8_active_casts 1",
+            "q8_discovered_devices{type=\"chromecast\"} 2",
+            "q8_discovered_devices{type=\"roku\"} 1",
+            "q8_sse_clients 3",
+        ] {
+            assert!(lines.contains(&expected), "missing {:?} in\n{}", expected, text);
+        }
+    }
+
+    #[test]
+    fn device_types_that_went_away_are_dropped() {
+        render(&snapshot());
+        let text = render(&Snapshot { devices: BTreeMap::new(), ..snapshot() });
+        assert!(!text.contains("q8_discovered_devices{"), "{}", text);
+    }
+
+    #[test]
+    fn a_stopped_cast_leaves_the_active_gauge() {
+        use crate::server::sse::{notify_cast_started, notify_display_stopped};
+
+        let before = active_casts();
+        notify_cast_started("metrics-test-display".into(), "markdown".into(), "session-1".into());
+        assert_eq!(active_casts(), before + 1);
+        assert_eq!(active_cast("metrics-test-display").as_deref(), Some("session-1"));
+
+        // Stopping without naming the session stops the one showing
+        notify_display_stopped("metrics-test-display".into(), None);
+        assert_eq!(active_casts(), before);
+        assert!(render(&Snapshot { active_casts: active_casts(), ..snapshot() }).contains(&format!("q8_active_casts {}", before)));
+
+        // A stale session doesn't take down the cast that replaced it
+        notify_cast_started("metrics-test-display".into(), "markdown".into(), "session-2".into());
+        notify_display_stopped("metrics-test-display".into(), Some("session-1".into()));
+        assert_eq!(active_cast("metrics-test-display").as_deref(), Some("session-2"));
+        notify_display_stopped("metrics-test-display".into(), Some("session-2".into()));
+        assert_eq!(active_cast("metrics-test-display"), None);
+    }
+}
@@ -1,6 +1,15 @@
 pub mod http;
 pub mod sse;
+pub mod ws;
 pub mod auth;
 pub mod api;
+pub mod cancel;
+pub mod groups;
+pub mod config;
+pub mod live_reload;
+pub mod metrics;
+pub mod playlist;
+pub mod rate_limit;
+pub mod request_log;
 
 pub use http::HttpServer;
\ No newline at end of file
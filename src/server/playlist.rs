@@ -0,0 +1,496 @@
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::groups;
+use super::sse::{notify_cast_started, notify_error};
+use crate::cache::ContentCache;
+use crate::display::{DisplayManager, WindowCommand};
+use crate::media::MediaEngine;
+use crate::{CasterError, ContentType, DisplayInfo, Result};
+
+lazy_static::lazy_static! {
+    // Keyed by display id; a display plays one playlist at a time
+    static ref PLAYLISTS: DashMap<String, RunningPlaylist> = DashMap::new();
+}
+
+/// How often a playing item is checked for having reached its end
+const END_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Content types a playlist can hold: those a cast window shows on the display
+const PLAYLIST_CONTENT_TYPES: &[&str] = &["markdown", "video", "audio", "image", "pdf", "screen_mirror"];
+
+/// One entry, in the same shape as a cast request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistItem {
+    pub content_type: String,
+    pub source: String,
+    #[serde(default)]
+    pub options: Value,
+    /// How long static content stays up; without it the item waits for `next`.
+    /// Video and audio advance when they finish.
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+}
+
+/// Items cast to one display in order, advancing on their own
+#[derive(Debug, Clone, Serialize)]
+pub struct Playlist {
+    pub id: Uuid,
+    pub display_id: String,
+    pub items: Vec<PlaylistItem>,
+    /// Index of the item showing
+    pub current: usize,
+    /// Start over after the last item instead of stopping on it
+    #[serde(rename = "loop")]
+    pub looping: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PlaylistControl {
+    Next,
+    Previous,
+    /// 0-based index
+    Jump(usize),
+}
+
+/// What a playlist or a cast group needs to cast to a display
+#[derive(Clone)]
+pub struct PlaylistContext {
+    pub display_manager: Arc<RwLock<DisplayManager>>,
+    pub media_engine: Arc<RwLock<MediaEngine>>,
+    pub content_cache: Arc<RwLock<ContentCache>>,
+}
+
+struct RunningPlaylist {
+    playlist: Playlist,
+    // Dropping the sender ends the playlist's task
+    control: mpsc::UnboundedSender<PlaylistControl>,
+}
+
+/// Start `items` on `display`, replacing any playlist already there.
+/// The first item is cast by the time this returns; the rest follow on their own.
+pub async fn start(context: PlaylistContext, display: DisplayInfo, items: Vec<PlaylistItem>, looping: bool) -> Result<Playlist> {
+    validate(&items)?;
+
+    let playlist = Playlist {
+        id: Uuid::new_v4(),
+        display_id: display.id.clone(),
+        items,
+        current: 0,
+        looping,
+    };
+
+    // The old playlist mustn't advance over the new one's first item
+    stop(&display.id);
+    let session = show(&context, &display, &playlist.items[0]).await?;
+
+    let (control, commands) = mpsc::unbounded_channel();
+    PLAYLISTS.insert(display.id.clone(), RunningPlaylist {
+        playlist: playlist.clone(),
+        control,
+    });
+    info!("Playlist {} started on {} with {} items", playlist.id, playlist.display_id, playlist.items.len());
+
+    tokio::spawn(run(context, display, playlist.clone(), session, commands));
+    Ok(playlist)
+}
+
+/// Refuse an empty playlist, or one with an item a cast window can't show
+fn validate(items: &[PlaylistItem]) -> Result<()> {
+    if items.is_empty() {
+        return Err(CasterError::InvalidRequest("A playlist needs at least one item".into()));
+    }
+    for (index, item) in items.iter().enumerate() {
+        if !PLAYLIST_CONTENT_TYPES.contains(&item.content_type.as_str()) {
+            return Err(CasterError::InvalidRequest(format!(
+                "Item {}: {} can't be part of a playlist",
+                index, item.content_type
+            )));
+        }
+        groups::parse_content(&item_payload(item))
+            .map_err(|e| CasterError::InvalidRequest(format!("Item {}: {}", index, e)))?;
+    }
+    Ok(())
+}
+
+/// Stop the playlist on a display, leaving whatever it shows; true if there was one
+pub fn stop(display_id: &str) -> bool {
+    match PLAYLISTS.remove(display_id) {
+        Some((_, running)) => {
+            info!("Playlist {} on {} stopped", running.playlist.id, display_id);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Move the playlist on a display to another item
+pub fn control(display_id: &str, command: PlaylistControl) -> Result<()> {
+    let running = PLAYLISTS.get(display_id)
+        .ok_or_else(|| CasterError::NotCasting(format!("No playlist on {}", display_id)))?;
+
+    if let PlaylistControl::Jump(index) = command {
+        if index >= running.playlist.items.len() {
+            return Err(CasterError::InvalidRequest(format!(
+                "No item {} in a playlist of {}",
+                index,
+                running.playlist.items.len()
+            )));
+        }
+    }
+
+    running.control.send(command)
+        .map_err(|_| CasterError::NotCasting(format!("No playlist on {}", display_id)))
+}
+
+/// The playlist on a display and where it has got to
+pub fn status(display_id: &str) -> Option<Playlist> {
+    PLAYLISTS.get(display_id).map(|running| running.playlist.clone())
+}
+
+/// Puts content on local displays: their cast windows, or a stand-in in tests
+pub(crate) trait DisplayCaster {
+    /// Displays that can be cast to
+    async fn displays(&self) -> Result<Vec<DisplayInfo>>;
+    /// Show content in the display's window. Returns the playback session for
+    /// video and audio, so a playlist can tell when it ends.
+    async fn cast(&self, display: &DisplayInfo, content_type: ContentType, source: &str) -> Result<Option<String>>;
+    /// Resolves when the content showing should give way to the next
+    async fn finished(&self, session: Option<&str>, duration: Option<Duration>);
+    /// Close the display's window
+    async fn close(&self, display_id: &str) -> Result<()>;
+}
+
+impl DisplayCaster for PlaylistContext {
+    async fn displays(&self) -> Result<Vec<DisplayInfo>> {
+        self.display_manager.read().await.list_displays().await
+    }
+
+    async fn cast(&self, display: &DisplayInfo, content_type: ContentType, source: &str) -> Result<Option<String>> {
+        cast_to_window(self, display, content_type, source).await
+    }
+
+    async fn finished(&self, session: Option<&str>, duration: Option<Duration>) {
+        item_finished(&self.media_engine, session, duration).await
+    }
+
+    async fn close(&self, display_id: &str) -> Result<()> {
+        self.display_manager.read().await.close_window(display_id)
+    }
+}
+
+async fn run(
+    caster: impl DisplayCaster,
+    display: DisplayInfo,
+    mut playlist: Playlist,
+    mut session: Option<String>,
+    mut commands: mpsc::UnboundedReceiver<PlaylistControl>,
+) {
+    let count = playlist.items.len();
+    // Items that failed in a row; once every item has, there's nothing left to show
+    let mut failures = 0;
+
+    loop {
+        // A failed item is skipped straight away
+        let duration = match failures {
+            0 => playlist.items[playlist.current].duration_secs.map(Duration::from_secs),
+            _ => Some(Duration::ZERO),
+        };
+        let next = tokio::select! {
+            command = commands.recv() => match command {
+                // Stopped or replaced
+                None => return,
+                Some(PlaylistControl::Next) if playlist.current + 1 < count => playlist.current + 1,
+                Some(PlaylistControl::Next) if playlist.looping => 0,
+                Some(PlaylistControl::Previous) if playlist.current > 0 => playlist.current - 1,
+                Some(PlaylistControl::Previous) if playlist.looping => count - 1,
+                Some(PlaylistControl::Jump(index)) => index,
+                // Already at an end
+                Some(_) => continue,
+            },
+            _ = caster.finished(session.as_deref(), duration) => {
+                if playlist.current + 1 < count {
+                    playlist.current + 1
+                } else if playlist.looping {
+                    0
+                } else {
+                    break;
+                }
+            }
+        };
+
+        playlist.current = next;
+        match PLAYLISTS.get_mut(&playlist.display_id) {
+            Some(mut running) if running.playlist.id == playlist.id => running.playlist.current = next,
+            _ => return,
+        }
+
+        session = match show(&caster, &display, &playlist.items[next]).await {
+            Ok(session) => {
+                failures = 0;
+                session
+            }
+            Err(e) => {
+                notify_error(format!("Playlist item {} failed on {}: {}", next, display.id, e));
+                failures += 1;
+                if failures >= count {
+                    warn!("Every item of playlist {} failed, stopping it", playlist.id);
+                    break;
+                }
+                None
+            }
+        };
+    }
+
+    PLAYLISTS.remove_if(&playlist.display_id, |_, running| running.playlist.id == playlist.id);
+    info!("Playlist {} on {} finished", playlist.id, playlist.display_id);
+}
+
+/// Resolves when the item showing should give way to the next: when its playback
+/// session ends, after `duration` for anything else, or never without one
+async fn item_finished(media_engine: &RwLock<MediaEngine>, session: Option<&str>, duration: Option<Duration>) {
+    match (session, duration) {
+        (Some(session), _) => {
+            let mut interval = tokio::time::interval(END_POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                let playing = media_engine.read().await.playback_position()
+                    .is_some_and(|playback| playback.session_id == session);
+                if !playing {
+                    return;
+                }
+            }
+        }
+        (None, Some(duration)) => tokio::time::sleep(duration).await,
+        (None, None) => std::future::pending().await,
+    }
+}
+
+/// Cast one item to the display's window, returning its playback session
+async fn show(caster: &impl DisplayCaster, display: &DisplayInfo, item: &PlaylistItem) -> Result<Option<String>> {
+    let (content_type, _) = groups::parse_content(&item_payload(item))?;
+    let session = caster.cast(display, content_type, &item.source).await?;
+
+    let session_id = session.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+    notify_cast_started(display.id.clone(), item.content_type.clone(), session_id);
+    Ok(session)
+}
+
+/// Open the display's window and show `source` in it, playing video and audio
+/// through the media engine. Returns the playback session for those.
+async fn cast_to_window(context: &PlaylistContext, display: &DisplayInfo, content_type: ContentType, source: &str) -> Result<Option<String>> {
+    let plays = matches!(content_type, ContentType::Video { .. } | ContentType::Audio { .. });
+    let mirrors = matches!(content_type, ContentType::ScreenMirror { .. });
+    let windowed = matches!(
+        content_type,
+        ContentType::Markdown { .. } | ContentType::Image { .. } | ContentType::Pdf { .. } | ContentType::Presentation { .. }
+    );
+    if !(plays || mirrors || windowed) {
+        return Err(CasterError::NotImplemented(format!(
+            "{} content can't be cast to a display yet",
+            groups::content_label(&content_type)
+        )));
+    }
+
+    let data = if plays || mirrors {
+        Vec::new()
+    } else {
+        context.content_cache.read().await.read_source(source).await?
+    };
+    let uri = match plays {
+        true => Some(crate::media::playback_uri(&*context.content_cache.read().await, source)?),
+        false => None,
+    };
+
+    let window = context.display_manager.write().await.create_window(&display.id, None).await?;
+    window.send(WindowCommand::SetContent {
+        content_type,
+        data,
+        title: Some(groups::source_title(source)),
+    })?;
+
+    let mut media_engine = context.media_engine.write().await;
+    let session = if let Some(uri) = uri {
+        if let Some(feeds) = media_engine.play_uri_on(&uri, Some(display))? {
+            feeds.attach(&window)?;
+        }
+        media_engine.playback_position().map(|playback| playback.session_id)
+    } else {
+        // The previous item's audio would otherwise carry on under this one
+        let ours = media_engine.playback_position()
+            .is_some_and(|playback| playback.display_id.as_deref() == Some(display.id.as_str()));
+        if ours {
+            media_engine.stop_playback();
+        }
+        None
+    };
+    Ok(session)
+}
+
+/// An item as the cast request body `groups::parse_content` reads
+fn item_payload(item: &PlaylistItem) -> Value {
+    serde_json::json!({
+        "content_type": item.content_type,
+        "source": item.source,
+        "options": item.options,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records what it's asked to show, failing sources that start with "fail".
+    /// Stops the playlist once it has shown `stop_after` items.
+    struct FakeCaster {
+        shown: Arc<Mutex<Vec<String>>>,
+        stop_after: usize,
+    }
+
+    impl DisplayCaster for FakeCaster {
+        async fn displays(&self) -> Result<Vec<DisplayInfo>> {
+            Ok(Vec::new())
+        }
+
+        async fn cast(&self, display: &DisplayInfo, _content_type: ContentType, source: &str) -> Result<Option<String>> {
+            let count = {
+                let mut shown = self.shown.lock().unwrap();
+                shown.push(source.to_string());
+                shown.len()
+            };
+            if count >= self.stop_after {
+                stop(&display.id);
+            }
+            match source.starts_with("fail") {
+                true => Err(CasterError::NotFound(source.to_string())),
+                false => Ok(None),
+            }
+        }
+
+        async fn finished(&self, _session: Option<&str>, duration: Option<Duration>) {
+            match duration {
+                Some(duration) => tokio::time::sleep(duration).await,
+                None => std::future::pending().await,
+            }
+        }
+
+        async fn close(&self, _display_id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn item(source: &str, duration_secs: Option<u64>) -> PlaylistItem {
+        PlaylistItem {
+            content_type: "image".into(),
+            source: source.into(),
+            options: Value::Null,
+            duration_secs,
+        }
+    }
+
+    /// Run a playlist whose first item is already showing until it ends, and
+    /// return the sources shown after it
+    async fn play(items: Vec<PlaylistItem>, looping: bool, commands: &[PlaylistControl], stop_after: usize) -> Vec<String> {
+        let playlist = Playlist {
+            id: Uuid::new_v4(),
+            display_id: Uuid::new_v4().to_string(),
+            items,
+            current: 0,
+            looping,
+        };
+        let (control, receiver) = mpsc::unbounded_channel();
+        for command in commands {
+            control.send(*command).unwrap();
+        }
+        PLAYLISTS.insert(playlist.display_id.clone(), RunningPlaylist { playlist: playlist.clone(), control });
+
+        let shown = Arc::new(Mutex::new(Vec::new()));
+        let caster = FakeCaster { shown: Arc::clone(&shown), stop_after };
+        let display = DisplayInfo {
+            id: playlist.display_id.clone(),
+            name: "Test".into(),
+            resolution: crate::Resolution { width: 1920, height: 1080 },
+            position: crate::Position { x: 0, y: 0 },
+            is_primary: true,
+            refresh_rate: 60.0,
+            scale_factor: 1.0,
+        };
+        tokio::time::timeout(Duration::from_secs(5), run(caster, display, playlist.clone(), None, receiver))
+            .await
+            .expect("the playlist never ended");
+        assert!(status(&playlist.display_id).is_none());
+
+        let shown = shown.lock().unwrap().clone();
+        shown
+    }
+
+    #[tokio::test]
+    async fn items_advance_and_the_playlist_ends_after_the_last() {
+        let shown = play(vec![item("a", Some(0)), item("b", Some(0)), item("c", Some(0))], false, &[], usize::MAX).await;
+        assert_eq!(shown, ["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn a_looping_playlist_starts_over() {
+        let shown = play(vec![item("a", Some(0)), item("b", Some(0)), item("c", Some(0))], true, &[], 5).await;
+        assert_eq!(shown, ["b", "c", "a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn a_failed_item_is_skipped_straight_away() {
+        // Without the skip, "fail-b" would wait for `next` forever
+        let shown = play(vec![item("a", Some(0)), item("fail-b", None), item("c", Some(0))], false, &[], usize::MAX).await;
+        assert_eq!(shown, ["fail-b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn a_looping_playlist_stops_once_every_item_has_failed() {
+        let items = vec![item("fail-a", Some(0)), item("fail-b", None), item("fail-c", None)];
+        let shown = play(items, true, &[], usize::MAX).await;
+        assert_eq!(shown, ["fail-b", "fail-c", "fail-a"]);
+    }
+
+    #[tokio::test]
+    async fn controls_move_between_items_but_not_past_the_ends() {
+        let commands = [
+            PlaylistControl::Jump(2),
+            // Already at the end of a playlist that doesn't loop
+            PlaylistControl::Next,
+            PlaylistControl::Previous,
+            PlaylistControl::Previous,
+            PlaylistControl::Previous,
+        ];
+        let shown = play(vec![item("a", None), item("b", None), item("c", None)], false, &commands, 3).await;
+        assert_eq!(shown, ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn bad_playlists_are_invalid_requests() {
+        assert!(matches!(validate(&[]), Err(CasterError::InvalidRequest(_))));
+        let stream = PlaylistItem { content_type: "stream".into(), ..item("rtsp://camera", None) };
+        assert!(matches!(validate(&[item("a", None), stream]), Err(CasterError::InvalidRequest(_))));
+        assert!(validate(&[item("a", None)]).is_ok());
+
+        let display_id = Uuid::new_v4().to_string();
+        assert!(matches!(control(&display_id, PlaylistControl::Next), Err(CasterError::NotCasting(_))));
+        let (control_tx, _commands) = mpsc::unbounded_channel();
+        let playlist = Playlist {
+            id: Uuid::new_v4(),
+            display_id: display_id.clone(),
+            items: vec![item("a", None)],
+            current: 0,
+            looping: false,
+        };
+        PLAYLISTS.insert(display_id.clone(), RunningPlaylist { playlist, control: control_tx });
+        assert!(matches!(control(&display_id, PlaylistControl::Jump(1)), Err(CasterError::InvalidRequest(_))));
+        assert!(control(&display_id, PlaylistControl::Jump(0)).is_ok());
+        stop(&display_id);
+    }
+}
@@ -0,0 +1,187 @@
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{header, StatusCode},
+    response::Response,
+};
+use dashmap::DashMap;
+use serde::Deserialize;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::auth::PathPattern;
+
+/// Buckets kept before idle ones are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// How long a client's bucket outlives its last request once pruning starts
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(60);
+
+/// Requests each client IP may make to the limited routes
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Sustained rate; 0 turns limiting off
+    pub requests_per_second: f64,
+    /// Requests allowed at once before the rate applies
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 2.0,
+            burst: 10,
+        }
+    }
+}
+
+/// A token bucket: refills at the configured rate up to `burst`
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+struct Limiter {
+    config: RateLimitConfig,
+    buckets: DashMap<IpAddr, Bucket>,
+}
+
+impl Limiter {
+    /// Take a token for `client`, or say how long until one is free
+    fn acquire(&self, client: IpAddr) -> Result<(), Duration> {
+        if self.buckets.len() > MAX_TRACKED_CLIENTS {
+            self.buckets.retain(|_, bucket| bucket.updated.elapsed() < IDLE_BUCKET_TTL);
+        }
+
+        let rate = self.config.requests_per_second;
+        let burst = self.config.burst.max(1) as f64;
+        let now = Instant::now();
+        let mut bucket = self.buckets.entry(client).or_insert(Bucket { tokens: burst, updated: now });
+
+        let refilled = bucket.updated.elapsed().as_secs_f64() * rate;
+        bucket.tokens = (bucket.tokens + refilled).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// Limits requests per client IP on the routes given to `limit`, answering 429
+/// with `Retry-After` once a client runs out. Needs the server started with
+/// `ConnectInfo<SocketAddr>` to tell clients apart.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<Limiter>,
+    routes: Arc<Vec<PathPattern>>,
+}
+
+impl RateLimitLayer {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            limiter: Arc::new(Limiter {
+                config,
+                buckets: DashMap::new(),
+            }),
+            routes: Arc::new(Vec::new()),
+        }
+    }
+
+    /// Limit requests under `path_prefix`, e.g. `/api/displays/*/cast`; `*` matches any
+    /// one segment. Every limited route draws on the same bucket per client.
+    pub fn limit(mut self, path_prefix: &str) -> Self {
+        Arc::make_mut(&mut self.routes).push(PathPattern::new(path_prefix));
+        self
+    }
+
+    fn is_limited(&self, path: &str) -> bool {
+        if self.limiter.config.requests_per_second <= 0.0 {
+            return false;
+        }
+        self.routes.iter().any(|pattern| pattern.matches(path))
+    }
+}
+
+impl<S> tower::Layer<S> for RateLimitLayer {
+    type Service = RateLimitMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitMiddleware {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    layer: RateLimitLayer,
+}
+
+impl<S> tower::Service<Request> for RateLimitMiddleware<S>
+where
+    S: tower::Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let inner = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, inner);
+
+        if self.layer.is_limited(request.uri().path()) {
+            // Without connect info every client shares one bucket
+            let client = request.extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+                .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+            if let Err(wait) = self.layer.limiter.acquire(client) {
+                let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+                return Box::pin(async move {
+                    Ok(Response::builder()
+                        .status(StatusCode::TOO_MANY_REQUESTS)
+                        .header(header::RETRY_AFTER, retry_after.to_string())
+                        .body(axum::body::Body::from("Too many requests"))
+                        .unwrap())
+                });
+            }
+        }
+
+        Box::pin(async move { inner.call(request).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_the_routes_it_was_given() {
+        let layer = RateLimitLayer::new(RateLimitConfig::default())
+            .limit("/api/displays/*/cast")
+            .limit("/api/screen/stream");
+
+        assert!(layer.is_limited("/api/displays/display_0/cast"));
+        assert!(layer.is_limited("/api/screen/stream/stop"));
+        assert!(!layer.is_limited("/api/displays/display_0/stop"));
+        assert!(!layer.is_limited("/api/displays"));
+
+        // A rate of 0 turns limiting off
+        let off = RateLimitLayer::new(RateLimitConfig { requests_per_second: 0.0, burst: 10 }).limit("/api");
+        assert!(!off.is_limited("/api/displays"));
+    }
+}
@@ -0,0 +1,84 @@
+use axum::extract::MatchedPath;
+use axum::http::{Request, Response};
+use serde::Deserialize;
+use std::time::Duration;
+use tower_http::trace::OnResponse;
+use tracing::{field, info, info_span, warn, Span};
+
+/// How requests are logged
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RequestLogConfig {
+    /// Requests that take at least this long are logged at warn
+    pub slow_request_ms: u64,
+}
+
+impl Default for RequestLogConfig {
+    fn default() -> Self {
+        Self {
+            slow_request_ms: 1000,
+        }
+    }
+}
+
+/// A span for each request, with its method, path and route. Status and
+/// latency are filled in when the response is ready; handlers add the cast
+/// session or device they acted on.
+pub fn make_span<B>(request: &Request<B>) -> Span {
+    let route = request.extensions().get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or("");
+    // A media token grants access to a file, so it stays out of the logs
+    let path = if route == "/media/:token" { route } else { request.uri().path() };
+
+    info_span!(
+        "request",
+        method = %request.method(),
+        path,
+        route,
+        status = field::Empty,
+        latency_ms = field::Empty,
+        session_id = field::Empty,
+        device = field::Empty,
+    )
+}
+
+/// Logs each response at info, or at warn once it's slower than the configured threshold
+#[derive(Debug, Clone)]
+pub struct LogResponse {
+    slow: Duration,
+}
+
+impl LogResponse {
+    pub fn new(config: &RequestLogConfig) -> Self {
+        Self {
+            slow: Duration::from_millis(config.slow_request_ms),
+        }
+    }
+}
+
+impl<B> OnResponse<B> for LogResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        let status = response.status().as_u16();
+        let latency_ms = latency.as_millis() as u64;
+        span.record("status", status);
+        span.record("latency_ms", latency_ms);
+
+        let _entered = span.enter();
+        if latency >= self.slow {
+            warn!(status, latency_ms, "Slow request");
+        } else {
+            info!(status, latency_ms, "Request finished");
+        }
+    }
+}
+
+/// Note the cast session a request started or stopped on its log line
+pub fn record_session(session_id: &str) {
+    Span::current().record("session_id", session_id);
+}
+
+/// Note the device or receiver a request acted on on its log line
+pub fn record_device(device: &str) {
+    Span::current().record("device", device);
+}
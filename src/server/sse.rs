@@ -1,16 +1,19 @@
 use axum::{
     response::sse::{Event, KeepAlive, Sse},
-    extract::State,
+    extract::{Query, State},
 };
 use futures::stream::Stream;
+use std::collections::HashSet;
 use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use tokio_stream::StreamExt as _;
 use tokio::sync::broadcast;
-use serde_json::json;
-use tracing::info;
+use tracing::{debug, info};
 
 use super::http::AppState;
+use super::metrics;
+use crate::network::DiscoveryEvent;
 
 // Global event broadcaster
 lazy_static::lazy_static! {
@@ -20,6 +23,29 @@ lazy_static::lazy_static! {
     };
 }
 
+/// Clients connected to /events right now
+static SSE_CLIENTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a client for as long as its stream is alive
+struct SseClient;
+
+impl SseClient {
+    fn connect() -> Self {
+        SSE_CLIENTS.fetch_add(1, Ordering::Relaxed);
+        SseClient
+    }
+}
+
+impl Drop for SseClient {
+    fn drop(&mut self) {
+        SSE_CLIENTS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub fn sse_client_count() -> usize {
+    SSE_CLIENTS.load(Ordering::Relaxed)
+}
+
 #[derive(Clone, Debug, serde::Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum CastEvent {
@@ -36,9 +62,50 @@ pub enum CastEvent {
         display_id: String,
         session_id: String,
     },
+    /// A watched cast's source changed and the display was updated
+    ContentUpdated {
+        display_id: String,
+        session_id: String,
+    },
+    CastProgress {
+        session_id: String,
+        display_id: String,
+        /// 0.0 to 1.0
+        progress: f32,
+        stage: String,
+    },
+    /// A cast PDF's page overview finished rendering; fetch it from `/api/cache/:key`
+    ThumbnailsReady {
+        display_id: String,
+        session_id: String,
+        key: String,
+    },
     ChromecastDiscovered {
         devices: Vec<serde_json::Value>,
     },
+    DeviceDiscovered {
+        device: serde_json::Value,
+    },
+    DeviceUpdated {
+        device: serde_json::Value,
+    },
+    DeviceLost {
+        device_id: String,
+    },
+    ChromecastStatus {
+        device_name: String,
+        state: String,
+        position: Option<f32>,
+        duration: Option<f32>,
+        volume: Option<f32>,
+    },
+    /// A Chromecast connection came up, was re-established, or gave up retrying
+    ChromecastConnection {
+        device_name: String,
+        state: String,
+        attempts: u32,
+        error: Option<String>,
+    },
     ReceiverStarted {
         protocols: Vec<String>,
         port: u16,
@@ -48,27 +115,68 @@ pub enum CastEvent {
     },
 }
 
+/// Optional `?types=a,b&display_id=x` filters for an SSE client
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct EventFilter {
+    types: Option<String>,
+    display_id: Option<String>,
+}
+
+impl EventFilter {
+    fn event_types(&self) -> Option<HashSet<String>> {
+        self.types.as_ref().map(|types| {
+            types.split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+    }
+}
+
+/// Whether an event passes the client's filters; events without a display_id
+/// are dropped once a display filter is set
+fn event_matches(event: &serde_json::Value, types: Option<&HashSet<String>>, display_id: Option<&str>) -> bool {
+    let type_ok = match (types, event["type"].as_str()) {
+        (Some(types), Some(event_type)) => types.is_empty() || types.contains(event_type),
+        _ => true,
+    };
+    let display_ok = match display_id {
+        Some(display_id) => event["display_id"].as_str() == Some(display_id),
+        None => true,
+    };
+
+    type_ok && display_ok
+}
+
 pub async fn sse_handler(
     State(_state): State<AppState>,
+    Query(filter): Query<EventFilter>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
     info!("New SSE client connected");
     
-    let rx = EVENT_BROADCASTER.subscribe();
+    let rx = subscribe_events();
+    let types = filter.event_types();
+    let display_id = filter.display_id;
+    let client = SseClient::connect();
     
     let stream = tokio_stream::wrappers::BroadcastStream::new(rx)
-        .map(|result| {
+        .filter_map(move |result| {
+            let _ = &client;
             match result {
                 Ok(event) => {
-                    let json = serde_json::to_string(&event).unwrap_or_default();
-                    Ok(Event::default()
+                    let json = serde_json::to_value(&event).unwrap_or_default();
+                    if !event_matches(&json, types.as_ref(), display_id.as_deref()) {
+                        return None;
+                    }
+                    Some(Ok(Event::default()
                         .event("cast-event")
-                        .data(json))
+                        .data(json.to_string())))
                 },
                 Err(_) => {
                     // Client lagged, send a sync event
-                    Ok(Event::default()
+                    Some(Ok(Event::default()
                         .event("sync-required")
-                        .data("{}"))
+                        .data("{}")))
                 }
             }
         });
@@ -81,12 +189,23 @@ pub async fn sse_handler(
         )
 }
 
+/// Receive every event broadcast from now on
+pub fn subscribe_events() -> broadcast::Receiver<CastEvent> {
+    EVENT_BROADCASTER.subscribe()
+}
+
 pub fn broadcast_event(event: CastEvent) {
     let _ = EVENT_BROADCASTER.send(event);
 }
 
 // Helper functions for common events
+/// `action` is what happened to the display, e.g. "configured"
+pub fn notify_display_changed(display_id: String, action: String) {
+    broadcast_event(CastEvent::DisplayChanged { display_id, action });
+}
+
 pub fn notify_cast_started(display_id: String, content_type: String, session_id: String) {
+    metrics::cast_started(&display_id, &session_id);
     broadcast_event(CastEvent::CastStarted {
         display_id,
         content_type,
@@ -94,13 +213,101 @@ pub fn notify_cast_started(display_id: String, content_type: String, session_id:
     });
 }
 
+pub fn notify_progress(session_id: String, display_id: String, progress: f32, stage: String) {
+    broadcast_event(CastEvent::CastProgress {
+        session_id,
+        display_id,
+        progress: progress.clamp(0.0, 1.0),
+        stage,
+    });
+}
+
+pub fn notify_thumbnails_ready(display_id: String, session_id: String, key: String) {
+    broadcast_event(CastEvent::ThumbnailsReady {
+        display_id,
+        session_id,
+        key,
+    });
+}
+
+pub fn notify_content_updated(display_id: String, session_id: String) {
+    broadcast_event(CastEvent::ContentUpdated {
+        display_id,
+        session_id,
+    });
+}
+
 pub fn notify_cast_stopped(display_id: String, session_id: String) {
+    metrics::cast_stopped(&display_id, &session_id);
     broadcast_event(CastEvent::CastStopped {
         display_id,
         session_id,
     });
 }
 
+/// Whatever is showing on `display_id` was stopped: `session_id` when the
+/// caller named one, otherwise the session that last started there
+pub fn notify_display_stopped(display_id: String, session_id: Option<String>) {
+    if let Some(session_id) = session_id.or_else(|| metrics::active_cast(&display_id)) {
+        notify_cast_stopped(display_id, session_id);
+    }
+}
+
 pub fn notify_error(message: String) {
     broadcast_event(CastEvent::Error { message });
-}
\ No newline at end of file
+}
+
+pub fn notify_chromecast_status(
+    device_name: String,
+    state: String,
+    position: Option<f32>,
+    duration: Option<f32>,
+    volume: Option<f32>,
+) {
+    broadcast_event(CastEvent::ChromecastStatus {
+        device_name,
+        state,
+        position,
+        duration,
+        volume,
+    });
+}
+
+pub fn notify_chromecast_connection(device_name: String, state: String, attempts: u32, error: Option<String>) {
+    broadcast_event(CastEvent::ChromecastConnection {
+        device_name,
+        state,
+        attempts,
+        error,
+    });
+}
+
+pub fn notify_device_discovered(device: serde_json::Value) {
+    broadcast_event(CastEvent::DeviceDiscovered { device });
+}
+
+pub fn notify_device_updated(device: serde_json::Value) {
+    broadcast_event(CastEvent::DeviceUpdated { device });
+}
+
+pub fn notify_device_lost(device_id: String) {
+    broadcast_event(CastEvent::DeviceLost { device_id });
+}
+
+/// Forward device discovery changes to SSE clients
+pub fn spawn_discovery_bridge(mut events: broadcast::Receiver<DiscoveryEvent>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(DiscoveryEvent::DeviceFound(device)) => notify_device_discovered(device.to_json()),
+                Ok(DiscoveryEvent::DeviceUpdated(device)) => notify_device_updated(device.to_json()),
+                Ok(DiscoveryEvent::DeviceLost(device)) => notify_device_lost(device.id),
+                // Dropped events are gone; clients resync from the device list
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    debug!("Discovery bridge missed {} events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
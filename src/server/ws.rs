@@ -0,0 +1,73 @@
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+};
+use futures::{SinkExt, StreamExt};
+use std::collections::HashSet;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info};
+
+use super::sse::subscribe_events;
+
+/// Client message narrowing which event types are forwarded
+#[derive(serde::Deserialize)]
+struct SubscribeFilter {
+    types: Vec<String>,
+}
+
+pub async fn ws_handler(ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(handle_socket)
+}
+
+async fn handle_socket(socket: WebSocket) {
+    info!("New WebSocket client connected");
+
+    let (mut sender, mut receiver) = socket.split();
+    let mut events = subscribe_events();
+    // None means every event type
+    let mut filter: Option<HashSet<String>> = None;
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let json = match event {
+                    Ok(event) => match serde_json::to_value(&event) {
+                        Ok(json) => json,
+                        Err(_) => continue,
+                    },
+                    // Same signal SSE clients get when they fall behind
+                    Err(RecvError::Lagged(_)) => serde_json::json!({"type": "sync_required"}),
+                    Err(RecvError::Closed) => break,
+                };
+
+                let wanted = match (&filter, json["type"].as_str()) {
+                    (Some(types), Some(event_type)) => types.contains(event_type),
+                    _ => true,
+                };
+
+                if wanted && sender.send(Message::Text(json.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            message = receiver.next() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<SubscribeFilter>(&text) {
+                    Ok(subscribe) => {
+                        debug!("WebSocket client subscribed to {:?}", subscribe.types);
+                        filter = (!subscribe.types.is_empty())
+                            .then(|| subscribe.types.into_iter().collect());
+                    }
+                    Err(e) => {
+                        let error = serde_json::json!({"type": "error", "message": format!("Invalid subscribe message: {}", e)});
+                        if sender.send(Message::Text(error.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+
+    info!("WebSocket client disconnected");
+}